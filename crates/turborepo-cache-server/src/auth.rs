@@ -0,0 +1,188 @@
+//! Token based team authentication. Teams and their tokens are read from a
+//! JSON file:
+//!
+//! ```json
+//! {
+//!   "teams": [
+//!     { "id": "team_frontend", "slug": "frontend", "tokens": ["..."] }
+//!   ]
+//! }
+//! ```
+//!
+//! Clients send their token as `Authorization: Bearer <token>` and select a
+//! team with the `teamId` or `slug` query parameters, exactly as they would
+//! against Vercel.
+
+use std::{collections::HashMap, path::Path};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::storage::{self, ArtifactKey};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to read teams file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("failed to parse teams file: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("invalid team id: {0}")]
+    InvalidTeam(#[from] storage::Error),
+    #[error("team \"{0}\" is defined more than once")]
+    DuplicateTeam(String),
+    #[error("team \"{0}\" has no tokens")]
+    NoTokens(String),
+}
+
+/// Why a request was refused.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum Rejection {
+    #[error("missing bearer token")]
+    MissingToken,
+    #[error("invalid token")]
+    InvalidToken,
+    #[error("token does not have access to the requested team")]
+    Forbidden,
+    #[error("token has access to more than one team, pass teamId or slug to choose one")]
+    AmbiguousTeam,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Team {
+    pub id: String,
+    #[serde(default)]
+    pub slug: Option<String>,
+    pub tokens: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TeamsFile {
+    teams: Vec<Team>,
+}
+
+#[derive(Debug, Default)]
+pub struct Teams {
+    teams: Vec<Team>,
+    // Token to the indices of the teams it grants access to
+    tokens: HashMap<String, Vec<usize>>,
+}
+
+impl Teams {
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let contents = std::fs::read_to_string(path)?;
+        let TeamsFile { teams } = serde_json::from_str(&contents)?;
+        Self::new(teams)
+    }
+
+    pub fn new(teams: Vec<Team>) -> Result<Self, Error> {
+        let mut tokens: HashMap<String, Vec<usize>> = HashMap::new();
+        for (index, team) in teams.iter().enumerate() {
+            // Team ids are used as storage keys
+            ArtifactKey::new(&team.id, "validate")?;
+            if teams[..index].iter().any(|other| other.id == team.id) {
+                return Err(Error::DuplicateTeam(team.id.clone()));
+            }
+            if team.tokens.is_empty() {
+                return Err(Error::NoTokens(team.id.clone()));
+            }
+            for token in &team.tokens {
+                tokens.entry(token.clone()).or_default().push(index);
+            }
+        }
+        Ok(Self { teams, tokens })
+    }
+
+    /// Resolves the team a request is for. If the request doesn't name a team
+    /// and the token only belongs to one, that team is used.
+    pub fn authorize(
+        &self,
+        token: Option<&str>,
+        team_id: Option<&str>,
+        slug: Option<&str>,
+    ) -> Result<&Team, Rejection> {
+        let token = token.ok_or(Rejection::MissingToken)?;
+        let teams = self.tokens.get(token).ok_or(Rejection::InvalidToken)?;
+        let mut teams = teams.iter().map(|&index| &self.teams[index]);
+
+        if team_id.is_none() && slug.is_none() {
+            let team = teams.next().ok_or(Rejection::InvalidToken)?;
+            return match teams.next() {
+                Some(_) => Err(Rejection::AmbiguousTeam),
+                None => Ok(team),
+            };
+        }
+
+        teams
+            .find(|team| {
+                team_id.map_or(true, |id| id == team.id)
+                    && slug.map_or(true, |slug| Some(slug) == team.slug.as_deref())
+            })
+            .ok_or(Rejection::Forbidden)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::*;
+
+    fn teams() -> Teams {
+        Teams::new(vec![
+            Team {
+                id: "team_a".to_string(),
+                slug: Some("a".to_string()),
+                tokens: vec!["token-a".to_string(), "shared".to_string()],
+            },
+            Team {
+                id: "team_b".to_string(),
+                slug: None,
+                tokens: vec!["token-b".to_string(), "shared".to_string()],
+            },
+        ])
+        .unwrap()
+    }
+
+    #[test_case(Some("token-a"), None, None, Ok("team_a") ; "only team")]
+    #[test_case(Some("token-a"), Some("team_a"), None, Ok("team_a") ; "by id")]
+    #[test_case(Some("token-a"), None, Some("a"), Ok("team_a") ; "by slug")]
+    #[test_case(Some("shared"), Some("team_b"), None, Ok("team_b") ; "shared token")]
+    #[test_case(Some("shared"), None, None, Err(Rejection::AmbiguousTeam) ; "ambiguous")]
+    #[test_case(Some("token-a"), Some("team_b"), None, Err(Rejection::Forbidden) ; "other team")]
+    #[test_case(Some("token-a"), Some("team_a"), Some("b"), Err(Rejection::Forbidden) ; "mismatched slug")]
+    #[test_case(Some("token-c"), None, None, Err(Rejection::InvalidToken) ; "unknown token")]
+    #[test_case(None, Some("team_a"), None, Err(Rejection::MissingToken) ; "no token")]
+    fn test_authorize(
+        token: Option<&str>,
+        team_id: Option<&str>,
+        slug: Option<&str>,
+        expected: Result<&str, Rejection>,
+    ) {
+        let teams = teams();
+        let actual = teams
+            .authorize(token, team_id, slug)
+            .map(|team| team.id.as_str());
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_invalid_teams() {
+        let team = |id: &str, tokens: &[&str]| Team {
+            id: id.to_string(),
+            slug: None,
+            tokens: tokens.iter().map(|token| token.to_string()).collect(),
+        };
+        assert!(matches!(
+            Teams::new(vec![team("../escape", &["t"])]),
+            Err(Error::InvalidTeam(_))
+        ));
+        assert!(matches!(
+            Teams::new(vec![team("a", &["t"]), team("a", &["u"])]),
+            Err(Error::DuplicateTeam(_))
+        ));
+        assert!(matches!(
+            Teams::new(vec![team("a", &[])]),
+            Err(Error::NoTokens(_))
+        ));
+    }
+}
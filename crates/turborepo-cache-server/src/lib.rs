@@ -0,0 +1,662 @@
+//! A self-hosted remote cache that speaks the same artifacts API as Vercel,
+//! for teams that want to run their cache in-house. Point turbo at it with
+//! `--api`/`TURBO_API` and use one of the tokens from the teams file.
+//!
+//! Artifacts are opaque to the server: it stores whatever the client uploads
+//! and hands back the `x-artifact-tag` signature untouched, so signed
+//! artifacts are still verified end to end by the client.
+
+#![deny(clippy::all)]
+
+pub mod auth;
+mod metrics;
+pub mod storage;
+
+use std::{
+    future::Future,
+    io,
+    net::TcpListener,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    body::StreamBody,
+    extract::{BodyStream, DefaultBodyLimit, Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use futures::StreamExt;
+use serde::Deserialize;
+use serde_json::json;
+use thiserror::Error;
+use tracing::{debug, warn};
+use turborepo_vercel_api::{AnalyticsEvent, CachingStatus, CachingStatusResponse};
+
+use crate::{
+    auth::{Rejection, Team, Teams},
+    metrics::Metrics,
+    storage::{ArtifactKey, ArtifactMetadata, Storage, SweepStats},
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("cache server failed: {0}")]
+    Io(#[from] io::Error),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ServerConfig {
+    /// The largest artifact that will be accepted, in bytes
+    pub max_artifact_size: Option<u64>,
+    /// How long artifacts are kept after being uploaded. Expired artifacts
+    /// are treated as misses and removed on the next sweep.
+    pub ttl: Option<Duration>,
+}
+
+pub struct CacheServer {
+    storage: Box<dyn Storage>,
+    teams: Teams,
+    config: ServerConfig,
+    metrics: Metrics,
+}
+
+impl CacheServer {
+    pub fn new(storage: impl Storage + 'static, teams: Teams, config: ServerConfig) -> Self {
+        Self {
+            storage: Box::new(storage),
+            teams,
+            config,
+            metrics: Metrics::default(),
+        }
+    }
+
+    pub fn router(self: Arc<Self>) -> Router {
+        Router::new()
+            .route("/v8/artifacts/status", get(caching_status))
+            .route("/v8/artifacts/events", post(record_events))
+            .route(
+                "/v8/artifacts/:hash",
+                get(get_artifact).head(head_artifact).put(put_artifact),
+            )
+            .route("/metrics", get(render_metrics))
+            // Artifact size is enforced by the upload handler instead
+            .layer(DefaultBodyLimit::disable())
+            .with_state(self)
+    }
+
+    /// Removes expired artifacts and refreshes the storage gauges.
+    pub async fn sweep(&self) -> Result<SweepStats, storage::Error> {
+        let cutoff = self
+            .config
+            .ttl
+            .and_then(|ttl| SystemTime::now().checked_sub(ttl));
+        let stats = self.storage.sweep(cutoff).await?;
+        self.metrics.record_sweep(stats);
+        Ok(stats)
+    }
+
+    /// Serves the API on `listener` until `shutdown` resolves, sweeping
+    /// storage every `sweep_interval`.
+    pub async fn serve(
+        self: Arc<Self>,
+        listener: TcpListener,
+        sweep_interval: Duration,
+        shutdown: impl Future<Output = ()> + Send + 'static,
+    ) -> Result<(), Error> {
+        let sweeper = tokio::spawn({
+            let server = self.clone();
+            async move {
+                let mut interval = tokio::time::interval(sweep_interval);
+                loop {
+                    interval.tick().await;
+                    match server.sweep().await {
+                        Ok(stats) if stats.evicted > 0 => {
+                            debug!("evicted {} expired artifacts", stats.evicted)
+                        }
+                        Ok(_) => (),
+                        Err(e) => warn!("failed to sweep artifacts: {e}"),
+                    }
+                }
+            }
+        });
+
+        let handle = axum_server::Handle::new();
+        tokio::spawn({
+            let handle = handle.clone();
+            async move {
+                shutdown.await;
+                handle.graceful_shutdown(Some(Duration::from_secs(30)));
+            }
+        });
+
+        let result = axum_server::from_tcp(listener)
+            .handle(handle)
+            .serve(self.router().into_make_service())
+            .await;
+        sweeper.abort();
+        Ok(result?)
+    }
+
+    fn authorize(&self, headers: &HeaderMap, params: &TeamParams) -> Result<&Team, ApiError> {
+        let token = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        self.teams
+            .authorize(token, params.team_id.as_deref(), params.slug.as_deref())
+            .map_err(ApiError::Rejected)
+    }
+
+    // Counts the error before it's turned into a response
+    fn record(&self, error: ApiError) -> ApiError {
+        let counter = match &error {
+            ApiError::Rejected(Rejection::Forbidden | Rejection::AmbiguousTeam) => {
+                &self.metrics.forbidden
+            }
+            ApiError::Rejected(_) => &self.metrics.unauthorized,
+            ApiError::TooLarge { .. } => &self.metrics.too_large,
+            ApiError::Storage(e) => {
+                warn!("storage error: {e}");
+                &self.metrics.errors
+            }
+            ApiError::InvalidKey(_) => return error,
+        };
+        Metrics::increment(counter);
+        error
+    }
+
+    // Resolves the artifact a request is for, counting it as a hit or miss
+    async fn lookup<'a>(
+        &'a self,
+        headers: &HeaderMap,
+        params: &TeamParams,
+        hash: &'a str,
+    ) -> Result<(ArtifactKey<'a>, Option<ArtifactMetadata>), ApiError> {
+        let team = self.authorize(headers, params)?;
+        let key = ArtifactKey::new(&team.id, hash).map_err(ApiError::InvalidKey)?;
+        let metadata = self
+            .storage
+            .head(key)
+            .await
+            .map_err(ApiError::Storage)?
+            .filter(|metadata| !metadata.is_expired(self.config.ttl, SystemTime::now()));
+        Metrics::increment(match metadata {
+            Some(_) => &self.metrics.hits,
+            None => &self.metrics.misses,
+        });
+        Ok((key, metadata))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TeamParams {
+    #[serde(rename = "teamId")]
+    team_id: Option<String>,
+    slug: Option<String>,
+}
+
+#[derive(Debug, Error)]
+#[error("artifact exceeds the size limit")]
+struct BodyTooLarge;
+
+#[derive(Debug, Error)]
+enum ApiError {
+    #[error(transparent)]
+    Rejected(Rejection),
+    #[error(transparent)]
+    InvalidKey(storage::Error),
+    #[error("artifact exceeds the maximum size of {limit} bytes")]
+    TooLarge { limit: u64 },
+    #[error("failed to access storage")]
+    Storage(storage::Error),
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            ApiError::Rejected(Rejection::MissingToken | Rejection::InvalidToken) => {
+                (StatusCode::UNAUTHORIZED, "unauthorized")
+            }
+            ApiError::Rejected(Rejection::Forbidden | Rejection::AmbiguousTeam) => {
+                (StatusCode::FORBIDDEN, "forbidden")
+            }
+            ApiError::InvalidKey(_) => (StatusCode::BAD_REQUEST, "bad_request"),
+            ApiError::TooLarge { .. } => (StatusCode::PAYLOAD_TOO_LARGE, "payload_too_large"),
+            ApiError::Storage(_) => (StatusCode::INTERNAL_SERVER_ERROR, "internal_server_error"),
+        };
+        // Same shape as the Vercel API so clients can show the message
+        let body = json!({ "error": { "code": code, "message": self.to_string() } });
+        (status, Json(body)).into_response()
+    }
+}
+
+fn artifact_headers(metadata: &ArtifactMetadata) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert("x-artifact-duration", HeaderValue::from(metadata.duration));
+    if let Some(tag) = metadata
+        .tag
+        .as_deref()
+        .and_then(|tag| HeaderValue::from_str(tag).ok())
+    {
+        headers.insert("x-artifact-tag", tag);
+    }
+    headers
+}
+
+async fn head_artifact(
+    State(server): State<Arc<CacheServer>>,
+    Path(hash): Path<String>,
+    Query(params): Query<TeamParams>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let (_, metadata) = server
+        .lookup(&headers, &params, &hash)
+        .await
+        .map_err(|e| server.record(e))?;
+    Ok(match metadata {
+        Some(metadata) => (StatusCode::OK, artifact_headers(&metadata)).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    })
+}
+
+async fn get_artifact(
+    State(server): State<Arc<CacheServer>>,
+    Path(hash): Path<String>,
+    Query(params): Query<TeamParams>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    async {
+        let (key, metadata) = server.lookup(&headers, &params, &hash).await?;
+        if metadata.is_none() {
+            return Ok(StatusCode::NOT_FOUND.into_response());
+        }
+        // The artifact can be evicted between the lookup and here, which is
+        // just a late miss
+        let Some(artifact) = server.storage.get(key).await.map_err(ApiError::Storage)? else {
+            return Ok(StatusCode::NOT_FOUND.into_response());
+        };
+
+        Metrics::add(&server.metrics.downloaded_bytes, artifact.metadata.size);
+        let mut headers = artifact_headers(&artifact.metadata);
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/octet-stream"),
+        );
+        headers.insert(
+            header::CONTENT_LENGTH,
+            HeaderValue::from(artifact.metadata.size),
+        );
+        Ok((StatusCode::OK, headers, StreamBody::new(artifact.body)).into_response())
+    }
+    .await
+    .map_err(|e| server.record(e))
+}
+
+async fn put_artifact(
+    State(server): State<Arc<CacheServer>>,
+    Path(hash): Path<String>,
+    Query(params): Query<TeamParams>,
+    headers: HeaderMap,
+    body: BodyStream,
+) -> Result<Response, ApiError> {
+    async {
+        let team = server.authorize(&headers, &params)?;
+        let key = ArtifactKey::new(&team.id, &hash).map_err(ApiError::InvalidKey)?;
+
+        let limit = server.config.max_artifact_size;
+        let content_length = headers
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok());
+        if let (Some(limit), Some(length)) = (limit, content_length) {
+            if length > limit {
+                return Err(ApiError::TooLarge { limit });
+            }
+        }
+
+        // Content-Length is optional, so the limit is also enforced while
+        // the body streams in
+        let mut received = 0;
+        let body = body
+            .map(move |chunk| {
+                let chunk = chunk.map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+                received += chunk.len() as u64;
+                match limit {
+                    Some(limit) if received > limit => {
+                        Err(io::Error::new(io::ErrorKind::Other, BodyTooLarge))
+                    }
+                    _ => Ok(chunk),
+                }
+            })
+            .boxed();
+
+        let metadata = ArtifactMetadata {
+            duration: headers
+                .get("x-artifact-duration")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok())
+                .unwrap_or_default(),
+            tag: headers
+                .get("x-artifact-tag")
+                .and_then(|value| value.to_str().ok())
+                .map(|tag| tag.to_string()),
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            size: 0,
+        };
+        let metadata =
+            server
+                .storage
+                .put(key, metadata, body)
+                .await
+                .map_err(|e| match (&e, limit) {
+                    (storage::Error::Io(io), Some(limit))
+                        if io
+                            .get_ref()
+                            .map_or(false, |inner| inner.is::<BodyTooLarge>()) =>
+                    {
+                        ApiError::TooLarge { limit }
+                    }
+                    _ => ApiError::Storage(e),
+                })?;
+
+        debug!("stored {hash} for {} ({} bytes)", team.id, metadata.size);
+        Metrics::increment(&server.metrics.uploads);
+        Metrics::add(&server.metrics.uploaded_bytes, metadata.size);
+        Ok((StatusCode::CREATED, Json(hash)).into_response())
+    }
+    .await
+    .map_err(|e| server.record(e))
+}
+
+async fn caching_status(
+    State(server): State<Arc<CacheServer>>,
+    Query(params): Query<TeamParams>,
+    headers: HeaderMap,
+) -> Result<Json<CachingStatusResponse>, ApiError> {
+    server
+        .authorize(&headers, &params)
+        .map_err(|e| server.record(e))?;
+    Ok(Json(CachingStatusResponse {
+        status: CachingStatus::Enabled,
+    }))
+}
+
+async fn record_events(
+    State(server): State<Arc<CacheServer>>,
+    Query(params): Query<TeamParams>,
+    headers: HeaderMap,
+    Json(events): Json<Vec<AnalyticsEvent>>,
+) -> Result<StatusCode, ApiError> {
+    server
+        .authorize(&headers, &params)
+        .map_err(|e| server.record(e))?;
+    Metrics::add(&server.metrics.events, events.len() as u64);
+    Ok(StatusCode::OK)
+}
+
+async fn render_metrics(State(server): State<Arc<CacheServer>>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        server.metrics.render(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::SocketAddr;
+
+    use tokio::sync::oneshot;
+    use turborepo_api_client::{APIClient, Client};
+
+    use super::*;
+    use crate::storage::DiskStorage;
+
+    const TOKEN: &str = "token";
+    const TEAM_ID: &str = "team_a";
+
+    struct TestServer {
+        addr: SocketAddr,
+        server: Arc<CacheServer>,
+        _shutdown: oneshot::Sender<()>,
+        _dir: tempfile::TempDir,
+    }
+
+    impl TestServer {
+        async fn start(config: ServerConfig) -> Self {
+            let dir = tempfile::tempdir().unwrap();
+            let storage = DiskStorage::new(dir.path()).await.unwrap();
+            let teams = Teams::new(vec![
+                Team {
+                    id: TEAM_ID.to_string(),
+                    slug: Some("a".to_string()),
+                    tokens: vec![TOKEN.to_string()],
+                },
+                Team {
+                    id: "team_b".to_string(),
+                    slug: None,
+                    tokens: vec!["other".to_string()],
+                },
+            ])
+            .unwrap();
+            let server = Arc::new(CacheServer::new(storage, teams, config));
+
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let addr = listener.local_addr().unwrap();
+            let (tx, rx) = oneshot::channel();
+            tokio::spawn(
+                server
+                    .clone()
+                    .serve(listener, Duration::from_secs(3600), async move {
+                        let _ = rx.await;
+                    }),
+            );
+
+            Self {
+                addr,
+                server,
+                _shutdown: tx,
+                _dir: dir,
+            }
+        }
+
+        fn client(&self) -> APIClient {
+            APIClient::new(format!("http://{}", self.addr), 10, "1.0.0", false).unwrap()
+        }
+
+        fn url(&self, path: &str) -> String {
+            format!("http://{}{path}", self.addr)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let server = TestServer::start(ServerConfig::default()).await;
+        let client = server.client();
+
+        let miss = client
+            .fetch_artifact("abc123", TOKEN, Some(TEAM_ID), None)
+            .await
+            .unwrap();
+        assert!(miss.is_none());
+
+        client
+            .put_artifact(
+                "abc123",
+                b"artifact",
+                1234,
+                Some("signature"),
+                TOKEN,
+                Some(TEAM_ID),
+                None,
+            )
+            .await
+            .unwrap();
+
+        let exists = client
+            .artifact_exists("abc123", TOKEN, None, Some("a"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(exists.headers()["x-artifact-duration"], "1234");
+
+        let hit = client
+            .fetch_artifact("abc123", TOKEN, Some(TEAM_ID), None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(hit.headers()["x-artifact-duration"], "1234");
+        assert_eq!(hit.headers()["x-artifact-tag"], "signature");
+        assert_eq!(hit.bytes().await.unwrap().as_ref(), b"artifact");
+
+        let status = client
+            .get_caching_status(TOKEN, Some(TEAM_ID), None)
+            .await
+            .unwrap();
+        assert!(matches!(status.status, CachingStatus::Enabled));
+    }
+
+    #[tokio::test]
+    async fn test_auth() {
+        let server = TestServer::start(ServerConfig::default()).await;
+        let client = server.client();
+        client
+            .put_artifact("abc123", b"artifact", 0, None, TOKEN, None, None)
+            .await
+            .unwrap();
+
+        // Another team's token can't see the artifact, or name this team
+        let other = client
+            .fetch_artifact("abc123", "other", None, None)
+            .await
+            .unwrap();
+        assert!(other.is_none());
+        let forbidden = client
+            .fetch_artifact("abc123", "other", Some(TEAM_ID), None)
+            .await;
+        assert!(matches!(
+            forbidden,
+            Err(turborepo_api_client::Error::UnknownStatus { code, .. }) if code == "forbidden"
+        ));
+
+        let unauthorized = reqwest::Client::new()
+            .get(server.url("/v8/artifacts/abc123"))
+            .bearer_auth("invalid")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(unauthorized.status(), StatusCode::UNAUTHORIZED);
+
+        let invalid_hash = reqwest::Client::new()
+            .get(server.url("/v8/artifacts/..%2Fescape"))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(invalid_hash.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_size_limit() {
+        let server = TestServer::start(ServerConfig {
+            max_artifact_size: Some(8),
+            ttl: None,
+        })
+        .await;
+        let client = server.client();
+
+        client
+            .put_artifact("small", b"12345678", 0, None, TOKEN, None, None)
+            .await
+            .unwrap();
+
+        let too_large = reqwest::Client::new()
+            .put(server.url("/v8/artifacts/large"))
+            .bearer_auth(TOKEN)
+            .body("123456789")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(too_large.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        // Without a Content-Length the limit is enforced while streaming
+        let chunks: Vec<Result<_, io::Error>> = vec![Ok("12345"), Ok("6789")];
+        let streamed = reqwest::Client::new()
+            .put(server.url("/v8/artifacts/large"))
+            .bearer_auth(TOKEN)
+            .body(reqwest::Body::wrap_stream(futures::stream::iter(chunks)))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(streamed.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let missing = client
+            .artifact_exists("large", TOKEN, None, None)
+            .await
+            .unwrap();
+        assert!(missing.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_ttl() {
+        let server = TestServer::start(ServerConfig {
+            max_artifact_size: None,
+            ttl: Some(Duration::ZERO),
+        })
+        .await;
+        let client = server.client();
+        client
+            .put_artifact("abc123", b"artifact", 0, None, TOKEN, None, None)
+            .await
+            .unwrap();
+
+        let expired = client
+            .fetch_artifact("abc123", TOKEN, None, None)
+            .await
+            .unwrap();
+        assert!(expired.is_none());
+
+        let stats = server.server.sweep().await.unwrap();
+        assert_eq!(stats.evicted, 1);
+        assert_eq!(stats.artifacts, 0);
+    }
+
+    #[tokio::test]
+    async fn test_metrics() {
+        let server = TestServer::start(ServerConfig::default()).await;
+        let client = server.client();
+        client
+            .put_artifact("abc123", b"artifact", 0, None, TOKEN, None, None)
+            .await
+            .unwrap();
+        client
+            .fetch_artifact("abc123", TOKEN, None, None)
+            .await
+            .unwrap();
+        client
+            .fetch_artifact("missing", TOKEN, None, None)
+            .await
+            .unwrap();
+
+        let metrics = reqwest::get(server.url("/metrics"))
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        for line in [
+            r#"turbo_cache_requests_total{result="hit"} 1"#,
+            r#"turbo_cache_requests_total{result="miss"} 1"#,
+            "turbo_cache_uploads_total 1",
+            "turbo_cache_uploaded_bytes_total 8",
+            "turbo_cache_downloaded_bytes_total 8",
+        ] {
+            assert!(metrics.lines().any(|l| l == line), "missing {line}");
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use std::{
+    net::{IpAddr, SocketAddr, TcpListener},
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use tracing::info;
+use tracing_subscriber::EnvFilter;
+use turborepo_cache_server::{auth::Teams, storage::DiskStorage, CacheServer, ServerConfig};
+
+/// A self-hosted remote cache for Turborepo
+#[derive(Debug, Parser)]
+#[command(name = "turbo-cache-server", version)]
+struct Args {
+    /// Address to listen on
+    #[arg(long, env = "TURBO_CACHE_SERVER_HOST", default_value = "0.0.0.0")]
+    host: IpAddr,
+    /// Port to listen on
+    #[arg(long, env = "TURBO_CACHE_SERVER_PORT", default_value_t = 3000)]
+    port: u16,
+    /// Directory to store artifacts in
+    #[arg(long, env = "TURBO_CACHE_SERVER_STORAGE_DIR")]
+    storage_dir: PathBuf,
+    /// JSON file listing the teams and the tokens that grant access to them
+    #[arg(long, env = "TURBO_CACHE_SERVER_TEAMS")]
+    teams: PathBuf,
+    /// Reject artifacts larger than this, e.g. `500M` or `2G`
+    #[arg(long, env = "TURBO_CACHE_SERVER_MAX_ARTIFACT_SIZE", value_parser = parse_size)]
+    max_artifact_size: Option<u64>,
+    /// Evict artifacts this long after they were uploaded, e.g. `7d`
+    #[arg(long, env = "TURBO_CACHE_SERVER_TTL", value_parser = humantime::parse_duration)]
+    ttl: Option<Duration>,
+    /// How often to look for expired artifacts
+    #[arg(
+        long,
+        env = "TURBO_CACHE_SERVER_SWEEP_INTERVAL",
+        default_value = "10m",
+        value_parser = humantime::parse_duration
+    )]
+    sweep_interval: Duration,
+}
+
+fn parse_size(size: &str) -> Result<u64, String> {
+    let size = size.trim();
+    let (number, multiplier) = match size.char_indices().last() {
+        Some((index, suffix)) if suffix.is_ascii_alphabetic() => {
+            let multiplier = match suffix.to_ascii_uppercase() {
+                'K' => 1 << 10,
+                'M' => 1 << 20,
+                'G' => 1 << 30,
+                _ => {
+                    return Err(format!(
+                        "unknown size suffix '{suffix}', expected K, M or G"
+                    ))
+                }
+            };
+            (&size[..index], multiplier)
+        }
+        _ => (size, 1),
+    };
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|number| number.checked_mul(multiplier))
+        .ok_or_else(|| format!("invalid size '{size}'"))
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .init();
+
+    let args = Args::parse();
+    let teams = Teams::load(&args.teams)
+        .with_context(|| format!("failed to load teams from {}", args.teams.display()))?;
+    let storage = DiskStorage::new(&args.storage_dir)
+        .await
+        .with_context(|| format!("failed to open {}", args.storage_dir.display()))?;
+    let server = Arc::new(CacheServer::new(
+        storage,
+        teams,
+        ServerConfig {
+            max_artifact_size: args.max_artifact_size,
+            ttl: args.ttl,
+        },
+    ));
+
+    let addr = SocketAddr::new(args.host, args.port);
+    let listener = TcpListener::bind(addr).with_context(|| format!("failed to bind {addr}"))?;
+    info!("listening on {addr}");
+    server
+        .serve(listener, args.sweep_interval, async {
+            let _ = tokio::signal::ctrl_c().await;
+            info!("shutting down");
+        })
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::parse_size;
+
+    #[test_case("1024", Ok(1024) ; "bytes")]
+    #[test_case("512K", Ok(512 * 1024) ; "kilobytes")]
+    #[test_case("500m", Ok(500 * 1024 * 1024) ; "megabytes")]
+    #[test_case("2G", Ok(2 * 1024 * 1024 * 1024) ; "gigabytes")]
+    #[test_case("2T", Err(()) ; "unknown suffix")]
+    #[test_case("M", Err(()) ; "no number")]
+    fn test_parse_size(size: &str, expected: Result<u64, ()>) {
+        assert_eq!(parse_size(size).map_err(|_| ()), expected);
+    }
+}
@@ -0,0 +1,163 @@
+//! Counters served at `/metrics` in the Prometheus text format.
+
+use std::{
+    fmt::Write,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::storage::SweepStats;
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    pub hits: AtomicU64,
+    pub misses: AtomicU64,
+    pub uploads: AtomicU64,
+    pub uploaded_bytes: AtomicU64,
+    pub downloaded_bytes: AtomicU64,
+    pub unauthorized: AtomicU64,
+    pub forbidden: AtomicU64,
+    pub too_large: AtomicU64,
+    pub errors: AtomicU64,
+    pub events: AtomicU64,
+    pub evicted: AtomicU64,
+    pub evicted_bytes: AtomicU64,
+    // Gauges, as of the last sweep
+    artifacts: AtomicU64,
+    stored_bytes: AtomicU64,
+}
+
+impl Metrics {
+    pub fn increment(counter: &AtomicU64) {
+        Self::add(counter, 1);
+    }
+
+    pub fn add(counter: &AtomicU64, value: u64) {
+        counter.fetch_add(value, Ordering::Relaxed);
+    }
+
+    pub fn record_sweep(&self, stats: SweepStats) {
+        Self::add(&self.evicted, stats.evicted);
+        Self::add(&self.evicted_bytes, stats.evicted_bytes);
+        self.artifacts.store(stats.artifacts, Ordering::Relaxed);
+        self.stored_bytes.store(stats.bytes, Ordering::Relaxed);
+    }
+
+    pub fn render(&self) -> String {
+        let get = |value: &AtomicU64| value.load(Ordering::Relaxed);
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, samples: &[(&str, u64)]| {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} {kind}");
+            for (labels, value) in samples {
+                let _ = writeln!(out, "{name}{labels} {value}");
+            }
+        };
+
+        metric(
+            "turbo_cache_requests_total",
+            "counter",
+            "Artifact downloads and existence checks, by result.",
+            &[
+                (r#"{result="hit"}"#, get(&self.hits)),
+                (r#"{result="miss"}"#, get(&self.misses)),
+            ],
+        );
+        metric(
+            "turbo_cache_uploads_total",
+            "counter",
+            "Artifacts uploaded.",
+            &[("", get(&self.uploads))],
+        );
+        metric(
+            "turbo_cache_uploaded_bytes_total",
+            "counter",
+            "Bytes of artifacts uploaded.",
+            &[("", get(&self.uploaded_bytes))],
+        );
+        metric(
+            "turbo_cache_downloaded_bytes_total",
+            "counter",
+            "Bytes of artifacts served.",
+            &[("", get(&self.downloaded_bytes))],
+        );
+        metric(
+            "turbo_cache_rejected_total",
+            "counter",
+            "Requests rejected before reaching storage, by reason.",
+            &[
+                (r#"{reason="unauthorized"}"#, get(&self.unauthorized)),
+                (r#"{reason="forbidden"}"#, get(&self.forbidden)),
+                (r#"{reason="too_large"}"#, get(&self.too_large)),
+            ],
+        );
+        metric(
+            "turbo_cache_errors_total",
+            "counter",
+            "Requests that failed because of a storage error.",
+            &[("", get(&self.errors))],
+        );
+        metric(
+            "turbo_cache_events_total",
+            "counter",
+            "Cache usage events reported by clients.",
+            &[("", get(&self.events))],
+        );
+        metric(
+            "turbo_cache_evicted_total",
+            "counter",
+            "Artifacts evicted after their TTL expired.",
+            &[("", get(&self.evicted))],
+        );
+        metric(
+            "turbo_cache_evicted_bytes_total",
+            "counter",
+            "Bytes of artifacts evicted after their TTL expired.",
+            &[("", get(&self.evicted_bytes))],
+        );
+        metric(
+            "turbo_cache_artifacts",
+            "gauge",
+            "Artifacts in storage as of the last sweep.",
+            &[("", get(&self.artifacts))],
+        );
+        metric(
+            "turbo_cache_stored_bytes",
+            "gauge",
+            "Bytes in storage as of the last sweep.",
+            &[("", get(&self.stored_bytes))],
+        );
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::default();
+        Metrics::increment(&metrics.hits);
+        Metrics::add(&metrics.uploaded_bytes, 512);
+        metrics.record_sweep(SweepStats {
+            evicted: 2,
+            evicted_bytes: 10,
+            artifacts: 3,
+            bytes: 30,
+        });
+
+        let rendered = metrics.render();
+        for line in [
+            "# TYPE turbo_cache_requests_total counter",
+            r#"turbo_cache_requests_total{result="hit"} 1"#,
+            r#"turbo_cache_requests_total{result="miss"} 0"#,
+            "turbo_cache_uploaded_bytes_total 512",
+            "turbo_cache_evicted_total 2",
+            "# TYPE turbo_cache_artifacts gauge",
+            "turbo_cache_artifacts 3",
+            "turbo_cache_stored_bytes 30",
+        ] {
+            assert!(rendered.lines().any(|l| l == line), "missing {line}");
+        }
+    }
+}
@@ -0,0 +1,305 @@
+use std::{
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::SystemTime,
+};
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use tokio::{fs, io::AsyncWriteExt};
+use tokio_util::io::ReaderStream;
+
+use super::{Artifact, ArtifactBody, ArtifactKey, ArtifactMetadata, Error, Storage, SweepStats};
+
+const TEMP_DIR: &str = ".tmp";
+const METADATA_EXTENSION: &str = "json";
+
+/// Stores artifacts on the local filesystem as `<root>/<team>/<hash>`, with
+/// the metadata next to it in `<hash>.json`.
+///
+/// Uploads are written to `<root>/.tmp` and renamed into place, so readers
+/// only ever see complete artifacts. The artifact is moved into place before
+/// its metadata, and lookups go through the metadata, so an artifact is
+/// visible exactly when both are present.
+pub struct DiskStorage {
+    root: PathBuf,
+    next_temp_id: AtomicU64,
+}
+
+impl DiskStorage {
+    /// Opens `root` as artifact storage, creating it if needed. Uploads that
+    /// were interrupted by a previous shutdown are discarded.
+    pub async fn new(root: impl Into<PathBuf>) -> Result<Self, Error> {
+        let root = root.into();
+        let temp_dir = root.join(TEMP_DIR);
+        match fs::remove_dir_all(&temp_dir).await {
+            Err(e) if e.kind() != ErrorKind::NotFound => return Err(e.into()),
+            _ => (),
+        }
+        fs::create_dir_all(&temp_dir).await?;
+        Ok(Self {
+            root,
+            next_temp_id: AtomicU64::new(0),
+        })
+    }
+
+    fn artifact_path(&self, key: ArtifactKey) -> PathBuf {
+        self.root.join(key.team()).join(key.hash())
+    }
+
+    fn metadata_path(&self, key: ArtifactKey) -> PathBuf {
+        self.artifact_path(key).with_extension(METADATA_EXTENSION)
+    }
+
+    fn temp_path(&self) -> PathBuf {
+        let id = self.next_temp_id.fetch_add(1, Ordering::Relaxed);
+        self.root
+            .join(TEMP_DIR)
+            .join(format!("{}-{id}", std::process::id()))
+    }
+
+    async fn read_metadata(path: &Path) -> Result<Option<ArtifactMetadata>, Error> {
+        match fs::read(path).await {
+            Ok(contents) => Ok(Some(serde_json::from_slice(&contents)?)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn write_temp(&self, body: &mut ArtifactBody) -> io::Result<(PathBuf, u64)> {
+        let path = self.temp_path();
+        let result = async {
+            let mut file = fs::File::create(&path).await?;
+            let mut size = 0;
+            while let Some(chunk) = body.next().await {
+                let chunk = chunk?;
+                size += chunk.len() as u64;
+                file.write_all(&chunk).await?;
+            }
+            file.sync_all().await?;
+            Ok(size)
+        }
+        .await;
+        match result {
+            Ok(size) => Ok((path, size)),
+            Err(e) => {
+                // Best effort, the temp dir is also cleared on startup
+                let _ = fs::remove_file(&path).await;
+                Err(e)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Storage for DiskStorage {
+    async fn head(&self, key: ArtifactKey<'_>) -> Result<Option<ArtifactMetadata>, Error> {
+        Self::read_metadata(&self.metadata_path(key)).await
+    }
+
+    async fn get(&self, key: ArtifactKey<'_>) -> Result<Option<Artifact>, Error> {
+        let Some(metadata) = self.head(key).await? else {
+            return Ok(None);
+        };
+        let file = match fs::File::open(self.artifact_path(key)).await {
+            Ok(file) => file,
+            // Evicted between reading the metadata and opening the artifact
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Some(Artifact {
+            metadata,
+            body: ReaderStream::new(file).boxed(),
+        }))
+    }
+
+    async fn put(
+        &self,
+        key: ArtifactKey<'_>,
+        mut metadata: ArtifactMetadata,
+        mut body: ArtifactBody,
+    ) -> Result<ArtifactMetadata, Error> {
+        let (artifact_temp, size) = self.write_temp(&mut body).await?;
+        metadata.size = size;
+
+        let metadata_temp = self.temp_path();
+        let result = async {
+            fs::write(&metadata_temp, serde_json::to_vec(&metadata)?).await?;
+            fs::create_dir_all(self.root.join(key.team())).await?;
+            fs::rename(&artifact_temp, self.artifact_path(key)).await?;
+            fs::rename(&metadata_temp, self.metadata_path(key)).await?;
+            Ok::<_, Error>(())
+        }
+        .await;
+        if result.is_err() {
+            let _ = fs::remove_file(&artifact_temp).await;
+            let _ = fs::remove_file(&metadata_temp).await;
+        }
+        result.map(|()| metadata)
+    }
+
+    async fn sweep(&self, cutoff: Option<SystemTime>) -> Result<SweepStats, Error> {
+        let mut stats = SweepStats::default();
+        let mut teams = fs::read_dir(&self.root).await?;
+        while let Some(team) = teams.next_entry().await? {
+            if team.file_name() == TEMP_DIR || !team.file_type().await?.is_dir() {
+                continue;
+            }
+            let mut entries = fs::read_dir(team.path()).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                if path
+                    .extension()
+                    .map_or(false, |ext| ext == METADATA_EXTENSION)
+                {
+                    continue;
+                }
+                let metadata_path = path.with_extension(METADATA_EXTENSION);
+                let (created, size) = match Self::read_metadata(&metadata_path).await? {
+                    Some(metadata) => (metadata.created(), metadata.size),
+                    // An artifact without metadata was orphaned by a crash
+                    // mid-upload, so it's aged by its modification time
+                    None => {
+                        let file = entry.metadata().await?;
+                        (file.modified()?, file.len())
+                    }
+                };
+                if cutoff.map_or(false, |cutoff| created < cutoff) {
+                    // Metadata goes first so the artifact stops being visible
+                    // before it disappears
+                    for path in [&metadata_path, &path] {
+                        match fs::remove_file(path).await {
+                            Err(e) if e.kind() != ErrorKind::NotFound => return Err(e.into()),
+                            _ => (),
+                        }
+                    }
+                    stats.evicted += 1;
+                    stats.evicted_bytes += size;
+                } else {
+                    stats.artifacts += 1;
+                    stats.bytes += size;
+                }
+            }
+        }
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use bytes::Bytes;
+    use futures::{stream, TryStreamExt};
+
+    use super::*;
+
+    fn body(chunks: &[&'static [u8]]) -> ArtifactBody {
+        stream::iter(
+            chunks
+                .iter()
+                .map(|chunk| Ok(Bytes::from_static(chunk)))
+                .collect::<Vec<_>>(),
+        )
+        .boxed()
+    }
+
+    fn metadata(created_at: u64) -> ArtifactMetadata {
+        ArtifactMetadata {
+            duration: 42,
+            tag: Some("signature".to_string()),
+            created_at,
+            size: 0,
+        }
+    }
+
+    async fn read(storage: &DiskStorage, key: ArtifactKey<'_>) -> Option<Vec<u8>> {
+        let artifact = storage.get(key).await.unwrap()?;
+        let chunks: Vec<Bytes> = artifact.body.try_collect().await.unwrap();
+        Some(chunks.concat())
+    }
+
+    #[tokio::test]
+    async fn test_round_trip() -> Result<(), Error> {
+        let dir = tempfile::tempdir()?;
+        let storage = DiskStorage::new(dir.path()).await?;
+        let key = ArtifactKey::new("team", "abc123")?;
+
+        assert!(storage.head(key).await?.is_none());
+        assert!(storage.get(key).await?.is_none());
+
+        let stored = storage
+            .put(key, metadata(1), body(&[b"hello ", b"world"]))
+            .await?;
+        assert_eq!(stored.size, 11);
+        assert_eq!(storage.head(key).await?, Some(stored));
+        assert_eq!(read(&storage, key).await.unwrap(), b"hello world");
+
+        // Other teams don't see the artifact
+        let other_team = ArtifactKey::new("other", "abc123")?;
+        assert!(storage.head(other_team).await?.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_failed_upload() -> Result<(), Error> {
+        let dir = tempfile::tempdir()?;
+        let storage = DiskStorage::new(dir.path()).await?;
+        let key = ArtifactKey::new("team", "abc123")?;
+        storage.put(key, metadata(1), body(&[b"original"])).await?;
+
+        let failing = stream::iter(vec![
+            Ok(Bytes::from_static(b"partial")),
+            Err(io::Error::new(ErrorKind::Other, "connection reset")),
+        ])
+        .boxed();
+        assert!(storage.put(key, metadata(2), failing).await.is_err());
+
+        // The original is untouched and nothing is left in the temp dir
+        assert_eq!(read(&storage, key).await.unwrap(), b"original");
+        let temp_files = std::fs::read_dir(dir.path().join(TEMP_DIR))?.count();
+        assert_eq!(temp_files, 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sweep() -> Result<(), Error> {
+        let dir = tempfile::tempdir()?;
+        let storage = DiskStorage::new(dir.path()).await?;
+        let old = ArtifactKey::new("team", "old")?;
+        let new = ArtifactKey::new("team", "new")?;
+        storage.put(old, metadata(100), body(&[b"old"])).await?;
+        storage.put(new, metadata(200), body(&[b"newer"])).await?;
+
+        let stats = storage.sweep(None).await?;
+        assert_eq!(
+            stats,
+            SweepStats {
+                evicted: 0,
+                evicted_bytes: 0,
+                artifacts: 2,
+                bytes: 8,
+            }
+        );
+
+        let cutoff = UNIX_EPOCH + Duration::from_secs(150);
+        let stats = storage.sweep(Some(cutoff)).await?;
+        assert_eq!(
+            stats,
+            SweepStats {
+                evicted: 1,
+                evicted_bytes: 3,
+                artifacts: 1,
+                bytes: 5,
+            }
+        );
+        assert!(storage.get(old).await?.is_none());
+        assert!(!dir.path().join("team").join("old").exists());
+        assert_eq!(read(&storage, new).await.unwrap(), b"newer");
+
+        Ok(())
+    }
+}
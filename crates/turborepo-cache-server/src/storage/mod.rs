@@ -0,0 +1,166 @@
+//! Where the cache server keeps artifacts. Backends implement [`Storage`];
+//! the server only ever deals with the trait so new backends can be added
+//! without touching the request handlers.
+
+mod disk;
+
+use std::{
+    io,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+pub use disk::DiskStorage;
+use futures::stream::BoxStream;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// A stream of artifact bytes, either being uploaded or downloaded.
+pub type ArtifactBody = BoxStream<'static, io::Result<Bytes>>;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("invalid {kind} \"{value}\": only letters, digits, '-' and '_' are allowed")]
+    InvalidKey { kind: &'static str, value: String },
+    #[error("failed to read artifact metadata: {0}")]
+    Metadata(#[from] serde_json::Error),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Identifies an artifact. Artifacts are namespaced by team so teams sharing
+/// a server can't read each other's outputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArtifactKey<'a> {
+    team: &'a str,
+    hash: &'a str,
+}
+
+impl<'a> ArtifactKey<'a> {
+    pub fn new(team: &'a str, hash: &'a str) -> Result<Self, Error> {
+        validate_key_part("team", team)?;
+        validate_key_part("hash", hash)?;
+        Ok(Self { team, hash })
+    }
+
+    pub fn team(&self) -> &'a str {
+        self.team
+    }
+
+    pub fn hash(&self) -> &'a str {
+        self.hash
+    }
+}
+
+// Keys end up as path components and object names, so we only allow a
+// conservative set of characters
+fn validate_key_part(kind: &'static str, value: &str) -> Result<(), Error> {
+    let valid = !value.is_empty()
+        && value.len() <= 128
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidKey {
+            kind,
+            value: value.to_string(),
+        })
+    }
+}
+
+/// Everything we know about an artifact besides its contents.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtifactMetadata {
+    /// How long the task took to produce the artifact, in milliseconds
+    pub duration: u64,
+    /// The `x-artifact-tag` signature sent by the client, returned verbatim so
+    /// clients can verify the artifact
+    pub tag: Option<String>,
+    /// Seconds since the unix epoch when the artifact was stored
+    pub created_at: u64,
+    pub size: u64,
+}
+
+impl ArtifactMetadata {
+    pub fn created(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.created_at)
+    }
+
+    pub fn is_expired(&self, ttl: Option<Duration>, now: SystemTime) -> bool {
+        ttl.map_or(false, |ttl| self.created() + ttl <= now)
+    }
+}
+
+pub struct Artifact {
+    pub metadata: ArtifactMetadata,
+    pub body: ArtifactBody,
+}
+
+/// The result of a sweep over the stored artifacts.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SweepStats {
+    pub evicted: u64,
+    pub evicted_bytes: u64,
+    pub artifacts: u64,
+    pub bytes: u64,
+}
+
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Returns the artifact's metadata, or `None` if it doesn't exist.
+    async fn head(&self, key: ArtifactKey<'_>) -> Result<Option<ArtifactMetadata>, Error>;
+
+    /// Returns the artifact, or `None` if it doesn't exist.
+    async fn get(&self, key: ArtifactKey<'_>) -> Result<Option<Artifact>, Error>;
+
+    /// Stores an artifact, replacing any existing artifact with the same key.
+    /// `metadata.size` is filled in from the body. Readers must never see a
+    /// partially written artifact, and a failed upload must not leave one
+    /// behind.
+    async fn put(
+        &self,
+        key: ArtifactKey<'_>,
+        metadata: ArtifactMetadata,
+        body: ArtifactBody,
+    ) -> Result<ArtifactMetadata, Error>;
+
+    /// Removes every artifact created before `cutoff`, if given, and reports
+    /// what is left.
+    async fn sweep(&self, cutoff: Option<SystemTime>) -> Result<SweepStats, Error>;
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case("0123456789abcdef", true ; "hash")]
+    #[test_case("team_my-team", true ; "team")]
+    #[test_case("", false ; "empty")]
+    #[test_case("..", false ; "parent")]
+    #[test_case("a/b", false ; "separator")]
+    #[test_case("a.json", false ; "extension")]
+    fn test_artifact_key(value: &str, valid: bool) {
+        assert_eq!(ArtifactKey::new(value, "abc").is_ok(), valid);
+        assert_eq!(ArtifactKey::new("team", value).is_ok(), valid);
+    }
+
+    #[test]
+    fn test_expiry() {
+        let metadata = ArtifactMetadata {
+            duration: 0,
+            tag: None,
+            created_at: 100,
+            size: 0,
+        };
+        let now = UNIX_EPOCH + Duration::from_secs(150);
+        assert!(!metadata.is_expired(None, now));
+        assert!(!metadata.is_expired(Some(Duration::from_secs(60)), now));
+        assert!(metadata.is_expired(Some(Duration::from_secs(50)), now));
+    }
+}
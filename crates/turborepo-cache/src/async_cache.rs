@@ -230,9 +230,11 @@ mod tests {
             skip_filesystem: true,
             workers: 10,
             remote_cache_opts: Some(RemoteCacheOpts {
-                unused_team_id: Some("my-team".to_string()),
+                team_id: Some("my-team".to_string()),
                 signature: false,
             }),
+            fs_limits: Default::default(),
+            remote_backend: Default::default(),
         };
 
         let api_client = APIClient::new(format!("http://localhost:{}", port), 200, "2.0.0", true)?;
@@ -310,9 +312,11 @@ mod tests {
             skip_filesystem: false,
             workers: 10,
             remote_cache_opts: Some(RemoteCacheOpts {
-                unused_team_id: Some("my-team".to_string()),
+                team_id: Some("my-team".to_string()),
                 signature: false,
             }),
+            fs_limits: Default::default(),
+            remote_backend: Default::default(),
         };
 
         // Initialize client with invalid API url to ensure that we don't hit the
@@ -400,9 +404,11 @@ mod tests {
             skip_filesystem: false,
             workers: 10,
             remote_cache_opts: Some(RemoteCacheOpts {
-                unused_team_id: Some("my-team".to_string()),
+                team_id: Some("my-team".to_string()),
                 signature: false,
             }),
+            fs_limits: Default::default(),
+            remote_backend: Default::default(),
         };
 
         let api_client = APIClient::new(format!("http://localhost:{}", port), 200, "2.0.0", true)?;
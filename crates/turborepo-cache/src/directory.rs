@@ -0,0 +1,284 @@
+//! Remote cache backend for a directory shared between machines, e.g. an NFS
+//! mount.
+//!
+//! Unlike `FSCache` this stores the compressed artifacts as-is, along with
+//! their signatures, and never restores them in place. Files are written to a
+//! temporary name and renamed into place so that readers on other machines
+//! never observe a partially written artifact.
+
+use std::{
+    backtrace::Backtrace,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use async_trait::async_trait;
+use camino::Utf8Path;
+use serde::{Deserialize, Serialize};
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
+
+use crate::{
+    remote::{RemoteArtifact, RemoteCacheBackend},
+    CacheError,
+};
+
+pub struct DirectoryCache {
+    directory: AbsoluteSystemPathBuf,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DirectoryCacheMetadata {
+    duration: u64,
+    tag: Option<String>,
+}
+
+impl DirectoryCache {
+    /// Relative directories are resolved against the repository root
+    pub fn new(directory: &Utf8Path, repo_root: &AbsoluteSystemPath) -> Result<Self, CacheError> {
+        let directory = AbsoluteSystemPathBuf::from_unknown(repo_root, directory);
+        directory.create_dir_all()?;
+
+        Ok(Self { directory })
+    }
+
+    fn archive_path(&self, hash: &str) -> AbsoluteSystemPathBuf {
+        self.directory.join_component(&format!("{}.tar.zst", hash))
+    }
+
+    fn metadata_path(&self, hash: &str) -> AbsoluteSystemPathBuf {
+        self.directory
+            .join_component(&format!("{}-meta.json", hash))
+    }
+
+    async fn write_atomic(path: &AbsoluteSystemPath, contents: &[u8]) -> Result<(), CacheError> {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        // Unique per process and write, since the directory is shared with other
+        // machines
+        let tmp_path = path.as_std_path().with_extension(format!(
+            "{}-{}.tmp",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        tokio::fs::write(&tmp_path, contents).await?;
+        if let Err(e) = tokio::fs::rename(&tmp_path, path).await {
+            let _ = tokio::fs::remove_file(&tmp_path).await;
+            return Err(e.into());
+        }
+
+        Ok(())
+    }
+
+    async fn read_metadata(
+        &self,
+        hash: &str,
+    ) -> Result<Option<DirectoryCacheMetadata>, CacheError> {
+        let contents = match tokio::fs::read(self.metadata_path(hash)).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        serde_json::from_slice(&contents)
+            .map(Some)
+            .map_err(|e| CacheError::InvalidMetadata(e, Backtrace::capture()))
+    }
+}
+
+#[async_trait]
+impl RemoteCacheBackend for DirectoryCache {
+    #[tracing::instrument(skip_all)]
+    async fn put_artifact(
+        &self,
+        hash: &str,
+        body: &[u8],
+        duration: u64,
+        tag: Option<&str>,
+    ) -> Result<(), CacheError> {
+        let metadata = serde_json::to_vec(&DirectoryCacheMetadata {
+            duration,
+            tag: tag.map(|tag| tag.to_string()),
+        })
+        .map_err(|e| CacheError::MetadataWriteFailure(e, Backtrace::capture()))?;
+
+        // The metadata file marks the artifact as complete, so it goes last
+        Self::write_atomic(&self.archive_path(hash), body).await?;
+        Self::write_atomic(&self.metadata_path(hash), &metadata).await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn fetch_artifact(&self, hash: &str) -> Result<Option<RemoteArtifact>, CacheError> {
+        let Some(metadata) = self.read_metadata(hash).await? else {
+            return Ok(None);
+        };
+        let body = match tokio::fs::read(self.archive_path(hash)).await {
+            Ok(body) => body,
+            // Another machine might have removed the artifact in the meantime
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Some(RemoteArtifact {
+            body: body.into(),
+            duration: metadata.duration,
+            tag: metadata.tag,
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn artifact_exists(&self, hash: &str) -> Result<Option<u64>, CacheError> {
+        Ok(self
+            .read_metadata(hash)
+            .await?
+            .map(|metadata| metadata.duration))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::Result;
+    use futures::future::try_join_all;
+    use tempfile::tempdir;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::DirectoryCache;
+    use crate::{
+        remote::{RemoteCache, RemoteCacheBackend},
+        signature_authentication::ArtifactSignatureAuthenticator,
+        test_cases::{get_test_cases, TestCase},
+        CacheError, CacheOpts, CacheSource, RemoteCacheOpts,
+    };
+
+    #[tokio::test]
+    async fn test_directory_cache() -> Result<()> {
+        let shared_dir = tempdir()?;
+        let test_cases = get_test_cases();
+
+        try_join_all(
+            test_cases
+                .iter()
+                .map(|test_case| round_trip_test(test_case, shared_dir.path())),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn round_trip_test(test_case: &TestCase, shared_dir: &std::path::Path) -> Result<()> {
+        let repo_root = tempdir()?;
+        let repo_root_path = AbsoluteSystemPathBuf::try_from(repo_root.path())?;
+        test_case.initialize(&repo_root_path)?;
+
+        let shared_dir = camino::Utf8Path::from_path(shared_dir).unwrap();
+        let cache = RemoteCache::new(
+            Box::new(DirectoryCache::new(shared_dir, &repo_root_path)?),
+            &CacheOpts::default(),
+            repo_root_path.to_owned(),
+            None,
+            None,
+        );
+
+        assert!(cache.fetch(test_case.hash).await?.is_none());
+        assert!(cache.exists(test_case.hash).await?.is_none());
+
+        let files: Vec<_> = test_case
+            .files
+            .iter()
+            .map(|f| f.path().to_owned())
+            .collect();
+        cache
+            .put(&repo_root_path, test_case.hash, &files, test_case.duration)
+            .await?;
+
+        let hit = cache.exists(test_case.hash).await?.unwrap();
+        assert_eq!(hit.time_saved, test_case.duration);
+        assert_eq!(hit.source, CacheSource::Remote);
+
+        // Restore into a different checkout sharing the same directory
+        let other_root = tempdir()?;
+        let other_root_path = AbsoluteSystemPathBuf::try_from(other_root.path())?;
+        let other_cache = RemoteCache::new(
+            Box::new(DirectoryCache::new(shared_dir, &other_root_path)?),
+            &CacheOpts::default(),
+            other_root_path.to_owned(),
+            None,
+            None,
+        );
+        let (hit, received_files) = other_cache.fetch(test_case.hash).await?.unwrap();
+        assert_eq!(hit.time_saved, test_case.duration);
+        for (test_file, received_file) in test_case.files.iter().zip(received_files) {
+            assert_eq!(&*received_file, test_file.path());
+            let file_path = other_root_path.resolve(&received_file);
+            if let Some(contents) = test_file.contents() {
+                assert_eq!(std::fs::read_to_string(file_path)?, contents);
+            } else {
+                assert!(file_path.exists());
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_relative_directory() -> Result<()> {
+        let repo_root = tempdir()?;
+        let repo_root_path = AbsoluteSystemPathBuf::try_from(repo_root.path())?;
+
+        let cache = DirectoryCache::new("shared/cache".into(), &repo_root_path)?;
+        cache.put_artifact("hash", b"body", 10, None).await?;
+
+        assert!(repo_root_path
+            .join_components(&["shared", "cache", "hash.tar.zst"])
+            .exists());
+        assert_eq!(cache.artifact_exists("hash").await?, Some(10));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_directory_cache_with_signature() -> Result<()> {
+        let shared_dir = tempdir()?;
+        let shared_dir = camino::Utf8Path::from_path(shared_dir.path()).unwrap();
+        let repo_root = tempdir()?;
+        let repo_root_path = AbsoluteSystemPathBuf::try_from(repo_root.path())?;
+        let test_case = &get_test_cases()[0];
+        test_case.initialize(&repo_root_path)?;
+        let files: Vec<_> = test_case
+            .files
+            .iter()
+            .map(|f| f.path().to_owned())
+            .collect();
+
+        let opts = CacheOpts {
+            remote_cache_opts: Some(RemoteCacheOpts::new(Some("my-team".to_string()), true)),
+            ..CacheOpts::default()
+        };
+        let mut cache = RemoteCache::new(
+            Box::new(DirectoryCache::new(shared_dir, &repo_root_path)?),
+            &opts,
+            repo_root_path.to_owned(),
+            Some("my-team"),
+            None,
+        );
+        cache.set_signer_verifier(ArtifactSignatureAuthenticator::new(
+            b"my-team".to_vec(),
+            Some(b"secret".to_vec()),
+        ));
+        cache
+            .put(&repo_root_path, test_case.hash, &files, test_case.duration)
+            .await?;
+        assert!(cache.fetch(test_case.hash).await?.is_some());
+
+        // Artifacts written without a signature are rejected
+        let unsigned = DirectoryCache::new(shared_dir, &repo_root_path)?;
+        let artifact = unsigned.fetch_artifact(test_case.hash).await?.unwrap();
+        unsigned
+            .put_artifact(test_case.hash, &artifact.body, artifact.duration, None)
+            .await?;
+        assert!(matches!(
+            cache.fetch(test_case.hash).await,
+            Err(CacheError::ArtifactTagMissing(_))
+        ));
+        Ok(())
+    }
+}
@@ -1,7 +1,18 @@
-use std::{backtrace::Backtrace, fs::OpenOptions};
+use std::{
+    backtrace::Backtrace,
+    collections::HashMap,
+    fs::{FileTimes, OpenOptions},
+    io::ErrorKind,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, SystemTime},
+};
 
 use camino::Utf8Path;
 use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
 use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
 use turborepo_analytics::AnalyticsSender;
 use turborepo_api_client::{analytics, analytics::AnalyticsEvent};
@@ -11,9 +22,55 @@ use crate::{
     CacheError, CacheHitMetadata, CacheSource,
 };
 
+// Once we go over the max size we evict down to this fraction of it, so we
+// don't have to scan the cache directory again on the very next write.
+const EVICTION_TARGET_RATIO: f64 = 0.9;
+
 pub struct FSCache {
     cache_directory: AbsoluteSystemPathBuf,
     analytics_recorder: Option<AnalyticsSender>,
+    limits: FSCacheLimits,
+    // Approximate size of the cache directory, only tracked if there's a max
+    // size to enforce. It gets corrected every time we scan the directory.
+    size: Option<AtomicU64>,
+    eviction_lock: Mutex<()>,
+}
+
+/// Bounds for the local filesystem cache.
+/// Entries are evicted least recently used first.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FSCacheLimits {
+    /// Maximum total size of the cache in bytes
+    pub max_size: Option<u64>,
+    /// Maximum time since an entry was last written or restored
+    pub max_age: Option<Duration>,
+}
+
+impl FSCacheLimits {
+    pub fn is_unbounded(&self) -> bool {
+        self.max_size.is_none() && self.max_age.is_none()
+    }
+}
+
+/// A single artifact in the filesystem cache, including its metadata file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheEntry {
+    pub hash: String,
+    pub size: u64,
+    pub last_accessed: SystemTime,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub size: u64,
+}
+
+impl CacheStats {
+    fn add(&mut self, entry: &CacheEntry) {
+        self.entries += 1;
+        self.size += entry.size;
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -46,6 +103,7 @@ impl FSCache {
         override_dir: Option<&Utf8Path>,
         repo_root: &AbsoluteSystemPath,
         analytics_recorder: Option<AnalyticsSender>,
+        limits: FSCacheLimits,
     ) -> Result<Self, CacheError> {
         let cache_directory = Self::resolve_cache_dir(repo_root, override_dir);
         cache_directory.create_dir_all()?;
@@ -53,9 +111,17 @@ impl FSCache {
         Ok(FSCache {
             cache_directory,
             analytics_recorder,
+            limits,
+            // Initialized by the first `prune`
+            size: limits.max_size.map(|_| AtomicU64::new(0)),
+            eviction_lock: Mutex::new(()),
         })
     }
 
+    pub fn cache_directory(&self) -> &AbsoluteSystemPath {
+        &self.cache_directory
+    }
+
     fn log_fetch(&self, event: analytics::CacheEvent, hash: &str, duration: u64) {
         // If analytics fails to record, it's not worth failing the cache
         if let Some(analytics_recorder) = &self.analytics_recorder {
@@ -97,6 +163,12 @@ impl FSCache {
 
         let restored_files = cache_reader.restore(anchor)?;
 
+        // Eviction is based on access times, which filesystems don't reliably
+        // update on reads, so we set it ourselves.
+        if let Err(e) = Self::mark_accessed(&cache_path) {
+            debug!("failed to update access time for {}: {}", cache_path, e);
+        }
+
         let meta = CacheMetadata::read(
             &self
                 .cache_directory
@@ -176,6 +248,165 @@ impl FSCache {
         serde_json::to_writer(metadata_file, &meta)
             .map_err(|e| CacheError::InvalidMetadata(e, Backtrace::capture()))?;
 
+        if let (Some(size), Some(max_size)) = (&self.size, self.limits.max_size) {
+            let entry_size =
+                cache_path.symlink_metadata()?.len() + metadata_path.symlink_metadata()?.len();
+            let new_size = size.fetch_add(entry_size, Ordering::Relaxed) + entry_size;
+            // The entry is already written, so failing to evict shouldn't
+            // fail the put
+            if new_size > max_size {
+                if let Err(e) = self.prune() {
+                    warn!("failed to evict local cache entries: {}", e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn mark_accessed(path: &AbsoluteSystemPath) -> Result<(), std::io::Error> {
+        let mut options = OpenOptions::new();
+        // Windows requires write access to update file times
+        options.write(true);
+        let file = path.open_with_options(options)?;
+        file.set_times(FileTimes::new().set_accessed(SystemTime::now()))
+    }
+
+    /// Lists all entries in the cache, least recently used first
+    pub fn entries(&self) -> Result<Vec<CacheEntry>, CacheError> {
+        let mut entries: HashMap<String, CacheEntry> = HashMap::new();
+        for dir_entry in std::fs::read_dir(&self.cache_directory)? {
+            let dir_entry = dir_entry?;
+            let file_name = dir_entry.file_name();
+            let Some(file_name) = file_name.to_str() else {
+                continue;
+            };
+            let (hash, is_archive) = if let Some(hash) = file_name.strip_suffix(".tar.zst") {
+                (hash, true)
+            } else if let Some(hash) = file_name.strip_suffix(".tar") {
+                (hash, true)
+            } else if let Some(hash) = file_name.strip_suffix("-meta.json") {
+                (hash, false)
+            } else {
+                continue;
+            };
+            let metadata = match dir_entry.metadata() {
+                Ok(metadata) => metadata,
+                // Entry was removed while we were listing the cache
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            let last_accessed = metadata
+                .accessed()
+                .or_else(|_| metadata.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+
+            let entry = entries
+                .entry(hash.to_string())
+                .or_insert_with(|| CacheEntry {
+                    hash: hash.to_string(),
+                    size: 0,
+                    last_accessed,
+                });
+            entry.size += metadata.len();
+            // The archive is what gets touched on fetch, so it takes precedence
+            // over the metadata file
+            if is_archive {
+                entry.last_accessed = last_accessed;
+            }
+        }
+
+        let mut entries = entries.into_values().collect::<Vec<_>>();
+        entries.sort_by(|a, b| {
+            a.last_accessed
+                .cmp(&b.last_accessed)
+                .then_with(|| a.hash.cmp(&b.hash))
+        });
+        Ok(entries)
+    }
+
+    pub fn stats(&self) -> Result<CacheStats, CacheError> {
+        let mut stats = CacheStats::default();
+        for entry in self.entries()? {
+            stats.add(&entry);
+        }
+        Ok(stats)
+    }
+
+    /// Evicts entries that are older than the max age, then least recently
+    /// used entries until the cache fits in the max size.
+    /// Returns what was removed.
+    pub fn prune(&self) -> Result<CacheStats, CacheError> {
+        let _guard = self.eviction_lock.lock().expect("eviction lock poisoned");
+        let entries = self.entries()?;
+        let mut remaining = entries.iter().map(|entry| entry.size).sum::<u64>();
+        // Only evict for size once we're over the limit, and then go a bit
+        // further so the next few writes don't trigger another eviction.
+        let target_size = self
+            .limits
+            .max_size
+            .filter(|max_size| remaining > *max_size)
+            .map(|max_size| (max_size as f64 * EVICTION_TARGET_RATIO) as u64);
+        let now = SystemTime::now();
+
+        let mut removed = CacheStats::default();
+        for entry in &entries {
+            let expired = self.limits.max_age.is_some_and(|max_age| {
+                now.duration_since(entry.last_accessed)
+                    .is_ok_and(|age| age > max_age)
+            });
+            let over_size = target_size.is_some_and(|target| remaining > target);
+            if !expired && !over_size {
+                continue;
+            }
+            self.remove_entry(&entry.hash)?;
+            remaining -= entry.size;
+            removed.add(entry);
+        }
+
+        if let Some(size) = &self.size {
+            size.store(remaining, Ordering::Relaxed);
+        }
+        if removed.entries > 0 {
+            debug!(
+                "evicted {} entries ({} bytes) from the local cache",
+                removed.entries, removed.size
+            );
+        }
+
+        Ok(removed)
+    }
+
+    /// Removes every entry from the cache. Returns what was removed.
+    pub fn clean(&self) -> Result<CacheStats, CacheError> {
+        let _guard = self.eviction_lock.lock().expect("eviction lock poisoned");
+        let mut removed = CacheStats::default();
+        for entry in self.entries()? {
+            self.remove_entry(&entry.hash)?;
+            removed.add(&entry);
+        }
+        if let Some(size) = &self.size {
+            size.store(0, Ordering::Relaxed);
+        }
+        Ok(removed)
+    }
+
+    fn remove_entry(&self, hash: &str) -> Result<(), CacheError> {
+        for file_name in [
+            format!("{}.tar.zst", hash),
+            format!("{}.tar", hash),
+            format!("{}-meta.json", hash),
+        ] {
+            match self
+                .cache_directory
+                .join_component(&file_name)
+                .remove_file()
+            {
+                Ok(()) => {}
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
         Ok(())
     }
 }
@@ -225,7 +456,12 @@ mod test {
         let (analytics_sender, analytics_handle) =
             start_analytics(api_auth.clone(), api_client.clone());
 
-        let cache = FSCache::new(None, repo_root_path, Some(analytics_sender.clone()))?;
+        let cache = FSCache::new(
+            None,
+            repo_root_path,
+            Some(analytics_sender.clone()),
+            FSCacheLimits::default(),
+        )?;
 
         let expected_miss = cache.fetch(repo_root_path, test_case.hash)?;
         assert!(expected_miss.is_none());
@@ -262,4 +498,177 @@ mod test {
         analytics_handle.close_with_timeout().await;
         Ok(())
     }
+
+    fn write_entry(
+        cache: &FSCache,
+        hash: &str,
+        size: usize,
+        last_accessed: SystemTime,
+    ) -> Result<()> {
+        let archive = cache
+            .cache_directory
+            .join_component(&format!("{hash}.tar.zst"));
+        archive.create_with_contents(vec![0u8; size])?;
+        cache
+            .cache_directory
+            .join_component(&format!("{hash}-meta.json"))
+            .create_with_contents("")?;
+        let mut options = OpenOptions::new();
+        options.write(true);
+        archive
+            .open_with_options(options)?
+            .set_times(FileTimes::new().set_accessed(last_accessed))?;
+        Ok(())
+    }
+
+    fn hashes(cache: &FSCache) -> Result<Vec<String>> {
+        Ok(cache
+            .entries()?
+            .into_iter()
+            .map(|entry| entry.hash)
+            .collect())
+    }
+
+    #[test]
+    fn test_entries_sorted_by_access_time() -> Result<()> {
+        let repo_root = tempdir()?;
+        let repo_root_path = AbsoluteSystemPath::from_std_path(repo_root.path())?;
+        let cache = FSCache::new(None, repo_root_path, None, FSCacheLimits::default())?;
+        let now = SystemTime::now();
+
+        write_entry(&cache, "newest", 10, now)?;
+        write_entry(&cache, "oldest", 20, now - Duration::from_secs(120))?;
+        write_entry(&cache, "middle", 30, now - Duration::from_secs(60))?;
+        cache
+            .cache_directory
+            .join_component("unrelated.txt")
+            .create_with_contents("not a cache entry")?;
+
+        assert_eq!(hashes(&cache)?, vec!["oldest", "middle", "newest"]);
+        assert_eq!(
+            cache.stats()?,
+            CacheStats {
+                entries: 3,
+                size: 60
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_prune_max_size_evicts_least_recently_used() -> Result<()> {
+        let repo_root = tempdir()?;
+        let repo_root_path = AbsoluteSystemPath::from_std_path(repo_root.path())?;
+        let unbounded = FSCache::new(None, repo_root_path, None, FSCacheLimits::default())?;
+        let now = SystemTime::now();
+        for (i, hash) in ["a", "b", "c", "d"].iter().enumerate() {
+            write_entry(
+                &unbounded,
+                hash,
+                100,
+                now - Duration::from_secs(60 * (4 - i as u64)),
+            )?;
+        }
+
+        let cache = FSCache::new(
+            None,
+            repo_root_path,
+            None,
+            FSCacheLimits {
+                max_size: Some(250),
+                max_age: None,
+            },
+        )?;
+        let removed = cache.prune()?;
+        assert_eq!(
+            removed,
+            CacheStats {
+                entries: 2,
+                size: 200
+            }
+        );
+        assert_eq!(hashes(&cache)?, vec!["c", "d"]);
+
+        // Under the limit nothing is evicted
+        assert_eq!(cache.prune()?, CacheStats::default());
+
+        write_entry(&cache, "e", 100, now - Duration::from_secs(600))?;
+        assert_eq!(hashes(&cache)?, vec!["e", "c", "d"]);
+        let removed = cache.prune()?;
+        assert_eq!(
+            removed,
+            CacheStats {
+                entries: 1,
+                size: 100
+            }
+        );
+        assert_eq!(hashes(&cache)?, vec!["c", "d"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_prune_max_age() -> Result<()> {
+        let repo_root = tempdir()?;
+        let repo_root_path = AbsoluteSystemPath::from_std_path(repo_root.path())?;
+        let cache = FSCache::new(
+            None,
+            repo_root_path,
+            None,
+            FSCacheLimits {
+                max_size: None,
+                max_age: Some(Duration::from_secs(3600)),
+            },
+        )?;
+        let now = SystemTime::now();
+        write_entry(&cache, "fresh", 10, now - Duration::from_secs(60))?;
+        write_entry(&cache, "stale", 10, now - Duration::from_secs(7200))?;
+
+        let removed = cache.prune()?;
+        assert_eq!(removed.entries, 1);
+        assert_eq!(hashes(&cache)?, vec!["fresh"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_clean() -> Result<()> {
+        let repo_root = tempdir()?;
+        let repo_root_path = AbsoluteSystemPath::from_std_path(repo_root.path())?;
+        let cache = FSCache::new(None, repo_root_path, None, FSCacheLimits::default())?;
+        let now = SystemTime::now();
+        write_entry(&cache, "a", 10, now)?;
+        write_entry(&cache, "b", 10, now)?;
+
+        let removed = cache.clean()?;
+        assert_eq!(removed.entries, 2);
+        assert_eq!(cache.stats()?, CacheStats::default());
+        Ok(())
+    }
+
+    #[test]
+    fn test_fetch_updates_access_time() -> Result<()> {
+        let repo_root = tempdir()?;
+        let repo_root_path = AbsoluteSystemPath::from_std_path(repo_root.path())?;
+        let cache = FSCache::new(None, repo_root_path, None, FSCacheLimits::default())?;
+        let file = AnchoredSystemPathBuf::from_raw("out.txt")?;
+        repo_root_path
+            .resolve(&file)
+            .create_with_contents("output")?;
+
+        cache.put(repo_root_path, "old", &[file.clone()], 0)?;
+        cache.put(repo_root_path, "new", &[file], 0)?;
+        let mut options = OpenOptions::new();
+        options.write(true);
+        cache
+            .cache_directory
+            .join_component("old.tar.zst")
+            .open_with_options(options)?
+            .set_times(
+                FileTimes::new().set_accessed(SystemTime::now() - Duration::from_secs(600)),
+            )?;
+        assert_eq!(hashes(&cache)?[0], "old");
+
+        cache.fetch(repo_root_path, "old")?.unwrap();
+        assert_eq!(hashes(&cache)?[1], "old");
+        Ok(())
+    }
 }
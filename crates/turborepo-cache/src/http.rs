@@ -1,85 +1,55 @@
-use std::{backtrace::Backtrace, io::Write};
+use std::backtrace::Backtrace;
 
-use tracing::debug;
-use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
-use turborepo_analytics::AnalyticsSender;
-use turborepo_api_client::{
-    analytics, analytics::AnalyticsEvent, APIAuth, APIClient, Client, Response,
-};
+use async_trait::async_trait;
+use turborepo_api_client::{APIAuth, APIClient, Client, Response};
 
 use crate::{
-    cache_archive::{CacheReader, CacheWriter},
-    signature_authentication::ArtifactSignatureAuthenticator,
-    CacheError, CacheHitMetadata, CacheOpts, CacheSource,
+    remote::{RemoteArtifact, RemoteCacheBackend},
+    CacheError,
 };
 
+/// Remote cache backend for the Vercel artifacts API
 pub struct HTTPCache {
     client: APIClient,
-    signer_verifier: Option<ArtifactSignatureAuthenticator>,
-    repo_root: AbsoluteSystemPathBuf,
     api_auth: APIAuth,
-    analytics_recorder: Option<AnalyticsSender>,
 }
 
 impl HTTPCache {
-    #[tracing::instrument(skip_all)]
-    pub fn new(
-        client: APIClient,
-        opts: &CacheOpts,
-        repo_root: AbsoluteSystemPathBuf,
-        api_auth: APIAuth,
-        analytics_recorder: Option<AnalyticsSender>,
-    ) -> HTTPCache {
-        let signer_verifier = if opts
-            .remote_cache_opts
-            .as_ref()
-            .map_or(false, |remote_cache_opts| remote_cache_opts.signature)
-        {
-            Some(ArtifactSignatureAuthenticator {
-                team_id: api_auth
-                    .team_id
-                    .as_deref()
-                    .unwrap_or_default()
-                    .as_bytes()
-                    .to_vec(),
-                secret_key_override: None,
-            })
-        } else {
-            None
-        };
+    pub fn new(client: APIClient, api_auth: APIAuth) -> HTTPCache {
+        HTTPCache { client, api_auth }
+    }
+
+    fn get_duration_from_response(response: &Response) -> Result<u64, CacheError> {
+        if let Some(duration_value) = response.headers().get("x-artifact-duration") {
+            let duration = duration_value
+                .to_str()
+                .map_err(|_| CacheError::InvalidDuration(Backtrace::capture()))?;
 
-        HTTPCache {
-            client,
-            signer_verifier,
-            repo_root,
-            api_auth,
-            analytics_recorder,
+            duration
+                .parse::<u64>()
+                .map_err(|_| CacheError::InvalidDuration(Backtrace::capture()))
+        } else {
+            Ok(0)
         }
     }
+}
 
+#[async_trait]
+impl RemoteCacheBackend for HTTPCache {
     #[tracing::instrument(skip_all)]
-    pub async fn put(
+    async fn put_artifact(
         &self,
-        anchor: &AbsoluteSystemPath,
         hash: &str,
-        files: &[AnchoredSystemPathBuf],
+        body: &[u8],
         duration: u64,
+        tag: Option<&str>,
     ) -> Result<(), CacheError> {
-        let mut artifact_body = Vec::new();
-        self.write(&mut artifact_body, anchor, files).await?;
-
-        let tag = self
-            .signer_verifier
-            .as_ref()
-            .map(|signer| signer.generate_tag(hash.as_bytes(), &artifact_body))
-            .transpose()?;
-
         self.client
             .put_artifact(
                 hash,
-                &artifact_body,
+                body,
                 duration,
-                tag.as_deref(),
+                tag,
                 &self.api_auth.token,
                 self.api_auth.team_id.as_deref(),
                 self.api_auth.team_slug.as_deref(),
@@ -90,25 +60,10 @@ impl HTTPCache {
     }
 
     #[tracing::instrument(skip_all)]
-    async fn write(
-        &self,
-        writer: impl Write,
-        anchor: &AbsoluteSystemPath,
-        files: &[AnchoredSystemPathBuf],
-    ) -> Result<(), CacheError> {
-        let mut cache_archive = CacheWriter::from_writer(writer, true)?;
-        for file in files {
-            cache_archive.add_file(anchor, file)?;
-        }
-
-        Ok(())
-    }
-
-    #[tracing::instrument(skip_all)]
-    pub async fn exists(&self, hash: &str) -> Result<Option<CacheHitMetadata>, CacheError> {
+    async fn fetch_artifact(&self, hash: &str) -> Result<Option<RemoteArtifact>, CacheError> {
         let Some(response) = self
             .client
-            .artifact_exists(
+            .fetch_artifact(
                 hash,
                 &self.api_auth.token,
                 self.api_auth.team_id.as_deref(),
@@ -120,50 +75,35 @@ impl HTTPCache {
         };
 
         let duration = Self::get_duration_from_response(&response)?;
+        let tag = response
+            .headers()
+            .get("x-artifact-tag")
+            .map(|tag| {
+                tag.to_str()
+                    .map(|tag| tag.to_string())
+                    .map_err(|_| CacheError::InvalidTag(Backtrace::capture()))
+            })
+            .transpose()?;
 
-        Ok(Some(CacheHitMetadata {
-            source: CacheSource::Remote,
-            time_saved: duration,
-        }))
-    }
-
-    fn get_duration_from_response(response: &Response) -> Result<u64, CacheError> {
-        if let Some(duration_value) = response.headers().get("x-artifact-duration") {
-            let duration = duration_value
-                .to_str()
-                .map_err(|_| CacheError::InvalidDuration(Backtrace::capture()))?;
-
-            duration
-                .parse::<u64>()
-                .map_err(|_| CacheError::InvalidDuration(Backtrace::capture()))
-        } else {
-            Ok(0)
-        }
-    }
+        let body = response.bytes().await.map_err(|e| {
+            CacheError::ApiClientError(
+                Box::new(turborepo_api_client::Error::ReqwestError(e)),
+                Backtrace::capture(),
+            )
+        })?;
 
-    fn log_fetch(&self, event: analytics::CacheEvent, hash: &str, duration: u64) {
-        // If analytics fails to record, it's not worth failing the cache
-        if let Some(analytics_recorder) = &self.analytics_recorder {
-            let analytics_event = AnalyticsEvent {
-                session_id: None,
-                source: analytics::CacheSource::Remote,
-                event,
-                hash: hash.to_string(),
-                duration,
-            };
-            debug!("logging fetch: {analytics_event:?}");
-            let _ = analytics_recorder.send(analytics_event);
-        }
+        Ok(Some(RemoteArtifact {
+            body,
+            duration,
+            tag,
+        }))
     }
 
     #[tracing::instrument(skip_all)]
-    pub async fn fetch(
-        &self,
-        hash: &str,
-    ) -> Result<Option<(CacheHitMetadata, Vec<AnchoredSystemPathBuf>)>, CacheError> {
+    async fn artifact_exists(&self, hash: &str) -> Result<Option<u64>, CacheError> {
         let Some(response) = self
             .client
-            .fetch_artifact(
+            .artifact_exists(
                 hash,
                 &self.api_auth.token,
                 self.api_auth.team_id.as_deref(),
@@ -171,64 +111,10 @@ impl HTTPCache {
             )
             .await?
         else {
-            self.log_fetch(analytics::CacheEvent::Miss, hash, 0);
             return Ok(None);
         };
 
-        let duration = Self::get_duration_from_response(&response)?;
-
-        let body = if let Some(signer_verifier) = &self.signer_verifier {
-            let expected_tag = response
-                .headers()
-                .get("x-artifact-tag")
-                .ok_or(CacheError::ArtifactTagMissing(Backtrace::capture()))?;
-
-            let expected_tag = expected_tag
-                .to_str()
-                .map_err(|_| CacheError::InvalidTag(Backtrace::capture()))?
-                .to_string();
-
-            let body = response.bytes().await.map_err(|e| {
-                CacheError::ApiClientError(
-                    Box::new(turborepo_api_client::Error::ReqwestError(e)),
-                    Backtrace::capture(),
-                )
-            })?;
-            let is_valid = signer_verifier.validate(hash.as_bytes(), &body, &expected_tag)?;
-
-            if !is_valid {
-                return Err(CacheError::InvalidTag(Backtrace::capture()));
-            }
-
-            body
-        } else {
-            response.bytes().await.map_err(|e| {
-                CacheError::ApiClientError(
-                    Box::new(turborepo_api_client::Error::ReqwestError(e)),
-                    Backtrace::capture(),
-                )
-            })?
-        };
-
-        let files = Self::restore_tar(&self.repo_root, &body)?;
-
-        self.log_fetch(analytics::CacheEvent::Hit, hash, duration);
-        Ok(Some((
-            CacheHitMetadata {
-                source: CacheSource::Remote,
-                time_saved: duration,
-            },
-            files,
-        )))
-    }
-
-    #[tracing::instrument(skip_all)]
-    pub(crate) fn restore_tar(
-        root: &AbsoluteSystemPath,
-        body: &[u8],
-    ) -> Result<Vec<AnchoredSystemPathBuf>, CacheError> {
-        let mut cache_reader = CacheReader::from_reader(body, true)?;
-        cache_reader.restore(root)
+        Ok(Some(Self::get_duration_from_response(&response)?))
     }
 }
 
@@ -244,6 +130,7 @@ mod test {
 
     use crate::{
         http::{APIAuth, HTTPCache},
+        remote::RemoteCache,
         test_cases::{get_test_cases, validate_analytics, TestCase},
         CacheOpts, CacheSource,
     };
@@ -285,11 +172,12 @@ mod test {
         let (analytics_recorder, analytics_handle) =
             start_analytics(api_auth.clone(), api_client.clone());
 
-        let cache = HTTPCache::new(
-            api_client,
+        let team_id = api_auth.team_id.clone();
+        let cache = RemoteCache::new(
+            Box::new(HTTPCache::new(api_client, api_auth)),
             &opts,
             repo_root_path.to_owned(),
-            api_auth,
+            team_id.as_deref(),
             Some(analytics_recorder),
         );
 
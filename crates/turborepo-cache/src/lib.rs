@@ -7,13 +7,19 @@
 mod async_cache;
 /// The core cache creation and restoration logic.
 pub mod cache_archive;
+/// Remote cache backed by a shared directory
+pub mod directory;
 /// File system cache
 pub mod fs;
-/// Remote cache
+/// Remote cache backed by the Vercel artifacts API
 pub mod http;
 /// A wrapper that allows reads and writes from the file system and remote
 /// cache.
 mod multiplexer;
+/// Remote cache that delegates artifact storage to a pluggable backend
+pub mod remote;
+/// Remote cache backed by an S3-compatible object store
+pub mod s3;
 /// Cache signature authentication lets users provide a private key to sign
 /// their cache payloads.
 pub mod signature_authentication;
@@ -27,7 +33,7 @@ use camino::Utf8PathBuf;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::signature_authentication::SignatureError;
+use crate::{fs::FSCacheLimits, s3::S3Opts, signature_authentication::SignatureError};
 
 #[derive(Debug, Error)]
 pub enum CacheError {
@@ -48,6 +54,17 @@ pub enum CacheError {
     SignatureError(#[from] SignatureError, #[backtrace] Backtrace),
     #[error("invalid duration")]
     InvalidDuration(#[backtrace] Backtrace),
+    #[error("failed to contact S3 remote cache: {0}")]
+    S3Request(#[source] reqwest::Error, #[backtrace] Backtrace),
+    #[error("S3 remote cache responded with {0}")]
+    S3Status(reqwest::StatusCode, #[backtrace] Backtrace),
+    #[error(
+        "S3 credentials not found. You must set the AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY \
+         environment variables"
+    )]
+    S3CredentialsMissing(#[backtrace] Backtrace),
+    #[error("invalid S3 endpoint: {0}")]
+    InvalidS3Endpoint(String, #[backtrace] Backtrace),
     #[error("Invalid file path: {0}")]
     PathError(#[from] turbopath::PathError, #[backtrace] Backtrace),
     #[error("links in the cache are cyclic")]
@@ -106,19 +123,36 @@ pub struct CacheOpts {
     pub skip_filesystem: bool,
     pub workers: u32,
     pub remote_cache_opts: Option<RemoteCacheOpts>,
+    pub fs_limits: FSCacheLimits,
+    pub remote_backend: RemoteCacheBackendOpts,
+}
+
+/// Where remote cache artifacts are stored
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum RemoteCacheBackendOpts {
+    /// The Vercel artifacts API, using the linked team
+    #[default]
+    Vercel,
+    S3(S3Opts),
+    /// A directory shared between machines, e.g. an NFS mount
+    Directory(Utf8PathBuf),
+}
+
+impl RemoteCacheBackendOpts {
+    /// Whether the backend needs `turbo link` to have been run
+    pub fn requires_link(&self) -> bool {
+        matches!(self, Self::Vercel)
+    }
 }
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct RemoteCacheOpts {
-    unused_team_id: Option<String>,
+    team_id: Option<String>,
     signature: bool,
 }
 
 impl RemoteCacheOpts {
-    pub fn new(unused_team_id: Option<String>, signature: bool) -> Self {
-        Self {
-            unused_team_id,
-            signature,
-        }
+    pub fn new(team_id: Option<String>, signature: bool) -> Self {
+        Self { team_id, signature }
     }
 }
@@ -5,20 +5,30 @@ use turbopath::{AbsoluteSystemPath, AnchoredSystemPathBuf};
 use turborepo_analytics::AnalyticsSender;
 use turborepo_api_client::{APIAuth, APIClient};
 
-use crate::{fs::FSCache, http::HTTPCache, CacheError, CacheHitMetadata, CacheOpts};
+use crate::{
+    directory::DirectoryCache,
+    fs::FSCache,
+    http::HTTPCache,
+    remote::{RemoteCache, RemoteCacheBackend},
+    s3::{S3Cache, S3Credentials},
+    CacheError, CacheHitMetadata, CacheOpts, RemoteCacheBackendOpts,
+};
+
+// The configured backend along with the team id used to sign artifacts
+type BackendWithTeamId = (Box<dyn RemoteCacheBackend>, Option<String>);
 
 pub struct CacheMultiplexer {
     // We use an `AtomicBool` instead of removing the cache because that would require
     // wrapping the cache in a `Mutex` which would cause a lot of contention.
     // This does create a mild race condition where we might use the cache
     // even though another thread might be removing it, but that's fine.
-    should_use_http_cache: AtomicBool,
+    should_use_remote_cache: AtomicBool,
     // Just for keeping track of whether we've already printed a warning about the remote cache
     // being read-only
     should_print_skipping_remote_put: AtomicBool,
     remote_cache_read_only: bool,
     fs: Option<FSCache>,
-    http: Option<HTTPCache>,
+    remote: Option<RemoteCache>,
 }
 
 impl CacheMultiplexer {
@@ -31,12 +41,12 @@ impl CacheMultiplexer {
         analytics_recorder: Option<AnalyticsSender>,
     ) -> Result<Self, CacheError> {
         let use_fs_cache = !opts.skip_filesystem;
-        let use_http_cache = !opts.skip_remote;
+        let use_remote_cache = !opts.skip_remote;
 
         // Since the above two flags are not mutually exclusive it is possible to
         // configure yourself out of having a cache. We should tell you about it
         // but we shouldn't fail your build for that reason.
-        if !use_fs_cache && !use_http_cache {
+        if !use_fs_cache && !use_remote_cache {
             warn!("no caches are enabled");
         }
 
@@ -46,37 +56,80 @@ impl CacheMultiplexer {
                     opts.override_dir.as_deref(),
                     repo_root,
                     analytics_recorder.clone(),
+                    opts.fs_limits,
                 )
             })
             .transpose()?;
+        // Apply the limits up front so stale entries from previous runs are
+        // evicted even if this run doesn't write anything. Eviction is best
+        // effort, a failure here shouldn't fail the run.
+        if let Some(fs_cache) = fs_cache.as_ref() {
+            if !opts.fs_limits.is_unbounded() {
+                if let Err(err) = fs_cache.prune() {
+                    warn!("failed to evict local cache entries: {err}");
+                }
+            }
+        }
 
-        let http_cache = use_http_cache
-            .then_some(api_auth)
-            .flatten()
-            .map(|api_auth| {
-                HTTPCache::new(
-                    api_client,
-                    opts,
-                    repo_root.to_owned(),
-                    api_auth,
-                    analytics_recorder.clone(),
-                )
-            });
+        let remote_cache = if use_remote_cache {
+            Self::remote_backend(opts, repo_root, api_client, api_auth)?.map(
+                |(backend, team_id)| {
+                    RemoteCache::new(
+                        backend,
+                        opts,
+                        repo_root.to_owned(),
+                        team_id.as_deref(),
+                        analytics_recorder.clone(),
+                    )
+                },
+            )
+        } else {
+            None
+        };
 
         Ok(CacheMultiplexer {
             should_print_skipping_remote_put: AtomicBool::new(true),
-            should_use_http_cache: AtomicBool::new(http_cache.is_some()),
+            should_use_remote_cache: AtomicBool::new(remote_cache.is_some()),
             remote_cache_read_only: opts.remote_cache_read_only,
             fs: fs_cache,
-            http: http_cache,
+            remote: remote_cache,
+        })
+    }
+
+    fn remote_backend(
+        opts: &CacheOpts,
+        repo_root: &AbsoluteSystemPath,
+        api_client: APIClient,
+        api_auth: Option<APIAuth>,
+    ) -> Result<Option<BackendWithTeamId>, CacheError> {
+        let configured_team_id = || {
+            opts.remote_cache_opts
+                .as_ref()
+                .and_then(|remote_cache_opts| remote_cache_opts.team_id.clone())
+        };
+        Ok(match &opts.remote_backend {
+            RemoteCacheBackendOpts::Vercel => api_auth.map(|api_auth| {
+                let team_id = api_auth.team_id.clone();
+                let backend: Box<dyn RemoteCacheBackend> =
+                    Box::new(HTTPCache::new(api_client, api_auth));
+                (backend, team_id)
+            }),
+            RemoteCacheBackendOpts::S3(s3_opts) => Some((
+                Box::new(S3Cache::new(s3_opts, S3Credentials::from_env()?)?),
+                configured_team_id(),
+            )),
+            RemoteCacheBackendOpts::Directory(directory) => Some((
+                Box::new(DirectoryCache::new(directory, repo_root)?),
+                configured_team_id(),
+            )),
         })
     }
 
     // This is technically a TOCTOU bug, but at worst it'll cause
     // a few extra cache requests.
-    fn get_http_cache(&self) -> Option<&HTTPCache> {
-        if self.should_use_http_cache.load(Ordering::Relaxed) {
-            self.http.as_ref()
+    fn get_remote_cache(&self) -> Option<&RemoteCache> {
+        if self.should_use_remote_cache.load(Ordering::Relaxed) {
+            self.remote.as_ref()
         } else {
             None
         }
@@ -95,8 +148,8 @@ impl CacheMultiplexer {
             .map(|fs| fs.put(anchor, key, files, duration))
             .transpose()?;
 
-        let http_result = match self.get_http_cache() {
-            Some(http) => {
+        let remote_result = match self.get_remote_cache() {
+            Some(remote) => {
                 if self.remote_cache_read_only {
                     if self
                         .should_print_skipping_remote_put
@@ -111,21 +164,21 @@ impl CacheMultiplexer {
                     // write to it
                     None
                 } else {
-                    let http_result = http.put(anchor, key, files, duration).await;
+                    let remote_result = remote.put(anchor, key, files, duration).await;
 
-                    Some(http_result)
+                    Some(remote_result)
                 }
             }
             _ => None,
         };
 
-        match http_result {
+        match remote_result {
             Some(Err(CacheError::ApiClientError(
                 box turborepo_api_client::Error::CacheDisabled { .. },
                 ..,
             ))) => {
-                warn!("failed to put to http cache: cache disabled");
-                self.should_use_http_cache.store(false, Ordering::Relaxed);
+                warn!("failed to put to remote cache: cache disabled");
+                self.should_use_remote_cache.store(false, Ordering::Relaxed);
                 Ok(())
             }
             Some(Err(e)) => Err(e),
@@ -145,9 +198,9 @@ impl CacheMultiplexer {
             }
         }
 
-        if let Some(http) = self.get_http_cache() {
+        if let Some(remote) = self.get_remote_cache() {
             if let Ok(Some((CacheHitMetadata { source, time_saved }, files))) =
-                http.fetch(key).await
+                remote.fetch(key).await
             {
                 // Store this into fs cache. We can ignore errors here because we know
                 // we have previously successfully stored in HTTP cache, and so the overall
@@ -176,13 +229,13 @@ impl CacheMultiplexer {
             }
         }
 
-        if let Some(http) = self.get_http_cache() {
-            match http.exists(key).await {
+        if let Some(remote) = self.get_remote_cache() {
+            match remote.exists(key).await {
                 cache_hit @ Ok(Some(_)) => {
                     return cache_hit;
                 }
                 Ok(None) => {}
-                Err(err) => debug!("failed to check http cache: {:?}", err),
+                Err(err) => debug!("failed to check remote cache: {:?}", err),
             }
         }
 
@@ -0,0 +1,197 @@
+use std::{backtrace::Backtrace, io::Write};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use tracing::debug;
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
+use turborepo_analytics::AnalyticsSender;
+use turborepo_api_client::{analytics, analytics::AnalyticsEvent};
+
+use crate::{
+    cache_archive::{CacheReader, CacheWriter},
+    signature_authentication::ArtifactSignatureAuthenticator,
+    CacheError, CacheHitMetadata, CacheOpts, CacheSource,
+};
+
+/// An artifact as stored by a remote cache backend: a compressed tarball
+/// along with the metadata needed to restore and verify it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteArtifact {
+    pub body: Bytes,
+    pub duration: u64,
+    pub tag: Option<String>,
+}
+
+/// Storage for remote cache artifacts.
+///
+/// Backends only move opaque artifact bodies around. Archiving, signing and
+/// verification are handled by `RemoteCache` so they behave the same
+/// regardless of where artifacts end up.
+#[async_trait]
+pub trait RemoteCacheBackend: Send + Sync {
+    async fn put_artifact(
+        &self,
+        hash: &str,
+        body: &[u8],
+        duration: u64,
+        tag: Option<&str>,
+    ) -> Result<(), CacheError>;
+
+    async fn fetch_artifact(&self, hash: &str) -> Result<Option<RemoteArtifact>, CacheError>;
+
+    /// Returns the duration of the task that produced the artifact if it
+    /// exists
+    async fn artifact_exists(&self, hash: &str) -> Result<Option<u64>, CacheError>;
+}
+
+pub struct RemoteCache {
+    backend: Box<dyn RemoteCacheBackend>,
+    signer_verifier: Option<ArtifactSignatureAuthenticator>,
+    repo_root: AbsoluteSystemPathBuf,
+    analytics_recorder: Option<AnalyticsSender>,
+}
+
+impl RemoteCache {
+    #[tracing::instrument(skip_all)]
+    pub fn new(
+        backend: Box<dyn RemoteCacheBackend>,
+        opts: &CacheOpts,
+        repo_root: AbsoluteSystemPathBuf,
+        team_id: Option<&str>,
+        analytics_recorder: Option<AnalyticsSender>,
+    ) -> RemoteCache {
+        let signer_verifier = if opts
+            .remote_cache_opts
+            .as_ref()
+            .map_or(false, |remote_cache_opts| remote_cache_opts.signature)
+        {
+            Some(ArtifactSignatureAuthenticator {
+                team_id: team_id.unwrap_or_default().as_bytes().to_vec(),
+                secret_key_override: None,
+            })
+        } else {
+            None
+        };
+
+        RemoteCache {
+            backend,
+            signer_verifier,
+            repo_root,
+            analytics_recorder,
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn set_signer_verifier(&mut self, signer_verifier: ArtifactSignatureAuthenticator) {
+        self.signer_verifier = Some(signer_verifier);
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn put(
+        &self,
+        anchor: &AbsoluteSystemPath,
+        hash: &str,
+        files: &[AnchoredSystemPathBuf],
+        duration: u64,
+    ) -> Result<(), CacheError> {
+        let mut artifact_body = Vec::new();
+        self.write(&mut artifact_body, anchor, files).await?;
+
+        let tag = self
+            .signer_verifier
+            .as_ref()
+            .map(|signer| signer.generate_tag(hash.as_bytes(), &artifact_body))
+            .transpose()?;
+
+        self.backend
+            .put_artifact(hash, &artifact_body, duration, tag.as_deref())
+            .await
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn write(
+        &self,
+        writer: impl Write,
+        anchor: &AbsoluteSystemPath,
+        files: &[AnchoredSystemPathBuf],
+    ) -> Result<(), CacheError> {
+        let mut cache_archive = CacheWriter::from_writer(writer, true)?;
+        for file in files {
+            cache_archive.add_file(anchor, file)?;
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn exists(&self, hash: &str) -> Result<Option<CacheHitMetadata>, CacheError> {
+        Ok(self
+            .backend
+            .artifact_exists(hash)
+            .await?
+            .map(|duration| CacheHitMetadata {
+                source: CacheSource::Remote,
+                time_saved: duration,
+            }))
+    }
+
+    fn log_fetch(&self, event: analytics::CacheEvent, hash: &str, duration: u64) {
+        // If analytics fails to record, it's not worth failing the cache
+        if let Some(analytics_recorder) = &self.analytics_recorder {
+            let analytics_event = AnalyticsEvent {
+                session_id: None,
+                source: analytics::CacheSource::Remote,
+                event,
+                hash: hash.to_string(),
+                duration,
+            };
+            debug!("logging fetch: {analytics_event:?}");
+            let _ = analytics_recorder.send(analytics_event);
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub async fn fetch(
+        &self,
+        hash: &str,
+    ) -> Result<Option<(CacheHitMetadata, Vec<AnchoredSystemPathBuf>)>, CacheError> {
+        let Some(RemoteArtifact {
+            body,
+            duration,
+            tag,
+        }) = self.backend.fetch_artifact(hash).await?
+        else {
+            self.log_fetch(analytics::CacheEvent::Miss, hash, 0);
+            return Ok(None);
+        };
+
+        if let Some(signer_verifier) = &self.signer_verifier {
+            let expected_tag = tag.ok_or(CacheError::ArtifactTagMissing(Backtrace::capture()))?;
+            let is_valid = signer_verifier.validate(hash.as_bytes(), &body, &expected_tag)?;
+
+            if !is_valid {
+                return Err(CacheError::InvalidTag(Backtrace::capture()));
+            }
+        }
+
+        let files = Self::restore_tar(&self.repo_root, &body)?;
+
+        self.log_fetch(analytics::CacheEvent::Hit, hash, duration);
+        Ok(Some((
+            CacheHitMetadata {
+                source: CacheSource::Remote,
+                time_saved: duration,
+            },
+            files,
+        )))
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub(crate) fn restore_tar(
+        root: &AbsoluteSystemPath,
+        body: &[u8],
+    ) -> Result<Vec<AnchoredSystemPathBuf>, CacheError> {
+        let mut cache_reader = CacheReader::from_reader(body, true)?;
+        cache_reader.restore(root)
+    }
+}
@@ -54,6 +54,15 @@ impl Vendor {
             .unwrap_or_default()
     }
 
+    /// Gets the ref that the current change will be merged into, if the CI
+    /// vendor exposes one. This is generally only set for pull request builds.
+    pub fn get_base_ref() -> Option<String> {
+        Vendor::infer()
+            .and_then(|v| v.base_ref_env_var)
+            .and_then(|v| env::var(v).ok())
+            .filter(|base_ref| !base_ref.is_empty())
+    }
+
     fn infer_inner() -> Option<&'static Vendor> {
         for env in get_vendors() {
             if let Some(eval_env) = &env.eval_env {
@@ -204,4 +213,28 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_error_annotations() {
+        let annotate = |name| {
+            get_vendor(name)
+                .behavior
+                .and_then(|behavior| behavior.error_annotation)
+                .unwrap()
+        };
+        assert_eq!(
+            annotate("GitHub Actions")("web#build failed", "exited (1)\nsee logs"),
+            "::error title=web#build failed::exited (1)%0Asee logs\n"
+        );
+        assert_eq!(
+            annotate("Azure Pipelines")("web#build failed", "exited (1)"),
+            "##vso[task.logissue type=error]web#build failed: exited (1)\r\n"
+        );
+        assert_eq!(
+            annotate("TeamCity")("web#build failed", "exited (1)"),
+            "##teamcity[buildProblem description='web#build failed: exited (1)']\n"
+        );
+        // GitLab can't annotate from the log, it uses `--report` artifacts instead
+        assert!(get_vendor("GitLab CI").behavior.is_none());
+    }
 }
@@ -1,4 +1,5 @@
 type GroupPrefixFn = fn(group_name: &str) -> String;
+type AnnotationFn = fn(title: &str, message: &str) -> String;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct VendorBehavior {
@@ -6,6 +7,9 @@ pub struct VendorBehavior {
     pub group_suffix: GroupPrefixFn,
     pub error_group_prefix: Option<GroupPrefixFn>,
     pub error_group_suffix: Option<GroupPrefixFn>,
+    /// Renders a line that the vendor surfaces as an error annotation on the
+    /// build, rather than only in its log
+    pub error_annotation: Option<AnnotationFn>,
 }
 
 impl VendorBehavior {
@@ -15,6 +19,7 @@ impl VendorBehavior {
             group_suffix: suffix,
             error_group_prefix: None,
             error_group_suffix: None,
+            error_annotation: None,
         }
     }
 
@@ -23,4 +28,63 @@ impl VendorBehavior {
         self.error_group_suffix = Some(suffix);
         self
     }
+
+    pub fn with_error_annotation(mut self, annotation: AnnotationFn) -> Self {
+        self.error_annotation = Some(annotation);
+        self
+    }
+}
+
+// Workflow command values can't contain newlines, and property values
+// additionally can't contain the `:` and `,` delimiters.
+// https://github.com/actions/toolkit/blob/main/packages/core/src/command.ts
+pub(crate) fn escape_github_data(data: &str) -> String {
+    data.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+pub(crate) fn escape_github_property(property: &str) -> String {
+    escape_github_data(property)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+// https://github.com/microsoft/azure-pipelines-tasks/blob/master/docs/authoring/commands.md
+pub(crate) fn escape_azure_data(data: &str) -> String {
+    data.replace('%', "%AZP25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+        .replace(']', "%5D")
+        .replace(';', "%3B")
+}
+
+// https://www.jetbrains.com/help/teamcity/service-messages.html#Escaped+Values
+pub(crate) fn escape_teamcity_value(value: &str) -> String {
+    value
+        .replace('|', "||")
+        .replace('\'', "|'")
+        .replace('\n', "|n")
+        .replace('\r', "|r")
+        .replace('[', "|[")
+        .replace(']', "|]")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escaping() {
+        assert_eq!(
+            escape_github_data("50% done\nnext line"),
+            "50%25 done%0Anext line"
+        );
+        assert_eq!(
+            escape_github_property("web:build, docs"),
+            "web%3Abuild%2C docs"
+        );
+        assert_eq!(escape_azure_data("a;b]\n"), "a%3Bb%5D%0A");
+        assert_eq!(escape_teamcity_value("it's [done]"), "it|'s |[done|]");
+    }
 }
@@ -1,6 +1,9 @@
 use std::{collections::HashMap, fmt::Debug, sync::OnceLock};
 
-use crate::vendor_behavior::VendorBehavior;
+use crate::vendor_behavior::{
+    escape_azure_data, escape_github_data, escape_github_property, escape_teamcity_value,
+    VendorBehavior,
+};
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct VendorEnvs {
@@ -18,6 +21,8 @@ pub struct Vendor {
     pub sha_env_var: Option<&'static str>,
     pub branch_env_var: Option<&'static str>,
     pub username_env_var: Option<&'static str>,
+    /// The branch or commit a pull request will be merged into
+    pub base_ref_env_var: Option<&'static str>,
     pub behavior: Option<VendorBehavior>,
 }
 
@@ -38,6 +43,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -51,6 +57,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -64,6 +71,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -77,10 +85,19 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
-                    behavior: Some(VendorBehavior::new(
-                        |group_name| format!("##[group]{group_name}\r\n"),
-                        |_| String::from("##[endgroup]\r\n"),
-                    )),
+                    base_ref_env_var: Some("SYSTEM_PULLREQUEST_TARGETBRANCH"),
+                    behavior: Some(
+                        VendorBehavior::new(
+                            |group_name| format!("##[group]{group_name}\r\n"),
+                            |_| String::from("##[endgroup]\r\n"),
+                        )
+                        .with_error_annotation(|title, message| {
+                            format!(
+                                "##vso[task.logissue type=error]{}\r\n",
+                                escape_azure_data(&format!("{title}: {message}"))
+                            )
+                        }),
+                    ),
                 },
                 Vendor {
                     name: "Bamboo",
@@ -93,6 +110,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -106,6 +124,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: Some("BITBUCKET_PR_DESTINATION_BRANCH"),
                     behavior: None,
                 },
                 Vendor {
@@ -119,6 +138,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -132,6 +152,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -145,6 +166,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: Some("BUILDKITE_PULL_REQUEST_BASE_BRANCH"),
                     behavior: None,
                 },
                 Vendor {
@@ -158,6 +180,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -171,6 +194,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -184,6 +208,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -197,6 +222,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -214,6 +240,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -227,6 +254,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: Some("DRONE_TARGET_BRANCH"),
                     behavior: None,
                 },
                 Vendor {
@@ -240,6 +268,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -253,6 +282,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -266,6 +296,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: Some("GITHUB_SHA"),
                     branch_env_var: Some("GITHUB_REF_NAME"),
                     username_env_var: Some("GITHUB_ACTOR"),
+                    base_ref_env_var: Some("GITHUB_BASE_REF"),
                     behavior: Some(
                         VendorBehavior::new(
                             |group_name| format!("::group::{group_name}\n"),
@@ -274,7 +305,14 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                         .with_error(
                             |group_name| format!("\x1B[;31m{group_name}\x1B[;0m\n"),
                             |_| String::new(),
-                        ),
+                        )
+                        .with_error_annotation(|title, message| {
+                            format!(
+                                "::error title={}::{}\n",
+                                escape_github_property(title),
+                                escape_github_data(message)
+                            )
+                        }),
                     ),
                 },
                 Vendor {
@@ -288,6 +326,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: Some("CI_MERGE_REQUEST_DIFF_BASE_SHA"),
                     behavior: None,
                 },
                 Vendor {
@@ -301,6 +340,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -314,6 +354,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -327,6 +368,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -340,6 +382,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -353,6 +396,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -366,6 +410,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: Some("CHANGE_TARGET"),
                     behavior: None,
                 },
                 Vendor {
@@ -379,6 +424,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -392,6 +438,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -405,6 +452,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -418,6 +466,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -431,6 +480,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -444,6 +494,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -457,6 +508,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -470,6 +522,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -483,6 +536,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -496,6 +550,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -513,6 +568,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -526,6 +582,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -539,6 +596,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -552,10 +610,19 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
-                    behavior: Some(VendorBehavior::new(
-                        |group_name| format!("##teamcity[blockOpened name='{group_name}']"),
-                        |group_name| format!("##teamcity[blockClosed name='{group_name}']"),
-                    )),
+                    base_ref_env_var: None,
+                    behavior: Some(
+                        VendorBehavior::new(
+                            |group_name| format!("##teamcity[blockOpened name='{group_name}']"),
+                            |group_name| format!("##teamcity[blockClosed name='{group_name}']"),
+                        )
+                        .with_error_annotation(|title, message| {
+                            format!(
+                                "##teamcity[buildProblem description='{}']\n",
+                                escape_teamcity_value(&format!("{title}: {message}"))
+                            )
+                        }),
+                    ),
                 },
                 Vendor {
                     name: "Travis CI",
@@ -568,6 +635,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: Some(VendorBehavior::new(
                         |group_name| format!("travis_fold:start:{group_name}\r\n"),
                         |group_name| format!("travis_fold:end:{group_name}\r\n"),
@@ -584,6 +652,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: Some("VERCEL_GIT_COMMIT_SHA"),
                     branch_env_var: Some("VERCEL_GIT_COMMIT_REF"),
                     username_env_var: Some("VERCEL_GIT_COMMIT_AUTHOR_LOGIN"),
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -597,6 +666,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -614,6 +684,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -627,6 +698,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
                 Vendor {
@@ -640,6 +712,7 @@ pub(crate) fn get_vendors() -> &'static [Vendor] {
                     sha_env_var: None,
                     branch_env_var: None,
                     username_env_var: None,
+                    base_ref_env_var: None,
                     behavior: None,
                 },
             ]
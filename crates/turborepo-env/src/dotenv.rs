@@ -0,0 +1,368 @@
+//! Loads dotenv files so their variables can be passed to a task.
+//!
+//! The syntax and precedence follow `turbopack-env`'s `load_env`: files are
+//! applied in order and a variable is only set if neither the environment nor
+//! an earlier file defined it. Values can reference other variables with
+//! `$NAME`, `${NAME}` or `${NAME:-default}`, which are resolved against the
+//! environment and everything loaded before them. Unlike the `dotenvs` crate
+//! that turbopack uses, nothing here touches the process environment.
+
+use std::collections::HashMap;
+
+use crate::{EnvironmentVariableMap, Error};
+
+/// A variable loaded from a dotenv file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DotEnvVar {
+    pub name: String,
+    pub value: String,
+    /// The file the variable was loaded from, as it was passed to
+    /// [`DotEnv::load`]
+    pub file: String,
+}
+
+/// The variables loaded from a list of dotenv files, in the order they were
+/// loaded.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DotEnv {
+    vars: Vec<DotEnvVar>,
+}
+
+impl DotEnv {
+    /// Loads `files`, given as `(name, contents)` pairs in order of
+    /// precedence. Variables already present in `env` are never overridden.
+    pub fn load<'a>(
+        env: &EnvironmentVariableMap,
+        files: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> Result<Self, Error> {
+        let mut vars: Vec<DotEnvVar> = Vec::new();
+        let mut loaded: HashMap<String, usize> = HashMap::new();
+        for (file, contents) in files {
+            let mut parser = Parser::new(contents);
+            loop {
+                let lookup = |name: &str| {
+                    env.get(name)
+                        .or_else(|| loaded.get(name).map(|&index| &vars[index].value))
+                        .cloned()
+                };
+                let Some((name, value)) =
+                    parser.next(&lookup).map_err(|line| Error::InvalidDotEnv {
+                        file: file.to_string(),
+                        line,
+                    })?
+                else {
+                    break;
+                };
+                if env.contains_key(name) || loaded.contains_key(name) {
+                    continue;
+                }
+                loaded.insert(name.to_string(), vars.len());
+                vars.push(DotEnvVar {
+                    name: name.to_string(),
+                    value,
+                    file: file.to_string(),
+                });
+            }
+        }
+        Ok(Self { vars })
+    }
+
+    pub fn vars(&self) -> &[DotEnvVar] {
+        &self.vars
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vars.is_empty()
+    }
+
+    /// Drops every variable whose name doesn't match `wildcard_patterns`.
+    pub fn retain_matching(&mut self, wildcard_patterns: &[impl AsRef<str>]) -> Result<(), Error> {
+        let allowed = self.to_env_map().from_wildcards(wildcard_patterns)?;
+        self.vars.retain(|var| allowed.contains_key(&var.name));
+        Ok(())
+    }
+
+    pub fn to_env_map(&self) -> EnvironmentVariableMap {
+        EnvironmentVariableMap(
+            self.vars
+                .iter()
+                .map(|var| (var.name.clone(), var.value.clone()))
+                .collect(),
+        )
+    }
+
+    /// Groups the variables by the file they came from, in load order.
+    pub fn by_file(&self) -> Vec<(&str, EnvironmentVariableMap)> {
+        let mut files: Vec<(&str, EnvironmentVariableMap)> = Vec::new();
+        for var in &self.vars {
+            let index = match files.iter().position(|(file, _)| *file == var.file) {
+                Some(index) => index,
+                None => {
+                    files.push((&var.file, EnvironmentVariableMap::default()));
+                    files.len() - 1
+                }
+            };
+            files[index].1.insert(var.name.clone(), var.value.clone());
+        }
+        files
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    rest: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        let input = input.strip_prefix('\u{FEFF}').unwrap_or(input);
+        Self { input, rest: input }
+    }
+
+    fn line(&self) -> usize {
+        let consumed = &self.input[..self.input.len() - self.rest.len()];
+        consumed.matches('\n').count() + 1
+    }
+
+    /// Returns the next variable, or the line number of a syntax error.
+    fn next(
+        &mut self,
+        lookup: &impl Fn(&str) -> Option<String>,
+    ) -> Result<Option<(&'a str, String)>, usize> {
+        loop {
+            self.rest = self.rest.trim_start();
+            if self.rest.is_empty() {
+                return Ok(None);
+            }
+            if self.rest.starts_with('#') {
+                let end = self.rest.find(['\n', '\r']).unwrap_or(self.rest.len());
+                self.rest = &self.rest[end..];
+                continue;
+            }
+            return match self.pair(lookup) {
+                Some(pair) => Ok(Some(pair)),
+                None => Err(self.line()),
+            };
+        }
+    }
+
+    fn pair(&mut self, lookup: &impl Fn(&str) -> Option<String>) -> Option<(&'a str, String)> {
+        let mut rest = self.rest;
+        if let Some(after) = rest.strip_prefix("export") {
+            if after.starts_with([' ', '\t']) {
+                rest = after.trim_start_matches([' ', '\t']);
+            }
+        }
+        let (name, rest) = split_name(rest)?;
+        let rest = rest
+            .trim_start_matches([' ', '\t'])
+            .strip_prefix('=')?
+            .trim_start_matches([' ', '\t']);
+
+        let quoted = |quote: char| {
+            let contents = rest.strip_prefix(quote)?;
+            let end = contents.find(quote)?;
+            Some((&contents[..end], &contents[end + 1..]))
+        };
+        let (value, rest) = if let Some((value, rest)) = quoted('`').or_else(|| quoted('\'')) {
+            (value.to_string(), rest)
+        } else if let Some((value, rest)) = quoted('"') {
+            (expand(value, true, lookup), rest)
+        } else {
+            // Unquoted values end at the end of the line or at a comment
+            let line_end = rest.find(['\n', '\r']).unwrap_or(rest.len());
+            let value_end = rest[..line_end].find('#').unwrap_or(line_end);
+            (
+                expand(rest[..value_end].trim(), false, lookup),
+                &rest[value_end..],
+            )
+        };
+
+        self.rest = rest;
+        Some((name, value))
+    }
+}
+
+fn split_name(input: &str) -> Option<(&str, &str)> {
+    if !input.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        return None;
+    }
+    let end = input
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.'))
+        .unwrap_or(input.len());
+    Some(input.split_at(end))
+}
+
+fn expand(
+    mut input: &str,
+    expand_new_lines: bool,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> String {
+    let mut output = String::new();
+    while !input.is_empty() {
+        if let Some((value, rest)) = substitution(input, lookup) {
+            output.push_str(&value.unwrap_or_default());
+            input = rest;
+            continue;
+        }
+        if let Some(rest) = input.strip_prefix('\\') {
+            let escaped = match rest.chars().next() {
+                Some('\\') => "\\",
+                Some('$') => "$",
+                Some('n') if expand_new_lines => "\n",
+                Some('n') => "\\n",
+                _ => {
+                    output.push('\\');
+                    input = rest;
+                    continue;
+                }
+            };
+            output.push_str(escaped);
+            input = &rest[1..];
+            continue;
+        }
+        // A `$` that doesn't start a substitution is kept as is
+        let end = input
+            .char_indices()
+            .skip(1)
+            .find(|(_, c)| matches!(c, '\\' | '$'))
+            .map_or(input.len(), |(end, _)| end);
+        output.push_str(&input[..end]);
+        input = &input[end..];
+    }
+    output
+}
+
+// Parses `$NAME`, `${NAME}` or `${NAME:-default}`, returning the resolved
+// value and the remaining input
+fn substitution<'a>(
+    input: &'a str,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Option<(Option<String>, &'a str)> {
+    let input = input.strip_prefix('$')?;
+    let Some(braced) = input.strip_prefix('{') else {
+        let (name, rest) = split_name(input)?;
+        return Some((lookup(name), rest));
+    };
+
+    let (name, rest) = split_name(braced)?;
+    let (default, rest) = match rest.strip_prefix(":-") {
+        Some(default) => match substitution(default, lookup) {
+            Some((value, rest)) => (value, rest),
+            None => {
+                let end = default.find('}')?;
+                (Some(default[..end].to_string()), &default[end..])
+            }
+        },
+        None => (None, rest),
+    };
+    let rest = rest.strip_prefix('}')?;
+    Some((lookup(name).or(default), rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    fn env(vars: &[(&str, &str)]) -> EnvironmentVariableMap {
+        EnvironmentVariableMap(
+            vars.iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        )
+    }
+
+    fn load(env_vars: &[(&str, &str)], files: &[(&str, &str)]) -> Vec<(String, String)> {
+        DotEnv::load(&env(env_vars), files.iter().copied())
+            .unwrap()
+            .vars()
+            .iter()
+            .map(|var| (var.name.clone(), var.value.clone()))
+            .collect()
+    }
+
+    #[test_case("A=1", &[("A", "1")] ; "simple")]
+    #[test_case("export A=1\nB = 2 ", &[("A", "1"), ("B", "2")] ; "export and spaces")]
+    #[test_case("# comment\n#\nA=1 # trailing\n", &[("A", "1")] ; "comments")]
+    #[test_case("A=\nB=2", &[("A", ""), ("B", "2")] ; "empty value")]
+    #[test_case("A='$B\\n'", &[("A", "$B\\n")] ; "single quotes are literal")]
+    #[test_case("A=`$B`", &[("A", "$B")] ; "backticks are literal")]
+    #[test_case("A=\"a\\nb # c\"", &[("A", "a\nb # c")] ; "double quotes expand newlines")]
+    #[test_case("A=a\\nb", &[("A", "a\\nb")] ; "unquoted newlines are kept")]
+    #[test_case("A=\"multi\nline\"", &[("A", "multi\nline")] ; "multiline")]
+    #[test_case("A=1\nB=$A-${A}", &[("A", "1"), ("B", "1-1")] ; "substitution")]
+    #[test_case("A=${MISSING:-fallback}", &[("A", "fallback")] ; "default")]
+    #[test_case("A=${MISSING:-${HOME}}", &[("A", "/home/me")] ; "nested default")]
+    #[test_case("A=$MISSING!", &[("A", "!")] ; "missing variable")]
+    #[test_case("A=\\$HOME costs $5", &[("A", "$HOME costs $5")] ; "escaped dollar")]
+    #[test_case("A=1\nA=2", &[("A", "1")] ; "first definition wins")]
+    #[test_case("HOME=/root\nA=$HOME", &[("A", "/home/me")] ; "environment wins")]
+    fn test_parse(contents: &str, expected: &[(&str, &str)]) {
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        assert_eq!(
+            load(&[("HOME", "/home/me")], &[(".env", contents)]),
+            expected
+        );
+    }
+
+    #[test_case("A", 1 ; "missing equals")]
+    #[test_case("A=1\n\n1A=2", 3 ; "invalid name")]
+    #[test_case("A=\"quoted\" trailing", 1 ; "trailing text")]
+    fn test_invalid(contents: &str, expected_line: usize) {
+        match DotEnv::load(&env(&[]), [(".env", contents)]) {
+            Err(Error::InvalidDotEnv { file, line }) => {
+                assert_eq!(file, ".env");
+                assert_eq!(line, expected_line);
+            }
+            other => panic!("expected a syntax error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_precedence() {
+        let dot_env = DotEnv::load(
+            &env(&[("FROM_ENV", "env")]),
+            [
+                (".env.local", "LOCAL=local\nSHARED=local"),
+                (
+                    ".env",
+                    "SHARED=default\nFROM_ENV=file\nREF=$LOCAL\nDEFAULT=default",
+                ),
+            ],
+        )
+        .unwrap();
+
+        let vars: Vec<_> = dot_env
+            .vars()
+            .iter()
+            .map(|var| (var.name.as_str(), var.value.as_str(), var.file.as_str()))
+            .collect();
+        assert_eq!(
+            vars,
+            vec![
+                ("LOCAL", "local", ".env.local"),
+                ("SHARED", "local", ".env.local"),
+                ("REF", "local", ".env"),
+                ("DEFAULT", "default", ".env"),
+            ]
+        );
+
+        let by_file = dot_env.by_file();
+        assert_eq!(by_file.len(), 2);
+        assert_eq!(by_file[0].0, ".env.local");
+        assert_eq!(by_file[1].1.names(), vec!["DEFAULT", "REF"]);
+    }
+
+    #[test]
+    fn test_retain_matching() {
+        let mut dot_env =
+            DotEnv::load(&env(&[]), [(".env", "API_URL=a\nAPI_KEY=b\nOTHER=c")]).unwrap();
+        dot_env.retain_matching(&["API_*", "!API_KEY"]).unwrap();
+        assert_eq!(dot_env.to_env_map().names(), vec!["API_URL"]);
+    }
+}
@@ -12,6 +12,8 @@ use serde::Serialize;
 use sha2::{Digest, Sha256};
 use thiserror::Error;
 
+pub mod dotenv;
+
 const DEFAULT_ENV_VARS: [&str; 1] = ["VERCEL_ANALYTICS_ID"];
 
 /// Environment mode after we've resolved the `Infer` variant
@@ -34,6 +36,8 @@ impl std::fmt::Display for ResolvedEnvMode {
 pub enum Error {
     #[error("Failed to parse regex: {0}")]
     Regex(#[from] regex::Error),
+    #[error("invalid syntax in {file} on line {line}")]
+    InvalidDotEnv { file: String, line: usize },
 }
 
 // TODO: Consider using immutable data structures here
@@ -0,0 +1,646 @@
+//! Caches the hashes of package inputs and invalidates them as files change,
+//! so that unchanged packages don't have to be re-hashed on every run.
+
+use std::{collections::HashMap, future::IntoFuture, sync::Arc, time::Duration};
+
+use ignore::{
+    gitignore::{Gitignore, GitignoreBuilder},
+    Match,
+};
+use notify::{event::ModifyKind, Event, EventKind};
+use thiserror::Error;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tracing::{debug, warn};
+use turbopath::{
+    AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPath, AnchoredSystemPathBuf,
+    RelativeUnixPathBuf,
+};
+
+use crate::{
+    cookies::{CookieError, CookieWatcher, CookieWriter, CookiedRequest},
+    NotifyError,
+};
+
+pub type FileHashes = HashMap<RelativeUnixPathBuf, String>;
+
+/// The inputs of a package that a set of file hashes is computed for
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HashSpec {
+    pub package_path: AnchoredSystemPathBuf,
+    pub inputs: Vec<String>,
+}
+
+impl HashSpec {
+    /// Whether a change to `path` could change the hashes for this spec.
+    fn is_affected_by(&self, path: &AnchoredSystemPath) -> bool {
+        // Inputs can reach outside of the package, in which case we can't
+        // tell which changes are relevant.
+        if self.inputs.iter().any(|input| input.contains("..")) {
+            return true;
+        }
+        // A change to an ancestor covers removing or renaming the package
+        path.as_path().starts_with(self.package_path.as_path())
+            || self.package_path.as_path().starts_with(path.as_path())
+    }
+
+    /// The path of a file in this package, as it's keyed in the hashes
+    fn package_relative(&self, path: &AnchoredSystemPath) -> Option<RelativeUnixPathBuf> {
+        let relative = path
+            .as_path()
+            .strip_prefix(self.package_path.as_path())
+            .ok()?;
+        Some(AnchoredSystemPath::new(relative.to_str()?).ok()?.to_unix())
+    }
+}
+
+/// Computes the hashes for a spec. This runs on a blocking thread, as it
+/// typically shells out to git or walks the file system.
+pub trait InputHasher: Send + Sync + 'static {
+    fn hash_inputs(&self, spec: &HashSpec) -> Result<FileHashes, String>;
+}
+
+impl<F> InputHasher for F
+where
+    F: Fn(&HashSpec) -> Result<FileHashes, String> + Send + Sync + 'static,
+{
+    fn hash_inputs(&self, spec: &HashSpec) -> Result<FileHashes, String> {
+        self(spec)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    CookieError(#[from] CookieError),
+    #[error("hashwatcher has closed")]
+    Closed,
+    #[error("hashwatcher request timed out")]
+    Timeout(#[from] tokio::time::error::Elapsed),
+    #[error("failed to hash inputs: {0}")]
+    Hashing(String),
+    #[error("inputs changed while they were being hashed")]
+    Invalidated,
+    #[error("file hashes are still being computed")]
+    Pending,
+}
+
+impl From<mpsc::error::SendError<CookiedRequest<Query>>> for Error {
+    fn from(_: mpsc::error::SendError<CookiedRequest<Query>>) -> Self {
+        Error::Closed
+    }
+}
+
+impl From<oneshot::error::RecvError> for Error {
+    fn from(_: oneshot::error::RecvError) -> Self {
+        Error::Closed
+    }
+}
+
+type Responder = oneshot::Sender<Result<Arc<FileHashes>, Error>>;
+
+#[derive(Debug)]
+pub struct Query {
+    spec: HashSpec,
+    // Whether to wait for the hashes if they aren't cached yet
+    wait: bool,
+    resp: Responder,
+}
+
+pub struct HashWatcher {
+    cookie_jar: CookieWriter,
+    // _exit_ch exists to trigger a close on the receiver when an instance
+    // of this struct is dropped. The task that is receiving events will exit,
+    // dropping the other sender for the broadcast channel, causing all receivers
+    // to be notified of a close.
+    _exit_ch: oneshot::Sender<()>,
+    query_ch: mpsc::Sender<CookiedRequest<Query>>,
+}
+
+impl HashWatcher {
+    pub fn new(
+        root: &AbsoluteSystemPath,
+        cookie_jar: CookieWriter,
+        recv: broadcast::Receiver<Result<Event, NotifyError>>,
+        hasher: impl InputHasher,
+    ) -> Self {
+        let (exit_ch, exit_signal) = oneshot::channel();
+        let (query_ch, query_recv) = mpsc::channel(256);
+        let cookie_root = cookie_jar.root().to_owned();
+        tokio::task::spawn(
+            HashTracker::new(
+                root.to_owned(),
+                cookie_root,
+                Arc::new(hasher),
+                exit_signal,
+                recv,
+                query_recv,
+            )
+            .watch(),
+        );
+        Self {
+            cookie_jar,
+            _exit_ch: exit_ch,
+            query_ch,
+        }
+    }
+
+    /// Returns the hashes for the given spec, computing them if they aren't
+    /// cached. A computation that outlives the timeout keeps running, so
+    /// later requests for the same spec can be served from the cache.
+    pub async fn get_file_hashes(
+        &self,
+        spec: HashSpec,
+        timeout: Duration,
+    ) -> Result<Arc<FileHashes>, Error> {
+        self.query(spec, true, timeout).await
+    }
+
+    /// Returns the hashes for the given spec if they are cached. Otherwise
+    /// starts computing them in the background and returns `Error::Pending`.
+    pub async fn get_cached_file_hashes(
+        &self,
+        spec: HashSpec,
+        timeout: Duration,
+    ) -> Result<Arc<FileHashes>, Error> {
+        self.query(spec, false, timeout).await
+    }
+
+    async fn query(
+        &self,
+        spec: HashSpec,
+        wait: bool,
+        timeout: Duration,
+    ) -> Result<Arc<FileHashes>, Error> {
+        let (tx, rx) = oneshot::channel();
+        let req = Query {
+            spec,
+            wait,
+            resp: tx,
+        };
+        let cookied_request = self.cookie_jar.cookie_request(req).await?;
+        self.query_ch.send(cookied_request).await?;
+        tokio::time::timeout(timeout, rx).await??
+    }
+}
+
+enum Entry {
+    /// The hashes are being computed. The version distinguishes this
+    /// computation from one that was started before an invalidation.
+    Pending {
+        version: u64,
+        waiters: Vec<Responder>,
+    },
+    Ready(Arc<FileHashes>),
+}
+
+struct Computed {
+    spec: HashSpec,
+    version: u64,
+    result: Result<FileHashes, String>,
+}
+
+struct HashTracker<H> {
+    root: AbsoluteSystemPathBuf,
+    cookie_root: AbsoluteSystemPathBuf,
+    hasher: Arc<H>,
+
+    entries: HashMap<HashSpec, Entry>,
+    next_version: u64,
+
+    exit_signal: oneshot::Receiver<()>,
+
+    recv: broadcast::Receiver<Result<Event, NotifyError>>,
+
+    query_recv: mpsc::Receiver<CookiedRequest<Query>>,
+
+    computed_tx: mpsc::UnboundedSender<Computed>,
+    computed_recv: mpsc::UnboundedReceiver<Computed>,
+
+    cookie_watcher: CookieWatcher<Query>,
+
+    ignores: Ignores,
+}
+
+impl<H: InputHasher> HashTracker<H> {
+    fn new(
+        root: AbsoluteSystemPathBuf,
+        cookie_root: AbsoluteSystemPathBuf,
+        hasher: Arc<H>,
+        exit_signal: oneshot::Receiver<()>,
+        recv: broadcast::Receiver<Result<Event, NotifyError>>,
+        query_recv: mpsc::Receiver<CookiedRequest<Query>>,
+    ) -> Self {
+        let (computed_tx, computed_recv) = mpsc::unbounded_channel();
+        Self {
+            root: root.clone(),
+            cookie_root: cookie_root.clone(),
+            hasher,
+            entries: HashMap::new(),
+            next_version: 0,
+            exit_signal,
+            recv,
+            query_recv,
+            computed_tx,
+            computed_recv,
+            cookie_watcher: CookieWatcher::new(cookie_root),
+            ignores: Ignores::new(root),
+        }
+    }
+
+    fn handle_cookied_query(&mut self, cookied_query: CookiedRequest<Query>) {
+        if let Some(query) = self.cookie_watcher.check_request(cookied_query) {
+            self.handle_query(query);
+        }
+    }
+
+    fn handle_query(&mut self, Query { spec, wait, resp }: Query) {
+        match self.entries.get_mut(&spec) {
+            Some(Entry::Ready(hashes)) => {
+                // If the client has gone away, we don't care about the error
+                let _ = resp.send(Ok(hashes.clone()));
+            }
+            Some(Entry::Pending { waiters, .. }) if wait => waiters.push(resp),
+            Some(Entry::Pending { .. }) => {
+                let _ = resp.send(Err(Error::Pending));
+            }
+            None => {
+                let version = self.next_version;
+                self.next_version += 1;
+                let waiters = if wait {
+                    vec![resp]
+                } else {
+                    let _ = resp.send(Err(Error::Pending));
+                    Vec::new()
+                };
+                self.entries
+                    .insert(spec.clone(), Entry::Pending { version, waiters });
+                let hasher = self.hasher.clone();
+                let computed_tx = self.computed_tx.clone();
+                tokio::task::spawn_blocking(move || {
+                    let result = hasher.hash_inputs(&spec);
+                    let _ = computed_tx.send(Computed {
+                        spec,
+                        version,
+                        result,
+                    });
+                });
+            }
+        }
+    }
+
+    fn handle_computed(&mut self, computed: Computed) {
+        let Computed {
+            spec,
+            version,
+            result,
+        } = computed;
+        // The entry was invalidated while we were hashing, so this result may
+        // not reflect what's on disk.
+        if !matches!(
+            self.entries.get(&spec),
+            Some(Entry::Pending { version: current, .. }) if *current == version
+        ) {
+            return;
+        }
+        let Some(Entry::Pending { waiters, .. }) = self.entries.remove(&spec) else {
+            unreachable!("checked for a pending entry above");
+        };
+        match result {
+            Ok(hashes) => {
+                let hashes = Arc::new(hashes);
+                for waiter in waiters {
+                    let _ = waiter.send(Ok(hashes.clone()));
+                }
+                self.entries.insert(spec, Entry::Ready(hashes));
+            }
+            Err(message) => {
+                debug!("failed to hash {}: {}", spec.package_path, message);
+                for waiter in waiters {
+                    let _ = waiter.send(Err(Error::Hashing(message.clone())));
+                }
+            }
+        }
+    }
+
+    fn handle_file_event(
+        &mut self,
+        file_event: Result<Result<Event, NotifyError>, broadcast::error::RecvError>,
+    ) {
+        match file_event {
+            Err(broadcast::error::RecvError::Closed) => (),
+            Err(e @ broadcast::error::RecvError::Lagged(_)) => self.on_error(e.to_string()),
+            Ok(Err(error)) => self.on_error(error.to_string()),
+            Ok(Ok(file_event)) => {
+                for path in file_event.paths {
+                    let path = AbsoluteSystemPathBuf::try_from(path)
+                        .expect("filewatching should produce absolute paths");
+                    if let Some(queries) = self
+                        .cookie_watcher
+                        .pop_ready_requests(file_event.kind, &path)
+                    {
+                        for query in queries {
+                            self.handle_query(query);
+                        }
+                        continue;
+                    }
+                    // Hashes only depend on file contents, which reads and
+                    // metadata changes like restored mtimes don't affect
+                    let changes_contents = !matches!(
+                        file_event.kind,
+                        EventKind::Access(_) | EventKind::Modify(ModifyKind::Metadata(_))
+                    );
+                    if !changes_contents || path.as_path().starts_with(self.cookie_root.as_path()) {
+                        continue;
+                    }
+                    let Ok(path) = self.root.anchor(&path) else {
+                        // irrelevant filesystem update
+                        continue;
+                    };
+                    self.handle_path_change(&path);
+                }
+            }
+        }
+    }
+
+    async fn watch(mut self) {
+        loop {
+            tokio::select! {
+                _ = &mut self.exit_signal => return,
+                Some(query) = self.query_recv.recv().into_future() => self.handle_cookied_query(query),
+                Some(computed) = self.computed_recv.recv().into_future() => self.handle_computed(computed),
+                file_event = self.recv.recv().into_future() => self.handle_file_event(file_event)
+            }
+        }
+    }
+
+    /// on_error takes the conservative approach of considering everything
+    /// changed in the event of any error related to filewatching
+    fn on_error(&mut self, err: String) {
+        warn!(
+            "encountered filewatching error, flushing all file hashes: {}",
+            err
+        );
+        self.invalidate(|_, _| true);
+    }
+
+    fn handle_path_change(&mut self, path: &AnchoredSystemPath) {
+        let mut components = path.components();
+        // Hashes only depend on file contents, which changes inside of .git don't
+        // affect
+        if components.next().map(|c| c.as_str()) == Some(".git") {
+            return;
+        }
+        // Ignore files change which files are hashed anywhere below them
+        if path.as_path().file_name() == Some(".gitignore".as_ref()) {
+            debug!("{} changed, flushing all file hashes", path);
+            self.ignores.clear();
+            self.invalidate(|_, _| true);
+            return;
+        }
+        // Ignored files, such as task logs and outputs, change on every run.
+        // They're only hashed if git tracks them anyway.
+        let is_ignored = self.ignores.is_ignored(path);
+        self.invalidate(|spec, entry| {
+            if !spec.is_affected_by(path) {
+                return false;
+            }
+            let is_affected = match entry {
+                _ if !is_ignored => true,
+                Entry::Ready(hashes) => spec
+                    .package_relative(path)
+                    .map_or(false, |path| hashes.contains_key(&path)),
+                Entry::Pending { .. } => false,
+            };
+            if is_affected {
+                debug!(
+                    "file change at {} invalidated hashes for {}",
+                    path, spec.package_path
+                );
+            }
+            is_affected
+        });
+    }
+
+    fn invalidate(&mut self, is_affected: impl Fn(&HashSpec, &Entry) -> bool) {
+        self.entries.retain(|spec, entry| {
+            if !is_affected(spec, entry) {
+                return true;
+            }
+            if let Entry::Pending { waiters, .. } = entry {
+                for waiter in waiters.drain(..) {
+                    let _ = waiter.send(Err(Error::Invalidated));
+                }
+            }
+            false
+        });
+    }
+}
+
+/// The repository's `.gitignore` files, loaded as they're needed
+struct Ignores {
+    root: AbsoluteSystemPathBuf,
+    matchers: HashMap<AnchoredSystemPathBuf, Option<Gitignore>>,
+}
+
+impl Ignores {
+    fn new(root: AbsoluteSystemPathBuf) -> Self {
+        Self {
+            root,
+            matchers: HashMap::new(),
+        }
+    }
+
+    fn clear(&mut self) {
+        self.matchers.clear();
+    }
+
+    fn is_ignored(&mut self, path: &AnchoredSystemPath) -> bool {
+        let absolute_path = self.root.resolve(path);
+        let is_dir = absolute_path.as_path().is_dir();
+        // The closest .gitignore to a path takes precedence
+        let mut dir = path.parent();
+        while let Some(current) = dir {
+            let root = &self.root;
+            let matcher = self.matchers.entry(current.to_owned()).or_insert_with(|| {
+                let dir = root.resolve(current);
+                let file = dir.join_component(".gitignore");
+                if !file.exists() {
+                    return None;
+                }
+                let mut builder = GitignoreBuilder::new(dir.as_std_path());
+                if let Some(err) = builder.add(file.as_std_path()) {
+                    debug!("failed to read {}: {}", file, err);
+                }
+                builder.build().ok()
+            });
+            if let Some(matcher) = matcher {
+                match matcher.matched_path_or_any_parents(absolute_path.as_std_path(), is_dir) {
+                    Match::Ignore(_) => return true,
+                    Match::Whitelist(_) => return false,
+                    Match::None => (),
+                }
+            }
+            dir = current.parent();
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPathBuf, RelativeUnixPathBuf};
+
+    use super::{Error, FileHashes, HashSpec, HashWatcher};
+    use crate::{cookies::CookieWriter, FileSystemWatcher};
+
+    fn spec(package_path: &str) -> HashSpec {
+        HashSpec {
+            package_path: AnchoredSystemPathBuf::from_raw(package_path).unwrap(),
+            inputs: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invalidates_changed_packages() {
+        let timeout = Duration::from_secs(2);
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path())
+            .unwrap()
+            .to_realpath()
+            .unwrap();
+        repo_root.join_component(".git").create_dir_all().unwrap();
+        repo_root
+            .join_component(".gitignore")
+            .create_with_contents(".turbo\n")
+            .unwrap();
+        for package in ["a", "b"] {
+            let file = repo_root.join_components(&["packages", package, "index.js"]);
+            file.ensure_dir().unwrap();
+            file.create_with_contents("initial").unwrap();
+        }
+
+        let watcher = FileSystemWatcher::new_with_default_cookie_dir(&repo_root)
+            .await
+            .unwrap();
+        let cookie_jar = CookieWriter::new(watcher.cookie_dir(), Duration::from_secs(2));
+        let hashed = Arc::new(AtomicUsize::new(0));
+        let hasher = {
+            let repo_root = repo_root.clone();
+            let hashed = hashed.clone();
+            move |spec: &HashSpec| {
+                hashed.fetch_add(1, Ordering::SeqCst);
+                let contents = repo_root
+                    .resolve(&spec.package_path)
+                    .join_component("index.js")
+                    .read_to_string()
+                    .map_err(|e| e.to_string())?;
+                Ok(FileHashes::from([(
+                    RelativeUnixPathBuf::new("index.js").unwrap(),
+                    contents,
+                )]))
+            }
+        };
+        let hash_watcher = HashWatcher::new(&repo_root, cookie_jar, watcher.subscribe(), hasher);
+
+        let a = spec("packages/a");
+        let hashes = hash_watcher
+            .get_file_hashes(a.clone(), timeout)
+            .await
+            .unwrap();
+        assert_eq!(hashes.values().next().unwrap(), "initial");
+        assert_eq!(hashed.load(Ordering::SeqCst), 1);
+
+        // Served from the cache
+        hash_watcher
+            .get_file_hashes(a.clone(), timeout)
+            .await
+            .unwrap();
+        assert_eq!(hashed.load(Ordering::SeqCst), 1);
+
+        // A change to another package doesn't invalidate the hashes
+        repo_root
+            .join_components(&["packages", "b", "index.js"])
+            .create_with_contents("changed")
+            .unwrap();
+        hash_watcher
+            .get_file_hashes(a.clone(), timeout)
+            .await
+            .unwrap();
+        assert_eq!(hashed.load(Ordering::SeqCst), 1);
+
+        // Neither does writing an ignored file to the package
+        let log_file = repo_root.join_components(&["packages", "a", ".turbo", "turbo-build.log"]);
+        log_file.ensure_dir().unwrap();
+        log_file.create_with_contents("logs").unwrap();
+        hash_watcher
+            .get_file_hashes(a.clone(), timeout)
+            .await
+            .unwrap();
+        assert_eq!(hashed.load(Ordering::SeqCst), 1);
+
+        // A change to the package does
+        repo_root
+            .join_components(&["packages", "a", "index.js"])
+            .create_with_contents("changed")
+            .unwrap();
+        let hashes = hash_watcher
+            .get_file_hashes(a.clone(), timeout)
+            .await
+            .unwrap();
+        assert_eq!(hashes.values().next().unwrap(), "changed");
+        assert_eq!(hashed.load(Ordering::SeqCst), 2);
+
+        // Different inputs are cached separately
+        let a_with_inputs = HashSpec {
+            inputs: vec!["src/**".to_string()],
+            ..a
+        };
+        hash_watcher
+            .get_file_hashes(a_with_inputs, timeout)
+            .await
+            .unwrap();
+        assert_eq!(hashed.load(Ordering::SeqCst), 3);
+
+        // Cache-only lookups don't wait for the hashes, but do compute them
+        let b = spec("packages/b");
+        let result = hash_watcher
+            .get_cached_file_hashes(b.clone(), timeout)
+            .await;
+        assert!(matches!(result, Err(Error::Pending)));
+        hash_watcher
+            .get_file_hashes(b.clone(), timeout)
+            .await
+            .unwrap();
+        let hashes = hash_watcher
+            .get_cached_file_hashes(b, timeout)
+            .await
+            .unwrap();
+        assert_eq!(hashes.values().next().unwrap(), "changed");
+        assert_eq!(hashed.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn test_is_affected_by() {
+        let a = spec("packages/a");
+        let path = |raw: &str| AnchoredSystemPathBuf::from_raw(raw).unwrap();
+        assert!(a.is_affected_by(&path("packages/a/src/index.js")));
+        assert!(a.is_affected_by(&path("packages")));
+        assert!(!a.is_affected_by(&path("packages/b/index.js")));
+        assert!(!a.is_affected_by(&path("packages/ab/index.js")));
+        assert!(spec("").is_affected_by(&path("packages/b/index.js")));
+
+        let outside = HashSpec {
+            inputs: vec!["../shared/**".to_string()],
+            ..a
+        };
+        assert!(outside.is_affected_by(&path("packages/b/index.js")));
+    }
+}
@@ -39,6 +39,7 @@ pub mod cookies;
 #[cfg(target_os = "macos")]
 mod fsevent;
 pub mod globwatcher;
+pub mod hash_watcher;
 pub mod package_watcher;
 
 #[cfg(not(target_os = "macos"))]
@@ -0,0 +1,129 @@
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+/// Extensions of the files whose imports are checked
+pub const SOURCE_EXTENSIONS: &[&str] = &["js", "jsx", "mjs", "cjs", "ts", "tsx", "mts", "cts"];
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Import<'a> {
+    pub specifier: &'a str,
+    /// 1-based line of the specifier
+    pub line: usize,
+}
+
+fn import_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r#"(?:\bfrom\s*|\bimport\s*|\b(?:require|import)\s*\(\s*)["']([^"'\n]+)["']"#)
+            .unwrap()
+    })
+}
+
+/// Finds the module specifiers of the static imports, re-exports, `require`
+/// calls and dynamic imports with a string literal in `source`.
+///
+/// This works line by line rather than parsing the source, so lines that
+/// start a comment are skipped but an import inside a string literal is
+/// still reported.
+pub fn imports(source: &str) -> Vec<Import> {
+    source
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let line = line.trim_start();
+            !(line.starts_with("//") || line.starts_with("/*") || line.starts_with('*'))
+        })
+        .flat_map(|(index, line)| {
+            import_regex()
+                .captures_iter(line)
+                .map(move |captures| Import {
+                    specifier: captures.get(1).expect("regex has a group").as_str(),
+                    line: index + 1,
+                })
+        })
+        .collect()
+}
+
+/// The package a bare specifier imports from, e.g. `@repo/ui` for
+/// `@repo/ui/button`. Returns `None` for relative, absolute and URL-like
+/// specifiers such as `node:fs`.
+pub fn package_name(specifier: &str) -> Option<&str> {
+    if specifier.starts_with('.') || specifier.starts_with('/') || specifier.contains(':') {
+        return None;
+    }
+    let mut segments = specifier.splitn(3, '/');
+    let first = segments.next()?;
+    if first.starts_with('@') {
+        let second = segments.next()?;
+        Some(&specifier[..first.len() + 1 + second.len()])
+    } else {
+        Some(first)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test]
+    fn test_imports() {
+        let source = r#"import React from "react";
+import { Button,
+  Card } from '@repo/ui/button';
+import "./styles.css";
+export * from "@repo/utils";
+// import { old } from "@repo/legacy";
+const config = require("@repo/config");
+const lazy = () => import("@repo/lazy");
+type Props = import("@repo/types").Props;
+"#;
+        assert_eq!(
+            imports(source),
+            [
+                Import {
+                    specifier: "react",
+                    line: 1
+                },
+                Import {
+                    specifier: "@repo/ui/button",
+                    line: 3
+                },
+                Import {
+                    specifier: "./styles.css",
+                    line: 4
+                },
+                Import {
+                    specifier: "@repo/utils",
+                    line: 5
+                },
+                Import {
+                    specifier: "@repo/config",
+                    line: 7
+                },
+                Import {
+                    specifier: "@repo/lazy",
+                    line: 8
+                },
+                Import {
+                    specifier: "@repo/types",
+                    line: 9
+                },
+            ]
+        );
+    }
+
+    #[test_case("react", Some("react") ; "unscoped")]
+    #[test_case("lodash/fp/map", Some("lodash") ; "unscoped subpath")]
+    #[test_case("@repo/ui", Some("@repo/ui") ; "scoped")]
+    #[test_case("@repo/ui/button/index", Some("@repo/ui") ; "scoped subpath")]
+    #[test_case("@repo", None ; "scope only")]
+    #[test_case("./button", None ; "relative")]
+    #[test_case("/abs/path", None ; "absolute")]
+    #[test_case("node:fs", None ; "node builtin")]
+    fn test_package_name(specifier: &str, expected: Option<&str>) {
+        assert_eq!(package_name(specifier), expected);
+    }
+}
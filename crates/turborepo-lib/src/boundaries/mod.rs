@@ -0,0 +1,476 @@
+//! Package boundaries: the rules in the root turbo.json `boundaries` about
+//! which tagged packages may depend on each other, and a scan of each
+//! package's JS/TS imports for workspaces it uses without declaring them.
+//! Used by `turbo boundaries`.
+
+mod imports;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use ignore::WalkBuilder;
+use itertools::Itertools;
+use serde::Serialize;
+use thiserror::Error;
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, RelativeUnixPath};
+use turborepo_repository::package_graph::{PackageGraph, PackageInfo, PackageName, PackageNode};
+
+use self::imports::{imports, package_name, SOURCE_EXTENSIONS};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("unable to read {path}: {source}")]
+    Read {
+        path: AbsoluteSystemPathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("unable to list the sources of {package}: {source}")]
+    Walk {
+        package: PackageName,
+        #[source]
+        source: ignore::Error,
+    },
+    #[error(transparent)]
+    Path(#[from] turbopath::PathError),
+}
+
+/// The `boundaries` of the root turbo.json
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BoundariesConfig {
+    /// Rules for the packages with each tag, keyed by tag
+    pub tags: BTreeMap<String, TagRules>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TagRules {
+    /// Restricts the packages that a package with the tag depends on
+    pub dependencies: Option<Permissions>,
+    /// Restricts the packages that depend on a package with the tag
+    pub dependents: Option<Permissions>,
+}
+
+/// The tags that the package on the other side of a dependency may have. It
+/// must have none of the `deny` tags and, if `allow` is set, one of the
+/// `allow` tags.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Permissions {
+    pub allow: Option<BTreeSet<String>>,
+    pub deny: BTreeSet<String>,
+}
+
+enum Rejection<'a> {
+    Denied(&'a str),
+    NotAllowed(&'a BTreeSet<String>),
+}
+
+impl Permissions {
+    fn check(&self, tags: &BTreeSet<String>) -> Result<(), Rejection> {
+        if let Some(denied) = self.deny.iter().find(|tag| tags.contains(*tag)) {
+            return Err(Rejection::Denied(denied));
+        }
+        match &self.allow {
+            Some(allow) if allow.is_disjoint(tags) => Err(Rejection::NotAllowed(allow)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A dependency or import that breaks the boundaries, located at the import
+/// or at the dependency in the package's manifest
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Violation {
+    /// Unix path of the file relative to the repository root
+    pub file: String,
+    /// 1-based line in `file`
+    pub line: usize,
+    pub message: String,
+}
+
+impl BoundariesConfig {
+    /// Checks a dependency of `dependent` on `dependency` against the rules
+    /// for the tags of both, returning why it isn't allowed
+    fn check_dependency(
+        &self,
+        dependent: &str,
+        dependent_tags: &BTreeSet<String>,
+        dependency: &str,
+        dependency_tags: &BTreeSet<String>,
+    ) -> Option<String> {
+        let edge = format!("\"{dependent}\" depends on \"{dependency}\"");
+        for tag in dependent_tags {
+            let Some(permissions) = self
+                .tags
+                .get(tag)
+                .and_then(|rules| rules.dependencies.as_ref())
+            else {
+                continue;
+            };
+            match permissions.check(dependency_tags) {
+                Ok(()) => (),
+                Err(Rejection::Denied(denied)) => {
+                    return Some(format!(
+                        "{edge}, but packages tagged \"{tag}\" can't depend on packages tagged \
+                         \"{denied}\""
+                    ))
+                }
+                Err(Rejection::NotAllowed(allow)) if allow.is_empty() => {
+                    return Some(format!(
+                        "{edge}, but packages tagged \"{tag}\" can't depend on other packages"
+                    ))
+                }
+                Err(Rejection::NotAllowed(allow)) => {
+                    return Some(format!(
+                        "{edge}, but packages tagged \"{tag}\" can only depend on packages tagged \
+                         {}",
+                        quoted(allow)
+                    ))
+                }
+            }
+        }
+        for tag in dependency_tags {
+            let Some(permissions) = self
+                .tags
+                .get(tag)
+                .and_then(|rules| rules.dependents.as_ref())
+            else {
+                continue;
+            };
+            match permissions.check(dependent_tags) {
+                Ok(()) => (),
+                Err(Rejection::Denied(denied)) => {
+                    return Some(format!(
+                        "{edge}, but packages tagged \"{tag}\" can't be depended on by packages \
+                         tagged \"{denied}\""
+                    ))
+                }
+                Err(Rejection::NotAllowed(allow)) if allow.is_empty() => {
+                    return Some(format!(
+                        "{edge}, but packages tagged \"{tag}\" can't be depended on"
+                    ))
+                }
+                Err(Rejection::NotAllowed(allow)) => {
+                    return Some(format!(
+                        "{edge}, but packages tagged \"{tag}\" can only be depended on by \
+                         packages tagged {}",
+                        quoted(allow)
+                    ))
+                }
+            }
+        }
+        None
+    }
+}
+
+fn quoted(tags: &BTreeSet<String>) -> String {
+    tags.iter().map(|tag| format!("\"{tag}\"")).join(", ")
+}
+
+/// Checks the dependencies between the packages of `pkg_graph` against
+/// `config`, and the imports of every JS/TS package for workspaces it doesn't
+/// declare. `tags` holds the tags from each package's turbo.json.
+pub fn check(
+    repo_root: &AbsoluteSystemPath,
+    pkg_graph: &PackageGraph,
+    config: &BoundariesConfig,
+    tags: &HashMap<PackageName, BTreeSet<String>>,
+) -> Result<Vec<Violation>, Error> {
+    let no_tags = BTreeSet::new();
+    let tags_of = |package: &PackageName| tags.get(package).unwrap_or(&no_tags);
+    let workspace_dirs = pkg_graph
+        .packages()
+        .filter(|(name, _)| **name != PackageName::Root)
+        .map(|(name, info)| (name, repo_root.resolve(info.package_path())))
+        .collect::<Vec<_>>();
+
+    let mut violations = Vec::new();
+    for (name, info) in pkg_graph.packages() {
+        let manifest_path = repo_root.resolve(info.package_json_path());
+        let manifest = manifest_path
+            .read_to_string()
+            .map_err(|source| Error::Read {
+                path: manifest_path.clone(),
+                source,
+            })?;
+        let dependencies = pkg_graph
+            .immediate_dependencies(&PackageNode::Workspace(name.clone()))
+            .into_iter()
+            .flatten();
+        for dependency in dependencies {
+            let PackageNode::Workspace(dependency) = dependency else {
+                continue;
+            };
+            if let Some(message) = config.check_dependency(
+                name.as_ref(),
+                tags_of(name),
+                dependency.as_ref(),
+                tags_of(dependency),
+            ) {
+                violations.push(Violation {
+                    file: info.package_json_path().to_unix().to_string(),
+                    line: line_of(&manifest, dependency.as_ref()),
+                    message,
+                });
+            }
+        }
+
+        if *name != PackageName::Root && info.ecosystem.is_none() {
+            violations.extend(check_imports(
+                repo_root,
+                pkg_graph,
+                name,
+                info,
+                &workspace_dirs,
+            )?);
+        }
+    }
+
+    violations.sort();
+    Ok(violations)
+}
+
+/// Checks the imports in the sources of `package` for workspaces that it
+/// doesn't declare as dependencies, or whose files it reaches into with a
+/// relative path
+fn check_imports(
+    repo_root: &AbsoluteSystemPath,
+    pkg_graph: &PackageGraph,
+    package: &PackageName,
+    info: &PackageInfo,
+    workspace_dirs: &[(&PackageName, AbsoluteSystemPathBuf)],
+) -> Result<Vec<Violation>, Error> {
+    let package_dir = repo_root.resolve(info.package_path());
+    let package_json = &info.package_json;
+    let declared = package_json
+        .all_dependencies()
+        .chain(package_json.peer_dependencies.iter().flatten())
+        .map(|(name, _)| name.as_str())
+        .collect::<HashSet<_>>();
+    // Workspaces nested in this one are checked on their own
+    let nested = workspace_dirs
+        .iter()
+        .filter(|(_, dir)| *dir != package_dir && package_dir.contains(dir))
+        .map(|(_, dir)| dir.as_std_path().to_owned())
+        .collect::<HashSet<_>>();
+
+    let walker = WalkBuilder::new(&package_dir)
+        .filter_entry(move |entry| {
+            entry.file_name() != "node_modules" && !nested.contains(entry.path())
+        })
+        .build();
+
+    let mut violations = Vec::new();
+    for entry in walker {
+        let entry = entry.map_err(|source| Error::Walk {
+            package: package.clone(),
+            source,
+        })?;
+        let is_source = entry
+            .file_type()
+            .is_some_and(|file_type| file_type.is_file())
+            && entry
+                .path()
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| SOURCE_EXTENSIONS.contains(&extension));
+        if !is_source {
+            continue;
+        }
+
+        let path = AbsoluteSystemPath::from_std_path(entry.path())?;
+        let contents = path.read().map_err(|source| Error::Read {
+            path: path.to_owned(),
+            source,
+        })?;
+        let file = repo_root.anchor(path)?.to_unix().to_string();
+        for import in imports(&String::from_utf8_lossy(&contents)) {
+            let message = if import.specifier.starts_with('.') {
+                let Some(target) = relative_import_target(path, import.specifier, workspace_dirs)
+                else {
+                    continue;
+                };
+                if target == package {
+                    continue;
+                }
+                format!(
+                    "\"{package}\" imports \"{}\" from \"{target}\" by relative path, depend on \
+                     \"{target}\" and import it by name instead",
+                    import.specifier
+                )
+            } else {
+                let Some(name) = package_name(import.specifier) else {
+                    continue;
+                };
+                if name == package.as_ref()
+                    || declared.contains(name)
+                    || pkg_graph.package_info(&PackageName::from(name)).is_none()
+                {
+                    continue;
+                }
+                format!(
+                    "\"{package}\" imports \"{name}\" but doesn't declare it as a dependency in \
+                     package.json"
+                )
+            };
+            violations.push(Violation {
+                file: file.clone(),
+                line: import.line,
+                message,
+            });
+        }
+    }
+
+    Ok(violations)
+}
+
+/// The workspace whose directory a relative import from `file` resolves into
+fn relative_import_target<'a>(
+    file: &AbsoluteSystemPath,
+    specifier: &str,
+    workspace_dirs: &[(&'a PackageName, AbsoluteSystemPathBuf)],
+) -> Option<&'a PackageName> {
+    let specifier = RelativeUnixPath::new(specifier).ok()?;
+    let target = file.parent()?.join_unix_path(specifier).ok()?;
+    workspace_dirs
+        .iter()
+        .filter(|(_, dir)| dir.contains(&target))
+        .max_by_key(|(_, dir)| dir.as_str().len())
+        .map(|(name, _)| *name)
+}
+
+/// The 1-based line of the manifest that mentions `dependency`, preferring
+/// a quoted mention as found in a package.json
+fn line_of(manifest: &str, dependency: &str) -> usize {
+    let quoted = format!("\"{dependency}\"");
+    manifest
+        .lines()
+        .position(|line| line.contains(&quoted))
+        .or_else(|| manifest.lines().position(|line| line.contains(dependency)))
+        .map_or(1, |index| index + 1)
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::*;
+
+    fn tags(tags: &[&str]) -> BTreeSet<String> {
+        tags.iter().map(|tag| tag.to_string()).collect()
+    }
+
+    fn config() -> BoundariesConfig {
+        BoundariesConfig {
+            tags: BTreeMap::from([
+                (
+                    "app".to_string(),
+                    TagRules {
+                        dependents: Some(Permissions {
+                            allow: Some(BTreeSet::new()),
+                            ..Permissions::default()
+                        }),
+                        ..TagRules::default()
+                    },
+                ),
+                (
+                    "ui".to_string(),
+                    TagRules {
+                        dependencies: Some(Permissions {
+                            allow: Some(tags(&["utils"])),
+                            ..Permissions::default()
+                        }),
+                        ..TagRules::default()
+                    },
+                ),
+                (
+                    "internal".to_string(),
+                    TagRules {
+                        dependents: Some(Permissions {
+                            deny: tags(&["public"]),
+                            ..Permissions::default()
+                        }),
+                        ..TagRules::default()
+                    },
+                ),
+            ]),
+        }
+    }
+
+    #[test_case(&["ui"], &["utils"], None ; "allowed dependency")]
+    #[test_case(
+        &["ui"],
+        &["config"],
+        Some("\"a\" depends on \"b\", but packages tagged \"ui\" can only depend on packages tagged \"utils\"")
+        ; "dependency not allowed"
+    )]
+    #[test_case(
+        &["ui"],
+        &[],
+        Some("\"a\" depends on \"b\", but packages tagged \"ui\" can only depend on packages tagged \"utils\"")
+        ; "untagged dependency not allowed"
+    )]
+    #[test_case(
+        &[],
+        &["app"],
+        Some("\"a\" depends on \"b\", but packages tagged \"app\" can't be depended on")
+        ; "no dependents allowed"
+    )]
+    #[test_case(
+        &["public"],
+        &["internal"],
+        Some("\"a\" depends on \"b\", but packages tagged \"internal\" can't be depended on by packages tagged \"public\"")
+        ; "denied dependent"
+    )]
+    #[test_case(&["app"], &["utils"], None ; "untagged rules")]
+    fn test_check_dependency(
+        dependent_tags: &[&str],
+        dependency_tags: &[&str],
+        expected: Option<&str>,
+    ) {
+        assert_eq!(
+            config()
+                .check_dependency("a", &tags(dependent_tags), "b", &tags(dependency_tags))
+                .as_deref(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_relative_import_target() {
+        let root =
+            AbsoluteSystemPathBuf::new(if cfg!(windows) { "C:\\repo" } else { "/repo" }).unwrap();
+        let web = PackageName::from("web");
+        let ui = PackageName::from("ui");
+        let workspace_dirs = [
+            (&web, root.join_components(&["apps", "web"])),
+            (&ui, root.join_components(&["packages", "ui"])),
+        ];
+        let file = root.join_components(&["apps", "web", "src", "index.ts"]);
+
+        assert_eq!(
+            relative_import_target(&file, "./button", &workspace_dirs),
+            Some(&web)
+        );
+        assert_eq!(
+            relative_import_target(&file, "../../../packages/ui/src/button", &workspace_dirs),
+            Some(&ui)
+        );
+        assert_eq!(
+            relative_import_target(&file, "../../../scripts/setup", &workspace_dirs),
+            None
+        );
+    }
+
+    #[test]
+    fn test_line_of() {
+        let manifest = r#"{
+  "name": "web",
+  "dependencies": {
+    "ui": "workspace:*",
+    "@repo/ui-kit": "workspace:*"
+  }
+}"#;
+        assert_eq!(line_of(manifest, "ui"), 4);
+        assert_eq!(line_of(manifest, "@repo/ui-kit"), 5);
+        assert_eq!(line_of(manifest, "missing"), 1);
+    }
+}
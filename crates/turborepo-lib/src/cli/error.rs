@@ -5,7 +5,7 @@ use thiserror::Error;
 use turborepo_repository::package_graph;
 
 use crate::{
-    commands::{bin, generate, prune},
+    commands::{bin, deps, generate, migrate_lockfile, prune},
     daemon::DaemonError,
     rewrite_json::RewriteError,
     run,
@@ -25,6 +25,10 @@ pub enum Error {
     #[diagnostic(transparent)]
     Config(#[from] crate::config::Error),
     #[error(transparent)]
+    Boundaries(#[from] crate::boundaries::Error),
+    #[error(transparent)]
+    Cache(#[from] turborepo_cache::CacheError),
+    #[error(transparent)]
     ChromeTracing(#[from] crate::tracing::Error),
     #[error(transparent)]
     BuildPackageGraph(#[from] package_graph::builder::Error),
@@ -35,8 +39,12 @@ pub enum Error {
     #[error(transparent)]
     Daemon(#[from] DaemonError),
     #[error(transparent)]
+    Deps(#[from] deps::Error),
+    #[error(transparent)]
     Generate(#[from] generate::Error),
     #[error(transparent)]
+    MigrateLockfile(#[from] migrate_lockfile::Error),
+    #[error(transparent)]
     Prune(#[from] prune::Error),
     #[error(transparent)]
     PackageJson(#[from] turborepo_repository::package_json::Error),
@@ -46,5 +54,18 @@ pub enum Error {
     #[diagnostic(transparent)]
     Run(#[from] run::Error),
     #[error(transparent)]
+    #[diagnostic(transparent)]
+    Query(#[from] crate::query::Error),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Sandbox(#[from] run::sandbox::Error),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Summary(#[from] run::summary::diff::Error),
+    #[error(transparent)]
+    RunSummary(#[from] run::summary::Error),
+    #[error(transparent)]
     SerdeJson(#[from] serde_json::Error),
+    #[error(transparent)]
+    Watch(#[from] run::watch::Error),
 }
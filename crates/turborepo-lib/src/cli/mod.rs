@@ -24,8 +24,8 @@ use turborepo_ui::UI;
 
 use crate::{
     commands::{
-        bin, daemon, generate, info, link, login, logout, prune, run, telemetry, unlink,
-        CommandBase,
+        bin, boundaries, cache, daemon, deps, generate, info, link, login, logout,
+        migrate_lockfile, prune, query, run, stats, summary, telemetry, unlink, watch, CommandBase,
     },
     get_version,
     shim::TurboState,
@@ -101,6 +101,104 @@ impl Display for LogOrder {
     }
 }
 
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, ValueEnum)]
+pub enum UIMode {
+    /// Print task output to the terminal as it is produced
+    #[default]
+    #[serde(rename = "stream")]
+    Stream,
+    /// Use the full screen terminal UI
+    #[serde(rename = "tui")]
+    Tui,
+}
+
+impl Display for UIMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            UIMode::Stream => "stream",
+            UIMode::Tui => "tui",
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, ValueEnum)]
+pub enum SandboxMode {
+    /// Run tasks without observing their file accesses
+    #[default]
+    #[serde(rename = "off")]
+    Off,
+    /// Warn about undeclared file accesses and don't cache the task
+    #[serde(rename = "warn")]
+    Warn,
+    /// Fail tasks that access files they don't declare
+    #[serde(rename = "strict")]
+    Strict,
+}
+
+impl Display for SandboxMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SandboxMode::Off => "off",
+            SandboxMode::Warn => "warn",
+            SandboxMode::Strict => "strict",
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+pub enum ReportFormat {
+    /// JUnit XML with a test case per task
+    #[serde(rename = "junit")]
+    Junit,
+    /// The run summary, as written by --summarize
+    #[serde(rename = "json")]
+    Json,
+    /// A GitLab Code Quality report with an issue per failed task
+    #[serde(rename = "codequality")]
+    CodeQuality,
+}
+
+impl Display for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ReportFormat::Junit => "junit",
+            ReportFormat::Json => "json",
+            ReportFormat::CodeQuality => "codequality",
+        })
+    }
+}
+
+/// A report to write once the run finishes, given as `<format>:<path>`
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Report {
+    pub format: ReportFormat,
+    pub path: Utf8PathBuf,
+}
+
+fn parse_report(s: &str) -> Result<Report, String> {
+    let (format, path) = s
+        .split_once(':')
+        .ok_or_else(|| format!("Expected <format>:<path>, e.g. junit:report.xml, got '{s}'"))?;
+    let format = match format {
+        "junit" => ReportFormat::Junit,
+        "json" => ReportFormat::Json,
+        "codequality" => ReportFormat::CodeQuality,
+        _ => {
+            return Err(format!(
+                "Unknown report format '{format}'. Supported formats are junit, json and \
+                 codequality"
+            ))
+        }
+    };
+    if path.is_empty() {
+        return Err(format!("Missing a path to write the {format} report to"));
+    }
+    Ok(Report {
+        format,
+        path: Utf8PathBuf::from(path),
+    })
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Serialize, ValueEnum)]
 pub enum DryRunMode {
     Text,
@@ -156,6 +254,14 @@ pub struct Args {
     /// Override the endpoint for API calls
     #[clap(long, global = true, value_parser)]
     pub api: Option<String>,
+    /// Evict least recently used entries from the local cache once it grows
+    /// past this size (e.g. "10GB")
+    #[clap(long, value_name = "SIZE", global = true, value_parser)]
+    pub cache_max_size: Option<String>,
+    /// Evict entries from the local cache that haven't been used for this
+    /// long (e.g. "7d")
+    #[clap(long, value_name = "DURATION", global = true, value_parser)]
+    pub cache_max_age: Option<String>,
     /// Force color usage in the terminal
     #[clap(long, global = true)]
     pub color: bool,
@@ -258,6 +364,52 @@ pub enum DaemonCommand {
     Logs,
 }
 
+#[derive(Subcommand, Copy, Clone, Debug, Serialize, PartialEq)]
+#[serde(tag = "command")]
+pub enum CacheCommand {
+    /// Evicts entries that are over the configured max size or max age
+    Prune,
+    /// Removes every entry from the local cache
+    Clean,
+    /// Reports the number of entries in the local cache and their total size
+    Stats {
+        /// Pass --json to report stats in JSON format
+        #[clap(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand, Clone, Debug, Serialize, PartialEq)]
+#[serde(tag = "command")]
+pub enum SummaryCommand {
+    /// Shows which hash inputs changed for the tasks whose hash differs
+    /// between two runs
+    Diff {
+        /// The earlier run, as the path to its summary or the id of a run
+        /// saved in .turbo/runs
+        from: String,
+        /// The later run, as the path to its summary or the id of a run
+        /// saved in .turbo/runs
+        to: String,
+    },
+}
+
+#[derive(Subcommand, Clone, Debug, Serialize, PartialEq)]
+#[serde(tag = "command")]
+pub enum DepsCommand {
+    /// Shows the external packages each workspace gained, lost or changed the
+    /// version of between two refs
+    Diff {
+        /// The ref to compare from
+        from: String,
+        /// The ref to compare to (default: the working tree)
+        to: Option<String>,
+        /// Output the changes as JSON
+        #[clap(long)]
+        json: bool,
+    },
+}
+
 #[derive(Subcommand, Copy, Clone, Debug, Serialize, PartialEq)]
 #[serde(tag = "command")]
 pub enum TelemetryCommand {
@@ -385,6 +537,8 @@ impl Args {
         track_usage!(tel, &self.token, Option::is_some);
         track_usage!(tel, &self.trace, Option::is_some);
         track_usage!(tel, &self.api, Option::is_some);
+        track_usage!(tel, &self.cache_max_size, Option::is_some);
+        track_usage!(tel, &self.cache_max_age, Option::is_some);
 
         // track values
         if let Some(remote_cache_timeout) = self.remote_cache_timeout {
@@ -420,6 +574,18 @@ pub enum Command {
     // them as `{ "Bin": {} }` instead of as `"Bin"`.
     /// Get the path to the Turbo binary
     Bin {},
+    /// Check the dependencies and imports between packages against the
+    /// `boundaries` rules and `tags` in turbo.json
+    Boundaries {},
+    /// Manage the local filesystem cache
+    Cache {
+        /// Override the filesystem cache directory
+        #[clap(long, value_parser)]
+        cache_dir: Option<Utf8PathBuf>,
+        #[clap(subcommand)]
+        #[serde(flatten)]
+        command: CacheCommand,
+    },
     /// Generate the autocompletion script for the specified shell
     #[serde(skip)]
     Completion { shell: Shell },
@@ -432,6 +598,12 @@ pub enum Command {
         #[serde(flatten)]
         command: Option<DaemonCommand>,
     },
+    /// Inspect the external dependencies of your workspaces
+    Deps {
+        #[clap(subcommand)]
+        #[serde(flatten)]
+        command: DepsCommand,
+    },
     /// Generate a new app / package
     #[clap(aliases = ["g", "gen"])]
     Generate {
@@ -486,6 +658,14 @@ pub enum Command {
     },
     /// Logout to your Vercel account
     Logout {},
+    /// Convert the lockfile to another package manager's format, keeping the
+    /// resolved versions of every package
+    MigrateLockfile {
+        /// The package manager to migrate to, optionally with a version, e.g.
+        /// `pnpm` or `pnpm@9.1.0`
+        #[clap(long)]
+        to: String,
+    },
     /// Prepare a subset of your monorepo.
     Prune {
         #[clap(hide = true, long)]
@@ -499,10 +679,35 @@ pub enum Command {
         scope_arg: Option<Vec<String>>,
         #[clap(long)]
         docker: bool,
+        /// Leave out devDependencies, only including the workspaces and
+        /// packages needed to run the targets in production
+        #[clap(long)]
+        production: bool,
         #[clap(long = "out-dir", default_value_t = String::from(prune::DEFAULT_OUTPUT_DIR), value_parser)]
         output_dir: String,
     },
+    /// Query the package and task graphs of your monorepo as JSON
+    ///
+    /// Available queries: packages, package(<name>), dependencies(<name>),
+    /// dependents(<name>), changed(<file>...), affected(<file>...) and
+    /// tasks(<task>..., <package query>...). For example
+    /// `turbo query "tasks(build, affected(packages/ui/src/index.ts))"`.
+    Query {
+        /// The query to evaluate
+        query: String,
+    },
 
+    /// Run a command, recording the files it and its subprocesses access.
+    /// Tasks are wrapped in this when running with --sandbox.
+    #[clap(name = "__sandbox", hide = true)]
+    #[serde(skip)]
+    Sandbox {
+        /// File to write the accessed files to
+        #[clap(long)]
+        report: Utf8PathBuf,
+        #[clap(last = true, required = true)]
+        command: Vec<String>,
+    },
     /// Run tasks across projects in your monorepo
     ///
     /// By default, turbo executes tasks in topological order (i.e.
@@ -513,6 +718,22 @@ pub enum Command {
     ///
     /// Arguments passed after '--' will be passed through to the named tasks.
     Run(Box<RunArgs>),
+    /// Report task duration trends, cache hit rates, the slowest tasks, the
+    /// most frequent cache miss causes and the critical path over recent runs
+    Stats {
+        /// How many of the most recent runs to report on
+        #[clap(long, default_value_t = 20, value_parser = clap::value_parser!(u32).range(1..))]
+        runs: u32,
+        /// Output the report as JSON
+        #[clap(long)]
+        json: bool,
+    },
+    /// Compare the run summaries saved with --summarize
+    Summary {
+        #[clap(subcommand)]
+        #[serde(flatten)]
+        command: SummaryCommand,
+    },
     /// Unlink the current directory from your Vercel organization and disable
     /// Remote Caching
     Unlink {
@@ -520,6 +741,12 @@ pub enum Command {
         #[clap(long, value_enum, default_value_t = LinkTarget::RemoteCache)]
         target: LinkTarget,
     },
+    /// Run tasks and re-run them whenever files in the packages they depend
+    /// on change
+    ///
+    /// Only the packages affected by a change, along with their dependents,
+    /// are re-run. Persistent tasks in those packages are restarted.
+    Watch(Box<RunArgs>),
 }
 
 #[derive(Parser, Clone, Debug, Default, Serialize, PartialEq)]
@@ -685,6 +912,12 @@ pub struct RunArgs {
     #[clap(long, requires = "scope")]
     pub no_deps: bool,
 
+    /// Only run tasks whose inputs changed since the given base ref, along
+    /// with the tasks that depend on them. Defaults to the base branch
+    /// reported by CI, or the merge base with the main branch.
+    #[clap(long, num_args = 0..=1, default_missing_value = "", value_name = "BASE", conflicts_with = "scope")]
+    pub affected: Option<String>,
+
     /// Avoid saving task results to the cache. Useful for development/watch
     /// tasks.
     #[clap(long)]
@@ -717,6 +950,18 @@ pub struct RunArgs {
     /// turbo decide based on its own heuristics. (default auto)
     #[clap(long, env = "TURBO_LOG_ORDER", value_enum, default_value_t = LogOrder::Auto)]
     pub log_order: LogOrder,
+    /// Use "tui" to show a full screen terminal UI with a pane for each
+    /// task's output. Tasks that accept input can be focused to type into
+    /// them. Falls back to "stream" when not attached to a terminal.
+    /// (default stream)
+    #[clap(long, env = "TURBO_UI", value_enum, default_value_t = UIMode::Stream)]
+    pub ui: UIMode,
+    /// Use "warn" or "strict" to observe the files each task reads and
+    /// writes, reporting accesses outside of its declared inputs and outputs.
+    /// "warn" prints the undeclared accesses and skips caching the task,
+    /// "strict" fails the task. Only supported on x86_64 Linux. (default off)
+    #[clap(long, env = "TURBO_SANDBOX", value_enum, default_value_t = SandboxMode::Off)]
+    pub sandbox: SandboxMode,
     /// Only executes the tasks specified, does not execute parent tasks.
     #[clap(long)]
     pub only: bool,
@@ -746,6 +991,17 @@ pub struct RunArgs {
     /// Generate a summary of the turbo run
     #[clap(long, env = "TURBO_RUN_SUMMARY", default_missing_value = "true")]
     pub summarize: Option<Option<bool>>,
+    /// Explain why tasks missed the cache by comparing their hash inputs
+    /// with the most recent run summary in .turbo/runs that includes them.
+    /// Implies --summarize, so later runs can compare against this one.
+    #[clap(long)]
+    pub explain_miss: bool,
+    /// Write a report of the task results once the run finishes, as
+    /// <format>:<path>. Use "junit" for JUnit XML with a test case per task,
+    /// "json" for the run summary, or "codequality" for a GitLab Code Quality
+    /// report of the failed tasks. Can be passed multiple times.
+    #[clap(long, value_name = "FORMAT:PATH", value_parser = parse_report, action = ArgAction::Append)]
+    pub report: Vec<Report>,
 
     /// Use "none" to remove prefixes from task logs. Use "task" to get task id
     /// prefixing. Use "auto" to let turbo decide how to prefix the logs
@@ -807,12 +1063,14 @@ impl RunArgs {
         track_usage!(telemetry, self.parallel, |val| val);
         track_usage!(telemetry, self.remote_only, |val| val);
         track_usage!(telemetry, self.remote_cache_read_only, |val| val);
+        track_usage!(telemetry, self.explain_miss, |val| val);
 
         // default to None
         track_usage!(telemetry, &self.cache_dir, Option::is_some);
         track_usage!(telemetry, &self.profile, Option::is_some);
         track_usage!(telemetry, &self.force, Option::is_some);
         track_usage!(telemetry, &self.since, Option::is_some);
+        track_usage!(telemetry, &self.affected, Option::is_some);
         track_usage!(telemetry, &self.pkg_inference_root, Option::is_some);
         track_usage!(telemetry, &self.anon_profile, Option::is_some);
         track_usage!(telemetry, &self.summarize, Option::is_some);
@@ -857,6 +1115,19 @@ impl RunArgs {
             telemetry.track_arg_value("log-prefix", self.log_prefix, EventType::NonSensitive);
         }
 
+        if self.ui != UIMode::default() {
+            telemetry.track_arg_value("ui", self.ui, EventType::NonSensitive);
+        }
+
+        if self.sandbox != SandboxMode::default() {
+            telemetry.track_arg_value("sandbox", self.sandbox, EventType::NonSensitive);
+        }
+
+        // track the formats used only
+        for report in &self.report {
+            telemetry.track_arg_value("report", report.format, EventType::NonSensitive);
+        }
+
         // track sizes
         if !self.filter.is_empty() {
             telemetry.track_arg_value("filter:length", self.filter.len(), EventType::NonSensitive);
@@ -923,6 +1194,11 @@ pub async fn run(
     ui: UI,
 ) -> Result<i32, Error> {
     let mut cli_args = Args::new();
+    // Every task of a sandboxed run goes through here, so skip telemetry and
+    // repository inference entirely
+    if let Some(Command::Sandbox { report, command }) = &cli_args.command {
+        return Ok(crate::run::sandbox::trace(report, command)?);
+    }
     let version = get_version();
 
     // track telemetry handle to close at the end of the run
@@ -1030,6 +1306,14 @@ pub async fn run(
 
             Ok(0)
         }
+        Command::Boundaries { .. } => {
+            CommandEventBuilder::new("boundaries")
+                .with_parent(&root_telemetry)
+                .track_call();
+            let base = CommandBase::new(cli_args, repo_root, version, ui);
+            let exit_code = boundaries::run(&base).await?;
+            Ok(exit_code)
+        }
         #[allow(unused_variables)]
         Command::Daemon { command, idle_time } => {
             CommandEventBuilder::new("daemon")
@@ -1065,6 +1349,25 @@ pub async fn run(
             generate::run(tag, command, &args, child_event)?;
             Ok(0)
         }
+        Command::Cache { cache_dir, command } => {
+            CommandEventBuilder::new("cache")
+                .with_parent(&root_telemetry)
+                .track_call();
+            let cache_dir = cache_dir.clone();
+            let command = *command;
+            let base = CommandBase::new(cli_args, repo_root, version, ui);
+            cache::run(&base, cache_dir.as_deref(), command)?;
+            Ok(0)
+        }
+        Command::Deps { command } => {
+            CommandEventBuilder::new("deps")
+                .with_parent(&root_telemetry)
+                .track_call();
+            let command = command.clone();
+            let base = CommandBase::new(cli_args, repo_root, version, ui);
+            deps::run(&base, &command).await?;
+            Ok(0)
+        }
         Command::Telemetry { command } => {
             let event = CommandEventBuilder::new("telemetry").with_parent(&root_telemetry);
             event.track_call();
@@ -1115,6 +1418,15 @@ pub async fn run(
 
             Ok(0)
         }
+        Command::MigrateLockfile { to } => {
+            CommandEventBuilder::new("migrate-lockfile")
+                .with_parent(&root_telemetry)
+                .track_call();
+            let to = to.clone();
+            let base = CommandBase::new(cli_args, repo_root, version, ui);
+            migrate_lockfile::run(&base, &to).await?;
+            Ok(0)
+        }
         Command::Login { sso_team } => {
             let event = CommandEventBuilder::new("login").with_parent(&root_telemetry);
             event.track_call();
@@ -1176,10 +1488,23 @@ pub async fn run(
             })?;
             Ok(exit_code)
         }
+        Command::Watch(args) => {
+            let event = CommandEventBuilder::new("watch").with_parent(&root_telemetry);
+            event.track_call();
+            if args.tasks.is_empty() {
+                return Err(Error::NoTasks(backtrace::Backtrace::capture()));
+            }
+            let base = CommandBase::new(cli_args.clone(), repo_root, version, ui);
+
+            args.track(&event);
+            let exit_code = watch::run(base, event).await?;
+            Ok(exit_code)
+        }
         Command::Prune {
             scope,
             scope_arg,
             docker,
+            production,
             output_dir,
         } => {
             let event = CommandEventBuilder::new("prune").with_parent(&root_telemetry);
@@ -1190,12 +1515,41 @@ pub async fn run(
                 .cloned()
                 .unwrap_or_default();
             let docker = *docker;
+            let production = *production;
             let output_dir = output_dir.clone();
             let base = CommandBase::new(cli_args, repo_root, version, ui);
             let event_child = event.child();
-            prune::prune(&base, &scope, docker, &output_dir, event_child).await?;
+            prune::prune(&base, &scope, docker, production, &output_dir, event_child).await?;
+            Ok(0)
+        }
+        Command::Query { query } => {
+            CommandEventBuilder::new("query")
+                .with_parent(&root_telemetry)
+                .track_call();
+            let query = query.clone();
+            let base = CommandBase::new(cli_args, repo_root, version, ui);
+            query::run(&base, &query).await?;
+            Ok(0)
+        }
+        Command::Stats { runs, json } => {
+            CommandEventBuilder::new("stats")
+                .with_parent(&root_telemetry)
+                .track_call();
+            let (runs, json) = (*runs, *json);
+            let base = CommandBase::new(cli_args, repo_root, version, ui);
+            stats::run(&base, runs as usize, json)?;
+            Ok(0)
+        }
+        Command::Summary { command } => {
+            CommandEventBuilder::new("summary")
+                .with_parent(&root_telemetry)
+                .track_call();
+            let command = command.clone();
+            let base = CommandBase::new(cli_args, repo_root, version, ui);
+            summary::run(&base, &command)?;
             Ok(0)
         }
+        Command::Sandbox { .. } => unreachable!("handled before telemetry is initialized"),
         Command::Completion { shell } => {
             CommandEventBuilder::new("completion")
                 .with_parent(&root_telemetry)
@@ -1280,7 +1634,9 @@ mod test {
     use anyhow::Result;
 
     use crate::cli::{
-        Args, Command, DryRunMode, EnvMode, LogOrder, LogPrefix, OutputLogsMode, RunArgs, Verbosity,
+        Args, CacheCommand, Command, DepsCommand, DryRunMode, EnvMode, LogOrder, LogPrefix,
+        OutputLogsMode, Report, ReportFormat, RunArgs, SandboxMode, SummaryCommand, UIMode,
+        Verbosity,
     };
 
     #[test_case::test_case(
@@ -1722,6 +2078,63 @@ mod test {
             ..Args::default()
         }
 	)]
+    #[test_case::test_case(
+		&["turbo", "run", "build", "--ui", "tui"],
+        Args {
+            command: Some(Command::Run(Box::new(RunArgs {
+                tasks: vec!["build".to_string()],
+                ui: UIMode::Tui,
+                ..get_default_run_args()
+            }))),
+            ..Args::default()
+        }
+	)]
+    #[test_case::test_case(
+		&["turbo", "run", "build", "--sandbox", "strict"],
+        Args {
+            command: Some(Command::Run(Box::new(RunArgs {
+                tasks: vec!["build".to_string()],
+                sandbox: SandboxMode::Strict,
+                ..get_default_run_args()
+            }))),
+            ..Args::default()
+        }
+	)]
+    #[test_case::test_case(
+		&["turbo", "run", "build", "--explain-miss"],
+        Args {
+            command: Some(Command::Run(Box::new(RunArgs {
+                tasks: vec!["build".to_string()],
+                explain_miss: true,
+                ..get_default_run_args()
+            }))),
+            ..Args::default()
+        }
+	)]
+    #[test_case::test_case(
+		&["turbo", "run", "build", "--report", "junit:reports/turbo.xml", "--report=json:summary.json", "--report=codequality:gl-code-quality.json"],
+        Args {
+            command: Some(Command::Run(Box::new(RunArgs {
+                tasks: vec!["build".to_string()],
+                report: vec![
+                    Report {
+                        format: ReportFormat::Junit,
+                        path: Utf8PathBuf::from("reports/turbo.xml"),
+                    },
+                    Report {
+                        format: ReportFormat::Json,
+                        path: Utf8PathBuf::from("summary.json"),
+                    },
+                    Report {
+                        format: ReportFormat::CodeQuality,
+                        path: Utf8PathBuf::from("gl-code-quality.json"),
+                    },
+                ],
+                ..get_default_run_args()
+            }))),
+            ..Args::default()
+        }
+	)]
     #[test_case::test_case(
 		&["turbo", "run", "build", "--log-prefix", "auto"],
         Args {
@@ -1861,6 +2274,30 @@ mod test {
         } ;
         "scope and since"
 	)]
+    #[test_case::test_case(
+		&["turbo", "run", "build", "--affected"],
+        Args {
+            command: Some(Command::Run(Box::new(RunArgs {
+                tasks: vec!["build".to_string()],
+                affected: Some("".to_string()),
+                ..get_default_run_args()
+            }))),
+            ..Args::default()
+        } ;
+        "affected without base"
+	)]
+    #[test_case::test_case(
+		&["turbo", "run", "build", "--affected=origin/release"],
+        Args {
+            command: Some(Command::Run(Box::new(RunArgs {
+                tasks: vec!["build".to_string()],
+                affected: Some("origin/release".to_string()),
+                ..get_default_run_args()
+            }))),
+            ..Args::default()
+        } ;
+        "affected with base"
+	)]
     #[test_case::test_case(
 		&["turbo", "build"],
         Args {
@@ -1909,6 +2346,11 @@ mod test {
         "the following required arguments were not provided" ;
         "since without filter or scope"
     )]
+    #[test_case::test_case(
+        &["turbo", "run", "build", "--scope", "foo", "--affected"],
+        "the argument '--scope <SCOPE>' cannot be used with '--affected [<BASE>]'" ;
+        "affected with scope"
+    )]
     #[test_case::test_case(
         &["turbo", "run", "build", "--include-dependencies"],
         "the following required arguments were not provided" ;
@@ -1919,6 +2361,16 @@ mod test {
         "the following required arguments were not provided" ;
         "no-deps without filter or scope"
     )]
+    #[test_case::test_case(
+        &["turbo", "run", "build", "--report", "tap:out.tap"],
+        "Unknown report format 'tap'" ;
+        "unknown report format"
+    )]
+    #[test_case::test_case(
+        &["turbo", "run", "build", "--report", "junit:"],
+        "Missing a path to write the junit report to" ;
+        "report without path"
+    )]
     fn test_parse_run_failures(args: &[&str], expected: &str) {
         assert_matches!(
             Args::try_parse_from(args),
@@ -1949,6 +2401,17 @@ mod test {
         .test();
     }
 
+    #[test]
+    fn test_parse_boundaries() {
+        assert_eq!(
+            Args::try_parse_from(["turbo", "boundaries"]).unwrap(),
+            Args {
+                command: Some(Command::Boundaries {}),
+                ..Args::default()
+            }
+        );
+    }
+
     #[test]
     fn test_parse_login() {
         assert_eq!(
@@ -1986,6 +2449,21 @@ mod test {
         .test();
     }
 
+    #[test]
+    fn test_parse_migrate_lockfile() {
+        assert_eq!(
+            Args::try_parse_from(["turbo", "migrate-lockfile", "--to=pnpm@9.1.0"]).unwrap(),
+            Args {
+                command: Some(Command::MigrateLockfile {
+                    to: "pnpm@9.1.0".to_string(),
+                }),
+                ..Args::default()
+            }
+        );
+
+        assert!(Args::try_parse_from(["turbo", "migrate-lockfile"]).is_err());
+    }
+
     #[test]
     fn test_parse_logout() {
         assert_eq!(
@@ -2036,12 +2514,176 @@ mod test {
         .test();
     }
 
+    #[test]
+    fn test_parse_watch() {
+        assert_eq!(
+            Args::try_parse_from(["turbo", "watch", "build", "dev"]).unwrap(),
+            Args {
+                command: Some(Command::Watch(Box::new(RunArgs {
+                    tasks: vec!["build".to_string(), "dev".to_string()],
+                    ..get_default_run_args()
+                }))),
+                ..Args::default()
+            }
+        );
+
+        CommandTestCase {
+            command: "watch",
+            command_args: vec![vec!["build"], vec!["--filter", "web"]],
+            global_args: vec![vec!["--cwd", "../examples/with-yarn"]],
+            expected_output: Args {
+                command: Some(Command::Watch(Box::new(RunArgs {
+                    tasks: vec!["build".to_string()],
+                    filter: vec!["web".to_string()],
+                    ..get_default_run_args()
+                }))),
+                cwd: Some(Utf8PathBuf::from("../examples/with-yarn")),
+                ..Args::default()
+            },
+        }
+        .test();
+    }
+
+    #[test]
+    fn test_parse_cache() {
+        assert_eq!(
+            Args::try_parse_from(["turbo", "cache", "stats", "--json"]).unwrap(),
+            Args {
+                command: Some(Command::Cache {
+                    cache_dir: None,
+                    command: CacheCommand::Stats { json: true },
+                }),
+                ..Args::default()
+            }
+        );
+
+        assert_eq!(
+            Args::try_parse_from([
+                "turbo",
+                "cache",
+                "--cache-dir",
+                "my-cache",
+                "prune",
+                "--cache-max-size",
+                "10GB",
+                "--cache-max-age",
+                "7d"
+            ])
+            .unwrap(),
+            Args {
+                command: Some(Command::Cache {
+                    cache_dir: Some(Utf8PathBuf::from("my-cache")),
+                    command: CacheCommand::Prune,
+                }),
+                cache_max_size: Some("10GB".to_string()),
+                cache_max_age: Some("7d".to_string()),
+                ..Args::default()
+            }
+        );
+
+        assert!(Args::try_parse_from(["turbo", "cache"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_deps() {
+        assert_eq!(
+            Args::try_parse_from(["turbo", "deps", "diff", "main"]).unwrap(),
+            Args {
+                command: Some(Command::Deps {
+                    command: DepsCommand::Diff {
+                        from: "main".to_string(),
+                        to: None,
+                        json: false,
+                    },
+                }),
+                ..Args::default()
+            }
+        );
+
+        assert_eq!(
+            Args::try_parse_from(["turbo", "deps", "diff", "main", "HEAD", "--json"]).unwrap(),
+            Args {
+                command: Some(Command::Deps {
+                    command: DepsCommand::Diff {
+                        from: "main".to_string(),
+                        to: Some("HEAD".to_string()),
+                        json: true,
+                    },
+                }),
+                ..Args::default()
+            }
+        );
+
+        assert!(Args::try_parse_from(["turbo", "deps", "diff"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_query() {
+        assert_eq!(
+            Args::try_parse_from(["turbo", "query", "dependents(ui)"]).unwrap(),
+            Args {
+                command: Some(Command::Query {
+                    query: "dependents(ui)".to_string(),
+                }),
+                ..Args::default()
+            }
+        );
+
+        assert!(Args::try_parse_from(["turbo", "query"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_stats() {
+        assert_eq!(
+            Args::try_parse_from(["turbo", "stats"]).unwrap(),
+            Args {
+                command: Some(Command::Stats {
+                    runs: 20,
+                    json: false,
+                }),
+                ..Args::default()
+            }
+        );
+
+        assert_eq!(
+            Args::try_parse_from(["turbo", "stats", "--runs", "5", "--json"]).unwrap(),
+            Args {
+                command: Some(Command::Stats {
+                    runs: 5,
+                    json: true,
+                }),
+                ..Args::default()
+            }
+        );
+
+        assert!(Args::try_parse_from(["turbo", "stats", "--runs", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_summary() {
+        assert_eq!(
+            Args::try_parse_from(["turbo", "summary", "diff", "run-a", "run-b"]).unwrap(),
+            Args {
+                command: Some(Command::Summary {
+                    command: SummaryCommand::Diff {
+                        from: "run-a".to_string(),
+                        to: "run-b".to_string(),
+                    },
+                }),
+                ..Args::default()
+            }
+        );
+
+        assert!(Args::try_parse_from(["turbo", "summary", "diff", "run-a"]).is_err());
+    }
+
     #[test]
     fn test_parse_prune() {
         let default_prune = Command::Prune {
             scope: None,
             scope_arg: Some(vec!["foo".into()]),
             docker: false,
+            production: false,
             output_dir: "out".to_string(),
         };
 
@@ -2072,6 +2714,7 @@ mod test {
                     scope: Some(vec!["bar".to_string()]),
                     scope_arg: None,
                     docker: false,
+                    production: false,
                     output_dir: "out".to_string(),
                 }),
                 ..Args::default()
@@ -2085,6 +2728,7 @@ mod test {
                     scope: None,
                     scope_arg: Some(vec!["foo".to_string(), "bar".to_string()]),
                     docker: false,
+                    production: false,
                     output_dir: "out".to_string(),
                 }),
                 ..Args::default()
@@ -2098,6 +2742,7 @@ mod test {
                     scope: None,
                     scope_arg: Some(vec!["foo".into()]),
                     docker: true,
+                    production: false,
                     output_dir: "out".to_string(),
                 }),
                 ..Args::default()
@@ -2111,12 +2756,27 @@ mod test {
                     scope: None,
                     scope_arg: Some(vec!["foo".into()]),
                     docker: false,
+                    production: false,
                     output_dir: "dist".to_string(),
                 }),
                 ..Args::default()
             }
         );
 
+        assert_eq!(
+            Args::try_parse_from(["turbo", "prune", "--docker", "--production", "foo"]).unwrap(),
+            Args {
+                command: Some(Command::Prune {
+                    scope: None,
+                    scope_arg: Some(vec!["foo".into()]),
+                    docker: true,
+                    production: true,
+                    output_dir: "out".to_string(),
+                }),
+                ..Args::default()
+            }
+        );
+
         CommandTestCase {
             command: "prune",
             command_args: vec![vec!["foo"], vec!["--out-dir", "dist"], vec!["--docker"]],
@@ -2126,6 +2786,7 @@ mod test {
                     scope: None,
                     scope_arg: Some(vec!["foo".into()]),
                     docker: true,
+                    production: false,
                     output_dir: "dist".to_string(),
                 }),
                 ..Args::default()
@@ -2142,6 +2803,7 @@ mod test {
                     scope: None,
                     scope_arg: Some(vec!["foo".into()]),
                     docker: true,
+                    production: false,
                     output_dir: "dist".to_string(),
                 }),
                 cwd: Some(Utf8PathBuf::from("../examples/with-yarn")),
@@ -2163,6 +2825,7 @@ mod test {
                     scope: Some(vec!["foo".to_string()]),
                     scope_arg: None,
                     docker: true,
+                    production: false,
                     output_dir: "dist".to_string(),
                 }),
                 ..Args::default()
@@ -0,0 +1,99 @@
+use std::collections::{BTreeSet, HashMap};
+
+use turbopath::AnchoredSystemPath;
+use turborepo_repository::{
+    package_graph::{PackageGraph, PackageName},
+    package_json::PackageJson,
+};
+use turborepo_ui::{color, cprintln, BOLD, BOLD_GREEN, BOLD_RED};
+
+use super::CommandBase;
+use crate::{boundaries, cli, config, turbo_json::TurboJson};
+
+pub async fn run(base: &CommandBase) -> Result<i32, cli::Error> {
+    let ui = base.ui;
+    let root_package_json = PackageJson::load(&base.repo_root.join_component("package.json"))?;
+    let pkg_graph = PackageGraph::builder(&base.repo_root, root_package_json.clone())
+        .with_manifest_discovery(
+            base.config()?
+                .package_discovery()?
+                .into_iter()
+                .map(|ecosystem| ecosystem.discovery()),
+        )
+        .build()
+        .await?;
+    let root_turbo_json = TurboJson::load(
+        &base.repo_root,
+        AnchoredSystemPath::empty(),
+        &root_package_json,
+        false,
+    )?;
+
+    let tags = package_tags(base, &pkg_graph, &root_turbo_json)?;
+    let violations = boundaries::check(
+        &base.repo_root,
+        &pkg_graph,
+        &root_turbo_json.boundaries,
+        &tags,
+    )?;
+
+    if violations.is_empty() {
+        cprintln!(
+            ui,
+            BOLD_GREEN,
+            "No boundary violations found in {} packages",
+            pkg_graph.len()
+        );
+        return Ok(0);
+    }
+
+    for violation in &violations {
+        println!(
+            "{} {}",
+            color!(ui, BOLD, "{}:{}", violation.file, violation.line),
+            violation.message
+        );
+    }
+    println!();
+    cprintln!(
+        ui,
+        BOLD_RED,
+        "Found {} boundary {}",
+        violations.len(),
+        if violations.len() == 1 {
+            "violation"
+        } else {
+            "violations"
+        }
+    );
+
+    Ok(1)
+}
+
+/// Reads the `tags` of every package from its turbo.json, if it has one
+fn package_tags(
+    base: &CommandBase,
+    pkg_graph: &PackageGraph,
+    root_turbo_json: &TurboJson,
+) -> Result<HashMap<PackageName, BTreeSet<String>>, config::Error> {
+    let mut tags = HashMap::new();
+    for (name, info) in pkg_graph.packages() {
+        let package_tags = match name {
+            PackageName::Root => root_turbo_json.tags.clone(),
+            PackageName::Other(_) => {
+                match TurboJson::load(
+                    &base.repo_root,
+                    info.package_path(),
+                    &info.package_json,
+                    false,
+                ) {
+                    Ok(turbo_json) => turbo_json.tags,
+                    Err(config::Error::NoTurboJSON) => continue,
+                    Err(e) => return Err(e),
+                }
+            }
+        };
+        tags.insert(name.clone(), package_tags.into_iter().collect());
+    }
+    Ok(tags)
+}
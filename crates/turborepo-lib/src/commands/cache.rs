@@ -0,0 +1,126 @@
+use camino::Utf8Path;
+use serde::Serialize;
+use turborepo_cache::fs::{CacheStats, FSCache};
+use turborepo_ui::{color, BOLD_GREEN, GREY};
+
+use super::CommandBase;
+use crate::{cli, cli::CacheCommand};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StatsOutput {
+    cache_dir: String,
+    entries: usize,
+    size: u64,
+    max_size: Option<u64>,
+    max_age_secs: Option<u64>,
+}
+
+pub fn run(
+    base: &CommandBase,
+    cache_dir: Option<&Utf8Path>,
+    command: CacheCommand,
+) -> Result<(), cli::Error> {
+    let limits = base.config()?.fs_cache_limits()?;
+    match command {
+        CacheCommand::Prune => {
+            if limits.is_unbounded() {
+                println!(
+                    "No local cache limits configured. Set --cache-max-size, --cache-max-age or \
+                     \"localCache\" in turbo.json"
+                );
+                return Ok(());
+            }
+            let cache = FSCache::new(cache_dir, &base.repo_root, None, limits)?;
+            let removed = cache.prune()?;
+            print_removed(base, removed);
+        }
+        CacheCommand::Clean => {
+            let cache = FSCache::new(cache_dir, &base.repo_root, None, Default::default())?;
+            let removed = cache.clean()?;
+            print_removed(base, removed);
+        }
+        CacheCommand::Stats { json } => {
+            let cache = FSCache::new(cache_dir, &base.repo_root, None, Default::default())?;
+            let stats = cache.stats()?;
+            if json {
+                let output = StatsOutput {
+                    cache_dir: cache.cache_directory().to_string(),
+                    entries: stats.entries,
+                    size: stats.size,
+                    max_size: limits.max_size,
+                    max_age_secs: limits.max_age.map(|age| age.as_secs()),
+                };
+                println!("{}", serde_json::to_string_pretty(&output)?);
+            } else {
+                println!(
+                    "cache directory: {}",
+                    color!(base.ui, GREY, "{}", cache.cache_directory())
+                );
+                println!("entries: {}", color!(base.ui, GREY, "{}", stats.entries));
+                println!(
+                    "size: {}",
+                    color!(base.ui, GREY, "{}", format_bytes(stats.size))
+                );
+                if let Some(max_size) = limits.max_size {
+                    println!(
+                        "max size: {}",
+                        color!(base.ui, GREY, "{}", format_bytes(max_size))
+                    );
+                }
+                if let Some(max_age) = limits.max_age {
+                    println!(
+                        "max age: {}",
+                        color!(base.ui, GREY, "{}", humantime::format_duration(max_age))
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn print_removed(base: &CommandBase, removed: CacheStats) {
+    println!(
+        "{} removed {} {} ({})",
+        color!(base.ui, BOLD_GREEN, "✓"),
+        removed.entries,
+        if removed.entries == 1 {
+            "entry"
+        } else {
+            "entries"
+        },
+        format_bytes(removed.size)
+    );
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::format_bytes;
+
+    #[test_case(0, "0 B")]
+    #[test_case(1023, "1023 B")]
+    #[test_case(1536, "1.5 KB")]
+    #[test_case(10 * 1024 * 1024 * 1024, "10.0 GB")]
+    fn test_format_bytes(bytes: u64, expected: &str) {
+        assert_eq!(format_bytes(bytes), expected);
+    }
+}
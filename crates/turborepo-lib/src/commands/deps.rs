@@ -0,0 +1,357 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    str::FromStr,
+};
+
+use serde::Serialize;
+use thiserror::Error;
+use turbopath::AbsoluteSystemPath;
+use turborepo_repository::{
+    package_graph::{PackageGraph, PackageName},
+    package_json::PackageJson,
+};
+use turborepo_scm::SCM;
+use turborepo_ui::{color, cprintln, BOLD, BOLD_CYAN, BOLD_GREEN, BOLD_RED, GREY, YELLOW};
+
+use super::CommandBase;
+use crate::{cli, cli::DepsCommand};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("unable to read {path} at {git_ref}: {source}")]
+    ReadAtRef {
+        path: String,
+        git_ref: String,
+        #[source]
+        source: turborepo_scm::Error,
+    },
+    #[error("unable to read {path}: {source}")]
+    Read {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    PackageJson(#[from] turborepo_repository::package_json::Error),
+    #[error(transparent)]
+    PackageManager(#[from] turborepo_repository::package_manager::Error),
+    #[error(transparent)]
+    Lockfile(#[from] turborepo_lockfiles::Error),
+}
+
+/// The versions of each external package in a workspace's transitive
+/// closure, keyed by workspace name
+type Closures = BTreeMap<String, BTreeMap<String, BTreeSet<String>>>;
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceChanges {
+    pub workspace: String,
+    pub added: Vec<PackageVersions>,
+    pub removed: Vec<PackageVersions>,
+    pub upgraded: Vec<PackageUpgrade>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageVersions {
+    pub name: String,
+    pub versions: Vec<String>,
+}
+
+/// A package that resolves to different versions at the two refs. A package
+/// can resolve to several versions in one closure, e.g. when workspace
+/// packages depend on it with incompatible ranges.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageUpgrade {
+    pub name: String,
+    pub from: Vec<String>,
+    pub to: Vec<String>,
+}
+
+pub async fn run(base: &CommandBase, command: &DepsCommand) -> Result<(), cli::Error> {
+    match command {
+        DepsCommand::Diff { from, to, json } => {
+            let root_package_json =
+                PackageJson::load(&base.repo_root.join_component("package.json"))?;
+            let pkg_graph = PackageGraph::builder(&base.repo_root, root_package_json)
+                .build()
+                .await?;
+            let scm = SCM::new(&base.repo_root);
+
+            let previous = closures_at(&base.repo_root, &scm, &pkg_graph, Some(from))?;
+            let current = closures_at(&base.repo_root, &scm, &pkg_graph, to.as_deref())?;
+            let changes = diff(&previous, &current);
+
+            if *json {
+                println!("{}", serde_json::to_string_pretty(&changes)?);
+                return Ok(());
+            }
+            print(
+                base,
+                &changes,
+                from,
+                to.as_deref().unwrap_or("working tree"),
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Computes the closures of the workspaces in `pkg_graph` from the lockfile
+/// and package.json files at `git_ref`, or on disk if it's `None`. Workspaces
+/// that don't have a package.json at `git_ref` are left out.
+fn closures_at(
+    repo_root: &AbsoluteSystemPath,
+    scm: &SCM,
+    pkg_graph: &PackageGraph,
+    git_ref: Option<&str>,
+) -> Result<Closures, Error> {
+    let read = |path: &AbsoluteSystemPath| match git_ref {
+        Some(git_ref) => scm
+            .previous_content(git_ref, path)
+            .map_err(|source| Error::ReadAtRef {
+                path: path.to_string(),
+                git_ref: git_ref.to_string(),
+                source,
+            }),
+        None => path.read().map_err(|source| Error::Read {
+            path: path.to_string(),
+            source,
+        }),
+    };
+    let parse = |contents: Vec<u8>| PackageJson::from_str(&String::from_utf8_lossy(&contents));
+
+    let package_manager = pkg_graph.package_manager();
+    let root_package_json = parse(read(&repo_root.join_component("package.json"))?)?;
+    let lockfile = package_manager.parse_lockfile_contents(
+        repo_root,
+        &root_package_json,
+        read(&package_manager.lockfile_path(repo_root))?,
+    )?;
+
+    let mut names = HashMap::new();
+    let mut workspaces = HashMap::new();
+    for (name, info) in pkg_graph.packages() {
+        if info.ecosystem.is_some() {
+            continue;
+        }
+        let package_json = match name {
+            PackageName::Root => root_package_json.clone(),
+            // The lockfile was readable, so a failure here means the workspace
+            // didn't exist yet
+            PackageName::Other(_) => match read(&repo_root.resolve(info.package_json_path())) {
+                Ok(contents) => parse(contents)?,
+                Err(_) => continue,
+            },
+        };
+        let Some(dependencies) =
+            pkg_graph.external_dependencies_from(repo_root, name, &package_json)
+        else {
+            continue;
+        };
+        let workspace_path = info.package_path().to_unix().to_string();
+        names.insert(workspace_path.clone(), name.to_string());
+        workspaces.insert(workspace_path, dependencies.into_iter().collect());
+    }
+
+    let closures = turborepo_lockfiles::all_transitive_closures_by_name(&*lockfile, workspaces)?;
+    Ok(closures
+        .into_iter()
+        .map(|(workspace_path, closure)| (names[&workspace_path].clone(), closure))
+        .collect())
+}
+
+/// Compares the closures of each workspace, leaving out workspaces that
+/// didn't change
+pub fn diff(previous: &Closures, current: &Closures) -> Vec<WorkspaceChanges> {
+    let empty = BTreeMap::new();
+    let workspaces = previous
+        .keys()
+        .chain(current.keys())
+        .collect::<BTreeSet<_>>();
+
+    workspaces
+        .into_iter()
+        .filter_map(|workspace| {
+            let before = previous.get(workspace).unwrap_or(&empty);
+            let after = current.get(workspace).unwrap_or(&empty);
+            let mut changes = WorkspaceChanges {
+                workspace: workspace.clone(),
+                ..Default::default()
+            };
+            for (name, versions) in after {
+                match before.get(name) {
+                    None => changes.added.push(PackageVersions {
+                        name: name.clone(),
+                        versions: versions.iter().cloned().collect(),
+                    }),
+                    Some(previous_versions) if previous_versions != versions => {
+                        changes.upgraded.push(PackageUpgrade {
+                            name: name.clone(),
+                            from: previous_versions.iter().cloned().collect(),
+                            to: versions.iter().cloned().collect(),
+                        })
+                    }
+                    Some(_) => (),
+                }
+            }
+            for (name, versions) in before {
+                if !after.contains_key(name) {
+                    changes.removed.push(PackageVersions {
+                        name: name.clone(),
+                        versions: versions.iter().cloned().collect(),
+                    });
+                }
+            }
+
+            let unchanged = changes.added.is_empty()
+                && changes.removed.is_empty()
+                && changes.upgraded.is_empty();
+            (!unchanged).then_some(changes)
+        })
+        .collect()
+}
+
+fn print(base: &CommandBase, changes: &[WorkspaceChanges], from: &str, to: &str) {
+    let ui = base.ui;
+    if changes.is_empty() {
+        println!(
+            "No external dependencies changed between {} and {}",
+            color!(ui, BOLD, "{}", from),
+            color!(ui, BOLD, "{}", to)
+        );
+        return;
+    }
+
+    println!(
+        "External dependency changes between {} and {}",
+        color!(ui, BOLD, "{}", from),
+        color!(ui, BOLD, "{}", to)
+    );
+    for workspace in changes {
+        println!();
+        cprintln!(ui, BOLD_CYAN, "{}", workspace.workspace);
+        for package in &workspace.added {
+            println!(
+                "  {} {} {}",
+                color!(ui, BOLD_GREEN, "+"),
+                package.name,
+                color!(ui, GREY, "{}", package.versions.join(", "))
+            );
+        }
+        for package in &workspace.removed {
+            println!(
+                "  {} {} {}",
+                color!(ui, BOLD_RED, "-"),
+                package.name,
+                color!(ui, GREY, "{}", package.versions.join(", "))
+            );
+        }
+        for package in &workspace.upgraded {
+            println!(
+                "  {} {} {}",
+                color!(ui, YELLOW, "~"),
+                package.name,
+                color!(
+                    ui,
+                    GREY,
+                    "{} -> {}",
+                    package.from.join(", "),
+                    package.to.join(", ")
+                )
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn closure(packages: &[(&str, &[&str])]) -> BTreeMap<String, BTreeSet<String>> {
+        packages
+            .iter()
+            .map(|(name, versions)| {
+                (
+                    name.to_string(),
+                    versions.iter().map(|version| version.to_string()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_diff() {
+        let previous = Closures::from([
+            (
+                "web".to_string(),
+                closure(&[("react", &["18.2.0"]), ("left-pad", &["1.3.0"])]),
+            ),
+            ("docs".to_string(), closure(&[("react", &["18.2.0"])])),
+        ]);
+        let current = Closures::from([
+            (
+                "web".to_string(),
+                closure(&[("react", &["18.3.1"]), ("lodash", &["4.17.21"])]),
+            ),
+            ("docs".to_string(), closure(&[("react", &["18.2.0"])])),
+            ("ui".to_string(), closure(&[("clsx", &["2.0.0"])])),
+        ]);
+
+        assert_eq!(
+            diff(&previous, &current),
+            [
+                WorkspaceChanges {
+                    workspace: "ui".to_string(),
+                    added: vec![PackageVersions {
+                        name: "clsx".to_string(),
+                        versions: vec!["2.0.0".to_string()],
+                    }],
+                    ..Default::default()
+                },
+                WorkspaceChanges {
+                    workspace: "web".to_string(),
+                    added: vec![PackageVersions {
+                        name: "lodash".to_string(),
+                        versions: vec!["4.17.21".to_string()],
+                    }],
+                    removed: vec![PackageVersions {
+                        name: "left-pad".to_string(),
+                        versions: vec!["1.3.0".to_string()],
+                    }],
+                    upgraded: vec![PackageUpgrade {
+                        name: "react".to_string(),
+                        from: vec!["18.2.0".to_string()],
+                        to: vec!["18.3.1".to_string()],
+                    }],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_multiple_versions() {
+        let previous = Closures::from([(
+            "web".to_string(),
+            closure(&[("semver", &["6.3.1", "7.5.4"])]),
+        )]);
+        let current = Closures::from([("web".to_string(), closure(&[("semver", &["7.5.4"])]))]);
+
+        assert_eq!(
+            diff(&previous, &current),
+            [WorkspaceChanges {
+                workspace: "web".to_string(),
+                upgraded: vec![PackageUpgrade {
+                    name: "semver".to_string(),
+                    from: vec!["6.3.1".to_string(), "7.5.4".to_string()],
+                    to: vec!["7.5.4".to_string()],
+                }],
+                ..Default::default()
+            }]
+        );
+        assert!(diff(&current, &current).is_empty());
+    }
+}
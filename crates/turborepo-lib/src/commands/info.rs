@@ -73,12 +73,17 @@ pub async fn run(
 ) -> Result<(), cli::Error> {
     let root_package_json = PackageJson::load(&base.repo_root.join_component("package.json"))?;
 
+    let config = base.config()?;
     let package_graph = PackageGraph::builder(&base.repo_root, root_package_json)
+        .with_manifest_discovery(
+            config
+                .package_discovery()?
+                .into_iter()
+                .map(|ecosystem| ecosystem.discovery()),
+        )
         .build()
         .await?;
 
-    let config = base.config()?;
-
     if let Some(workspace) = workspace {
         let workspace_details = WorkspaceDetails::new(&package_graph, workspace);
         if json {
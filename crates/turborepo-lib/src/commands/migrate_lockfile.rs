@@ -0,0 +1,239 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    process::Command,
+};
+
+use serde::Serialize;
+use thiserror::Error;
+use turbopath::AbsoluteSystemPath;
+use turborepo_lockfiles::{
+    Lockfile, NpmLockfile, PnpmLockfile, ResolvedGraph, WorkspaceManifest, Yarn1Lockfile,
+};
+use turborepo_repository::{
+    package_graph::PackageGraph, package_json::PackageJson, package_manager::PackageManager,
+};
+use turborepo_ui::{color, cprintln, BOLD, BOLD_GREEN, GREY};
+
+use super::CommandBase;
+use crate::{
+    cli,
+    rewrite_json::{set_path, unset_path, RewriteError},
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("io error while migrating lockfile: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("json error while migrating lockfile: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("yaml error while migrating lockfile: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+    #[error(transparent)]
+    Rewrite(#[from] RewriteError),
+    #[error("unable to find {0} to determine its version, pass a version with --to={0}@<version>")]
+    MissingBinary(String, #[source] which::Error),
+    #[error("unable to parse the version of {name}: {output}")]
+    InvalidVersion { name: String, output: String },
+    #[error("unknown package manager {0}, expected one of npm, pnpm or yarn")]
+    UnknownPackageManager(String),
+    #[error("cannot migrate to {0}, only npm, pnpm and yarn v1 lockfiles can be written")]
+    UnsupportedTarget(PackageManager),
+    #[error("the repository already uses {0}")]
+    SamePackageManager(PackageManager),
+    #[error("cannot migrate without a parsed lockfile")]
+    MissingLockfile,
+    #[error(transparent)]
+    PackageManager(#[from] turborepo_repository::package_manager::Error),
+    #[error(transparent)]
+    Lockfile(#[from] turborepo_lockfiles::Error),
+}
+
+#[derive(Serialize)]
+struct PnpmWorkspace<'a> {
+    packages: &'a [String],
+}
+
+pub async fn run(base: &CommandBase, to: &str) -> Result<(), cli::Error> {
+    let root_package_json = PackageJson::load(&base.repo_root.join_component("package.json"))?;
+    let pkg_graph = PackageGraph::builder(&base.repo_root, root_package_json)
+        .build()
+        .await?;
+    migrate(base, &pkg_graph, to)?;
+
+    Ok(())
+}
+
+fn migrate(base: &CommandBase, pkg_graph: &PackageGraph, to: &str) -> Result<(), Error> {
+    let ui = base.ui;
+    let repo_root = &base.repo_root;
+    let lockfile = pkg_graph.lockfile().ok_or(Error::MissingLockfile)?;
+
+    let source = *pkg_graph.package_manager();
+    let (target, package_manager_field) = target_package_manager(repo_root, to)?;
+    if target == source {
+        return Err(Error::SamePackageManager(target));
+    }
+
+    let workspaces = pkg_graph
+        .packages()
+        .filter(|(_, info)| info.ecosystem.is_none())
+        .filter_map(|(name, info)| {
+            let package_json = &info.package_json;
+            let external = pkg_graph.external_dependencies_from(repo_root, name, package_json)?;
+            let manifest = WorkspaceManifest {
+                name: package_json.name.clone(),
+                version: package_json.version.clone(),
+                dependencies: package_json.dependencies.clone().unwrap_or_default(),
+                dev_dependencies: package_json.dev_dependencies.clone().unwrap_or_default(),
+                optional_dependencies: package_json
+                    .optional_dependencies
+                    .clone()
+                    .unwrap_or_default(),
+                workspace_dependencies: package_json
+                    .all_dependencies()
+                    .map(|(name, _)| name.clone())
+                    .filter(|name| !external.contains_key(name))
+                    .collect::<BTreeSet<_>>(),
+            };
+            Some((info.package_path().to_unix().to_string(), manifest))
+        })
+        .collect::<BTreeMap<_, _>>();
+    let graph = ResolvedGraph::new(lockfile, workspaces)?;
+
+    // Only carry over the globs the user configured, the target package
+    // manager adds its own default exclusions
+    let (inclusions, exclusions) = source.get_configured_workspace_globs(repo_root)?;
+    let globs = inclusions
+        .into_iter()
+        .chain(exclusions.into_iter().map(|glob| format!("!{glob}")))
+        .collect::<Vec<_>>();
+
+    // Everything is rendered before anything is written, so a failure here
+    // leaves the repository untouched
+    let contents = encode(target, &graph, &globs)?;
+    let root_package_json_path = repo_root.join_component("package.json");
+    let mut root_package_json = root_package_json_path.read_to_string()?;
+    let pnpm_workspace_yaml = if target == PackageManager::Pnpm {
+        if let Some(updated) = unset_path(&root_package_json, &["workspaces"], true)? {
+            root_package_json = updated;
+        }
+        Some(serde_yaml::to_string(&PnpmWorkspace { packages: &globs })?)
+    } else {
+        root_package_json = set_path(
+            &root_package_json,
+            &["workspaces"],
+            &serde_json::to_string(&globs)?,
+        )?;
+        None
+    };
+    root_package_json = set_path(
+        &root_package_json,
+        &["packageManager"],
+        &serde_json::to_string(&package_manager_field)?,
+    )?;
+
+    // The previous lockfile is only removed once everything else is written,
+    // so an interrupted migration can still be undone
+    let previous_lockfile_path = source.lockfile_path(repo_root);
+    let lockfile_path = target.lockfile_path(repo_root);
+    lockfile_path.create_with_contents(contents)?;
+    let pnpm_workspace_path = repo_root.join_component("pnpm-workspace.yaml");
+    if let Some(pnpm_workspace_yaml) = pnpm_workspace_yaml {
+        pnpm_workspace_path.create_with_contents(pnpm_workspace_yaml)?;
+    }
+    root_package_json_path.create_with_contents(root_package_json)?;
+    if target != PackageManager::Pnpm && pnpm_workspace_path.exists() {
+        pnpm_workspace_path.remove_file()?;
+    }
+    if previous_lockfile_path != lockfile_path {
+        previous_lockfile_path.remove_file()?;
+    }
+
+    cprintln!(
+        ui,
+        BOLD_GREEN,
+        "Migrated {} to {}",
+        previous_lockfile_path.file_name().unwrap_or_default(),
+        lockfile_path.file_name().unwrap_or_default()
+    );
+    println!(
+        "{}",
+        color!(
+            ui,
+            GREY,
+            "Set packageManager to {} and moved workspace globs to {}",
+            package_manager_field,
+            target
+                .workspace_glob_source(repo_root)
+                .file_name()
+                .unwrap_or_default()
+        )
+    );
+    println!(
+        "Run {} to finish the migration",
+        color!(ui, BOLD, "{} install", target.command())
+    );
+
+    Ok(())
+}
+
+/// Resolves `to`, either `<name>` or `<name>@<version>`, to a package manager
+/// and the value for the `packageManager` field. The version is read from the
+/// installed binary if it isn't given.
+fn target_package_manager(
+    repo_root: &AbsoluteSystemPath,
+    to: &str,
+) -> Result<(PackageManager, String), Error> {
+    let package_manager_field = match to.split_once('@') {
+        Some(_) => to.to_string(),
+        None => {
+            if !matches!(to, "npm" | "pnpm" | "yarn") {
+                return Err(Error::UnknownPackageManager(to.to_string()));
+            }
+            let binary = which::which(to).map_err(|e| Error::MissingBinary(to.to_string(), e))?;
+            let output = Command::new(binary)
+                .arg("--version")
+                .current_dir(repo_root)
+                .output()?;
+            let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            if version.parse::<node_semver::Version>().is_err() {
+                return Err(Error::InvalidVersion {
+                    name: to.to_string(),
+                    output: version,
+                });
+            }
+            format!("{to}@{version}")
+        }
+    };
+
+    let package_json = PackageJson {
+        package_manager: Some(package_manager_field.clone()),
+        ..Default::default()
+    };
+    let target = PackageManager::read_package_manager(&package_json)?
+        .ok_or_else(|| Error::UnknownPackageManager(to.to_string()))?;
+    match target {
+        PackageManager::Npm | PackageManager::Pnpm | PackageManager::Yarn => {
+            Ok((target, package_manager_field))
+        }
+        PackageManager::Pnpm6 | PackageManager::Berry | PackageManager::Bun => {
+            Err(Error::UnsupportedTarget(target))
+        }
+    }
+}
+
+fn encode(
+    target: PackageManager,
+    graph: &ResolvedGraph,
+    workspace_globs: &[String],
+) -> Result<Vec<u8>, Error> {
+    let contents = match target {
+        PackageManager::Npm => NpmLockfile::from_graph(graph, workspace_globs).encode()?,
+        PackageManager::Pnpm => PnpmLockfile::from_graph(graph).encode()?,
+        PackageManager::Yarn => Yarn1Lockfile::from_graph(graph).encode()?,
+        PackageManager::Pnpm6 | PackageManager::Berry | PackageManager::Bun => {
+            return Err(Error::UnsupportedTarget(target))
+        }
+    };
+    Ok(contents)
+}
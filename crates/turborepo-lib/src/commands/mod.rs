@@ -11,16 +11,24 @@ use crate::{
 };
 
 pub(crate) mod bin;
+pub(crate) mod boundaries;
+pub(crate) mod cache;
 pub(crate) mod daemon;
+pub(crate) mod deps;
 pub(crate) mod generate;
 pub(crate) mod info;
 pub(crate) mod link;
 pub(crate) mod login;
 pub(crate) mod logout;
+pub(crate) mod migrate_lockfile;
 pub(crate) mod prune;
+pub(crate) mod query;
 pub(crate) mod run;
+pub(crate) mod stats;
+pub(crate) mod summary;
 pub(crate) mod telemetry;
 pub(crate) mod unlink;
+pub(crate) mod watch;
 
 #[derive(Debug)]
 pub struct CommandBase {
@@ -65,6 +73,8 @@ impl CommandBase {
             .with_team_slug(self.args.team.clone())
             .with_token(self.args.token.clone())
             .with_timeout(self.args.remote_cache_timeout)
+            .with_cache_max_size(self.args.cache_max_size.clone())
+            .with_cache_max_age(self.args.cache_max_age.clone())
             .build()
     }
 
@@ -1,11 +1,15 @@
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
-use std::sync::OnceLock;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::OnceLock,
+};
 
 use lazy_static::lazy_static;
 use tracing::trace;
 use turbopath::{
-    AbsoluteSystemPathBuf, AnchoredSystemPath, AnchoredSystemPathBuf, RelativeUnixPath,
+    AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPath, AnchoredSystemPathBuf,
+    RelativeUnixPath,
 };
 use turborepo_repository::{
     package_graph::{self, PackageGraph, PackageName, PackageNode},
@@ -15,7 +19,10 @@ use turborepo_telemetry::events::command::CommandEventBuilder;
 use turborepo_ui::BOLD;
 
 use super::CommandBase;
-use crate::turbo_json::RawTurboJson;
+use crate::{
+    rewrite_json::{unset_path, RewriteError},
+    turbo_json::RawTurboJson,
+};
 
 pub const DEFAULT_OUTPUT_DIR: &str = "out";
 
@@ -30,6 +37,8 @@ pub enum Error {
     #[error("path error while pruning: {0}")]
     Path(#[from] turbopath::PathError),
     #[error(transparent)]
+    Rewrite(#[from] RewriteError),
+    #[error(transparent)]
     TurboJsonParser(#[from] crate::turbo_json::parser::Error),
     #[error(transparent)]
     PackageJson(#[from] turborepo_repository::package_json::Error),
@@ -45,8 +54,6 @@ pub enum Error {
     MissingWorkspace(PackageName),
     #[error("Cannot prune without parsed lockfile")]
     MissingLockfile,
-    #[error("Prune is not supported for Bun")]
-    BunUnsupported,
 }
 
 // Files that should be copied from root and if they're required for install
@@ -88,20 +95,15 @@ pub async fn prune(
     base: &CommandBase,
     scope: &[String],
     docker: bool,
+    production: bool,
     output_dir: &str,
     telemetry: CommandEventBuilder,
 ) -> Result<(), Error> {
     telemetry.track_arg_usage("docker", docker);
+    telemetry.track_arg_usage("production", production);
     telemetry.track_arg_usage("out-dir", output_dir != DEFAULT_OUTPUT_DIR);
 
-    let prune = Prune::new(base, scope, docker, output_dir).await?;
-
-    if matches!(
-        prune.package_graph.package_manager(),
-        turborepo_repository::package_manager::PackageManager::Bun
-    ) {
-        return Err(Error::BunUnsupported);
-    }
+    let prune = Prune::new(base, scope, docker, production, output_dir).await?;
 
     println!(
         "Generating pruned monorepo for {} in {}",
@@ -122,18 +124,31 @@ pub async fn prune(
 
     let mut workspace_paths = Vec::new();
     let mut workspace_names = Vec::new();
-    let workspaces = prune.internal_dependencies();
-    let lockfile_keys: Vec<_> = prune
-        .package_graph
-        .transitive_external_dependencies(workspaces.iter())
-        .into_iter()
-        .map(|pkg| pkg.key.clone())
-        .collect();
+    let (workspaces, lockfile_keys) = match prune.production {
+        true => prune.production_dependencies()?,
+        false => {
+            let workspaces = prune.internal_dependencies();
+            let lockfile_keys = prune
+                .package_graph
+                .transitive_external_dependencies(workspaces.iter())
+                .into_iter()
+                .map(|pkg| pkg.key.clone())
+                .collect();
+            (workspaces, lockfile_keys)
+        }
+    };
+    let mut dev_dependencies = HashMap::new();
     for workspace in workspaces {
         let entry = prune
             .package_graph
             .package_info(&workspace)
             .ok_or_else(|| Error::MissingWorkspace(workspace.clone()))?;
+        if prune.production {
+            dev_dependencies.insert(
+                entry.package_path().to_unix().to_string(),
+                dev_only_dependencies(&entry.package_json),
+            );
+        }
 
         // We don't want to do any copying for the root workspace
         if let PackageName::Other(workspace) = workspace {
@@ -154,14 +169,26 @@ pub async fn prune(
     trace!("new workspaces: {}", workspace_paths.join(", "));
     trace!("lockfile keys: {}", lockfile_keys.join(", "));
 
-    let lockfile = prune
+    let original_lockfile = prune
         .package_graph
         .lockfile()
-        .expect("Lockfile presence already checked")
-        .subgraph(&workspace_paths, &lockfile_keys)?;
+        .expect("Lockfile presence already checked");
+    let lockfile = match prune.production {
+        true => original_lockfile.production_subgraph(
+            &workspace_paths,
+            &lockfile_keys,
+            &dev_dependencies,
+        )?,
+        false => original_lockfile.subgraph(&workspace_paths, &lockfile_keys)?,
+    };
 
     let lockfile_contents = lockfile.encode()?;
-    let lockfile_name = prune.package_graph.package_manager().lockfile_name();
+    let lockfile_name = match prune.package_graph.package_manager() {
+        // bun.lockb is a binary format that only bun can write, so we output the
+        // yarn-style lockfile that bun prints for it instead
+        turborepo_repository::package_manager::PackageManager::Bun => "yarn.lock",
+        package_manager => package_manager.lockfile_name(),
+    };
     let lockfile_path = prune.out_directory.join_component(lockfile_name);
     lockfile_path.create_with_contents(&lockfile_contents)?;
     if prune.docker {
@@ -183,11 +210,7 @@ pub async fn prune(
 
     prune.copy_turbo_json(&workspace_names)?;
 
-    let original_patches = prune
-        .package_graph
-        .lockfile()
-        .expect("lockfile presence checked earlier")
-        .patches()?;
+    let original_patches = original_lockfile.patches()?;
     if !original_patches.is_empty() {
         let pruned_patches = lockfile.patches()?;
         trace!(
@@ -195,10 +218,13 @@ pub async fn prune(
             original_patches,
             pruned_patches
         );
-        let pruned_json = prune
+        let mut pruned_json = prune
             .package_graph
             .package_manager()
             .prune_patched_packages(prune.package_graph.root_package_json(), &pruned_patches);
+        if prune.production {
+            pruned_json.dev_dependencies = None;
+        }
         let mut pruned_json_contents = serde_json::to_string_pretty(&pruned_json)?;
         // Add trailing newline to match Go behavior
         pruned_json_contents.push('\n');
@@ -228,17 +254,50 @@ pub async fn prune(
         }
     } else {
         prune.copy_file(package_json(), Some(CopyDestination::Docker))?;
+        if prune.production {
+            strip_dev_dependencies(&prune.full_directory.resolve(package_json()))?;
+            if prune.docker {
+                strip_dev_dependencies(&prune.docker_directory().resolve(package_json()))?;
+            }
+        }
     }
 
     Ok(())
 }
 
+// The devDependencies of a package that it doesn't also depend on in production
+fn dev_only_dependencies(package_json: &PackageJson) -> HashSet<String> {
+    package_json
+        .dev_dependencies
+        .iter()
+        .flatten()
+        .map(|(name, _)| name)
+        .filter(|name| {
+            !package_json
+                .production_dependencies()
+                .any(|(dependency, _)| dependency == *name)
+        })
+        .cloned()
+        .collect()
+}
+
+// Removes the devDependencies field from a copied package.json, leaving the
+// rest of the file as it was
+fn strip_dev_dependencies(package_json_path: &AbsoluteSystemPath) -> Result<(), Error> {
+    let contents = package_json_path.read_to_string()?;
+    if let Some(contents) = unset_path(&contents, &["devDependencies"], true)? {
+        package_json_path.create_with_contents(contents)?;
+    }
+    Ok(())
+}
+
 struct Prune<'a> {
     package_graph: PackageGraph,
     root: AbsoluteSystemPathBuf,
     out_directory: AbsoluteSystemPathBuf,
     full_directory: AbsoluteSystemPathBuf,
     docker: bool,
+    production: bool,
     scope: &'a [String],
 }
 
@@ -256,6 +315,7 @@ impl<'a> Prune<'a> {
         base: &CommandBase,
         scope: &'a [String],
         docker: bool,
+        production: bool,
         output_dir: &str,
     ) -> Result<Self, Error> {
         if scope.is_empty() {
@@ -278,6 +338,7 @@ impl<'a> Prune<'a> {
 
         trace!("scope: {}", scope.join(", "));
         trace!("docker: {}", docker);
+        trace!("production: {}", production);
         trace!("out directory: {}", &out_directory);
 
         for target in scope {
@@ -314,6 +375,7 @@ impl<'a> Prune<'a> {
             out_directory,
             full_directory,
             docker,
+            production,
             scope,
         })
     }
@@ -390,11 +452,15 @@ impl<'a> Prune<'a> {
 
         turborepo_fs::recursive_copy(original_dir, &target_dir)?;
 
+        if self.production {
+            strip_dev_dependencies(&target_dir.resolve(package_json()))?;
+        }
+
         if self.docker {
             let docker_workspace_dir = self.docker_directory().resolve(&relative_workspace_dir);
             docker_workspace_dir.ensure_dir()?;
             turborepo_fs::copy_file(
-                package_json_path,
+                target_dir.resolve(package_json()),
                 docker_workspace_dir.resolve(package_json()),
             )?;
         }
@@ -402,6 +468,22 @@ impl<'a> Prune<'a> {
         Ok(())
     }
 
+    // The workspaces and lockfile keys needed in production, following only
+    // dependencies and optionalDependencies
+    fn production_dependencies(&self) -> Result<(Vec<PackageName>, Vec<String>), Error> {
+        let targets = std::iter::once(PackageName::Root)
+            .chain(self.scope.iter().cloned().map(PackageName::Other))
+            .collect::<Vec<_>>();
+        let (workspaces, external) = self
+            .package_graph
+            .production_closure(&self.root, targets.iter())?;
+
+        let mut names: Vec<_> = workspaces.into_iter().collect();
+        names.sort();
+        let lockfile_keys = external.into_iter().map(|pkg| pkg.key).collect();
+        Ok((names, lockfile_keys))
+    }
+
     fn internal_dependencies(&self) -> Vec<PackageName> {
         let workspaces = std::iter::once(PackageNode::Workspace(PackageName::Root))
             .chain(
@@ -445,3 +527,43 @@ impl<'a> Prune<'a> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use tempfile::TempDir;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::strip_dev_dependencies;
+
+    #[test]
+    fn test_strip_dev_dependencies_keeps_formatting() {
+        let dir = TempDir::new().unwrap();
+        let path = AbsoluteSystemPathBuf::try_from(dir.path())
+            .unwrap()
+            .join_component("package.json");
+        path.create_with_contents(
+            r#"{
+    "name": "web",
+    "version": "1.0.0",
+    "scripts": { "build": "next build" },
+    "dependencies": { "react": "^18.2.0" },
+    "devDependencies": {
+        "typescript": "^5.0.0"
+    }
+}
+"#,
+        )
+        .unwrap();
+        strip_dev_dependencies(&path).unwrap();
+        assert_eq!(
+            path.read_to_string().unwrap(),
+            r#"{
+    "name": "web",
+    "version": "1.0.0",
+    "scripts": { "build": "next build" },
+    "dependencies": { "react": "^18.2.0" }
+}
+"#
+        );
+    }
+}
@@ -0,0 +1,40 @@
+use turbopath::AnchoredSystemPath;
+use turborepo_repository::{package_graph::PackageGraph, package_json::PackageJson};
+
+use super::CommandBase;
+use crate::{
+    cli,
+    query::{Query, QueryContext},
+    turbo_json::TurboJson,
+};
+
+pub async fn run(base: &CommandBase, query: &str) -> Result<(), cli::Error> {
+    // Parse first so that typos are reported without loading the repository
+    let query = Query::parse(query)?;
+
+    let root_package_json = PackageJson::load(&base.repo_root.join_component("package.json"))?;
+    let pkg_graph = PackageGraph::builder(&base.repo_root, root_package_json.clone())
+        .with_manifest_discovery(
+            base.config()?
+                .package_discovery()?
+                .into_iter()
+                .map(|ecosystem| ecosystem.discovery()),
+        )
+        .build()
+        .await?;
+    let root_turbo_json = TurboJson::load(
+        &base.repo_root,
+        AnchoredSystemPath::empty(),
+        &root_package_json,
+        false,
+    )?;
+
+    let output = query.evaluate(&QueryContext {
+        repo_root: &base.repo_root,
+        pkg_graph: &pkg_graph,
+        root_turbo_json: &root_turbo_json,
+    })?;
+    println!("{}", serde_json::to_string_pretty(&output)?);
+
+    Ok(())
+}
@@ -1,8 +1,11 @@
+use futures::Future;
 use turborepo_telemetry::events::command::CommandEventBuilder;
 
 use crate::{commands::CommandBase, run, run::Run, signal::SignalHandler};
 
-pub async fn run(base: CommandBase, telemetry: CommandEventBuilder) -> Result<i32, run::Error> {
+/// Creates a future that resolves when the process receives a signal that
+/// should cause turbo to shut down
+pub(crate) fn get_signal() -> Result<impl Future<Output = Option<()>>, run::Error> {
     #[cfg(windows)]
     let signal = {
         let mut ctrl_c = tokio::signal::windows::ctrl_c().map_err(run::Error::SignalHandler)?;
@@ -27,6 +30,11 @@ pub async fn run(base: CommandBase, telemetry: CommandEventBuilder) -> Result<i3
         }
     };
 
+    Ok(signal)
+}
+
+pub async fn run(base: CommandBase, telemetry: CommandEventBuilder) -> Result<i32, run::Error> {
+    let signal = get_signal()?;
     let handler = SignalHandler::new(signal);
 
     let api_auth = base.api_auth()?;
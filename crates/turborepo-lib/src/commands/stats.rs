@@ -0,0 +1,108 @@
+use chrono::Duration;
+use turborepo_ui::{color, cprintln, BOLD, BOLD_CYAN, GREY};
+
+use super::CommandBase;
+use crate::{
+    cli,
+    run::summary::{history::History, stats::Stats, TurboDuration},
+};
+
+// How many entries the slowest task and miss cause lists show
+const TOP_ENTRIES: usize = 10;
+
+pub fn run(base: &CommandBase, runs: usize, json: bool) -> Result<(), cli::Error> {
+    let records = History::new(&base.repo_root).recent(runs)?;
+    let stats = Stats::new(&records);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+
+    if stats.runs == 0 {
+        println!("No runs recorded yet");
+        return Ok(());
+    }
+
+    let ui = base.ui;
+    println!(
+        "Reporting on the last {}",
+        color!(ui, BOLD, "{} run(s)", stats.runs)
+    );
+    println!();
+
+    cprintln!(ui, BOLD_CYAN, "Tasks");
+    for task in &stats.tasks {
+        let trend = match task.trend {
+            Some(trend) => format!(", last run {:+.0}% vs. mean", trend * 100.0),
+            None => String::new(),
+        };
+        println!(
+            "  {} {}",
+            color!(ui, BOLD, "{}", task.task_id),
+            color!(
+                ui,
+                GREY,
+                "{}/{} cache hits ({:.0}%), mean {}{}",
+                task.cache_hits,
+                task.runs,
+                task.hit_rate() * 100.0,
+                format_duration(task.mean_duration),
+                trend
+            )
+        );
+    }
+    println!();
+
+    cprintln!(ui, BOLD_CYAN, "Slowest Tasks");
+    let slowest = stats.slowest();
+    if slowest.is_empty() {
+        println!("  no task executed without a cache hit");
+    }
+    for task in slowest.into_iter().take(TOP_ENTRIES) {
+        println!(
+            "  {} {}",
+            color!(ui, BOLD, "{}", task.task_id),
+            color!(
+                ui,
+                GREY,
+                "mean {}, max {}",
+                format_duration(task.mean_duration),
+                format_duration(task.max_duration)
+            )
+        );
+    }
+    println!();
+
+    cprintln!(ui, BOLD_CYAN, "Cache Miss Causes");
+    if stats.miss_causes.is_empty() {
+        println!(
+            "  no cache misses were explained, use --summarize or --explain-miss to record why \
+             tasks miss the cache"
+        );
+    }
+    for cause in stats.miss_causes.iter().take(TOP_ENTRIES) {
+        println!("  {} {}", color!(ui, BOLD, "{}x", cause.count), cause.cause);
+    }
+    println!();
+
+    cprintln!(ui, BOLD_CYAN, "Critical Path");
+    println!(
+        "  {} {}",
+        stats.critical_path.tasks.join(" -> "),
+        color!(
+            ui,
+            GREY,
+            "({})",
+            format_duration(Some(stats.critical_path.duration))
+        )
+    );
+
+    Ok(())
+}
+
+fn format_duration(milliseconds: Option<i64>) -> String {
+    match milliseconds {
+        Some(milliseconds) => TurboDuration::from(Duration::milliseconds(milliseconds)).to_string(),
+        None => "-".to_string(),
+    }
+}
@@ -0,0 +1,45 @@
+use turborepo_ui::{color, BOLD, GREY};
+
+use super::CommandBase;
+use crate::{
+    cli,
+    cli::SummaryCommand,
+    run::summary::diff::{self, SavedRunSummary},
+};
+
+pub fn run(base: &CommandBase, command: &SummaryCommand) -> Result<(), cli::Error> {
+    match command {
+        SummaryCommand::Diff { from, to } => {
+            let previous = SavedRunSummary::find(&base.repo_root, from)?;
+            let current = SavedRunSummary::find(&base.repo_root, to)?;
+            let diffs = diff::diff(&previous, &current);
+            if diffs.is_empty() {
+                println!(
+                    "No task hashes changed between runs {} and {}",
+                    previous.id, current.id
+                );
+            }
+            for diff in diffs {
+                println!(
+                    "{} {}",
+                    color!(base.ui, BOLD, "{}", diff.task_id),
+                    color!(base.ui, GREY, "{} -> {}", diff.previous_hash, diff.hash)
+                );
+                if diff.changes.is_empty() {
+                    println!(
+                        "  {}",
+                        color!(
+                            base.ui,
+                            GREY,
+                            "none of the inputs recorded in run summaries changed"
+                        )
+                    );
+                } else {
+                    diff.print(base.ui);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
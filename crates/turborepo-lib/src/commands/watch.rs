@@ -0,0 +1,17 @@
+use turborepo_telemetry::events::command::CommandEventBuilder;
+
+use crate::{
+    commands::{run::get_signal, CommandBase},
+    run::watch::{Error, WatchClient},
+    signal::SignalHandler,
+};
+
+pub async fn run(base: CommandBase, telemetry: CommandEventBuilder) -> Result<i32, Error> {
+    let signal = get_signal()?;
+    let handler = SignalHandler::new(signal);
+
+    let client = WatchClient::new(base);
+    let result = client.start(&handler, telemetry).await;
+    handler.close().await;
+    result
+}
@@ -1,4 +1,9 @@
-use std::{collections::HashMap, ffi::OsString, io};
+use std::{
+    collections::{BTreeMap, HashMap},
+    ffi::OsString,
+    io,
+    time::Duration,
+};
 
 use convert_case::{Case, Casing};
 use miette::{Diagnostic, SourceSpan};
@@ -6,12 +11,20 @@ use serde::Deserialize;
 use struct_iterable::Iterable;
 use thiserror::Error;
 use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPath};
+use turborepo_cache::{fs::FSCacheLimits, s3::S3Opts, RemoteCacheBackendOpts};
 use turborepo_dirs::config_dir;
 use turborepo_errors::TURBO_SITE;
-use turborepo_repository::package_json::{Error as PackageJsonError, PackageJson};
+use turborepo_repository::{
+    discovery::Ecosystem,
+    package_json::{Error as PackageJsonError, PackageJson},
+};
 
 pub use crate::turbo_json::RawTurboJson;
-use crate::{commands::CommandBase, turbo_json};
+use crate::{
+    commands::CommandBase,
+    run::otel::{OtelOpts, OtelProtocol},
+    turbo_json,
+};
 
 #[allow(clippy::enum_variant_names)]
 #[derive(Debug, Error, Diagnostic)]
@@ -103,6 +116,53 @@ pub enum Error {
         #[source_code]
         text: String,
     },
+    #[error(
+        "Invalid `{field}` duration \"{value}\". Expected a duration like \"30s\" or \"10m\"."
+    )]
+    InvalidTaskDuration {
+        field: &'static str,
+        value: String,
+        #[label("invalid duration")]
+        span: Option<SourceSpan>,
+        #[source_code]
+        text: String,
+    },
+    #[error("`command` has an unterminated quote. Quotes must be closed when `shell` is false.")]
+    InvalidTaskCommand {
+        #[label("command declared here")]
+        span: Option<SourceSpan>,
+        #[source_code]
+        text: String,
+    },
+    #[error("`{field}` can only be set on tasks with a `command`")]
+    FieldRequiresCommand {
+        field: &'static str,
+        #[label("set here")]
+        span: Option<SourceSpan>,
+        #[source_code]
+        text: String,
+    },
+    #[error("Resource \"{resource}\" is not declared in the root turbo.json `resources`")]
+    UnknownResource {
+        resource: String,
+        #[label("resource used here")]
+        span: Option<SourceSpan>,
+        #[source_code]
+        text: String,
+    },
+    #[error(
+        "Task needs {weight} of resource \"{resource}\" but the pool only has a capacity of \
+         {capacity}"
+    )]
+    ResourceExceedsCapacity {
+        resource: String,
+        weight: u32,
+        capacity: u32,
+        #[label("requested here")]
+        span: Option<SourceSpan>,
+        #[source_code]
+        text: String,
+    },
     #[error("No \"extends\" key found in {path}")]
     NoExtends { path: String },
     #[error("Failed to create APIClient: {0}")]
@@ -117,6 +177,37 @@ pub enum Error {
     InvalidRemoteCacheTimeout(#[source] std::num::ParseIntError),
     #[error("TURBO_PREFLIGHT should be either 1 or 0.")]
     InvalidPreflight,
+    #[error(
+        "Invalid remote cache backend \"{0}\". Expected one of \"vercel\", \"s3\" or \
+         \"directory\"."
+    )]
+    InvalidRemoteCacheBackend(String),
+    #[error(
+        "The S3 remote cache requires a bucket. Set \"s3Bucket\" under \"remoteCache\" in \
+         turbo.json or TURBO_REMOTE_CACHE_S3_BUCKET."
+    )]
+    MissingS3Bucket,
+    #[error(
+        "The directory remote cache requires a directory. Set \"directory\" under \"remoteCache\" \
+         in turbo.json or TURBO_REMOTE_CACHE_DIR."
+    )]
+    MissingRemoteCacheDir,
+    #[error("Invalid cache max size \"{0}\". Expected a size like \"500MB\" or \"10GB\".")]
+    InvalidCacheMaxSize(String),
+    #[error("Invalid cache max age \"{value}\". Expected a duration like \"12h\" or \"7d\".")]
+    InvalidCacheMaxAge {
+        value: String,
+        #[source]
+        source: humantime::DurationError,
+    },
+    #[error(
+        "Invalid OpenTelemetry protocol \"{0}\". Expected either \"http/protobuf\" or \"grpc\"."
+    )]
+    InvalidOtelProtocol(String),
+    #[error("OTEL_EXPORTER_OTLP_HEADERS should be a list of key=value pairs separated by commas.")]
+    InvalidOtelHeaders,
+    #[error(transparent)]
+    UnknownEcosystem(#[from] turborepo_repository::discovery::UnknownEcosystem),
     #[error(transparent)]
     #[diagnostic(transparent)]
     TurboJsonParseError(#[from] turbo_json::parser::Error),
@@ -134,6 +225,7 @@ macro_rules! create_builder {
 const DEFAULT_API_URL: &str = "https://vercel.com/api";
 const DEFAULT_LOGIN_URL: &str = "https://vercel.com";
 const DEFAULT_TIMEOUT: u64 = 30;
+const DEFAULT_S3_REGION: &str = "us-east-1";
 
 // We intentionally don't derive Serialize so that different parts
 // of the code that want to display the config can tune how they
@@ -163,6 +255,17 @@ pub struct ConfigurationOptions {
     pub(crate) timeout: Option<u64>,
     pub(crate) enabled: Option<bool>,
     pub(crate) spaces_id: Option<String>,
+    pub(crate) cache_max_size: Option<String>,
+    pub(crate) cache_max_age: Option<String>,
+    pub(crate) remote_cache_backend: Option<String>,
+    pub(crate) s3_bucket: Option<String>,
+    pub(crate) s3_region: Option<String>,
+    pub(crate) s3_endpoint: Option<String>,
+    pub(crate) remote_cache_dir: Option<String>,
+    pub(crate) otel_endpoint: Option<String>,
+    pub(crate) otel_protocol: Option<String>,
+    pub(crate) otel_headers: Option<BTreeMap<String, String>>,
+    pub(crate) package_discovery: Option<Vec<String>>,
 }
 
 #[derive(Default)]
@@ -219,6 +322,123 @@ impl ConfigurationOptions {
     pub fn spaces_id(&self) -> Option<&str> {
         self.spaces_id.as_deref()
     }
+
+    /// Maximum size of the local cache in bytes
+    pub fn cache_max_size(&self) -> Result<Option<u64>, Error> {
+        non_empty_str(self.cache_max_size.as_deref())
+            .map(|size| {
+                parse_byte_size(size).ok_or_else(|| Error::InvalidCacheMaxSize(size.to_string()))
+            })
+            .transpose()
+    }
+
+    /// Maximum time since a local cache entry was last used
+    pub fn cache_max_age(&self) -> Result<Option<Duration>, Error> {
+        non_empty_str(self.cache_max_age.as_deref())
+            .map(|age| {
+                humantime::parse_duration(age).map_err(|source| Error::InvalidCacheMaxAge {
+                    value: age.to_string(),
+                    source,
+                })
+            })
+            .transpose()
+    }
+
+    pub fn remote_cache_backend(&self) -> Result<RemoteCacheBackendOpts, Error> {
+        match non_empty_str(self.remote_cache_backend.as_deref()) {
+            None | Some("vercel") => Ok(RemoteCacheBackendOpts::Vercel),
+            Some("s3") => {
+                let bucket =
+                    non_empty_str(self.s3_bucket.as_deref()).ok_or(Error::MissingS3Bucket)?;
+                Ok(RemoteCacheBackendOpts::S3(S3Opts {
+                    bucket: bucket.to_string(),
+                    region: non_empty_str(self.s3_region.as_deref())
+                        .unwrap_or(DEFAULT_S3_REGION)
+                        .to_string(),
+                    endpoint: non_empty_str(self.s3_endpoint.as_deref()).map(|s| s.to_string()),
+                    timeout: self.timeout(),
+                }))
+            }
+            Some("directory") => {
+                let directory = non_empty_str(self.remote_cache_dir.as_deref())
+                    .ok_or(Error::MissingRemoteCacheDir)?;
+                Ok(RemoteCacheBackendOpts::Directory(directory.into()))
+            }
+            Some(other) => Err(Error::InvalidRemoteCacheBackend(other.to_string())),
+        }
+    }
+
+    pub fn fs_cache_limits(&self) -> Result<FSCacheLimits, Error> {
+        Ok(FSCacheLimits {
+            max_size: self.cache_max_size()?,
+            max_age: self.cache_max_age()?,
+        })
+    }
+
+    /// Where runs should be exported to over OTLP, if anywhere
+    pub fn otel(&self) -> Result<Option<OtelOpts>, Error> {
+        let Some(endpoint) = non_empty_str(self.otel_endpoint.as_deref()) else {
+            return Ok(None);
+        };
+        let protocol = match non_empty_str(self.otel_protocol.as_deref()) {
+            None | Some("http/protobuf") => OtelProtocol::HttpProtobuf,
+            Some("grpc") => OtelProtocol::Grpc,
+            Some(other) => return Err(Error::InvalidOtelProtocol(other.to_string())),
+        };
+        Ok(Some(OtelOpts {
+            endpoint: endpoint.to_string(),
+            protocol,
+            headers: self.otel_headers.clone().unwrap_or_default(),
+        }))
+    }
+
+    /// The non-JavaScript ecosystems whose packages are added to the package
+    /// graph
+    pub fn package_discovery(&self) -> Result<Vec<Ecosystem>, Error> {
+        Ok(self
+            .package_discovery
+            .iter()
+            .flatten()
+            .map(|ecosystem| ecosystem.parse())
+            .collect::<Result<_, _>>()?)
+    }
+}
+
+// Parses headers in the format OpenTelemetry SDKs use for
+// OTEL_EXPORTER_OTLP_HEADERS: "key1=value1,key2=value2"
+fn parse_otel_headers(headers: &str) -> Result<BTreeMap<String, String>, Error> {
+    headers
+        .split(',')
+        .filter(|pair| !pair.trim().is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').ok_or(Error::InvalidOtelHeaders)?;
+            let key = key.trim();
+            if key.is_empty() {
+                return Err(Error::InvalidOtelHeaders);
+            }
+            Ok((key.to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+// Parses sizes like "500MB" or "1.5GB" into bytes. Units are binary
+// (1KB = 1024 bytes) and a bare number is treated as bytes.
+fn parse_byte_size(size: &str) -> Option<u64> {
+    let size = size.trim();
+    let unit_start = size
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(size.len());
+    let (number, unit) = size.split_at(unit_start);
+    let number = number.parse::<f64>().ok()?;
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        "T" | "TB" | "TIB" => 1 << 40,
+        _ => return None,
+    };
+    Some((number * multiplier as f64) as u64)
 }
 
 // Maps Some("") to None to emulate how Go handles empty strings
@@ -258,6 +478,21 @@ impl ResolvedConfigurationOptions for RawTurboJson {
             .experimental_spaces
             .and_then(|spaces| spaces.id)
             .map(|spaces_id| spaces_id.into());
+        if let Some(local_cache) = self.local_cache {
+            opts.cache_max_size = local_cache.max_size.map(|size| size.into());
+            opts.cache_max_age = local_cache.max_age.map(|age| age.into());
+        }
+        if let Some(otel) = self.otel {
+            opts.otel_endpoint = otel.endpoint.map(|endpoint| endpoint.into());
+            opts.otel_protocol = otel.protocol.map(|protocol| protocol.into());
+            opts.otel_headers = otel.headers;
+        }
+        opts.package_discovery = self.package_discovery.map(|ecosystems| {
+            ecosystems
+                .into_iter()
+                .map(|ecosystem| ecosystem.into())
+                .collect()
+        });
         Ok(opts)
     }
 }
@@ -285,6 +520,28 @@ fn get_env_var_config(
     turbo_mapping.insert(OsString::from("turbo_teamid"), "team_id");
     turbo_mapping.insert(OsString::from("turbo_token"), "token");
     turbo_mapping.insert(OsString::from("turbo_remote_cache_timeout"), "timeout");
+    turbo_mapping.insert(OsString::from("turbo_cache_max_size"), "cache_max_size");
+    turbo_mapping.insert(OsString::from("turbo_cache_max_age"), "cache_max_age");
+    turbo_mapping.insert(
+        OsString::from("turbo_remote_cache_backend"),
+        "remote_cache_backend",
+    );
+    turbo_mapping.insert(OsString::from("turbo_remote_cache_s3_bucket"), "s3_bucket");
+    turbo_mapping.insert(OsString::from("turbo_remote_cache_s3_region"), "s3_region");
+    turbo_mapping.insert(
+        OsString::from("turbo_remote_cache_s3_endpoint"),
+        "s3_endpoint",
+    );
+    turbo_mapping.insert(OsString::from("turbo_remote_cache_dir"), "remote_cache_dir");
+    turbo_mapping.insert(
+        OsString::from("otel_exporter_otlp_endpoint"),
+        "otel_endpoint",
+    );
+    turbo_mapping.insert(
+        OsString::from("otel_exporter_otlp_protocol"),
+        "otel_protocol",
+    );
+    turbo_mapping.insert(OsString::from("otel_exporter_otlp_headers"), "otel_headers");
 
     // We do not enable new config sources:
     // turbo_mapping.insert(String::from("turbo_signature"), "signature"); // new
@@ -354,6 +611,11 @@ fn get_env_var_config(
         None
     };
 
+    let otel_headers = output_map
+        .get("otel_headers")
+        .map(|headers| parse_otel_headers(headers))
+        .transpose()?;
+
     // We currently don't pick up a Spaces ID via env var, we likely won't
     // continue using the Spaces name, we can add an env var when we have the
     // name we want to stick with.
@@ -374,6 +636,19 @@ fn get_env_var_config(
         // Processed numbers
         timeout,
         spaces_id,
+
+        // Validated when the local cache is constructed
+        cache_max_size: output_map.get("cache_max_size").cloned(),
+        cache_max_age: output_map.get("cache_max_age").cloned(),
+        remote_cache_backend: output_map.get("remote_cache_backend").cloned(),
+        s3_bucket: output_map.get("s3_bucket").cloned(),
+        s3_region: output_map.get("s3_region").cloned(),
+        s3_endpoint: output_map.get("s3_endpoint").cloned(),
+        remote_cache_dir: output_map.get("remote_cache_dir").cloned(),
+        otel_endpoint: output_map.get("otel_endpoint").cloned(),
+        otel_protocol: output_map.get("otel_protocol").cloned(),
+        otel_headers,
+        package_discovery: None,
     };
 
     Ok(output)
@@ -418,6 +693,17 @@ fn get_override_env_var_config(
         enabled: None,
         timeout: None,
         spaces_id: None,
+        cache_max_size: None,
+        cache_max_age: None,
+        remote_cache_backend: None,
+        s3_bucket: None,
+        s3_region: None,
+        s3_endpoint: None,
+        remote_cache_dir: None,
+        otel_endpoint: None,
+        otel_protocol: None,
+        otel_headers: None,
+        package_discovery: None,
     };
 
     Ok(output)
@@ -507,6 +793,8 @@ impl TurborepoConfigBuilder {
     create_builder!(with_enabled, enabled, Option<bool>);
     create_builder!(with_preflight, preflight, Option<bool>);
     create_builder!(with_timeout, timeout, Option<u64>);
+    create_builder!(with_cache_max_size, cache_max_size, Option<String>);
+    create_builder!(with_cache_max_age, cache_max_age, Option<String>);
 
     pub fn build(&self) -> Result<ConfigurationOptions, Error> {
         // Priority, from least significant to most significant:
@@ -591,6 +879,39 @@ impl TurborepoConfigBuilder {
                     if let Some(spaces_id) = current_source_config.spaces_id {
                         acc.spaces_id = Some(spaces_id);
                     }
+                    if let Some(cache_max_size) = current_source_config.cache_max_size {
+                        acc.cache_max_size = Some(cache_max_size);
+                    }
+                    if let Some(cache_max_age) = current_source_config.cache_max_age {
+                        acc.cache_max_age = Some(cache_max_age);
+                    }
+                    if let Some(remote_cache_backend) = current_source_config.remote_cache_backend {
+                        acc.remote_cache_backend = Some(remote_cache_backend);
+                    }
+                    if let Some(s3_bucket) = current_source_config.s3_bucket {
+                        acc.s3_bucket = Some(s3_bucket);
+                    }
+                    if let Some(s3_region) = current_source_config.s3_region {
+                        acc.s3_region = Some(s3_region);
+                    }
+                    if let Some(s3_endpoint) = current_source_config.s3_endpoint {
+                        acc.s3_endpoint = Some(s3_endpoint);
+                    }
+                    if let Some(remote_cache_dir) = current_source_config.remote_cache_dir {
+                        acc.remote_cache_dir = Some(remote_cache_dir);
+                    }
+                    if let Some(otel_endpoint) = current_source_config.otel_endpoint {
+                        acc.otel_endpoint = Some(otel_endpoint);
+                    }
+                    if let Some(otel_protocol) = current_source_config.otel_protocol {
+                        acc.otel_protocol = Some(otel_protocol);
+                    }
+                    if let Some(otel_headers) = current_source_config.otel_headers {
+                        acc.otel_headers = Some(otel_headers);
+                    }
+                    if let Some(package_discovery) = current_source_config.package_discovery {
+                        acc.package_discovery = Some(package_discovery);
+                    }
 
                     acc
                 })
@@ -601,14 +922,21 @@ impl TurborepoConfigBuilder {
 
 #[cfg(test)]
 mod test {
-    use std::{collections::HashMap, ffi::OsString};
+    use std::{collections::HashMap, ffi::OsString, time::Duration};
 
     use tempfile::TempDir;
+    use test_case::test_case;
     use turbopath::AbsoluteSystemPathBuf;
-
-    use crate::config::{
-        get_env_var_config, get_override_env_var_config, ConfigurationOptions,
-        TurborepoConfigBuilder, DEFAULT_API_URL, DEFAULT_LOGIN_URL, DEFAULT_TIMEOUT,
+    use turborepo_cache::{s3::S3Opts, RemoteCacheBackendOpts};
+    use turborepo_repository::discovery::Ecosystem;
+
+    use crate::{
+        config::{
+            get_env_var_config, get_override_env_var_config, parse_byte_size, parse_otel_headers,
+            ConfigurationOptions, Error, TurborepoConfigBuilder, DEFAULT_API_URL,
+            DEFAULT_LOGIN_URL, DEFAULT_TIMEOUT,
+        },
+        run::otel::{OtelOpts, OtelProtocol},
     };
 
     #[test]
@@ -624,6 +952,8 @@ mod test {
         assert!(!defaults.preflight());
         assert_eq!(defaults.timeout(), DEFAULT_TIMEOUT);
         assert_eq!(defaults.spaces_id(), None);
+        assert_eq!(defaults.cache_max_size().unwrap(), None);
+        assert_eq!(defaults.cache_max_age().unwrap(), None);
     }
 
     #[test]
@@ -743,4 +1073,245 @@ mod test {
         assert_eq!(config.token().unwrap(), vercel_artifacts_token);
         assert_eq!(config.spaces_id().unwrap(), "my-spaces-id");
     }
+
+    #[test]
+    fn test_local_cache_layering() {
+        let tmp_dir = TempDir::new().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp_dir.path()).unwrap();
+        let global_config_path = AbsoluteSystemPathBuf::try_from(
+            TempDir::new().unwrap().path().join("nonexistent.json"),
+        )
+        .unwrap();
+
+        repo_root
+            .join_component("turbo.json")
+            .create_with_contents(r#"{"localCache": {"maxSize": "10GB", "maxAge": "7d"}}"#)
+            .unwrap();
+
+        let mut env: HashMap<OsString, OsString> = HashMap::new();
+        env.insert("turbo_cache_max_age".into(), "12h".into());
+
+        let builder = TurborepoConfigBuilder {
+            repo_root,
+            override_config: Default::default(),
+            global_config_path: Some(global_config_path),
+            environment: env,
+        };
+
+        let config = builder.build().unwrap();
+        assert_eq!(
+            config.cache_max_size().unwrap(),
+            Some(10 * 1024 * 1024 * 1024)
+        );
+        assert_eq!(
+            config.cache_max_age().unwrap(),
+            Some(Duration::from_secs(12 * 60 * 60))
+        );
+    }
+
+    #[test_case("1024", Some(1024) ; "bare number")]
+    #[test_case("500MB", Some(500 * 1024 * 1024) ; "megabytes")]
+    #[test_case("1.5 gb", Some(1536 * 1024 * 1024) ; "fractional gigabytes")]
+    #[test_case("2TiB", Some(2 * 1024 * 1024 * 1024 * 1024) ; "tebibytes")]
+    #[test_case("10 parsecs", None ; "unknown unit")]
+    #[test_case("GB", None ; "missing number")]
+    fn test_parse_byte_size(size: &str, expected: Option<u64>) {
+        assert_eq!(parse_byte_size(size), expected);
+    }
+
+    #[test]
+    fn test_invalid_cache_limits() {
+        let config = ConfigurationOptions {
+            cache_max_size: Some("lots".into()),
+            cache_max_age: Some("forever".into()),
+            ..Default::default()
+        };
+        assert!(config.cache_max_size().is_err());
+        assert!(config.cache_max_age().is_err());
+    }
+
+    #[test]
+    fn test_remote_cache_backend() {
+        let tmp_dir = TempDir::new().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp_dir.path()).unwrap();
+        let global_config_path = AbsoluteSystemPathBuf::try_from(
+            TempDir::new().unwrap().path().join("nonexistent.json"),
+        )
+        .unwrap();
+
+        repo_root
+            .join_component("turbo.json")
+            .create_with_contents(
+                r#"{"remoteCache": {"backend": "s3", "s3Bucket": "turbo", "s3Region": "eu-west-1"}}"#,
+            )
+            .unwrap();
+
+        let mut env: HashMap<OsString, OsString> = HashMap::new();
+        env.insert(
+            "turbo_remote_cache_s3_endpoint".into(),
+            "http://localhost:9000".into(),
+        );
+
+        let builder = TurborepoConfigBuilder {
+            repo_root,
+            override_config: Default::default(),
+            global_config_path: Some(global_config_path),
+            environment: env,
+        };
+
+        let config = builder.build().unwrap();
+        assert_eq!(
+            config.remote_cache_backend().unwrap(),
+            RemoteCacheBackendOpts::S3(S3Opts {
+                bucket: "turbo".to_string(),
+                region: "eu-west-1".to_string(),
+                endpoint: Some("http://localhost:9000".to_string()),
+                timeout: DEFAULT_TIMEOUT,
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_remote_cache_backend() {
+        let defaults = ConfigurationOptions::default();
+        assert_eq!(
+            defaults.remote_cache_backend().unwrap(),
+            RemoteCacheBackendOpts::Vercel
+        );
+
+        let directory = ConfigurationOptions {
+            remote_cache_backend: Some("directory".into()),
+            remote_cache_dir: Some("/mnt/turbo".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            directory.remote_cache_backend().unwrap(),
+            RemoteCacheBackendOpts::Directory("/mnt/turbo".into())
+        );
+
+        let missing_bucket = ConfigurationOptions {
+            remote_cache_backend: Some("s3".into()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            missing_bucket.remote_cache_backend(),
+            Err(Error::MissingS3Bucket)
+        ));
+
+        let unknown = ConfigurationOptions {
+            remote_cache_backend: Some("ftp".into()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            unknown.remote_cache_backend(),
+            Err(Error::InvalidRemoteCacheBackend(_))
+        ));
+    }
+
+    #[test]
+    fn test_otel_layering() {
+        let tmp_dir = TempDir::new().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp_dir.path()).unwrap();
+        let global_config_path = AbsoluteSystemPathBuf::try_from(
+            TempDir::new().unwrap().path().join("nonexistent.json"),
+        )
+        .unwrap();
+
+        repo_root
+            .join_component("turbo.json")
+            .create_with_contents(
+                r#"{"otel": {"endpoint": "http://localhost:4318", "headers": {"x-team": "web"}}}"#,
+            )
+            .unwrap();
+
+        let mut env: HashMap<OsString, OsString> = HashMap::new();
+        env.insert(
+            "otel_exporter_otlp_endpoint".into(),
+            "http://collector:4317".into(),
+        );
+        env.insert("otel_exporter_otlp_protocol".into(), "grpc".into());
+        env.insert(
+            "otel_exporter_otlp_headers".into(),
+            "authorization=Bearer abc, x-team=docs".into(),
+        );
+
+        let builder = TurborepoConfigBuilder {
+            repo_root,
+            override_config: Default::default(),
+            global_config_path: Some(global_config_path),
+            environment: env,
+        };
+
+        let config = builder.build().unwrap();
+        assert_eq!(
+            config.otel().unwrap(),
+            Some(OtelOpts {
+                endpoint: "http://collector:4317".to_string(),
+                protocol: OtelProtocol::Grpc,
+                headers: [
+                    ("authorization".to_string(), "Bearer abc".to_string()),
+                    ("x-team".to_string(), "docs".to_string()),
+                ]
+                .into_iter()
+                .collect(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_otel() {
+        assert_eq!(ConfigurationOptions::default().otel().unwrap(), None);
+
+        let unknown_protocol = ConfigurationOptions {
+            otel_endpoint: Some("http://localhost:4318".into()),
+            otel_protocol: Some("http/json".into()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            unknown_protocol.otel(),
+            Err(Error::InvalidOtelProtocol(_))
+        ));
+
+        assert!(matches!(
+            parse_otel_headers("authorization"),
+            Err(Error::InvalidOtelHeaders)
+        ));
+    }
+
+    #[test]
+    fn test_package_discovery() {
+        let tmp_dir = TempDir::new().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp_dir.path()).unwrap();
+        let global_config_path = AbsoluteSystemPathBuf::try_from(
+            TempDir::new().unwrap().path().join("nonexistent.json"),
+        )
+        .unwrap();
+
+        repo_root
+            .join_component("turbo.json")
+            .create_with_contents(r#"{"packageDiscovery": ["cargo", "python"]}"#)
+            .unwrap();
+
+        let builder = TurborepoConfigBuilder {
+            repo_root,
+            override_config: Default::default(),
+            global_config_path: Some(global_config_path),
+            environment: HashMap::new(),
+        };
+
+        let config = builder.build().unwrap();
+        assert_eq!(
+            config.package_discovery().unwrap(),
+            vec![Ecosystem::Cargo, Ecosystem::Python]
+        );
+
+        let unknown = ConfigurationOptions {
+            package_discovery: Some(vec!["maven".into()]),
+            ..Default::default()
+        };
+        assert!(matches!(
+            unknown.package_discovery(),
+            Err(Error::UnknownEcosystem(_))
+        ));
+    }
 }
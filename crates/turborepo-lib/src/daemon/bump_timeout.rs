@@ -29,6 +29,11 @@ impl BumpTimeout {
         }
     }
 
+    /// How far each reset moves the deadline past the current time
+    pub fn increment(&self) -> Duration {
+        self.increment
+    }
+
     pub fn duration(&self) -> Duration {
         Duration::from_millis(self.deadline.load(Ordering::Relaxed))
     }
@@ -4,7 +4,8 @@ use globwalk::ValidatedGlob;
 use thiserror::Error;
 use tonic::{Code, Status};
 use tracing::info;
-use turbopath::AbsoluteSystemPathBuf;
+use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPath, RelativeUnixPathBuf};
+use turborepo_scm::package_deps::GitHashes;
 
 use super::{
     connector::{DaemonConnector, DaemonConnectorError},
@@ -133,6 +134,47 @@ impl<T> DaemonClient<T> {
 
         Ok(response)
     }
+
+    /// Get the hashes of a package's input files. Returns an `Unavailable`
+    /// error if the daemon hasn't hashed them since they last changed.
+    pub async fn get_file_hashes(
+        &mut self,
+        package_path: &AnchoredSystemPath,
+        inputs: &[String],
+    ) -> Result<GitHashes, DaemonError> {
+        self.client
+            .get_file_hashes(proto::GetFileHashesRequest {
+                package_path: package_path.to_string(),
+                input_globs: inputs.to_vec(),
+            })
+            .await?
+            .into_inner()
+            .file_hashes
+            .into_iter()
+            .map(|(path, hash)| {
+                let path =
+                    RelativeUnixPathBuf::new(path).map_err(|_| DaemonError::MalformedResponse)?;
+                Ok((path, hash))
+            })
+            .collect()
+    }
+
+    /// Subscribe to the daemon's file watcher. Returns once the daemon is
+    /// watching, so no changes made after this call are missed.
+    pub async fn watch_files(
+        &mut self,
+    ) -> Result<tonic::Streaming<proto::WatchFilesResponse>, DaemonError> {
+        let mut stream = self
+            .client
+            .watch_files(proto::WatchFilesRequest {})
+            .await?
+            .into_inner();
+        stream
+            .message()
+            .await?
+            .ok_or_else(|| DaemonError::Unavailable("file watching stopped".to_string()))?;
+        Ok(stream)
+    }
 }
 
 impl DaemonClient<DaemonConnector> {
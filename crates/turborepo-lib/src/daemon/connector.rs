@@ -628,6 +628,22 @@ mod test {
         ) -> Result<tonic::Response<proto::DiscoverPackagesResponse>, tonic::Status> {
             unimplemented!()
         }
+
+        async fn get_file_hashes(
+            &self,
+            _req: tonic::Request<proto::GetFileHashesRequest>,
+        ) -> Result<tonic::Response<proto::GetFileHashesResponse>, tonic::Status> {
+            unimplemented!()
+        }
+
+        type WatchFilesStream = futures::stream::Empty<tonic::Result<proto::WatchFilesResponse>>;
+
+        async fn watch_files(
+            &self,
+            _req: tonic::Request<proto::WatchFilesRequest>,
+        ) -> Result<tonic::Response<Self::WatchFilesStream>, tonic::Status> {
+            unimplemented!()
+        }
     }
 
     #[tokio::test]
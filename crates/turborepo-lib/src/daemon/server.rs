@@ -14,6 +14,7 @@
 
 use std::{
     collections::{HashMap, HashSet},
+    pin::Pin,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc, Mutex,
@@ -21,26 +22,28 @@ use std::{
     time::{Duration, Instant},
 };
 
-use futures::Future;
+use futures::{Future, Stream};
 use semver::Version;
 use thiserror::Error;
 use tokio::{
     select,
-    sync::{mpsc, oneshot, watch},
+    sync::{broadcast::error::RecvError, mpsc, oneshot, watch},
 };
 use tonic::transport::{NamedService, Server};
 use tower::ServiceBuilder;
 use tracing::{error, info, trace, warn};
-use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
 use turborepo_filewatch::{
     cookies::CookieWriter,
     globwatcher::{Error as GlobWatcherError, GlobError, GlobSet, GlobWatcher},
+    hash_watcher::{Error as HashWatcherError, FileHashes, HashSpec, HashWatcher},
     package_watcher::PackageWatcher,
     FileSystemWatcher, WatchError,
 };
 use turborepo_repository::discovery::{
     DiscoveryResponse, LocalPackageDiscoveryBuilder, PackageDiscovery, PackageDiscoveryBuilder,
 };
+use turborepo_scm::SCM;
 
 use super::{bump_timeout::BumpTimeout, endpoint::SocketOpenError, proto, Paths};
 use crate::daemon::{bump_timeout_layer::BumpTimeoutLayer, endpoint::listen_socket};
@@ -60,6 +63,7 @@ pub struct FileWatching {
     _watcher: FileSystemWatcher,
     pub glob_watcher: GlobWatcher,
     pub package_watcher: PackageWatcher,
+    pub hash_watcher: HashWatcher,
 }
 
 #[derive(Debug, Error)]
@@ -70,6 +74,10 @@ enum RpcError {
     InvalidGlob(#[from] GlobError),
     #[error("globwatching failed: {0}")]
     GlobWatching(#[from] GlobWatcherError),
+    #[error("file hashing failed: {0}")]
+    FileHashing(#[from] HashWatcherError),
+    #[error("invalid package path: {0}")]
+    InvalidPackagePath(#[from] turbopath::PathError),
     #[error("filewatching unavailable")]
     NoFileWatching,
 }
@@ -82,6 +90,8 @@ impl From<RpcError> for tonic::Status {
             }
             RpcError::InvalidGlob(e) => tonic::Status::invalid_argument(e.to_string()),
             RpcError::GlobWatching(e) => tonic::Status::unavailable(e.to_string()),
+            RpcError::FileHashing(e) => tonic::Status::unavailable(e.to_string()),
+            RpcError::InvalidPackagePath(e) => tonic::Status::invalid_argument(e.to_string()),
             RpcError::NoFileWatching => tonic::Status::unavailable("filewatching unavailable"),
         }
     }
@@ -94,7 +104,18 @@ async fn start_filewatching<PD: PackageDiscovery + Send + 'static>(
 ) -> Result<(), WatchError> {
     let watcher = FileSystemWatcher::new_with_default_cookie_dir(&repo_root).await?;
     let cookie_writer = CookieWriter::new(watcher.cookie_dir(), Duration::from_millis(100));
-    let glob_watcher = GlobWatcher::new(&repo_root, cookie_writer, watcher.subscribe());
+    let glob_watcher = GlobWatcher::new(&repo_root, cookie_writer.clone(), watcher.subscribe());
+    let scm = SCM::new(&repo_root);
+    let hash_root = repo_root.clone();
+    let hash_watcher = HashWatcher::new(
+        &repo_root,
+        cookie_writer,
+        watcher.subscribe(),
+        move |spec: &HashSpec| {
+            scm.get_package_file_hashes(&hash_root, &spec.package_path, &spec.inputs, None)
+                .map_err(|e| e.to_string())
+        },
+    );
     let package_watcher =
         PackageWatcher::new(repo_root.clone(), watcher.subscribe(), backup_discovery)
             .await
@@ -105,6 +126,7 @@ async fn start_filewatching<PD: PackageDiscovery + Send + 'static>(
         _watcher: watcher,
         glob_watcher,
         package_watcher,
+        hash_watcher,
     })));
     Ok(())
 }
@@ -237,12 +259,16 @@ where
 
         // when one of these futures complete, let the server gracefully shutdown
         let (grpc_shutdown_tx, shutdown_reason) = oneshot::channel();
+        // Streaming RPCs never finish on their own, so they need to be told to
+        // close for the graceful shutdown to complete.
+        let (close_streams_tx, close_streams) = watch::channel(false);
         let shutdown_fut = async move {
             select! {
                 _ = shutdown_signal.recv() => grpc_shutdown_tx.send(CloseReason::Shutdown).ok(),
                 _ = timeout_fut => grpc_shutdown_tx.send(CloseReason::Timeout).ok(),
                 reason = external_shutdown => grpc_shutdown_tx.send(reason).ok(),
             };
+            let _ = close_streams_tx.send(true);
         };
 
         // Run the actual service. It takes ownership of the struct given to it,
@@ -254,6 +280,9 @@ where
             times_saved: Arc::new(Mutex::new(HashMap::new())),
             start_time: Instant::now(),
             log_file: paths.log_file.clone(),
+            repo_root: repo_root.clone(),
+            bump_timeout: bump_timeout.clone(),
+            close_streams,
         };
         let server_fut = {
             let service = ServiceBuilder::new()
@@ -297,8 +326,14 @@ struct TurboGrpcServiceInner {
     times_saved: Arc<Mutex<HashMap<String, u64>>>,
     start_time: Instant,
     log_file: AbsoluteSystemPathBuf,
+    repo_root: AbsoluteSystemPathBuf,
+    bump_timeout: Arc<BumpTimeout>,
+    close_streams: watch::Receiver<bool>,
 }
 
+type WatchFilesStream =
+    Pin<Box<dyn Stream<Item = Result<proto::WatchFilesResponse, tonic::Status>> + Send>>;
+
 impl TurboGrpcServiceInner {
     async fn trigger_shutdown(&self) {
         info!("triggering shutdown");
@@ -353,6 +388,79 @@ impl TurboGrpcServiceInner {
             package_manager: fw.package_watcher.get_package_manager().await,
         })
     }
+
+    async fn get_file_hashes(
+        &self,
+        package_path: String,
+        inputs: Vec<String>,
+    ) -> Result<Arc<FileHashes>, RpcError> {
+        let spec = HashSpec {
+            package_path: AnchoredSystemPathBuf::from_raw(package_path)?,
+            inputs,
+        };
+        let fw = self.wait_for_filewatching().await?;
+        // Runs fall back to hashing locally, so rather than holding them up
+        // on a cache miss we hash in the background for the next run.
+        Ok(fw
+            .hash_watcher
+            .get_cached_file_hashes(spec, REQUEST_TIMEOUT)
+            .await?)
+    }
+
+    fn watch_files(&self) -> WatchFilesStream {
+        let watcher_rx = self.watcher_rx.clone();
+        let repo_root = self.repo_root.clone();
+        let bump_timeout = self.bump_timeout.clone();
+        let mut close_streams = self.close_streams.clone();
+        Box::pin(async_stream::try_stream! {
+            // Filewatching may still be starting up if the client just spawned
+            // the daemon, so we wait for it as long as the root watcher does.
+            let mut events = {
+                let fw = wait_for_filewatching(watcher_rx, Duration::from_secs(5)).await?;
+                fw._watcher.subscribe()
+            };
+            yield proto::WatchFilesResponse::default();
+
+            // An open stream means the client is still using the daemon, even
+            // if nothing changes, so it mustn't time out while we're waiting
+            let mut keep_alive = tokio::time::interval(
+                (bump_timeout.increment() / 2).max(Duration::from_millis(1)),
+            );
+            loop {
+                let event = select! {
+                    _ = close_streams.wait_for(|close| *close) => break,
+                    _ = keep_alive.tick() => {
+                        bump_timeout.reset();
+                        continue;
+                    }
+                    event = events.recv() => event,
+                };
+                let response = match event {
+                    Ok(Ok(event)) if event.kind.is_access() => continue,
+                    Ok(Ok(event)) => proto::WatchFilesResponse {
+                        paths: event
+                            .paths
+                            .into_iter()
+                            .filter_map(|path| AbsoluteSystemPathBuf::try_from(path).ok())
+                            .filter_map(|path| repo_root.anchor(path).ok())
+                            .map(|path| path.to_string())
+                            .collect(),
+                        rescan: false,
+                    },
+                    Ok(Err(err)) => {
+                        warn!("file watching error: {err}");
+                        continue;
+                    }
+                    Err(RecvError::Lagged(_)) => proto::WatchFilesResponse {
+                        paths: Vec::new(),
+                        rescan: true,
+                    },
+                    Err(RecvError::Closed) => break,
+                };
+                yield response;
+            }
+        })
+    }
 }
 
 async fn wait_for_filewatching(
@@ -512,6 +620,31 @@ impl proto::turbod_server::Turbod for TurboGrpcServiceInner {
             package_manager: proto::PackageManager::from(resp.package_manager).into(),
         }))
     }
+
+    async fn get_file_hashes(
+        &self,
+        request: tonic::Request<proto::GetFileHashesRequest>,
+    ) -> Result<tonic::Response<proto::GetFileHashesResponse>, tonic::Status> {
+        let inner = request.into_inner();
+        let file_hashes = self
+            .get_file_hashes(inner.package_path, inner.input_globs)
+            .await?;
+        Ok(tonic::Response::new(proto::GetFileHashesResponse {
+            file_hashes: file_hashes
+                .iter()
+                .map(|(path, hash)| (path.to_string(), hash.clone()))
+                .collect(),
+        }))
+    }
+
+    type WatchFilesStream = WatchFilesStream;
+
+    async fn watch_files(
+        &self,
+        _request: tonic::Request<proto::WatchFilesRequest>,
+    ) -> Result<tonic::Response<Self::WatchFilesStream>, tonic::Status> {
+        Ok(tonic::Response::new(self.watch_files()))
+    }
 }
 
 /// Determine whether a server can serve a client's request based on its
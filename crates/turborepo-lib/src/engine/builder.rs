@@ -179,13 +179,7 @@ impl<'a> EngineBuilder<'a> {
                     task_id: task_id.to_string(),
                 });
             }
-            let raw_task_definition = RawTaskDefinition::from_iter(self.task_definition_chain(
-                &mut turbo_jsons,
-                &task_id,
-                &task_id.as_non_workspace_task_name(),
-            )?);
-
-            let task_definition = TaskDefinition::try_from(raw_task_definition)?;
+            let task_definition = self.resolve_task_definition(&mut turbo_jsons, &task_id)?;
 
             // Skip this iteration of the loop if we've already seen this taskID
             if visited.contains(&task_id) {
@@ -261,11 +255,42 @@ impl<'a> EngineBuilder<'a> {
 
         graph::validate_graph(&engine.task_graph)?;
 
+        if let Some(root_turbo_json) = turbo_jsons.get(&PackageName::Root) {
+            engine.resource_pools = root_turbo_json.resources.clone();
+        }
+
         Ok(engine.seal())
     }
 
+    /// Resolves the definition of a single task without building the rest of
+    /// the task graph, merging the root and workspace turbo.json entries the
+    /// same way `build` does.
+    pub fn task_definition(mut self, task_id: &TaskId) -> Result<TaskDefinition, Error> {
+        let mut turbo_jsons = self.turbo_jsons.take().unwrap_or_default();
+        self.resolve_task_definition(&mut turbo_jsons, task_id)
+    }
+
     // Helper methods used when building the engine
 
+    fn resolve_task_definition(
+        &self,
+        turbo_jsons: &mut HashMap<PackageName, TurboJson>,
+        task_id: &TaskId,
+    ) -> Result<TaskDefinition, Error> {
+        let raw_task_definition = RawTaskDefinition::from_iter(self.task_definition_chain(
+            turbo_jsons,
+            task_id,
+            &task_id.as_non_workspace_task_name(),
+        )?);
+
+        // The root turbo.json was loaded while building the definition chain
+        if let Some(root_turbo_json) = turbo_jsons.get(&PackageName::Root) {
+            raw_task_definition.validate_resources(&root_turbo_json.resources)?;
+        }
+
+        Ok(TaskDefinition::try_from(raw_task_definition)?)
+    }
+
     fn has_task_definition(
         &self,
         turbo_jsons: &mut HashMap<PackageName, TurboJson>,
@@ -614,6 +639,57 @@ mod test {
         assert_eq!(has_def, expected);
     }
 
+    #[test_case("a#build", &["special"], &[] ; "package task in root")]
+    #[test_case("b#build", &["outer"], &["dist/**"] ; "merged with root")]
+    #[test_case("c#build", &["primary"], &["dist/**"] ; "root only")]
+    fn test_resolve_task_definition(
+        task_id: &'static str,
+        expected_inputs: &[&str],
+        expected_outputs: &[&str],
+    ) {
+        let repo_root_dir = TempDir::new("repo").unwrap();
+        let repo_root = AbsoluteSystemPathBuf::new(repo_root_dir.path().to_str().unwrap()).unwrap();
+        let package_graph = mock_package_graph(
+            &repo_root,
+            package_jsons! {
+                repo_root,
+                "a" => [],
+                "b" => [],
+                "c" => ["a", "b"]
+            },
+        );
+        let turbo_jsons = vec![
+            (
+                PackageName::Root,
+                turbo_json(json!({
+                    "pipeline": {
+                        "build": { "inputs": ["primary"], "outputs": ["dist/**"] },
+                        "a#build": { "inputs": ["special"] },
+                    }
+                })),
+            ),
+            (
+                PackageName::from("b"),
+                turbo_json(json!({
+                    "extends": ["//"],
+                    "pipeline": {
+                        "build": { "inputs": ["outer"]},
+                    }
+                })),
+            ),
+        ]
+        .into_iter()
+        .collect();
+
+        let task_definition = EngineBuilder::new(&repo_root, &package_graph, false)
+            .with_turbo_jsons(Some(turbo_jsons))
+            .task_definition(&TaskId::try_from(task_id).unwrap())
+            .unwrap();
+
+        assert_eq!(task_definition.inputs, expected_inputs);
+        assert_eq!(task_definition.outputs.inclusions, expected_outputs);
+    }
+
     macro_rules! deps {
         {} => {
             HashMap::new()
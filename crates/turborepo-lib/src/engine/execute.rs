@@ -1,4 +1,7 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 
 use futures::{stream::FuturesUnordered, StreamExt};
 use tokio::sync::{mpsc, oneshot, Semaphore};
@@ -54,7 +57,7 @@ pub struct StopExecution;
 
 impl Engine {
     /// Execute a task graph by sending task ids to the visitor
-    /// while respecting concurrency limits.
+    /// while respecting concurrency limits and resource pool budgets.
     /// The visitor is expected to handle any error handling on it's end.
     /// We enforce this by only allowing the returning of a sentinel error
     /// type which will stop any further execution of tasks.
@@ -74,6 +77,12 @@ impl Engine {
             concurrency,
         } = options;
         let sema = Arc::new(Semaphore::new(concurrency));
+        let resource_pools: Arc<HashMap<_, _>> = Arc::new(
+            self.resource_pools
+                .iter()
+                .map(|(name, capacity)| (name.clone(), Semaphore::new(*capacity as usize)))
+                .collect(),
+        );
         let mut tasks: FuturesUnordered<tokio::task::JoinHandle<Result<(), ExecuteError>>> =
             FuturesUnordered::new();
 
@@ -83,6 +92,7 @@ impl Engine {
         while let Some((node_id, done)) = nodes.recv().await {
             let visitor = visitor.clone();
             let sema = sema.clone();
+            let resource_pools = resource_pools.clone();
             let walker = walker.clone();
             let this = self.clone();
 
@@ -102,6 +112,25 @@ impl Engine {
                     return Ok(());
                 };
 
+                // Reserve the task's share of each resource pool before taking a
+                // concurrency slot so a task waiting on a busy pool doesn't hold up tasks
+                // that don't need it. Pools are always acquired in name order so two tasks
+                // can't each end up holding a pool that the other is waiting on.
+                let mut _resource_permits = Vec::new();
+                if let Some(task_definition) = this.task_definitions.get(task_id) {
+                    for (resource, weight) in &task_definition.resources {
+                        // Resources are validated against the declared pools when the engine
+                        // is built
+                        let Some(pool) = resource_pools.get(resource) else {
+                            continue;
+                        };
+                        _resource_permits.push(pool.acquire_many(*weight).await.expect(
+                            "Resource pool semaphore closed while tasks are still attempting to \
+                             acquire permits",
+                        ));
+                    }
+                }
+
                 // Acquire the semaphore unless parallel
                 let _permit = match parallel {
                     false => Some(sema.acquire().await.expect(
@@ -149,3 +178,57 @@ impl<T, U> Message<T, U> {
         (Self { info, callback }, receiver)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::BTreeMap,
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use super::*;
+    use crate::task_graph::TaskDefinition;
+
+    #[tokio::test]
+    async fn test_resource_pools_limit_running_tasks() {
+        let mut engine = Engine::new();
+        for package in ["a", "b", "c", "d"] {
+            let task_id = TaskId::new(package, "build");
+            engine.connect_to_root(&task_id);
+            engine.add_definition(
+                task_id,
+                TaskDefinition {
+                    resources: BTreeMap::from([("memory".to_string(), 2)]),
+                    ..Default::default()
+                },
+            );
+        }
+        engine.resource_pools = BTreeMap::from([("memory".to_string(), 4)]);
+        let engine = Arc::new(engine.seal());
+
+        let (sender, mut receiver) = mpsc::channel(4);
+        let handle = tokio::spawn(engine.execute(ExecutionOptions::new(false, 10), sender));
+
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+        let mut tasks = Vec::new();
+        while let Some(Message { callback, .. }) = receiver.recv().await {
+            let running = running.clone();
+            let max_running = max_running.clone();
+            tasks.push(tokio::spawn(async move {
+                let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+                max_running.fetch_max(now_running, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                callback.send(Ok(())).ok();
+            }));
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+        handle.await.unwrap().unwrap();
+
+        assert_eq!(max_running.load(Ordering::SeqCst), 2);
+    }
+}
@@ -5,7 +5,7 @@ mod dot;
 mod mermaid;
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     fmt,
 };
 
@@ -42,6 +42,7 @@ pub struct Engine<S = Built> {
     root_index: petgraph::graph::NodeIndex,
     task_lookup: HashMap<TaskId<'static>, petgraph::graph::NodeIndex>,
     task_definitions: HashMap<TaskId<'static>, TaskDefinition>,
+    resource_pools: BTreeMap<String, u32>,
 }
 
 impl Engine<Building> {
@@ -54,6 +55,7 @@ impl Engine<Building> {
             root_index,
             task_lookup: HashMap::default(),
             task_definitions: HashMap::default(),
+            resource_pools: BTreeMap::default(),
         }
     }
 
@@ -85,6 +87,7 @@ impl Engine<Building> {
             task_lookup,
             root_index,
             task_definitions,
+            resource_pools,
             ..
         } = self;
         Engine {
@@ -93,6 +96,7 @@ impl Engine<Building> {
             task_lookup,
             root_index,
             task_definitions,
+            resource_pools,
         }
     }
 }
@@ -130,6 +134,54 @@ impl Engine<Built> {
         )
     }
 
+    /// Creates a new engine containing only the given tasks along with
+    /// everything they transitively depend on.
+    pub fn retain_with_dependencies(&self, tasks: &HashSet<TaskId<'static>>) -> Engine<Built> {
+        let mut to_visit = tasks
+            .iter()
+            .filter_map(|task_id| self.task_lookup.get(task_id).copied())
+            .collect::<Vec<_>>();
+        let mut keep = HashSet::from([self.root_index]);
+        while let Some(index) = to_visit.pop() {
+            if keep.insert(index) {
+                to_visit.extend(
+                    self.task_graph
+                        .neighbors_directed(index, petgraph::Direction::Outgoing),
+                );
+            }
+        }
+
+        let task_graph = self.task_graph.filter_map(
+            |index, node| keep.contains(&index).then(|| node.clone()),
+            |_, _| Some(()),
+        );
+        let mut root_index = self.root_index;
+        let mut task_lookup = HashMap::new();
+        for index in task_graph.node_indices() {
+            match &task_graph[index] {
+                TaskNode::Root => root_index = index,
+                TaskNode::Task(task_id) => {
+                    task_lookup.insert(task_id.clone(), index);
+                }
+            }
+        }
+        let task_definitions = self
+            .task_definitions
+            .iter()
+            .filter(|(task_id, _)| task_lookup.contains_key(*task_id))
+            .map(|(task_id, definition)| (task_id.clone(), definition.clone()))
+            .collect();
+
+        Engine {
+            marker: std::marker::PhantomData,
+            task_graph,
+            root_index,
+            task_lookup,
+            task_definitions,
+            resource_pools: self.resource_pools.clone(),
+        }
+    }
+
     // TODO get rid of static lifetime and figure out right way to tell compiler the
     // lifetime of the return ref
     pub fn task_definition(&self, task_id: &TaskId<'static>) -> Option<&TaskDefinition> {
@@ -190,7 +242,8 @@ impl Engine<Built> {
                             package: dep_id.package().to_string(),
                         })?;
                     if task_definition.persistent
-                        && package_json.scripts.contains_key(dep_id.task())
+                        && (task_definition.command.is_some()
+                            || package_json.scripts.contains_key(dep_id.task()))
                     {
                         return Err(ValidateError::DependencyOnPersistentTask {
                             persistent_task: dep_id.to_string(),
@@ -199,24 +252,20 @@ impl Engine<Built> {
                     }
                 }
 
-                // check if the package for the task has that task in its package.json
+                // check if the task has a command, either in turbo.json or in the package's
+                // package.json
                 let info = package_graph
                     .package_info(&PackageName::from(task_id.package().to_string()))
                     .expect("package graph should contain workspace info for task package");
 
-                let package_has_task = info
-                    .package_json
-                    .scripts
-                    .get(task_id.task())
-                    // handle legacy behaviour from go where an empty string may appear
-                    .map_or(false, |script| !script.is_empty());
-
-                let task_is_persistent = self
-                    .task_definitions
-                    .get(task_id)
-                    .map_or(false, |task_def| task_def.persistent);
+                let Some(task_def) = self.task_definitions.get(task_id) else {
+                    return Ok(false);
+                };
+                let package_has_task = task_def
+                    .resolved_command(&info.package_json, task_id.task())
+                    .is_some();
 
-                Ok(task_is_persistent && package_has_task)
+                Ok(task_def.persistent && package_has_task)
             })
             .fold((0, Vec::new()), |(mut count, mut errs), result| {
                 match result {
@@ -385,4 +434,45 @@ mod test {
         // if our limit is greater, then it should pass
         engine.validate(&graph, 4).expect("ok");
     }
+
+    #[test]
+    fn test_retain_with_dependencies() {
+        // a#build -> b#build -> c#build, d#build stands alone
+        let mut engine = Engine::new();
+        let [a, b, c, d] = ["a", "b", "c", "d"].map(|package| TaskId::new(package, "build"));
+        for task_id in [&a, &b, &c, &d] {
+            engine.get_index(task_id);
+            engine.add_definition(task_id.clone(), TaskDefinition::default());
+        }
+        let (a_index, b_index, c_index) = (
+            engine.get_index(&a),
+            engine.get_index(&b),
+            engine.get_index(&c),
+        );
+        engine.task_graph.add_edge(a_index, b_index, ());
+        engine.task_graph.add_edge(b_index, c_index, ());
+        engine.connect_to_root(&c);
+        engine.connect_to_root(&d);
+        let engine = engine.seal();
+
+        let retained = engine.retain_with_dependencies(&HashSet::from([b.clone()]));
+        let tasks = retained
+            .tasks()
+            .filter_map(|node| match node {
+                TaskNode::Task(task_id) => Some(task_id.clone()),
+                TaskNode::Root => None,
+            })
+            .collect::<HashSet<_>>();
+        assert_eq!(tasks, HashSet::from([b.clone(), c.clone()]));
+        assert_eq!(
+            retained.dependencies(&b),
+            Some(HashSet::from([&TaskNode::Task(c.clone())]))
+        );
+        assert_eq!(
+            retained.dependencies(&c),
+            Some(HashSet::from([&TaskNode::Root]))
+        );
+        assert!(retained.task_definition(&a).is_none());
+        assert!(retained.task_definition(&c).is_some());
+    }
 }
@@ -41,7 +41,7 @@ mod proto_capnp {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TaskHashable<'a> {
     // hashes
     pub(crate) global_hash: &'a str,
@@ -61,6 +61,12 @@ pub struct TaskHashable<'a> {
     pub(crate) pass_through_env: &'a [String],
     pub(crate) env_mode: ResolvedEnvMode,
     pub(crate) dot_env: &'a [turbopath::RelativeUnixPathBuf],
+    pub(crate) inject_dot_env: bool,
+
+    // command, only set when declared in turbo.json
+    pub(crate) command: Option<&'a str>,
+    pub(crate) cwd: Option<&'a turbopath::RelativeUnixPathBuf>,
+    pub(crate) shell: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -211,6 +217,16 @@ impl From<TaskHashable<'_>> for Builder<HeapAllocator> {
 
         builder.set_task(task_hashable.task);
         builder.set_env_mode(task_hashable.env_mode.into());
+        builder.set_inject_dot_env(task_hashable.inject_dot_env);
+        // Scripts are hashed as part of package.json, so these are left unset
+        // for them to keep their hashes stable
+        if let Some(command) = task_hashable.command {
+            builder.set_command(command);
+            builder.set_shell(task_hashable.shell);
+            if let Some(cwd) = task_hashable.cwd {
+                builder.set_cwd(cwd.as_str());
+            }
+        }
 
         {
             let output_builder: Builder<_> = task_hashable.outputs.into();
@@ -408,9 +424,32 @@ mod test {
             pass_through_env: &["pass_thru_env".to_string()],
             env_mode: ResolvedEnvMode::Loose,
             dot_env: &[turbopath::RelativeUnixPathBuf::new("dotenv".to_string()).unwrap()],
+            inject_dot_env: false,
+            command: None,
+            cwd: None,
+            shell: true,
         };
 
-        assert_eq!(task_hashable.hash(), "ff765ee2f83bc034");
+        assert_eq!(task_hashable.clone().hash(), "ff765ee2f83bc034");
+
+        // Injecting the .env files changes what the task sees, so it's part of
+        // the hash. Leaving it off must not change existing hashes.
+        let injected = TaskHashable {
+            inject_dot_env: true,
+            ..task_hashable.clone()
+        };
+        assert_ne!(injected.hash(), "ff765ee2f83bc034");
+
+        let command = TaskHashable {
+            command: Some("cargo build"),
+            ..task_hashable.clone()
+        };
+        let without_shell = TaskHashable {
+            shell: false,
+            ..command.clone()
+        };
+        assert_ne!(command.clone().hash(), "ff765ee2f83bc034");
+        assert_ne!(command.hash(), without_shell.hash());
     }
 
     #[test]
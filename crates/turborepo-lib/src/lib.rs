@@ -11,6 +11,7 @@
 #![allow(clippy::needless_pass_by_ref_mut)]
 #![allow(dead_code)]
 
+mod boundaries;
 mod child;
 mod cli;
 mod commands;
@@ -24,6 +25,7 @@ pub(crate) mod globwatcher;
 mod hash;
 mod opts;
 mod process;
+mod query;
 mod rewrite_json;
 mod run;
 mod shim;
@@ -39,6 +41,7 @@ pub use crate::{
     cli::Args,
     daemon::{DaemonClient, DaemonConnector, Paths as DaemonPaths},
     run::package_discovery::DaemonPackageDiscovery,
+    turbo_json::analysis::{TurboJsonAnalyzer, TurboJsonDiagnostic},
 };
 
 pub fn get_version() -> &'static str {
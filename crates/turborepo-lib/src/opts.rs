@@ -1,12 +1,17 @@
 use std::backtrace;
 
 use thiserror::Error;
+use tracing::warn;
 use turbopath::AnchoredSystemPathBuf;
 use turborepo_cache::CacheOpts;
+use turborepo_repository::discovery::Ecosystem;
 
 use crate::{
-    cli::{Command, DryRunMode, EnvMode, LogOrder, LogPrefix, OutputLogsMode, RunArgs},
-    run::task_id::TaskId,
+    cli::{
+        Command, DryRunMode, EnvMode, LogOrder, LogPrefix, OutputLogsMode, Report, RunArgs,
+        SandboxMode, UIMode,
+    },
+    run::{otel::OtelOpts, sandbox, task_id::TaskId},
     Args,
 };
 
@@ -51,6 +56,15 @@ impl Opts {
             cmd.push_str(pattern);
         }
 
+        match &self.scope_opts.affected {
+            Some(Some(base)) => {
+                cmd.push_str(" --affected=");
+                cmd.push_str(base);
+            }
+            Some(None) => cmd.push_str(" --affected"),
+            None => (),
+        }
+
         if self.run_opts.parallel {
             cmd.push_str(" --parallel");
         }
@@ -135,8 +149,15 @@ pub struct RunOpts {
     pub(crate) single_package: bool,
     pub log_prefix: ResolvedLogPrefix,
     pub log_order: ResolvedLogOrder,
+    pub(crate) ui_mode: UIMode,
+    pub(crate) sandbox: SandboxMode,
     pub summarize: Option<Option<bool>>,
+    pub(crate) explain_miss: bool,
+    pub(crate) reports: Vec<Report>,
     pub(crate) experimental_space_id: Option<String>,
+    // Resolved from config rather than flags, see `Run::new`
+    pub(crate) otel: Option<OtelOpts>,
+    pub(crate) package_discovery: Vec<Ecosystem>,
     pub is_github_actions: bool,
 }
 
@@ -208,12 +229,37 @@ impl<'a> TryFrom<&'a RunArgs> for RunOpts {
             LogOrder::Grouped => (false, ResolvedLogOrder::Grouped, args.log_prefix.into()),
         };
 
+        // The terminal UI takes over the screen, so only use it when there is a
+        // terminal to take over and tasks are actually going to run
+        let ui_mode = match args.ui {
+            UIMode::Tui
+                if atty::is(atty::Stream::Stdout) && args.dry_run.is_none() && graph.is_none() =>
+            {
+                UIMode::Tui
+            }
+            UIMode::Tui | UIMode::Stream => UIMode::Stream,
+        };
+
+        let sandbox = match args.sandbox {
+            SandboxMode::Warn | SandboxMode::Strict if !sandbox::is_supported() => {
+                warn!("--sandbox is only supported on x86_64 Linux, running tasks without it");
+                SandboxMode::Off
+            }
+            mode => mode,
+        };
+
         Ok(Self {
             tasks: args.tasks.clone(),
             log_prefix,
             log_order,
+            ui_mode,
+            sandbox,
             summarize: args.summarize,
+            explain_miss: args.explain_miss,
+            reports: args.report.clone(),
             experimental_space_id: args.experimental_space_id.clone(),
+            otel: None,
+            package_discovery: Vec::new(),
             framework_inference: args.framework_inference,
             env_mode: args.env_mode,
             concurrency,
@@ -313,6 +359,9 @@ pub struct ScopeOpts {
     pub global_deps: Vec<String>,
     pub filter_patterns: Vec<String>,
     pub ignore_patterns: Vec<String>,
+    /// Set when only affected tasks should run. Holds the base ref to compare
+    /// against, or `None` if it should be inferred.
+    pub affected: Option<Option<String>>,
 }
 
 impl<'a> TryFrom<&'a RunArgs> for ScopeOpts {
@@ -337,6 +386,10 @@ impl<'a> TryFrom<&'a RunArgs> for ScopeOpts {
             legacy_filter,
             filter_patterns: args.filter.clone(),
             ignore_patterns: args.ignore.clone(),
+            affected: args
+                .affected
+                .as_ref()
+                .map(|base| Some(base.clone()).filter(|base| !base.is_empty())),
         })
     }
 }
@@ -416,6 +469,7 @@ mod test {
         continue_on_error: bool,
         dry_run: Option<DryRunMode>,
         legacy_filter: Option<LegacyFilter>,
+        affected: Option<Option<String>>,
     }
 
     #[test_case(TestCaseOpts {
@@ -512,6 +566,23 @@ mod test {
         },
         "turbo run build --filter=my-app --dry=json"
     )]
+    #[test_case    (
+        TestCaseOpts {
+            tasks: vec!["build".to_string()],
+            affected: Some(None),
+            ..Default::default()
+        },
+        "turbo run build --affected"
+    )]
+    #[test_case    (
+        TestCaseOpts {
+            filter_patterns: vec!["my-app".to_string()],
+            tasks: vec!["build".to_string()],
+            affected: Some(Some("origin/release".to_string())),
+            ..Default::default()
+        },
+        "turbo run build --filter=my-app --affected=origin/release"
+    )]
     fn test_synthesize_command(opts_input: TestCaseOpts, expected: &str) {
         let run_opts = RunOpts {
             tasks: opts_input.tasks,
@@ -529,8 +600,14 @@ mod test {
             single_package: false,
             log_prefix: crate::opts::ResolvedLogPrefix::Task,
             log_order: crate::opts::ResolvedLogOrder::Stream,
+            ui_mode: crate::cli::UIMode::Stream,
+            sandbox: crate::cli::SandboxMode::Off,
             summarize: None,
+            explain_miss: false,
+            reports: Vec::new(),
             experimental_space_id: None,
+            otel: None,
+            package_discovery: Vec::new(),
             is_github_actions: false,
         };
         let cache_opts = CacheOpts::default();
@@ -542,6 +619,7 @@ mod test {
             global_deps: vec![],
             filter_patterns: opts_input.filter_patterns,
             ignore_patterns: vec![],
+            affected: opts_input.affected,
         };
         let opts = Opts {
             run_opts,
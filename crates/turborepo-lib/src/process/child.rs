@@ -26,7 +26,7 @@ use std::{
 
 use portable_pty::{native_pty_system, Child as PtyChild, MasterPty as PtyController};
 use tokio::{
-    io::{AsyncBufRead, AsyncBufReadExt, BufReader},
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncWriteExt, BufReader},
     join,
     process::Command as TokioCommand,
     sync::{mpsc, watch, RwLock},
@@ -263,6 +263,27 @@ impl ChildHandle {
             }
         }
     }
+
+    /// Kills the child along with anything it spawned. The child leads its
+    /// own process group, which can't be reused until the child is reaped, so
+    /// this must only be called while `wait` hasn't returned yet.
+    pub async fn kill_group(&mut self) -> io::Result<()> {
+        #[cfg(unix)]
+        if let Some(pid) = self.pid {
+            use nix::{
+                errno::Errno,
+                sys::signal::{killpg, Signal},
+                unistd::Pid,
+            };
+            debug!("sending SIGKILL to process group {}", pid);
+            match killpg(Pid::from_raw(pid as i32), Signal::SIGKILL) {
+                // The group is already gone
+                Ok(()) | Err(Errno::ESRCH) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        self.kill().await
+    }
 }
 
 struct SpawnResult {
@@ -426,9 +447,29 @@ impl ChildCommandChannel {
     }
 }
 
+/// Writing to the channel forwards the bytes to the child's stdin. This blocks
+/// until the child process picks up the input so it must not be used from
+/// within an async context.
+impl Write for ChildCommandChannel {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .blocking_send(ChildCommand::Input(buf.to_vec()))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "child process exited"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
 pub enum ChildCommand {
     Stop,
     Kill,
+    /// Kill the child and every process in its process group
+    KillGroup,
+    /// Write the bytes to the child's stdin
+    Input(Vec<u8>),
 }
 
 impl Child {
@@ -464,6 +505,8 @@ impl Child {
 
         let state = Arc::new(RwLock::new(ChildState::Running(command_tx)));
         let task_state = state.clone();
+        let stdin = Arc::new(Mutex::new(stdin));
+        let task_stdin = stdin.clone();
 
         let _task = tokio::spawn(async move {
             // On Windows it is important that this gets dropped once the child process
@@ -475,13 +518,21 @@ impl Child {
                 task_state,
                 exit_tx,
             };
-            tokio::select! {
-                command = command_rx.recv() => {
-                    manager.handle_child_command(command, &mut child, controller).await;
-                }
-                status = child.wait() => {
-                    drop(controller);
-                    manager.handle_child_exit(status).await;
+            loop {
+                tokio::select! {
+                    command = command_rx.recv() => {
+                        if let Some(ChildCommand::Input(input)) = command {
+                            write_input(&task_stdin, &input).await;
+                            continue;
+                        }
+                        manager.handle_child_command(command, &mut child, controller).await;
+                        break;
+                    }
+                    status = child.wait() => {
+                        drop(controller);
+                        manager.handle_child_exit(status).await;
+                        break;
+                    }
                 }
             }
 
@@ -492,7 +543,7 @@ impl Child {
             pid,
             state,
             exit_channel: exit_rx,
-            stdin: Arc::new(Mutex::new(stdin)),
+            stdin,
             output: Arc::new(Mutex::new(output)),
             label,
         })
@@ -536,6 +587,16 @@ impl Child {
 
     /// Kill the `Child` process immediately.
     pub async fn kill(&mut self) -> Option<ChildExit> {
+        self.kill_with(ChildCommand::Kill).await
+    }
+
+    /// Kill the `Child` process and anything it spawned immediately. On
+    /// Windows this is the same as `kill`.
+    pub async fn kill_group(&mut self) -> Option<ChildExit> {
+        self.kill_with(ChildCommand::KillGroup).await
+    }
+
+    async fn kill_with(&mut self, command: ChildCommand) -> Option<ChildExit> {
         let mut watch = self.exit_channel.clone();
 
         let fut = async {
@@ -547,7 +608,7 @@ impl Child {
 
             // if this fails, it's because the channel is dropped (toctou)
             // we can just ignore it
-            child.kill().await.ok();
+            child.0.send(command).await.ok();
         };
 
         let (_, code) = join! {
@@ -566,6 +627,11 @@ impl Child {
         self.pid
     }
 
+    /// A channel for sending commands to the child, `None` if it has exited
+    pub async fn command_channel(&self) -> Option<ChildCommandChannel> {
+        self.state.read().await.command_channel()
+    }
+
     fn stdin(&mut self) -> Option<ChildInput> {
         self.stdin.lock().unwrap().take()
     }
@@ -715,6 +781,31 @@ impl Child {
     }
 }
 
+// Forwards input to the child's stdin. The input is taken out of the lock while
+// writing so it isn't held across an await.
+async fn write_input(stdin: &Mutex<Option<ChildInput>>, input: &[u8]) {
+    let child_input = stdin.lock().unwrap().take();
+    let child_input = match child_input {
+        Some(ChildInput::Std(mut stdin)) => {
+            let result = match stdin.write_all(input).await {
+                Ok(()) => stdin.flush().await,
+                Err(e) => Err(e),
+            };
+            result.ok().map(|_| ChildInput::Std(stdin))
+        }
+        Some(ChildInput::Pty(mut stdin)) => stdin
+            .write_all(input)
+            .and_then(|_| stdin.flush())
+            .ok()
+            .map(|_| ChildInput::Pty(stdin)),
+        None => {
+            debug!("dropping input for child without stdin");
+            None
+        }
+    };
+    *stdin.lock().unwrap() = child_input;
+}
+
 // Adds a trailing newline if necessary to the buffer
 fn add_trailing_newline(buffer: &mut Vec<u8>) {
     // If the line doesn't end with a newline, that indicates we hit a EOF.
@@ -746,6 +837,18 @@ impl ChildStateManager {
                 debug!("killing child process");
                 ShutdownStyle::Kill.process(child).await
             }
+            // The child hasn't been reaped since we're handling a command rather
+            // than its exit, so its process group is still ours to kill
+            Some(ChildCommand::KillGroup) => {
+                debug!("killing child process group");
+                match child.kill_group().await {
+                    Ok(_) => ChildState::Exited(ChildExit::Killed),
+                    Err(_) => ChildState::Exited(ChildExit::Failed),
+                }
+            }
+            Some(ChildCommand::Input(_)) => {
+                unreachable!("input is forwarded before handling child commands")
+            }
         };
         match state {
             ChildState::Exited(exit) => {
@@ -790,7 +893,7 @@ mod test {
 
     use futures::{stream::FuturesUnordered, StreamExt};
     use test_case::test_case;
-    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt};
     use tracing_test::traced_test;
     use turbopath::AbsoluteSystemPathBuf;
 
@@ -937,6 +1040,51 @@ mod test {
         assert_matches!(&*state, ChildState::Exited(ChildExit::Finished(Some(0))));
     }
 
+    #[test_case(false)]
+    #[test_case(TEST_PTY)]
+    #[tokio::test]
+    async fn test_input_through_command_channel(use_pty: bool) {
+        let script = find_script_dir().join_component("stdin_stdout.js");
+        let mut cmd = Command::new("node");
+        cmd.args([script.as_std_path()]);
+        cmd.open_stdin();
+        let mut child = Child::spawn(cmd, ShutdownStyle::Kill, use_pty).unwrap();
+
+        tokio::time::sleep(STARTUP_DELAY).await;
+
+        let mut channel = child.command_channel().await.unwrap();
+        tokio::task::spawn_blocking(move || {
+            std::io::Write::write_all(&mut channel, b"hello world\n")
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        let mut output = String::new();
+        match child.outputs().unwrap() {
+            ChildOutput::Std { stdout, .. } => {
+                tokio::io::BufReader::new(stdout)
+                    .read_line(&mut output)
+                    .await
+                    .unwrap();
+            }
+            ChildOutput::Pty(stdout) => {
+                // The pty echoes the input back before the child writes it
+                let mut stdout = std::io::BufReader::new(stdout);
+                while output.matches("hello world").count() < 2 {
+                    if std::io::BufRead::read_line(&mut stdout, &mut output).unwrap() == 0 {
+                        break;
+                    }
+                }
+            }
+        };
+
+        assert!(output.contains("hello world"), "got: {}", output);
+
+        child.kill().await;
+        assert!(child.command_channel().await.is_none());
+    }
+
     #[test_case(false)]
     #[test_case(TEST_PTY)]
     #[tokio::test]
@@ -1170,6 +1318,32 @@ mod test {
         assert_matches!(exit, Some(ChildExit::Killed));
     }
 
+    #[cfg(unix)]
+    #[test_case(false)]
+    #[test_case(TEST_PTY)]
+    #[tokio::test]
+    async fn test_kill_group_keeps_output(use_pty: bool) {
+        // The background sleep holds on to stdout, so the output only closes
+        // once it is killed as well
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo started; sleep 100 & wait"]);
+        let mut child = Child::spawn(cmd, ShutdownStyle::Kill, use_pty).unwrap();
+        let mut killer = child.clone();
+
+        let mut out = Vec::new();
+        let (exit, _) = tokio::time::timeout(Duration::from_secs(5), async {
+            tokio::join!(child.wait_with_piped_outputs(&mut out), async {
+                tokio::time::sleep(STARTUP_DELAY).await;
+                killer.kill_group().await
+            })
+        })
+        .await
+        .expect("output should close once the process group is killed");
+
+        assert!(String::from_utf8(out).unwrap().contains("started"));
+        assert_matches!(exit.unwrap(), Some(ChildExit::Killed));
+    }
+
     #[test_case(false)]
     #[test_case(TEST_PTY)]
     #[tokio::test]
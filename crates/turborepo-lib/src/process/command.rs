@@ -9,6 +9,7 @@ use turbopath::AbsoluteSystemPathBuf;
 
 /// A command builder that can be used to build both regular
 /// child processes and ones spawned hooked up to a PTY
+#[derive(Clone)]
 pub struct Command {
     program: OsString,
     args: Vec<OsString>,
@@ -71,6 +72,24 @@ impl Command {
         self
     }
 
+    /// Run the command through `program` instead, passing it `args` followed
+    /// by the original program and its arguments
+    pub fn wrap<I, S>(&mut self, program: impl AsRef<OsStr>, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let wrapped_program = std::mem::replace(&mut self.program, program.as_ref().to_os_string());
+        let wrapped_args = std::mem::take(&mut self.args);
+        self.args = args
+            .into_iter()
+            .map(|arg| arg.as_ref().to_os_string())
+            .chain(std::iter::once(wrapped_program))
+            .chain(wrapped_args)
+            .collect();
+        self
+    }
+
     /// Configure the child process to spawn with a piped stdin
     pub fn open_stdin(&mut self) -> &mut Self {
         self.open_stdin = true;
@@ -0,0 +1,632 @@
+//! Structured queries over the package graph, the task graph and the changed
+//! packages for a set of files. Used by `turbo query`.
+//!
+//! Queries never run anything, they only describe what is in the repository
+//! or what `turbo run` would do.
+
+mod parser;
+
+use std::collections::HashSet;
+
+use itertools::Itertools;
+use miette::{Diagnostic, SourceSpan};
+use serde::Serialize;
+use thiserror::Error;
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
+use turborepo_repository::{
+    change_mapper::{ChangeMapError, ChangeMapper, LockfileChange, PackageChanges},
+    package_graph::{PackageGraph, PackageName, PackageNode},
+};
+
+use self::parser::{Arg, Call};
+use crate::{
+    engine::{EngineBuilder, TaskNode},
+    run::task_id::TaskName,
+    turbo_json::TurboJson,
+};
+
+const QUERIES: &str = "packages, package, dependencies, dependents, changed, affected, tasks";
+
+#[derive(Debug, Error, Diagnostic)]
+pub enum Error {
+    #[error("Invalid query: {message}")]
+    #[diagnostic(code(invalid_query))]
+    Parse {
+        message: String,
+        #[source_code]
+        text: String,
+        #[label("here")]
+        span: Option<SourceSpan>,
+    },
+    #[error("Unknown query \"{name}\". Available queries are: {QUERIES}")]
+    #[diagnostic(code(unknown_query))]
+    UnknownQuery {
+        name: String,
+        #[source_code]
+        text: String,
+        #[label("unknown query")]
+        span: Option<SourceSpan>,
+    },
+    #[error("\"{name}\" expects {expected}")]
+    #[diagnostic(code(invalid_query_arguments))]
+    InvalidArguments {
+        name: String,
+        expected: &'static str,
+        #[source_code]
+        text: String,
+        #[label("invalid arguments")]
+        span: Option<SourceSpan>,
+    },
+    #[error("Package \"{0}\" not found in the workspace")]
+    MissingPackage(String),
+    #[error(transparent)]
+    ChangeMapper(#[from] ChangeMapError),
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Engine(#[from] crate::engine::BuilderError),
+    #[error(transparent)]
+    Path(#[from] turbopath::PathError),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Query {
+    /// Every package in the workspace
+    Packages,
+    /// A single package along with its direct dependencies and dependents
+    Package(String),
+    /// Transitive workspace dependencies of a package
+    Dependencies(String),
+    /// Transitive dependents of a package
+    Dependents(String),
+    /// Packages containing the given files
+    Changed(Vec<String>),
+    /// Packages containing the given files along with their dependents
+    Affected(Vec<String>),
+    /// The task graph `turbo run` would execute for the tasks, optionally
+    /// limited to the packages matched by other queries
+    Tasks {
+        tasks: Vec<String>,
+        packages: Vec<Query>,
+    },
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum QueryOutput {
+    Packages(Vec<PackageSummary>),
+    Package(PackageDetails),
+    Tasks(Vec<TaskSummary>),
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageSummary {
+    pub name: String,
+    pub path: String,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageDetails {
+    pub name: String,
+    pub path: String,
+    pub dependencies: Vec<String>,
+    pub dependents: Vec<String>,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskSummary {
+    pub task_id: String,
+    pub package: String,
+    pub task: String,
+    pub dependencies: Vec<String>,
+    pub dependents: Vec<String>,
+}
+
+/// Everything a query can be evaluated against
+pub struct QueryContext<'a> {
+    pub repo_root: &'a AbsoluteSystemPath,
+    pub pkg_graph: &'a PackageGraph,
+    pub root_turbo_json: &'a TurboJson,
+}
+
+impl Query {
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let call = parser::parse(text)?;
+        Self::from_call(call, text)
+    }
+
+    fn from_call(call: Call, text: &str) -> Result<Self, Error> {
+        let invalid_arguments = |expected| Error::InvalidArguments {
+            name: call.name.clone(),
+            expected,
+            text: text.to_string(),
+            span: Some(call.span),
+        };
+
+        let mut values = Vec::new();
+        let mut calls = Vec::new();
+        for arg in call.args.iter().cloned() {
+            match arg {
+                Arg::Value(value, _) => values.push(value),
+                Arg::Call(call) => calls.push(call),
+            }
+        }
+
+        let single_package = |values: Vec<String>, calls: &[Call]| match (values.len(), calls) {
+            (1, []) => Ok(values.into_iter().next().expect("checked length")),
+            _ => Err(invalid_arguments("a single package name")),
+        };
+
+        match call.name.as_str() {
+            "packages" if call.args.is_empty() => Ok(Query::Packages),
+            "packages" => Err(invalid_arguments("no arguments")),
+            "package" => Ok(Query::Package(single_package(values, &calls)?)),
+            "dependencies" => Ok(Query::Dependencies(single_package(values, &calls)?)),
+            "dependents" => Ok(Query::Dependents(single_package(values, &calls)?)),
+            "changed" | "affected" if values.is_empty() || !calls.is_empty() => {
+                Err(invalid_arguments("one or more file paths"))
+            }
+            "changed" => Ok(Query::Changed(values)),
+            "affected" => Ok(Query::Affected(values)),
+            "tasks" if values.is_empty() => Err(invalid_arguments(
+                "one or more task names, optionally followed by package queries",
+            )),
+            "tasks" => {
+                let packages = calls
+                    .into_iter()
+                    .map(|call| {
+                        let span = call.span;
+                        match Self::from_call(call, text)? {
+                            Query::Tasks { .. } => Err(Error::InvalidArguments {
+                                name: "tasks".to_string(),
+                                expected: "package queries, not another tasks query",
+                                text: text.to_string(),
+                                span: Some(span),
+                            }),
+                            query => Ok(query),
+                        }
+                    })
+                    .collect::<Result<_, _>>()?;
+                Ok(Query::Tasks {
+                    tasks: values,
+                    packages,
+                })
+            }
+            _ => Err(Error::UnknownQuery {
+                name: call.name.clone(),
+                text: text.to_string(),
+                span: Some(call.span),
+            }),
+        }
+    }
+
+    pub fn evaluate(&self, ctx: &QueryContext) -> Result<QueryOutput, Error> {
+        match self {
+            Query::Package(name) => {
+                let package = ctx.package(name)?;
+                let node = PackageNode::Workspace(package.clone());
+                let names = |nodes: Option<HashSet<&PackageNode>>| {
+                    nodes
+                        .into_iter()
+                        .flatten()
+                        .filter_map(workspace_name)
+                        .map(|name| name.to_string())
+                        .sorted()
+                        .collect()
+                };
+
+                Ok(QueryOutput::Package(PackageDetails {
+                    name: package.to_string(),
+                    path: ctx.package_path(&package),
+                    dependencies: names(ctx.pkg_graph.immediate_dependencies(&node)),
+                    dependents: names(ctx.pkg_graph.immediate_ancestors(&node)),
+                }))
+            }
+            Query::Tasks { tasks, packages } => Ok(QueryOutput::Tasks(ctx.tasks(tasks, packages)?)),
+            query => {
+                let packages = query.packages(ctx)?;
+                Ok(QueryOutput::Packages(
+                    packages
+                        .into_iter()
+                        .map(|name| PackageSummary {
+                            path: ctx.package_path(&name),
+                            name: name.to_string(),
+                        })
+                        .sorted()
+                        .collect(),
+                ))
+            }
+        }
+    }
+
+    /// The set of packages matched by this query
+    fn packages(&self, ctx: &QueryContext) -> Result<HashSet<PackageName>, Error> {
+        match self {
+            Query::Packages => Ok(ctx
+                .pkg_graph
+                .packages()
+                .map(|(name, _)| name.clone())
+                .collect()),
+            Query::Package(name) => Ok(Some(ctx.package(name)?).into_iter().collect()),
+            Query::Dependencies(name) => {
+                let node = PackageNode::Workspace(ctx.package(name)?);
+                Ok(ctx
+                    .pkg_graph
+                    .dependencies(&node)
+                    .into_iter()
+                    .filter_map(workspace_name)
+                    .cloned()
+                    .collect())
+            }
+            Query::Dependents(name) => {
+                let node = PackageNode::Workspace(ctx.package(name)?);
+                Ok(ctx
+                    .pkg_graph
+                    .ancestors(&node)
+                    .into_iter()
+                    .filter_map(workspace_name)
+                    .cloned()
+                    .collect())
+            }
+            Query::Changed(files) => ctx.changed_packages(files),
+            Query::Affected(files) => {
+                let changed = ctx.changed_packages(files)?;
+                let mut affected = changed.clone();
+                for package in changed {
+                    affected.extend(
+                        ctx.pkg_graph
+                            .ancestors(&PackageNode::Workspace(package))
+                            .into_iter()
+                            .filter_map(workspace_name)
+                            .cloned(),
+                    );
+                }
+                Ok(affected)
+            }
+            Query::Tasks { .. } => unreachable!("tasks queries are rejected during parsing"),
+        }
+    }
+}
+
+impl<'a> QueryContext<'a> {
+    fn package(&self, name: &str) -> Result<PackageName, Error> {
+        let package = PackageName::from(name);
+        match self.pkg_graph.package_info(&package) {
+            Some(_) => Ok(package),
+            None => Err(Error::MissingPackage(name.to_string())),
+        }
+    }
+
+    fn package_path(&self, package: &PackageName) -> String {
+        self.pkg_graph
+            .package_dir(package)
+            .map(|path| path.to_unix().to_string())
+            .unwrap_or_default()
+    }
+
+    fn changed_packages(&self, files: &[String]) -> Result<HashSet<PackageName>, Error> {
+        let changed_files = files
+            .iter()
+            .map(|file| {
+                let path = AbsoluteSystemPathBuf::from_unknown(self.repo_root, file);
+                self.repo_root.anchor(&path)
+            })
+            .collect::<Result<HashSet<AnchoredSystemPathBuf>, _>>()?;
+
+        // We only know the current lockfile contents, so any change to it affects
+        // every package
+        let lockfile_path = self
+            .pkg_graph
+            .package_manager()
+            .lockfile_path(self.repo_root);
+        let lockfile_change =
+            ChangeMapper::lockfile_changed(self.repo_root, &changed_files, &lockfile_path)
+                .then_some(LockfileChange::Empty);
+
+        let change_mapper = ChangeMapper::new(
+            self.pkg_graph,
+            self.root_turbo_json.global_deps.clone(),
+            Vec::new(),
+        );
+        match change_mapper.changed_packages(changed_files, lockfile_change)? {
+            PackageChanges::All => Ok(self
+                .pkg_graph
+                .packages()
+                .map(|(name, _)| name.clone())
+                .collect()),
+            PackageChanges::Some(packages) => {
+                Ok(packages.into_iter().map(|package| package.name).collect())
+            }
+        }
+    }
+
+    fn tasks(&self, tasks: &[String], packages: &[Query]) -> Result<Vec<TaskSummary>, Error> {
+        let workspaces = if packages.is_empty() {
+            // Mirror `turbo run`: every package, along with the root if one of the
+            // tasks is defined as a root task
+            let mut workspaces: HashSet<_> = self
+                .pkg_graph
+                .packages()
+                .map(|(name, _)| name.clone())
+                .filter(|name| name != &PackageName::Root)
+                .collect();
+            let has_root_task = tasks.iter().any(|task| {
+                let task_name = TaskName::from(task.as_str());
+                let task_name = if task_name.is_package_task() {
+                    task_name
+                } else {
+                    task_name.into_root_task()
+                };
+                self.root_turbo_json.pipeline.contains_key(&task_name)
+            });
+            if has_root_task {
+                workspaces.insert(PackageName::Root);
+            }
+            workspaces
+        } else {
+            let mut workspaces = HashSet::new();
+            for query in packages {
+                workspaces.extend(query.packages(self)?);
+            }
+            workspaces
+        };
+
+        let engine = EngineBuilder::new(self.repo_root, self.pkg_graph, false)
+            .with_root_tasks(self.root_turbo_json.pipeline.keys().cloned())
+            .with_turbo_jsons(Some(
+                Some((PackageName::Root, self.root_turbo_json.clone()))
+                    .into_iter()
+                    .collect(),
+            ))
+            .with_workspaces(workspaces.into_iter().collect())
+            .with_tasks(
+                tasks
+                    .iter()
+                    .map(|task| TaskName::from(task.as_str()).into_owned()),
+            )
+            .build()?;
+
+        let task_ids = |nodes: Option<HashSet<&TaskNode>>| -> Vec<String> {
+            nodes
+                .into_iter()
+                .flatten()
+                .filter_map(|node| match node {
+                    TaskNode::Task(task_id) => Some(task_id.to_string()),
+                    TaskNode::Root => None,
+                })
+                .sorted()
+                .collect()
+        };
+
+        Ok(engine
+            .tasks()
+            .filter_map(|node| match node {
+                TaskNode::Task(task_id) => Some(task_id),
+                TaskNode::Root => None,
+            })
+            .sorted()
+            .map(|task_id| TaskSummary {
+                task_id: task_id.to_string(),
+                package: task_id.package().to_string(),
+                task: task_id.task().to_string(),
+                dependencies: task_ids(engine.dependencies(task_id)),
+                dependents: task_ids(engine.dependents(task_id)),
+            })
+            .collect())
+    }
+}
+
+fn workspace_name(node: &PackageNode) -> Option<&PackageName> {
+    match node {
+        PackageNode::Workspace(name) => Some(name),
+        PackageNode::Root => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use tempfile::TempDir;
+    use test_case::test_case;
+    use turbopath::AbsoluteSystemPathBuf;
+    use turborepo_repository::package_json::PackageJson;
+
+    use super::*;
+
+    // a <- b <- c, d has no dependencies
+    async fn setup() -> (TempDir, AbsoluteSystemPathBuf, PackageGraph, TurboJson) {
+        let tmp = TempDir::new().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        repo_root
+            .join_component("package.json")
+            .create_with_contents(
+                json!({
+                    "name": "root",
+                    "packageManager": "npm@8.19.4",
+                    "workspaces": ["packages/*"],
+                })
+                .to_string(),
+            )
+            .unwrap();
+        repo_root
+            .join_component("turbo.json")
+            .create_with_contents(
+                json!({
+                    "globalDependencies": ["shared.env"],
+                    "pipeline": {
+                        "build": { "dependsOn": ["^build"] },
+                        "test": { "dependsOn": ["build"] },
+                    },
+                })
+                .to_string(),
+            )
+            .unwrap();
+        for (name, deps) in [
+            ("a", vec![]),
+            ("b", vec!["a"]),
+            ("c", vec!["b"]),
+            ("d", vec![]),
+        ] {
+            let package_json = repo_root.join_components(&["packages", name, "package.json"]);
+            package_json.ensure_dir().unwrap();
+            let dependencies: serde_json::Map<_, _> = deps
+                .into_iter()
+                .map(|dep| (dep.to_string(), json!("*")))
+                .collect();
+            package_json
+                .create_with_contents(
+                    json!({"name": name, "dependencies": dependencies}).to_string(),
+                )
+                .unwrap();
+        }
+
+        let root_package_json =
+            PackageJson::load(&repo_root.join_component("package.json")).unwrap();
+        let pkg_graph = PackageGraph::builder(&repo_root, root_package_json.clone())
+            .build()
+            .await
+            .unwrap();
+        let turbo_json = TurboJson::load(
+            &repo_root,
+            turbopath::AnchoredSystemPath::empty(),
+            &root_package_json,
+            false,
+        )
+        .unwrap();
+
+        (tmp, repo_root, pkg_graph, turbo_json)
+    }
+
+    async fn evaluate(query: &str) -> serde_json::Value {
+        let (_tmp, repo_root, pkg_graph, root_turbo_json) = setup().await;
+        let output = Query::parse(query)
+            .unwrap()
+            .evaluate(&QueryContext {
+                repo_root: &repo_root,
+                pkg_graph: &pkg_graph,
+                root_turbo_json: &root_turbo_json,
+            })
+            .unwrap();
+        serde_json::to_value(output).unwrap()
+    }
+
+    fn names(output: &serde_json::Value) -> Vec<&str> {
+        output["packages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|package| package["name"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test_case("packages", &["//", "a", "b", "c", "d"] ; "packages")]
+    #[test_case("dependencies(c)", &["a", "b"] ; "dependencies")]
+    #[test_case("dependents(a)", &["b", "c"] ; "dependents")]
+    #[test_case("changed(packages/b/src/index.ts, README.md)", &["//", "b"] ; "changed")]
+    #[test_case("affected(packages/b/src/index.ts)", &["b", "c"] ; "affected")]
+    #[test_case("affected(shared.env)", &["//", "a", "b", "c", "d"] ; "global dependency")]
+    #[test_case("affected(package-lock.json)", &["//", "a", "b", "c", "d"] ; "lockfile")]
+    #[tokio::test]
+    async fn test_package_queries(query: &str, expected: &[&str]) {
+        assert_eq!(names(&evaluate(query).await), expected);
+    }
+
+    #[tokio::test]
+    async fn test_package() {
+        assert_eq!(
+            evaluate("package(b)").await,
+            json!({
+                "package": {
+                    "name": "b",
+                    "path": "packages/b",
+                    "dependencies": ["a"],
+                    "dependents": ["c"],
+                }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tasks() {
+        let output = evaluate("tasks(test, affected(packages/b/index.ts))").await;
+        assert_eq!(
+            output,
+            json!({
+                "tasks": [
+                    {
+                        "taskId": "a#build",
+                        "package": "a",
+                        "task": "build",
+                        "dependencies": [],
+                        "dependents": ["b#build"],
+                    },
+                    {
+                        "taskId": "b#build",
+                        "package": "b",
+                        "task": "build",
+                        "dependencies": ["a#build"],
+                        "dependents": ["b#test", "c#build"],
+                    },
+                    {
+                        "taskId": "b#test",
+                        "package": "b",
+                        "task": "test",
+                        "dependencies": ["b#build"],
+                        "dependents": [],
+                    },
+                    {
+                        "taskId": "c#build",
+                        "package": "c",
+                        "task": "build",
+                        "dependencies": ["b#build"],
+                        "dependents": ["c#test"],
+                    },
+                    {
+                        "taskId": "c#test",
+                        "package": "c",
+                        "task": "test",
+                        "dependencies": ["c#build"],
+                        "dependents": [],
+                    },
+                ]
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_package() {
+        let (_tmp, repo_root, pkg_graph, root_turbo_json) = setup().await;
+        let result = Query::parse("dependents(missing)")
+            .unwrap()
+            .evaluate(&QueryContext {
+                repo_root: &repo_root,
+                pkg_graph: &pkg_graph,
+                root_turbo_json: &root_turbo_json,
+            });
+        assert!(matches!(result, Err(Error::MissingPackage(name)) if name == "missing"));
+    }
+
+    #[test_case("packages(a)" ; "packages with arguments")]
+    #[test_case("dependents()" ; "missing package")]
+    #[test_case("dependents(a, b)" ; "too many packages")]
+    #[test_case("affected()" ; "missing files")]
+    #[test_case("tasks(packages())" ; "missing tasks")]
+    #[test_case("tasks(build, tasks(lint))" ; "nested tasks")]
+    fn test_invalid_arguments(query: &str) {
+        assert!(matches!(
+            Query::parse(query),
+            Err(Error::InvalidArguments { .. })
+        ));
+    }
+
+    #[test]
+    fn test_unknown_query() {
+        assert!(matches!(
+            Query::parse("ancestors(a)"),
+            Err(Error::UnknownQuery { name, .. }) if name == "ancestors"
+        ));
+    }
+}
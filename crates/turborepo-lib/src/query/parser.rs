@@ -0,0 +1,211 @@
+//! Parser for `turbo query` expressions.
+//!
+//! The grammar is intentionally tiny:
+//!
+//! ```text
+//! expr  := ident [ "(" [ arg ( "," arg )* ] ")" ]
+//! arg   := ident "(" ... ")" | value
+//! value := bare word | "double quoted string"
+//! ```
+//!
+//! Bare words run until whitespace, `,`, `(` or `)`, which covers package
+//! names, task names and file paths without quoting.
+
+use miette::SourceSpan;
+
+use super::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Call {
+    pub name: String,
+    pub args: Vec<Arg>,
+    pub span: SourceSpan,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Arg {
+    Value(String, SourceSpan),
+    Call(Call),
+}
+
+pub fn parse(input: &str) -> Result<Call, Error> {
+    let mut parser = Parser { input, pos: 0 };
+    let call = parser.call()?;
+    parser.skip_whitespace();
+    if parser.pos < input.len() {
+        return Err(parser.error("unexpected input after query", parser.pos, input.len()));
+    }
+    Ok(call)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn call(&mut self) -> Result<Call, Error> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let name = self.word();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(self.error("expected a query name", start, self.pos.max(start + 1)));
+        }
+        let name = name.to_string();
+
+        self.skip_whitespace();
+        let mut args = Vec::new();
+        if self.eat('(') {
+            self.skip_whitespace();
+            if !self.eat(')') {
+                loop {
+                    args.push(self.arg()?);
+                    self.skip_whitespace();
+                    if self.eat(')') {
+                        break;
+                    }
+                    if !self.eat(',') {
+                        return Err(self.error("expected ',' or ')'", self.pos, self.pos + 1));
+                    }
+                }
+            }
+        }
+
+        Ok(Call {
+            name,
+            args,
+            span: (start, self.pos - start).into(),
+        })
+    }
+
+    fn arg(&mut self) -> Result<Arg, Error> {
+        self.skip_whitespace();
+        let start = self.pos;
+        if self.eat('"') {
+            return self.quoted(start);
+        }
+
+        let word = self.word();
+        if word.is_empty() {
+            return Err(self.error("expected an argument", start, start + 1));
+        }
+        let after_word = self.pos;
+        self.skip_whitespace();
+        if self.peek() == Some('(') {
+            self.pos = start;
+            return Ok(Arg::Call(self.call()?));
+        }
+        self.pos = after_word;
+
+        Ok(Arg::Value(
+            word.to_string(),
+            (start, after_word - start).into(),
+        ))
+    }
+
+    fn quoted(&mut self, start: usize) -> Result<Arg, Error> {
+        let mut value = String::new();
+        let mut chars = self.input[self.pos..].char_indices();
+        while let Some((offset, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += offset + 1;
+                    return Ok(Arg::Value(value, (start, self.pos - start).into()));
+                }
+                '\\' => match chars.next() {
+                    Some((_, escaped)) => value.push(escaped),
+                    None => break,
+                },
+                c => value.push(c),
+            }
+        }
+
+        Err(self.error("unterminated string", start, self.input.len()))
+    }
+
+    fn word(&mut self) -> &'a str {
+        let rest = &self.input[self.pos..];
+        let len = rest
+            .find(|c: char| c.is_whitespace() || matches!(c, ',' | '(' | ')' | '"'))
+            .unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += expected.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.input[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn error(&self, message: &str, start: usize, end: usize) -> Error {
+        let end = end.min(self.input.len()).max(start);
+        Error::Parse {
+            message: message.to_string(),
+            text: self.input.to_string(),
+            span: Some((start, end - start).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
+    use super::*;
+
+    fn names(arg: &Arg) -> String {
+        match arg {
+            Arg::Value(value, _) => value.clone(),
+            Arg::Call(call) => format!(
+                "{}({})",
+                call.name,
+                call.args.iter().map(names).collect::<Vec<_>>().join(",")
+            ),
+        }
+    }
+
+    #[test_case("packages", "packages()" ; "bare name")]
+    #[test_case("packages()", "packages()" ; "empty args")]
+    #[test_case("dependents(@scope/ui)", "dependents(@scope/ui)" ; "scoped package")]
+    #[test_case(
+        "affected( apps/web/src/index.ts , packages/ui/package.json )",
+        "affected(apps/web/src/index.ts,packages/ui/package.json)" ;
+        "whitespace"
+    )]
+    #[test_case(r#"changed("dir with spaces/a.ts")"#, "changed(dir with spaces/a.ts)" ; "quoted")]
+    #[test_case(
+        "tasks(build, lint, affected(packages/ui/index.ts))",
+        "tasks(build,lint,affected(packages/ui/index.ts))" ;
+        "nested"
+    )]
+    #[test_case("tasks(web#build)", "tasks(web#build)" ; "package task")]
+    fn test_parse(input: &str, expected: &str) {
+        let call = parse(input).unwrap();
+        assert_eq!(names(&Arg::Call(call)), expected);
+    }
+
+    #[test_case("" ; "empty")]
+    #[test_case("packages(" ; "unclosed")]
+    #[test_case("dependents(a b)" ; "missing comma")]
+    #[test_case("dependents(a,)" ; "trailing comma")]
+    #[test_case("changed(\"a.ts)" ; "unterminated string")]
+    #[test_case("packages() extra" ; "trailing input")]
+    #[test_case("@scope/ui" ; "invalid name")]
+    fn test_parse_error(input: &str) {
+        assert!(matches!(parse(input), Err(Error::Parse { .. })));
+    }
+}
@@ -0,0 +1,494 @@
+//! Selection of the tasks affected by changes since a base ref for
+//! `turbo run --affected`. Rather than running every task in a package that
+//! changed, a task is only selected when a changed file is one of its hashed
+//! inputs or when a task it depends on was selected.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    fmt,
+};
+
+use miette::Diagnostic;
+use thiserror::Error;
+use tracing::debug;
+use turbopath::{AbsoluteSystemPath, AnchoredSystemPath, AnchoredSystemPathBuf};
+use turborepo_ci::Vendor;
+use turborepo_repository::{
+    change_mapper::ChangeMapper,
+    package_graph::{PackageGraph, PackageName},
+};
+use turborepo_scm::SCM;
+use turborepo_ui::{cprintln, GREY, UI};
+use wax::Program;
+
+use crate::{
+    engine::{Engine, TaskNode},
+    run::task_id::TaskId,
+    task_graph::{InputsMatcher, TaskDefinition},
+};
+
+/// Files that affect every task when they change, in addition to the
+/// configured global dependencies
+const DEFAULT_GLOBAL_DEPS: [&str; 2] = ["package.json", "turbo.json"];
+/// Branches that are tried, in order, when no base is given and CI doesn't
+/// report one
+const DEFAULT_BASE_BRANCHES: [&str; 4] = ["main", "origin/main", "master", "origin/master"];
+
+#[derive(Debug, Error, Diagnostic)]
+pub enum Error {
+    #[error("Unable to find a base to compare against for --affected")]
+    #[diagnostic(help("pass the ref to compare against with --affected=<ref>"))]
+    NoBase,
+    #[error("Unable to find a common ancestor of {base} and HEAD for --affected: {err}")]
+    MergeBase {
+        base: String,
+        err: turborepo_scm::Error,
+    },
+    #[error("Unable to find changed files for --affected: {0}")]
+    ChangedFiles(turborepo_scm::Error),
+    #[error("Invalid global dependency glob: {0}")]
+    GlobalDeps(Box<wax::BuildError>),
+    #[error("Invalid inputs for {task_id}: {err}")]
+    Inputs {
+        task_id: String,
+        err: Box<wax::BuildError>,
+    },
+}
+
+/// Why a task was selected to run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AffectedReason {
+    /// A file every task depends on changed
+    GlobalFile(AnchoredSystemPathBuf),
+    /// The lockfile changed, but we couldn't tell which packages it affects
+    Lockfile,
+    /// The external dependencies of the task's package changed
+    ExternalDependencies,
+    /// Files that are inputs to the task changed
+    Inputs(Vec<AnchoredSystemPathBuf>),
+    /// A task this task depends on was affected
+    Dependency(TaskId<'static>),
+}
+
+/// Which packages had external dependency changes according to the lockfile
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LockfileChange {
+    Unchanged,
+    Packages(HashSet<PackageName>),
+    Unknown,
+}
+
+#[derive(Debug)]
+pub struct AffectedTasks {
+    base: String,
+    merge_base: String,
+    tasks: BTreeMap<TaskId<'static>, AffectedReason>,
+}
+
+impl AffectedTasks {
+    /// Finds the tasks in `engine` affected by changes between `base`, or the
+    /// inferred base if none is given, and the working tree.
+    pub fn calculate(
+        repo_root: &AbsoluteSystemPath,
+        scm: &SCM,
+        base: Option<&str>,
+        pkg_dep_graph: &PackageGraph,
+        engine: &Engine,
+        global_deps: &[String],
+    ) -> Result<Self, Error> {
+        let (base, merge_base) = resolve_base(repo_root, scm, base)?;
+        debug!("--affected comparing against {base} ({merge_base})");
+
+        let changed_files = scm
+            .changed_files(repo_root, Some(&merge_base), "HEAD")
+            .map_err(Error::ChangedFiles)?;
+        let lockfile = lockfile_change(repo_root, scm, &merge_base, pkg_dep_graph, &changed_files);
+        let package_dirs = pkg_dep_graph
+            .packages()
+            .map(|(name, info)| (name.clone(), info.package_path().to_owned()))
+            .collect();
+
+        let tasks = select_tasks(
+            engine,
+            &package_dirs,
+            global_deps,
+            &changed_files,
+            &lockfile,
+        )?;
+
+        Ok(Self {
+            base,
+            merge_base,
+            tasks,
+        })
+    }
+
+    /// Restricts the engine to the affected tasks and the tasks they depend on
+    pub fn retain(&self, engine: &Engine) -> Engine {
+        engine.retain_with_dependencies(&self.tasks.keys().cloned().collect())
+    }
+
+    pub fn print(&self, ui: UI) {
+        let short_sha = self.merge_base.get(..7).unwrap_or(&self.merge_base);
+        if self.tasks.is_empty() {
+            cprintln!(
+                ui,
+                GREY,
+                "• No tasks affected by changes since {} ({short_sha})",
+                self.base
+            );
+            return;
+        }
+        cprintln!(
+            ui,
+            GREY,
+            "• Affected tasks since {} ({short_sha}):",
+            self.base
+        );
+        for (task_id, reason) in &self.tasks {
+            cprintln!(ui, GREY, "  {task_id}: {reason}");
+        }
+    }
+}
+
+/// Picks the ref to compare against and finds where HEAD diverged from it
+fn resolve_base(
+    repo_root: &AbsoluteSystemPath,
+    scm: &SCM,
+    base: Option<&str>,
+) -> Result<(String, String), Error> {
+    if let Some(base) = base {
+        let merge_base =
+            scm.merge_base(repo_root, base, "HEAD")
+                .map_err(|err| Error::MergeBase {
+                    base: base.to_string(),
+                    err,
+                })?;
+        return Ok((base.to_string(), merge_base));
+    }
+
+    // CI usually only has a shallow clone with remote branches, so try the
+    // remote version of the branch CI reports as well
+    let ci_candidates = Vendor::get_base_ref()
+        .into_iter()
+        .flat_map(|base| [format!("origin/{base}"), base]);
+    let candidates = ci_candidates.chain(DEFAULT_BASE_BRANCHES.map(String::from));
+    for candidate in candidates {
+        match scm.merge_base(repo_root, &candidate, "HEAD") {
+            Ok(merge_base) => return Ok((candidate, merge_base)),
+            Err(e) => debug!("unable to use {candidate} as the --affected base: {e}"),
+        }
+    }
+
+    Err(Error::NoBase)
+}
+
+/// Works out which packages' external dependencies changed. Does *not* error
+/// if the previous lockfile can't be read, instead the change is unknown.
+fn lockfile_change(
+    repo_root: &AbsoluteSystemPath,
+    scm: &SCM,
+    merge_base: &str,
+    pkg_dep_graph: &PackageGraph,
+    changed_files: &HashSet<AnchoredSystemPathBuf>,
+) -> LockfileChange {
+    let package_manager = pkg_dep_graph.package_manager();
+    let lockfile_path = package_manager.lockfile_path(repo_root);
+    if !ChangeMapper::lockfile_changed(repo_root, changed_files, &lockfile_path) {
+        return LockfileChange::Unchanged;
+    }
+
+    let Ok(content) = scm.previous_content(merge_base, &lockfile_path) else {
+        return LockfileChange::Unknown;
+    };
+    let Ok(previous) = package_manager.parse_lockfile(pkg_dep_graph.root_package_json(), &content)
+    else {
+        return LockfileChange::Unknown;
+    };
+    match pkg_dep_graph.changed_packages_from_lockfile(previous.as_ref()) {
+        Ok(packages) => {
+            LockfileChange::Packages(packages.into_iter().map(|package| package.name).collect())
+        }
+        Err(_) => LockfileChange::Unknown,
+    }
+}
+
+fn select_tasks(
+    engine: &Engine,
+    package_dirs: &HashMap<PackageName, AnchoredSystemPathBuf>,
+    global_deps: &[String],
+    changed_files: &HashSet<AnchoredSystemPathBuf>,
+    lockfile: &LockfileChange,
+) -> Result<BTreeMap<TaskId<'static>, AffectedReason>, Error> {
+    let mut changed_files = changed_files.iter().collect::<Vec<_>>();
+    changed_files.sort();
+
+    let global_matcher = wax::any(
+        global_deps
+            .iter()
+            .map(|glob| glob.as_str())
+            .filter(|glob| !glob.starts_with('!'))
+            .chain(DEFAULT_GLOBAL_DEPS),
+    )
+    .map_err(|err| Error::GlobalDeps(Box::new(err)))?;
+    let global_change = changed_files
+        .iter()
+        .find(|file| global_matcher.is_match(file.as_path()));
+
+    let mut tasks = BTreeMap::new();
+    for node in engine.tasks() {
+        let TaskNode::Task(task_id) = node else {
+            continue;
+        };
+        let package = PackageName::from(task_id.package());
+        let reason = if let Some(file) = global_change {
+            Some(AffectedReason::GlobalFile((*file).clone()))
+        } else if *lockfile == LockfileChange::Unknown {
+            Some(AffectedReason::Lockfile)
+        } else {
+            let inputs = match package_dirs.get(&package) {
+                Some(package_dir) => changed_inputs(
+                    task_id,
+                    engine.task_definition(task_id),
+                    package_dir,
+                    &changed_files,
+                )?,
+                None => Vec::new(),
+            };
+            if !inputs.is_empty() {
+                Some(AffectedReason::Inputs(inputs))
+            } else if matches!(lockfile, LockfileChange::Packages(packages) if packages.contains(&package))
+            {
+                Some(AffectedReason::ExternalDependencies)
+            } else {
+                None
+            }
+        };
+        if let Some(reason) = reason {
+            tasks.insert(task_id.clone(), reason);
+        }
+    }
+
+    // Anything downstream of an affected task needs to run as well since its
+    // hash includes the hashes of its dependencies
+    let mut queue = tasks.keys().cloned().collect::<VecDeque<_>>();
+    while let Some(task_id) = queue.pop_front() {
+        let mut dependents = engine
+            .dependents(&task_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|node| match node {
+                TaskNode::Task(dependent) => Some(dependent),
+                TaskNode::Root => None,
+            })
+            .collect::<Vec<_>>();
+        dependents.sort();
+        for dependent in dependents {
+            if !tasks.contains_key(dependent) {
+                tasks.insert(
+                    dependent.clone(),
+                    AffectedReason::Dependency(task_id.clone()),
+                );
+                queue.push_back(dependent.clone());
+            }
+        }
+    }
+
+    Ok(tasks)
+}
+
+/// Finds the changed files that are part of the task's hashed inputs
+fn changed_inputs(
+    task_id: &TaskId,
+    definition: Option<&TaskDefinition>,
+    package_dir: &AnchoredSystemPath,
+    changed_files: &[&AnchoredSystemPathBuf],
+) -> Result<Vec<AnchoredSystemPathBuf>, Error> {
+    let matcher = match definition {
+        Some(definition) => definition.inputs_matcher(),
+        None => InputsMatcher::new(&[], &[]),
+    }
+    .map_err(|err| Error::Inputs {
+        task_id: task_id.to_string(),
+        err: Box::new(err),
+    })?;
+
+    Ok(changed_files
+        .iter()
+        .filter(|file| {
+            let Ok(relative) = file.as_path().strip_prefix(package_dir.as_path()) else {
+                return false;
+            };
+            matcher.is_match(relative)
+        })
+        .map(|file| (*file).clone())
+        .collect())
+}
+
+impl fmt::Display for AffectedReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AffectedReason::GlobalFile(file) => write!(f, "global dependency {file} changed"),
+            AffectedReason::Lockfile => f.write_str("lockfile changed"),
+            AffectedReason::ExternalDependencies => f.write_str("external dependencies changed"),
+            AffectedReason::Inputs(files) => match files.as_slice() {
+                [file] => write!(f, "{file} changed"),
+                [file, rest @ ..] => write!(f, "{file} and {} other inputs changed", rest.len()),
+                [] => f.write_str("inputs changed"),
+            },
+            AffectedReason::Dependency(task_id) => write!(f, "depends on {task_id}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::*;
+
+    fn anchored(path: &str) -> AnchoredSystemPathBuf {
+        AnchoredSystemPathBuf::from_raw(path).unwrap()
+    }
+
+    fn engine(tasks: &[(&str, &str, &[&str])]) -> Engine {
+        let mut engine = Engine::new();
+        for (package, task, inputs) in tasks {
+            let task_id = TaskId::new(package, task).into_owned();
+            engine.connect_to_root(&task_id);
+            engine.add_definition(
+                task_id,
+                TaskDefinition {
+                    inputs: inputs.iter().map(|input| input.to_string()).collect(),
+                    ..Default::default()
+                },
+            );
+        }
+        engine.seal()
+    }
+
+    fn package_dirs() -> HashMap<PackageName, AnchoredSystemPathBuf> {
+        HashMap::from([
+            (PackageName::Root, anchored("")),
+            (PackageName::from("a"), anchored("packages/a")),
+            (PackageName::from("b"), anchored("packages/b")),
+        ])
+    }
+
+    #[test_case(&[], "packages/a/README.md", true ; "no inputs")]
+    #[test_case(&["src/**"], "packages/a/src/index.ts", true ; "matching input")]
+    #[test_case(&["src/**"], "packages/a/README.md", false ; "non-matching input")]
+    #[test_case(&["src/**"], "packages/a/package.json", true ; "package json always included")]
+    #[test_case(&["src/**", "!src/**/*.test.ts"], "packages/a/src/index.test.ts", false ; "excluded input")]
+    #[test_case(&["$TURBO_DEFAULT$"], "packages/a/README.md", true ; "default inputs")]
+    #[test_case(&["$TURBO_DEFAULT$", "!*.md"], "packages/a/README.md", false ; "default inputs with exclusion")]
+    #[test_case(&[], "packages/b/src/index.ts", false ; "other package")]
+    #[test_case(&[], "packages/ab/src/index.ts", false ; "package with shared prefix")]
+    fn test_changed_inputs(inputs: &[&str], file: &str, expected: bool) {
+        let task_id = TaskId::new("a", "build");
+        let definition = TaskDefinition {
+            inputs: inputs.iter().map(|input| input.to_string()).collect(),
+            ..Default::default()
+        };
+        let file = anchored(file);
+        let changed = changed_inputs(
+            &task_id,
+            Some(&definition),
+            &anchored("packages/a"),
+            &[&file],
+        )
+        .unwrap();
+        assert_eq!(!changed.is_empty(), expected);
+    }
+
+    #[test]
+    fn test_select_tasks_by_inputs() {
+        let engine = engine(&[
+            ("a", "build", &["src/**"]),
+            ("a", "lint", &[]),
+            ("b", "build", &["src/**"]),
+        ]);
+        let changed_files = HashSet::from([anchored("packages/a/README.md")]);
+
+        let tasks = select_tasks(
+            &engine,
+            &package_dirs(),
+            &[],
+            &changed_files,
+            &LockfileChange::Unchanged,
+        )
+        .unwrap();
+
+        assert_eq!(
+            tasks,
+            BTreeMap::from([(
+                TaskId::new("a", "lint").into_owned(),
+                AffectedReason::Inputs(vec![anchored("packages/a/README.md")])
+            )])
+        );
+    }
+
+    #[test_case(&[], "turbo.json" ; "default global dependency")]
+    #[test_case(&["tsconfig.*.json"], "tsconfig.base.json" ; "configured global dependency")]
+    fn test_select_tasks_global_change(global_deps: &[&str], file: &str) {
+        let engine = engine(&[("a", "build", &["src/**"]), ("b", "build", &[])]);
+        let global_deps = global_deps
+            .iter()
+            .map(|glob| glob.to_string())
+            .collect::<Vec<_>>();
+        let changed_files = HashSet::from([anchored(file)]);
+
+        let tasks = select_tasks(
+            &engine,
+            &package_dirs(),
+            &global_deps,
+            &changed_files,
+            &LockfileChange::Unchanged,
+        )
+        .unwrap();
+
+        assert_eq!(tasks.len(), 2);
+        assert!(tasks
+            .values()
+            .all(|reason| *reason == AffectedReason::GlobalFile(anchored(file))));
+    }
+
+    #[test]
+    fn test_select_tasks_lockfile() {
+        let engine = engine(&[("a", "build", &[]), ("b", "build", &[])]);
+        let changed_files = HashSet::new();
+
+        let tasks = select_tasks(
+            &engine,
+            &package_dirs(),
+            &[],
+            &changed_files,
+            &LockfileChange::Packages(HashSet::from([PackageName::from("b")])),
+        )
+        .unwrap();
+        assert_eq!(
+            tasks,
+            BTreeMap::from([(
+                TaskId::new("b", "build").into_owned(),
+                AffectedReason::ExternalDependencies
+            )])
+        );
+
+        let tasks = select_tasks(
+            &engine,
+            &package_dirs(),
+            &[],
+            &changed_files,
+            &LockfileChange::Unknown,
+        )
+        .unwrap();
+        assert_eq!(tasks.len(), 2);
+    }
+
+    #[test_case(AffectedReason::Inputs(vec![anchored("src/a.ts")]), "src/a.ts changed" ; "single input")]
+    #[test_case(AffectedReason::Inputs(vec![anchored("src/a.ts"), anchored("src/b.ts"), anchored("src/c.ts")]), "src/a.ts and 2 other inputs changed" ; "multiple inputs")]
+    #[test_case(AffectedReason::Dependency(TaskId::new("a", "build").into_owned()), "depends on a#build" ; "dependency")]
+    fn test_reason_display(reason: AffectedReason, expected: &str) {
+        assert_eq!(reason.to_string(), expected);
+    }
+}
@@ -5,7 +5,7 @@ use turborepo_repository::package_graph;
 use super::graph_visualizer;
 use crate::{
     config, daemon, engine, opts,
-    run::{global_hash, scope},
+    run::{affected, global_hash, scope},
     task_graph, task_hash,
 };
 
@@ -40,6 +40,9 @@ pub enum Error {
     #[error(transparent)]
     Scope(#[from] scope::ResolutionError),
     #[error(transparent)]
+    #[diagnostic(transparent)]
+    Affected(#[from] affected::Error),
+    #[error(transparent)]
     GlobalHash(#[from] global_hash::Error),
     #[error(transparent)]
     TaskHash(#[from] task_hash::Error),
@@ -1,14 +1,18 @@
 #![allow(dead_code)]
 
+mod affected;
 mod cache;
 mod error;
 pub(crate) mod global_hash;
 mod graph_visualizer;
+pub(crate) mod otel;
 pub(crate) mod package_discovery;
+pub(crate) mod sandbox;
 mod scope;
 pub(crate) mod summary;
 pub mod task_access;
 pub mod task_id;
+pub(crate) mod watch;
 
 use std::{
     collections::HashSet,
@@ -21,7 +25,7 @@ pub use cache::{ConfigCache, RunCache, TaskCache};
 use chrono::{DateTime, Local};
 use itertools::Itertools;
 use rayon::iter::ParallelBridge;
-use tracing::debug;
+use tracing::{debug, warn};
 use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPath};
 use turborepo_analytics::{start_analytics, AnalyticsHandle, AnalyticsSender};
 use turborepo_api_client::{APIAuth, APIClient};
@@ -39,7 +43,11 @@ use turborepo_telemetry::events::{
     repo::{RepoEventBuilder, RepoType},
     EventBuilder,
 };
-use turborepo_ui::{cprint, cprintln, ColorSelector, BOLD_GREY, GREY, UI};
+use turborepo_ui::{
+    cprint, cprintln,
+    tui::{self, AppSender},
+    ColorSelector, BOLD_GREY, GREY, UI,
+};
 #[cfg(feature = "daemon-package-discovery")]
 use {
     crate::run::package_discovery::DaemonPackageDiscovery,
@@ -52,13 +60,16 @@ use {
 use self::task_id::TaskName;
 pub use crate::run::error::Error;
 use crate::{
-    cli::{DryRunMode, EnvMode},
+    cli::{DryRunMode, EnvMode, SandboxMode, UIMode},
     commands::CommandBase,
     daemon::DaemonConnector,
-    engine::{Engine, EngineBuilder},
-    opts::Opts,
+    engine::{Engine, EngineBuilder, TaskNode},
+    opts::{Opts, RunOpts},
     process::ProcessManager,
-    run::{global_hash::get_global_hash_inputs, summary::RunTracker, task_access::TaskAccess},
+    run::{
+        affected::AffectedTasks, global_hash::get_global_hash_inputs, sandbox::Sandbox,
+        summary::RunTracker, task_access::TaskAccess,
+    },
     shim::TurboState,
     signal::{SignalHandler, SignalSubscriber},
     task_graph::Visitor,
@@ -81,25 +92,28 @@ impl Run {
         let mut opts: Opts = base.args().try_into()?;
         let config = base.config()?;
         let is_linked = turborepo_api_client::is_linked(&api_auth);
-        if !is_linked {
+        opts.cache_opts.remote_backend = config.remote_cache_backend()?;
+        if !is_linked && opts.cache_opts.remote_backend.requires_link() {
             opts.cache_opts.skip_remote = true;
         } else if let Some(enabled) = config.enabled {
             // We're linked, but if the user has explicitly enabled or disabled, use that
             // value
             opts.cache_opts.skip_remote = !enabled;
         }
-        // Note that we don't currently use the team_id value here. In the future, we
-        // should probably verify that we only use the signature value when the
-        // configured team_id matches the final resolved team_id.
-        let unused_remote_cache_opts_team_id = config.team_id().map(|team_id| team_id.to_string());
+        // The configured team_id is only used to sign artifacts for self-hosted
+        // backends. The Vercel backend signs with the linked team instead. In the
+        // future, we should probably verify that we only use the signature value
+        // when the configured team_id matches the final resolved team_id.
+        let remote_cache_opts_team_id = config.team_id().map(|team_id| team_id.to_string());
         let signature = config.signature();
-        opts.cache_opts.remote_cache_opts = Some(RemoteCacheOpts::new(
-            unused_remote_cache_opts_team_id,
-            signature,
-        ));
+        opts.cache_opts.remote_cache_opts =
+            Some(RemoteCacheOpts::new(remote_cache_opts_team_id, signature));
+        opts.cache_opts.fs_limits = config.fs_cache_limits()?;
         if opts.run_opts.experimental_space_id.is_none() {
             opts.run_opts.experimental_space_id = config.spaces_id().map(|s| s.to_owned());
         }
+        opts.run_opts.otel = config.otel()?;
+        opts.run_opts.package_discovery = config.package_discovery()?;
         let version = base.version();
         let CommandBase { repo_root, ui, .. } = base;
         Ok(Self {
@@ -283,7 +297,14 @@ impl Run {
 
         let mut pkg_dep_graph = {
             let builder = PackageGraph::builder(&self.repo_root, root_package_json.clone())
-                .with_single_package_mode(self.opts.run_opts.single_package);
+                .with_single_package_mode(self.opts.run_opts.single_package)
+                .with_manifest_discovery(
+                    self.opts
+                        .run_opts
+                        .package_discovery
+                        .iter()
+                        .map(|ecosystem| ecosystem.discovery()),
+                );
 
             #[cfg(feature = "daemon-package-discovery")]
             let builder = {
@@ -369,8 +390,39 @@ impl Run {
         let env_at_execution_start = EnvironmentVariableMap::infer();
         let mut engine = self.build_engine(&pkg_dep_graph, &root_turbo_json, &filtered_pkgs)?;
 
+        let global_deps = root_turbo_json
+            .global_deps
+            .iter()
+            .chain(&self.opts.scope_opts.global_deps)
+            .cloned()
+            .chain(
+                root_turbo_json
+                    .global_dot_env
+                    .iter()
+                    .flatten()
+                    .map(|path| path.to_string()),
+            )
+            .collect::<Vec<_>>();
+        let affected = match &self.opts.scope_opts.affected {
+            Some(base) => Some(AffectedTasks::calculate(
+                &self.repo_root,
+                &scm,
+                base.as_deref(),
+                &pkg_dep_graph,
+                &engine,
+                &global_deps,
+            )?),
+            None => None,
+        };
+        if let Some(affected) = &affected {
+            engine = affected.retain(&engine);
+        }
+
         if self.opts.run_opts.dry_run.is_none() && self.opts.run_opts.graph.is_none() {
             self.print_run_prelude(&filtered_pkgs);
+            if let Some(affected) = &affected {
+                affected.print(self.ui);
+            }
         }
 
         let root_workspace = pkg_dep_graph
@@ -403,6 +455,20 @@ impl Run {
 
         let color_selector = ColorSelector::default();
 
+        let workspaces = pkg_dep_graph.packages().collect();
+        let daemon_file_hashes = match &daemon {
+            Some(daemon) => {
+                PackageInputsHashes::fetch_from_daemon(
+                    daemon,
+                    engine.tasks(),
+                    &workspaces,
+                    engine.task_definitions(),
+                )
+                .await
+            }
+            None => Default::default(),
+        };
+
         let runcache = Arc::new(RunCache::new(
             async_cache,
             &self.repo_root,
@@ -429,7 +495,6 @@ impl Run {
             global_env_mode = EnvMode::Strict;
         }
 
-        let workspaces = pkg_dep_graph.packages().collect();
         let package_inputs_hashes = PackageInputsHashes::calculate_file_hashes(
             &scm,
             engine.tasks().par_bridge(),
@@ -437,11 +502,15 @@ impl Run {
             engine.task_definitions(),
             &self.repo_root,
             &run_telemetry,
+            &daemon_file_hashes,
         )?;
 
         if self.opts.run_opts.parallel {
             pkg_dep_graph.remove_package_dependencies();
             engine = self.build_engine(&pkg_dep_graph, &root_turbo_json, &filtered_pkgs)?;
+            if let Some(affected) = &affected {
+                engine = affected.retain(&engine);
+            }
         }
 
         if let Some(graph_opts) = &self.opts.run_opts.graph {
@@ -470,6 +539,15 @@ impl Run {
             env
         };
 
+        // Variables from injected .env files make it through strict mode if
+        // they match the global env, as they would if they were already set
+        let global_env_patterns = root_turbo_json
+            .global_env
+            .iter()
+            .chain(root_turbo_json.global_pass_through_env.iter().flatten())
+            .cloned()
+            .collect();
+
         let run_tracker = RunTracker::new(
             start_at,
             self.opts.synthesize_command(),
@@ -484,6 +562,29 @@ impl Run {
             &scm,
         );
 
+        let sandbox = (self.opts.run_opts.sandbox != SandboxMode::Off).then(|| {
+            // The lockfile is covered by the hash of each package's external
+            // dependencies, so the package manager reading it is expected
+            let lockfile = pkg_dep_graph
+                .package_manager()
+                .lockfile_path(&self.repo_root);
+            let global_deps = global_deps
+                .into_iter()
+                .chain(
+                    self.repo_root
+                        .anchor(lockfile)
+                        .map(|lockfile| lockfile.to_unix().to_string()),
+                )
+                .collect();
+            Sandbox::new(
+                self.opts.run_opts.sandbox,
+                self.repo_root.clone(),
+                &pkg_dep_graph,
+                global_deps,
+                &global_env,
+            )
+        });
+
         let mut visitor = Visitor::new(
             pkg_dep_graph.clone(),
             runcache,
@@ -499,17 +600,45 @@ impl Run {
             self.processes.clone(),
             &self.repo_root,
             global_env,
+            global_env_patterns,
         );
 
         if self.opts.run_opts.dry_run.is_some() {
             visitor.dry_run();
+        } else if let Some(sandbox) = sandbox {
+            visitor.sandbox(sandbox);
         }
 
+        let tui = (self.opts.run_opts.ui_mode == UIMode::Tui).then(|| {
+            let tasks = Self::tui_tasks(&engine, &pkg_dep_graph, &self.opts.run_opts);
+            let (sender, receiver) = AppSender::new();
+            // Raw mode swallows ctrl-c, so the UI shuts the run down itself
+            let signal_handler = signal_handler.clone();
+            let runtime = tokio::runtime::Handle::current();
+            let handle = std::thread::spawn(move || {
+                tui::run_app(tasks, receiver, || {
+                    let signal_handler = signal_handler.clone();
+                    runtime.spawn(async move { signal_handler.close().await });
+                })
+            });
+            visitor.tui_sender(sender.clone());
+            (sender, handle)
+        });
+
         // we look for this log line to mark the start of the run
         // in benchmarks, so please don't remove it
         debug!("running visitor");
 
-        let errors = visitor.visit(engine.clone(), &run_telemetry).await?;
+        let errors = visitor.visit(engine.clone(), &run_telemetry).await;
+
+        if let Some((sender, handle)) = tui {
+            sender.stop();
+            match handle.join().expect("terminal UI panicked") {
+                Ok(()) => (),
+                Err(e) => warn!("terminal UI failed: {e}"),
+            }
+        }
+        let errors = errors?;
 
         let exit_code = errors
             .iter()
@@ -541,6 +670,33 @@ impl Run {
         Ok(exit_code)
     }
 
+    /// The tasks to show in the terminal UI, leaving out tasks without a
+    /// command as they never run
+    fn tui_tasks(engine: &Engine, pkg_dep_graph: &PackageGraph, run_opts: &RunOpts) -> Vec<String> {
+        engine
+            .tasks()
+            .filter_map(|node| match node {
+                TaskNode::Task(task) => Some(task),
+                TaskNode::Root => None,
+            })
+            .filter(|task| {
+                let package_json = pkg_dep_graph.package_json(&PackageName::from(task.package()));
+                engine
+                    .task_definition(task)
+                    .zip(package_json)
+                    .and_then(|(task_definition, package_json)| {
+                        task_definition.resolved_command(package_json, task.task())
+                    })
+                    .is_some()
+            })
+            .map(|task| match run_opts.single_package {
+                true => task.task().to_string(),
+                false => task.to_string(),
+            })
+            .sorted()
+            .collect()
+    }
+
     fn build_engine(
         &self,
         pkg_dep_graph: &PackageGraph,
@@ -0,0 +1,748 @@
+//! Exports runs to an OpenTelemetry collector over OTLP. Each run is sent as
+//! one trace with a child span per task, along with metrics describing how
+//! much of the run was served from the cache.
+mod proto;
+
+use std::{collections::BTreeMap, time::Duration};
+
+use prost::Message;
+use thiserror::Error;
+use tonic::{
+    codec::ProstCodec,
+    codegen::http::uri::PathAndQuery,
+    metadata::{MetadataKey, MetadataValue},
+    transport::{ClientTlsConfig, Endpoint},
+};
+
+use self::proto::{
+    any_value, metric, number_data_point, AnyValue, ExportMetricsServiceRequest,
+    ExportMetricsServiceResponse, ExportPartialSuccess, ExportTraceServiceRequest,
+    ExportTraceServiceResponse, Gauge, InstrumentationScope, KeyValue, Metric, NumberDataPoint,
+    Resource, ResourceMetrics, ResourceSpans, ScopeMetrics, ScopeSpans, Span, Status, Sum,
+};
+
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("invalid OTLP endpoint {0}")]
+    InvalidEndpoint(String),
+    #[error("invalid OTLP header {0}")]
+    InvalidHeader(String),
+    #[error("failed to send OTLP request: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("OTLP collector responded to {path} with {status}")]
+    HttpStatus {
+        path: &'static str,
+        status: reqwest::StatusCode,
+    },
+    #[error("failed to decode OTLP response: {0}")]
+    Decode(#[from] prost::DecodeError),
+    #[error("failed to connect to OTLP collector: {0}")]
+    Transport(#[from] tonic::transport::Error),
+    #[error("OTLP collector rejected the export: {0}")]
+    Grpc(#[from] tonic::Status),
+    #[error("OTLP collector rejected {rejected} {kind}: {message}")]
+    PartiallyRejected {
+        kind: &'static str,
+        rejected: i64,
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OtelProtocol {
+    HttpProtobuf,
+    Grpc,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OtelOpts {
+    /// Base URL of the collector. Signal paths such as `/v1/traces` are
+    /// appended to it when exporting over HTTP.
+    pub endpoint: String,
+    pub protocol: OtelProtocol,
+    pub headers: BTreeMap<String, String>,
+}
+
+/// A finished run, as exported to the collector. Times are in milliseconds
+/// since the Unix epoch, matching the run summary.
+#[derive(Debug)]
+pub struct RunTrace<'a> {
+    pub run_id: String,
+    pub trace_id: [u8; 16],
+    pub command: &'a str,
+    pub turbo_version: &'a str,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub exit_code: i32,
+    pub tasks: Vec<TaskSpan<'a>>,
+}
+
+#[derive(Debug)]
+pub struct TaskSpan<'a> {
+    pub task_id: String,
+    pub package: &'a str,
+    pub hash: &'a str,
+    pub cache_hit: bool,
+    pub cache_source: Option<&'static str>,
+    pub time_saved: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub exit_code: Option<i32>,
+    pub error: Option<&'a str>,
+}
+
+/// Sends the run's trace and cache metrics to the configured collector
+pub async fn export(opts: &OtelOpts, run: &RunTrace<'_>) -> Result<(), Error> {
+    let traces = run.traces();
+    let metrics = run.metrics();
+    match opts.protocol {
+        OtelProtocol::HttpProtobuf => {
+            let client = reqwest::Client::builder().timeout(EXPORT_TIMEOUT).build()?;
+            let response: ExportTraceServiceResponse =
+                http_export(&client, opts, "/v1/traces", traces).await?;
+            check_partial_success("spans", response.partial_success)?;
+            let response: ExportMetricsServiceResponse =
+                http_export(&client, opts, "/v1/metrics", metrics).await?;
+            check_partial_success("data points", response.partial_success)
+        }
+        OtelProtocol::Grpc => {
+            let mut endpoint = Endpoint::from_shared(opts.endpoint.clone())
+                .map_err(|_| Error::InvalidEndpoint(opts.endpoint.clone()))?
+                .timeout(EXPORT_TIMEOUT)
+                .connect_timeout(EXPORT_TIMEOUT);
+            if opts.endpoint.starts_with("https://") {
+                endpoint = endpoint.tls_config(ClientTlsConfig::new())?;
+            }
+            let mut client = tonic::client::Grpc::new(endpoint.connect().await?);
+            let response: ExportTraceServiceResponse =
+                grpc_export(&mut client, opts, proto::TRACE_SERVICE_EXPORT, traces).await?;
+            check_partial_success("spans", response.partial_success)?;
+            let response: ExportMetricsServiceResponse =
+                grpc_export(&mut client, opts, proto::METRICS_SERVICE_EXPORT, metrics).await?;
+            check_partial_success("data points", response.partial_success)
+        }
+    }
+}
+
+async fn http_export<Req: Message, Res: Message + Default>(
+    client: &reqwest::Client,
+    opts: &OtelOpts,
+    path: &'static str,
+    request: Req,
+) -> Result<Res, Error> {
+    let url = format!("{}{}", opts.endpoint.trim_end_matches('/'), path);
+    let mut builder = client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/x-protobuf")
+        .body(request.encode_to_vec());
+    for (key, value) in &opts.headers {
+        builder = builder.header(key, value);
+    }
+    let response = builder.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(Error::HttpStatus { path, status });
+    }
+    Ok(Res::decode(response.bytes().await?)?)
+}
+
+async fn grpc_export<Req, Res>(
+    client: &mut tonic::client::Grpc<tonic::transport::Channel>,
+    opts: &OtelOpts,
+    path: &'static str,
+    request: Req,
+) -> Result<Res, Error>
+where
+    Req: Message + Send + Sync + 'static,
+    Res: Message + Default + Send + Sync + 'static,
+{
+    let mut request = tonic::Request::new(request);
+    for (key, value) in &opts.headers {
+        let key = MetadataKey::from_bytes(key.to_ascii_lowercase().as_bytes())
+            .map_err(|_| Error::InvalidHeader(key.clone()))?;
+        let value = MetadataValue::try_from(value.as_str())
+            .map_err(|_| Error::InvalidHeader(key.to_string()))?;
+        request.metadata_mut().insert(key, value);
+    }
+    client
+        .ready()
+        .await
+        .map_err(|err| tonic::Status::unavailable(err.to_string()))?;
+    let response = client
+        .unary(
+            request,
+            PathAndQuery::from_static(path),
+            ProstCodec::default(),
+        )
+        .await?;
+    Ok(response.into_inner())
+}
+
+fn check_partial_success(
+    kind: &'static str,
+    partial_success: Option<ExportPartialSuccess>,
+) -> Result<(), Error> {
+    match partial_success {
+        Some(ExportPartialSuccess {
+            rejected,
+            error_message,
+        }) if rejected > 0 => Err(Error::PartiallyRejected {
+            kind,
+            rejected,
+            message: error_message,
+        }),
+        _ => Ok(()),
+    }
+}
+
+impl<'a> RunTrace<'a> {
+    fn traces(&self) -> ExportTraceServiceRequest {
+        let root_span_id = rand::random::<[u8; 8]>().to_vec();
+        let root = Span {
+            trace_id: self.trace_id.to_vec(),
+            span_id: root_span_id.clone(),
+            parent_span_id: Vec::new(),
+            name: "turbo run".to_string(),
+            kind: proto::SPAN_KIND_INTERNAL,
+            start_time_unix_nano: unix_nanos(self.start_time),
+            end_time_unix_nano: unix_nanos(self.end_time),
+            attributes: vec![
+                attribute("turbo.run.id", self.run_id.as_str()),
+                attribute("turbo.command", self.command),
+                attribute("turbo.exit_code", i64::from(self.exit_code)),
+            ],
+            status: Some(status(
+                self.exit_code == 0,
+                (self.exit_code != 0).then(|| format!("run exited with code {}", self.exit_code)),
+            )),
+        };
+
+        let spans = std::iter::once(root)
+            .chain(self.tasks.iter().map(|task| {
+                let mut attributes = vec![
+                    attribute("turbo.task.id", task.task_id.as_str()),
+                    attribute("turbo.package", task.package),
+                    attribute("turbo.task.hash", task.hash),
+                    attribute(
+                        "turbo.task.cache.status",
+                        if task.cache_hit { "HIT" } else { "MISS" },
+                    ),
+                    attribute("turbo.task.cache.time_saved_ms", task.time_saved as i64),
+                ];
+                if let Some(source) = task.cache_source {
+                    attributes.push(attribute("turbo.task.cache.source", source));
+                }
+                if let Some(exit_code) = task.exit_code {
+                    attributes.push(attribute("turbo.task.exit_code", i64::from(exit_code)));
+                }
+                let succeeded = task.error.is_none() && task.exit_code.unwrap_or(0) == 0;
+                Span {
+                    trace_id: self.trace_id.to_vec(),
+                    span_id: rand::random::<[u8; 8]>().to_vec(),
+                    parent_span_id: root_span_id.clone(),
+                    name: task.task_id.clone(),
+                    kind: proto::SPAN_KIND_INTERNAL,
+                    start_time_unix_nano: unix_nanos(task.start_time),
+                    end_time_unix_nano: unix_nanos(task.end_time),
+                    attributes,
+                    status: Some(status(succeeded, task.error.map(|e| e.to_string()))),
+                }
+            }))
+            .collect();
+
+        ExportTraceServiceRequest {
+            resource_spans: vec![ResourceSpans {
+                resource: Some(self.resource()),
+                scope_spans: vec![ScopeSpans {
+                    scope: Some(self.scope()),
+                    spans,
+                }],
+            }],
+        }
+    }
+
+    fn metrics(&self) -> ExportMetricsServiceRequest {
+        let hits = self.tasks.iter().filter(|task| task.cache_hit).count() as i64;
+        let misses = self.tasks.len() as i64 - hits;
+        let time_saved = self.tasks.iter().map(|task| task.time_saved as i64).sum();
+        let start_time = unix_nanos(self.start_time);
+        let end_time = unix_nanos(self.end_time);
+        let point = |value| NumberDataPoint {
+            start_time_unix_nano: start_time,
+            time_unix_nano: end_time,
+            attributes: Vec::new(),
+            value: Some(value),
+        };
+        let sum = |name: &str, description: &str, unit: &str, value: i64| Metric {
+            name: name.to_string(),
+            description: description.to_string(),
+            unit: unit.to_string(),
+            data: Some(metric::Data::Sum(Sum {
+                data_points: vec![point(number_data_point::Value::AsInt(value))],
+                aggregation_temporality: proto::AGGREGATION_TEMPORALITY_DELTA,
+                is_monotonic: true,
+            })),
+        };
+
+        let mut metrics = vec![
+            sum(
+                "turbo.cache.hits",
+                "Tasks restored from the cache",
+                "{task}",
+                hits,
+            ),
+            sum(
+                "turbo.cache.misses",
+                "Tasks that had to be executed",
+                "{task}",
+                misses,
+            ),
+            sum(
+                "turbo.cache.time_saved",
+                "Time the cache hits originally took to execute",
+                "ms",
+                time_saved,
+            ),
+        ];
+        // A run without any tasks has no meaningful hit rate
+        if !self.tasks.is_empty() {
+            metrics.push(Metric {
+                name: "turbo.cache.hit_rate".to_string(),
+                description: "Fraction of tasks restored from the cache".to_string(),
+                unit: "1".to_string(),
+                data: Some(metric::Data::Gauge(Gauge {
+                    data_points: vec![point(number_data_point::Value::AsDouble(
+                        hits as f64 / self.tasks.len() as f64,
+                    ))],
+                })),
+            });
+        }
+
+        ExportMetricsServiceRequest {
+            resource_metrics: vec![ResourceMetrics {
+                resource: Some(self.resource()),
+                scope_metrics: vec![ScopeMetrics {
+                    scope: Some(self.scope()),
+                    metrics,
+                }],
+            }],
+        }
+    }
+
+    fn resource(&self) -> Resource {
+        Resource {
+            attributes: vec![
+                attribute("service.name", "turbo"),
+                attribute("service.version", self.turbo_version),
+            ],
+        }
+    }
+
+    fn scope(&self) -> InstrumentationScope {
+        InstrumentationScope {
+            name: "turbo".to_string(),
+            version: self.turbo_version.to_string(),
+        }
+    }
+}
+
+fn status(ok: bool, message: Option<String>) -> Status {
+    Status {
+        message: message.unwrap_or_default(),
+        code: if ok {
+            proto::STATUS_CODE_OK
+        } else {
+            proto::STATUS_CODE_ERROR
+        },
+    }
+}
+
+fn unix_nanos(millis: i64) -> u64 {
+    u64::try_from(millis).unwrap_or_default() * 1_000_000
+}
+
+fn attribute(key: &str, value: impl Into<any_value::Value>) -> KeyValue {
+    KeyValue {
+        key: key.to_string(),
+        value: Some(AnyValue {
+            value: Some(value.into()),
+        }),
+    }
+}
+
+impl From<&str> for any_value::Value {
+    fn from(value: &str) -> Self {
+        Self::StringValue(value.to_string())
+    }
+}
+
+impl From<i64> for any_value::Value {
+    fn from(value: i64) -> Self {
+        Self::IntValue(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        convert::Infallible,
+        marker::PhantomData,
+        net::SocketAddr,
+        sync::{Arc, Mutex},
+        task::{Context, Poll},
+    };
+
+    use axum::{
+        body::Bytes,
+        extract::State,
+        http::{HeaderMap, StatusCode},
+        routing::post,
+        Router,
+    };
+    use futures::future::BoxFuture;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::{
+        body::BoxBody,
+        codegen::{http, Service},
+        server::NamedService,
+        transport::{Body, Server},
+    };
+
+    use super::*;
+
+    /// Everything a collector stub was sent, along with the value of the
+    /// `x-team` header on the last request
+    #[derive(Default)]
+    struct Received {
+        traces: Vec<ExportTraceServiceRequest>,
+        metrics: Vec<ExportMetricsServiceRequest>,
+        team: Option<String>,
+    }
+
+    type Shared = Arc<Mutex<Received>>;
+
+    fn run_trace() -> RunTrace<'static> {
+        RunTrace {
+            run_id: "2aXHCBk8Dx2zYAZjGswYECzKFSn".to_string(),
+            trace_id: [7; 16],
+            command: "turbo run build",
+            turbo_version: "1.0.0",
+            start_time: 1_000,
+            end_time: 5_000,
+            exit_code: 1,
+            tasks: vec![
+                TaskSpan {
+                    task_id: "web#build".to_string(),
+                    package: "web",
+                    hash: "abc123",
+                    cache_hit: true,
+                    cache_source: Some("LOCAL"),
+                    time_saved: 1_500,
+                    start_time: 1_100,
+                    end_time: 1_200,
+                    exit_code: Some(0),
+                    error: None,
+                },
+                TaskSpan {
+                    task_id: "docs#build".to_string(),
+                    package: "docs",
+                    hash: "def456",
+                    cache_hit: false,
+                    cache_source: None,
+                    time_saved: 0,
+                    start_time: 1_100,
+                    end_time: 4_900,
+                    exit_code: Some(2),
+                    error: Some("command exited (2)"),
+                },
+            ],
+        }
+    }
+
+    fn opts(endpoint: String, protocol: OtelProtocol) -> OtelOpts {
+        OtelOpts {
+            endpoint,
+            protocol,
+            headers: [("X-Team".to_string(), "web".to_string())]
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    async fn http_collector(received: Shared) -> SocketAddr {
+        async fn traces(
+            State(received): State<Shared>,
+            headers: HeaderMap,
+            body: Bytes,
+        ) -> Result<Vec<u8>, StatusCode> {
+            let request =
+                ExportTraceServiceRequest::decode(body).map_err(|_| StatusCode::BAD_REQUEST)?;
+            let mut received = received.lock().unwrap();
+            received.traces.push(request);
+            received.team = headers
+                .get("x-team")
+                .and_then(|team| team.to_str().ok())
+                .map(|team| team.to_string());
+            Ok(ExportTraceServiceResponse::default().encode_to_vec())
+        }
+
+        async fn metrics(
+            State(received): State<Shared>,
+            body: Bytes,
+        ) -> Result<Vec<u8>, StatusCode> {
+            let request =
+                ExportMetricsServiceRequest::decode(body).map_err(|_| StatusCode::BAD_REQUEST)?;
+            received.lock().unwrap().metrics.push(request);
+            Ok(ExportMetricsServiceResponse::default().encode_to_vec())
+        }
+
+        let app = Router::new()
+            .route("/v1/traces", post(traces))
+            .route("/v1/metrics", post(metrics))
+            .with_state(received);
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = axum::Server::from_tcp(listener)
+            .unwrap()
+            .serve(app.into_make_service());
+        tokio::spawn(server);
+        addr
+    }
+
+    /// A gRPC service that decodes a single OTLP export request type and
+    /// records it
+    struct GrpcStub<Req, Res> {
+        received: Shared,
+        record: fn(&mut Received, Req, &tonic::metadata::MetadataMap),
+        _response: PhantomData<fn() -> Res>,
+    }
+
+    impl<Req, Res> Clone for GrpcStub<Req, Res> {
+        fn clone(&self) -> Self {
+            Self {
+                received: self.received.clone(),
+                record: self.record,
+                _response: PhantomData,
+            }
+        }
+    }
+
+    impl NamedService for GrpcStub<ExportTraceServiceRequest, ExportTraceServiceResponse> {
+        const NAME: &'static str = "opentelemetry.proto.collector.trace.v1.TraceService";
+    }
+
+    impl NamedService for GrpcStub<ExportMetricsServiceRequest, ExportMetricsServiceResponse> {
+        const NAME: &'static str = "opentelemetry.proto.collector.metrics.v1.MetricsService";
+    }
+
+    impl<Req, Res> Service<http::Request<Body>> for GrpcStub<Req, Res>
+    where
+        Req: Message + Default + Send + 'static,
+        Res: Message + Default + Send + 'static,
+    {
+        type Response = http::Response<BoxBody>;
+        type Error = Infallible;
+        type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: http::Request<Body>) -> Self::Future {
+            let stub = self.clone();
+            Box::pin(async move {
+                let handler = tower::service_fn(move |request: tonic::Request<Req>| {
+                    let stub = stub.clone();
+                    async move {
+                        let metadata = request.metadata().clone();
+                        let mut received = stub.received.lock().unwrap();
+                        (stub.record)(&mut received, request.into_inner(), &metadata);
+                        Ok::<_, tonic::Status>(tonic::Response::new(Res::default()))
+                    }
+                });
+                let mut grpc = tonic::server::Grpc::new(ProstCodec::<Res, Req>::default());
+                Ok(grpc.unary(handler, request).await)
+            })
+        }
+    }
+
+    async fn grpc_collector(received: Shared) -> SocketAddr {
+        let traces = GrpcStub::<ExportTraceServiceRequest, ExportTraceServiceResponse> {
+            received: received.clone(),
+            record: |received, request, metadata| {
+                received.traces.push(request);
+                received.team = metadata
+                    .get("x-team")
+                    .and_then(|team| team.to_str().ok())
+                    .map(|team| team.to_string());
+            },
+            _response: PhantomData,
+        };
+        let metrics = GrpcStub::<ExportMetricsServiceRequest, ExportMetricsServiceResponse> {
+            received,
+            record: |received, request, _| received.metrics.push(request),
+            _response: PhantomData,
+        };
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = Server::builder()
+            .add_service(traces)
+            .add_service(metrics)
+            .serve_with_incoming(TcpListenerStream::new(listener));
+        tokio::spawn(server);
+        addr
+    }
+
+    fn string_attribute<'a>(attributes: &'a [KeyValue], key: &str) -> Option<&'a str> {
+        attributes
+            .iter()
+            .find(|attribute| attribute.key == key)
+            .and_then(
+                |attribute| match attribute.value.as_ref()?.value.as_ref()? {
+                    any_value::Value::StringValue(value) => Some(value.as_str()),
+                    _ => None,
+                },
+            )
+    }
+
+    fn int_attribute(attributes: &[KeyValue], key: &str) -> Option<i64> {
+        attributes
+            .iter()
+            .find(|attribute| attribute.key == key)
+            .and_then(
+                |attribute| match attribute.value.as_ref()?.value.as_ref()? {
+                    any_value::Value::IntValue(value) => Some(*value),
+                    _ => None,
+                },
+            )
+    }
+
+    fn assert_received(received: &Received) {
+        assert_eq!(received.team.as_deref(), Some("web"));
+
+        assert_eq!(received.traces.len(), 1);
+        let resource_spans = &received.traces[0].resource_spans[0];
+        let resource = resource_spans.resource.as_ref().unwrap();
+        assert_eq!(
+            string_attribute(&resource.attributes, "service.name"),
+            Some("turbo")
+        );
+        let spans = &resource_spans.scope_spans[0].spans;
+        assert_eq!(spans.len(), 3);
+        let root = &spans[0];
+        assert!(spans
+            .iter()
+            .all(|span| span.trace_id == vec![7; 16] && span.span_id.len() == 8));
+        assert_eq!(root.status.as_ref().unwrap().code, proto::STATUS_CODE_ERROR);
+        assert_eq!(root.start_time_unix_nano, 1_000_000_000);
+
+        let web = spans.iter().find(|span| span.name == "web#build").unwrap();
+        assert_eq!(web.parent_span_id, root.span_id);
+        assert_eq!(
+            string_attribute(&web.attributes, "turbo.package"),
+            Some("web")
+        );
+        assert_eq!(
+            string_attribute(&web.attributes, "turbo.task.hash"),
+            Some("abc123")
+        );
+        assert_eq!(
+            string_attribute(&web.attributes, "turbo.task.cache.status"),
+            Some("HIT")
+        );
+        assert_eq!(
+            string_attribute(&web.attributes, "turbo.task.cache.source"),
+            Some("LOCAL")
+        );
+        assert_eq!(web.status.as_ref().unwrap().code, proto::STATUS_CODE_OK);
+
+        let docs = spans.iter().find(|span| span.name == "docs#build").unwrap();
+        assert_eq!(
+            string_attribute(&docs.attributes, "turbo.task.cache.status"),
+            Some("MISS")
+        );
+        assert_eq!(
+            int_attribute(&docs.attributes, "turbo.task.exit_code"),
+            Some(2)
+        );
+        let docs_status = docs.status.as_ref().unwrap();
+        assert_eq!(docs_status.code, proto::STATUS_CODE_ERROR);
+        assert_eq!(docs_status.message, "command exited (2)");
+
+        assert_eq!(received.metrics.len(), 1);
+        let metrics = &received.metrics[0].resource_metrics[0].scope_metrics[0].metrics;
+        let value = |name: &str| {
+            let metric = metrics.iter().find(|metric| metric.name == name).unwrap();
+            let points = match metric.data.as_ref().unwrap() {
+                metric::Data::Gauge(gauge) => &gauge.data_points,
+                metric::Data::Sum(sum) => &sum.data_points,
+            };
+            points[0].value.clone().unwrap()
+        };
+        assert_eq!(
+            value("turbo.cache.hit_rate"),
+            number_data_point::Value::AsDouble(0.5)
+        );
+        assert_eq!(
+            value("turbo.cache.hits"),
+            number_data_point::Value::AsInt(1)
+        );
+        assert_eq!(
+            value("turbo.cache.misses"),
+            number_data_point::Value::AsInt(1)
+        );
+        assert_eq!(
+            value("turbo.cache.time_saved"),
+            number_data_point::Value::AsInt(1_500)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_http() {
+        let received = Shared::default();
+        let addr = http_collector(received.clone()).await;
+
+        let opts = opts(format!("http://{addr}/"), OtelProtocol::HttpProtobuf);
+        export(&opts, &run_trace()).await.unwrap();
+
+        assert_received(&received.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_export_grpc() {
+        let received = Shared::default();
+        let addr = grpc_collector(received.clone()).await;
+
+        let opts = opts(format!("http://{addr}"), OtelProtocol::Grpc);
+        export(&opts, &run_trace()).await.unwrap();
+
+        assert_received(&received.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_export_http_error_status() {
+        let app = Router::new().route(
+            "/v1/traces",
+            post(|| async { StatusCode::SERVICE_UNAVAILABLE }),
+        );
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service()),
+        );
+
+        let opts = opts(format!("http://{addr}"), OtelProtocol::HttpProtobuf);
+        let err = export(&opts, &run_trace()).await.unwrap_err();
+        assert!(matches!(
+            err,
+            Error::HttpStatus {
+                path: "/v1/traces",
+                status: StatusCode::SERVICE_UNAVAILABLE,
+            }
+        ));
+    }
+}
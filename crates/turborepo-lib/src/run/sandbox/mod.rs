@@ -0,0 +1,707 @@
+//! Observes the files each task reads and writes while it runs and checks them
+//! against what the task declares in turbo.json. A task that reads a file
+//! which isn't part of its hash gets restored from the cache even after that
+//! file changes, so undeclared inputs poison the cache.
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+mod tracer;
+
+use std::{
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
+    path::Path,
+    sync::Arc,
+};
+
+use camino::Utf8Path;
+use miette::Diagnostic;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use thiserror::Error;
+use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPath, AnchoredSystemPathBuf};
+use turborepo_env::EnvironmentVariableMap;
+use turborepo_repository::package_graph::{PackageGraph, PackageName};
+use wax::Program;
+
+use crate::{
+    cli::SandboxMode,
+    engine::{Engine, TaskNode},
+    process::Command,
+    run::{
+        task_access::{trace_file_path, TaskAccessTraceFile},
+        task_id::TaskId,
+    },
+    task_graph::{TaskDefinition, TURBO_DEFAULT},
+};
+
+/// Name of the file in `.turbo/<hash>/` the tracer writes its report to
+const REPORT_FILE: &str = "sandbox.json";
+/// Files every task may read without declaring them, in addition to the
+/// configured global dependencies
+const DEFAULT_GLOBAL_DEPS: [&str; 2] = ["package.json", "turbo.json"];
+/// Directories whose contents are managed by tools rather than tasks
+const IGNORED_DIRS: [&str; 3] = [".git", ".turbo", "node_modules"];
+
+#[derive(Debug, Error, Diagnostic)]
+pub enum Error {
+    #[error("--sandbox is only supported on x86_64 Linux")]
+    Unsupported,
+    #[error("Unable to trace command: {0}")]
+    Trace(std::io::Error),
+    #[error("Unable to write sandbox report: {0}")]
+    WriteReport(std::io::Error),
+    #[error("Unable to read sandbox report: {0}")]
+    ReadReport(std::io::Error),
+    #[error("Invalid sandbox report: {0}")]
+    InvalidReport(#[from] serde_json::Error),
+    #[error("Invalid glob for {task_id}: {err}")]
+    Glob {
+        task_id: String,
+        err: Box<wax::BuildError>,
+    },
+    #[error(transparent)]
+    Path(#[from] turbopath::PathError),
+}
+
+/// The absolute paths of the files a traced command read and wrote
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SandboxReport {
+    pub reads: BTreeSet<String>,
+    pub writes: BTreeSet<String>,
+}
+
+/// Runs `command`, writing the files it accesses to `report` and returning its
+/// exit code. This is what sandboxed tasks get wrapped in.
+pub fn trace(report: &Utf8Path, command: &[String]) -> Result<i32, Error> {
+    let report_path = AbsoluteSystemPathBuf::from_cwd(report)?;
+    let (program, args) = command
+        .split_first()
+        .expect("clap requires a command to trace");
+    let (exit_code, report) = trace_command(program, args)?;
+    let report = serde_json::to_string(&report)?;
+    report_path
+        .ensure_dir()
+        .and_then(|()| report_path.create_with_contents(report))
+        .map_err(Error::WriteReport)?;
+    Ok(exit_code)
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+fn trace_command(program: &str, args: &[String]) -> Result<(i32, SandboxReport), Error> {
+    use nix::sys::signal::{self, SigHandler, Signal};
+
+    // Interrupts are sent to the whole process group, so the command gets them
+    // as well. Keep tracing until it has finished shutting down.
+    unsafe { signal::signal(Signal::SIGINT, SigHandler::SigIgn) }
+        .map_err(|e| Error::Trace(e.into()))?;
+    tracer::trace(program, args).map_err(Error::Trace)
+}
+
+#[cfg(not(all(target_os = "linux", target_arch = "x86_64")))]
+fn trace_command(_program: &str, _args: &[String]) -> Result<(i32, SandboxReport), Error> {
+    Err(Error::Unsupported)
+}
+
+/// Whether tasks can be sandboxed on this platform
+pub fn is_supported() -> bool {
+    cfg!(all(target_os = "linux", target_arch = "x86_64"))
+}
+
+/// The sandbox settings shared by every task in a run
+pub struct Sandbox {
+    mode: SandboxMode,
+    repo_root: AbsoluteSystemPathBuf,
+    global_deps: Vec<String>,
+    global_env: HashSet<String>,
+    package_dirs: HashMap<PackageName, AnchoredSystemPathBuf>,
+}
+
+/// Checks the accesses of a single task
+pub struct TaskSandbox {
+    sandbox: Arc<Sandbox>,
+    task_id: TaskId<'static>,
+    task_hash: String,
+    definition: TaskDefinition,
+    /// The task along with every task it depends on, and their package
+    /// directories. Files that are inputs or outputs of any of them are part
+    /// of the task's hash.
+    declared: Vec<(AnchoredSystemPathBuf, TaskDefinition)>,
+}
+
+/// Accesses a task made that its definition doesn't account for
+#[derive(Debug, Default, PartialEq)]
+pub struct Violations {
+    /// Files read that aren't part of the task's hash
+    pub reads: BTreeSet<AnchoredSystemPathBuf>,
+    /// Files written outside of the task's outputs
+    pub writes: BTreeSet<AnchoredSystemPathBuf>,
+    /// Environment variables read that aren't part of the task's hash. Only
+    /// tools that write a trace file report these.
+    pub env: BTreeSet<String>,
+}
+
+impl Sandbox {
+    pub fn new(
+        mode: SandboxMode,
+        repo_root: AbsoluteSystemPathBuf,
+        package_graph: &PackageGraph,
+        global_deps: Vec<String>,
+        global_env: &EnvironmentVariableMap,
+    ) -> Self {
+        let package_dirs = package_graph
+            .packages()
+            .map(|(name, info)| (name.clone(), info.package_path().to_owned()))
+            .collect();
+        Self {
+            mode,
+            repo_root,
+            global_deps,
+            global_env: global_env.keys().cloned().collect(),
+            package_dirs,
+        }
+    }
+
+    pub fn mode(&self) -> SandboxMode {
+        self.mode
+    }
+
+    pub fn task(
+        self: &Arc<Self>,
+        task_id: &TaskId<'static>,
+        task_hash: &str,
+        engine: &Engine,
+    ) -> TaskSandbox {
+        let mut declared = Vec::new();
+        let mut visited = HashSet::from([task_id.clone()]);
+        let mut queue = VecDeque::from([task_id.clone()]);
+        while let Some(task) = queue.pop_front() {
+            if let (Some(definition), Some(package_dir)) = (
+                engine.task_definition(&task),
+                self.package_dirs.get(&PackageName::from(task.package())),
+            ) {
+                declared.push((package_dir.clone(), definition.clone()));
+            }
+            for dependency in engine.dependencies(&task).into_iter().flatten() {
+                if let TaskNode::Task(dependency) = dependency {
+                    if visited.insert(dependency.clone()) {
+                        queue.push_back(dependency.clone());
+                    }
+                }
+            }
+        }
+
+        TaskSandbox {
+            sandbox: self.clone(),
+            task_id: task_id.clone(),
+            task_hash: task_hash.to_string(),
+            definition: engine.task_definition(task_id).cloned().unwrap_or_default(),
+            declared,
+        }
+    }
+}
+
+impl TaskSandbox {
+    pub fn mode(&self) -> SandboxMode {
+        self.sandbox.mode
+    }
+
+    fn report_path(&self) -> AbsoluteSystemPathBuf {
+        self.sandbox
+            .repo_root
+            .join_components(&[".turbo", &self.task_hash, REPORT_FILE])
+    }
+
+    /// Runs the command under the tracer
+    pub fn wrap(&self, cmd: &mut Command) -> std::io::Result<()> {
+        let turbo = std::env::current_exe()?;
+        cmd.wrap(
+            turbo,
+            [
+                "--skip-infer",
+                "__sandbox",
+                "--report",
+                self.report_path().as_str(),
+                "--",
+            ],
+        );
+        Ok(())
+    }
+
+    /// Compares the accesses the tracer recorded against the task definition
+    pub fn check(&self) -> Result<Violations, Error> {
+        let report = self
+            .report_path()
+            .read_to_string()
+            .map_err(Error::ReadReport)?;
+        let report: SandboxReport = serde_json::from_str(&report)?;
+        let env_var_keys = trace_file_path(&self.sandbox.repo_root, &self.task_hash)
+            .read_to_string()
+            .ok()
+            .and_then(|trace| serde_json::from_str::<TaskAccessTraceFile>(&trace).ok())
+            .map(|trace| {
+                trace
+                    .accessed
+                    .env_var_keys
+                    .iter()
+                    .map(|key| key.to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.violations(&report, env_var_keys)
+    }
+
+    fn violations(
+        &self,
+        report: &SandboxReport,
+        env_var_keys: Vec<String>,
+    ) -> Result<Violations, Error> {
+        let to_error = |task_id: &TaskId, err| Error::Glob {
+            task_id: task_id.to_string(),
+            err: Box::new(err),
+        };
+        let global_matcher = wax::any(
+            DEFAULT_GLOBAL_DEPS
+                .iter()
+                .copied()
+                .chain(self.sandbox.global_deps.iter().map(|glob| glob.as_str()))
+                .filter(|glob| !glob.starts_with('!'))
+                .map(|glob| glob.trim_start_matches('/')),
+        )
+        .map_err(|err| to_error(&self.task_id, err))?;
+        let declared = self
+            .declared
+            .iter()
+            .map(|(package_dir, definition)| {
+                Ok((
+                    package_dir.as_path(),
+                    definition.inputs_matcher()?,
+                    outputs_matcher(definition)?,
+                ))
+            })
+            .collect::<Result<Vec<_>, wax::BuildError>>()
+            .map_err(|err| to_error(&self.task_id, err))?;
+        let own_outputs =
+            outputs_matcher(&self.definition).map_err(|err| to_error(&self.task_id, err))?;
+        let own_package_dir = self.own_package_dir();
+
+        let mut violations = Violations::default();
+        for read in self.repo_files(&report.reads) {
+            let is_global = global_matcher.is_match(read.as_path());
+            // Package managers read every workspace's package.json to find the
+            // workspaces, and those are already part of the package graph
+            let is_package_json = read.parent().map_or(false, |dir| {
+                read.as_path().ends_with("package.json")
+                    && self
+                        .sandbox
+                        .package_dirs
+                        .values()
+                        .any(|package_dir| package_dir.as_path() == dir.as_path())
+            });
+            let is_declared = declared.iter().any(|(package_dir, inputs, outputs)| {
+                read.as_path()
+                    .strip_prefix(package_dir)
+                    .map_or(false, |relative| {
+                        inputs.is_match(relative) || outputs.is_match(relative)
+                    })
+            });
+            if !is_global && !is_package_json && !is_declared {
+                violations.reads.insert(read);
+            }
+        }
+        for write in self.repo_files(&report.writes) {
+            let is_output = own_package_dir.map_or(false, |package_dir| {
+                write
+                    .as_path()
+                    .strip_prefix(package_dir.as_path())
+                    .map_or(false, |relative| own_outputs.is_match(relative))
+            });
+            if !is_output {
+                violations.writes.insert(write);
+            }
+        }
+
+        let env_var_keys = EnvironmentVariableMap::from(
+            env_var_keys
+                .into_iter()
+                .map(|key| (key, String::new()))
+                .collect::<HashMap<_, _>>(),
+        );
+        let declared_env = env_var_keys
+            .from_wildcards(
+                &self
+                    .definition
+                    .env
+                    .iter()
+                    .chain(self.definition.pass_through_env.iter().flatten())
+                    .collect::<Vec<_>>(),
+            )
+            .unwrap_or_default();
+        violations.env = env_var_keys
+            .keys()
+            .filter(|key| {
+                !declared_env.contains_key(*key) && !self.sandbox.global_env.contains(*key)
+            })
+            .cloned()
+            .collect();
+
+        Ok(violations)
+    }
+
+    fn own_package_dir(&self) -> Option<&AnchoredSystemPathBuf> {
+        self.sandbox
+            .package_dirs
+            .get(&PackageName::from(self.task_id.package()))
+    }
+
+    /// The files in the repository out of the absolute paths in the report,
+    /// skipping directories and anything managed by tools
+    fn repo_files<'a>(
+        &'a self,
+        paths: &'a BTreeSet<String>,
+    ) -> impl Iterator<Item = AnchoredSystemPathBuf> + 'a {
+        paths.iter().filter_map(|path| {
+            let path = AbsoluteSystemPathBuf::new(path.as_str()).ok()?;
+            let anchored = self.sandbox.repo_root.anchor(&path).ok()?;
+            let is_ignored = anchored
+                .components()
+                .any(|component| IGNORED_DIRS.contains(&component.as_str()));
+            (!is_ignored && !path.as_std_path().is_dir()).then_some(anchored)
+        })
+    }
+
+    /// Describes the violations, followed by the turbo.json changes that would
+    /// declare them
+    pub fn describe(&self, violations: &Violations) -> Vec<String> {
+        let mut lines = Vec::new();
+        if !violations.reads.is_empty() {
+            lines.push("read files that aren't part of its inputs:".to_string());
+            lines.extend(violations.reads.iter().map(|path| format!("  {path}")));
+        }
+        if !violations.writes.is_empty() {
+            lines.push("wrote files outside of its outputs:".to_string());
+            lines.extend(violations.writes.iter().map(|path| format!("  {path}")));
+        }
+        if !violations.env.is_empty() {
+            lines.push(format!(
+                "read environment variables that aren't part of its hash: {}",
+                violations
+                    .env
+                    .iter()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        let other_packages = violations
+            .reads
+            .iter()
+            .filter_map(|path| self.package_of(path))
+            .filter(|package| package.as_ref() != self.task_id.package())
+            .map(|package| package.to_string())
+            .collect::<BTreeSet<_>>();
+        if !other_packages.is_empty() {
+            lines.push(format!(
+                "depend on a task in {} that declares the files it reads",
+                other_packages.into_iter().collect::<Vec<_>>().join(", ")
+            ));
+        }
+        if let Some(suggestion) = self.suggestion(violations) {
+            lines.push("declare them in turbo.json:".to_string());
+            let suggestion =
+                serde_json::to_string_pretty(&suggestion).expect("json values serialize");
+            lines.extend(suggestion.lines().map(|line| format!("  {line}")));
+        }
+        lines
+    }
+
+    /// The package that contains `path`, if it's not in the root package
+    fn package_of(&self, path: &AnchoredSystemPath) -> Option<&PackageName> {
+        self.sandbox
+            .package_dirs
+            .iter()
+            .filter(|(name, package_dir)| {
+                **name != PackageName::Root && path.as_path().starts_with(package_dir.as_path())
+            })
+            .max_by_key(|(_, package_dir)| package_dir.as_str().len())
+            .map(|(name, _)| name)
+    }
+
+    /// A turbo.json snippet with the task's current configuration extended to
+    /// cover the violations
+    fn suggestion(&self, violations: &Violations) -> Option<Value> {
+        let own_package_dir = self.own_package_dir()?;
+        let is_own_file = |path: &AnchoredSystemPath| {
+            self.package_of(path)
+                .map_or(false, |package| package.as_ref() == self.task_id.package())
+        };
+        let package_relative = |path: &AnchoredSystemPath| {
+            path.as_path()
+                .strip_prefix(own_package_dir.as_path())
+                .ok()
+                .map(|relative| relative.to_string_lossy().replace('\\', "/"))
+        };
+
+        let mut inputs = BTreeSet::new();
+        let mut global_deps = BTreeSet::new();
+        for read in &violations.reads {
+            if self.package_of(read).is_none() {
+                global_deps.insert(read.to_unix().to_string());
+            } else if is_own_file(read) {
+                inputs.extend(package_relative(read));
+            }
+        }
+        let outputs = violations
+            .writes
+            .iter()
+            .filter(|write| is_own_file(write))
+            .filter_map(|write| package_relative(write))
+            .map(|relative| match relative.split_once('/') {
+                Some((dir, _)) => format!("{dir}/**"),
+                None => relative,
+            })
+            .collect::<BTreeSet<_>>();
+
+        let mut task = Map::new();
+        if !inputs.is_empty() {
+            let mut all_inputs = self.definition.inputs.clone();
+            if all_inputs.is_empty() {
+                all_inputs.push(TURBO_DEFAULT.to_string());
+            }
+            all_inputs.extend(inputs);
+            task.insert("inputs".to_string(), json!(all_inputs));
+        }
+        if !outputs.is_empty() {
+            let all_outputs = self
+                .definition
+                .outputs
+                .inclusions
+                .iter()
+                .cloned()
+                .chain(outputs)
+                .chain(
+                    self.definition
+                        .outputs
+                        .exclusions
+                        .iter()
+                        .map(|exclusion| format!("!{exclusion}")),
+                )
+                .collect::<Vec<_>>();
+            task.insert("outputs".to_string(), json!(all_outputs));
+        }
+        if !violations.env.is_empty() {
+            let all_env = self
+                .definition
+                .env
+                .iter()
+                .cloned()
+                .chain(violations.env.iter().cloned())
+                .collect::<Vec<_>>();
+            task.insert("env".to_string(), json!(all_env));
+        }
+
+        let mut suggestion = Map::new();
+        if !global_deps.is_empty() {
+            suggestion.insert("globalDependencies".to_string(), json!(global_deps));
+        }
+        if !task.is_empty() {
+            suggestion.insert(
+                "pipeline".to_string(),
+                json!({ self.task_id.to_string(): task }),
+            );
+        }
+        (!suggestion.is_empty()).then_some(Value::Object(suggestion))
+    }
+}
+
+impl Violations {
+    pub fn is_empty(&self) -> bool {
+        self.reads.is_empty() && self.writes.is_empty() && self.env.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.reads.len() + self.writes.len() + self.env.len()
+    }
+}
+
+/// Matches package relative paths against a task's outputs
+fn outputs_matcher(definition: &TaskDefinition) -> Result<OutputsMatcher, wax::BuildError> {
+    Ok(OutputsMatcher {
+        inclusions: wax::any(
+            definition
+                .outputs
+                .inclusions
+                .iter()
+                .map(|glob| glob.as_str()),
+        )?,
+        exclusions: wax::any(
+            definition
+                .outputs
+                .exclusions
+                .iter()
+                .map(|glob| glob.as_str()),
+        )?,
+    })
+}
+
+struct OutputsMatcher<'a> {
+    inclusions: wax::Any<'a>,
+    exclusions: wax::Any<'a>,
+}
+
+impl OutputsMatcher<'_> {
+    fn is_match(&self, path: &Path) -> bool {
+        self.inclusions.is_match(path) && !self.exclusions.is_match(path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+    use crate::task_graph::TaskOutputs;
+
+    fn task_sandbox() -> TaskSandbox {
+        let repo_root = AbsoluteSystemPathBuf::new("/repo").unwrap();
+        let package_dirs: HashMap<_, _> = [("//", ""), ("a", "packages/a"), ("b", "packages/b")]
+            .into_iter()
+            .map(|(name, dir)| {
+                (
+                    PackageName::from(name),
+                    AnchoredSystemPathBuf::from_raw(dir).unwrap(),
+                )
+            })
+            .collect();
+        let definition = TaskDefinition {
+            inputs: vec!["src/**".to_string()],
+            outputs: TaskOutputs {
+                inclusions: vec!["dist/**".to_string()],
+                exclusions: vec!["dist/cache/**".to_string()],
+            },
+            env: vec!["API_*".to_string()],
+            ..Default::default()
+        };
+        let declared = vec![
+            (
+                package_dirs[&PackageName::from("b")].clone(),
+                definition.clone(),
+            ),
+            (
+                package_dirs[&PackageName::from("a")].clone(),
+                TaskDefinition::default(),
+            ),
+        ];
+        TaskSandbox {
+            sandbox: Arc::new(Sandbox {
+                mode: SandboxMode::Warn,
+                repo_root,
+                global_deps: vec!["tsconfig.json".to_string()],
+                global_env: HashSet::from(["CI".to_string()]),
+                package_dirs,
+            }),
+            task_id: TaskId::new("b", "build").into_owned(),
+            task_hash: "abc".to_string(),
+            definition,
+            declared,
+        }
+    }
+
+    fn report(reads: &[&str], writes: &[&str]) -> SandboxReport {
+        SandboxReport {
+            reads: reads.iter().map(|path| path.to_string()).collect(),
+            writes: writes.iter().map(|path| path.to_string()).collect(),
+        }
+    }
+
+    fn paths(paths: &[&str]) -> BTreeSet<AnchoredSystemPathBuf> {
+        paths
+            .iter()
+            .map(|path| AnchoredSystemPathBuf::from_raw(path).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_violations() {
+        let sandbox = task_sandbox();
+        let report = report(
+            &[
+                "/repo/packages/b/src/index.ts",
+                "/repo/packages/b/README.md",
+                "/repo/packages/b/package.json",
+                "/repo/packages/a/index.js",
+                "/repo/packages/a/package.json",
+                "/repo/turbo.json",
+                "/repo/tsconfig.json",
+                "/repo/config.txt",
+                "/repo/node_modules/dep/index.js",
+                "/usr/lib/node/index.js",
+            ],
+            &[
+                "/repo/packages/b/dist/index.js",
+                "/repo/packages/b/dist/cache/data",
+                "/repo/packages/b/tmp/log.txt",
+                "/repo/packages/b/.turbo/turbo-build.log",
+                "/tmp/scratch",
+            ],
+        );
+        let violations = sandbox
+            .violations(
+                &report,
+                vec![
+                    "API_URL".to_string(),
+                    "CI".to_string(),
+                    "SECRET".to_string(),
+                ],
+            )
+            .unwrap();
+
+        assert_eq!(
+            violations,
+            Violations {
+                reads: paths(&["config.txt", "packages/b/README.md"]),
+                writes: paths(&["packages/b/dist/cache/data", "packages/b/tmp/log.txt"]),
+                env: BTreeSet::from(["SECRET".to_string()]),
+            }
+        );
+    }
+
+    #[test]
+    fn test_suggestion() {
+        let sandbox = task_sandbox();
+        let violations = Violations {
+            reads: paths(&[
+                "config.txt",
+                "packages/a/dist/index.js",
+                "packages/b/README.md",
+            ]),
+            writes: paths(&["packages/b/tmp/log.txt", "packages/b/log.txt"]),
+            env: BTreeSet::from(["SECRET".to_string()]),
+        };
+
+        assert_eq!(
+            sandbox.suggestion(&violations),
+            Some(json!({
+                "globalDependencies": ["config.txt"],
+                "pipeline": {
+                    "b#build": {
+                        "inputs": ["src/**", "README.md"],
+                        "outputs": ["dist/**", "log.txt", "tmp/**", "!dist/cache/**"],
+                        "env": ["API_*", "SECRET"],
+                    }
+                }
+            }))
+        );
+        assert!(sandbox
+            .describe(&violations)
+            .contains(&"depend on a task in a that declares the files it reads".to_string()));
+    }
+
+    #[test]
+    fn test_no_suggestion() {
+        let sandbox = task_sandbox();
+        let violations = Violations {
+            reads: paths(&["packages/a/dist/index.js"]),
+            ..Default::default()
+        };
+        assert_eq!(sandbox.suggestion(&violations), None);
+    }
+}
@@ -0,0 +1,326 @@
+//! Records the files a command and every process it spawns access by stopping
+//! them at each system call with ptrace. Only the path arguments of a handful
+//! of system calls are inspected, but ptrace stops on all of them, so traced
+//! commands run noticeably slower.
+
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::OsString,
+    fs::File,
+    io,
+    os::unix::{ffi::OsStringExt, fs::FileExt, process::CommandExt},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use nix::{
+    errno::Errno,
+    libc::{self, user_regs_struct},
+    sys::{
+        ptrace::{self, Options},
+        signal::{self, SigHandler, Signal},
+        wait::{waitpid, WaitPidFlag, WaitStatus},
+    },
+    unistd::Pid,
+};
+
+use super::SandboxReport;
+
+/// Pseudo filesystems that are accessed all the time and never hold task files
+const IGNORED_ROOTS: [&str; 3] = ["/proc", "/dev", "/sys"];
+
+enum Access {
+    Read(PathBuf),
+    Write(PathBuf),
+}
+
+#[derive(Default)]
+struct Tracer {
+    /// Processes that are stopped in a system call along with the accesses
+    /// that system call makes if it succeeds
+    in_syscall: HashMap<Pid, Vec<Access>>,
+    /// Processes that have reported their initial stop
+    started: HashSet<Pid>,
+    report: SandboxReport,
+}
+
+/// Runs the command to completion, returning its exit code along with the files
+/// it accessed
+pub(super) fn trace(program: &str, args: &[String]) -> io::Result<(i32, SandboxReport)> {
+    let mut command = Command::new(program);
+    command.args(args);
+    unsafe {
+        command.pre_exec(|| {
+            // The tracer ignores interrupts, but the command should still get them
+            signal::signal(Signal::SIGINT, SigHandler::SigDfl)?;
+            ptrace::traceme()?;
+            Ok(())
+        });
+    }
+    let child = command.spawn()?;
+    let root = Pid::from_raw(child.id() as i32);
+
+    // The command stops with a SIGTRAP once it has been exec'd
+    waitpid(root, None)?;
+    ptrace::setoptions(
+        root,
+        Options::PTRACE_O_TRACESYSGOOD
+            | Options::PTRACE_O_TRACEFORK
+            | Options::PTRACE_O_TRACEVFORK
+            | Options::PTRACE_O_TRACECLONE
+            | Options::PTRACE_O_TRACEEXEC
+            | Options::PTRACE_O_EXITKILL,
+    )?;
+    ptrace::syscall(root, None)?;
+
+    let mut tracer = Tracer::default();
+    tracer.started.insert(root);
+    let mut exit_code = 1;
+    loop {
+        let status = match waitpid(None, Some(WaitPidFlag::__WALL)) {
+            Ok(status) => status,
+            Err(Errno::EINTR) => continue,
+            // Every traced process has exited
+            Err(Errno::ECHILD) => break,
+            Err(e) => return Err(e.into()),
+        };
+        match status {
+            WaitStatus::PtraceSyscall(pid) => {
+                tracer.on_syscall(pid);
+                resume(pid, None);
+            }
+            WaitStatus::PtraceEvent(pid, _, _) => resume(pid, None),
+            // New processes start out stopped, which shouldn't be passed on to them
+            WaitStatus::Stopped(pid, Signal::SIGSTOP) if tracer.started.insert(pid) => {
+                resume(pid, None)
+            }
+            WaitStatus::Stopped(pid, signal) => resume(pid, Some(signal)),
+            WaitStatus::Exited(pid, code) => {
+                tracer.on_exit(pid);
+                if pid == root {
+                    exit_code = code;
+                }
+            }
+            WaitStatus::Signaled(pid, signal, _) => {
+                tracer.on_exit(pid);
+                if pid == root {
+                    exit_code = 128 + signal as i32;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    Ok((exit_code, tracer.report))
+}
+
+fn resume(pid: Pid, signal: Option<Signal>) {
+    // The process can be killed out from under us at any point, in which case
+    // there is nothing to resume
+    ptrace::syscall(pid, signal).ok();
+}
+
+impl Tracer {
+    fn on_syscall(&mut self, pid: Pid) {
+        if let Some(accesses) = self.in_syscall.remove(&pid) {
+            let succeeded = ptrace::getregs(pid).map_or(false, |regs| (regs.rax as i64) >= 0);
+            if succeeded {
+                for access in accesses {
+                    self.record(access);
+                }
+            }
+        } else {
+            let accesses = ptrace::getregs(pid)
+                .map(|regs| syscall_accesses(pid, &regs))
+                .unwrap_or_default();
+            self.in_syscall.insert(pid, accesses);
+        }
+    }
+
+    fn on_exit(&mut self, pid: Pid) {
+        self.in_syscall.remove(&pid);
+        self.started.remove(&pid);
+    }
+
+    fn record(&mut self, access: Access) {
+        let (Access::Read(path) | Access::Write(path)) = &access;
+        if IGNORED_ROOTS.iter().any(|root| path.starts_with(root)) {
+            return;
+        }
+        match access {
+            Access::Read(path) => {
+                let path = path.to_string_lossy().into_owned();
+                // Reading back a file the command wrote itself doesn't make it an input
+                if !self.report.writes.contains(&path) {
+                    self.report.reads.insert(path);
+                }
+            }
+            Access::Write(path) => {
+                self.report
+                    .writes
+                    .insert(path.to_string_lossy().into_owned());
+            }
+        }
+    }
+}
+
+/// The files a system call would access, based on its arguments at entry
+fn syscall_accesses(pid: Pid, regs: &user_regs_struct) -> Vec<Access> {
+    let args = [regs.rdi, regs.rsi, regs.rdx, regs.r10, regs.r8, regs.r9];
+    let cwd = libc::AT_FDCWD as u64;
+    let path = |dirfd: u64, address: u64| resolve_path(pid, dirfd as i32, address);
+    let write = |dirfd: u64, address: u64| path(dirfd, address).map(Access::Write);
+    let read = |dirfd: u64, address: u64| path(dirfd, address).map(Access::Read);
+
+    let accesses = match regs.orig_rax as i64 {
+        libc::SYS_open => vec![open_access(path(cwd, args[0]), args[1])],
+        libc::SYS_openat => vec![open_access(path(args[0], args[1]), args[2])],
+        // openat2 passes its flags in a struct, as the first field
+        libc::SYS_openat2 => {
+            vec![read_u64(pid, args[2]).and_then(|flags| open_access(path(args[0], args[1]), flags))]
+        }
+        libc::SYS_execve => vec![read(cwd, args[0])],
+        libc::SYS_execveat => vec![read(args[0], args[1])],
+        libc::SYS_creat | libc::SYS_truncate | libc::SYS_unlink => vec![write(cwd, args[0])],
+        libc::SYS_unlinkat => vec![write(args[0], args[1])],
+        libc::SYS_rename => vec![write(cwd, args[0]), write(cwd, args[1])],
+        libc::SYS_renameat | libc::SYS_renameat2 => {
+            vec![write(args[0], args[1]), write(args[2], args[3])]
+        }
+        libc::SYS_link | libc::SYS_symlink => vec![write(cwd, args[1])],
+        libc::SYS_linkat => vec![write(args[2], args[3])],
+        libc::SYS_symlinkat => vec![write(args[1], args[2])],
+        _ => Vec::new(),
+    };
+    accesses.into_iter().flatten().collect()
+}
+
+fn open_access(path: Option<PathBuf>, flags: u64) -> Option<Access> {
+    let flags = flags as i32;
+    // Opening a directory or only getting a handle to a path doesn't read
+    // anything
+    if flags & (libc::O_DIRECTORY | libc::O_PATH) != 0 {
+        return None;
+    }
+    let path = path?;
+    if flags & libc::O_ACCMODE != libc::O_RDONLY || flags & (libc::O_CREAT | libc::O_TRUNC) != 0 {
+        Some(Access::Write(path))
+    } else {
+        Some(Access::Read(path))
+    }
+}
+
+/// Reads the path at `address` in the process's memory, resolving it
+/// against the directory `dirfd` refers to when it is relative
+fn resolve_path(pid: Pid, dirfd: i32, address: u64) -> Option<PathBuf> {
+    let path = read_path(pid, address)?;
+    if path.is_absolute() {
+        return Some(path_clean::clean(path));
+    }
+    let base = if dirfd == libc::AT_FDCWD {
+        std::fs::read_link(format!("/proc/{pid}/cwd"))
+    } else {
+        std::fs::read_link(format!("/proc/{pid}/fd/{dirfd}"))
+    }
+    .ok()?;
+    Some(path_clean::clean(base.join(path)))
+}
+
+fn read_path(pid: Pid, address: u64) -> Option<PathBuf> {
+    const PAGE_SIZE: u64 = 4096;
+    const PATH_MAX: usize = libc::PATH_MAX as usize;
+
+    if address == 0 {
+        return None;
+    }
+    let mem = process_memory(pid)?;
+    let mut path = Vec::new();
+    let mut buffer = [0; PAGE_SIZE as usize];
+    let mut offset = address;
+    // Read a page at a time so a path that sits right before the end of a
+    // mapping doesn't fail the read
+    while path.len() < PATH_MAX {
+        let len = (PAGE_SIZE - offset % PAGE_SIZE) as usize;
+        let read = mem.read_at(&mut buffer[..len], offset).ok()?;
+        if read == 0 {
+            return None;
+        }
+        if let Some(end) = buffer[..read].iter().position(|byte| *byte == 0) {
+            path.extend_from_slice(&buffer[..end]);
+            return Some(PathBuf::from(OsString::from_vec(path)));
+        }
+        path.extend_from_slice(&buffer[..read]);
+        offset += read as u64;
+    }
+    None
+}
+
+fn read_u64(pid: Pid, address: u64) -> Option<u64> {
+    let mut buffer = [0; 8];
+    process_memory(pid)?
+        .read_exact_at(&mut buffer, address)
+        .ok()?;
+    Some(u64::from_ne_bytes(buffer))
+}
+
+fn process_memory(pid: Pid) -> Option<File> {
+    File::open(Path::new("/proc").join(pid.to_string()).join("mem")).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TEST_NAME: &str = "run::sandbox::tracer::test::test_trace_records_reads_and_writes";
+    const TEST_DIR_VAR: &str = "TURBO_TEST_TRACE_DIR";
+
+    #[test]
+    fn test_trace_records_reads_and_writes() {
+        // The tracer waits on every child of its process, which would include
+        // the children of other tests, so trace from a process of our own
+        if let Ok(dir) = std::env::var(TEST_DIR_VAR) {
+            let dir = Path::new(&dir);
+            let (exit_code, report) = trace(
+                "sh",
+                &[
+                    "-c".to_string(),
+                    format!(
+                        "cat {input} > {output} && cat {output} && exit 3",
+                        input = dir.join("input.txt").display(),
+                        output = dir.join("output.txt").display(),
+                    ),
+                ],
+            )
+            .unwrap();
+            assert_eq!(exit_code, 3);
+            std::fs::write(
+                dir.join("report.json"),
+                serde_json::to_string(&report).unwrap(),
+            )
+            .unwrap();
+            return;
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("input.txt");
+        let output = dir.path().join("output.txt");
+        std::fs::write(&input, "hello").unwrap();
+
+        let status = Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", TEST_NAME, "--test-threads=1"])
+            .env(TEST_DIR_VAR, dir.path())
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let report: SandboxReport =
+            serde_json::from_slice(&std::fs::read(dir.path().join("report.json")).unwrap())
+                .unwrap();
+        let input = input.to_string_lossy().into_owned();
+        let output = output.to_string_lossy().into_owned();
+        assert!(report.reads.contains(&input));
+        assert!(report.writes.contains(&output));
+        // Reading back its own output isn't an input
+        assert!(!report.reads.contains(&output));
+    }
+}
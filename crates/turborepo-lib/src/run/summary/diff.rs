@@ -0,0 +1,906 @@
+//! Compares the hash inputs recorded in two run summaries to explain why a
+//! task's hash, and with it its cache key, differs between the runs.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use miette::Diagnostic;
+use serde::Deserialize;
+use serde_json::Value;
+use thiserror::Error;
+use tracing::debug;
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
+use turborepo_ui::{color, GREY, UI};
+
+/// The fields of a resolved task definition that are part of the task's hash
+const HASHED_DEFINITION_FIELDS: [&str; 5] =
+    ["inputs", "outputs", "env", "passThroughEnv", "dotEnv"];
+
+#[derive(Debug, Error, Diagnostic)]
+pub enum Error {
+    #[error("Unable to read run summary {path}: {err}")]
+    Read {
+        path: AbsoluteSystemPathBuf,
+        err: std::io::Error,
+    },
+    #[error("Invalid run summary {path}: {err}")]
+    Parse {
+        path: AbsoluteSystemPathBuf,
+        err: serde_json::Error,
+    },
+    #[error("No run summary found for {0}")]
+    #[diagnostic(help(
+        "pass the path to a summary file or the id of a run saved in .turbo/runs with --summarize"
+    ))]
+    NotFound(String),
+    #[error(transparent)]
+    Path(#[from] turbopath::PathError),
+}
+
+/// The parts of a saved run summary that go into task hashes
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedRunSummary {
+    pub id: String,
+    #[serde(rename = "globalCacheInputs")]
+    global: GlobalInputs,
+    tasks: Vec<SavedTask>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GlobalInputs {
+    root_key: String,
+    #[serde(default)]
+    files: BTreeMap<String, String>,
+    hash_of_external_dependencies: String,
+    global_dot_env: Option<Vec<String>>,
+    environment_variables: GlobalEnvVars,
+}
+
+#[derive(Debug, Deserialize)]
+struct GlobalEnvVars {
+    specified: SpecifiedEnv,
+    #[serde(flatten)]
+    values: EnvValues,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpecifiedEnv {
+    env: Vec<String>,
+    pass_through_env: Option<Vec<String>>,
+}
+
+/// Environment variables as `NAME=<hash of value>` pairs
+#[derive(Debug, Deserialize)]
+struct EnvValues {
+    configured: Option<Vec<String>>,
+    inferred: Option<Vec<String>>,
+    #[serde(rename = "passthrough")]
+    pass_through: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SavedTask {
+    task_id: String,
+    hash: String,
+    #[serde(default)]
+    inputs: BTreeMap<String, String>,
+    hash_of_external_dependencies: String,
+    // Only recorded by newer versions of turbo
+    #[serde(default)]
+    external_dependencies: Vec<ExternalDependency>,
+    cache: SavedCacheSummary,
+    #[serde(default)]
+    cli_arguments: Vec<String>,
+    #[serde(default)]
+    dependencies: Vec<String>,
+    #[serde(default)]
+    resolved_task_definition: BTreeMap<String, Value>,
+    env_mode: String,
+    environment_variables: EnvValues,
+    execution: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExternalDependency {
+    key: String,
+    version: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SavedCacheSummary {
+    status: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// A single hash input that differs between two runs of a task
+#[derive(Debug, PartialEq)]
+pub enum Change {
+    /// The global cache key is built into turbo and changes when it's upgraded
+    GlobalCacheKey,
+    GlobalFile {
+        path: String,
+        kind: ChangeKind,
+    },
+    GlobalExternalDependencies,
+    GlobalEnv {
+        name: String,
+        kind: ChangeKind,
+    },
+    /// A global option in turbo.json
+    GlobalConfig {
+        field: &'static str,
+        previous: String,
+        current: String,
+    },
+    File {
+        path: String,
+        kind: ChangeKind,
+    },
+    Env {
+        name: String,
+        kind: ChangeKind,
+    },
+    ExternalDependency {
+        key: String,
+        previous: Option<String>,
+        current: Option<String>,
+    },
+    /// The external dependencies changed, but at least one of the summaries
+    /// doesn't list them
+    ExternalDependencies,
+    /// A field of the task's definition in turbo.json
+    TaskConfig {
+        field: String,
+        previous: String,
+        current: String,
+    },
+    EnvMode {
+        previous: String,
+        current: String,
+    },
+    Arguments {
+        previous: Vec<String>,
+        current: Vec<String>,
+    },
+    Dependency {
+        task_id: String,
+        kind: ChangeKind,
+    },
+}
+
+/// The hash inputs of a task that differ between two runs
+#[derive(Debug, PartialEq)]
+pub struct TaskDiff {
+    pub task_id: String,
+    pub previous_hash: String,
+    pub hash: String,
+    pub changes: Vec<Change>,
+}
+
+/// Why a task missed the cache, found by comparing it with the most recent
+/// earlier run that includes it
+#[derive(Debug, PartialEq)]
+pub enum MissExplanation {
+    NoEarlierRun,
+    /// The hash didn't change, so the outputs weren't in the cache or the cache
+    /// was bypassed
+    SameHash {
+        previous: String,
+    },
+    /// The hash changed, but none of the inputs recorded in run summaries did
+    UnrecordedChange {
+        previous: String,
+    },
+    Changed {
+        previous: String,
+        diff: TaskDiff,
+    },
+}
+
+/// Run summaries saved in `.turbo/runs`, loaded most recent first as they're
+/// needed
+pub struct RunHistory {
+    /// Summaries that haven't been loaded yet, oldest first
+    paths: Vec<AbsoluteSystemPathBuf>,
+    summaries: Vec<SavedRunSummary>,
+}
+
+impl SavedRunSummary {
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn load(path: &AbsoluteSystemPath) -> Result<Self, Error> {
+        let json = path.read_to_string().map_err(|err| Error::Read {
+            path: path.to_owned(),
+            err,
+        })?;
+        Self::from_json(&json).map_err(|err| Error::Parse {
+            path: path.to_owned(),
+            err,
+        })
+    }
+
+    /// Loads a summary given either a path to it or the id of a run saved in
+    /// `.turbo/runs`
+    pub fn find(repo_root: &AbsoluteSystemPath, run: &str) -> Result<Self, Error> {
+        let path = AbsoluteSystemPathBuf::from_cwd(run)?;
+        if path.as_std_path().is_file() {
+            return Self::load(&path);
+        }
+        let path = runs_dir(repo_root).join_component(&format!("{run}.json"));
+        if path.as_std_path().is_file() {
+            return Self::load(&path);
+        }
+        Err(Error::NotFound(run.to_string()))
+    }
+
+    /// Tasks that ran without a cache hit even though they can be cached
+    pub fn cache_misses(&self) -> impl Iterator<Item = &str> {
+        self.tasks
+            .iter()
+            .filter(|task| {
+                task.cache.status == "MISS"
+                    && task.execution.is_some()
+                    && task.resolved_task_definition.get("cache") != Some(&Value::Bool(false))
+            })
+            .map(|task| task.task_id.as_str())
+    }
+
+    fn task(&self, task_id: &str) -> Option<&SavedTask> {
+        self.tasks.iter().find(|task| task.task_id == task_id)
+    }
+}
+
+impl RunHistory {
+    /// Collects the runs saved before the run with the id `before`. Run ids
+    /// are sortable by the time they were created at.
+    pub fn new(repo_root: &AbsoluteSystemPath, before: &str) -> Self {
+        let mut paths = std::fs::read_dir(runs_dir(repo_root).as_std_path())
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let path = AbsoluteSystemPathBuf::try_from(entry.ok()?.path()).ok()?;
+                let id = path.as_path().file_name()?.strip_suffix(".json")?;
+                (id < before).then_some(path)
+            })
+            .collect::<Vec<_>>();
+        paths.sort();
+        Self {
+            paths,
+            summaries: Vec::new(),
+        }
+    }
+
+    /// The most recent run that includes `task_id`
+    pub fn last_run_of(&mut self, task_id: &str) -> Option<&SavedRunSummary> {
+        if let Some(index) = self
+            .summaries
+            .iter()
+            .position(|summary| summary.task(task_id).is_some())
+        {
+            return Some(&self.summaries[index]);
+        }
+        while let Some(path) = self.paths.pop() {
+            match SavedRunSummary::load(&path) {
+                Ok(summary) => {
+                    let has_task = summary.task(task_id).is_some();
+                    self.summaries.push(summary);
+                    if has_task {
+                        return self.summaries.last();
+                    }
+                }
+                Err(err) => debug!("skipping run summary: {err}"),
+            }
+        }
+        None
+    }
+}
+
+impl MissExplanation {
+    /// Explains why `task_id` missed the cache in `current`
+    pub fn new(history: &mut RunHistory, current: &SavedRunSummary, task_id: &str) -> Self {
+        let Some(previous) = history.last_run_of(task_id) else {
+            return MissExplanation::NoEarlierRun;
+        };
+        let diff = diff_task(previous, current, task_id).expect("both runs include the task");
+        let previous = previous.id.clone();
+        if diff.previous_hash == diff.hash {
+            MissExplanation::SameHash { previous }
+        } else if diff.changes.is_empty() {
+            MissExplanation::UnrecordedChange { previous }
+        } else {
+            MissExplanation::Changed { previous, diff }
+        }
+    }
+
+    /// Short descriptions of each cause, suitable for counting across runs
+    pub fn causes(&self) -> Vec<String> {
+        match self {
+            MissExplanation::NoEarlierRun => vec!["no earlier run to compare against".to_string()],
+            MissExplanation::SameHash { .. } => {
+                vec!["outputs missing from the cache or the cache was bypassed".to_string()]
+            }
+            MissExplanation::UnrecordedChange { .. } => {
+                vec!["inputs not recorded in run summaries changed".to_string()]
+            }
+            MissExplanation::Changed { diff, .. } => diff
+                .changes
+                .iter()
+                .map(|change| change.to_string())
+                .collect(),
+        }
+    }
+}
+
+pub(crate) fn runs_dir(repo_root: &AbsoluteSystemPath) -> AbsoluteSystemPathBuf {
+    repo_root.join_components(&[".turbo", "runs"])
+}
+
+/// Compares the tasks that are in both runs and have different hashes
+pub fn diff(previous: &SavedRunSummary, current: &SavedRunSummary) -> Vec<TaskDiff> {
+    current
+        .tasks
+        .iter()
+        .filter_map(|task| diff_task(previous, current, &task.task_id))
+        .filter(|diff| diff.previous_hash != diff.hash)
+        .collect()
+}
+
+/// Compares the hash inputs of `task_id` across two runs, returning `None` if
+/// it isn't in both of them
+pub fn diff_task(
+    previous: &SavedRunSummary,
+    current: &SavedRunSummary,
+    task_id: &str,
+) -> Option<TaskDiff> {
+    let previous_task = previous.task(task_id)?;
+    let current_task = current.task(task_id)?;
+
+    let mut changes = global_changes(&previous.global, &current.global);
+    changes.extend(
+        map_changes(&previous_task.inputs, &current_task.inputs)
+            .map(|(path, kind)| Change::File { path, kind }),
+    );
+    changes.extend(
+        map_changes(
+            &env_hashes(&previous_task.environment_variables),
+            &env_hashes(&current_task.environment_variables),
+        )
+        .map(|(name, kind)| Change::Env { name, kind }),
+    );
+    changes.extend(external_dependency_changes(previous_task, current_task));
+    for field in HASHED_DEFINITION_FIELDS {
+        let previous_value = previous_task.resolved_task_definition.get(field);
+        let current_value = current_task.resolved_task_definition.get(field);
+        if previous_value != current_value {
+            changes.push(Change::TaskConfig {
+                field: field.to_string(),
+                previous: display_value(previous_value),
+                current: display_value(current_value),
+            });
+        }
+    }
+    if previous_task.env_mode != current_task.env_mode {
+        changes.push(Change::EnvMode {
+            previous: previous_task.env_mode.clone(),
+            current: current_task.env_mode.clone(),
+        });
+    }
+    if previous_task.cli_arguments != current_task.cli_arguments {
+        changes.push(Change::Arguments {
+            previous: previous_task.cli_arguments.clone(),
+            current: current_task.cli_arguments.clone(),
+        });
+    }
+    changes.extend(dependency_changes(
+        previous,
+        previous_task,
+        current,
+        current_task,
+    ));
+
+    Some(TaskDiff {
+        task_id: task_id.to_string(),
+        previous_hash: previous_task.hash.clone(),
+        hash: current_task.hash.clone(),
+        changes,
+    })
+}
+
+fn global_changes(previous: &GlobalInputs, current: &GlobalInputs) -> Vec<Change> {
+    let mut changes = Vec::new();
+    if previous.root_key != current.root_key {
+        changes.push(Change::GlobalCacheKey);
+    }
+    changes.extend(
+        map_changes(&previous.files, &current.files)
+            .map(|(path, kind)| Change::GlobalFile { path, kind }),
+    );
+    if previous.hash_of_external_dependencies != current.hash_of_external_dependencies {
+        changes.push(Change::GlobalExternalDependencies);
+    }
+    changes.extend(
+        map_changes(
+            &env_hashes(&previous.environment_variables.values),
+            &env_hashes(&current.environment_variables.values),
+        )
+        .map(|(name, kind)| Change::GlobalEnv { name, kind }),
+    );
+
+    let previous_env = &previous.environment_variables.specified;
+    let current_env = &current.environment_variables.specified;
+    let fields = [
+        ("globalEnv", Some(&previous_env.env), Some(&current_env.env)),
+        (
+            "globalPassThroughEnv",
+            previous_env.pass_through_env.as_ref(),
+            current_env.pass_through_env.as_ref(),
+        ),
+        (
+            "globalDotEnv",
+            previous.global_dot_env.as_ref(),
+            current.global_dot_env.as_ref(),
+        ),
+    ];
+    for (field, previous, current) in fields {
+        if previous != current {
+            changes.push(Change::GlobalConfig {
+                field,
+                previous: display_list(previous),
+                current: display_list(current),
+            });
+        }
+    }
+    changes
+}
+
+fn external_dependency_changes(previous: &SavedTask, current: &SavedTask) -> Vec<Change> {
+    if previous.hash_of_external_dependencies == current.hash_of_external_dependencies {
+        return Vec::new();
+    }
+    let previous_versions = versions(previous);
+    let current_versions = versions(current);
+
+    let mut changes = Vec::new();
+    let keys = previous_versions
+        .keys()
+        .chain(current_versions.keys())
+        .collect::<BTreeSet<_>>();
+    for key in keys {
+        let previous_version = previous_versions.get(key);
+        let current_version = current_versions.get(key);
+        if previous_version != current_version {
+            let join = |versions: Option<&BTreeSet<&str>>| {
+                versions.map(|versions| versions.iter().copied().collect::<Vec<_>>().join(", "))
+            };
+            changes.push(Change::ExternalDependency {
+                key: key.to_string(),
+                previous: join(previous_version),
+                current: join(current_version),
+            });
+        }
+    }
+    // Summaries from before dependencies were recorded only have the hash
+    if changes.is_empty() {
+        changes.push(Change::ExternalDependencies);
+    }
+    changes
+}
+
+/// The versions of each external dependency of a task
+fn versions(task: &SavedTask) -> BTreeMap<&str, BTreeSet<&str>> {
+    let mut versions = BTreeMap::<&str, BTreeSet<&str>>::new();
+    for dependency in &task.external_dependencies {
+        versions
+            .entry(&dependency.key)
+            .or_default()
+            .insert(&dependency.version);
+    }
+    versions
+}
+
+fn dependency_changes(
+    previous: &SavedRunSummary,
+    previous_task: &SavedTask,
+    current: &SavedRunSummary,
+    current_task: &SavedTask,
+) -> Vec<Change> {
+    let previous_dependencies = previous_task.dependencies.iter().collect::<BTreeSet<_>>();
+    let current_dependencies = current_task.dependencies.iter().collect::<BTreeSet<_>>();
+    let mut changes = Vec::new();
+    for task_id in previous_dependencies.union(&current_dependencies) {
+        let kind = match (
+            previous_dependencies.contains(task_id),
+            current_dependencies.contains(task_id),
+        ) {
+            (true, false) => ChangeKind::Removed,
+            (false, true) => ChangeKind::Added,
+            _ => {
+                let previous_hash = previous.task(task_id).map(|task| &task.hash);
+                let current_hash = current.task(task_id).map(|task| &task.hash);
+                if previous_hash == current_hash {
+                    continue;
+                }
+                ChangeKind::Modified
+            }
+        };
+        changes.push(Change::Dependency {
+            task_id: task_id.to_string(),
+            kind,
+        });
+    }
+    changes
+}
+
+/// The keys whose values differ between two maps
+fn map_changes<'a>(
+    previous: &'a BTreeMap<String, String>,
+    current: &'a BTreeMap<String, String>,
+) -> impl Iterator<Item = (String, ChangeKind)> + 'a {
+    let removed = previous
+        .keys()
+        .filter(|key| !current.contains_key(*key))
+        .map(|key| (key.clone(), ChangeKind::Removed));
+    let added_or_modified = current
+        .iter()
+        .filter_map(|(key, value)| match previous.get(key) {
+            None => Some((key.clone(), ChangeKind::Added)),
+            Some(previous_value) if previous_value != value => {
+                Some((key.clone(), ChangeKind::Modified))
+            }
+            Some(_) => None,
+        });
+    removed.chain(added_or_modified)
+}
+
+fn env_hashes(values: &EnvValues) -> BTreeMap<String, String> {
+    [&values.configured, &values.inferred, &values.pass_through]
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|pair| {
+            let (name, hash) = pair.split_once('=')?;
+            Some((name.to_string(), hash.to_string()))
+        })
+        .collect()
+}
+
+fn display_value(value: Option<&Value>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "nothing".to_string(),
+    }
+}
+
+fn display_list(list: Option<&Vec<String>>) -> String {
+    match list {
+        Some(list) => format!("[{}]", list.join(", ")),
+        None => "nothing".to_string(),
+    }
+}
+
+impl TaskDiff {
+    pub fn print(&self, ui: UI) {
+        for change in &self.changes {
+            println!("  {}", color!(ui, GREY, "- {}", change));
+        }
+    }
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Modified => "modified",
+        })
+    }
+}
+
+impl ChangeKind {
+    fn env_change(&self) -> &'static str {
+        match self {
+            ChangeKind::Added => "was set",
+            ChangeKind::Removed => "was unset",
+            ChangeKind::Modified => "changed value",
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::GlobalCacheKey => {
+                write!(
+                    f,
+                    "the global cache key changed, which happens when turbo is upgraded"
+                )
+            }
+            Change::GlobalFile { path, kind } => write!(f, "global dependency {path} was {kind}"),
+            Change::GlobalExternalDependencies => {
+                write!(f, "the root package's external dependencies changed")
+            }
+            Change::GlobalEnv { name, kind } => write!(
+                f,
+                "global environment variable {name} {}",
+                kind.env_change()
+            ),
+            Change::GlobalConfig {
+                field,
+                previous,
+                current,
+            } => write!(
+                f,
+                "{field} in turbo.json changed from {previous} to {current}"
+            ),
+            Change::File { path, kind } => write!(f, "input {path} was {kind}"),
+            Change::Env { name, kind } => {
+                write!(f, "environment variable {name} {}", kind.env_change())
+            }
+            Change::ExternalDependency {
+                key,
+                previous,
+                current,
+            } => match (previous, current) {
+                (Some(previous), Some(current)) => write!(
+                    f,
+                    "external dependency {key} changed from {previous} to {current}"
+                ),
+                (None, Some(current)) => {
+                    write!(f, "external dependency {key} {current} was added")
+                }
+                (Some(previous), None) => {
+                    write!(f, "external dependency {key} {previous} was removed")
+                }
+                (None, None) => write!(f, "external dependency {key} changed"),
+            },
+            Change::ExternalDependencies => write!(f, "external dependencies changed"),
+            Change::TaskConfig {
+                field,
+                previous,
+                current,
+            } => write!(
+                f,
+                "task {field} in turbo.json changed from {previous} to {current}"
+            ),
+            Change::EnvMode { previous, current } => {
+                write!(f, "env mode changed from {previous} to {current}")
+            }
+            Change::Arguments { previous, current } => write!(
+                f,
+                "arguments passed to the task changed from [{}] to [{}]",
+                previous.join(" "),
+                current.join(" ")
+            ),
+            Change::Dependency { task_id, kind } => match kind {
+                ChangeKind::Added => write!(f, "now depends on {task_id}"),
+                ChangeKind::Removed => write!(f, "no longer depends on {task_id}"),
+                ChangeKind::Modified => write!(f, "dependency {task_id} has a different hash"),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    fn summary(id: &str, global_file: &str, tasks: Value) -> SavedRunSummary {
+        serde_json::from_value(json!({
+            "id": id,
+            "globalCacheInputs": {
+                "rootKey": "key",
+                "files": { "config.txt": global_file },
+                "hashOfExternalDependencies": "root",
+                "globalDotEnv": null,
+                "environmentVariables": {
+                    "specified": { "env": [], "passThroughEnv": null },
+                    "configured": [],
+                    "inferred": [],
+                    "passthrough": null
+                }
+            },
+            "tasks": tasks,
+        }))
+        .unwrap()
+    }
+
+    fn task(hash: &str, inputs: Value, env: Value, dependencies: Value) -> Value {
+        json!({
+            "taskId": "web#build",
+            "hash": hash,
+            "inputs": inputs,
+            "hashOfExternalDependencies": "deps",
+            "cache": { "status": "MISS" },
+            "cliArguments": [],
+            "dependencies": dependencies,
+            "resolvedTaskDefinition": { "outputs": ["dist/**"], "cache": true },
+            "envMode": "loose",
+            "environmentVariables": {
+                "configured": env,
+                "inferred": [],
+                "passthrough": null
+            },
+            "execution": { "exitCode": 0 },
+        })
+    }
+
+    #[test]
+    fn test_diff_task() {
+        let previous = summary(
+            "1",
+            "a",
+            json!([task(
+                "1",
+                json!({ "index.js": "a", "old.js": "b" }),
+                json!(["API_URL=a", "REMOVED="]),
+                json!([])
+            )]),
+        );
+        let current = summary(
+            "2",
+            "b",
+            json!([task(
+                "2",
+                json!({ "index.js": "c", "new.js": "d" }),
+                json!(["API_URL=b"]),
+                json!(["ui#build"])
+            )]),
+        );
+
+        let diff = diff_task(&previous, &current, "web#build").unwrap();
+        assert_eq!(diff.previous_hash, "1");
+        assert_eq!(diff.hash, "2");
+        assert_eq!(
+            diff.changes,
+            vec![
+                Change::GlobalFile {
+                    path: "config.txt".into(),
+                    kind: ChangeKind::Modified
+                },
+                Change::File {
+                    path: "old.js".into(),
+                    kind: ChangeKind::Removed
+                },
+                Change::File {
+                    path: "index.js".into(),
+                    kind: ChangeKind::Modified
+                },
+                Change::File {
+                    path: "new.js".into(),
+                    kind: ChangeKind::Added
+                },
+                Change::Env {
+                    name: "REMOVED".into(),
+                    kind: ChangeKind::Removed
+                },
+                Change::Env {
+                    name: "API_URL".into(),
+                    kind: ChangeKind::Modified
+                },
+                Change::Dependency {
+                    task_id: "ui#build".into(),
+                    kind: ChangeKind::Added
+                },
+            ]
+        );
+        assert!(diff_task(&previous, &current, "docs#build").is_none());
+    }
+
+    #[test]
+    fn test_diff_external_dependencies_and_config() {
+        let mut previous_task = task("1", json!({}), json!([]), json!([]));
+        previous_task["externalDependencies"] = json!([
+            { "key": "lodash", "version": "4.17.20" },
+            { "key": "react", "version": "18.2.0" }
+        ]);
+        let mut current_task = task("2", json!({}), json!([]), json!([]));
+        current_task["hashOfExternalDependencies"] = json!("other");
+        current_task["externalDependencies"] = json!([
+            { "key": "lodash", "version": "4.17.21" },
+            { "key": "react", "version": "18.2.0" },
+            { "key": "zod", "version": "3.0.0" }
+        ]);
+        current_task["resolvedTaskDefinition"]["outputs"] = json!(["build/**"]);
+        current_task["cliArguments"] = json!(["--verbose"]);
+
+        let previous = summary("1", "a", json!([previous_task]));
+        let current = summary("2", "a", json!([current_task]));
+        let changes = diff_task(&previous, &current, "web#build")
+            .unwrap()
+            .changes
+            .iter()
+            .map(|change| change.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            vec![
+                "external dependency lodash changed from 4.17.20 to 4.17.21",
+                "external dependency zod 3.0.0 was added",
+                "task outputs in turbo.json changed from [\"dist/**\"] to [\"build/**\"]",
+                "arguments passed to the task changed from [] to [--verbose]",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_skips_unchanged_tasks() {
+        let previous = summary(
+            "1",
+            "a",
+            json!([task("1", json!({}), json!([]), json!([]))]),
+        );
+        let current = summary(
+            "2",
+            "a",
+            json!([task("1", json!({}), json!([]), json!([]))]),
+        );
+        assert!(diff(&previous, &current).is_empty());
+        assert_eq!(
+            current.cache_misses().collect::<Vec<_>>(),
+            vec!["web#build"]
+        );
+    }
+
+    #[test]
+    fn test_history_finds_most_recent_run() {
+        let repo_root = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(repo_root.path()).unwrap();
+        let runs = runs_dir(&repo_root);
+        runs.create_dir_all().unwrap();
+        let write = |id: &str, tasks: Value| {
+            let summary = json!({
+                "id": id,
+                "globalCacheInputs": {
+                    "rootKey": "key",
+                    "hashOfExternalDependencies": "",
+                    "globalDotEnv": null,
+                    "environmentVariables": {
+                        "specified": { "env": [], "passThroughEnv": null },
+                        "configured": null,
+                        "inferred": null,
+                        "passthrough": null
+                    }
+                },
+                "tasks": tasks,
+            });
+            runs.join_component(&format!("{id}.json"))
+                .create_with_contents(summary.to_string())
+                .unwrap();
+        };
+        write("1", json!([task("a", json!({}), json!([]), json!([]))]));
+        write("2", json!([]));
+        write("3", json!([task("b", json!({}), json!([]), json!([]))]));
+        runs.join_component("4.json")
+            .create_with_contents("not json")
+            .unwrap();
+
+        let mut history = RunHistory::new(&repo_root, "5");
+        assert_eq!(history.last_run_of("web#build").unwrap().id, "3");
+        assert!(history.last_run_of("docs#build").is_none());
+
+        let mut history = RunHistory::new(&repo_root, "3");
+        assert_eq!(history.last_run_of("web#build").unwrap().id, "1");
+
+        assert_eq!(SavedRunSummary::find(&repo_root, "2").unwrap().id, "2");
+        assert!(matches!(
+            SavedRunSummary::find(&repo_root, "6"),
+            Err(Error::NotFound(_))
+        ));
+    }
+}
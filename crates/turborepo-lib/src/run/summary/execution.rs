@@ -26,7 +26,7 @@ pub struct ExecutionTracker {
 #[serde(rename_all = "camelCase")]
 pub struct ExecutionSummary<'a> {
     // a synthesized turbo command to produce this invocation
-    command: String,
+    pub(crate) command: String,
     // the (possibly empty) path from the turborepo root to where the command was run
     #[serde(rename = "repoPath")]
     repo_path: &'a AnchoredSystemPath,
@@ -195,6 +195,7 @@ pub struct TaskTracker<T> {
     sender: mpsc::Sender<Message>,
     started_at: T,
     task_id: TaskId<'static>,
+    attempts: Vec<TaskAttempt>,
 }
 
 #[derive(Debug, Clone)]
@@ -222,6 +223,27 @@ pub struct TaskExecutionSummary {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
     pub exit_code: Option<i32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attempts: Vec<TaskAttempt>,
+}
+
+/// A single run of a task's command. Tasks configured with `retries` or
+/// `timeout` record one of these for every time the command was started.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskAttempt {
+    pub start_time: i64,
+    pub end_time: i64,
+    pub outcome: AttemptOutcome,
+    pub exit_code: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AttemptOutcome {
+    Succeeded,
+    Failed,
+    TimedOut,
 }
 
 impl TaskExecutionSummary {
@@ -264,6 +286,7 @@ impl ExecutionTracker {
             sender: self.sender.clone(),
             task_id,
             started_at: (),
+            attempts: Vec::new(),
         }
     }
 
@@ -302,6 +325,7 @@ impl TaskTracker<()> {
             sender,
             started_at,
             task_id,
+            attempts: Vec::new(),
         }
     }
 
@@ -329,11 +353,27 @@ impl TaskTracker<chrono::DateTime<Local>> {
     // internal turbo error
     pub fn cancel(self) {}
 
+    // Record the outcome of a single run of the task's command
+    pub fn record_attempt(
+        &mut self,
+        started_at: DateTime<Local>,
+        outcome: AttemptOutcome,
+        exit_code: Option<i32>,
+    ) {
+        self.attempts.push(TaskAttempt {
+            start_time: started_at.timestamp_millis(),
+            end_time: Local::now().timestamp_millis(),
+            outcome,
+            exit_code,
+        });
+    }
+
     pub async fn cached(self) -> TaskExecutionSummary {
         let Self {
             sender,
             started_at,
             task_id,
+            attempts,
         } = self;
 
         let ended_at = Local::now();
@@ -343,6 +383,7 @@ impl TaskTracker<chrono::DateTime<Local>> {
             // Go synthesizes a zero exit code on cache hits
             exit_code: Some(0),
             error: None,
+            attempts,
         };
 
         let state = TaskState {
@@ -364,6 +405,7 @@ impl TaskTracker<chrono::DateTime<Local>> {
             sender,
             started_at,
             task_id,
+            attempts,
         } = self;
 
         let ended_at = Local::now();
@@ -372,6 +414,7 @@ impl TaskTracker<chrono::DateTime<Local>> {
             end_time: ended_at.timestamp_millis(),
             exit_code: Some(exit_code),
             error: None,
+            attempts,
         };
 
         let state = TaskState {
@@ -397,6 +440,7 @@ impl TaskTracker<chrono::DateTime<Local>> {
             sender,
             started_at,
             task_id,
+            attempts,
         } = self;
 
         let ended_at = Local::now();
@@ -405,6 +449,7 @@ impl TaskTracker<chrono::DateTime<Local>> {
             end_time: ended_at.timestamp_millis(),
             exit_code,
             error: Some(error.to_string()),
+            attempts,
         };
 
         let state = TaskState {
@@ -488,6 +533,31 @@ mod test {
         );
     }
 
+    #[tokio::test]
+    async fn test_attempts() {
+        let summary = ExecutionTracker::new();
+        let tracker = summary.task_tracker(TaskId::new("foo", "build"));
+        let mut tracker = tracker.start().await;
+        tracker.record_attempt(Local::now(), AttemptOutcome::Failed, Some(1));
+        tracker.record_attempt(Local::now(), AttemptOutcome::Succeeded, Some(0));
+        tracker.build_succeeded(0).await;
+
+        let mut state = summary.finish().await.unwrap();
+        let execution = state.tasks.pop().unwrap().execution.unwrap();
+        let outcomes = execution
+            .attempts
+            .iter()
+            .map(|attempt| (attempt.outcome, attempt.exit_code))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            outcomes,
+            vec![
+                (AttemptOutcome::Failed, Some(1)),
+                (AttemptOutcome::Succeeded, Some(0))
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn test_timing() {
         let summary = ExecutionTracker::new();
@@ -518,7 +588,8 @@ mod test {
             start_time: 123,
             end_time: 234,
             exit_code: Some(0),
-            error: None
+            error: None,
+            attempts: vec![],
         },
         json!({ "startTime": 123, "endTime": 234, "exitCode": 0 })
         ; "success"
@@ -529,10 +600,43 @@ mod test {
             end_time: 234,
             exit_code: Some(1),
             error: Some("cannot find anything".into()),
+            attempts: vec![],
         },
         json!({ "startTime": 123, "endTime": 234, "exitCode": 1, "error": "cannot find anything" })
         ; "failure"
     )]
+    #[test_case(
+        TaskExecutionSummary {
+            start_time: 123,
+            end_time: 456,
+            exit_code: Some(0),
+            error: None,
+            attempts: vec![
+                TaskAttempt {
+                    start_time: 123,
+                    end_time: 234,
+                    outcome: AttemptOutcome::TimedOut,
+                    exit_code: None,
+                },
+                TaskAttempt {
+                    start_time: 345,
+                    end_time: 456,
+                    outcome: AttemptOutcome::Succeeded,
+                    exit_code: Some(0),
+                },
+            ],
+        },
+        json!({
+            "startTime": 123,
+            "endTime": 456,
+            "exitCode": 0,
+            "attempts": [
+                { "startTime": 123, "endTime": 234, "outcome": "timedOut", "exitCode": null },
+                { "startTime": 345, "endTime": 456, "outcome": "succeeded", "exitCode": 0 },
+            ],
+        })
+        ; "retried"
+    )]
     fn test_serialization(value: impl serde::Serialize, expected: serde_json::Value) {
         assert_eq!(serde_json::to_value(value).unwrap(), expected);
     }
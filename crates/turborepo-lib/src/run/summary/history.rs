@@ -0,0 +1,269 @@
+//! A compact record of every run, kept in an append-only file next to the full
+//! run summaries so `turbo stats` can report on many runs without loading each
+//! of their summaries, or needing them to be saved at all. The oldest runs are
+//! dropped once the file grows past `MAX_SIZE`.
+
+use std::{
+    fs::OpenOptions,
+    io::{self, Read, Seek, SeekFrom, Write},
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
+
+use super::{diff::runs_dir, Error};
+
+const HISTORY_FILE: &str = "history.jsonl";
+
+// Once the history grows past this size, only the most recent runs filling
+// half of it are kept
+const MAX_SIZE: u64 = 16 * 1024 * 1024;
+
+// How much of the end of the file is read at a time when looking for the most
+// recent runs
+const READ_CHUNK_SIZE: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunRecord {
+    pub id: String,
+    pub command: String,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub exit_code: i32,
+    pub tasks: Vec<TaskRecord>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskRecord {
+    pub task_id: String,
+    pub hash: String,
+    pub cache_hit: bool,
+    pub cacheable: bool,
+    // Missing for tasks that never started, e.g. because a dependency failed
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub exit_code: Option<i32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<String>,
+    // Finding these compares against the summaries of earlier runs, so they're
+    // only recorded for runs whose summary is saved too
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub miss_causes: Vec<String>,
+}
+
+impl TaskRecord {
+    /// How long the task ran for, in milliseconds
+    pub fn duration(&self) -> Option<i64> {
+        Some(self.end_time? - self.start_time?)
+    }
+}
+
+/// The run history of a repository, one JSON record per line, oldest first
+pub struct History {
+    path: AbsoluteSystemPathBuf,
+    max_size: u64,
+}
+
+impl History {
+    pub fn new(repo_root: &AbsoluteSystemPath) -> Self {
+        Self {
+            path: runs_dir(repo_root).join_component(HISTORY_FILE),
+            max_size: MAX_SIZE,
+        }
+    }
+
+    pub fn append(&self, record: &RunRecord) -> Result<(), Error> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+
+        self.path.ensure_dir()?;
+        let mut options = OpenOptions::new();
+        options.create(true).append(true);
+        let mut file = self.path.open_with_options(options)?;
+        // A single write keeps records from concurrent runs on separate lines
+        file.write_all(&line)?;
+
+        if file.metadata()?.len() > self.max_size {
+            self.truncate()?;
+        }
+
+        Ok(())
+    }
+
+    /// Drops the oldest runs, keeping the ones in the last half of `max_size`
+    fn truncate(&self) -> Result<(), Error> {
+        let mut file = self.path.open()?;
+        let start = file
+            .seek(SeekFrom::End(0))?
+            .saturating_sub(self.max_size / 2);
+        file.seek(SeekFrom::Start(start))?;
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)?;
+        // The first record is likely cut off
+        if start > 0 {
+            let first_line_end = contents
+                .iter()
+                .position(|byte| *byte == b'\n')
+                .map_or(contents.len(), |index| index + 1);
+            contents.drain(..first_line_end);
+        }
+
+        // Replace the file in one step so `recent` never reads a partial one
+        let temp_path = self
+            .path
+            .parent()
+            .expect("history file is in the runs directory")
+            .join_component(&format!("{HISTORY_FILE}.{}", std::process::id()));
+        temp_path.create_with_contents(contents)?;
+        temp_path.rename(&self.path)?;
+
+        Ok(())
+    }
+
+    /// Reads the `limit` most recent runs, oldest first. Only the end of the
+    /// file is read, so this stays fast as the history grows.
+    pub fn recent(&self, limit: usize) -> Result<Vec<RunRecord>, Error> {
+        let mut file = match self.path.open() {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut end = file.seek(SeekFrom::End(0))?;
+        let mut tail = Vec::new();
+        let mut records = Vec::new();
+        while records.len() < limit && end > 0 {
+            let start = end.saturating_sub(READ_CHUNK_SIZE);
+            let mut chunk = vec![0; (end - start) as usize];
+            file.seek(SeekFrom::Start(start))?;
+            file.read_exact(&mut chunk)?;
+            chunk.extend(tail);
+            end = start;
+
+            // The first line of the chunk may be cut off, so it's kept for the
+            // next chunk unless this is the start of the file
+            let mut lines = chunk.split(|byte| *byte == b'\n').collect::<Vec<_>>();
+            tail = match end {
+                0 => Vec::new(),
+                _ => lines.remove(0).to_vec(),
+            };
+            for line in lines.into_iter().rev() {
+                if records.len() == limit {
+                    break;
+                }
+                if line.is_empty() {
+                    continue;
+                }
+                match serde_json::from_slice(line) {
+                    Ok(record) => records.push(record),
+                    Err(err) => debug!("skipping invalid run history record: {err}"),
+                }
+            }
+        }
+
+        records.reverse();
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tempdir::TempDir;
+    use test_case::test_case;
+
+    use super::*;
+
+    fn record(id: usize) -> RunRecord {
+        RunRecord {
+            id: id.to_string(),
+            command: "turbo run build".to_string(),
+            start_time: 0,
+            end_time: 1000,
+            exit_code: 0,
+            tasks: vec![TaskRecord {
+                task_id: "web#build".to_string(),
+                // make records big enough that reading them spans chunks
+                hash: "a".repeat(1000),
+                cache_hit: false,
+                cacheable: true,
+                start_time: Some(0),
+                end_time: Some(1000),
+                exit_code: Some(0),
+                dependencies: vec![],
+                miss_causes: vec!["input src/index.ts was modified".to_string()],
+            }],
+        }
+    }
+
+    #[test_case(0, 5, &[] ; "empty")]
+    #[test_case(3, 5, &["0", "1", "2"] ; "fewer than limit")]
+    #[test_case(200, 3, &["197", "198", "199"] ; "most recent")]
+    fn test_recent(runs: usize, limit: usize, expected: &[&str]) {
+        let dir = TempDir::new("history").unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(dir.path()).unwrap();
+        let history = History::new(&repo_root);
+        for id in 0..runs {
+            history.append(&record(id)).unwrap();
+        }
+
+        let recent = history.recent(limit).unwrap();
+
+        let ids = recent.iter().map(|run| run.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids, expected);
+        if let Some(run) = recent.first() {
+            assert_eq!(run, &record(run.id.parse().unwrap()));
+        }
+    }
+
+    #[test]
+    fn test_drops_oldest_runs() {
+        let dir = TempDir::new("history").unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(dir.path()).unwrap();
+        let history = History {
+            max_size: 10_000,
+            ..History::new(&repo_root)
+        };
+        for id in 0..50 {
+            history.append(&record(id)).unwrap();
+        }
+
+        assert!(history.path.symlink_metadata().unwrap().len() <= history.max_size);
+        let ids = history
+            .recent(usize::MAX)
+            .unwrap()
+            .into_iter()
+            .map(|run| run.id.parse::<usize>().unwrap())
+            .collect::<Vec<_>>();
+        // Every record is complete and the most recent ones are kept in order
+        assert!(!ids.is_empty() && ids.len() < 50);
+        assert_eq!(ids, (50 - ids.len()..50).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_skips_invalid_records() {
+        let dir = TempDir::new("history").unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(dir.path()).unwrap();
+        let history = History::new(&repo_root);
+        history.append(&record(0)).unwrap();
+        let mut options = OpenOptions::new();
+        options.append(true);
+        history
+            .path
+            .open_with_options(options)
+            .unwrap()
+            .write_all(b"{\"id\": \"truncated\n")
+            .unwrap();
+        history.append(&record(1)).unwrap();
+
+        let ids = history
+            .recent(10)
+            .unwrap()
+            .into_iter()
+            .map(|run| run.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["0", "1"]);
+    }
+}
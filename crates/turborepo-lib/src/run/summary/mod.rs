@@ -3,48 +3,65 @@
 //! A tracker tracks the live data and then gets turned into a summary for
 //! displaying it We have this split because the tracker representation is not
 //! exactly what we want to display to the user.
+pub mod diff;
 #[allow(dead_code)]
 mod duration;
 mod execution;
 mod global_hash;
+pub mod history;
+mod report;
 mod scm;
 mod spaces;
+pub mod stats;
 mod task;
 mod task_factory;
-use std::{collections::HashSet, io, io::Write};
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    io::Write,
+};
 
 use chrono::{DateTime, Local};
 pub use duration::TurboDuration;
-pub use execution::{TaskExecutionSummary, TaskTracker};
+pub use execution::{AttemptOutcome, TaskExecutionSummary, TaskTracker};
 pub use global_hash::GlobalHashSummary;
 use itertools::Itertools;
 use serde::Serialize;
 pub use spaces::{SpacesTaskClient, SpacesTaskInformation};
 use svix_ksuid::{Ksuid, KsuidLike};
 use tabwriter::TabWriter;
+pub use task::TaskSummaryTaskDefinition;
 use thiserror::Error;
 use tracing::{error, log::warn};
 use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPath};
 use turborepo_api_client::{spaces::CreateSpaceRunPayload, APIAuth, APIClient};
+use turborepo_ci::Vendor;
 use turborepo_env::EnvironmentVariableMap;
 use turborepo_repository::package_graph::{PackageGraph, PackageName};
 use turborepo_scm::SCM;
 use turborepo_ui::{color, cprintln, cwriteln, BOLD, BOLD_CYAN, GREY, UI};
 
 use self::{
-    execution::TaskState, task::SinglePackageTaskSummary, task_factory::TaskSummaryFactory,
+    diff::{MissExplanation, RunHistory, SavedRunSummary},
+    execution::TaskState,
+    history::{History, RunRecord, TaskRecord},
+    task::SinglePackageTaskSummary,
+    task_factory::TaskSummaryFactory,
 };
 use super::task_id::TaskId;
 use crate::{
     cli,
-    cli::DryRunMode,
+    cli::{DryRunMode, Report},
     engine::Engine,
     opts::RunOpts,
-    run::summary::{
-        execution::{ExecutionSummary, ExecutionTracker},
-        scm::SCMState,
-        spaces::{SpaceRequest, SpacesClient, SpacesClientHandle},
-        task::TaskSummary,
+    run::{
+        otel::{self, OtelOpts, RunTrace, TaskSpan},
+        summary::{
+            execution::{ExecutionSummary, ExecutionTracker},
+            scm::SCMState,
+            spaces::{SpaceRequest, SpacesClient, SpacesClientHandle},
+            task::TaskSummary,
+        },
     },
     task_hash::TaskHashTracker,
 };
@@ -125,9 +142,15 @@ pub struct RunSummary<'a> {
     #[serde(skip)]
     should_save: bool,
     #[serde(skip)]
+    explain_miss: bool,
+    #[serde(skip)]
+    reports: &'a [Report],
+    #[serde(skip)]
     run_type: RunType,
     #[serde(skip)]
     spaces_client_handle: Option<SpacesClientHandle>,
+    #[serde(skip)]
+    otel: Option<&'a OtelOpts>,
 }
 
 /// We use this to track the run, so it's constructed before the run.
@@ -208,7 +231,8 @@ impl RunTracker {
         task_factory: TaskSummaryFactory<'a>,
     ) -> Result<RunSummary<'a>, Error> {
         let single_package = run_opts.single_package;
-        let should_save = run_opts.summarize.flatten().is_some_and(|s| s);
+        // Explaining cache misses relies on the summaries of earlier runs
+        let should_save = run_opts.summarize.flatten().is_some_and(|s| s) || run_opts.explain_miss;
 
         let run_type = match run_opts.dry_run {
             None => RunType::Real,
@@ -248,8 +272,11 @@ impl RunTracker {
             monorepo: !single_package,
             repo_root,
             should_save,
+            explain_miss: run_opts.explain_miss,
+            reports: &run_opts.reports,
             run_type,
             spaces_client_handle: self.spaces_client_handle,
+            otel: run_opts.otel.as_ref(),
         })
     }
 
@@ -385,11 +412,32 @@ impl<'a> RunSummary<'a> {
             return self.close_dry_run(pkg_dep_graph, ui);
         }
 
+        // Explaining misses reads the summaries of earlier runs, which are only
+        // written when this one is saved too. Runs that aren't saved are still
+        // recorded in the history, just without their miss causes.
+        let misses = if self.should_save {
+            self.cache_misses().unwrap_or_else(|err| {
+                warn!("Error explaining cache misses: {}", err);
+                Vec::new()
+            })
+        } else {
+            Vec::new()
+        };
+
         if self.should_save {
             if let Err(err) = self.save() {
                 warn!("Error writing run summary: {}", err)
             }
         }
+        // Unlike the full summary, every run is recorded so that `turbo stats`
+        // covers normal usage
+        if let Err(err) = self.record_history(&misses) {
+            warn!("Error writing run history: {}", err)
+        }
+
+        if let Err(err) = self.write_reports() {
+            warn!("Error writing report: {}", err)
+        }
 
         if let Some(execution) = &self.execution {
             let path = self.get_path();
@@ -397,14 +445,158 @@ impl<'a> RunSummary<'a> {
             execution.print(ui, path, failed_tasks);
         }
 
+        print!(
+            "{}",
+            self.annotations(Vendor::infer().and_then(|vendor| vendor.behavior.as_ref()))
+        );
+
+        if self.explain_miss {
+            Self::explain_misses(&misses, ui);
+        }
+
         if let Some(spaces_client_handle) = self.spaces_client_handle.take() {
             self.send_to_space(spaces_client_handle, end_time, exit_code)
                 .await;
         }
 
+        if let (Some(opts), Some(trace)) = (self.otel, self.otel_trace()) {
+            if let Err(err) = otel::export(opts, &trace).await {
+                warn!("Error exporting run to OpenTelemetry: {}", err);
+            }
+        }
+
         Ok(())
     }
 
+    fn otel_trace(&self) -> Option<RunTrace> {
+        let execution = self.execution.as_ref()?;
+        // The ksuid's payload is random, so it doubles as a trace id that can
+        // be traced back to the run summary
+        let trace_id = self.id.payload().try_into().ok()?;
+        let tasks = self
+            .tasks
+            .iter()
+            .filter_map(|task| {
+                // Tasks that never started, e.g. because a dependency failed,
+                // don't get a span
+                let task_execution = task.shared.execution.as_ref()?;
+                let cache = &task.shared.cache;
+                Some(TaskSpan {
+                    task_id: task.task_id.to_string(),
+                    package: &task.package,
+                    hash: &task.shared.hash,
+                    cache_hit: cache.is_hit(),
+                    cache_source: cache.source(),
+                    time_saved: cache.time_saved(),
+                    start_time: task_execution.start_time,
+                    end_time: task_execution.end_time,
+                    exit_code: task_execution.exit_code,
+                    error: task_execution.error.as_deref(),
+                })
+            })
+            .collect();
+
+        Some(RunTrace {
+            run_id: self.id.to_string(),
+            trace_id,
+            command: &execution.command,
+            turbo_version: self.turbo_version,
+            start_time: execution.start_time,
+            end_time: execution.end_time,
+            exit_code: execution.exit_code,
+            tasks,
+        })
+    }
+
+    /// Compares each task that missed the cache against the most recent saved
+    /// run that includes it
+    fn cache_misses(&mut self) -> Result<Vec<(String, MissExplanation)>, Error> {
+        let current = SavedRunSummary::from_json(&self.format_json()?)?;
+        let mut history = RunHistory::new(self.repo_root, &current.id);
+        Ok(current
+            .cache_misses()
+            .map(|task_id| {
+                let explanation = MissExplanation::new(&mut history, &current, task_id);
+                (task_id.to_string(), explanation)
+            })
+            .collect())
+    }
+
+    fn explain_misses(misses: &[(String, MissExplanation)], ui: UI) {
+        if misses.is_empty() {
+            return;
+        }
+
+        cprintln!(ui, BOLD_CYAN, "Cache Misses");
+        for (task_id, explanation) in misses {
+            let task = color!(ui, BOLD, "{}", task_id);
+            match explanation {
+                MissExplanation::NoEarlierRun => {
+                    println!("{task} has no earlier run saved in .turbo/runs to compare against")
+                }
+                MissExplanation::SameHash { previous } => println!(
+                    "{task} has the same hash as in run {previous}, so its outputs weren't in the \
+                     cache or the cache was bypassed"
+                ),
+                MissExplanation::UnrecordedChange { previous } => println!(
+                    "{task} has a different hash than in run {previous}, but none of the inputs \
+                     recorded in run summaries changed"
+                ),
+                MissExplanation::Changed { previous, diff } => {
+                    println!("{task} changed since run {previous}:");
+                    diff.print(ui);
+                }
+            }
+        }
+        println!();
+    }
+
+    /// Appends this run to the history that `turbo stats` reports on
+    fn record_history(&self, misses: &[(String, MissExplanation)]) -> Result<(), Error> {
+        let Some(execution) = &self.execution else {
+            return Ok(());
+        };
+        let causes = misses
+            .iter()
+            .map(|(task_id, explanation)| (task_id.as_str(), explanation.causes()))
+            .collect::<HashMap<_, _>>();
+
+        let tasks = self
+            .tasks
+            .iter()
+            .map(|task| {
+                let task_id = task.task_id.to_string();
+                let miss_causes = causes.get(task_id.as_str()).cloned().unwrap_or_default();
+                let task_execution = task.shared.execution.as_ref();
+                TaskRecord {
+                    hash: task.shared.hash.clone(),
+                    cache_hit: task.shared.cache.is_hit(),
+                    cacheable: task.shared.resolved_task_definition.cacheable(),
+                    start_time: task_execution.map(|execution| execution.start_time),
+                    end_time: task_execution.map(|execution| execution.end_time),
+                    exit_code: task_execution.and_then(|execution| execution.exit_code),
+                    dependencies: task
+                        .shared
+                        .dependencies
+                        .iter()
+                        .map(|dependency| dependency.to_string())
+                        .collect(),
+                    miss_causes,
+                    task_id,
+                }
+            })
+            .collect();
+
+        History::new(self.repo_root).append(&RunRecord {
+            id: self.id.to_string(),
+            command: execution.command.clone(),
+            start_time: execution.start_time,
+            end_time: execution.end_time,
+            exit_code: execution.exit_code,
+            tasks,
+        })
+    }
+
     #[tracing::instrument(skip_all)]
     async fn send_to_space(
         &self,
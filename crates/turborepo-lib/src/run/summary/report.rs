@@ -0,0 +1,430 @@
+//! Renders the results of a run in formats that CI systems understand: report
+//! files requested with `--report`, and error annotations for the detected CI
+//! vendor. GitLab has no way to annotate a job from its log, so failures are
+//! surfaced there through the JUnit and Code Quality report artifacts instead.
+
+use std::{collections::BTreeMap, fmt::Write};
+
+use serde::Serialize;
+use thiserror::Error;
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
+use turborepo_ci::VendorBehavior;
+
+use super::{task::TaskSummary, RunSummary};
+use crate::cli::ReportFormat;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to write {format} report to {path}: {source}")]
+    Write {
+        format: ReportFormat,
+        path: AbsoluteSystemPathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    Summary(#[from] Box<super::Error>),
+    #[error("failed to render {format} report: {source}")]
+    Render {
+        format: ReportFormat,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+impl<'a> RunSummary<'a> {
+    pub(super) fn write_reports(&mut self) -> Result<(), Error> {
+        for report in self.reports {
+            // Relative paths are resolved the same way as `--graph`
+            let path = AbsoluteSystemPathBuf::from_unknown(self.repo_root, report.path.clone());
+            let contents = match report.format {
+                ReportFormat::Junit => self.junit(),
+                ReportFormat::Json => self.format_json().map_err(Box::new)?,
+                ReportFormat::CodeQuality => {
+                    code_quality(&self.tasks).map_err(|source| Error::Render {
+                        format: report.format,
+                        source,
+                    })?
+                }
+            };
+            path.ensure_dir()
+                .and_then(|()| path.create_with_contents(contents))
+                .map_err(|source| Error::Write {
+                    format: report.format,
+                    path: path.clone(),
+                    source,
+                })?;
+        }
+        Ok(())
+    }
+
+    fn junit(&self) -> String {
+        let time = self.execution.as_ref().map_or(0.0, |execution| {
+            seconds(execution.end_time - execution.start_time)
+        });
+        junit(self.repo_root, &self.tasks, time)
+    }
+
+    /// Renders an error annotation for every failed task, if the CI vendor
+    /// supports them
+    pub(super) fn annotations(&self, behavior: Option<&VendorBehavior>) -> String {
+        let Some(annotate) = behavior.and_then(|behavior| behavior.error_annotation) else {
+            return String::new();
+        };
+        self.get_failed_tasks()
+            .into_iter()
+            .map(|task| {
+                let message = task
+                    .shared
+                    .execution
+                    .as_ref()
+                    .and_then(|execution| execution.error.as_deref())
+                    .unwrap_or("task failed");
+                annotate(&format!("{} failed", task.task_id), message)
+            })
+            .collect()
+    }
+}
+
+/// Renders the tasks as JUnit XML, with a test suite per package and a test
+/// case per task. Failed tasks include their logs.
+fn junit(repo_root: &AbsoluteSystemPath, tasks: &[TaskSummary], time: f64) -> String {
+    let mut packages: BTreeMap<&str, Vec<&TaskSummary>> = BTreeMap::new();
+    for task in tasks {
+        packages.entry(&task.package).or_default().push(task);
+    }
+
+    let mut suites = String::new();
+    let (mut total_tests, mut total_failures, mut total_skipped) = (0, 0, 0);
+    for (package, tasks) in packages {
+        let mut cases = String::new();
+        let tests = tasks.len();
+        let (mut failures, mut skipped, mut time) = (0, 0, 0.0);
+        for task in tasks {
+            let cache = &task.shared.cache;
+            let task_time = task.shared.execution.as_ref().map_or(0.0, |execution| {
+                seconds(execution.end_time - execution.start_time)
+            });
+            time += task_time;
+            let _ = writeln!(
+                cases,
+                r#"    <testcase name="{}" classname="{}" time="{task_time:.3}">"#,
+                escape(&task.task_id.to_string()),
+                escape(package),
+            );
+            cases.push_str("      <properties>\n");
+            let mut properties = vec![
+                ("turbo.hash", task.shared.hash.clone()),
+                (
+                    "turbo.cache.status",
+                    if cache.is_hit() { "HIT" } else { "MISS" }.to_string(),
+                ),
+            ];
+            if let Some(source) = cache.source() {
+                properties.push(("turbo.cache.source", source.to_string()));
+                properties.push(("turbo.cache.time_saved_ms", cache.time_saved().to_string()));
+            }
+            for (name, value) in properties {
+                let _ = writeln!(
+                    cases,
+                    r#"        <property name="{name}" value="{}"/>"#,
+                    escape(&value)
+                );
+            }
+            cases.push_str("      </properties>\n");
+            match &task.shared.execution {
+                None => {
+                    skipped += 1;
+                    cases.push_str(r#"      <skipped message="task did not run"/>"#);
+                    cases.push('\n');
+                }
+                Some(execution) if execution.is_failure() => {
+                    failures += 1;
+                    let message = execution.error.as_deref().unwrap_or("task failed");
+                    let _ = writeln!(
+                        cases,
+                        r#"      <failure message="{}" type="{}">{}</failure>"#,
+                        escape(message),
+                        execution
+                            .exit_code
+                            .map_or("error".to_string(), |code| format!("exit code {code}")),
+                        escape(&task_log(repo_root, task))
+                    );
+                }
+                Some(_) => (),
+            }
+            cases.push_str("    </testcase>\n");
+        }
+
+        total_tests += tests;
+        total_failures += failures;
+        total_skipped += skipped;
+        let _ = writeln!(
+            suites,
+            r#"  <testsuite name="{}" tests="{tests}" failures="{failures}" errors="0" skipped="{skipped}" time="{time:.3}">"#,
+            escape(package),
+        );
+        suites.push_str(&cases);
+        suites.push_str("  </testsuite>\n");
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites name=\"turbo run\" \
+         tests=\"{total_tests}\" failures=\"{total_failures}\" errors=\"0\" \
+         skipped=\"{total_skipped}\" time=\"{time:.3}\">\n{suites}</testsuites>\n"
+    )
+}
+
+fn task_log(repo_root: &AbsoluteSystemPath, task: &TaskSummary) -> String {
+    let path = AbsoluteSystemPathBuf::from_unknown(repo_root, task.shared.log_file.as_str());
+    // A missing log shouldn't stop the rest of the report from being written
+    path.read_to_string()
+        .map(|log| console::strip_ansi_codes(&log).into_owned())
+        .unwrap_or_default()
+}
+
+// https://docs.gitlab.com/ee/ci/testing/code_quality.html#implement-a-custom-tool
+#[derive(Debug, Serialize)]
+struct CodeQualityIssue {
+    description: String,
+    check_name: &'static str,
+    fingerprint: String,
+    severity: &'static str,
+    location: CodeQualityLocation,
+}
+
+#[derive(Debug, Serialize)]
+struct CodeQualityLocation {
+    path: String,
+    lines: CodeQualityLines,
+}
+
+#[derive(Debug, Serialize)]
+struct CodeQualityLines {
+    begin: u32,
+}
+
+/// Renders the failed tasks as a GitLab Code Quality report, pointing each
+/// issue at the package.json of the failed task's package
+fn code_quality(tasks: &[TaskSummary]) -> Result<String, serde_json::Error> {
+    let issues = tasks
+        .iter()
+        .filter_map(|task| {
+            let execution = task.shared.execution.as_ref()?;
+            if !execution.is_failure() {
+                return None;
+            }
+            let message = execution.error.as_deref().unwrap_or("task failed");
+            let package_json = task
+                .shared
+                .directory
+                .as_deref()
+                .and_then(|directory| AnchoredSystemPathBuf::from_raw(directory).ok())
+                .unwrap_or_default()
+                .join_component("package.json")
+                .to_unix();
+            Some(CodeQualityIssue {
+                description: format!("{} failed: {message}", task.task_id),
+                check_name: "turbo-task-failure",
+                // Stable across runs so GitLab can tell when a failure is fixed
+                fingerprint: task.task_id.to_string(),
+                severity: "major",
+                location: CodeQualityLocation {
+                    path: package_json.to_string(),
+                    lines: CodeQualityLines { begin: 1 },
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+    serde_json::to_string_pretty(&issues)
+}
+
+fn seconds(millis: i64) -> f64 {
+    millis.max(0) as f64 / 1000.0
+}
+
+// Escapes text for use in XML attributes and content, dropping control
+// characters that XML 1.0 can't represent at all
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() => (),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use pretty_assertions::assert_eq;
+    use turbopath::AbsoluteSystemPathBuf;
+    use turborepo_cache::{CacheHitMetadata, CacheSource};
+
+    use super::{code_quality, escape, junit};
+    use crate::run::{
+        summary::{
+            execution::TaskExecutionSummary,
+            task::{
+                SharedTaskSummary, TaskCacheSummary, TaskEnvConfiguration, TaskEnvVarSummary,
+                TaskSummary, TaskSummaryTaskDefinition,
+            },
+            EnvMode,
+        },
+        task_id::TaskId,
+    };
+
+    fn task(
+        package: &str,
+        task: &str,
+        cache: TaskCacheSummary,
+        execution: Option<TaskExecutionSummary>,
+    ) -> TaskSummary {
+        TaskSummary {
+            task_id: TaskId::new(package, task).into_owned(),
+            task: task.to_string(),
+            package: package.to_string(),
+            shared: SharedTaskSummary {
+                hash: format!("{package}-{task}-hash"),
+                inputs: BTreeMap::new(),
+                hash_of_external_dependencies: String::new(),
+                external_dependencies: Vec::new(),
+                cache,
+                command: format!("echo {task}"),
+                cli_arguments: Vec::new(),
+                outputs: None,
+                excluded_outputs: None,
+                log_file: format!("apps/{package}/.turbo/turbo-{task}.log"),
+                directory: Some(format!("apps/{package}")),
+                dependencies: Vec::new(),
+                dependents: Vec::new(),
+                resolved_task_definition: TaskSummaryTaskDefinition::default(),
+                expanded_outputs: Vec::new(),
+                framework: String::new(),
+                env_mode: EnvMode::Strict,
+                environment_variables: TaskEnvVarSummary {
+                    specified: TaskEnvConfiguration {
+                        env: Vec::new(),
+                        pass_through_env: None,
+                    },
+                    configured: Vec::new(),
+                    inferred: Vec::new(),
+                    pass_through: None,
+                    dot_env: None,
+                },
+                dot_env: None,
+                execution,
+            },
+        }
+    }
+
+    fn execution(start_time: i64, end_time: i64, exit_code: i32) -> TaskExecutionSummary {
+        TaskExecutionSummary {
+            start_time,
+            end_time,
+            error: (exit_code != 0).then(|| format!("command exited ({exit_code})")),
+            exit_code: Some(exit_code),
+            attempts: Vec::new(),
+        }
+    }
+
+    fn tasks() -> Vec<TaskSummary> {
+        let hit = TaskCacheSummary::from(Some(CacheHitMetadata {
+            source: CacheSource::Local,
+            time_saved: 1200,
+        }));
+        vec![
+            task("docs", "build", hit, Some(execution(0, 0, 0))),
+            // A dependency failed, so this never ran
+            task("docs", "test", TaskCacheSummary::cache_miss(), None),
+            task(
+                "web",
+                "build",
+                TaskCacheSummary::cache_miss(),
+                Some(execution(1000, 3500, 0)),
+            ),
+            task(
+                "web",
+                "lint",
+                TaskCacheSummary::cache_miss(),
+                Some(execution(1000, 1500, 1)),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_junit() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        let log = repo_root.join_components(&["apps", "web", ".turbo", "turbo-lint.log"]);
+        log.ensure_dir().unwrap();
+        log.create_with_contents("\x1b[31merror\x1b[0m: a < b\n")
+            .unwrap();
+
+        let report = junit(&repo_root, &tasks(), 4.0);
+
+        assert!(report.contains(
+            r#"<testsuites name="turbo run" tests="4" failures="1" errors="0" skipped="1" time="4.000">"#
+        ));
+        assert!(report.contains(
+            r#"<testsuite name="docs" tests="2" failures="0" errors="0" skipped="1" time="0.000">"#
+        ));
+        assert!(report.contains(
+            r#"<testsuite name="web" tests="2" failures="1" errors="0" skipped="0" time="3.000">"#
+        ));
+        assert!(report.contains(
+            r#"<property name="turbo.cache.status" value="HIT"/>
+        <property name="turbo.cache.source" value="LOCAL"/>
+        <property name="turbo.cache.time_saved_ms" value="1200"/>"#
+        ));
+        assert!(report.contains(
+            r#"<testcase name="docs#test" classname="docs" time="0.000">
+      <properties>
+        <property name="turbo.hash" value="docs-test-hash"/>
+        <property name="turbo.cache.status" value="MISS"/>
+      </properties>
+      <skipped message="task did not run"/>"#
+        ));
+        assert!(report.contains(
+            "<failure message=\"command exited (1)\" type=\"exit code 1\">error: a &lt; \
+             b\n</failure>"
+        ));
+        assert_eq!(report.matches("<failure").count(), 1);
+    }
+
+    #[test]
+    fn test_code_quality() {
+        let report: serde_json::Value =
+            serde_json::from_str(&code_quality(&tasks()).unwrap()).unwrap();
+        assert_eq!(
+            report,
+            serde_json::json!([{
+                "description": "web#lint failed: command exited (1)",
+                "check_name": "turbo-task-failure",
+                "fingerprint": "web#lint",
+                "severity": "major",
+                "location": {
+                    "path": "apps/web/package.json",
+                    "lines": { "begin": 1 }
+                }
+            }])
+        );
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape("<a href=\"x\">Tom & Jerry's</a>\x1b\n"),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&apos;s&lt;/a&gt;\n"
+        );
+    }
+}
@@ -0,0 +1,345 @@
+//! Trends across the runs recorded in the run history, reported by
+//! `turbo stats`
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+
+use super::history::RunRecord;
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Stats {
+    pub runs: usize,
+    pub tasks: Vec<TaskStats>,
+    pub miss_causes: Vec<MissCause>,
+    pub critical_path: CriticalPath,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskStats {
+    pub task_id: String,
+    pub runs: usize,
+    pub cache_hits: usize,
+    /// Durations in milliseconds of the runs where the task executed instead
+    /// of being restored from the cache, oldest first
+    pub durations: Vec<i64>,
+    pub mean_duration: Option<i64>,
+    pub max_duration: Option<i64>,
+    /// How much the most recent duration differs from the mean of the earlier
+    /// ones, as a fraction of that mean
+    pub trend: Option<f64>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissCause {
+    pub cause: String,
+    pub count: usize,
+}
+
+/// The chain of dependent tasks with the longest total mean duration in the
+/// most recent run
+#[derive(Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CriticalPath {
+    pub tasks: Vec<String>,
+    pub duration: i64,
+}
+
+impl TaskStats {
+    pub fn hit_rate(&self) -> f64 {
+        match self.runs {
+            0 => 0.0,
+            runs => self.cache_hits as f64 / runs as f64,
+        }
+    }
+}
+
+impl Stats {
+    /// Summarizes `runs`, which are expected oldest first
+    pub fn new(runs: &[RunRecord]) -> Self {
+        let mut by_task: BTreeMap<&str, TaskStats> = BTreeMap::new();
+        let mut miss_causes: HashMap<&str, usize> = HashMap::new();
+        for task in runs.iter().flat_map(|run| &run.tasks) {
+            let stats = by_task
+                .entry(task.task_id.as_str())
+                .or_insert_with(|| TaskStats {
+                    task_id: task.task_id.clone(),
+                    runs: 0,
+                    cache_hits: 0,
+                    durations: Vec::new(),
+                    mean_duration: None,
+                    max_duration: None,
+                    trend: None,
+                });
+            stats.runs += 1;
+            if task.cache_hit {
+                stats.cache_hits += 1;
+            } else if let Some(duration) = task.duration() {
+                // Restoring from the cache says nothing about how long the
+                // task takes, so only executions count towards durations
+                stats.durations.push(duration);
+            }
+            for cause in &task.miss_causes {
+                *miss_causes.entry(cause.as_str()).or_default() += 1;
+            }
+        }
+
+        let mut tasks = by_task.into_values().collect::<Vec<_>>();
+        for stats in &mut tasks {
+            stats.mean_duration = mean(&stats.durations);
+            stats.max_duration = stats.durations.iter().copied().max();
+            stats.trend = match stats.durations.split_last() {
+                Some((last, earlier)) => mean(earlier)
+                    .filter(|mean| *mean > 0)
+                    .map(|mean| (*last - mean) as f64 / mean as f64),
+                None => None,
+            };
+        }
+
+        let mut miss_causes = miss_causes
+            .into_iter()
+            .map(|(cause, count)| MissCause {
+                cause: cause.to_string(),
+                count,
+            })
+            .collect::<Vec<_>>();
+        miss_causes.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.cause.cmp(&b.cause)));
+
+        let critical_path = runs
+            .last()
+            .map(|run| critical_path(run, &tasks))
+            .unwrap_or_default();
+
+        Self {
+            runs: runs.len(),
+            tasks,
+            miss_causes,
+            critical_path,
+        }
+    }
+
+    /// Tasks ordered by their mean duration, slowest first
+    pub fn slowest(&self) -> Vec<&TaskStats> {
+        let mut tasks = self
+            .tasks
+            .iter()
+            .filter(|task| task.mean_duration.is_some())
+            .collect::<Vec<_>>();
+        tasks.sort_by(|a, b| b.mean_duration.cmp(&a.mean_duration));
+        tasks
+    }
+}
+
+fn mean(durations: &[i64]) -> Option<i64> {
+    match durations.len() {
+        0 => None,
+        len => Some(durations.iter().sum::<i64>() / len as i64),
+    }
+}
+
+// Tasks are weighted by their mean duration rather than how long they took in
+// the last run, since a cache hit there would otherwise hide them
+fn critical_path(run: &RunRecord, tasks: &[TaskStats]) -> CriticalPath {
+    let weights = tasks
+        .iter()
+        .map(|task| (task.task_id.as_str(), task.mean_duration.unwrap_or(0)))
+        .collect::<HashMap<_, _>>();
+    let dependencies = run
+        .tasks
+        .iter()
+        .map(|task| (task.task_id.as_str(), task.dependencies.as_slice()))
+        .collect::<HashMap<_, _>>();
+
+    // The longest path ending at each task, and the dependency it came through
+    let mut longest: HashMap<&str, (i64, Option<&str>)> = HashMap::new();
+    for task in &run.tasks {
+        longest_path_to(&task.task_id, &dependencies, &weights, &mut longest);
+    }
+
+    let Some((mut current, (duration, _))) = longest
+        .iter()
+        .max_by(|a, b| a.1 .0.cmp(&b.1 .0).then_with(|| b.0.cmp(a.0)))
+        .map(|(task, path)| (*task, *path))
+    else {
+        return CriticalPath::default();
+    };
+    let mut path = vec![current.to_string()];
+    while let Some((_, Some(previous))) = longest.get(current) {
+        path.push(previous.to_string());
+        current = *previous;
+    }
+    path.reverse();
+
+    CriticalPath {
+        tasks: path,
+        duration,
+    }
+}
+
+fn longest_path_to<'a>(
+    task_id: &'a str,
+    dependencies: &HashMap<&'a str, &'a [String]>,
+    weights: &HashMap<&str, i64>,
+    longest: &mut HashMap<&'a str, (i64, Option<&'a str>)>,
+) -> i64 {
+    if let Some((duration, _)) = longest.get(task_id) {
+        return *duration;
+    }
+    // Mark the task before visiting its dependencies so a malformed record
+    // with a cycle can't recurse forever
+    longest.insert(task_id, (0, None));
+
+    let mut through = None;
+    let mut before = 0;
+    for dependency in dependencies.get(task_id).copied().unwrap_or_default() {
+        // Dependencies that didn't run, e.g. because they were filtered out,
+        // aren't part of the run's graph
+        if !dependencies.contains_key(dependency.as_str()) {
+            continue;
+        }
+        let duration = longest_path_to(dependency, dependencies, weights, longest);
+        if through.is_none() || duration > before {
+            before = duration;
+            through = Some(dependency.as_str());
+        }
+    }
+
+    let duration = before + weights.get(task_id).copied().unwrap_or(0);
+    longest.insert(task_id, (duration, through));
+    duration
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::run::summary::history::TaskRecord;
+
+    fn task(task_id: &str, duration: i64, cache_hit: bool, dependencies: &[&str]) -> TaskRecord {
+        TaskRecord {
+            task_id: task_id.to_string(),
+            hash: "hash".to_string(),
+            cache_hit,
+            cacheable: true,
+            start_time: Some(0),
+            end_time: Some(duration),
+            exit_code: Some(0),
+            dependencies: dependencies.iter().map(|dep| dep.to_string()).collect(),
+            miss_causes: match cache_hit {
+                true => vec![],
+                false => vec!["input src/index.ts was modified".to_string()],
+            },
+        }
+    }
+
+    fn run(id: &str, tasks: Vec<TaskRecord>) -> RunRecord {
+        RunRecord {
+            id: id.to_string(),
+            command: "turbo run build".to_string(),
+            start_time: 0,
+            end_time: 0,
+            exit_code: 0,
+            tasks,
+        }
+    }
+
+    #[test]
+    fn test_task_stats() {
+        let runs = [
+            run("1", vec![task("web#build", 100, false, &[])]),
+            run("2", vec![task("web#build", 5, true, &[])]),
+            run("3", vec![task("web#build", 300, false, &[])]),
+        ];
+
+        let stats = Stats::new(&runs);
+
+        assert_eq!(stats.runs, 3);
+        assert_eq!(
+            stats.tasks,
+            [TaskStats {
+                task_id: "web#build".to_string(),
+                runs: 3,
+                cache_hits: 1,
+                durations: vec![100, 300],
+                mean_duration: Some(200),
+                max_duration: Some(300),
+                trend: Some(2.0),
+            }]
+        );
+        assert_eq!(
+            stats.miss_causes,
+            [MissCause {
+                cause: "input src/index.ts was modified".to_string(),
+                count: 2,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_slowest() {
+        let runs = [run(
+            "1",
+            vec![
+                task("a#build", 10, false, &[]),
+                task("b#build", 30, false, &[]),
+                task("c#build", 20, true, &[]),
+            ],
+        )];
+
+        let stats = Stats::new(&runs);
+
+        let slowest = stats
+            .slowest()
+            .into_iter()
+            .map(|task| task.task_id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(slowest, ["b#build", "a#build"]);
+    }
+
+    #[test]
+    fn test_critical_path() {
+        let runs = [
+            run(
+                "1",
+                vec![
+                    task("utils#build", 100, false, &[]),
+                    task("ui#build", 50, false, &["utils#build"]),
+                ],
+            ),
+            run(
+                "2",
+                vec![
+                    task("utils#build", 0, true, &[]),
+                    task("ui#build", 10, true, &["utils#build"]),
+                    task("web#build", 200, false, &["ui#build", "config#build"]),
+                    task("docs#build", 20, false, &["utils#build"]),
+                ],
+            ),
+        ];
+
+        let stats = Stats::new(&runs);
+
+        assert_eq!(
+            stats.critical_path,
+            CriticalPath {
+                tasks: vec![
+                    "utils#build".to_string(),
+                    "ui#build".to_string(),
+                    "web#build".to_string()
+                ],
+                duration: 350,
+            }
+        );
+    }
+
+    #[test]
+    fn test_empty() {
+        let stats = Stats::new(&[]);
+
+        assert_eq!(stats.runs, 0);
+        assert!(stats.tasks.is_empty());
+        assert_eq!(stats.critical_path, CriticalPath::default());
+    }
+}
@@ -3,7 +3,8 @@ use std::collections::BTreeMap;
 use serde::Serialize;
 use turbopath::{AnchoredSystemPathBuf, RelativeUnixPathBuf};
 use turborepo_cache::CacheHitMetadata;
-use turborepo_env::{DetailedMap, EnvironmentVariableMap};
+use turborepo_env::{dotenv::DotEnv, DetailedMap, EnvironmentVariableMap};
+use turborepo_lockfiles::Package;
 
 use super::{execution::TaskExecutionSummary, EnvMode};
 use crate::{
@@ -66,6 +67,8 @@ pub(crate) struct SharedTaskSummary<T> {
     pub hash: String,
     pub inputs: BTreeMap<RelativeUnixPathBuf, String>,
     pub hash_of_external_dependencies: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub external_dependencies: Vec<Package>,
     pub cache: TaskCacheSummary,
     pub command: String,
     pub cli_arguments: Vec<String>,
@@ -105,6 +108,22 @@ pub struct TaskSummaryTaskDefinition {
     env: Vec<String>,
     pass_through_env: Option<Vec<String>>,
     dot_env: Option<Vec<RelativeUnixPathBuf>>,
+    #[serde(skip_serializing_if = "is_false")]
+    inject_dot_env: bool,
+    #[serde(skip_serializing_if = "is_zero")]
+    retries: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry_delay: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timeout: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    resources: BTreeMap<String, u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    command: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cwd: Option<RelativeUnixPathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shell: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -115,6 +134,16 @@ pub struct TaskEnvVarSummary {
     pub inferred: Vec<String>,
     #[serde(rename = "passthrough")]
     pub pass_through: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dot_env: Option<Vec<DotEnvFileSummary>>,
+}
+
+/// The variables a `dotEnv` file injected into the task's environment
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DotEnvFileSummary {
+    pub file: String,
+    pub variables: Vec<String>,
 }
 
 impl TaskCacheSummary {
@@ -127,6 +156,22 @@ impl TaskCacheSummary {
             source: None,
         }
     }
+
+    pub(crate) fn is_hit(&self) -> bool {
+        matches!(self.status, CacheStatus::Hit)
+    }
+
+    // Matches how the source is serialized in the summary
+    pub(crate) fn source(&self) -> Option<&'static str> {
+        self.source.map(|source| match source {
+            CacheSource::Local => "LOCAL",
+            CacheSource::Remote => "REMOTE",
+        })
+    }
+
+    pub(crate) fn time_saved(&self) -> u64 {
+        self.time_saved
+    }
 }
 
 impl From<Option<CacheHitMetadata>> for TaskCacheSummary {
@@ -173,6 +218,7 @@ impl TaskEnvVarSummary {
         task_definition: &TaskDefinition,
         env_vars: DetailedMap,
         env_at_execution_start: &EnvironmentVariableMap,
+        dot_env: Option<&DotEnv>,
     ) -> Result<Self, turborepo_env::Error> {
         // TODO: this operation differs from the actual env that gets passed in during
         // task execution it should be unified, but first we should copy Go's
@@ -195,6 +241,16 @@ impl TaskEnvVarSummary {
             configured: env_vars.by_source.explicit.to_secret_hashable(),
             inferred: env_vars.by_source.matching.to_secret_hashable(),
             pass_through,
+            dot_env: dot_env.map(|dot_env| {
+                dot_env
+                    .by_file()
+                    .into_iter()
+                    .map(|(file, vars)| DotEnvFileSummary {
+                        file: file.to_string(),
+                        variables: vars.to_secret_hashable(),
+                    })
+                    .collect()
+            }),
         })
     }
 }
@@ -218,6 +274,7 @@ impl From<SharedTaskSummary<TaskId<'static>>> for SharedTaskSummary<String> {
             hash,
             inputs,
             hash_of_external_dependencies,
+            external_dependencies,
             cache,
             command,
             cli_arguments,
@@ -239,6 +296,7 @@ impl From<SharedTaskSummary<TaskId<'static>>> for SharedTaskSummary<String> {
             hash,
             inputs,
             hash_of_external_dependencies,
+            external_dependencies,
             cache,
             command,
             cli_arguments,
@@ -265,6 +323,12 @@ impl From<SharedTaskSummary<TaskId<'static>>> for SharedTaskSummary<String> {
     }
 }
 
+impl TaskSummaryTaskDefinition {
+    pub(crate) fn cacheable(&self) -> bool {
+        self.cache
+    }
+}
+
 impl From<TaskDefinition> for TaskSummaryTaskDefinition {
     fn from(value: TaskDefinition) -> Self {
         let TaskDefinition {
@@ -277,11 +341,19 @@ impl From<TaskDefinition> for TaskSummaryTaskDefinition {
             mut env,
             pass_through_env,
             dot_env,
+            inject_dot_env,
             topological_dependencies,
             task_dependencies,
             mut inputs,
             output_mode,
             persistent,
+            retries,
+            retry_delay,
+            timeout,
+            resources,
+            command,
+            cwd,
+            shell,
         } = value;
 
         let mut outputs = inclusions;
@@ -317,10 +389,27 @@ impl From<TaskDefinition> for TaskSummaryTaskDefinition {
             pass_through_env,
             // This should _not_ be sorted.
             dot_env,
+            inject_dot_env,
+            retries,
+            retry_delay: retry_delay.map(|delay| humantime::format_duration(delay).to_string()),
+            timeout: timeout.map(|timeout| humantime::format_duration(timeout).to_string()),
+            resources,
+            // Only meaningful alongside a command
+            shell: command.as_ref().map(|_| shell),
+            command,
+            cwd,
         }
     }
 }
 
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
 #[cfg(test)]
 mod test {
     use serde_json::json;
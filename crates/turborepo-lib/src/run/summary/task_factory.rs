@@ -1,5 +1,6 @@
 use std::collections::HashSet;
 
+use itertools::Itertools;
 use turborepo_env::EnvironmentVariableMap;
 use turborepo_repository::package_graph::{PackageGraph, PackageInfo, PackageName};
 
@@ -109,16 +110,14 @@ impl<'a> TaskSummaryFactory<'a> {
         workspace_info: &PackageInfo,
         display_task: impl Fn(&TaskNode) -> Option<T> + Copy,
     ) -> Result<SharedTaskSummary<T>, Error> {
-        // TODO: command should be optional
-        let command = workspace_info
-            .package_json
-            .scripts
-            .get(task_id.task())
-            .cloned()
-            .unwrap_or_else(|| "<NONEXISTENT>".to_string());
-
         let task_definition = self.task_definition(task_id)?;
 
+        // TODO: command should be optional
+        let command = task_definition
+            .resolved_command(&workspace_info.package_json, task_id.task())
+            .unwrap_or("<NONEXISTENT>")
+            .to_string();
+
         let expanded_outputs = self
             .hash_tracker
             .expanded_outputs(task_id)
@@ -157,6 +156,13 @@ impl<'a> TaskSummaryFactory<'a> {
             hash_of_external_dependencies: get_external_deps_hash(
                 &workspace_info.transitive_dependencies,
             ),
+            external_dependencies: workspace_info
+                .transitive_dependencies
+                .iter()
+                .flatten()
+                .cloned()
+                .sorted()
+                .collect(),
             cache: cache_summary,
             command,
             cli_arguments: self.run_opts.pass_through_args.to_vec(),
@@ -195,6 +201,7 @@ impl<'a> TaskSummaryFactory<'a> {
                 task_definition,
                 env_vars,
                 self.env_at_start,
+                self.hash_tracker.dot_env(task_id).as_ref(),
             )
             .expect("invalid glob in task definition should have been caught earlier"),
             dot_env: task_definition.dot_env.clone(),
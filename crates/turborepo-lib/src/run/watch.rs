@@ -0,0 +1,670 @@
+//! `turbo watch`
+//!
+//! Runs the requested tasks once and then re-runs them whenever files change.
+//! Changed files are mapped to packages with the same `ChangeMapper` used by
+//! `--filter`, and each re-run only covers the changed packages and their
+//! dependents. Every run is a regular `Run` that reloads `turbo.json` and
+//! rebuilds the engine for its subgraph, so configuration edits are picked up
+//! without restarting `turbo watch`.
+//!
+//! Runs that are still in flight, which is always the case for persistent
+//! tasks, get stopped and merged into the next run when one of the packages
+//! they cover changes. Runs covering unrelated packages are left alone.
+//!
+//! File changes come from the daemon's file watcher, so the repository isn't
+//! watched twice. When the daemon is disabled or can't be reached we watch the
+//! repository ourselves, and we switch to doing so if the daemon goes away
+//! while we're watching.
+
+use std::{
+    collections::{HashMap, HashSet},
+    io::IsTerminal,
+    path::PathBuf,
+    time::Duration,
+};
+
+use futures::{
+    future::LocalBoxFuture,
+    stream::{FuturesUnordered, LocalBoxStream},
+    FutureExt, Stream, StreamExt,
+};
+use ignore::gitignore::Gitignore;
+use itertools::Itertools;
+use thiserror::Error;
+use tokio::{sync::broadcast::error::RecvError, time::Instant};
+use tracing::{debug, warn};
+use turbopath::{
+    AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPath, AnchoredSystemPathBuf,
+};
+use turborepo_filewatch::{FileSystemWatcher, WatchError};
+use turborepo_repository::{
+    change_mapper::{ChangeMapError, ChangeMapper, LockfileChange, PackageChanges},
+    discovery::Ecosystem,
+    package_graph::{PackageGraph, PackageName, PackageNode},
+    package_json::PackageJson,
+};
+use turborepo_scm::SCM;
+use turborepo_telemetry::events::{command::CommandEventBuilder, EventBuilder};
+use turborepo_ui::UI;
+
+use super::scope;
+use crate::{
+    cli::{Command, RunArgs},
+    commands::CommandBase,
+    daemon::{proto, DaemonClient, DaemonConnector, DaemonError},
+    opts::ScopeOpts,
+    run::{self, Run},
+    signal::SignalHandler,
+    turbo_json::TurboJson,
+    Args,
+};
+
+// How long we wait for the filesystem to settle before mapping the changes
+const DEBOUNCE: Duration = Duration::from_millis(150);
+// The longest we wait after the first pending change, so a steady stream of
+// writes can't hold off the next run forever
+const MAX_DEBOUNCE: Duration = Duration::from_secs(1);
+
+// Paths that never affect task inputs, and that turbo writes to itself
+const IGNORED_COMPONENTS: &[&str] = &[".git", "node_modules", ".turbo"];
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to start file watching: {0}")]
+    FileWatching(#[from] WatchError),
+    #[error("file watching stopped unexpectedly")]
+    FileWatchingClosed,
+    #[error("failed to watch files through the daemon: {0}")]
+    Daemon(#[from] DaemonError),
+    #[error("failed to map changed files to packages: {0}")]
+    ChangeMapper(#[from] ChangeMapError),
+    #[error(transparent)]
+    Run(#[from] run::Error),
+}
+
+/// The set of packages a run covers
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RunScope {
+    All,
+    Packages(HashSet<PackageName>),
+}
+
+impl RunScope {
+    fn overlaps(&self, other: &RunScope) -> bool {
+        match (self, other) {
+            (RunScope::All, _) | (_, RunScope::All) => true,
+            (RunScope::Packages(a), RunScope::Packages(b)) => !a.is_disjoint(b),
+        }
+    }
+
+    fn union(self, other: RunScope) -> RunScope {
+        match (self, other) {
+            (RunScope::Packages(mut a), RunScope::Packages(b)) => {
+                a.extend(b);
+                RunScope::Packages(a)
+            }
+            _ => RunScope::All,
+        }
+    }
+}
+
+struct InFlightRun {
+    scope: RunScope,
+    handler: SignalHandler,
+}
+
+type RunFuture = LocalBoxFuture<'static, (usize, Result<i32, run::Error>)>;
+
+enum FileChange {
+    Paths(Vec<PathBuf>),
+    // Events were missed, so any file might have changed
+    Rescan,
+}
+
+type FileChanges = LocalBoxStream<'static, Result<FileChange, Error>>;
+
+pub struct WatchClient {
+    args: Args,
+    run_args: RunArgs,
+    repo_root: AbsoluteSystemPathBuf,
+    version: &'static str,
+    ui: UI,
+    // Previous lockfile contents so lockfile edits only invalidate the packages
+    // whose external dependencies changed
+    lockfile_contents: Option<Vec<u8>>,
+    // Task outputs are usually gitignored, without these we would rerun tasks
+    // every time they write their outputs
+    gitignores: Vec<Gitignore>,
+    // Invalid values are reported by the run itself
+    package_discovery: Vec<Ecosystem>,
+}
+
+impl WatchClient {
+    pub fn new(base: CommandBase) -> Self {
+        let version = base.version();
+        let args = base.args().clone();
+        let run_args = match &args.command {
+            Some(Command::Watch(run_args)) => run_args.as_ref().clone(),
+            _ => unreachable!("watch client must be created for the watch command"),
+        };
+        let package_discovery = base
+            .config()
+            .ok()
+            .and_then(|config| config.package_discovery().ok())
+            .unwrap_or_default();
+        let CommandBase { repo_root, ui, .. } = base;
+
+        Self {
+            args,
+            run_args,
+            repo_root,
+            version,
+            ui,
+            lockfile_contents: None,
+            gitignores: Vec::new(),
+            package_discovery,
+        }
+    }
+
+    pub async fn start(
+        mut self,
+        signal_handler: &SignalHandler,
+        telemetry: CommandEventBuilder,
+    ) -> Result<i32, Error> {
+        let Some(signal_subscriber) = signal_handler.subscribe() else {
+            // We're already shutting down, there's nothing to watch
+            return Ok(0);
+        };
+        let exit = signal_subscriber.listen();
+        tokio::pin!(exit);
+
+        let mut changes = self.file_changes().await?;
+        if let Ok(pkg_graph) = self.package_graph().await {
+            self.lockfile_contents = pkg_graph
+                .package_manager()
+                .lockfile_path(&self.repo_root)
+                .read()
+                .ok();
+            self.load_gitignores(&pkg_graph);
+        }
+
+        let mut next_id = 0;
+        let mut in_flight: HashMap<usize, InFlightRun> = HashMap::new();
+        let mut runs: FuturesUnordered<RunFuture> = FuturesUnordered::new();
+
+        runs.push(self.start_run(
+            next_id,
+            RunScope::All,
+            Vec::new(),
+            &telemetry,
+            &mut in_flight,
+        ));
+        next_id += 1;
+
+        let mut changed_files = HashSet::new();
+        let mut rerun_all = false;
+        let mut deadline: Option<Instant> = None;
+        let mut pending_since: Option<Instant> = None;
+
+        loop {
+            tokio::select! {
+                _guard = &mut exit => {
+                    // The guard is held until every in-flight run has stopped its tasks
+                    for run in in_flight.values() {
+                        run.handler.close().await;
+                    }
+                    return Ok(0);
+                }
+                Some((id, result)) = runs.next(), if !runs.is_empty() => {
+                    in_flight.remove(&id);
+                    match result {
+                        Ok(0) => {}
+                        Ok(exit_code) => debug!("watch run {id} exited with {exit_code}"),
+                        // A failing run shouldn't stop us from watching, the
+                        // next change might fix it
+                        Err(err) => warn!("{err}"),
+                    }
+                }
+                change = changes.next() => {
+                    match change {
+                        Some(Ok(FileChange::Paths(paths))) => {
+                            let paths = self.changed_paths(paths);
+                            if paths.is_empty() {
+                                continue;
+                            }
+                            changed_files.extend(paths);
+                        }
+                        Some(Ok(FileChange::Rescan)) => {
+                            debug!("file watcher lagged, rerunning all packages");
+                            rerun_all = true;
+                        }
+                        Some(Err(err)) => return Err(err),
+                        None => return Err(Error::FileWatchingClosed),
+                    }
+                    let now = Instant::now();
+                    deadline = Some(debounce_deadline(now, *pending_since.get_or_insert(now)));
+                }
+                _ = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                    deadline = None;
+                    pending_since = None;
+                    let changed_files = std::mem::take(&mut changed_files);
+                    debug!("changed files: {:?}", changed_files);
+
+                    let scope = if std::mem::take(&mut rerun_all) {
+                        Ok(Some(RunScope::All))
+                    } else {
+                        self.changed_scope(changed_files).await
+                    };
+                    let scope = match scope {
+                        Ok(Some(scope)) => scope,
+                        Ok(None) => continue,
+                        Err(err) => {
+                            // Most likely a file is mid-edit (e.g. an invalid turbo.json),
+                            // so we wait for the next change instead of exiting
+                            warn!("{err}");
+                            continue;
+                        }
+                    };
+                    // Stop any runs that cover the changed packages and fold them into
+                    // the new run so their persistent tasks are restarted.
+                    let (scope, stopping) = Self::stop_overlapping(scope, &mut in_flight);
+                    runs.push(self.start_run(next_id, scope, stopping, &telemetry, &mut in_flight));
+                    next_id += 1;
+                }
+            }
+        }
+    }
+
+    /// Subscribes to the daemon's file watcher, falling back to watching the
+    /// repository ourselves if the daemon isn't available.
+    async fn file_changes(&self) -> Result<FileChanges, Error> {
+        if let Some((client, changes)) = self.connect_daemon().await? {
+            debug!("watching files through the daemon");
+            return Ok(daemon_changes(client, changes, self.repo_root.clone()).boxed_local());
+        }
+        let watcher = FileSystemWatcher::new_with_default_cookie_dir(&self.repo_root).await?;
+        Ok(local_changes(watcher).boxed_local())
+    }
+
+    async fn connect_daemon(
+        &self,
+    ) -> Result<
+        Option<(
+            DaemonClient<DaemonConnector>,
+            tonic::Streaming<proto::WatchFilesResponse>,
+        )>,
+        Error,
+    > {
+        let is_ci_or_not_tty = turborepo_ci::is_ci() || !std::io::stdout().is_terminal();
+        let forced = match (is_ci_or_not_tty, self.run_args.daemon()) {
+            (_, Some(false)) => {
+                debug!("skipping turbod since --no-daemon was passed");
+                return Ok(None);
+            }
+            (true, None) => {
+                debug!("skipping turbod since we appear to be in a non-interactive context");
+                return Ok(None);
+            }
+            (_, forced) => forced == Some(true),
+        };
+
+        let connector = DaemonConnector::new(true, true, &self.repo_root);
+        let connection: Result<_, DaemonError> = async {
+            let mut client = connector.connect().await?;
+            let changes = client.watch_files().await?;
+            Ok((client, changes))
+        }
+        .await;
+        match connection {
+            Ok(connection) => Ok(Some(connection)),
+            Err(err) if forced => Err(err.into()),
+            Err(err) => {
+                debug!("failed to watch files through the daemon: {err}");
+                Ok(None)
+            }
+        }
+    }
+
+    fn stop_overlapping(
+        mut scope: RunScope,
+        in_flight: &mut HashMap<usize, InFlightRun>,
+    ) -> (RunScope, Vec<SignalHandler>) {
+        let overlapping = in_flight
+            .iter()
+            .filter(|(_, run)| run.scope.overlaps(&scope))
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        let mut stopping = Vec::with_capacity(overlapping.len());
+        for id in overlapping {
+            let run = in_flight.remove(&id).expect("id came from in flight runs");
+            scope = scope.union(run.scope);
+            let handler = run.handler.clone();
+            tokio::spawn(async move { handler.close().await });
+            stopping.push(run.handler);
+        }
+        (scope, stopping)
+    }
+
+    fn start_run(
+        &self,
+        id: usize,
+        scope: RunScope,
+        stopping: Vec<SignalHandler>,
+        telemetry: &CommandEventBuilder,
+        in_flight: &mut HashMap<usize, InFlightRun>,
+    ) -> RunFuture {
+        // Each run gets its own handler so it can be stopped without
+        // affecting the other runs
+        let handler = SignalHandler::new(futures::future::pending());
+        in_flight.insert(
+            id,
+            InFlightRun {
+                scope: scope.clone(),
+                handler: handler.clone(),
+            },
+        );
+
+        let base = CommandBase::new(
+            self.args_for_scope(&scope),
+            self.repo_root.clone(),
+            self.version,
+            self.ui,
+        );
+        let telemetry = telemetry.child();
+        async move {
+            // Wait for the runs we replaced to clean up so that persistent
+            // tasks aren't started twice
+            for handler in stopping {
+                handler.done().await;
+            }
+            let result: Result<i32, run::Error> = async {
+                let api_auth = base.api_auth()?;
+                let api_client = base.api_client()?;
+                let run = Run::new(base, api_auth)?;
+                let result = run.run(&handler, telemetry, api_client).await;
+                handler.close().await;
+                result
+            }
+            .await;
+            (id, result)
+        }
+        .boxed_local()
+    }
+
+    fn args_for_scope(&self, scope: &RunScope) -> Args {
+        let mut run_args = self.run_args.clone();
+        if let RunScope::Packages(packages) = scope {
+            run_args.filter = packages
+                .iter()
+                .map(|name| name.to_string())
+                .sorted()
+                .collect();
+            // These are only valid alongside the user's own filters, which are
+            // already accounted for in `scope`
+            run_args.scope = Vec::new();
+            run_args.since = None;
+            run_args.ignore = Vec::new();
+            run_args.include_dependencies = false;
+            run_args.no_deps = false;
+        }
+
+        let mut args = self.args.clone();
+        args.command = Some(Command::Run(Box::new(run_args)));
+        args
+    }
+
+    /// Maps the changed files to the scope of the run needed to bring tasks up
+    /// to date. Returns `None` if none of the watched packages are affected.
+    async fn changed_scope(
+        &mut self,
+        changed_files: HashSet<AnchoredSystemPathBuf>,
+    ) -> Result<Option<RunScope>, Error> {
+        if self.run_args.single_package {
+            return Ok(Some(RunScope::All));
+        }
+
+        let pkg_graph = self.package_graph().await?;
+        // .gitignore files might have changed as well
+        self.load_gitignores(&pkg_graph);
+        let root_turbo_json = TurboJson::load(
+            &self.repo_root,
+            AnchoredSystemPath::empty(),
+            pkg_graph.root_package_json(),
+            false,
+        )
+        .map_err(run::Error::from)?;
+
+        let lockfile_path = pkg_graph.package_manager().lockfile_path(&self.repo_root);
+        let lockfile_change =
+            ChangeMapper::lockfile_changed(&self.repo_root, &changed_files, &lockfile_path).then(
+                || {
+                    let previous = self.lockfile_contents.take();
+                    self.lockfile_contents = lockfile_path.read().ok();
+                    previous.map_or(LockfileChange::Empty, LockfileChange::WithContent)
+                },
+            );
+
+        let mut global_deps = root_turbo_json.global_deps.clone();
+        global_deps.extend(self.run_args.global_deps.iter().cloned());
+        let change_mapper = ChangeMapper::new(&pkg_graph, global_deps, Vec::new());
+
+        let changed_packages =
+            match change_mapper.changed_packages(changed_files, lockfile_change)? {
+                PackageChanges::All => return Ok(Some(RunScope::All)),
+                PackageChanges::Some(packages) => packages,
+            };
+
+        // Dependents of a changed package need to be rerun as well
+        let mut affected = HashSet::new();
+        for package in changed_packages {
+            let node = PackageNode::Workspace(package.name.clone());
+            affected.extend(
+                pkg_graph
+                    .ancestors(&node)
+                    .into_iter()
+                    .filter_map(|node| match node {
+                        PackageNode::Workspace(name) => Some(name.clone()),
+                        PackageNode::Root => None,
+                    }),
+            );
+            affected.insert(package.name);
+        }
+
+        // Respect any filters the user passed to `turbo watch`
+        if let Some(watched) = self.watched_packages(&pkg_graph)? {
+            affected.retain(|package| watched.contains(package));
+        }
+
+        Ok((!affected.is_empty()).then_some(RunScope::Packages(affected)))
+    }
+
+    fn watched_packages(
+        &self,
+        pkg_graph: &PackageGraph,
+    ) -> Result<Option<HashSet<PackageName>>, Error> {
+        let scope_opts = ScopeOpts::try_from(&self.run_args).map_err(run::Error::from)?;
+        if scope_opts.get_filters().is_empty() {
+            return Ok(None);
+        }
+        let scm = SCM::new(&self.repo_root);
+        let (packages, _) = scope::resolve_packages(&scope_opts, &self.repo_root, pkg_graph, &scm)
+            .map_err(run::Error::from)?;
+        Ok(Some(packages))
+    }
+
+    async fn package_graph(&self) -> Result<PackageGraph, run::Error> {
+        let root_package_json = PackageJson::load(&self.repo_root.join_component("package.json"))?;
+        Ok(PackageGraph::builder(&self.repo_root, root_package_json)
+            .with_manifest_discovery(
+                self.package_discovery
+                    .iter()
+                    .map(|ecosystem| ecosystem.discovery()),
+            )
+            .build()
+            .await?)
+    }
+
+    fn changed_paths(&self, paths: Vec<PathBuf>) -> Vec<AnchoredSystemPathBuf> {
+        paths
+            .into_iter()
+            .filter_map(|path| AbsoluteSystemPathBuf::try_from(path).ok())
+            // Directories get events whenever their entries change, the files
+            // themselves are what we care about
+            .filter(|path| !path.as_std_path().is_dir())
+            .filter_map(|path| {
+                let anchored = self.repo_root.anchor(&path).ok()?;
+                let is_ignored_component = anchored
+                    .components()
+                    .any(|component| IGNORED_COMPONENTS.contains(&component.as_str()));
+                (!is_ignored_component && !self.is_gitignored(&path)).then_some(anchored)
+            })
+            .collect()
+    }
+
+    fn is_gitignored(&self, path: &AbsoluteSystemPath) -> bool {
+        self.gitignores.iter().any(|gitignore| {
+            path.as_std_path().starts_with(gitignore.path())
+                && gitignore
+                    .matched_path_or_any_parents(path.as_std_path(), false)
+                    .is_ignore()
+        })
+    }
+
+    fn load_gitignores(&mut self, pkg_graph: &PackageGraph) {
+        self.gitignores = pkg_graph
+            .packages()
+            .map(|(_, info)| {
+                self.repo_root
+                    .resolve(info.package_path())
+                    .join_component(".gitignore")
+            })
+            .filter(|path| path.exists())
+            .map(|path| {
+                let (gitignore, err) = Gitignore::new(path.as_std_path());
+                if let Some(err) = err {
+                    debug!("failed to parse {path}: {err}");
+                }
+                gitignore
+            })
+            .collect();
+    }
+}
+
+fn daemon_changes(
+    client: DaemonClient<DaemonConnector>,
+    mut changes: tonic::Streaming<proto::WatchFilesResponse>,
+    repo_root: AbsoluteSystemPathBuf,
+) -> impl Stream<Item = Result<FileChange, Error>> {
+    async_stream::try_stream! {
+        // The connection is closed once the client is dropped
+        let _client = client;
+        loop {
+            let response = match changes.message().await {
+                Ok(Some(response)) => response,
+                Ok(None) => break,
+                Err(err) => {
+                    debug!("daemon file watching failed: {err}");
+                    break;
+                }
+            };
+            if response.rescan {
+                yield FileChange::Rescan;
+                continue;
+            }
+            let paths = response
+                .paths
+                .into_iter()
+                .filter_map(|path| AnchoredSystemPathBuf::from_raw(path).ok())
+                .map(|path| repo_root.resolve(&path).as_std_path().to_owned())
+                .collect();
+            yield FileChange::Paths(paths);
+        }
+
+        // The daemon went away, e.g. it was stopped or timed out
+        warn!("lost connection to the daemon, watching files directly");
+        let watcher = FileSystemWatcher::new_with_default_cookie_dir(&repo_root).await?;
+        // Anything could have changed before we started watching
+        yield FileChange::Rescan;
+        for await change in local_changes(watcher) {
+            yield change?;
+        }
+    }
+}
+
+fn local_changes(watcher: FileSystemWatcher) -> impl Stream<Item = Result<FileChange, Error>> {
+    // Subscribe right away so nothing is missed before the stream is polled
+    let mut events = watcher.subscribe();
+    async_stream::try_stream! {
+        // The watcher stops when it's dropped
+        let _watcher = watcher;
+        loop {
+            match events.recv().await {
+                Ok(Ok(event)) if event.kind.is_access() => {}
+                Ok(Ok(event)) => {
+                    yield FileChange::Paths(event.paths);
+                }
+                Ok(Err(err)) => warn!("file watching error: {err}"),
+                Err(RecvError::Lagged(_)) => {
+                    yield FileChange::Rescan;
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }
+}
+
+// When to act on pending changes if nothing else changes by then
+fn debounce_deadline(now: Instant, pending_since: Instant) -> Instant {
+    (now + DEBOUNCE).min(pending_since + MAX_DEBOUNCE)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use test_case::test_case;
+    use tokio::time::Instant;
+    use turborepo_repository::package_graph::PackageName;
+
+    use super::{debounce_deadline, RunScope, DEBOUNCE, MAX_DEBOUNCE};
+
+    fn packages(names: &[&str]) -> RunScope {
+        RunScope::Packages(
+            names
+                .iter()
+                .map(|name| PackageName::from(name.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test_case(RunScope::All, packages(&["a"]), true ; "all overlaps everything")]
+    #[test_case(packages(&["a", "b"]), packages(&["b"]), true ; "shared package")]
+    #[test_case(packages(&["a"]), packages(&["b"]), false ; "disjoint packages")]
+    #[test_case(packages(&[]), packages(&["b"]), false ; "empty scope")]
+    fn test_run_scope_overlaps(a: RunScope, b: RunScope, expected: bool) {
+        assert_eq!(a.overlaps(&b), expected);
+        assert_eq!(b.overlaps(&a), expected);
+    }
+
+    #[test]
+    fn test_run_scope_union() {
+        assert_eq!(
+            packages(&["a"]).union(packages(&["b"])),
+            packages(&["a", "b"])
+        );
+        assert_eq!(packages(&["a"]).union(RunScope::All), RunScope::All);
+        assert_eq!(
+            RunScope::Packages(HashSet::new()).union(packages(&["a"])),
+            packages(&["a"])
+        );
+    }
+
+    #[test]
+    fn test_debounce_deadline() {
+        let start = Instant::now();
+        assert_eq!(debounce_deadline(start, start), start + DEBOUNCE);
+        // Changes keep pushing the deadline back until the maximum wait
+        let later = start + MAX_DEBOUNCE - DEBOUNCE / 2;
+        assert_eq!(debounce_deadline(later, start), start + MAX_DEBOUNCE);
+    }
+}
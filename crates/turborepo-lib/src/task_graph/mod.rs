@@ -1,11 +1,13 @@
 mod visitor;
 
-use std::str::FromStr;
+use std::{collections::BTreeMap, path::Path, str::FromStr, time::Duration};
 
 use globwalk::{GlobError, ValidatedGlob};
 use serde::{Deserialize, Serialize};
 use turbopath::{AnchoredSystemPath, AnchoredSystemPathBuf, RelativeUnixPathBuf};
+use turborepo_repository::package_json::PackageJson;
 pub use visitor::{Error as VisitorError, Visitor};
+use wax::Program;
 
 use crate::{
     cli::OutputLogsMode,
@@ -50,6 +52,10 @@ pub struct TaskDefinition {
 
     pub(crate) dot_env: Option<Vec<RelativeUnixPathBuf>>,
 
+    // InjectDotEnv loads the DotEnv files into the Task's environment, on top
+    // of including them in its hash
+    pub(crate) inject_dot_env: bool,
+
     // TopologicalDependencies are tasks from package dependencies.
     // E.g. "build" is a topological dependency in:
     // dependsOn: ['^build'].
@@ -72,6 +78,31 @@ pub struct TaskDefinition {
     // Persistent indicates whether the Task is expected to exit or not
     // Tasks marked Persistent do not exit (e.g. --watch mode or dev servers)
     pub persistent: bool,
+
+    // Retries is the number of times a failed Task is re-run before the
+    // failure is reported
+    pub(crate) retries: u32,
+
+    // RetryDelay is how long to wait between a failed attempt and the next one
+    pub(crate) retry_delay: Option<Duration>,
+
+    // Timeout is how long a single attempt may run before it is killed
+    pub(crate) timeout: Option<Duration>,
+
+    // Resources is how much of each named resource pool the Task occupies
+    // while it runs
+    pub(crate) resources: BTreeMap<String, u32>,
+
+    // Command is run for the Task instead of the package.json script of the
+    // same name
+    pub(crate) command: Option<String>,
+
+    // Cwd is the directory, relative to the package, that Command is run in
+    pub(crate) cwd: Option<RelativeUnixPathBuf>,
+
+    // Shell runs Command through the platform's shell rather than splitting
+    // it into a program and its arguments
+    pub(crate) shell: bool,
 }
 
 impl Default for TaskDefinition {
@@ -87,6 +118,14 @@ impl Default for TaskDefinition {
             output_mode: Default::default(),
             persistent: Default::default(),
             dot_env: Default::default(),
+            inject_dot_env: Default::default(),
+            retries: Default::default(),
+            retry_delay: Default::default(),
+            timeout: Default::default(),
+            resources: Default::default(),
+            command: Default::default(),
+            cwd: Default::default(),
+            shell: true,
         }
     }
 }
@@ -104,6 +143,20 @@ impl FromIterator<RawTaskDefinition> for RawTaskDefinition {
 const LOG_DIR: &str = ".turbo";
 
 impl TaskDefinition {
+    /// The command the task runs: its own `command` if it has one, otherwise
+    /// the package's script of the same name. Empty scripts don't run
+    /// anything.
+    pub fn resolved_command<'a>(
+        &'a self,
+        package_json: &'a PackageJson,
+        task_name: &str,
+    ) -> Option<&'a str> {
+        self.command
+            .as_deref()
+            .or_else(|| package_json.scripts.get(task_name).map(String::as_str))
+            .filter(|command| !command.is_empty())
+    }
+
     pub fn workspace_relative_log_file(task_name: &str) -> AnchoredSystemPathBuf {
         let log_dir = AnchoredSystemPath::new(LOG_DIR)
             .expect("LOG_DIR should be a valid AnchoredSystemPathBuf");
@@ -163,6 +216,70 @@ impl TaskDefinition {
     }
 }
 
+/// The special input that stands for every file that would be hashed without
+/// any `inputs`
+pub const TURBO_DEFAULT: &str = "$TURBO_DEFAULT$";
+
+/// Matches package relative paths against a task's `inputs`. This mirrors how
+/// package files are selected for hashing: without `inputs` every file in the
+/// package counts, otherwise only the matching files along with the package's
+/// `package.json`, `turbo.json` and `dotEnv` files.
+pub struct InputsMatcher<'a> {
+    include_all: bool,
+    includes: wax::Any<'a>,
+    excludes: wax::Any<'a>,
+}
+
+impl<'a> InputsMatcher<'a> {
+    pub fn new(
+        inputs: &'a [String],
+        dot_env: &'a [RelativeUnixPathBuf],
+    ) -> Result<Self, wax::BuildError> {
+        let (excludes, includes): (Vec<_>, Vec<_>) = inputs
+            .iter()
+            .map(|input| input.as_str())
+            .partition(|input| input.starts_with('!'));
+        let include_all = inputs.is_empty() || includes.contains(&TURBO_DEFAULT);
+
+        let includes = wax::any(
+            includes
+                .into_iter()
+                .filter(|input| *input != TURBO_DEFAULT)
+                .chain(dot_env.iter().map(|path| path.as_str()))
+                .chain(["package.json", "turbo.json"])
+                .map(|input| input.trim_start_matches('/')),
+        )?;
+        let excludes = wax::any(
+            excludes
+                .into_iter()
+                .map(|input| input[1..].trim_start_matches('/')),
+        )?;
+
+        Ok(Self {
+            include_all,
+            includes,
+            excludes,
+        })
+    }
+
+    /// Whether the task has no `inputs` restricting it, in which case every
+    /// file in the package that isn't excluded is an input
+    pub fn includes_all(&self) -> bool {
+        self.include_all
+    }
+
+    pub fn is_match(&self, path: impl AsRef<Path>) -> bool {
+        let path = path.as_ref();
+        (self.include_all || self.includes.is_match(path)) && !self.excludes.is_match(path)
+    }
+}
+
+impl TaskDefinition {
+    pub fn inputs_matcher(&self) -> Result<InputsMatcher, wax::BuildError> {
+        InputsMatcher::new(&self.inputs, self.dot_env.as_deref().unwrap_or_default())
+    }
+}
+
 fn task_log_filename(task_name: &str) -> String {
     format!("turbo-{}.log", task_name.replace(':', "$colon$"))
 }
@@ -2,10 +2,13 @@ use std::{
     borrow::Cow,
     collections::HashSet,
     io::Write,
+    path::PathBuf,
+    pin::pin,
     sync::{Arc, Mutex, OnceLock},
     time::{Duration, Instant},
 };
 
+use chrono::{DateTime, Local};
 use console::{Style, StyledObject};
 use futures::{stream::FuturesUnordered, StreamExt};
 use regex::Regex;
@@ -21,24 +24,29 @@ use turborepo_repository::{
 use turborepo_telemetry::events::{
     generic::GenericEventBuilder, task::PackageTaskEventBuilder, EventBuilder, TrackedErrors,
 };
-use turborepo_ui::{ColorSelector, OutputClient, OutputSink, OutputWriter, PrefixedUI, UI};
+use turborepo_ui::{
+    tui::{AppSender, CacheResult, TuiTask},
+    ColorSelector, OutputClient, OutputSink, OutputWriter, PrefixedUI, UI,
+};
 use which::which;
 
 use crate::{
-    cli::EnvMode,
+    cli::{EnvMode, SandboxMode},
     engine::{Engine, ExecutionOptions, StopExecution},
     opts::RunOpts,
     process::{ChildExit, Command, ProcessManager},
     run::{
         global_hash::GlobalHashableInputs,
+        sandbox::{Sandbox, TaskSandbox},
         summary::{
-            self, GlobalHashSummary, RunTracker, SpacesTaskClient, SpacesTaskInformation,
-            TaskExecutionSummary, TaskTracker,
+            self, AttemptOutcome, GlobalHashSummary, RunTracker, SpacesTaskClient,
+            SpacesTaskInformation, TaskExecutionSummary, TaskTracker,
         },
         task_access::TaskAccess,
         task_id::TaskId,
         RunCache, TaskCache,
     },
+    task_graph::TaskDefinition,
     task_hash::{self, PackageInputsHashes, TaskHashTracker, TaskHashTrackerState, TaskHasher},
 };
 
@@ -47,6 +55,7 @@ pub struct Visitor<'a> {
     color_cache: ColorSelector,
     dry: bool,
     global_env: EnvironmentVariableMap,
+    global_env_patterns: Vec<String>,
     global_env_mode: EnvMode,
     manager: ProcessManager,
     run_opts: &'a RunOpts,
@@ -58,6 +67,8 @@ pub struct Visitor<'a> {
     sink: OutputSink<StdWriter>,
     task_hasher: TaskHasher<'a>,
     ui: UI,
+    tui_sender: Option<AppSender>,
+    sandbox: Option<Arc<Sandbox>>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -79,6 +90,8 @@ pub enum Error {
     TaskHash(#[from] task_hash::Error),
     #[error(transparent)]
     RunSummary(#[from] summary::Error),
+    #[error(transparent)]
+    Path(#[from] turbopath::PathError),
 }
 
 impl<'a> Visitor<'a> {
@@ -101,6 +114,7 @@ impl<'a> Visitor<'a> {
         manager: ProcessManager,
         repo_root: &'a AbsoluteSystemPath,
         global_env: EnvironmentVariableMap,
+        global_env_patterns: Vec<String>,
     ) -> Self {
         let task_hasher = TaskHasher::new(
             package_inputs_hashes,
@@ -126,6 +140,9 @@ impl<'a> Visitor<'a> {
             task_hasher,
             ui,
             global_env,
+            global_env_patterns,
+            tui_sender: None,
+            sandbox: None,
         }
     }
 
@@ -163,14 +180,14 @@ impl<'a> Visitor<'a> {
 
             let package_task_event =
                 PackageTaskEventBuilder::new(info.package(), info.task()).with_parent(telemetry);
-            let command = workspace_info
-                .package_json
-                .scripts
-                .get(info.task())
-                .cloned();
+            let task_definition = engine
+                .task_definition(&info)
+                .ok_or(Error::MissingDefinition)?;
+            let command =
+                task_definition.resolved_command(&workspace_info.package_json, info.task());
 
             match command {
-                Some(cmd) if info.package() == ROOT_PKG_NAME && turbo_regex().is_match(&cmd) => {
+                Some(cmd) if info.package() == ROOT_PKG_NAME && turbo_regex().is_match(cmd) => {
                     package_task_event.track_error(TrackedErrors::RecursiveError);
                     return Err(Error::RecursiveTurbo {
                         task_name: info.to_string(),
@@ -180,10 +197,6 @@ impl<'a> Visitor<'a> {
                 _ => (),
             }
 
-            let task_definition = engine
-                .task_definition(&info)
-                .ok_or(Error::MissingDefinition)?;
-
             let task_env_mode = match self.global_env_mode {
                 // Task env mode is only independent when global env mode is `infer`.
                 EnvMode::Infer if task_definition.pass_through_env.is_some() => {
@@ -214,9 +227,18 @@ impl<'a> Visitor<'a> {
             // We do this calculation earlier than we do in Go due to the `task_hasher`
             // being !Send. In the future we can look at doing this right before
             // task execution instead.
-            let execution_env =
+            let mut execution_env =
                 self.task_hasher
                     .env(&info, task_env_mode, task_definition, &self.global_env)?;
+            if let Some(dot_env) = self.task_hasher.dot_env(
+                &info,
+                task_env_mode,
+                task_definition,
+                &self.repo_root.resolve(workspace_info.package_path()),
+                &self.global_env_patterns,
+            )? {
+                execution_env.union(&dot_env.to_env_map());
+            }
 
             let task_cache = self.run_cache.task_cache(
                 task_definition,
@@ -243,21 +265,24 @@ impl<'a> Visitor<'a> {
                     // the following block should never get hit. In the meantime, keep it after
                     // hashing so that downstream tasks can count on the hash existing
                     //
-                    // bail if the script doesn't exist or is empty
-                    if command.map_or(true, |s| s.is_empty()) {
+                    // bail if the task has no command and the script doesn't exist or is empty
+                    if command.is_none() {
                         continue;
                     }
 
                     let workspace_directory = self.repo_root.resolve(workspace_info.package_path());
+                    let workspace_directory = match &task_definition.cwd {
+                        Some(cwd) => workspace_directory.join_unix_path(cwd)?,
+                        None => workspace_directory,
+                    };
 
-                    let persistent = task_definition.persistent;
                     let mut exec_context = factory.exec_context(
                         info.clone(),
                         task_hash,
                         task_cache,
                         workspace_directory,
                         execution_env,
-                        persistent,
+                        task_definition,
                         self.task_access.clone(),
                     );
 
@@ -370,6 +395,14 @@ impl<'a> Visitor<'a> {
         task_id: &TaskId,
         vendor_behavior: Option<&VendorBehavior>,
     ) -> OutputClient<impl std::io::Write> {
+        // Each task gets its own pane in the terminal UI so there is no need to
+        // group or decorate its output
+        if let Some(sender) = &self.tui_sender {
+            let task = sender.task(self.display_task_id(task_id));
+            let sink = OutputSink::new(StdWriter::from(task.clone()), StdWriter::from(task));
+            return sink.logger(turborepo_ui::OutputClientBehavior::Passthrough);
+        }
+
         let behavior = match self.run_opts.log_order {
             crate::opts::ResolvedLogOrder::Stream if self.run_tracker.spaces_enabled() => {
                 turborepo_ui::OutputClientBehavior::InMemoryBuffer
@@ -403,6 +436,10 @@ impl<'a> Visitor<'a> {
     }
 
     fn prefix<'b>(&self, task_id: &'b TaskId) -> Cow<'b, str> {
+        // The terminal UI already shows which task the output belongs to
+        if self.tui_sender.is_some() {
+            return "".into();
+        }
         match self.run_opts.log_prefix {
             crate::opts::ResolvedLogPrefix::Task if self.run_opts.single_package => {
                 task_id.task().into()
@@ -452,6 +489,16 @@ impl<'a> Visitor<'a> {
     pub fn dry_run(&mut self) {
         self.dry = true;
     }
+
+    /// Send task status and output to the terminal UI instead of stdout
+    pub fn tui_sender(&mut self, sender: AppSender) {
+        self.tui_sender = Some(sender);
+    }
+
+    /// Trace the files tasks access and check them against their definitions
+    pub fn sandbox(&mut self, sandbox: Sandbox) {
+        self.sandbox = Some(Arc::new(sandbox));
+    }
 }
 
 // A tiny enum that allows us to use the same type for stdout and stderr without
@@ -460,6 +507,7 @@ enum StdWriter {
     Out(std::io::Stdout),
     Err(std::io::Stderr),
     Null(std::io::Sink),
+    Tui(TuiTask),
 }
 
 impl StdWriter {
@@ -468,6 +516,7 @@ impl StdWriter {
             StdWriter::Out(out) => out,
             StdWriter::Err(err) => err,
             StdWriter::Null(null) => null,
+            StdWriter::Tui(task) => task,
         }
     }
 }
@@ -490,6 +539,12 @@ impl From<std::io::Sink> for StdWriter {
     }
 }
 
+impl From<TuiTask> for StdWriter {
+    fn from(value: TuiTask) -> Self {
+        Self::Tui(value)
+    }
+}
+
 impl std::io::Write for StdWriter {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
         self.writer().write(buf)
@@ -505,6 +560,17 @@ fn turbo_regex() -> &'static Regex {
     RE.get_or_init(|| Regex::new(r"(?:^|\s)turbo(?:$|\s)").unwrap())
 }
 
+// Looks the program up in PATH, resolving relative paths like `./run.sh`
+// against the directory the command runs in
+fn find_program(program: &str, cwd: &AbsoluteSystemPath) -> Result<PathBuf, std::io::Error> {
+    which::which_in(program, std::env::var_os("PATH"), cwd).map_err(|e| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("unable to find {program}: {e}"),
+        )
+    })
+}
+
 // Error that comes from the execution of the task
 #[derive(Debug, thiserror::Error, Clone)]
 #[error("{task_id}: {cause}")]
@@ -520,6 +586,10 @@ enum TaskErrorCause {
     Spawn { msg: String },
     #[error("command {command} exited ({exit_code})")]
     Exit { command: String, exit_code: i32 },
+    #[error("command {command} timed out after {timeout}")]
+    Timeout { command: String, timeout: String },
+    #[error("accessed {accesses} files or environment variables it doesn't declare")]
+    Sandbox { accesses: usize },
 }
 
 impl TaskError {
@@ -557,6 +627,13 @@ impl TaskErrorCause {
     fn from_execution(command: String, exit_code: i32) -> Self {
         TaskErrorCause::Exit { command, exit_code }
     }
+
+    fn from_timeout(command: String, timeout: Duration) -> Self {
+        TaskErrorCause::Timeout {
+            command,
+            timeout: humantime::format_duration(timeout).to_string(),
+        }
+    }
 }
 
 struct ExecContextFactory<'a> {
@@ -589,11 +666,24 @@ impl<'a> ExecContextFactory<'a> {
         task_cache: TaskCache,
         workspace_directory: AbsoluteSystemPathBuf,
         execution_env: EnvironmentVariableMap,
-        persistent: bool,
+        task_definition: &TaskDefinition,
         task_access: TaskAccess,
     ) -> ExecContext {
         let task_id_for_display = self.visitor.display_task_id(&task_id);
+        let ui_task = self
+            .visitor
+            .tui_sender
+            .as_ref()
+            .map(|sender| sender.task(task_id_for_display.clone()));
         let pass_through_args = self.visitor.run_opts.args_for_task(&task_id);
+        // Persistent tasks never finish and uncached tasks can't poison the cache,
+        // so there is nothing to check for either
+        let sandbox = self
+            .visitor
+            .sandbox
+            .as_ref()
+            .filter(|_| task_definition.cache && !task_definition.persistent)
+            .map(|sandbox| sandbox.task(&task_id, &task_hash, self.engine));
         ExecContext {
             engine: self.engine.clone(),
             ui: self.visitor.ui,
@@ -614,8 +704,15 @@ impl<'a> ExecContextFactory<'a> {
             continue_on_error: self.visitor.run_opts.continue_on_error,
             pass_through_args,
             errors: self.errors.clone(),
-            persistent,
+            persistent: task_definition.persistent,
+            command: task_definition.command.clone(),
+            shell: task_definition.shell,
+            retries: task_definition.retries,
+            retry_delay: task_definition.retry_delay,
+            timeout: task_definition.timeout,
             task_access,
+            sandbox,
+            ui_task,
         }
     }
 
@@ -650,7 +747,15 @@ struct ExecContext {
     pass_through_args: Option<Vec<String>>,
     errors: Arc<Mutex<Vec<TaskError>>>,
     persistent: bool,
+    // The task's turbo.json command, run instead of a package.json script
+    command: Option<String>,
+    shell: bool,
+    retries: u32,
+    retry_delay: Option<Duration>,
+    timeout: Option<Duration>,
     task_access: TaskAccess,
+    sandbox: Option<TaskSandbox>,
+    ui_task: Option<TuiTask>,
 }
 
 enum ExecOutcome {
@@ -688,11 +793,14 @@ impl ExecContext {
         spaces_client: Option<SpacesTaskClient>,
         telemetry: &PackageTaskEventBuilder,
     ) {
-        let tracker = tracker.start().await;
+        let mut tracker = tracker.start().await;
+        if let Some(ui_task) = &self.ui_task {
+            ui_task.start();
+        }
         let span = tracing::debug_span!("execute_task", task = %self.task_id.task());
         span.follows_from(parent_span_id);
         let mut result = self
-            .execute_inner(&output_client, telemetry)
+            .execute_inner(&output_client, &mut tracker, telemetry)
             .instrument(span)
             .await;
 
@@ -709,6 +817,13 @@ impl ExecContext {
             }
         };
 
+        if let Some(ui_task) = &self.ui_task {
+            match result {
+                ExecOutcome::Success(_) => ui_task.succeeded(),
+                ExecOutcome::Internal | ExecOutcome::Task { .. } => ui_task.failed(),
+            }
+        }
+
         match result {
             ExecOutcome::Success(outcome) => {
                 let task_summary = match outcome {
@@ -764,6 +879,7 @@ impl ExecContext {
     async fn execute_inner(
         &mut self,
         output_client: &OutputClient<impl std::io::Write>,
+        tracker: &mut TaskTracker<DateTime<Local>>,
         telemetry: &PackageTaskEventBuilder,
     ) -> ExecOutcome {
         let task_start = Instant::now();
@@ -787,6 +903,9 @@ impl ExecContext {
                 );
                 self.hash_tracker
                     .insert_cache_status(self.task_id.clone(), status);
+                if let Some(ui_task) = &self.ui_task {
+                    ui_task.cache(CacheResult::Hit);
+                }
                 return ExecOutcome::Success(SuccessOutcome::CacheHit);
             }
             Ok(None) => (),
@@ -796,21 +915,34 @@ impl ExecContext {
             }
         }
 
-        let Ok(package_manager_binary) = which(self.package_manager.command()) else {
-            return ExecOutcome::Internal;
-        };
-
-        let mut cmd = Command::new(package_manager_binary);
-        let mut args = vec!["run".to_string(), self.task_id.task().to_string()];
-        if let Some(pass_through_args) = &self.pass_through_args {
-            args.extend(
-                self.package_manager
-                    .arg_separator(pass_through_args.as_slice())
-                    .map(|s| s.to_string()),
-            );
-            args.extend(pass_through_args.iter().cloned());
+        if let Some(ui_task) = &self.ui_task {
+            ui_task.cache(CacheResult::Miss);
         }
-        cmd.args(args);
+
+        let mut cmd = match &self.command {
+            Some(command) => match self.command_process(command) {
+                Ok(cmd) => cmd,
+                Err(e) => return self.spawn_failed(&mut prefixed_ui, e),
+            },
+            None => {
+                let Ok(package_manager_binary) = which(self.package_manager.command()) else {
+                    return ExecOutcome::Internal;
+                };
+
+                let mut cmd = Command::new(package_manager_binary);
+                let mut args = vec!["run".to_string(), self.task_id.task().to_string()];
+                if let Some(pass_through_args) = &self.pass_through_args {
+                    args.extend(
+                        self.package_manager
+                            .arg_separator(pass_through_args.as_slice())
+                            .map(|s| s.to_string()),
+                    );
+                    args.extend(pass_through_args.iter().cloned());
+                }
+                cmd.args(args);
+                cmd
+            }
+        };
         cmd.current_dir(self.workspace_directory.clone());
 
         // We clear the env before populating it with variables we expect
@@ -821,12 +953,20 @@ impl ExecContext {
         // enable task access tracing
 
         // set the trace file env var - frameworks that support this can use it to
-        // write out a trace file that we will use to automatically cache the task
-        if self.task_access.is_enabled() {
+        // write out a trace file that we will use to automatically cache the task.
+        // The sandbox uses it to find out which environment variables were read.
+        if self.task_access.is_enabled() || self.sandbox.is_some() {
             let (task_access_trace_key, trace_file) = self.task_access.get_env_var(&self.task_hash);
             cmd.env(task_access_trace_key, trace_file.to_string());
         }
 
+        if let Some(sandbox) = &self.sandbox {
+            if let Err(e) = sandbox.wrap(&mut cmd) {
+                error!("unable to sandbox \"{}\": {e}", self.task_id);
+                return ExecOutcome::Internal;
+            }
+        }
+
         // Many persistent tasks if started hooked up to a pseudoterminal
         // will shut down if stdin is closed, so we open it even if we don't pass
         // anything to it.
@@ -846,100 +986,281 @@ impl ExecContext {
             }
         };
 
-        let mut process = match self.manager.spawn(cmd, Duration::from_millis(500)) {
-            Some(Ok(child)) => child,
-            // Turbo was unable to spawn a process
-            Some(Err(e)) => {
-                // Note: we actually failed to spawn, but this matches the Go output
-                prefixed_ui.error(format!("command finished with error: {e}"));
-                let error_string = e.to_string();
-                self.errors
-                    .lock()
-                    .expect("lock poisoned")
-                    .push(TaskError::from_spawn(self.task_id_for_display.clone(), e));
-                return ExecOutcome::Task {
-                    exit_code: None,
-                    message: error_string,
-                };
+        // Attempts are only worth reporting when the task can have more than one, or
+        // when an attempt can end in something other than the command exiting
+        let track_attempts = self.retries > 0 || self.timeout.is_some();
+        let mut attempt = 0;
+        let (process, exit_status) = loop {
+            let attempt_start = Local::now();
+            let mut process = match self.manager.spawn(cmd.clone(), Duration::from_millis(500)) {
+                Some(Ok(child)) => child,
+                // Turbo was unable to spawn a process
+                Some(Err(e)) => return self.spawn_failed(&mut prefixed_ui, e),
+                // Turbo is shutting down
+                None => {
+                    return ExecOutcome::Internal;
+                }
+            };
+
+            // Let the terminal UI forward keystrokes to tasks that accept input
+            if let Some(ui_task) = &self.ui_task {
+                if self.persistent {
+                    if let Some(channel) = process.command_channel().await {
+                        ui_task.set_stdin(Box::new(channel));
+                    }
+                }
             }
-            // Turbo is shutting down
-            None => {
-                return ExecOutcome::Internal;
+
+            let mut killer = process.clone();
+            let mut timed_out = false;
+            let wait_result = {
+                let mut wait = pin!(process.wait_with_piped_outputs(&mut stdout_writer));
+                match self.timeout {
+                    Some(timeout) => match tokio::time::timeout(timeout, &mut wait).await {
+                        Ok(result) => result,
+                        Err(_) => {
+                            timed_out = true;
+                            // Kill anything the task spawned too, otherwise it can hold on
+                            // to the output, and keep piping what was written before the kill
+                            killer.kill_group().await;
+                            wait.await
+                        }
+                    },
+                    None => wait.await,
+                }
+            };
+            let exit_status = match wait_result {
+                // The task may have exited on its own before it could be killed
+                Ok(Some(ChildExit::Killed)) if timed_out => None,
+                Ok(Some(exit_status)) => Some(exit_status),
+                Err(e) => {
+                    telemetry.track_error(TrackedErrors::FailedToPipeOutputs);
+                    error!("unable to pipe outputs from command: {e}");
+                    return ExecOutcome::Internal;
+                }
+                Ok(None) => {
+                    // TODO: how can this happen? we only update the
+                    // exit status with Some and it is only initialized with
+                    // None. Is it still running?
+                    telemetry.track_error(TrackedErrors::UnknownChildExit);
+                    error!("unable to determine why child exited");
+                    return ExecOutcome::Internal;
+                }
+            };
+
+            let (outcome, exit_code) = match exit_status {
+                Some(ChildExit::Finished(Some(0))) => (AttemptOutcome::Succeeded, Some(0)),
+                Some(ChildExit::Finished(Some(code))) => (AttemptOutcome::Failed, Some(code)),
+                None => (AttemptOutcome::TimedOut, None),
+                // Anything else means the process was stopped out from under us, which
+                // we don't retry
+                Some(exit_status) => break (process, Some(exit_status)),
+            };
+            if track_attempts {
+                tracker.record_attempt(attempt_start, outcome, exit_code);
             }
-        };
 
-        let exit_status = match process.wait_with_piped_outputs(&mut stdout_writer).await {
-            Ok(Some(exit_status)) => exit_status,
-            Err(e) => {
-                telemetry.track_error(TrackedErrors::FailedToPipeOutputs);
-                error!("unable to pipe outputs from command: {e}");
-                return ExecOutcome::Internal;
+            if outcome == AttemptOutcome::Succeeded || attempt >= self.retries {
+                break (process, exit_status);
             }
-            Ok(None) => {
-                // TODO: how can this happen? we only update the
-                // exit status with Some and it is only initialized with
-                // None. Is it still running?
-                telemetry.track_error(TrackedErrors::UnknownChildExit);
-                error!("unable to determine why child exited");
-                return ExecOutcome::Internal;
+            attempt += 1;
+
+            // Keep the output of the failed attempt around for the user
+            if let Err(e) = stdout_writer.flush() {
+                error!("error flushing logs: {e}");
+            }
+            let reason = match outcome {
+                AttemptOutcome::TimedOut => "timed out".to_string(),
+                _ => format!("exited ({})", exit_code.unwrap_or_default()),
+            };
+            prefixed_ui.warn(format!(
+                "command {reason}, retrying (attempt {} of {})",
+                attempt + 1,
+                self.retries + 1
+            ));
+            if let Some(retry_delay) = self.retry_delay {
+                tokio::time::sleep(retry_delay).await;
             }
         };
         let task_duration = task_start.elapsed();
 
         match exit_status {
-            ChildExit::Finished(Some(0)) => {
+            Some(ChildExit::Finished(Some(0))) => {
                 // Attempt to flush stdout_writer and log any errors encountered
                 if let Err(e) = stdout_writer.flush() {
                     error!("{e}");
-                } else if self
-                    .task_access
-                    .can_cache(&self.task_hash, &self.task_id_for_display)
-                    .unwrap_or(true)
-                {
-                    if let Err(e) = self.task_cache.save_outputs(task_duration, telemetry).await {
-                        error!("error caching output: {e}");
-                    } else {
-                        // If no errors, update hash tracker with expanded outputs
-                        self.hash_tracker.insert_expanded_outputs(
-                            self.task_id.clone(),
-                            self.task_cache.expanded_outputs().to_vec(),
-                        );
+                } else {
+                    let sandbox_can_cache = match self.check_sandbox(&mut prefixed_ui) {
+                        Ok(can_cache) => can_cache,
+                        Err(error) => {
+                            return self.task_failed(
+                                &mut stdout_writer,
+                                &mut prefixed_ui,
+                                error,
+                                None,
+                            )
+                        }
+                    };
+                    if sandbox_can_cache
+                        && self
+                            .task_access
+                            .can_cache(&self.task_hash, &self.task_id_for_display)
+                            .unwrap_or(true)
+                    {
+                        if let Err(e) = self.task_cache.save_outputs(task_duration, telemetry).await
+                        {
+                            error!("error caching output: {e}");
+                        } else {
+                            // If no errors, update hash tracker with expanded outputs
+                            self.hash_tracker.insert_expanded_outputs(
+                                self.task_id.clone(),
+                                self.task_cache.expanded_outputs().to_vec(),
+                            );
+                        }
                     }
                 }
 
                 // Return success outcome
                 ExecOutcome::Success(SuccessOutcome::Run)
             }
-            ChildExit::Finished(Some(code)) => {
-                // If there was an error, flush the buffered output
-                if let Err(e) = stdout_writer.flush() {
-                    error!("error flushing logs: {e}");
-                }
-                if let Err(e) = self.task_cache.on_error(&mut prefixed_ui) {
-                    error!("error reading logs: {e}");
-                }
+            Some(ChildExit::Finished(Some(code))) => {
                 let error = TaskErrorCause::from_execution(process.label().to_string(), code);
-                let message = error.to_string();
-                if self.continue_on_error {
-                    prefixed_ui.warn("command finished with error, but continuing...");
-                } else {
-                    prefixed_ui.error(format!("command finished with error: {error}"));
-                }
-                self.errors.lock().expect("lock poisoned").push(TaskError {
-                    task_id: self.task_id_for_display.clone(),
-                    cause: error,
-                });
-                ExecOutcome::Task {
-                    exit_code: Some(code),
-                    message,
-                }
+                self.task_failed(&mut stdout_writer, &mut prefixed_ui, error, Some(code))
+            }
+            None => {
+                let timeout = self
+                    .timeout
+                    .expect("only attempts with a timeout can time out");
+                let error = TaskErrorCause::from_timeout(process.label().to_string(), timeout);
+                self.task_failed(&mut stdout_writer, &mut prefixed_ui, error, None)
             }
             // All of these indicate a failure where we don't know how to recover
-            ChildExit::Finished(None)
-            | ChildExit::Killed
-            | ChildExit::KilledExternal
-            | ChildExit::Failed => ExecOutcome::Internal,
+            Some(
+                ChildExit::Finished(None)
+                | ChildExit::Killed
+                | ChildExit::KilledExternal
+                | ChildExit::Failed,
+            ) => ExecOutcome::Internal,
+        }
+    }
+
+    /// Builds the process for a command declared in turbo.json. Pass through
+    /// arguments are appended to the command like they are to scripts.
+    fn command_process(&self, command: &str) -> Result<Command, std::io::Error> {
+        let pass_through_args = self.pass_through_args.as_deref().unwrap_or_default();
+        let (program, args) = if !self.shell {
+            // Quotes were validated when the task definition was loaded
+            let mut words = shell_words::split(command)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+            if words.is_empty() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "command is empty",
+                ));
+            }
+            let program = words.remove(0);
+            words.extend(pass_through_args.iter().cloned());
+            (program, words)
+        } else if cfg!(windows) {
+            let mut command = command.to_string();
+            for arg in pass_through_args {
+                command.push(' ');
+                match arg.contains(char::is_whitespace) {
+                    true => command.push_str(&format!("\"{arg}\"")),
+                    false => command.push_str(arg),
+                }
+            }
+            let args = ["/d", "/s", "/c", &command].map(String::from);
+            ("cmd".to_string(), args.to_vec())
+        } else {
+            // The arguments become the script's positional parameters
+            let script = format!("{command} \"$@\"");
+            let mut args = ["-c", &script, "sh"].map(String::from).to_vec();
+            args.extend(pass_through_args.iter().cloned());
+            ("sh".to_string(), args)
+        };
+        let mut cmd = Command::new(find_program(&program, &self.workspace_directory)?);
+        cmd.args(args);
+        Ok(cmd)
+    }
+
+    fn spawn_failed(
+        &self,
+        prefixed_ui: &mut PrefixedUI<impl Write>,
+        e: std::io::Error,
+    ) -> ExecOutcome {
+        // Note: we actually failed to spawn, but this matches the Go output
+        prefixed_ui.error(format!("command finished with error: {e}"));
+        let error_string = e.to_string();
+        self.errors
+            .lock()
+            .expect("lock poisoned")
+            .push(TaskError::from_spawn(self.task_id_for_display.clone(), e));
+        ExecOutcome::Task {
+            exit_code: None,
+            message: error_string,
+        }
+    }
+
+    /// Reports the accesses the sandbox saw that the task doesn't declare. In
+    /// strict mode these fail the task, otherwise the task's outputs just
+    /// don't get cached, which is what the returned bool indicates.
+    fn check_sandbox(
+        &self,
+        prefixed_ui: &mut PrefixedUI<impl Write>,
+    ) -> Result<bool, TaskErrorCause> {
+        let Some(sandbox) = &self.sandbox else {
+            return Ok(true);
+        };
+        let violations = match sandbox.check() {
+            Ok(violations) if violations.is_empty() => return Ok(true),
+            Ok(violations) => violations,
+            Err(e) => {
+                prefixed_ui.warn(format!("unable to check sandboxed accesses: {e}"));
+                return Ok(false);
+            }
+        };
+        let lines = sandbox.describe(&violations);
+        if sandbox.mode() == SandboxMode::Strict {
+            for line in lines {
+                prefixed_ui.error(line);
+            }
+            return Err(TaskErrorCause::Sandbox {
+                accesses: violations.len(),
+            });
+        }
+        for line in lines {
+            prefixed_ui.warn(line);
+        }
+        prefixed_ui.warn("not caching outputs because of undeclared accesses");
+        Ok(false)
+    }
+
+    fn task_failed(
+        &mut self,
+        stdout_writer: &mut impl Write,
+        prefixed_ui: &mut PrefixedUI<impl Write>,
+        error: TaskErrorCause,
+        exit_code: Option<i32>,
+    ) -> ExecOutcome {
+        // If there was an error, flush the buffered output
+        if let Err(e) = stdout_writer.flush() {
+            error!("error flushing logs: {e}");
+        }
+        if let Err(e) = self.task_cache.on_error(prefixed_ui) {
+            error!("error reading logs: {e}");
+        }
+        let message = error.to_string();
+        if self.continue_on_error {
+            prefixed_ui.warn("command finished with error, but continuing...");
+        } else {
+            prefixed_ui.error(format!("command finished with error: {error}"));
         }
+        self.errors.lock().expect("lock poisoned").push(TaskError {
+            task_id: self.task_id_for_display.clone(),
+            cause: error,
+        });
+        ExecOutcome::Task { exit_code, message }
     }
 
     fn spaces_task_info(
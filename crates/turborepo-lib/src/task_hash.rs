@@ -1,22 +1,29 @@
 use std::{
     collections::{HashMap, HashSet},
+    io,
     sync::{Arc, Mutex},
 };
 
+use futures::{future, StreamExt};
 use rayon::prelude::*;
 use serde::Serialize;
 use thiserror::Error;
 use tracing::{debug, Span};
-use turbopath::{AbsoluteSystemPath, AnchoredSystemPath, AnchoredSystemPathBuf};
+use turbopath::{
+    AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPath, AnchoredSystemPathBuf,
+};
 use turborepo_cache::CacheHitMetadata;
-use turborepo_env::{BySource, DetailedMap, EnvironmentVariableMap, ResolvedEnvMode};
+use turborepo_env::{
+    dotenv::DotEnv, BySource, DetailedMap, EnvironmentVariableMap, ResolvedEnvMode,
+};
 use turborepo_repository::package_graph::{PackageInfo, PackageName};
-use turborepo_scm::SCM;
+use turborepo_scm::{package_deps::GitHashes, SCM};
 use turborepo_telemetry::events::{
     generic::GenericEventBuilder, task::PackageTaskEventBuilder, EventBuilder,
 };
 
 use crate::{
+    daemon::{DaemonClient, DaemonConnector},
     engine::TaskNode,
     framework::infer_framework,
     hash::{FileHashes, LockFilePackages, TaskHashable, TurboHash},
@@ -39,6 +46,12 @@ pub enum Error {
     Mutex,
     #[error("missing environment variables for {0}")]
     MissingEnvVars(TaskId<'static>),
+    #[error("failed to read {path}: {source}")]
+    DotEnvRead {
+        path: AbsoluteSystemPathBuf,
+        #[source]
+        source: io::Error,
+    },
     #[error(transparent)]
     Scm(#[from] turborepo_scm::Error),
     #[error(transparent)]
@@ -59,6 +72,19 @@ impl TaskHashable<'_> {
     }
 }
 
+/// Input hashes that have already been computed elsewhere, keyed by package
+/// path and input globs
+pub type PrecomputedFileHashes = HashMap<(AnchoredSystemPathBuf, Vec<String>), GitHashes>;
+
+// How many packages to request hashes for from the daemon at once
+const DAEMON_FILE_HASH_CONCURRENCY: usize = 32;
+
+fn package_path(pkg: &PackageInfo) -> &AnchoredSystemPath {
+    pkg.package_json_path
+        .parent()
+        .unwrap_or_else(|| AnchoredSystemPath::new("").unwrap())
+}
+
 #[derive(Debug, Default)]
 pub struct PackageInputsHashes {
     hashes: HashMap<TaskId<'static>, String>,
@@ -66,7 +92,53 @@ pub struct PackageInputsHashes {
 }
 
 impl PackageInputsHashes {
-    #[tracing::instrument(skip(all_tasks, workspaces, task_definitions, repo_root, scm))]
+    /// Fetches the input hashes the daemon has cached for the given tasks.
+    /// Packages the daemon can't serve are left out, to be hashed locally.
+    #[tracing::instrument(skip_all)]
+    pub async fn fetch_from_daemon<'a>(
+        daemon: &DaemonClient<DaemonConnector>,
+        all_tasks: impl Iterator<Item = &'a TaskNode>,
+        workspaces: &HashMap<&PackageName, &PackageInfo>,
+        task_definitions: &HashMap<TaskId<'static>, TaskDefinition>,
+    ) -> PrecomputedFileHashes {
+        let specs: HashSet<_> = all_tasks
+            .filter_map(|task| {
+                let TaskNode::Task(task_id) = task else {
+                    return None;
+                };
+                let task_definition = task_definitions.get(task_id)?;
+                let pkg = workspaces.get(&task_id.to_workspace_name())?;
+                Some((package_path(pkg).to_owned(), task_definition.inputs.clone()))
+            })
+            .collect();
+
+        futures::stream::iter(specs)
+            .map(|(package_path, inputs)| {
+                let mut daemon = daemon.clone();
+                async move {
+                    match daemon.get_file_hashes(&package_path, &inputs).await {
+                        Ok(hashes) => Some(((package_path, inputs), hashes)),
+                        Err(e) => {
+                            debug!("daemon did not provide file hashes for {package_path}: {e}");
+                            None
+                        }
+                    }
+                }
+            })
+            .buffer_unordered(DAEMON_FILE_HASH_CONCURRENCY)
+            .filter_map(future::ready)
+            .collect()
+            .await
+    }
+
+    #[tracing::instrument(skip(
+        all_tasks,
+        workspaces,
+        task_definitions,
+        repo_root,
+        scm,
+        precomputed
+    ))]
     pub fn calculate_file_hashes<'a>(
         scm: &SCM,
         all_tasks: impl ParallelIterator<Item = &'a TaskNode>,
@@ -74,8 +146,9 @@ impl PackageInputsHashes {
         task_definitions: &HashMap<TaskId<'static>, TaskDefinition>,
         repo_root: &AbsoluteSystemPath,
         telemetry: &GenericEventBuilder,
+        precomputed: &PrecomputedFileHashes,
     ) -> Result<PackageInputsHashes, Error> {
-        tracing::trace!(scm_manual=%scm.is_manual(), "scm running in {} mode", if scm.is_manual() { "manual" } else { "git" });
+        tracing::trace!(scm_manual=%scm.is_manual(), "scm running in {} mode", scm.mode());
 
         let span = Span::current();
 
@@ -98,7 +171,7 @@ impl PackageInputsHashes {
                     PackageTaskEventBuilder::new(task_id.package(), task_id.task())
                         .with_parent(telemetry);
 
-                package_task_event.track_scm_mode(if scm.is_manual() { "manual" } else { "git" });
+                package_task_event.track_scm_mode(scm.mode());
                 let workspace_name = task_id.to_workspace_name();
 
                 let pkg = match workspaces
@@ -109,20 +182,23 @@ impl PackageInputsHashes {
                     Err(err) => return Some(Err(err)),
                 };
 
-                let package_path = pkg
-                    .package_json_path
-                    .parent()
-                    .unwrap_or_else(|| AnchoredSystemPath::new("").unwrap());
-
-                let scm_telemetry = package_task_event.child();
-                let mut hash_object = match scm.get_package_file_hashes(
-                    repo_root,
-                    package_path,
-                    &task_definition.inputs,
-                    Some(scm_telemetry),
-                ) {
-                    Ok(hash_object) => hash_object,
-                    Err(err) => return Some(Err(err.into())),
+                let package_path = package_path(pkg);
+
+                let precomputed_key = (package_path.to_owned(), task_definition.inputs.clone());
+                let mut hash_object = match precomputed.get(&precomputed_key) {
+                    Some(hash_object) => hash_object.clone(),
+                    None => {
+                        let scm_telemetry = package_task_event.child();
+                        match scm.get_package_file_hashes(
+                            repo_root,
+                            package_path,
+                            &task_definition.inputs,
+                            Some(scm_telemetry),
+                        ) {
+                            Ok(hash_object) => hash_object,
+                            Err(err) => return Some(Err(err.into())),
+                        }
+                    }
                 };
                 if let Some(dot_env) = &task_definition.dot_env {
                     if !dot_env.is_empty() {
@@ -171,6 +247,8 @@ pub struct TaskHashTrackerState {
     #[serde(skip)]
     package_task_framework: HashMap<TaskId<'static>, String>,
     #[serde(skip)]
+    package_task_dot_env: HashMap<TaskId<'static>, DotEnv>,
+    #[serde(skip)]
     package_task_outputs: HashMap<TaskId<'static>, Vec<AnchoredSystemPathBuf>>,
     #[serde(skip)]
     package_task_cache: HashMap<TaskId<'static>, CacheHitMetadata>,
@@ -336,6 +414,10 @@ impl<'a> TaskHasher<'a> {
                 .unwrap_or_default(),
             env_mode: task_env_mode,
             dot_env: task_definition.dot_env.as_deref().unwrap_or_default(),
+            inject_dot_env: task_definition.inject_dot_env,
+            command: task_definition.command.as_deref(),
+            cwd: task_definition.cwd.as_ref(),
+            shell: task_definition.shell,
         };
 
         let task_hash = task_hashable.calculate_task_hash();
@@ -435,6 +517,55 @@ impl<'a> TaskHasher<'a> {
             ResolvedEnvMode::Loose => Ok(self.env_at_execution_start.clone()),
         }
     }
+
+    /// Loads the task's `dotEnv` files if it injects them into its
+    /// environment. In strict mode only the variables matching the task's or
+    /// the global env patterns are kept.
+    pub fn dot_env(
+        &self,
+        task_id: &TaskId<'static>,
+        task_env_mode: ResolvedEnvMode,
+        task_definition: &TaskDefinition,
+        package_dir: &AbsoluteSystemPath,
+        global_env_patterns: &[String],
+    ) -> Result<Option<DotEnv>, Error> {
+        let Some(files) = task_definition
+            .dot_env
+            .as_deref()
+            .filter(|_| task_definition.inject_dot_env)
+        else {
+            return Ok(None);
+        };
+
+        let mut contents = Vec::with_capacity(files.len());
+        for file in files {
+            let path = package_dir.resolve(&file.to_anchored_system_path_buf());
+            match path.read_to_string() {
+                Ok(text) => contents.push((file.as_str(), text)),
+                // Missing files are skipped, same as when hashing them
+                Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+                Err(source) => return Err(Error::DotEnvRead { path, source }),
+            }
+        }
+        let mut dot_env = DotEnv::load(
+            self.env_at_execution_start,
+            contents.iter().map(|(file, text)| (*file, text.as_str())),
+        )?;
+
+        if matches!(task_env_mode, ResolvedEnvMode::Strict) {
+            let patterns: Vec<&String> = task_definition
+                .env
+                .iter()
+                .chain(task_definition.pass_through_env.iter().flatten())
+                .chain(global_env_patterns)
+                .collect();
+            dot_env.retain_matching(&patterns)?;
+        }
+
+        self.task_hash_tracker
+            .insert_dot_env(task_id.clone(), dot_env.clone());
+        Ok(Some(dot_env))
+    }
 }
 
 pub fn get_external_deps_hash(
@@ -497,6 +628,16 @@ impl TaskHashTracker {
         state.package_task_env_vars.get(task_id).cloned()
     }
 
+    fn insert_dot_env(&self, task_id: TaskId<'static>, dot_env: DotEnv) {
+        let mut state = self.state.lock().expect("hash tracker mutex poisoned");
+        state.package_task_dot_env.insert(task_id, dot_env);
+    }
+
+    pub fn dot_env(&self, task_id: &TaskId) -> Option<DotEnv> {
+        let state = self.state.lock().expect("hash tracker mutex poisoned");
+        state.package_task_dot_env.get(task_id).cloned()
+    }
+
     pub fn framework(&self, task_id: &TaskId) -> Option<String> {
         let state = self.state.lock().expect("hash tracker mutex poisoned");
         state.package_task_framework.get(task_id).cloned()
@@ -0,0 +1,378 @@
+//! Checks a single turbo.json the way `turbo run` would, reporting problems
+//! as byte ranges into its text so editors can show them while the file is
+//! being edited.
+
+use std::{collections::HashMap, ops::Range};
+
+use miette::{Diagnostic, SourceSpan};
+use turbopath::{AbsoluteSystemPath, AnchoredSystemPath};
+use turborepo_repository::package_graph::{PackageGraph, PackageName};
+
+use super::{
+    validate_extends, validate_no_package_task_syntax, RawTurboJson, TurboJson, CONFIG_FILE,
+    ENV_PIPELINE_DELIMITER, TOPOLOGICAL_PIPELINE_DELIMITER,
+};
+use crate::{
+    engine::{BuilderError, EngineBuilder, ValidateError},
+    run::{
+        summary::TaskSummaryTaskDefinition,
+        task_id::{TaskId, TaskName},
+    },
+};
+
+/// A problem found in a turbo.json.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TurboJsonDiagnostic {
+    pub message: String,
+    /// Byte range in the analyzed text, if the problem can be located there
+    pub range: Option<Range<usize>>,
+    pub code: &'static str,
+}
+
+pub struct TurboJsonAnalyzer<'a> {
+    repo_root: &'a AbsoluteSystemPath,
+    package_graph: &'a PackageGraph,
+}
+
+impl<'a> TurboJsonAnalyzer<'a> {
+    pub fn new(repo_root: &'a AbsoluteSystemPath, package_graph: &'a PackageGraph) -> Self {
+        Self {
+            repo_root,
+            package_graph,
+        }
+    }
+
+    /// Validates `contents` as the turbo.json at `path`, including building
+    /// and validating the task graph for the tasks it defines.
+    pub fn diagnostics(
+        &self,
+        path: &AnchoredSystemPath,
+        contents: &str,
+    ) -> Vec<TurboJsonDiagnostic> {
+        let turbo_json = match RawTurboJson::parse(contents, path) {
+            Ok(raw) => raw,
+            Err(e) => {
+                return e
+                    .related()
+                    .into_iter()
+                    .flatten()
+                    .map(|d| TurboJsonDiagnostic::new(d, contents, "turbo:parse-error"))
+                    .collect()
+            }
+        };
+        let turbo_json = match TurboJson::try_from(without_env_dependencies(turbo_json)) {
+            Ok(turbo_json) => turbo_json,
+            Err(e) => {
+                return vec![TurboJsonDiagnostic::new(
+                    &e,
+                    contents,
+                    "turbo:invalid-config",
+                )]
+            }
+        };
+
+        let Some(package) = self.package_for(path) else {
+            return vec![];
+        };
+
+        if package != PackageName::Root {
+            let errors = turbo_json.validate(&[validate_no_package_task_syntax, validate_extends]);
+            if !errors.is_empty() {
+                return errors
+                    .iter()
+                    .map(|e| TurboJsonDiagnostic::new(e, contents, "turbo:invalid-config"))
+                    .collect();
+            }
+        }
+
+        self.task_graph_diagnostics(package, turbo_json, contents)
+    }
+
+    /// Resolves `task` as seen from the turbo.json at `path`, merging it with
+    /// the root turbo.json it extends. The result has the same shape as the
+    /// task definitions in `turbo run --dry=json`.
+    pub fn task_definition(
+        &self,
+        path: &AnchoredSystemPath,
+        contents: &str,
+        task: &str,
+    ) -> Option<serde_json::Value> {
+        let package = self.package_for(path)?;
+        let turbo_json = RawTurboJson::parse(contents, path).ok()?;
+        let turbo_json = without_env_dependencies(turbo_json).try_into().ok()?;
+        let mut turbo_jsons = self.load_root(&package);
+        turbo_jsons.insert(package.clone(), turbo_json);
+
+        let task_name = TaskName::from(task);
+        let task_id = task_name
+            .task_id()
+            .unwrap_or_else(|| TaskId::new(package.as_ref(), task_name.task()));
+
+        let definition = EngineBuilder::new(self.repo_root, self.package_graph, false)
+            .with_turbo_jsons(Some(turbo_jsons))
+            .task_definition(&task_id)
+            .ok()?;
+
+        serde_json::to_value(TaskSummaryTaskDefinition::from(definition)).ok()
+    }
+
+    fn package_for(&self, path: &AnchoredSystemPath) -> Option<PackageName> {
+        self.package_graph
+            .packages()
+            .find(|(_, info)| *info.package_path().join_component(CONFIG_FILE) == *path)
+            .map(|(name, _)| name.clone())
+    }
+
+    // The engine builder loads any turbo.json it isn't handed from disk. We load
+    // the root ourselves so the deprecated env dependencies in it are dropped
+    // before they can be printed.
+    fn load_root(&self, package: &PackageName) -> HashMap<PackageName, TurboJson> {
+        let mut turbo_jsons = HashMap::new();
+        if *package != PackageName::Root {
+            let path = AnchoredSystemPath::new(CONFIG_FILE).expect("turbo.json is relative");
+            let root = RawTurboJson::read(self.repo_root, path)
+                .ok()
+                .and_then(|raw| without_env_dependencies(raw).try_into().ok());
+            if let Some(root) = root {
+                turbo_jsons.insert(PackageName::Root, root);
+            }
+        }
+        turbo_jsons
+    }
+
+    fn task_graph_diagnostics(
+        &self,
+        package: PackageName,
+        turbo_json: TurboJson,
+        contents: &str,
+    ) -> Vec<TurboJsonDiagnostic> {
+        let tasks = turbo_json.pipeline.keys().cloned().collect::<Vec<_>>();
+        let (workspaces, root_turbo_json) = if package == PackageName::Root {
+            let workspaces = self
+                .package_graph
+                .packages()
+                .map(|(name, _)| name.clone())
+                .collect();
+            (workspaces, Some(&turbo_json))
+        } else {
+            (vec![package.clone()], None)
+        };
+
+        let mut turbo_jsons = self.load_root(&package);
+        let root_tasks = root_turbo_json
+            .or_else(|| turbo_jsons.get(&PackageName::Root))
+            .map(|root| root.pipeline.keys().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+        turbo_jsons.insert(package.clone(), turbo_json.clone());
+
+        let engine = EngineBuilder::new(self.repo_root, self.package_graph, false)
+            .with_turbo_jsons(Some(turbo_jsons))
+            .with_root_tasks(root_tasks)
+            .with_workspaces(workspaces)
+            .with_tasks(tasks)
+            .build();
+
+        let engine = match engine {
+            Ok(engine) => engine,
+            Err(e) => return vec![self.builder_diagnostic(e, &turbo_json, contents)],
+        };
+
+        // Concurrency is a flag passed to `turbo run`, so there is nothing in the
+        // turbo.json to check it against.
+        let Err(errors) = engine.validate(self.package_graph, u32::MAX) else {
+            return vec![];
+        };
+
+        errors
+            .into_iter()
+            .map(|e| {
+                let range = match &e {
+                    ValidateError::DependencyOnPersistentTask {
+                        persistent_task,
+                        dependant,
+                    } => find_dependency(&turbo_json, contents, dependant, persistent_task),
+                    _ => None,
+                };
+                TurboJsonDiagnostic {
+                    message: e.to_string(),
+                    range,
+                    code: "turbo:invalid-task-graph",
+                }
+            })
+            .collect()
+    }
+
+    fn builder_diagnostic(
+        &self,
+        error: BuilderError,
+        turbo_json: &TurboJson,
+        contents: &str,
+    ) -> TurboJsonDiagnostic {
+        let range = match &error {
+            BuilderError::Config(e) => {
+                return TurboJsonDiagnostic::new(e, contents, "turbo:invalid-config")
+            }
+            BuilderError::Validation { errors } => {
+                if let Some(e) = errors.first() {
+                    return TurboJsonDiagnostic::new(e, contents, "turbo:invalid-config");
+                }
+                None
+            }
+            BuilderError::MissingTaskForRoot { task_id }
+            | BuilderError::MissingWorkspaceFromTask { task_id, .. }
+            | BuilderError::MissingWorkspaceTask { task_id, .. } => {
+                find_reference(turbo_json, contents, task_id)
+            }
+            _ => None,
+        };
+
+        TurboJsonDiagnostic {
+            message: error.to_string(),
+            range,
+            code: "turbo:invalid-task-graph",
+        }
+    }
+}
+
+impl TurboJsonDiagnostic {
+    fn new(diagnostic: &dyn Diagnostic, contents: &str, code: &'static str) -> Self {
+        let range = diagnostic
+            .labels()
+            .into_iter()
+            .flatten()
+            .map(|label| *label.inner())
+            .find(|span| is_span_of(diagnostic, span, contents))
+            .map(|span| span.offset()..span.offset() + span.len());
+
+        Self {
+            message: diagnostic.to_string(),
+            range,
+            code,
+        }
+    }
+}
+
+// Errors raised while building the task graph can come from any turbo.json,
+// so only keep spans that point at the same text in the file being analyzed.
+fn is_span_of(diagnostic: &dyn Diagnostic, span: &SourceSpan, contents: &str) -> bool {
+    let Some(source) = diagnostic.source_code() else {
+        return false;
+    };
+    let Ok(source) = source.read_span(span, 0, 0) else {
+        return false;
+    };
+    let start = source.span().offset();
+    contents.as_bytes().get(start..start + source.span().len()) == Some(source.data())
+}
+
+// Conversion prints a deprecation notice for `$` dependencies. Editors report
+// those on their own, and printing would corrupt a language server's stdout.
+fn without_env_dependencies(mut raw: RawTurboJson) -> RawTurboJson {
+    if let Some(global_dependencies) = &mut raw.global_dependencies {
+        global_dependencies.retain(|dep| !dep.starts_with(ENV_PIPELINE_DELIMITER));
+    }
+    for (_, task) in raw.pipeline.iter_mut().flat_map(|p| p.iter_mut()) {
+        if let Some(depends_on) = &mut task.value.depends_on {
+            depends_on
+                .value
+                .retain(|dep| !dep.starts_with(ENV_PIPELINE_DELIMITER));
+        }
+    }
+    raw
+}
+
+// Finds the `dependsOn` entry of `dependant` that refers to `dependency`.
+fn find_dependency(
+    turbo_json: &TurboJson,
+    contents: &str,
+    dependant: &str,
+    dependency: &str,
+) -> Option<Range<usize>> {
+    let dependant = TaskId::try_from(dependant).ok()?;
+    let dependency = TaskId::try_from(dependency).ok()?;
+    let task = turbo_json
+        .pipeline
+        .get(&dependant.as_task_name())
+        .or_else(|| {
+            turbo_json
+                .pipeline
+                .get(&dependant.as_non_workspace_task_name())
+        })?;
+    let depends_on = task.value.depends_on.as_ref()?;
+    let entry = depends_on
+        .value
+        .iter()
+        .find(|dep| refers_to(dep, Some(dependant.package()), &dependency))?;
+    find_string(contents, depends_on.range.clone()?, entry)
+}
+
+// Finds the first `dependsOn` entry anywhere in the file that refers to
+// `task_id`.
+fn find_reference(turbo_json: &TurboJson, contents: &str, task_id: &str) -> Option<Range<usize>> {
+    let task_id = TaskId::try_from(task_id).ok()?;
+    turbo_json.pipeline.values().find_map(|task| {
+        let depends_on = task.value.depends_on.as_ref()?;
+        let entry = depends_on
+            .value
+            .iter()
+            .find(|dep| refers_to(dep, None, &task_id))?;
+        find_string(contents, depends_on.range.clone()?, entry)
+    })
+}
+
+// A bare dependency runs in the dependant's own package, so it can only be
+// matched against a package when we know which package the dependant is in.
+fn refers_to(dependency: &str, dependant_package: Option<&str>, task_id: &TaskId) -> bool {
+    if let Some(dependency) = dependency.strip_prefix(TOPOLOGICAL_PIPELINE_DELIMITER) {
+        return dependency == task_id.task();
+    }
+    match TaskName::from(dependency).task_id() {
+        Some(dependency) => dependency == *task_id,
+        None => {
+            dependency == task_id.task()
+                && dependant_package.map_or(true, |package| package == task_id.package())
+        }
+    }
+}
+
+// Locates the string literal `value` in `contents`, excluding its quotes.
+fn find_string(contents: &str, within: Range<usize>, value: &str) -> Option<Range<usize>> {
+    let haystack = contents.get(within.clone())?;
+    let start = within.start + haystack.find(&format!("\"{}\"", value))? + 1;
+    Some(start..start + value.len())
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case(r#"{"dependsOn": ["^build", "lint"]}"#, "lint", Some(26..30) ; "plain")]
+    #[test_case(r#"{"dependsOn": ["^build", "lint"]}"#, "^build", Some(16..22) ; "topological")]
+    #[test_case(r#"{"dependsOn": ["^build", "lint"]}"#, "test", None ; "missing")]
+    fn test_find_string(contents: &str, value: &str, expected: Option<Range<usize>>) {
+        let within = 14..contents.len() - 1;
+        assert_eq!(find_string(contents, within, value), expected);
+        if let Some(range) = expected {
+            assert_eq!(&contents[range], value);
+        }
+    }
+
+    #[test_case("build", Some("web"), "web#build", true ; "bare task")]
+    #[test_case("build", Some("docs"), "web#build", false ; "bare task in other package")]
+    #[test_case("build", None, "web#build", true ; "bare task in unknown package")]
+    #[test_case("^build", Some("docs"), "web#build", true ; "topological task")]
+    #[test_case("web#build", Some("docs"), "web#build", true ; "package task")]
+    #[test_case("docs#build", Some("web"), "web#build", false ; "other package")]
+    #[test_case("lint", Some("web"), "web#build", false ; "other task")]
+    fn test_refers_to(
+        dependency: &str,
+        dependant_package: Option<&str>,
+        task_id: &str,
+        expected: bool,
+    ) {
+        let task_id = TaskId::try_from(task_id).unwrap();
+        assert_eq!(refers_to(dependency, dependant_package, &task_id), expected);
+    }
+}
@@ -2,6 +2,7 @@ use std::{
     collections::{BTreeMap, HashMap, HashSet},
     ops::{Deref, DerefMut},
     sync::Arc,
+    time::Duration,
 };
 
 use camino::Utf8Path;
@@ -13,6 +14,7 @@ use turborepo_errors::Spanned;
 use turborepo_repository::{package_graph::ROOT_PKG_NAME, package_json::PackageJson};
 
 use crate::{
+    boundaries::{BoundariesConfig, Permissions, TagRules},
     cli::OutputLogsMode,
     config::{ConfigurationOptions, Error},
     run::{
@@ -23,6 +25,7 @@ use crate::{
     unescape::UnescapedString,
 };
 
+pub mod analysis;
 pub mod parser;
 
 #[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
@@ -53,6 +56,9 @@ pub struct TurboJson {
     pub(crate) global_env: Vec<String>,
     pub(crate) global_pass_through_env: Option<Vec<String>>,
     pub(crate) pipeline: Pipeline,
+    pub(crate) resources: BTreeMap<String, u32>,
+    pub(crate) tags: Vec<String>,
+    pub(crate) boundaries: BoundariesConfig,
 }
 
 // Iterable is required to enumerate allowed keys
@@ -75,6 +81,16 @@ pub(crate) struct RawRemoteCacheOptions {
     timeout: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    backend: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    s3_bucket: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    s3_region: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    s3_endpoint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    directory: Option<String>,
 }
 
 impl From<&RawRemoteCacheOptions> for ConfigurationOptions {
@@ -88,11 +104,97 @@ impl From<&RawRemoteCacheOptions> for ConfigurationOptions {
             preflight: remote_cache_opts.preflight,
             timeout: remote_cache_opts.timeout,
             enabled: remote_cache_opts.enabled,
+            remote_cache_backend: remote_cache_opts.backend.clone(),
+            s3_bucket: remote_cache_opts.s3_bucket.clone(),
+            s3_region: remote_cache_opts.s3_region.clone(),
+            s3_endpoint: remote_cache_opts.s3_endpoint.clone(),
+            remote_cache_dir: remote_cache_opts.directory.clone(),
             ..Self::default()
         }
     }
 }
 
+// Iterable is required to enumerate allowed keys
+#[derive(Clone, Debug, Default, Iterable, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RawLocalCacheOptions {
+    // Sizes and durations are kept as strings and validated
+    // when the local cache is constructed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) max_size: Option<UnescapedString>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) max_age: Option<UnescapedString>,
+}
+
+// Iterable is required to enumerate allowed keys
+#[derive(Clone, Debug, Default, Iterable, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RawOtelOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) endpoint: Option<UnescapedString>,
+    // Validated when the run options are resolved
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) protocol: Option<UnescapedString>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) headers: Option<BTreeMap<String, String>>,
+}
+
+// Iterable is required to enumerate allowed keys
+#[derive(Clone, Debug, Default, Iterable, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RawBoundaries {
+    // Rules for the packages with each tag, keyed by tag
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) tags: Option<BTreeMap<String, RawTagRules>>,
+}
+
+// Iterable is required to enumerate allowed keys
+#[derive(Clone, Debug, Default, Iterable, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RawTagRules {
+    // Which packages a package with the tag may depend on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) dependencies: Option<RawPermissions>,
+    // Which packages may depend on a package with the tag
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) dependents: Option<RawPermissions>,
+}
+
+// Iterable is required to enumerate allowed keys
+#[derive(Clone, Debug, Default, Iterable, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RawPermissions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) allow: Option<Vec<UnescapedString>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) deny: Option<Vec<UnescapedString>>,
+}
+
+impl From<RawBoundaries> for BoundariesConfig {
+    fn from(raw: RawBoundaries) -> Self {
+        let permissions = |raw: RawPermissions| Permissions {
+            allow: raw
+                .allow
+                .map(|allow| allow.into_iter().map(String::from).collect()),
+            deny: raw.deny.into_iter().flatten().map(String::from).collect(),
+        };
+        BoundariesConfig {
+            tags: raw
+                .tags
+                .into_iter()
+                .flatten()
+                .map(|(tag, rules)| {
+                    let rules = TagRules {
+                        dependencies: rules.dependencies.map(permissions),
+                        dependents: rules.dependents.map(permissions),
+                    };
+                    (tag, rules)
+                })
+                .collect(),
+        }
+    }
+}
+
 #[derive(Serialize, Default, Debug, Clone, Iterable)]
 #[serde(rename_all = "camelCase")]
 // The raw deserialized turbo.json file.
@@ -127,6 +229,25 @@ pub struct RawTurboJson {
     // Configuration options when interfacing with the remote cache
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) remote_cache: Option<RawRemoteCacheOptions>,
+    // Limits for the local filesystem cache
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) local_cache: Option<RawLocalCacheOptions>,
+    // Capacities of the named resource pools that tasks draw from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resources: Option<BTreeMap<String, u32>>,
+    // Where to export run traces and metrics over OTLP
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) otel: Option<RawOtelOptions>,
+    // Other ecosystems to discover packages from, e.g. "cargo"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) package_discovery: Option<Vec<UnescapedString>>,
+    // Tags of the package, used by the boundaries rules
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tags: Option<Vec<UnescapedString>>,
+    // Which tagged packages may depend on each other. Only read from the root
+    // turbo.json.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    boundaries: Option<RawBoundaries>,
 }
 
 #[derive(Serialize, Default, Debug, PartialEq, Clone)]
@@ -163,21 +284,37 @@ pub struct RawTaskDefinition {
     #[serde(skip_serializing_if = "Spanned::is_none")]
     cache: Spanned<Option<bool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    command: Option<Spanned<UnescapedString>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cwd: Option<Spanned<UnescapedString>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     depends_on: Option<Spanned<Vec<UnescapedString>>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     dot_env: Option<Spanned<Vec<UnescapedString>>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     env: Option<Vec<Spanned<UnescapedString>>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    inject_dot_env: Option<Spanned<bool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     inputs: Option<Vec<Spanned<UnescapedString>>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pass_through_env: Option<Vec<Spanned<UnescapedString>>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     persistent: Option<Spanned<bool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    shell: Option<Spanned<bool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     outputs: Option<Vec<Spanned<UnescapedString>>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     output_mode: Option<Spanned<OutputLogsMode>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retries: Option<Spanned<u32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry_delay: Option<Spanned<UnescapedString>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timeout: Option<Spanned<UnescapedString>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resources: Option<Spanned<BTreeMap<String, u32>>>,
 }
 
 macro_rules! set_field {
@@ -204,6 +341,46 @@ impl RawTaskDefinition {
         set_field!(self, other, env);
         set_field!(self, other, pass_through_env);
         set_field!(self, other, dot_env);
+        set_field!(self, other, inject_dot_env);
+        set_field!(self, other, retries);
+        set_field!(self, other, retry_delay);
+        set_field!(self, other, timeout);
+        set_field!(self, other, resources);
+        set_field!(self, other, command);
+        set_field!(self, other, cwd);
+        set_field!(self, other, shell);
+    }
+
+    // Checks that every resource this task uses is declared in the root
+    // turbo.json with enough capacity for the task to ever be scheduled
+    pub fn validate_resources(&self, pools: &BTreeMap<String, u32>) -> Result<(), Error> {
+        let Some(resources) = &self.resources else {
+            return Ok(());
+        };
+        for (resource, weight) in resources.iter() {
+            match pools.get(resource) {
+                None => {
+                    let (span, text) = resources.span_and_text();
+                    return Err(Error::UnknownResource {
+                        resource: resource.clone(),
+                        span,
+                        text,
+                    });
+                }
+                Some(capacity) if weight > capacity => {
+                    let (span, text) = resources.span_and_text();
+                    return Err(Error::ResourceExceedsCapacity {
+                        resource: resource.clone(),
+                        weight: *weight,
+                        capacity: *capacity,
+                        span,
+                        text,
+                    });
+                }
+                Some(_) => (),
+            }
+        }
+        Ok(())
     }
 }
 
@@ -344,6 +521,41 @@ impl TryFrom<RawTaskDefinition> for TaskDefinition {
             })
             .transpose()?;
 
+        let shell = raw_task.shell.as_deref().copied().unwrap_or(true);
+        if let Some(command) = &raw_task.command {
+            if !shell && shell_words::split(&command.value).is_err() {
+                let (span, text) = command.span_and_text();
+                return Err(Error::InvalidTaskCommand { span, text });
+            }
+        } else if let Some((field, (span, text))) = raw_task
+            .cwd
+            .as_ref()
+            .map(|cwd| ("cwd", cwd.span_and_text()))
+            .or_else(|| {
+                raw_task
+                    .shell
+                    .as_ref()
+                    .map(|shell| ("shell", shell.span_and_text()))
+            })
+        {
+            return Err(Error::FieldRequiresCommand { field, span, text });
+        }
+
+        let cwd = raw_task
+            .cwd
+            .map(|cwd| {
+                if Utf8Path::new(&cwd.value).is_absolute() {
+                    let (span, text) = cwd.span_and_text();
+                    return Err(Error::AbsolutePathInConfig {
+                        field: "cwd",
+                        span,
+                        text,
+                    });
+                }
+                Ok(RelativeUnixPathBuf::new(cwd.into_inner())?)
+            })
+            .transpose()?;
+
         Ok(TaskDefinition {
             outputs,
             cache: cache.into_inner().unwrap_or(true),
@@ -353,12 +565,44 @@ impl TryFrom<RawTaskDefinition> for TaskDefinition {
             inputs,
             pass_through_env,
             dot_env,
+            inject_dot_env: *raw_task.inject_dot_env.unwrap_or_default(),
             output_mode: *raw_task.output_mode.unwrap_or_default(),
             persistent: *raw_task.persistent.unwrap_or_default(),
+            retries: raw_task.retries.map_or(0, |retries| retries.into_inner()),
+            retry_delay: raw_task
+                .retry_delay
+                .map(|delay| parse_task_duration(delay, "retryDelay"))
+                .transpose()?,
+            timeout: raw_task
+                .timeout
+                .map(|timeout| parse_task_duration(timeout, "timeout"))
+                .transpose()?,
+            resources: raw_task
+                .resources
+                .map(|resources| resources.into_inner())
+                .unwrap_or_default(),
+            command: raw_task.command.map(|command| command.into_inner().into()),
+            cwd,
+            shell,
         })
     }
 }
 
+fn parse_task_duration(
+    duration: Spanned<UnescapedString>,
+    field: &'static str,
+) -> Result<Duration, Error> {
+    humantime::parse_duration(&duration.value).map_err(|_| {
+        let (span, text) = duration.span_and_text();
+        Error::InvalidTaskDuration {
+            field,
+            value: duration.value.to_string(),
+            span,
+            text,
+        }
+    })
+}
+
 impl RawTurboJson {
     pub(crate) fn read(
         repo_root: &AbsoluteSystemPath,
@@ -499,6 +743,14 @@ impl TryFrom<RawTurboJson> for TurboJson {
                 })
                 .transpose()?,
             pipeline: raw_turbo.pipeline.unwrap_or_default(),
+            resources: raw_turbo.resources.unwrap_or_default(),
+            tags: raw_turbo
+                .tags
+                .into_iter()
+                .flatten()
+                .map(String::from)
+                .collect(),
+            boundaries: raw_turbo.boundaries.map(Into::into).unwrap_or_default(),
             // copy these over, we don't need any changes here.
             extends: raw_turbo
                 .extends
@@ -699,7 +951,11 @@ fn gather_env_vars(
 
 #[cfg(test)]
 mod tests {
-    use std::fs;
+    use std::{
+        collections::{BTreeMap, BTreeSet},
+        fs,
+        time::Duration,
+    };
 
     use anyhow::Result;
     use biome_deserialize::json::deserialize_from_json_str;
@@ -713,6 +969,7 @@ mod tests {
 
     use super::{Pipeline, RawTurboJson, Spanned};
     use crate::{
+        boundaries::{BoundariesConfig, Permissions, TagRules},
         cli::OutputLogsMode,
         run::task_id::TaskName,
         task_graph::{TaskDefinition, TaskOutputs},
@@ -739,6 +996,38 @@ mod tests {
             ..TurboJson::default()
         }
     )]
+    #[test_case(r#"{ "resources": { "memory": 8, "gpu-lock": 1 } }"#,
+        TurboJson {
+            resources: BTreeMap::from([("gpu-lock".to_string(), 1), ("memory".to_string(), 8)]),
+            ..TurboJson::default()
+        }
+    ; "resource pools")]
+    #[test_case(r#"{ "tags": ["ui", "internal"] }"#,
+        TurboJson {
+            tags: vec!["ui".to_string(), "internal".to_string()],
+            ..TurboJson::default()
+        }
+    ; "tags")]
+    #[test_case(r#"{ "boundaries": { "tags": { "app": { "dependents": { "allow": [] } }, "ui": { "dependencies": { "allow": ["utils"], "deny": ["app"] } } } } }"#,
+        TurboJson {
+            boundaries: BoundariesConfig {
+                tags: BTreeMap::from([
+                    ("app".to_string(), TagRules {
+                        dependents: Some(Permissions { allow: Some(BTreeSet::new()), deny: BTreeSet::new() }),
+                        ..TagRules::default()
+                    }),
+                    ("ui".to_string(), TagRules {
+                        dependencies: Some(Permissions {
+                            allow: Some(BTreeSet::from(["utils".to_string()])),
+                            deny: BTreeSet::from(["app".to_string()]),
+                        }),
+                        ..TagRules::default()
+                    }),
+                ]),
+            },
+            ..TurboJson::default()
+        }
+    ; "boundaries")]
     fn test_get_root_turbo_no_synthesizing(
         turbo_json_content: &str,
         expected_turbo_json: TurboJson,
@@ -877,6 +1166,20 @@ mod tests {
         }
         ; "empty dotenv"
     )]
+    #[test_case(
+        r#"{ "dotEnv": [".env"], "injectDotEnv": true }"#,
+        RawTaskDefinition {
+            dot_env: Some(Spanned::new(vec![".env".into()]).with_range(12..20)),
+            inject_dot_env: Some(Spanned::new(true).with_range(38..42)),
+            ..RawTaskDefinition::default()
+        },
+        TaskDefinition {
+            dot_env: Some(vec![RelativeUnixPathBuf::new(".env").unwrap()]),
+            inject_dot_env: true,
+            ..Default::default()
+        }
+        ; "inject dotenv"
+    )]
     #[test_case(
         r#"{
           "dependsOn": ["cli#build"],
@@ -893,16 +1196,25 @@ mod tests {
             depends_on: Some(Spanned::new(vec!["cli#build".into()]).with_range(25..38)),
             dot_env: Some(Spanned::new(vec!["package/a/.env".into()]).with_range(60..78)),
             env: Some(vec![Spanned::<UnescapedString>::new("OS".into()).with_range(98..102)]),
+            inject_dot_env: None,
             pass_through_env: Some(vec![Spanned::<UnescapedString>::new("AWS_SECRET_KEY".into()).with_range(134..150)]),
             outputs: Some(vec![Spanned::<UnescapedString>::new("package/a/dist".into()).with_range(175..191)]),
             cache: Spanned::new(Some(false)).with_range(213..218),
             inputs: Some(vec![Spanned::<UnescapedString>::new("package/a/src/**".into()).with_range(241..259)]),
             output_mode: Some(Spanned::new(OutputLogsMode::Full).with_range(286..292)),
             persistent: Some(Spanned::new(true).with_range(318..322)),
+            retries: None,
+            retry_delay: None,
+            timeout: None,
+            resources: None,
+            command: None,
+            cwd: None,
+            shell: None,
         },
         TaskDefinition {
           dot_env: Some(vec![RelativeUnixPathBuf::new("package/a/.env").unwrap()]),
           env: vec!["OS".to_string()],
+          inject_dot_env: false,
           outputs: TaskOutputs {
               inclusions: vec!["package/a/dist".to_string()],
               exclusions: vec![],
@@ -914,6 +1226,13 @@ mod tests {
           task_dependencies: vec!["cli#build".into()],
           topological_dependencies: vec![],
           persistent: true,
+          retries: 0,
+          retry_delay: None,
+          timeout: None,
+          resources: BTreeMap::new(),
+          command: None,
+          cwd: None,
+          shell: true,
         }
       ; "full"
     )]
@@ -933,16 +1252,25 @@ mod tests {
             depends_on: Some(Spanned::new(vec!["cli#build".into()]).with_range(29..42)),
             dot_env: Some(Spanned::new(vec!["package\\a\\.env".into()]).with_range(68..88)),
             env: Some(vec![Spanned::<UnescapedString>::new("OS".into()).with_range(112..116)]),
+            inject_dot_env: None,
             pass_through_env: Some(vec![Spanned::<UnescapedString>::new("AWS_SECRET_KEY".into()).with_range(152..168)]),
             outputs: Some(vec![Spanned::<UnescapedString>::new("package\\a\\dist".into()).with_range(197..215)]),
             cache: Spanned::new(Some(false)).with_range(241..246),
             inputs: Some(vec![Spanned::<UnescapedString>::new("package\\a\\src\\**".into()).with_range(273..294)]),
             output_mode: Some(Spanned::new(OutputLogsMode::Full).with_range(325..331)),
             persistent: Some(Spanned::new(true).with_range(361..365)),
+            retries: None,
+            retry_delay: None,
+            timeout: None,
+            resources: None,
+            command: None,
+            cwd: None,
+            shell: None,
         },
         TaskDefinition {
             dot_env: Some(vec![RelativeUnixPathBuf::new("package\\a\\.env").unwrap()]),
             env: vec!["OS".to_string()],
+            inject_dot_env: false,
             outputs: TaskOutputs {
                 inclusions: vec!["package\\a\\dist".to_string()],
                 exclusions: vec![],
@@ -954,9 +1282,48 @@ mod tests {
             task_dependencies: vec!["cli#build".into()],
             topological_dependencies: vec![],
             persistent: true,
+            retries: 0,
+            retry_delay: None,
+            timeout: None,
+            resources: BTreeMap::new(),
+            command: None,
+            cwd: None,
+            shell: true,
         }
       ; "full (windows)"
     )]
+    #[test_case(
+        r#"{ "command": "cargo build --release", "cwd": "crates/cli", "shell": false }"#,
+        RawTaskDefinition {
+            command: Some(Spanned::<UnescapedString>::new("cargo build --release".into()).with_range(13..36)),
+            cwd: Some(Spanned::<UnescapedString>::new("crates/cli".into()).with_range(45..57)),
+            shell: Some(Spanned::new(false).with_range(68..73)),
+            ..RawTaskDefinition::default()
+        },
+        TaskDefinition {
+            command: Some("cargo build --release".to_string()),
+            cwd: Some(RelativeUnixPathBuf::new("crates/cli").unwrap()),
+            shell: false,
+            ..Default::default()
+        }
+      ; "command"
+    )]
+    #[test_case(
+        r#"{ "retries": 2, "retryDelay": "5s", "timeout": "10m" }"#,
+        RawTaskDefinition {
+            retries: Some(Spanned::new(2).with_range(13..14)),
+            retry_delay: Some(Spanned::<UnescapedString>::new("5s".into()).with_range(30..34)),
+            timeout: Some(Spanned::<UnescapedString>::new("10m".into()).with_range(47..52)),
+            ..RawTaskDefinition::default()
+        },
+        TaskDefinition {
+            retries: 2,
+            retry_delay: Some(Duration::from_secs(5)),
+            timeout: Some(Duration::from_secs(600)),
+            ..Default::default()
+        }
+      ; "retries and timeout"
+    )]
     fn test_deserialize_task_definition(
         task_definition_content: &str,
         expected_raw_task_definition: RawTaskDefinition,
@@ -976,6 +1343,108 @@ mod tests {
         Ok(())
     }
 
+    #[test_case(r#"{ "retryDelay": "soon" }"#, "retryDelay" ; "retry delay")]
+    #[test_case(r#"{ "timeout": "10" }"#, "timeout" ; "timeout without unit")]
+    fn test_invalid_task_duration(task_definition_content: &str, expected_field: &str) {
+        let raw_task_definition: RawTaskDefinition = deserialize_from_json_str(
+            task_definition_content,
+            JsonParserOptions::default().with_allow_comments(),
+        )
+        .into_deserialized()
+        .unwrap();
+
+        let result: Result<TaskDefinition, _> = raw_task_definition.try_into();
+        match result {
+            Err(crate::config::Error::InvalidTaskDuration { field, .. }) => {
+                assert_eq!(field, expected_field)
+            }
+            other => panic!("expected an invalid duration error, got {other:?}"),
+        }
+    }
+
+    #[test_case(r#"{ "cwd": "src" }"#, "cwd" ; "cwd without command")]
+    #[test_case(r#"{ "shell": false }"#, "shell" ; "shell without command")]
+    fn test_field_requires_command(task_definition_content: &str, expected_field: &str) {
+        let raw_task_definition: RawTaskDefinition = deserialize_from_json_str(
+            task_definition_content,
+            JsonParserOptions::default().with_allow_comments(),
+        )
+        .into_deserialized()
+        .unwrap();
+
+        let result: Result<TaskDefinition, _> = raw_task_definition.try_into();
+        match result {
+            Err(crate::config::Error::FieldRequiresCommand { field, .. }) => {
+                assert_eq!(field, expected_field)
+            }
+            other => panic!("expected a missing command error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_unterminated_quote_in_command() {
+        let raw_task_definition: RawTaskDefinition = deserialize_from_json_str(
+            r#"{ "command": "echo 'hello", "shell": false }"#,
+            JsonParserOptions::default().with_allow_comments(),
+        )
+        .into_deserialized()
+        .unwrap();
+
+        let result: Result<TaskDefinition, _> = raw_task_definition.try_into();
+        assert!(matches!(
+            result,
+            Err(crate::config::Error::InvalidTaskCommand { .. })
+        ));
+    }
+
+    #[test]
+    fn test_merge_retries() {
+        let mut task_definition = RawTaskDefinition {
+            retries: Some(Spanned::new(2)),
+            timeout: Some(Spanned::new("1m".into())),
+            ..RawTaskDefinition::default()
+        };
+        task_definition.merge(RawTaskDefinition {
+            retries: Some(Spanned::new(0)),
+            retry_delay: Some(Spanned::new("1s".into())),
+            ..RawTaskDefinition::default()
+        });
+
+        let task_definition: TaskDefinition = task_definition.try_into().unwrap();
+        assert_eq!(task_definition.retries, 0);
+        assert_eq!(task_definition.retry_delay, Some(Duration::from_secs(1)));
+        assert_eq!(task_definition.timeout, Some(Duration::from_secs(60)));
+    }
+
+    #[test_case(r#"{ "resources": { "memory": 4 } }"#, None ; "within capacity")]
+    #[test_case(r#"{ "resources": { "memory": 8, "gpu-lock": 1 } }"#, None ; "whole pools")]
+    #[test_case(r#"{}"#, None ; "no resources")]
+    #[test_case(
+        r#"{ "resources": { "disk": 1 } }"#,
+        Some("Resource \"disk\" is not declared in the root turbo.json `resources`") ;
+        "unknown resource"
+    )]
+    #[test_case(
+        r#"{ "resources": { "memory": 9 } }"#,
+        Some("Task needs 9 of resource \"memory\" but the pool only has a capacity of 8") ;
+        "exceeds capacity"
+    )]
+    fn test_validate_resources(task_definition_content: &str, expected_error: Option<&str>) {
+        let pools = BTreeMap::from([("gpu-lock".to_string(), 1), ("memory".to_string(), 8)]);
+        let raw_task_definition: RawTaskDefinition = deserialize_from_json_str(
+            task_definition_content,
+            JsonParserOptions::default().with_allow_comments(),
+        )
+        .into_deserialized()
+        .unwrap();
+
+        let error = raw_task_definition
+            .validate_resources(&pools)
+            .err()
+            .map(|e| e.to_string());
+        assert_eq!(error.as_deref(), expected_error);
+    }
+
     #[test_case("[]", TaskOutputs::default() ; "empty")]
     #[test_case(r#"["target/**"]"#, TaskOutputs { inclusions: vec!["target/**".to_string()], exclusions: vec![] })]
     #[test_case(
@@ -20,7 +20,10 @@ use thiserror::Error;
 use turbopath::AnchoredSystemPath;
 use turborepo_errors::WithMetadata;
 
-use super::RawRemoteCacheOptions;
+use super::{
+    RawBoundaries, RawLocalCacheOptions, RawOtelOptions, RawPermissions, RawRemoteCacheOptions,
+    RawTagRules,
+};
 use crate::{
     cli::OutputLogsMode,
     config::ConfigurationOptions,
@@ -213,6 +216,19 @@ impl DeserializationVisitor for RawTaskDefinitionVisitor {
                         result.cache = Spanned::new(Some(cache)).with_range(range);
                     }
                 }
+                "command" => {
+                    if let Some(command) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.command = Some(Spanned::new(command).with_range(range));
+                    }
+                }
+                "cwd" => {
+                    if let Some(cwd) = UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.cwd = Some(Spanned::new(cwd).with_range(range));
+                    }
+                }
                 "dependsOn" => {
                     if let Some(depends_on) = Vec::deserialize(&value, &key_text, diagnostics) {
                         result.depends_on = Some(Spanned::new(depends_on).with_range(range));
@@ -228,6 +244,13 @@ impl DeserializationVisitor for RawTaskDefinitionVisitor {
                         result.env = Some(env);
                     }
                 }
+                "injectDotEnv" => {
+                    if let Some(inject_dot_env) = bool::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.inject_dot_env =
+                            Some(Spanned::new(inject_dot_env).with_range(range));
+                    }
+                }
                 "inputs" => {
                     if let Some(inputs) = Vec::deserialize(&value, &key_text, diagnostics) {
                         result.inputs = Some(inputs);
@@ -249,6 +272,11 @@ impl DeserializationVisitor for RawTaskDefinitionVisitor {
                         result.outputs = Some(outputs);
                     }
                 }
+                "shell" => {
+                    if let Some(shell) = bool::deserialize(&value, &key_text, diagnostics) {
+                        result.shell = Some(Spanned::new(shell).with_range(range));
+                    }
+                }
                 "outputMode" => {
                     if let Some(output_mode) =
                         OutputLogsMode::deserialize(&value, &key_text, diagnostics)
@@ -256,6 +284,30 @@ impl DeserializationVisitor for RawTaskDefinitionVisitor {
                         result.output_mode = Some(Spanned::new(output_mode).with_range(range));
                     }
                 }
+                "retries" => {
+                    if let Some(retries) = u32::deserialize(&value, &key_text, diagnostics) {
+                        result.retries = Some(Spanned::new(retries).with_range(range));
+                    }
+                }
+                "retryDelay" => {
+                    if let Some(retry_delay) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.retry_delay = Some(Spanned::new(retry_delay).with_range(range));
+                    }
+                }
+                "timeout" => {
+                    if let Some(timeout) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.timeout = Some(Spanned::new(timeout).with_range(range));
+                    }
+                }
+                "resources" => {
+                    if let Some(resources) = BTreeMap::deserialize(&value, &key_text, diagnostics) {
+                        result.resources = Some(Spanned::new(resources).with_range(range));
+                    }
+                }
                 unknown_key => {
                     diagnostics.push(create_unknown_key_diagnostic_from_struct(
                         &result,
@@ -404,6 +456,160 @@ impl DeserializationVisitor for RawRemoteCacheOptionsVisitor {
                         result.enabled = Some(enabled);
                     }
                 }
+                "backend" => {
+                    if let Some(backend) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.backend = Some(backend.into());
+                    }
+                }
+                "s3Bucket" => {
+                    if let Some(s3_bucket) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.s3_bucket = Some(s3_bucket.into());
+                    }
+                }
+                "s3Region" => {
+                    if let Some(s3_region) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.s3_region = Some(s3_region.into());
+                    }
+                }
+                "s3Endpoint" => {
+                    if let Some(s3_endpoint) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.s3_endpoint = Some(s3_endpoint.into());
+                    }
+                }
+                "directory" => {
+                    if let Some(directory) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.directory = Some(directory.into());
+                    }
+                }
+                unknown_key => diagnostics.push(create_unknown_key_diagnostic_from_struct(
+                    &result,
+                    unknown_key,
+                    key.range(),
+                )),
+            }
+        }
+        Some(result)
+    }
+}
+
+impl Deserializable for RawLocalCacheOptions {
+    fn deserialize(
+        value: &impl DeserializableValue,
+        name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self> {
+        value.deserialize(RawLocalCacheOptionsVisitor, name, diagnostics)
+    }
+}
+
+struct RawLocalCacheOptionsVisitor;
+
+impl DeserializationVisitor for RawLocalCacheOptionsVisitor {
+    type Output = RawLocalCacheOptions;
+
+    const EXPECTED_TYPE: VisitableType = VisitableType::MAP;
+
+    fn visit_map(
+        self,
+        // Iterator of key-value pairs.
+        members: impl Iterator<Item = Option<(impl DeserializableValue, impl DeserializableValue)>>,
+        // range of the map in the source text.
+        _: TextRange,
+        _name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self::Output> {
+        let mut result = RawLocalCacheOptions::default();
+        for (key, value) in members.flatten() {
+            let Some(key_text) = Text::deserialize(&key, "", diagnostics) else {
+                continue;
+            };
+            match key_text.text() {
+                "maxSize" => {
+                    if let Some(max_size) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.max_size = Some(max_size);
+                    }
+                }
+                "maxAge" => {
+                    if let Some(max_age) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.max_age = Some(max_age);
+                    }
+                }
+                unknown_key => diagnostics.push(create_unknown_key_diagnostic_from_struct(
+                    &result,
+                    unknown_key,
+                    key.range(),
+                )),
+            }
+        }
+        Some(result)
+    }
+}
+
+impl Deserializable for RawOtelOptions {
+    fn deserialize(
+        value: &impl DeserializableValue,
+        name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self> {
+        value.deserialize(RawOtelOptionsVisitor, name, diagnostics)
+    }
+}
+
+struct RawOtelOptionsVisitor;
+
+impl DeserializationVisitor for RawOtelOptionsVisitor {
+    type Output = RawOtelOptions;
+
+    const EXPECTED_TYPE: VisitableType = VisitableType::MAP;
+
+    fn visit_map(
+        self,
+        // Iterator of key-value pairs.
+        members: impl Iterator<Item = Option<(impl DeserializableValue, impl DeserializableValue)>>,
+        // range of the map in the source text.
+        _: TextRange,
+        _name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self::Output> {
+        let mut result = RawOtelOptions::default();
+        for (key, value) in members.flatten() {
+            let Some(key_text) = Text::deserialize(&key, "", diagnostics) else {
+                continue;
+            };
+            match key_text.text() {
+                "endpoint" => {
+                    if let Some(endpoint) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.endpoint = Some(endpoint);
+                    }
+                }
+                "protocol" => {
+                    if let Some(protocol) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.protocol = Some(protocol);
+                    }
+                }
+                "headers" => {
+                    if let Some(headers) = BTreeMap::deserialize(&value, &key_text, diagnostics) {
+                        result.headers = Some(headers);
+                    }
+                }
                 unknown_key => diagnostics.push(create_unknown_key_diagnostic_from_struct(
                     &result,
                     unknown_key,
@@ -495,6 +701,182 @@ impl DeserializationVisitor for ConfigurationOptionsVisitor {
                         result.enabled = Some(enabled);
                     }
                 }
+                "cacheMaxSize" => {
+                    if let Some(cache_max_size) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.cache_max_size = Some(cache_max_size.into());
+                    }
+                }
+                "cacheMaxAge" => {
+                    if let Some(cache_max_age) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.cache_max_age = Some(cache_max_age.into());
+                    }
+                }
+                "remoteCacheBackend" => {
+                    if let Some(remote_cache_backend) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.remote_cache_backend = Some(remote_cache_backend.into());
+                    }
+                }
+                "s3Bucket" => {
+                    if let Some(s3_bucket) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.s3_bucket = Some(s3_bucket.into());
+                    }
+                }
+                "s3Region" => {
+                    if let Some(s3_region) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.s3_region = Some(s3_region.into());
+                    }
+                }
+                "s3Endpoint" => {
+                    if let Some(s3_endpoint) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.s3_endpoint = Some(s3_endpoint.into());
+                    }
+                }
+                "remoteCacheDir" => {
+                    if let Some(remote_cache_dir) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.remote_cache_dir = Some(remote_cache_dir.into());
+                    }
+                }
+                "otelEndpoint" => {
+                    if let Some(otel_endpoint) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.otel_endpoint = Some(otel_endpoint.into());
+                    }
+                }
+                "otelProtocol" => {
+                    if let Some(otel_protocol) =
+                        UnescapedString::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.otel_protocol = Some(otel_protocol.into());
+                    }
+                }
+                "otelHeaders" => {
+                    if let Some(otel_headers) =
+                        BTreeMap::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.otel_headers = Some(otel_headers);
+                    }
+                }
+                unknown_key => diagnostics.push(create_unknown_key_diagnostic_from_struct(
+                    &result,
+                    unknown_key,
+                    key.range(),
+                )),
+            }
+        }
+
+        Some(result)
+    }
+}
+
+impl Deserializable for RawBoundaries {
+    fn deserialize(
+        value: &impl DeserializableValue,
+        name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self> {
+        value.deserialize(RawBoundariesVisitor, name, diagnostics)
+    }
+}
+
+struct RawBoundariesVisitor;
+
+impl DeserializationVisitor for RawBoundariesVisitor {
+    type Output = RawBoundaries;
+
+    const EXPECTED_TYPE: VisitableType = VisitableType::MAP;
+
+    fn visit_map(
+        self,
+        // Iterator of key-value pairs.
+        members: impl Iterator<Item = Option<(impl DeserializableValue, impl DeserializableValue)>>,
+        // range of the map in the source text.
+        _: TextRange,
+        _name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self::Output> {
+        let mut result = RawBoundaries::default();
+        for (key, value) in members.flatten() {
+            let Some(key_text) = Text::deserialize(&key, "", diagnostics) else {
+                continue;
+            };
+            match key_text.text() {
+                "tags" => {
+                    if let Some(tags) = BTreeMap::deserialize(&value, &key_text, diagnostics) {
+                        result.tags = Some(tags);
+                    }
+                }
+                unknown_key => diagnostics.push(create_unknown_key_diagnostic_from_struct(
+                    &result,
+                    unknown_key,
+                    key.range(),
+                )),
+            }
+        }
+        Some(result)
+    }
+}
+
+impl Deserializable for RawTagRules {
+    fn deserialize(
+        value: &impl DeserializableValue,
+        name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self> {
+        value.deserialize(RawTagRulesVisitor, name, diagnostics)
+    }
+}
+
+struct RawTagRulesVisitor;
+
+impl DeserializationVisitor for RawTagRulesVisitor {
+    type Output = RawTagRules;
+
+    const EXPECTED_TYPE: VisitableType = VisitableType::MAP;
+
+    fn visit_map(
+        self,
+        // Iterator of key-value pairs.
+        members: impl Iterator<Item = Option<(impl DeserializableValue, impl DeserializableValue)>>,
+        // range of the map in the source text.
+        _: TextRange,
+        _name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self::Output> {
+        let mut result = RawTagRules::default();
+        for (key, value) in members.flatten() {
+            let Some(key_text) = Text::deserialize(&key, "", diagnostics) else {
+                continue;
+            };
+            match key_text.text() {
+                "dependencies" => {
+                    if let Some(dependencies) =
+                        RawPermissions::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.dependencies = Some(dependencies);
+                    }
+                }
+                "dependents" => {
+                    if let Some(dependents) =
+                        RawPermissions::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.dependents = Some(dependents);
+                    }
+                }
                 unknown_key => diagnostics.push(create_unknown_key_diagnostic_from_struct(
                     &result,
                     unknown_key,
@@ -502,7 +884,59 @@ impl DeserializationVisitor for ConfigurationOptionsVisitor {
                 )),
             }
         }
+        Some(result)
+    }
+}
+
+impl Deserializable for RawPermissions {
+    fn deserialize(
+        value: &impl DeserializableValue,
+        name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self> {
+        value.deserialize(RawPermissionsVisitor, name, diagnostics)
+    }
+}
+
+struct RawPermissionsVisitor;
+
+impl DeserializationVisitor for RawPermissionsVisitor {
+    type Output = RawPermissions;
 
+    const EXPECTED_TYPE: VisitableType = VisitableType::MAP;
+
+    fn visit_map(
+        self,
+        // Iterator of key-value pairs.
+        members: impl Iterator<Item = Option<(impl DeserializableValue, impl DeserializableValue)>>,
+        // range of the map in the source text.
+        _: TextRange,
+        _name: &str,
+        diagnostics: &mut Vec<DeserializationDiagnostic>,
+    ) -> Option<Self::Output> {
+        let mut result = RawPermissions::default();
+        for (key, value) in members.flatten() {
+            let Some(key_text) = Text::deserialize(&key, "", diagnostics) else {
+                continue;
+            };
+            match key_text.text() {
+                "allow" => {
+                    if let Some(allow) = Vec::deserialize(&value, &key_text, diagnostics) {
+                        result.allow = Some(allow);
+                    }
+                }
+                "deny" => {
+                    if let Some(deny) = Vec::deserialize(&value, &key_text, diagnostics) {
+                        result.deny = Some(deny);
+                    }
+                }
+                unknown_key => diagnostics.push(create_unknown_key_diagnostic_from_struct(
+                    &result,
+                    unknown_key,
+                    key.range(),
+                )),
+            }
+        }
         Some(result)
     }
 }
@@ -595,6 +1029,43 @@ impl DeserializationVisitor for RawTurboJsonVisitor {
                         result.remote_cache = Some(remote_cache);
                     }
                 }
+                "localCache" => {
+                    if let Some(local_cache) =
+                        RawLocalCacheOptions::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.local_cache = Some(local_cache);
+                    }
+                }
+                "resources" => {
+                    if let Some(resources) = BTreeMap::deserialize(&value, &key_text, diagnostics) {
+                        result.resources = Some(resources);
+                    }
+                }
+                "otel" => {
+                    if let Some(otel) = RawOtelOptions::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.otel = Some(otel);
+                    }
+                }
+                "packageDiscovery" => {
+                    if let Some(package_discovery) =
+                        Vec::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.package_discovery = Some(package_discovery);
+                    }
+                }
+                "tags" => {
+                    if let Some(tags) = Vec::deserialize(&value, &key_text, diagnostics) {
+                        result.tags = Some(tags);
+                    }
+                }
+                "boundaries" => {
+                    if let Some(boundaries) =
+                        RawBoundaries::deserialize(&value, &key_text, diagnostics)
+                    {
+                        result.boundaries = Some(boundaries);
+                    }
+                }
                 unknown_key => {
                     diagnostics.push(create_unknown_key_diagnostic_from_struct(
                         &result,
@@ -653,7 +1124,11 @@ impl WithMetadata for RawTaskDefinition {
         self.pass_through_env.add_text(text.clone());
         self.persistent.add_text(text.clone());
         self.outputs.add_text(text.clone());
-        self.output_mode.add_text(text);
+        self.output_mode.add_text(text.clone());
+        self.retries.add_text(text.clone());
+        self.retry_delay.add_text(text.clone());
+        self.timeout.add_text(text.clone());
+        self.resources.add_text(text);
     }
 
     fn add_path(&mut self, path: Arc<str>) {
@@ -664,7 +1139,11 @@ impl WithMetadata for RawTaskDefinition {
         self.pass_through_env.add_path(path.clone());
         self.persistent.add_path(path.clone());
         self.outputs.add_path(path.clone());
-        self.output_mode.add_path(path);
+        self.output_mode.add_path(path.clone());
+        self.retries.add_path(path.clone());
+        self.retry_delay.add_path(path.clone());
+        self.timeout.add_path(path.clone());
+        self.resources.add_path(path);
     }
 }
 
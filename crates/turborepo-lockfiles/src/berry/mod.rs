@@ -382,6 +382,53 @@ impl BerryLockfile {
         })
     }
 
+    /// Produces a copy of the lockfile where the given workspaces no longer
+    /// depend on their devDependencies, keyed by workspace path
+    fn without_dev_dependencies(
+        &self,
+        dev_dependencies: &HashMap<String, HashSet<String>>,
+    ) -> Self {
+        let mut locator_package = self.locator_package.clone();
+        for (workspace_path, names) in dev_dependencies {
+            let workspace_path = match workspace_path.as_str() {
+                // The root workspace is referenced as "workspace:."
+                "" => ".",
+                path => path,
+            };
+            let Some(package) = self
+                .workspace_path_to_locator
+                .get(workspace_path)
+                .and_then(|locator| locator_package.get_mut(locator))
+            else {
+                continue;
+            };
+            // Workspace entries list devDependencies alongside the others
+            if let Some(dependencies) = &mut package.dependencies {
+                dependencies.retain(|name, _| !names.contains(name));
+                if dependencies.is_empty() {
+                    package.dependencies = None;
+                }
+            }
+            if let Some(meta) = &mut package.dependencies_meta {
+                meta.retain(|name, _| !names.contains(name));
+                if meta.is_empty() {
+                    package.dependencies_meta = None;
+                }
+            }
+        }
+
+        Self {
+            data: self.data.clone(),
+            resolutions: self.resolutions.clone(),
+            resolver: self.resolver.clone(),
+            locator_package,
+            patches: self.patches.clone(),
+            extensions: self.extensions.clone(),
+            overrides: self.overrides.clone(),
+            workspace_path_to_locator: self.workspace_path_to_locator.clone(),
+        }
+    }
+
     fn resolve_dependency(
         &self,
         locator: &Locator,
@@ -486,6 +533,44 @@ impl Lockfile for BerryLockfile {
         Ok(Some(map))
     }
 
+    fn resolved_package(&self, key: &str) -> Result<Option<crate::ResolvedPackage>, crate::Error> {
+        let locator = Locator::try_from(key).map_err(Error::from)?;
+        if locator.reference.starts_with("workspace:") {
+            return Ok(None);
+        }
+        let Some(package) = self.locator_package.get(&locator) else {
+            return Ok(None);
+        };
+
+        let mut dependencies = Map::new();
+        let mut optional_dependencies = Map::new();
+        for (name, range) in package.dependencies.iter().flatten() {
+            // Other formats leave out the protocol for registry dependencies
+            let range = range.strip_prefix("npm:").unwrap_or(range).to_string();
+            let optional = package
+                .dependencies_meta
+                .as_ref()
+                .and_then(|meta| meta.get(name))
+                .and_then(|meta| meta.optional)
+                .unwrap_or(false);
+            match optional {
+                true => optional_dependencies.insert(name.clone(), range),
+                false => dependencies.insert(name.clone(), range),
+            };
+        }
+
+        // Berry's checksums are hashes of its own cache archives rather than of
+        // the package tarballs, so there's no integrity to carry over
+        Ok(Some(crate::ResolvedPackage {
+            name: locator.ident.to_string(),
+            version: package.version.clone(),
+            resolved: None,
+            integrity: None,
+            dependencies,
+            optional_dependencies,
+        }))
+    }
+
     fn subgraph(
         &self,
         workspace_packages: &[String],
@@ -495,6 +580,19 @@ impl Lockfile for BerryLockfile {
         Ok(Box::new(subgraph))
     }
 
+    fn production_subgraph(
+        &self,
+        workspace_packages: &[String],
+        packages: &[String],
+        dev_dependencies: &HashMap<String, HashSet<String>>,
+    ) -> Result<Box<dyn Lockfile>, crate::Error> {
+        // Dropping the devDependencies first keeps the subgraph from following them
+        let subgraph = self
+            .without_dev_dependencies(dev_dependencies)
+            .subgraph(workspace_packages, packages)?;
+        Ok(Box::new(subgraph))
+    }
+
     fn encode(&self) -> Result<Vec<u8>, crate::Error> {
         Ok(self.lockfile()?.to_string().into_bytes())
     }
@@ -936,6 +1034,33 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_production_subgraph() {
+        let data: LockfileData =
+            serde_yaml::from_str(include_str!("../../fixtures/minimal-berry.lock")).unwrap();
+        let lockfile = BerryLockfile::new(data, None).unwrap();
+        let dev_dependencies = HashMap::from([(
+            "packages/b".to_string(),
+            HashSet::from(["lodash".to_string()]),
+        )]);
+
+        let subgraph = lockfile
+            .without_dev_dependencies(&dev_dependencies)
+            .subgraph(&["packages/b".into(), "packages/c".into()], &[])
+            .unwrap();
+        let subgraph_data = subgraph.lockfile().unwrap();
+
+        let b = &subgraph_data.packages["b@workspace:packages/b"];
+        assert_eq!(
+            b.dependencies,
+            Some(Map::from([("c".to_string(), "*".to_string())]))
+        );
+        assert!(!subgraph_data
+            .packages
+            .keys()
+            .any(|key| key.starts_with("lodash@")));
+    }
+
     #[test]
     fn test_builtin_patch_descriptors() {
         let data =
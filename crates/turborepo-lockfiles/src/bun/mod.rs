@@ -1,13 +1,18 @@
-use std::{any::Any, str::FromStr};
+use std::{any::Any, fmt, str::FromStr};
 
-use serde::Deserialize;
-
-use crate::Lockfile;
+use crate::{
+    yarn1::{self, Entry},
+    Lockfile,
+};
 
 mod de;
 
 type Map<K, V> = std::collections::BTreeMap<K, V>;
 
+// Bun records a hash of the binary lockfile in the header of its yarn-style
+// output
+const HASH_PREFIX: &str = "# bun ./bun.lockb --hash: ";
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("unable to parse: {0}")]
@@ -16,26 +21,12 @@ pub enum Error {
     SymlStructure(#[from] serde_json::Error),
     #[error("unexpected non-utf8 yarn.lock")]
     NonUTF8(#[from] std::str::Utf8Error),
-    #[error("Turborepo cannot serialize Bun lockfiles.")]
-    NotImplemented(),
 }
 
 #[derive(Debug)]
 pub struct BunLockfile {
     inner: Map<String, Entry>,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct Entry {
-    name: Option<String>,
-    version: String,
-    uid: Option<String>,
-    resolved: Option<String>,
-    integrity: Option<String>,
-    registry: Option<String>,
-    dependencies: Option<Map<String, String>>,
-    optional_dependencies: Option<Map<String, String>>,
+    hash: Option<String>,
 }
 
 impl BunLockfile {
@@ -51,7 +42,12 @@ impl FromStr for BunLockfile {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let value = de::parse_syml(s)?;
         let inner = serde_json::from_value(value)?;
-        Ok(Self { inner })
+        let hash = s
+            .lines()
+            .take_while(|line| line.starts_with('#'))
+            .find_map(|line| line.strip_prefix(HASH_PREFIX))
+            .map(|hash| hash.to_string());
+        Ok(Self { inner, hash })
     }
 }
 
@@ -105,28 +101,51 @@ impl Lockfile for BunLockfile {
             inner.insert(key.clone(), entry.clone());
         }
 
-        Ok(Box::new(Self { inner }))
+        // The hash describes the full lockfile so it isn't valid for the subgraph
+        Ok(Box::new(Self { inner, hash: None }))
     }
 
     fn encode(&self) -> Result<Vec<u8>, crate::Error> {
-        Err(crate::Error::Bun(Error::NotImplemented()))
+        Ok(self.to_string().into_bytes())
+    }
+
+    fn resolved_package(&self, key: &str) -> Result<Option<crate::ResolvedPackage>, crate::Error> {
+        let Some(entry) = self.inner.get(key) else {
+            return Ok(None);
+        };
+        let name = match &entry.name {
+            Some(name) => name.as_str(),
+            None => yarn1::package_name(key),
+        };
+        Ok(Some(crate::ResolvedPackage {
+            name: name.to_string(),
+            version: entry.version.clone(),
+            resolved: entry.resolved.clone(),
+            integrity: entry.integrity.clone(),
+            dependencies: entry.dependencies.clone().unwrap_or_default(),
+            optional_dependencies: entry.optional_dependencies.clone().unwrap_or_default(),
+        }))
     }
 
     fn global_change(&self, other: &dyn Lockfile) -> bool {
         let any_other = other as &dyn Any;
-        // Downcast returns none if the concrete type doesn't match
-        // if the types don't match then we changed package managers
-        any_other.downcast_ref::<Self>().is_none()
+        // The text form doesn't carry any settings of its own, the only global
+        // state is the hash bun records for the binary lockfile
+        if let Some(other) = any_other.downcast_ref::<Self>() {
+            self.hash != other.hash
+        } else {
+            true
+        }
     }
 }
 
-impl Entry {
-    fn dependency_entries(&self) -> impl Iterator<Item = (String, String)> + '_ {
-        self.dependencies
-            .iter()
-            .flatten()
-            .chain(self.optional_dependencies.iter().flatten())
-            .map(|(k, v)| (k.clone(), v.clone()))
+impl fmt::Display for BunLockfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut header = yarn1::HEADER.to_string();
+        if let Some(hash) = &self.hash {
+            header.push_str(&format!("{HASH_PREFIX}{hash}\n"));
+        }
+        yarn1::write_lockfile(f, &header, &self.inner)
     }
 }
 
@@ -141,9 +160,125 @@ fn possible_keys<'a>(name: &'a str, version: &'a str) -> impl Iterator<Item = St
 
 #[cfg(test)]
 mod test {
+    use pretty_assertions::assert_eq;
+    use test_case::test_case;
+
     use super::*;
+
+    const BUN: &str = include_str!("../../fixtures/bun.lock");
     const FULL: &str = include_str!("../../fixtures/yarn1full.lock");
 
+    #[test_case(BUN ; "bun lockfile")]
+    #[test_case(FULL ; "lockfile without hash")]
+    fn test_roundtrip(input: &str) {
+        let lockfile = BunLockfile::from_str(input).unwrap();
+        assert_eq!(input, lockfile.to_string());
+    }
+
+    #[test]
+    fn test_registry_roundtrip() {
+        let input = r#"# THIS IS AN AUTOGENERATED FILE. DO NOT EDIT THIS FILE DIRECTLY.
+# yarn lockfile v1
+
+
+is-odd@^3.0.1:
+  version "3.0.1"
+  resolved "https://registry.npmjs.org/is-odd/-/is-odd-3.0.1.tgz"
+  integrity sha512-CQpnWPrDwmP1+SMHXZhtLtJv90yiyVfluGsX5iNCVkrhQtU3TQHsUWPG9wkdk9Lgd5yNpAg9jQEo90CBaXgWMA==
+  registry "https://registry.npmjs.org/"
+"#;
+        let lockfile = BunLockfile::from_str(input).unwrap();
+        assert_eq!(input, lockfile.to_string());
+    }
+
+    #[test]
+    fn test_hash() {
+        let lockfile = BunLockfile::from_str(BUN).unwrap();
+        assert_eq!(
+            lockfile.hash.as_deref(),
+            Some("6F8B2D5E9A4C1B37-4d1e8f0a6b2c9e13-A3C7E51F0B9D2864-7e2f5c8a1d4b9063")
+        );
+        let lockfile = BunLockfile::from_str(FULL).unwrap();
+        assert_eq!(lockfile.hash, None);
+    }
+
+    #[test]
+    fn test_subgraph() {
+        let lockfile = BunLockfile::from_str(BUN).unwrap();
+        let subgraph = lockfile
+            .subgraph(
+                &["packages/ui".into()],
+                &[
+                    "chalk@^2.4.2".into(),
+                    "ansi-styles@^3.2.1".into(),
+                    "turbo@^1.9.3".into(),
+                    "not-in-lockfile@1.0.0".into(),
+                ],
+            )
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(subgraph.encode().unwrap()).unwrap(),
+            r#"# THIS IS AN AUTOGENERATED FILE. DO NOT EDIT THIS FILE DIRECTLY.
+# yarn lockfile v1
+
+
+ansi-styles@^3.2.1:
+  version "3.2.1"
+  resolved "https://registry.npmjs.org/ansi-styles/-/ansi-styles-3.2.1.tgz"
+  integrity sha512-VT0ZI6kZRdTh8YyJw3SMbYm/u+NqfsAxEpWO0Pf9sq8/e94WxxOpPKx9FR1FlyCtOVDNOQ+8ntlqFxiRc+r5qA==
+  dependencies:
+    color-convert "^1.9.0"
+
+chalk@^2.4.2:
+  version "2.4.2"
+  resolved "https://registry.npmjs.org/chalk/-/chalk-2.4.2.tgz"
+  integrity sha512-Mti+f9lpJNcwF4tWV8/OrTTtF1gZi+f8FqlyAdouralcFWFQWF2+NgCHShjkCb+IFBLq9buZwE1xckQU4peSuQ==
+  dependencies:
+    ansi-styles "^3.2.1"
+    escape-string-regexp "^1.0.5"
+    supports-color "^5.3.0"
+
+turbo@^1.9.3:
+  version "1.9.3"
+  resolved "https://registry.npmjs.org/turbo/-/turbo-1.9.3.tgz"
+  integrity sha512-ID7mxmaLUPKG/hVkp+h0VuucB1U99RPCJD9cEuSEOdIPoSIuomcIClEJtKamUsdPLhLCud+BvapBNnhgh58Nzw==
+  optionalDependencies:
+    turbo-darwin-64 "1.9.3"
+    turbo-darwin-arm64 "1.9.3"
+    turbo-linux-64 "1.9.3"
+    turbo-linux-arm64 "1.9.3"
+    turbo-windows-64 "1.9.3"
+    turbo-windows-arm64 "1.9.3"
+"#
+        );
+    }
+
+    #[test]
+    fn test_subgraph_roundtrip() {
+        let lockfile = BunLockfile::from_str(BUN).unwrap();
+        let keys = lockfile.inner.keys().cloned().collect::<Vec<_>>();
+        let subgraph = lockfile.subgraph(&[], &keys).unwrap();
+        let encoded = String::from_utf8(subgraph.encode().unwrap()).unwrap();
+        let reparsed = BunLockfile::from_str(&encoded).unwrap();
+        assert_eq!(reparsed.inner, lockfile.inner);
+    }
+
+    #[test]
+    fn test_global_change() {
+        let lockfile = BunLockfile::from_str(BUN).unwrap();
+        assert!(!lockfile.global_change(&BunLockfile::from_str(BUN).unwrap()));
+        let rehashed = BunLockfile::from_str(&BUN.replace(
+            "6F8B2D5E9A4C1B37-4d1e8f0a6b2c9e13",
+            "0000000000000000-4d1e8f0a6b2c9e13",
+        ))
+        .unwrap();
+        assert!(lockfile.global_change(&rehashed));
+        let unhashed = BunLockfile::from_str(FULL).unwrap();
+        assert!(lockfile.global_change(&unhashed));
+        let yarn = crate::Yarn1Lockfile::from_str(FULL).unwrap();
+        assert!(lockfile.global_change(&yarn));
+    }
+
     #[test]
     fn test_key_splitting() {
         let lockfile = BunLockfile::from_str(FULL).unwrap();
@@ -0,0 +1,357 @@
+//! Translating the packages one lockfile resolves into another lockfile
+//! format, so that switching package managers keeps the exact versions and
+//! integrity hashes instead of resolving everything again.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{Error, Lockfile};
+
+/// A package's lockfile entry in a form every lockfile format can be written
+/// from
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ResolvedPackage {
+    pub name: String,
+    pub version: String,
+    /// Where the package was fetched from, usually a registry tarball
+    pub resolved: Option<String>,
+    pub integrity: Option<String>,
+    /// The ranges the package depends on, keyed by name. Formats that only
+    /// record the versions dependencies resolved to use those instead.
+    pub dependencies: BTreeMap<String, String>,
+    pub optional_dependencies: BTreeMap<String, String>,
+}
+
+/// The parts of a workspace's package.json that lockfiles record
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct WorkspaceManifest {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub dependencies: BTreeMap<String, String>,
+    pub dev_dependencies: BTreeMap<String, String>,
+    pub optional_dependencies: BTreeMap<String, String>,
+    /// Names of the dependencies that are other workspaces
+    pub workspace_dependencies: BTreeSet<String>,
+}
+
+impl WorkspaceManifest {
+    /// All dependencies that aren't other workspaces
+    pub fn external_dependencies(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.all_dependencies()
+            .filter(|(name, _)| !self.workspace_dependencies.contains(*name))
+    }
+
+    fn all_dependencies(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.dependencies
+            .iter()
+            .chain(&self.dev_dependencies)
+            .chain(&self.optional_dependencies)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct GraphPackage {
+    pub(crate) package: ResolvedPackage,
+    /// The package each dependency resolved to, keyed by dependency name
+    pub(crate) dependencies: BTreeMap<String, String>,
+}
+
+/// The packages a lockfile resolves each workspace's dependencies to. Packages
+/// are identified by `name@version`, so a package that some lockfile formats
+/// install in several places appears once.
+#[derive(Debug, Default)]
+pub struct ResolvedGraph {
+    /// Keyed by the workspace's unix path relative to the repository root,
+    /// which is empty for the root workspace
+    pub(crate) workspaces: BTreeMap<String, WorkspaceManifest>,
+    /// The package each external dependency of a workspace resolved to
+    pub(crate) workspace_dependencies: BTreeMap<String, BTreeMap<String, String>>,
+    pub(crate) packages: BTreeMap<String, GraphPackage>,
+}
+
+impl ResolvedGraph {
+    pub fn new<L: Lockfile + ?Sized>(
+        lockfile: &L,
+        workspaces: BTreeMap<String, WorkspaceManifest>,
+    ) -> Result<Self, Error> {
+        let mut graph = Self::default();
+        for (workspace_path, manifest) in &workspaces {
+            let mut resolved = BTreeMap::new();
+            for (name, range) in manifest.external_dependencies() {
+                if let Some(id) = graph.add_package(lockfile, workspace_path, name, range)? {
+                    resolved.insert(name.clone(), id);
+                }
+            }
+            graph
+                .workspace_dependencies
+                .insert(workspace_path.clone(), resolved);
+        }
+        graph.workspaces = workspaces;
+        Ok(graph)
+    }
+
+    /// The package and its dependencies with `name@version`
+    pub fn package(&self, id: &str) -> Option<(&ResolvedPackage, &BTreeMap<String, String>)> {
+        self.packages
+            .get(id)
+            .map(|package| (&package.package, &package.dependencies))
+    }
+
+    pub fn packages(&self) -> impl Iterator<Item = &ResolvedPackage> {
+        self.packages.values().map(|package| &package.package)
+    }
+
+    /// The path of the workspace with `name`
+    pub(crate) fn workspace_path(&self, name: &str) -> Option<&str> {
+        self.workspaces
+            .iter()
+            .find(|(_, manifest)| manifest.name.as_deref() == Some(name))
+            .map(|(path, _)| path.as_str())
+    }
+
+    /// Adds the package `name` resolves to along with its dependencies and
+    /// returns its id, or `None` if the lockfile doesn't contain it, e.g. an
+    /// optional dependency for another platform
+    fn add_package<L: Lockfile + ?Sized>(
+        &mut self,
+        lockfile: &L,
+        workspace_path: &str,
+        name: &str,
+        range: &str,
+    ) -> Result<Option<String>, Error> {
+        let Some(package) = lockfile.resolve_package(workspace_path, name, range)? else {
+            return Ok(None);
+        };
+        let resolved = lockfile
+            .resolved_package(&package.key)?
+            .ok_or_else(|| Error::MissingPackage(package.key.clone()))?;
+        let id = format!("{}@{}", resolved.name, resolved.version);
+        if self.packages.contains_key(&id) {
+            return Ok(Some(id));
+        }
+        // Insert the package before visiting its dependencies so cycles end here
+        self.packages.insert(
+            id.clone(),
+            GraphPackage {
+                package: resolved,
+                dependencies: BTreeMap::new(),
+            },
+        );
+
+        let mut dependencies = BTreeMap::new();
+        for (dependency, range) in lockfile.all_dependencies(&package.key)?.unwrap_or_default() {
+            if let Some(dependency_id) =
+                self.add_package(lockfile, workspace_path, &dependency, &range)?
+            {
+                let name = self.packages[&dependency_id].package.name.clone();
+                dependencies.insert(name, dependency_id);
+            }
+        }
+        if let Some(package) = self.packages.get_mut(&id) {
+            package.dependencies = dependencies;
+        }
+
+        Ok(Some(id))
+    }
+}
+
+/// The path to `to` relative to `from`, where both are unix paths relative to
+/// the same directory
+pub(crate) fn relative_path(from: &str, to: &str) -> String {
+    let from = from.split('/').filter(|segment| !segment.is_empty());
+    let to = to.split('/').filter(|segment| !segment.is_empty());
+    let mut from = from.peekable();
+    let mut to = to.peekable();
+    while let (Some(a), Some(b)) = (from.peek(), to.peek()) {
+        if a != b {
+            break;
+        }
+        from.next();
+        to.next();
+    }
+    let segments = from.map(|_| "..").chain(to).collect::<Vec<_>>();
+    match segments.is_empty() {
+        true => ".".to_string(),
+        false => segments.join("/"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use test_case::test_case;
+
+    use super::*;
+    use crate::{all_transitive_closures_by_name, NpmLockfile, PnpmLockfile, Yarn1Lockfile};
+
+    // The workspaces recorded in an npm lockfile, named after their directory
+    fn npm_workspaces(contents: &[u8]) -> BTreeMap<String, WorkspaceManifest> {
+        let lockfile: serde_json::Value = serde_json::from_slice(contents).unwrap();
+        let packages = lockfile["packages"].as_object().unwrap();
+        let workspace_paths = packages
+            .keys()
+            .filter(|key| !key.contains("node_modules"))
+            .collect::<Vec<_>>();
+        let names = workspace_paths
+            .iter()
+            .filter_map(|path| path.rsplit('/').next())
+            .filter(|name| !name.is_empty())
+            .map(|name| name.to_string())
+            .collect::<BTreeSet<_>>();
+        let ranges = |entry: &serde_json::Value, field: &str| -> BTreeMap<String, String> {
+            serde_json::from_value(entry[field].clone()).unwrap_or_default()
+        };
+
+        workspace_paths
+            .into_iter()
+            .map(|path| {
+                let entry = &packages[path];
+                let mut manifest = WorkspaceManifest {
+                    name: path.rsplit('/').next().map(|name| name.to_string()),
+                    version: entry["version"].as_str().map(|version| version.to_string()),
+                    dependencies: ranges(entry, "dependencies"),
+                    dev_dependencies: ranges(entry, "devDependencies"),
+                    optional_dependencies: ranges(entry, "optionalDependencies"),
+                    workspace_dependencies: BTreeSet::new(),
+                };
+                manifest.workspace_dependencies = manifest
+                    .all_dependencies()
+                    .filter(|(name, _)| names.contains(*name))
+                    .map(|(name, _)| name.clone())
+                    .collect();
+                (path.clone(), manifest)
+            })
+            .collect()
+    }
+
+    // The workspaces recorded in a v6 pnpm lockfile, named after their
+    // directory
+    fn pnpm_workspaces(contents: &[u8]) -> BTreeMap<String, WorkspaceManifest> {
+        let lockfile: serde_yaml::Value = serde_yaml::from_slice(contents).unwrap();
+        let importers = lockfile["importers"].as_mapping().unwrap();
+        importers
+            .iter()
+            .map(|(path, importer)| {
+                let path = match path.as_str().unwrap() {
+                    "." => "",
+                    path => path,
+                };
+                let mut manifest = WorkspaceManifest {
+                    name: path.rsplit('/').next().map(|name| name.to_string()),
+                    ..Default::default()
+                };
+                for (field, dependencies) in [
+                    ("dependencies", &mut manifest.dependencies),
+                    ("devDependencies", &mut manifest.dev_dependencies),
+                    ("optionalDependencies", &mut manifest.optional_dependencies),
+                ] {
+                    for (name, dependency) in importer[field].as_mapping().into_iter().flatten() {
+                        let name = name.as_str().unwrap().to_string();
+                        if dependency["version"].as_str().unwrap().starts_with("link:") {
+                            manifest.workspace_dependencies.insert(name.clone());
+                        }
+                        let specifier = dependency["specifier"].as_str().unwrap();
+                        dependencies.insert(name, specifier.to_string());
+                    }
+                }
+                (path.to_string(), manifest)
+            })
+            .collect()
+    }
+
+    fn closures<L: Lockfile + ?Sized>(
+        lockfile: &L,
+        workspaces: &BTreeMap<String, WorkspaceManifest>,
+    ) -> HashMap<String, BTreeMap<String, BTreeSet<String>>> {
+        let external = workspaces
+            .iter()
+            .map(|(path, manifest)| {
+                let dependencies = manifest
+                    .external_dependencies()
+                    .map(|(name, range)| (name.clone(), range.clone()))
+                    .collect();
+                (path.clone(), dependencies)
+            })
+            .collect();
+        let mut closures = all_transitive_closures_by_name(lockfile, external).unwrap();
+        // Patches and peer dependencies aren't carried over, so leave out the
+        // suffixes pnpm adds to versions for them
+        for closure in closures.values_mut() {
+            for versions in closure.values_mut() {
+                *versions = versions
+                    .iter()
+                    .map(|version| version.split(['(', '_']).next().unwrap().to_string())
+                    .collect();
+            }
+        }
+        closures
+    }
+
+    #[test]
+    fn test_npm_conversions_keep_versions() {
+        let contents = include_bytes!("../fixtures/npm-lock.json");
+        let lockfile = NpmLockfile::load(contents).unwrap();
+        let workspaces = npm_workspaces(contents);
+        let expected = closures(&lockfile, &workspaces);
+        assert!(expected["apps/web"].contains_key("lodash"));
+        let graph = ResolvedGraph::new(&lockfile, workspaces.clone()).unwrap();
+
+        let npm = NpmLockfile::from_graph(&graph, &["apps/*".into(), "packages/*".into()]);
+        let npm = NpmLockfile::load(&npm.encode().unwrap()).unwrap();
+        assert_eq!(closures(&npm, &workspaces), expected);
+
+        let pnpm = PnpmLockfile::from_graph(&graph);
+        let pnpm = PnpmLockfile::from_bytes(&pnpm.encode().unwrap()).unwrap();
+        assert_eq!(closures(&pnpm, &workspaces), expected);
+
+        let yarn = Yarn1Lockfile::from_graph(&graph);
+        let yarn = Yarn1Lockfile::from_bytes(&yarn.encode().unwrap()).unwrap();
+        assert_eq!(closures(&yarn, &workspaces), expected);
+    }
+
+    #[test]
+    fn test_pnpm_conversions_keep_versions() {
+        let contents = include_bytes!("../fixtures/pnpm8.yaml");
+        let lockfile = PnpmLockfile::from_bytes(contents).unwrap();
+        let workspaces = pnpm_workspaces(contents);
+        let expected = closures(&lockfile, &workspaces);
+        assert!(expected["packages/b"].contains_key("is-even"));
+        let graph = ResolvedGraph::new(&lockfile, workspaces.clone()).unwrap();
+
+        let npm = NpmLockfile::from_graph(&graph, &["packages/*".into()]);
+        let npm = NpmLockfile::load(&npm.encode().unwrap()).unwrap();
+        assert_eq!(closures(&npm, &workspaces), expected);
+
+        let yarn = Yarn1Lockfile::from_graph(&graph);
+        let yarn = Yarn1Lockfile::from_bytes(&yarn.encode().unwrap()).unwrap();
+        assert_eq!(closures(&yarn, &workspaces), expected);
+    }
+
+    #[test]
+    fn test_integrity_is_kept() {
+        let contents = include_bytes!("../fixtures/npm-lock.json");
+        let lockfile = NpmLockfile::load(contents).unwrap();
+        let graph = ResolvedGraph::new(&lockfile, npm_workspaces(contents)).unwrap();
+        let (lodash, _) = graph.package("lodash@4.17.21").unwrap();
+        assert_eq!(
+            lodash.integrity.as_deref(),
+            Some(
+                "sha512-v2kDEe57lecTulaDIuNTPy3Ry4gLGJ6Z1O3vE1krgXZNrsQ+LFTGHVxVjcXPs17LhbZVGedAJv8XZ1tvj5FvSg=="
+            )
+        );
+
+        let yarn = Yarn1Lockfile::from_graph(&graph);
+        let encoded = String::from_utf8(yarn.encode().unwrap()).unwrap();
+        assert!(encoded.contains(
+            "lodash@^4.17.21:\n  version \"4.17.21\"\n  resolved \"https://registry.npmjs.org/lodash/-/lodash-4.17.21.tgz\"\n  integrity sha512-v2kDEe57lecTulaDIuNTPy3Ry4gLGJ6Z1O3vE1krgXZNrsQ+LFTGHVxVjcXPs17LhbZVGedAJv8XZ1tvj5FvSg=="
+        ));
+    }
+
+    #[test_case("packages/a", "packages/ui", "../ui" ; "sibling")]
+    #[test_case("", "packages/ui", "packages/ui" ; "from root")]
+    #[test_case("apps/web", "", "../.." ; "to root")]
+    #[test_case("apps/web", "apps/web", "." ; "same")]
+    fn test_relative_path(from: &str, to: &str, expected: &str) {
+        assert_eq!(relative_path(from, to), expected);
+    }
+}
@@ -3,6 +3,7 @@
 
 mod berry;
 mod bun;
+mod convert;
 mod error;
 mod npm;
 mod pnpm;
@@ -10,11 +11,12 @@ mod yarn1;
 
 use std::{
     any::Any,
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
 };
 
 pub use berry::{Error as BerryError, *};
 pub use bun::BunLockfile;
+pub use convert::{ResolvedGraph, ResolvedPackage, WorkspaceManifest};
 pub use error::Error;
 pub use npm::*;
 pub use pnpm::{pnpm_global_change, pnpm_subgraph, PnpmLockfile};
@@ -51,8 +53,35 @@ pub trait Lockfile: Send + Sync + Any + std::fmt::Debug {
         packages: &[String],
     ) -> Result<Box<dyn Lockfile>, Error>;
 
+    /// Like `subgraph`, but for workspaces whose package.json has had its
+    /// devDependencies removed, so the workspace entries no longer list them.
+    /// `dev_dependencies` maps workspace paths, with `""` for the root, to
+    /// the names they only declare as devDependencies. Lockfiles without
+    /// workspace entries are pruned as usual.
+    fn production_subgraph(
+        &self,
+        workspace_packages: &[String],
+        packages: &[String],
+        _dev_dependencies: &HashMap<String, HashSet<String>>,
+    ) -> Result<Box<dyn Lockfile>, Error> {
+        self.subgraph(workspace_packages, packages)
+    }
+
     fn encode(&self) -> Result<Vec<u8>, Error>;
 
+    /// The entry for `key` in a form that can be written to another lockfile
+    /// format. Returns `None` for keys that aren't external packages, e.g.
+    /// workspaces.
+    fn resolved_package(&self, _key: &str) -> Result<Option<ResolvedPackage>, Error> {
+        Ok(None)
+    }
+
+    /// The package name of a dependency returned by `all_dependencies`, for
+    /// lockfiles that don't list dependencies by name
+    fn dependency_name<'a>(&self, dependency: &'a str) -> &'a str {
+        dependency
+    }
+
     /// All patch files referenced in the lockfile
     fn patches(&self) -> Result<Vec<RelativeUnixPathBuf>, Error> {
         Ok(Vec::new())
@@ -77,6 +106,31 @@ pub fn all_transitive_closures<L: Lockfile + ?Sized>(
         .collect()
 }
 
+/// Like `all_transitive_closures`, but groups each workspace's packages by
+/// the name they're depended on by, mapped to the versions they resolve to.
+/// Lockfile keys don't share a format, so the names can't be recovered from
+/// the `Package`s themselves.
+pub fn all_transitive_closures_by_name<L: Lockfile + ?Sized>(
+    lockfile: &L,
+    workspaces: HashMap<String, HashMap<String, String>>,
+) -> Result<HashMap<String, BTreeMap<String, BTreeSet<String>>>, Error> {
+    workspaces
+        .into_par_iter()
+        .map(|(workspace, unresolved_deps)| {
+            let mut resolved_deps = HashMap::new();
+            transitive_closure_helper(lockfile, &workspace, unresolved_deps, &mut resolved_deps)?;
+            let mut closure: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+            for (pkg, name) in resolved_deps {
+                closure
+                    .entry(lockfile.dependency_name(&name).to_string())
+                    .or_default()
+                    .insert(pkg.version);
+            }
+            Ok((workspace, closure))
+        })
+        .collect()
+}
+
 // this should get replaced by petgraph in the future :)
 #[tracing::instrument(skip_all)]
 pub fn transitive_closure<L: Lockfile + ?Sized>(
@@ -84,7 +138,7 @@ pub fn transitive_closure<L: Lockfile + ?Sized>(
     workspace_path: &str,
     unresolved_deps: HashMap<String, String>,
 ) -> Result<HashSet<Package>, Error> {
-    let mut transitive_deps = HashSet::new();
+    let mut transitive_deps = HashMap::new();
     transitive_closure_helper(
         lockfile,
         workspace_path,
@@ -92,14 +146,15 @@ pub fn transitive_closure<L: Lockfile + ?Sized>(
         &mut transitive_deps,
     )?;
 
-    Ok(transitive_deps)
+    Ok(transitive_deps.into_keys().collect())
 }
 
+// Resolved packages are recorded with the name they were first depended on by
 fn transitive_closure_helper<L: Lockfile + ?Sized>(
     lockfile: &L,
     workspace_path: &str,
     unresolved_deps: HashMap<String, impl AsRef<str>>,
-    resolved_deps: &mut HashSet<Package>,
+    resolved_deps: &mut HashMap<Package, String>,
 ) -> Result<(), Error> {
     for (name, specifier) in unresolved_deps {
         let pkg = lockfile.resolve_package(workspace_path, &name, specifier.as_ref())?;
@@ -108,12 +163,12 @@ fn transitive_closure_helper<L: Lockfile + ?Sized>(
             None => {
                 continue;
             }
-            Some(pkg) if resolved_deps.contains(&pkg) => {
+            Some(pkg) if resolved_deps.contains_key(&pkg) => {
                 continue;
             }
             Some(pkg) => {
                 let all_deps = lockfile.all_dependencies(&pkg.key)?;
-                resolved_deps.insert(pkg);
+                resolved_deps.insert(pkg, name);
                 if let Some(deps) = all_deps {
                     transitive_closure_helper(lockfile, workspace_path, deps, resolved_deps)?;
                 }
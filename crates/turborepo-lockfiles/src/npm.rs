@@ -1,9 +1,13 @@
-use std::{any::Any, collections::HashMap};
+use std::{
+    any::Any,
+    collections::{HashMap, HashSet, VecDeque},
+    iter,
+};
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use super::{Error, Lockfile, Package};
+use super::{Error, Lockfile, Package, ResolvedGraph, ResolvedPackage};
 
 type Map<K, V> = std::collections::BTreeMap<K, V>;
 
@@ -25,18 +29,20 @@ pub struct NpmLockfile {
     other: Map<String, Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 #[serde(rename_all = "camelCase")]
 struct NpmPackage {
+    #[serde(skip_serializing_if = "Option::is_none")]
     version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     resolved: Option<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
     dependencies: Map<String, String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
     dev_dependencies: Map<String, String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
     peer_dependencies: Map<String, String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
     optional_dependencies: Map<String, String>,
     // We want to reserialize any additional fields, but we don't use them
     // we keep them as raw values to avoid describing the correct schema.
@@ -107,37 +113,71 @@ impl Lockfile for NpmLockfile {
         workspace_packages: &[String],
         packages: &[String],
     ) -> Result<Box<dyn Lockfile>, Error> {
-        let mut pruned_packages = Map::new();
-        for pkg_key in packages {
-            let pkg = self.get_package(pkg_key)?;
-            pruned_packages.insert(pkg_key.to_string(), pkg.clone());
-        }
-        if let Some(root) = self.packages.get("") {
-            pruned_packages.insert("".into(), root.clone());
-        }
-        for workspace in workspace_packages {
-            let pkg = self.get_package(workspace)?;
-            pruned_packages.insert(workspace.to_string(), pkg.clone());
+        Ok(Box::new(self.subgraph(workspace_packages, packages)?))
+    }
 
-            for (key, entry) in &self.packages {
-                if entry.resolved.as_deref() == Some(workspace) {
-                    pruned_packages.insert(key.clone(), entry.clone());
-                    break;
-                }
+    // Dependencies are listed by their location in node_modules rather than
+    // their name
+    fn dependency_name<'a>(&self, dependency: &'a str) -> &'a str {
+        dependency
+            .rsplit_once("node_modules/")
+            .map_or(dependency, |(_, name)| name)
+    }
+
+    fn production_subgraph(
+        &self,
+        workspace_packages: &[String],
+        packages: &[String],
+        _dev_dependencies: &HashMap<String, HashSet<String>>,
+    ) -> Result<Box<dyn Lockfile>, Error> {
+        let mut subgraph = self.subgraph(workspace_packages, packages)?;
+        // Workspace entries mirror their package.json, which no longer has any
+        // devDependencies
+        for workspace in workspace_packages
+            .iter()
+            .map(String::as_str)
+            .chain(iter::once(""))
+        {
+            if let Some(entry) = subgraph.packages.get_mut(workspace) {
+                entry.dev_dependencies.clear();
             }
         }
-        Ok(Box::new(Self {
-            lockfile_version: 3,
-            packages: pruned_packages,
-            dependencies: Map::default(),
-            other: self.other.clone(),
-        }))
+        Ok(Box::new(subgraph))
     }
 
     fn encode(&self) -> Result<Vec<u8>, crate::Error> {
         Ok(serde_json::to_vec_pretty(&self)?)
     }
 
+    fn resolved_package(&self, key: &str) -> Result<Option<ResolvedPackage>, Error> {
+        // Workspaces are the only entries that aren't installed in node_modules
+        let Some((_, name)) = key.rsplit_once("node_modules/") else {
+            return Ok(None);
+        };
+        let Some(pkg) = self.packages.get(key) else {
+            return Ok(None);
+        };
+        if pkg.is_link() {
+            return Ok(None);
+        }
+        let version = pkg
+            .version
+            .clone()
+            .ok_or_else(|| Error::MissingVersion(key.to_string()))?;
+        Ok(Some(ResolvedPackage {
+            name: name.to_string(),
+            version,
+            resolved: pkg.resolved.clone(),
+            integrity: pkg
+                .other
+                .get("integrity")
+                .and_then(Value::as_str)
+                .map(|integrity| integrity.to_string()),
+            dependencies: pkg.dependencies.clone(),
+            optional_dependencies: pkg.optional_dependencies.clone(),
+        }))
+    }
+
     fn global_change(&self, other: &dyn Lockfile) -> bool {
         let any_other = other as &dyn Any;
         if let Some(other) = any_other.downcast_ref::<Self>() {
@@ -166,6 +206,150 @@ impl NpmLockfile {
         }
     }
 
+    /// Lays out the packages in `graph` the way npm installs them. Packages are
+    /// hoisted to the root node_modules unless a different version already
+    /// takes that place, in which case they're nested under the package that
+    /// depends on them.
+    pub fn from_graph(graph: &ResolvedGraph, workspace_globs: &[String]) -> Self {
+        let mut packages = Map::new();
+        for (path, manifest) in &graph.workspaces {
+            let mut other = Map::new();
+            if let Some(name) = &manifest.name {
+                other.insert("name".to_string(), Value::from(name.as_str()));
+            }
+            if path.is_empty() {
+                other.insert("workspaces".to_string(), Value::from(workspace_globs));
+            } else if let Some(name) = &manifest.name {
+                packages.insert(
+                    format!("node_modules/{name}"),
+                    NpmPackage {
+                        resolved: Some(path.clone()),
+                        other: Map::from([("link".to_string(), Value::Bool(true))]),
+                        ..Default::default()
+                    },
+                );
+            }
+            packages.insert(
+                path.clone(),
+                NpmPackage {
+                    version: manifest.version.clone(),
+                    dependencies: manifest.dependencies.clone(),
+                    dev_dependencies: manifest.dev_dependencies.clone(),
+                    optional_dependencies: manifest.optional_dependencies.clone(),
+                    other,
+                    ..Default::default()
+                },
+            );
+        }
+
+        // The root workspace sorts first, so its dependencies get hoisted
+        // before any other workspace's
+        let mut queue = graph
+            .workspace_dependencies
+            .iter()
+            .map(|(path, dependencies)| (path.clone(), dependencies))
+            .collect::<VecDeque<_>>();
+        while let Some((parent, dependencies)) = queue.pop_front() {
+            for (name, id) in dependencies {
+                let Some((package, package_dependencies)) = graph.package(id) else {
+                    continue;
+                };
+                let lookup = Self::lookup_keys(&parent, name);
+                let key = match lookup.iter().find_map(|key| packages.get(key)) {
+                    Some(installed) if installed.version.as_ref() == Some(&package.version) => {
+                        continue;
+                    }
+                    Some(_) => lookup.first(),
+                    None => lookup.last(),
+                };
+                let key = key.expect("lookup always includes the root").clone();
+
+                let mut other = Map::new();
+                if let Some(integrity) = &package.integrity {
+                    other.insert("integrity".to_string(), Value::from(integrity.as_str()));
+                }
+                packages.insert(
+                    key.clone(),
+                    NpmPackage {
+                        version: Some(package.version.clone()),
+                        resolved: package.resolved.clone(),
+                        dependencies: package.dependencies.clone(),
+                        optional_dependencies: package.optional_dependencies.clone(),
+                        other,
+                        ..Default::default()
+                    },
+                );
+                queue.push_back((key, package_dependencies));
+            }
+        }
+
+        let mut other = Map::from([("requires".to_string(), Value::Bool(true))]);
+        if let Some(root) = graph.workspaces.get("") {
+            if let Some(name) = &root.name {
+                other.insert("name".to_string(), Value::from(name.as_str()));
+            }
+            if let Some(version) = &root.version {
+                other.insert("version".to_string(), Value::from(version.as_str()));
+            }
+        }
+        Self {
+            lockfile_version: 3,
+            packages,
+            dependencies: Map::new(),
+            other,
+        }
+    }
+
+    // The keys node looks up `name` at from a package installed at `key`,
+    // nearest first
+    fn lookup_keys(key: &str, name: &str) -> Vec<String> {
+        let mut keys = Vec::new();
+        let mut dir = key;
+        loop {
+            keys.push(match dir {
+                "" => format!("node_modules/{name}"),
+                dir => format!("{dir}/node_modules/{name}"),
+            });
+            if dir.is_empty() {
+                break;
+            }
+            dir = dir
+                .rfind("/node_modules/")
+                .map_or("", |index| &dir[..index]);
+        }
+        keys
+    }
+
+    /// Produces a new lockfile containing only the given workspaces and
+    /// packages
+    fn subgraph(&self, workspace_packages: &[String], packages: &[String]) -> Result<Self, Error> {
+        let mut pruned_packages = Map::new();
+        for pkg_key in packages {
+            let pkg = self.get_package(pkg_key)?;
+            pruned_packages.insert(pkg_key.to_string(), pkg.clone());
+        }
+        if let Some(root) = self.packages.get("") {
+            pruned_packages.insert("".into(), root.clone());
+        }
+        for workspace in workspace_packages {
+            let pkg = self.get_package(workspace)?;
+            pruned_packages.insert(workspace.to_string(), pkg.clone());
+
+            for (key, entry) in &self.packages {
+                if entry.resolved.as_deref() == Some(workspace) {
+                    pruned_packages.insert(key.clone(), entry.clone());
+                    break;
+                }
+            }
+        }
+        Ok(Self {
+            lockfile_version: 3,
+            packages: pruned_packages,
+            dependencies: Map::default(),
+            other: self.other.clone(),
+        })
+    }
+
     fn get_package(&self, package: impl AsRef<str>) -> Result<&NpmPackage, Error> {
         let pkg_str = package.as_ref();
         self.packages
@@ -200,6 +384,10 @@ impl NpmLockfile {
 }
 
 impl NpmPackage {
+    fn is_link(&self) -> bool {
+        self.other.get("link").and_then(Value::as_bool) == Some(true)
+    }
+
     pub fn dep_keys(&self) -> impl Iterator<Item = &String> {
         self.dependencies
             .keys()
@@ -412,6 +600,30 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_production_subgraph() -> Result<(), Error> {
+        let lockfile = NpmLockfile::load(include_bytes!("../fixtures/npm-lock.json"))?;
+        let pruned = lockfile.production_subgraph(
+            &["apps/docs".into(), "packages/ui".into()],
+            &["node_modules/lodash".into()],
+            &HashMap::new(),
+        )?;
+        let pruned: Value = serde_json::from_slice(&pruned.encode()?)?;
+        for workspace in ["", "apps/docs", "packages/ui"] {
+            assert!(
+                pruned["packages"][workspace]
+                    .get("devDependencies")
+                    .is_none(),
+                "{workspace} kept its devDependencies"
+            );
+        }
+        assert_eq!(
+            pruned["packages"]["apps/docs"]["dependencies"]["lodash"],
+            "^3.0.0"
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_npm_lockfile_serialization_stable() -> Result<(), Error> {
         let lockfile = NpmLockfile::load(include_bytes!("../fixtures/npm-lock.json"))?;
@@ -449,4 +661,71 @@ mod test {
         assert!(closures.get("packages/c").unwrap().is_empty());
         Ok(())
     }
+
+    #[test]
+    fn test_closures_by_name() -> Result<(), Error> {
+        let lockfile =
+            NpmLockfile::load(include_bytes!("../fixtures/workspace-peer-dependency.json"))?;
+        let closures = crate::all_transitive_closures_by_name(
+            &lockfile,
+            vec![(
+                "packages/a".into(),
+                vec![("eslint-plugin-turbo".into(), "^1.9.3".into())]
+                    .into_iter()
+                    .collect(),
+            )]
+            .into_iter()
+            .collect(),
+        )?;
+        let closure = closures.get("packages/a").unwrap();
+        assert_eq!(
+            closure.get("eslint-plugin-turbo").unwrap(),
+            &["1.9.3".to_string()].into_iter().collect()
+        );
+        // every package in the closure is found by name
+        let all_closures = crate::all_transitive_closures(
+            &lockfile,
+            vec![(
+                "packages/a".into(),
+                vec![("eslint-plugin-turbo".into(), "^1.9.3".into())]
+                    .into_iter()
+                    .collect(),
+            )]
+            .into_iter()
+            .collect(),
+        )?;
+        assert_eq!(
+            closure
+                .values()
+                .map(|versions| versions.len())
+                .sum::<usize>(),
+            all_closures.get("packages/a").unwrap().len()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_closures_by_name_nested() -> Result<(), Error> {
+        let lockfile = NpmLockfile::load(include_bytes!("../fixtures/npm-lock.json"))?;
+        let closures = crate::all_transitive_closures_by_name(
+            &lockfile,
+            vec![(
+                "".into(),
+                vec![("@babel/generator".into(), "^7.19.3".into())]
+                    .into_iter()
+                    .collect(),
+            )]
+            .into_iter()
+            .collect(),
+        )?;
+        let closure = closures.get("").unwrap();
+        // Installed at
+        // node_modules/@babel/generator/node_modules/@jridgewell/gen-mapping
+        assert_eq!(
+            closure.get("@jridgewell/gen-mapping").unwrap(),
+            &["0.3.2".to_string()].into_iter().collect()
+        );
+        assert!(closure.keys().all(|name| !name.contains("node_modules")));
+        Ok(())
+    }
 }
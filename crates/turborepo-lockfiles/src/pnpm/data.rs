@@ -1,9 +1,14 @@
-use std::{any::Any, borrow::Cow, collections::BTreeMap};
+use std::{
+    any::Any,
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet},
+};
 
 use serde::{Deserialize, Serialize};
 use turbopath::RelativeUnixPathBuf;
 
-use super::{dep_path::DepPath, Error, LockfileVersion};
+use super::{dep_path::DepPath, Error, LockfileVersion, VersionFormat};
+use crate::{convert::relative_path, ResolvedGraph, ResolvedPackage};
 
 type Map<K, V> = std::collections::BTreeMap<K, V>;
 
@@ -149,6 +154,110 @@ impl PnpmLockfile {
         Ok(this)
     }
 
+    /// Writes the packages in `graph` as a v6 lockfile
+    pub fn from_graph(graph: &ResolvedGraph) -> Self {
+        let mut importers = Map::new();
+        for (path, manifest) in &graph.workspaces {
+            let resolved = graph.workspace_dependencies.get(path);
+            let dependencies = |dependencies: &Map<String, String>| {
+                let dependencies = dependencies
+                    .iter()
+                    .filter_map(|(name, specifier)| {
+                        let version = match manifest.workspace_dependencies.contains(name) {
+                            true => {
+                                let target = graph.workspace_path(name)?;
+                                format!("link:{}", relative_path(path, target))
+                            }
+                            false => {
+                                let id = resolved?.get(name)?;
+                                graph.package(id)?.0.version.clone()
+                            }
+                        };
+                        let specifier = specifier.clone();
+                        Some((name.clone(), Dependency { specifier, version }))
+                    })
+                    .collect::<Map<_, _>>();
+                (!dependencies.is_empty()).then_some(dependencies)
+            };
+            let key = match path.as_str() {
+                "" => ".".to_string(),
+                path => path.to_string(),
+            };
+            importers.insert(
+                key,
+                ProjectSnapshot {
+                    dependencies: DependencyInfo::V6 {
+                        dependencies: dependencies(&manifest.dependencies),
+                        optional_dependencies: dependencies(&manifest.optional_dependencies),
+                        dev_dependencies: dependencies(&manifest.dev_dependencies),
+                    },
+                    dependencies_meta: None,
+                    publish_directory: None,
+                },
+            );
+        }
+
+        let packages = graph
+            .packages
+            .values()
+            .map(|package| {
+                let versions = |ranges: &Map<String, String>| {
+                    let versions = ranges
+                        .keys()
+                        .filter_map(|name| {
+                            let id = package.dependencies.get(name)?;
+                            Some((name.clone(), graph.package(id)?.0.version.clone()))
+                        })
+                        .collect::<Map<_, _>>();
+                    (!versions.is_empty()).then_some(versions)
+                };
+                let resolved = &package.package;
+                let snapshot = PackageSnapshot {
+                    resolution: PackageResolution {
+                        type_field: None,
+                        integrity: resolved.integrity.clone(),
+                        // pnpm only records where packages came from if it
+                        // can't verify them
+                        tarball: match resolved.integrity {
+                            Some(_) => None,
+                            None => resolved.resolved.clone(),
+                        },
+                        directory: None,
+                        repo: None,
+                        commit: None,
+                    },
+                    id: None,
+                    name: None,
+                    version: None,
+                    dependencies: versions(&resolved.dependencies),
+                    optional_dependencies: versions(&resolved.optional_dependencies),
+                    patched: None,
+                    other: Map::new(),
+                };
+                (format!("/{}@{}", resolved.name, resolved.version), snapshot)
+            })
+            .collect::<Map<_, _>>();
+
+        Self {
+            lockfile_version: LockfileVersion {
+                version: "6.0".to_string(),
+                format: VersionFormat::String,
+            },
+            settings: Some(LockfileSettings {
+                auto_install_peers: Some(true),
+                exclude_links_from_lockfile: Some(false),
+            }),
+            never_built_dependencies: None,
+            only_built_dependencies: None,
+            overrides: None,
+            package_extensions_checksum: None,
+            patched_dependencies: None,
+            importers,
+            packages: (!packages.is_empty()).then_some(packages),
+            time: None,
+        }
+    }
+
     fn get_packages(&self, key: &str) -> Option<&PackageSnapshot> {
         self.packages
             .as_ref()
@@ -234,6 +343,77 @@ impl PnpmLockfile {
         }
     }
 
+    // The root importer and those of the given workspaces
+    fn workspace_importers(&self, workspace_packages: &[String]) -> Map<String, ProjectSnapshot> {
+        self.importers
+            .iter()
+            .filter(|(key, _)| key.as_str() == "." || workspace_packages.contains(key))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect()
+    }
+
+    /// Produces a new lockfile containing only the given importers and
+    /// packages
+    fn importers_subgraph(
+        &self,
+        importers: Map<String, ProjectSnapshot>,
+        packages: &[String],
+    ) -> Result<Self, crate::Error> {
+        let mut pruned_packages = Map::new();
+        for package in packages {
+            let entry = self
+                .get_packages(package.as_str())
+                .ok_or_else(|| crate::Error::MissingPackage(package.clone()))?;
+            pruned_packages.insert(package.clone(), entry.clone());
+        }
+        for importer in importers.values() {
+            // Find all injected packages in each workspace and include it in
+            // the pruned lockfile
+            for dependency in
+                importer
+                    .dependencies_meta
+                    .iter()
+                    .flatten()
+                    .filter_map(|(dep, meta)| match meta.injected {
+                        Some(true) => Some(dep),
+                        _ => None,
+                    })
+            {
+                let (_, version) = importer
+                    .dependencies
+                    .find_resolution(dependency)
+                    .ok_or_else(|| Error::MissingInjectedPackage(dependency.clone()))?;
+
+                let entry = self
+                    .get_packages(version)
+                    .ok_or_else(|| crate::Error::MissingPackage(version.into()))?;
+                pruned_packages.insert(version.to_string(), entry.clone());
+            }
+        }
+
+        let patches = self
+            .patched_dependencies
+            .as_ref()
+            .map(|patches| Self::prune_patches(patches, &pruned_packages))
+            .transpose()?;
+
+        Ok(Self {
+            importers,
+            packages: match pruned_packages.is_empty() {
+                false => Some(pruned_packages),
+                true => None,
+            },
+            lockfile_version: self.lockfile_version.clone(),
+            never_built_dependencies: self.never_built_dependencies.clone(),
+            only_built_dependencies: self.only_built_dependencies.clone(),
+            overrides: self.overrides.clone(),
+            package_extensions_checksum: self.package_extensions_checksum.clone(),
+            patched_dependencies: patches,
+            time: None,
+            settings: self.settings.clone(),
+        })
+    }
+
     fn prune_patches(
         patches: &Map<String, PatchFile>,
         pruned_packages: &Map<String, PackageSnapshot>,
@@ -341,71 +521,59 @@ impl crate::Lockfile for PnpmLockfile {
         ))
     }
 
+    fn resolved_package(&self, key: &str) -> Result<Option<ResolvedPackage>, crate::Error> {
+        let Some(entry) = self.get_packages(key) else {
+            return Ok(None);
+        };
+        let dep_path = DepPath::try_from(key).map_err(Error::from)?;
+        // Dependencies are recorded with the version they resolved to, which
+        // includes any peer dependency or patch suffix
+        let versions = |dependencies: &Option<Map<String, String>>| {
+            dependencies
+                .iter()
+                .flatten()
+                .map(|(name, version)| {
+                    let version = version.split(['(', '_']).next().unwrap_or(version);
+                    (name.clone(), version.to_string())
+                })
+                .collect()
+        };
+        Ok(Some(ResolvedPackage {
+            name: entry
+                .name
+                .clone()
+                .unwrap_or_else(|| dep_path.name.to_string()),
+            version: entry
+                .version
+                .clone()
+                .unwrap_or_else(|| dep_path.version.to_string()),
+            resolved: entry.resolution.tarball.clone(),
+            integrity: entry.resolution.integrity.clone(),
+            dependencies: versions(&entry.dependencies),
+            optional_dependencies: versions(&entry.optional_dependencies),
+        }))
+    }
+
     fn subgraph(
         &self,
         workspace_packages: &[String],
         packages: &[String],
     ) -> Result<Box<dyn crate::Lockfile>, crate::Error> {
-        let importers = self
-            .importers
-            .iter()
-            .filter(|(key, _)| key.as_str() == "." || workspace_packages.contains(key))
-            .map(|(k, v)| (k.clone(), v.clone()))
-            .collect::<Map<_, _>>();
-
-        let mut pruned_packages = Map::new();
-        for package in packages {
-            let entry = self
-                .get_packages(package.as_str())
-                .ok_or_else(|| crate::Error::MissingPackage(package.clone()))?;
-            pruned_packages.insert(package.clone(), entry.clone());
-        }
-        for importer in importers.values() {
-            // Find all injected packages in each workspace and include it in
-            // the pruned lockfile
-            for dependency in
-                importer
-                    .dependencies_meta
-                    .iter()
-                    .flatten()
-                    .filter_map(|(dep, meta)| match meta.injected {
-                        Some(true) => Some(dep),
-                        _ => None,
-                    })
-            {
-                let (_, version) = importer
-                    .dependencies
-                    .find_resolution(dependency)
-                    .ok_or_else(|| Error::MissingInjectedPackage(dependency.clone()))?;
+        let importers = self.workspace_importers(workspace_packages);
+        Ok(Box::new(self.importers_subgraph(importers, packages)?))
+    }
 
-                let entry = self
-                    .get_packages(version)
-                    .ok_or_else(|| crate::Error::MissingPackage(version.into()))?;
-                pruned_packages.insert(version.to_string(), entry.clone());
-            }
+    fn production_subgraph(
+        &self,
+        workspace_packages: &[String],
+        packages: &[String],
+        _dev_dependencies: &HashMap<String, HashSet<String>>,
+    ) -> Result<Box<dyn crate::Lockfile>, crate::Error> {
+        let mut importers = self.workspace_importers(workspace_packages);
+        for importer in importers.values_mut() {
+            importer.remove_dev_dependencies();
         }
-
-        let patches = self
-            .patched_dependencies
-            .as_ref()
-            .map(|patches| Self::prune_patches(patches, &pruned_packages))
-            .transpose()?;
-
-        Ok(Box::new(Self {
-            importers,
-            packages: match pruned_packages.is_empty() {
-                false => Some(pruned_packages),
-                true => None,
-            },
-            lockfile_version: self.lockfile_version.clone(),
-            never_built_dependencies: self.never_built_dependencies.clone(),
-            only_built_dependencies: self.only_built_dependencies.clone(),
-            overrides: self.overrides.clone(),
-            package_extensions_checksum: self.package_extensions_checksum.clone(),
-            patched_dependencies: patches,
-            time: None,
-            settings: self.settings.clone(),
-        }))
+        Ok(Box::new(self.importers_subgraph(importers, packages)?))
     }
 
     fn encode(&self) -> Result<Vec<u8>, crate::Error> {
@@ -433,6 +601,17 @@ impl crate::Lockfile for PnpmLockfile {
     }
 }
 
+impl ProjectSnapshot {
+    // Drops the devDependencies of the importer, along with the specifiers and
+    // metadata of the ones it doesn't also depend on in production
+    fn remove_dev_dependencies(&mut self) {
+        let dev_only = self.dependencies.remove_dev_dependencies();
+        if let Some(meta) = &mut self.dependencies_meta {
+            meta.retain(|name, _| !dev_only.contains(name));
+        }
+    }
+}
+
 impl DependencyInfo {
     // Given a dependency will find the specifier and resolved version that
     // appear in the importer object
@@ -461,6 +640,47 @@ impl DependencyInfo {
         }
     }
 
+    // Removes the devDependencies, returning the names of the ones that
+    // aren't also production dependencies
+    fn remove_dev_dependencies(&mut self) -> HashSet<String> {
+        match self {
+            DependencyInfo::PreV6 {
+                specifiers,
+                dependencies,
+                optional_dependencies,
+                dev_dependencies,
+            } => {
+                let dev_only =
+                    Self::dev_only(dev_dependencies.take(), dependencies, optional_dependencies);
+                if let Some(specifiers) = specifiers {
+                    specifiers.retain(|name, _| !dev_only.contains(name));
+                }
+                dev_only
+            }
+            DependencyInfo::V6 {
+                dependencies,
+                optional_dependencies,
+                dev_dependencies,
+            } => Self::dev_only(dev_dependencies.take(), dependencies, optional_dependencies),
+        }
+    }
+
+    fn dev_only<V, D>(
+        dev_dependencies: Option<Map<String, D>>,
+        dependencies: &Option<Map<String, V>>,
+        optional_dependencies: &Option<Map<String, V>>,
+    ) -> HashSet<String> {
+        dev_dependencies
+            .into_iter()
+            .flatten()
+            .map(|(name, _)| name)
+            .filter(|name| {
+                Self::get_resolution(dependencies, name).is_none()
+                    && Self::get_resolution(optional_dependencies, name).is_none()
+            })
+            .collect()
+    }
+
     fn get_resolution<'a, V>(maybe_map: &'a Option<Map<String, V>>, key: &str) -> Option<&'a V> {
         maybe_map.as_ref().and_then(|maybe_map| maybe_map.get(key))
     }
@@ -742,6 +962,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_production_subgraph() {
+        let lockfile = PnpmLockfile::from_bytes(PNPM7).unwrap();
+        let pruned = lockfile
+            .production_subgraph(&["apps/web".into()], &[], &HashMap::new())
+            .unwrap();
+        let pruned = PnpmLockfile::from_bytes(&pruned.encode().unwrap()).unwrap();
+
+        let DependencyInfo::PreV6 {
+            specifiers,
+            dependencies,
+            dev_dependencies,
+            ..
+        } = &pruned.importers["apps/web"].dependencies
+        else {
+            panic!("expected a pre-v6 importer");
+        };
+        assert_eq!(dev_dependencies, &None);
+        assert!(dependencies.as_ref().unwrap().contains_key("next"));
+        let specifiers = specifiers.as_ref().unwrap();
+        assert!(specifiers.contains_key("next"));
+        assert!(!specifiers.contains_key("@babel/core"));
+        assert!(!specifiers.contains_key("tsconfig"));
+    }
+
     #[test]
     fn test_prune_patches() {
         let lockfile = PnpmLockfile::from_bytes(PNPM_PATCH).unwrap();
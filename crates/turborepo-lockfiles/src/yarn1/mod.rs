@@ -2,11 +2,13 @@ use std::{any::Any, str::FromStr};
 
 use serde::Deserialize;
 
-use crate::Lockfile;
+use crate::{Lockfile, ResolvedGraph};
 
 mod de;
 mod ser;
 
+pub(crate) use ser::{write_lockfile, HEADER};
+
 type Map<K, V> = std::collections::BTreeMap<K, V>;
 
 #[derive(Debug, thiserror::Error)]
@@ -26,15 +28,15 @@ pub struct Yarn1Lockfile {
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct Entry {
-    name: Option<String>,
-    version: String,
-    uid: Option<String>,
-    resolved: Option<String>,
-    integrity: Option<String>,
-    registry: Option<String>,
-    dependencies: Option<Map<String, String>>,
-    optional_dependencies: Option<Map<String, String>>,
+pub(crate) struct Entry {
+    pub(crate) name: Option<String>,
+    pub(crate) version: String,
+    pub(crate) uid: Option<String>,
+    pub(crate) resolved: Option<String>,
+    pub(crate) integrity: Option<String>,
+    pub(crate) registry: Option<String>,
+    pub(crate) dependencies: Option<Map<String, String>>,
+    pub(crate) optional_dependencies: Option<Map<String, String>>,
 }
 
 impl Yarn1Lockfile {
@@ -44,6 +46,56 @@ impl Yarn1Lockfile {
     }
 }
 
+impl Yarn1Lockfile {
+    /// Writes the packages in `graph` as entries for each range they're
+    /// depended on with
+    pub fn from_graph(graph: &ResolvedGraph) -> Self {
+        let mut inner = Map::new();
+        let mut add = |name: &str, range: &str, id: &str| {
+            let Some((package, _)) = graph.package(id) else {
+                return;
+            };
+            inner
+                .entry(format!("{name}@{range}"))
+                .or_insert_with(|| Entry {
+                    version: package.version.clone(),
+                    resolved: package.resolved.clone(),
+                    integrity: package.integrity.clone(),
+                    dependencies: (!package.dependencies.is_empty())
+                        .then(|| package.dependencies.clone()),
+                    optional_dependencies: (!package.optional_dependencies.is_empty())
+                        .then(|| package.optional_dependencies.clone()),
+                    ..Default::default()
+                });
+        };
+
+        for (path, manifest) in &graph.workspaces {
+            let Some(resolved) = graph.workspace_dependencies.get(path) else {
+                continue;
+            };
+            for (name, range) in manifest.external_dependencies() {
+                if let Some(id) = resolved.get(name) {
+                    add(name, range, id);
+                }
+            }
+        }
+        for package in graph.packages.values() {
+            let ranges = package
+                .package
+                .dependencies
+                .iter()
+                .chain(&package.package.optional_dependencies);
+            for (name, range) in ranges {
+                if let Some(id) = package.dependencies.get(name) {
+                    add(name, range, id);
+                }
+            }
+        }
+
+        Self { inner }
+    }
+}
+
 impl FromStr for Yarn1Lockfile {
     type Err = super::Error;
 
@@ -111,6 +163,24 @@ impl Lockfile for Yarn1Lockfile {
         Ok(self.to_string().into_bytes())
     }
 
+    fn resolved_package(&self, key: &str) -> Result<Option<crate::ResolvedPackage>, crate::Error> {
+        let Some(entry) = self.inner.get(key) else {
+            return Ok(None);
+        };
+        let name = match &entry.name {
+            Some(name) => name.as_str(),
+            None => package_name(key),
+        };
+        Ok(Some(crate::ResolvedPackage {
+            name: name.to_string(),
+            version: entry.version.clone(),
+            resolved: entry.resolved.clone(),
+            integrity: entry.integrity.clone(),
+            dependencies: entry.dependencies.clone().unwrap_or_default(),
+            optional_dependencies: entry.optional_dependencies.clone().unwrap_or_default(),
+        }))
+    }
+
     fn global_change(&self, other: &dyn Lockfile) -> bool {
         let any_other = other as &dyn Any;
         // Downcast returns none if the concrete type doesn't match
@@ -126,7 +196,7 @@ pub fn yarn_subgraph(contents: &[u8], packages: &[String]) -> Result<Vec<u8>, cr
 }
 
 impl Entry {
-    fn dependency_entries(&self) -> impl Iterator<Item = (String, String)> + '_ {
+    pub(crate) fn dependency_entries(&self) -> impl Iterator<Item = (String, String)> + '_ {
         self.dependencies
             .iter()
             .flatten()
@@ -135,6 +205,15 @@ impl Entry {
     }
 }
 
+// Keys are the package name followed by the range it was resolved from
+pub(crate) fn package_name(key: &str) -> &str {
+    // Skip the leading @ of scoped packages
+    match key.get(1..).and_then(|rest| rest.find('@')) {
+        Some(index) => &key[..index + 1],
+        None => key,
+    }
+}
+
 const PROTOCOLS: &[&str] = ["", "npm:", "file:", "workspace:", "yarn:"].as_slice();
 
 fn possible_keys<'a>(name: &'a str, version: &'a str) -> impl Iterator<Item = String> + 'a {
@@ -161,6 +240,22 @@ mod test {
         assert_eq!(input, lockfile.to_string());
     }
 
+    #[test]
+    fn test_registry_roundtrip() {
+        let input = r#"# THIS IS AN AUTOGENERATED FILE. DO NOT EDIT THIS FILE DIRECTLY.
+# yarn lockfile v1
+
+
+is-odd@^3.0.1:
+  version "3.0.1"
+  resolved "https://registry.npmjs.org/is-odd/-/is-odd-3.0.1.tgz"
+  integrity sha512-CQpnWPrDwmP1+SMHXZhtLtJv90yiyVfluGsX5iNCVkrhQtU3TQHsUWPG9wkdk9Lgd5yNpAg9jQEo90CBaXgWMA==
+  registry "https://registry.npmjs.org/"
+"#;
+        let lockfile = Yarn1Lockfile::from_str(input).unwrap();
+        assert_eq!(input, lockfile.to_string());
+    }
+
     #[test]
     fn test_key_splitting() {
         let lockfile = Yarn1Lockfile::from_str(FULL).unwrap();
@@ -4,49 +4,58 @@ use std::{
     fmt,
 };
 
-use super::{Entry, Yarn1Lockfile};
+use super::{Entry, Map, Yarn1Lockfile};
 
 const INDENT: &str = "  ";
 
-impl Yarn1Lockfile {
-    fn reverse_lookup(&self) -> HashMap<&Entry, HashSet<&str>> {
-        let mut reverse_lookup = HashMap::new();
-        for (key, value) in self.inner.iter() {
-            let keys: &mut HashSet<&str> = reverse_lookup.entry(value).or_default();
-            keys.insert(key);
-        }
-        reverse_lookup
-    }
-}
+pub(crate) const HEADER: &str =
+    "# THIS IS AN AUTOGENERATED FILE. DO NOT EDIT THIS FILE DIRECTLY.\n# yarn lockfile v1\n";
 
 impl fmt::Display for Yarn1Lockfile {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str(
-            "# THIS IS AN AUTOGENERATED FILE. DO NOT EDIT THIS FILE DIRECTLY.\n# yarn lockfile \
-             v1\n\n",
-        )?;
-        let reverse_lookup = self.reverse_lookup();
-        let mut added_keys: HashSet<&str> = HashSet::with_capacity(self.inner.len());
-        for (key, entry) in self.inner.iter() {
-            if added_keys.contains(key.as_str()) {
-                continue;
-            }
-
-            let all_keys = reverse_lookup
-                .get(entry)
-                .expect("entry in lockfile should appear as a key in reverse lookup");
-            added_keys.extend(all_keys);
-            let mut keys = all_keys.iter().copied().collect::<Vec<_>>();
-            // Keys must be sorted before they get wrapped
-            keys.sort();
-
-            let wrapped_keys = keys.into_iter().map(maybe_wrap).collect::<Vec<_>>();
-            let key_line = wrapped_keys.join(", ");
-
-            f.write_fmt(format_args!("\n{}:\n{}\n", key_line, entry))?;
+        write_lockfile(f, HEADER, &self.inner)
+    }
+}
+
+fn reverse_lookup(entries: &Map<String, Entry>) -> HashMap<&Entry, HashSet<&str>> {
+    let mut reverse_lookup = HashMap::new();
+    for (key, value) in entries.iter() {
+        let keys: &mut HashSet<&str> = reverse_lookup.entry(value).or_default();
+        keys.insert(key);
+    }
+    reverse_lookup
+}
+
+/// Writes `entries` in the yarn v1 format, starting with the comment lines in
+/// `header`
+pub(crate) fn write_lockfile(
+    f: &mut fmt::Formatter<'_>,
+    header: &str,
+    entries: &Map<String, Entry>,
+) -> fmt::Result {
+    f.write_str(header)?;
+    f.write_str("\n")?;
+    let reverse_lookup = reverse_lookup(entries);
+    let mut added_keys: HashSet<&str> = HashSet::with_capacity(entries.len());
+    for (key, entry) in entries.iter() {
+        if added_keys.contains(key.as_str()) {
+            continue;
         }
-        Ok(())
+
+        let all_keys = reverse_lookup
+            .get(entry)
+            .expect("entry in lockfile should appear as a key in reverse lookup");
+        added_keys.extend(all_keys);
+        let mut keys = all_keys.iter().copied().collect::<Vec<_>>();
+        // Keys must be sorted before they get wrapped
+        keys.sort();
+
+        let wrapped_keys = keys.into_iter().map(maybe_wrap).collect::<Vec<_>>();
+        let key_line = wrapped_keys.join(", ");
+
+        f.write_fmt(format_args!("\n{}:\n{}\n", key_line, entry))?;
     }
+    Ok(())
 }
 
 impl fmt::Display for Entry {
@@ -87,7 +96,7 @@ impl fmt::Display for Entry {
         }
         if let Some(registry) = &self.registry {
             f.write_fmt(format_args!(
-                "{}{INDENT}integrity {}",
+                "{}{INDENT}registry {}",
                 leading.leading(),
                 maybe_wrap(registry)
             ))?;
@@ -3,6 +3,7 @@
 
 use std::{
     collections::{HashMap, HashSet},
+    ops::Range as ByteRange,
     str::FromStr,
     sync::{Arc, Mutex},
 };
@@ -17,10 +18,13 @@ use tower_lsp::{
     lsp_types::*,
     Client, LanguageServer,
 };
-use turbopath::AbsoluteSystemPathBuf;
-use turborepo_lib::{DaemonClient, DaemonConnector, DaemonPackageDiscovery, DaemonPaths};
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
+use turborepo_lib::{
+    DaemonClient, DaemonConnector, DaemonPackageDiscovery, DaemonPaths, TurboJsonAnalyzer,
+};
 use turborepo_repository::{
     discovery::{self, DiscoveryResponse, PackageDiscovery},
+    package_graph::{self, PackageGraph},
     package_json::PackageJson,
 };
 
@@ -159,6 +163,9 @@ impl LanguageServer for Backend {
                         work_done_progress: None,
                     },
                 })),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                definition_provider: Some(OneOf::Left(true)),
+                rename_provider: Some(OneOf::Left(true)),
                 ..ServerCapabilities::default()
             },
         })
@@ -328,6 +335,210 @@ impl LanguageServer for Backend {
         Ok(Some(locations))
     }
 
+    async fn hover(&self, params: HoverParams) -> LspResult<Option<Hover>> {
+        let uri = params.text_document_position_params.text_document.uri;
+        let Some(rope) = self.rope(&uri) else {
+            return Ok(None);
+        };
+        let text = rope.chunks().join("");
+
+        let Some(task) =
+            task_reference_at(&text, &rope, params.text_document_position_params.position)
+                .filter(|task| task.is_pipeline_key)
+        else {
+            return Ok(None);
+        };
+
+        let Some(repo_root) = self.repo_root() else {
+            return Ok(None);
+        };
+        let Some(path) = anchored_path(&repo_root, &uri) else {
+            return Ok(None);
+        };
+
+        let package_graph = match self.package_graph(&repo_root).await {
+            Ok(package_graph) => package_graph,
+            Err(e) => {
+                self.client
+                    .log_message(MessageType::WARNING, e.to_string())
+                    .await;
+                return Ok(None);
+            }
+        };
+
+        let Some(definition) = TurboJsonAnalyzer::new(&repo_root, &package_graph)
+            .task_definition(&path, &text, &task.name)
+        else {
+            return Ok(None);
+        };
+
+        let definition =
+            serde_json::to_string_pretty(&definition).map_err(|_| Error::internal_error())?;
+
+        Ok(Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format!("```json\n{}\n```", definition),
+            }),
+            range: Some(convert_ranges(&rope, task.range)),
+        }))
+    }
+
+    async fn goto_definition(
+        &self,
+        params: GotoDefinitionParams,
+    ) -> LspResult<Option<GotoDefinitionResponse>> {
+        let uri = params.text_document_position_params.text_document.uri;
+        let Some(rope) = self.rope(&uri) else {
+            return Ok(None);
+        };
+        let text = rope.chunks().join("");
+
+        let Some(task) =
+            task_reference_at(&text, &rope, params.text_document_position_params.position)
+        else {
+            return Ok(None);
+        };
+
+        let Some(repo_root) = self.repo_root() else {
+            return Ok(None);
+        };
+
+        let packages = match self.package_discovery().await {
+            Ok(packages) => packages,
+            Err(e) => {
+                self.client
+                    .log_message(MessageType::WARNING, e.to_string())
+                    .await;
+                return Err(Error::internal_error());
+            }
+        };
+
+        let (package, task_name) = task.package_and_task();
+        // a task without a package refers to the script of the workspace whose
+        // turbo.json it is in, or to any workspace's script in the root turbo.json
+        let turbo_json_dir = uri
+            .to_file_path()
+            .ok()
+            .and_then(|path| path.parent().map(|dir| dir.to_owned()));
+        let in_root = turbo_json_dir.as_deref() == Some(repo_root.as_std_path());
+
+        let mut locations = vec![];
+        for wd in packages.workspaces {
+            let Ok(data) = std::fs::read_to_string(&wd.package_json) else {
+                continue;
+            };
+            let Ok(package_json) = PackageJson::from_str(&data) else {
+                continue;
+            };
+
+            let in_package = match package {
+                Some(package) if package == ROOT_PACKAGE => {
+                    repo_root.join_component("package.json") == wd.package_json
+                }
+                Some(package) => package_json.name.as_deref() == Some(package),
+                None if !in_root => {
+                    turbo_json_dir.as_deref() == wd.package_json.parent().map(|p| p.as_std_path())
+                }
+                None => true,
+            };
+            if !in_package {
+                continue;
+            }
+
+            let rope = crop::Rope::from(data.as_str());
+            if let Some(range) = script_range(&data, task_name) {
+                locations.push(Location::new(
+                    Url::from_file_path(&wd.package_json).expect("only fails if path is relative"),
+                    convert_ranges(&rope, range),
+                ));
+            }
+        }
+
+        Ok(Some(GotoDefinitionResponse::Array(locations)))
+    }
+
+    async fn rename(&self, params: RenameParams) -> LspResult<Option<WorkspaceEdit>> {
+        let uri = params.text_document_position.text_document.uri;
+        let Some(rope) = self.rope(&uri) else {
+            return Ok(None);
+        };
+        let text = rope.chunks().join("");
+
+        let Some(task) = task_reference_at(&text, &rope, params.text_document_position.position)
+        else {
+            return Ok(None);
+        };
+        let (_, old_name) = task.package_and_task();
+
+        if params.new_name.is_empty() || params.new_name.contains(['#', '^', '$']) {
+            return Err(Error::invalid_params(
+                "task names cannot be empty or contain '#', '^' or '$'",
+            ));
+        }
+
+        let Some(repo_root) = self.repo_root() else {
+            return Ok(None);
+        };
+
+        let packages = match self.package_discovery().await {
+            Ok(packages) => packages,
+            Err(e) => {
+                self.client
+                    .log_message(MessageType::WARNING, e.to_string())
+                    .await;
+                return Err(Error::internal_error());
+            }
+        };
+
+        let turbo_jsons = packages
+            .workspaces
+            .iter()
+            .filter_map(|wd| wd.package_json.parent())
+            .map(|dir| dir.join_component("turbo.json"))
+            .chain(Some(repo_root.join_component("turbo.json")))
+            .unique();
+
+        let mut changes = HashMap::new();
+        for turbo_json in turbo_jsons {
+            let Ok(file_uri) = Url::from_file_path(&turbo_json) else {
+                continue;
+            };
+            // prefer the editor's copy of the file, it may have unsaved changes
+            let rope = match self.rope(&file_uri) {
+                Some(rope) => rope,
+                None => match std::fs::read_to_string(&turbo_json) {
+                    Ok(data) => crop::Rope::from(data),
+                    Err(_) => continue,
+                },
+            };
+            let text = rope.chunks().join("");
+
+            let edits = rename_ranges(&text, old_name)
+                .into_iter()
+                .map(|range| TextEdit {
+                    range: convert_ranges(
+                        &rope,
+                        jsonc_parser::common::Range {
+                            start: range.start,
+                            end: range.end,
+                        },
+                    ),
+                    new_text: params.new_name.clone(),
+                })
+                .collect::<Vec<_>>();
+
+            if !edits.is_empty() {
+                changes.insert(file_uri, edits);
+            }
+        }
+
+        Ok(Some(WorkspaceEdit {
+            changes: Some(changes),
+            ..Default::default()
+        }))
+    }
+
     async fn code_lens(&self, params: CodeLensParams) -> LspResult<Option<Vec<CodeLens>>> {
         self.client
             .log_message(MessageType::INFO, "code lens!")
@@ -580,21 +791,52 @@ impl Backend {
     }
 
     pub async fn package_discovery(&self) -> Result<DiscoveryResponse, discovery::Error> {
-        let mut daemon = {
-            let mut daemon = self.daemon.clone();
-            let daemon = daemon.wait_for(|d| d.is_some()).await;
-            let daemon = daemon.as_ref().expect("only fails if self is dropped");
-            daemon
-                .as_ref()
-                .expect("guaranteed to be some above")
-                .clone()
-        };
+        let mut daemon = self.daemon().await;
 
         DaemonPackageDiscovery::new(&mut daemon)
             .discover_packages()
             .await
     }
 
+    /// Build the package graph, using the daemon to discover packages.
+    pub async fn package_graph(
+        &self,
+        repo_root: &AbsoluteSystemPath,
+    ) -> Result<PackageGraph, package_graph::Error> {
+        let root_package_json = PackageJson::load(&repo_root.join_component("package.json"))?;
+        let mut daemon = self.daemon().await;
+
+        PackageGraph::builder(repo_root, root_package_json)
+            .with_package_discovery(DaemonPackageDiscovery::new(&mut daemon))
+            .build()
+            .await
+    }
+
+    async fn daemon(&self) -> DaemonClient<DaemonConnector> {
+        let mut daemon = self.daemon.clone();
+        let daemon = daemon.wait_for(|d| d.is_some()).await;
+        let daemon = daemon.as_ref().expect("only fails if self is dropped");
+        daemon
+            .as_ref()
+            .expect("guaranteed to be some above")
+            .clone()
+    }
+
+    fn repo_root(&self) -> Option<AbsoluteSystemPathBuf> {
+        self.repo_root
+            .lock()
+            .expect("only fails if poisoned")
+            .clone()
+    }
+
+    fn rope(&self, uri: &Url) -> Option<crop::Rope> {
+        self.files
+            .lock()
+            .expect("only fails if poisoned")
+            .get(uri)
+            .cloned() // cloning is cheap
+    }
+
     /// Handle a file update to a rope, emitting diagnostics if necessary.
     async fn handle_file_update(&self, uri: Url, rope: Option<crop::Rope>, version: Option<i32>) {
         let rope = match rope {
@@ -663,15 +905,49 @@ impl Backend {
 
         let mut diagnostics = vec![];
 
+        // run the same validation as `turbo run`, which also catches errors
+        // in the task graph that only show up once dependencies are resolved
+        if let Some(path) = anchored_path(&repo_root, &uri) {
+            match self.package_graph(&repo_root).await {
+                Ok(package_graph) => diagnostics.extend(
+                    TurboJsonAnalyzer::new(&repo_root, &package_graph)
+                        .diagnostics(&path, &contents)
+                        .into_iter()
+                        .map(|diagnostic| Diagnostic {
+                            message: diagnostic.message,
+                            range: convert_ranges(
+                                &rope,
+                                diagnostic
+                                    .range
+                                    .map(|range| jsonc_parser::common::Range {
+                                        start: range.start,
+                                        end: range.end,
+                                    })
+                                    .unwrap_or(jsonc_parser::common::Range { start: 0, end: 0 }),
+                            ),
+                            severity: Some(DiagnosticSeverity::ERROR),
+                            code: Some(NumberOrString::String(diagnostic.code.to_string())),
+                            ..Default::default()
+                        }),
+                ),
+                Err(e) => {
+                    self.client
+                        .log_message(MessageType::WARNING, e.to_string())
+                        .await
+                }
+            }
+        }
+
         // ParseResult cannot be sent across threads, so we must ensure it is dropped
         // before we send the diagnostics. easiest way is just to scope it
-        {
+        'ast: {
             let parse =
                 jsonc_parser::parse_to_ast(&contents, &Default::default(), &Default::default());
 
             let parse = match parse {
                 Ok(parse) => parse,
-                Err(_) => return, // if it is not a valid json, then there are no diagnostics
+                // if it is not a valid json, turbo's parser has already reported why
+                Err(_) => break 'ast,
             };
 
             let object = parse.value.as_ref().and_then(|v| v.as_object());
@@ -875,3 +1151,184 @@ fn collapse_string_range(range: jsonc_parser::common::Range) -> jsonc_parser::co
         end: range.end - 1,
     }
 }
+
+/// The name of the root package in a `package#task` reference.
+const ROOT_PACKAGE: &str = "//";
+
+/// Get the path of a file relative to the repo root, if it is inside it.
+fn anchored_path(repo_root: &AbsoluteSystemPath, uri: &Url) -> Option<AnchoredSystemPathBuf> {
+    let path = uri.to_file_path().ok()?;
+    let path = AbsoluteSystemPathBuf::try_from(path).ok()?;
+    repo_root.anchor(&path).ok()
+}
+
+/// A task named in a turbo.json, either as a pipeline key or in `dependsOn`.
+#[derive(Debug, PartialEq)]
+struct TaskReference {
+    name: String,
+    /// the range of the name, without quotes
+    range: jsonc_parser::common::Range,
+    is_pipeline_key: bool,
+}
+
+impl TaskReference {
+    /// Split the reference into its package, if any, and task name.
+    fn package_and_task(&self) -> (Option<&str>, &str) {
+        let name = self.name.strip_prefix('^').unwrap_or(&self.name);
+        match name.split_once('#') {
+            Some((package, task)) => (Some(package), task),
+            None => (None, name),
+        }
+    }
+}
+
+fn task_references(text: &str) -> Vec<TaskReference> {
+    let Ok(parse) = jsonc_parser::parse_to_ast(text, &Default::default(), &Default::default())
+    else {
+        return vec![];
+    };
+
+    let pipeline = parse
+        .value
+        .as_ref()
+        .and_then(|v| v.as_object())
+        .and_then(|o| o.get_object("pipeline"))
+        .map(|p| p.properties.iter());
+
+    let mut references = vec![];
+    for property in pipeline.into_iter().flatten() {
+        let mut range = property.range;
+        range.start += 1; // account for quote
+        range.end = range.start + property.name.as_str().len();
+
+        references.push(TaskReference {
+            name: property.name.as_str().to_string(),
+            range,
+            is_pipeline_key: true,
+        });
+
+        let depends_on = property
+            .value
+            .as_object()
+            .and_then(|o| o.get_array("dependsOn"))
+            .map(|a| a.elements.iter());
+        references.extend(
+            depends_on
+                .into_iter()
+                .flatten()
+                .filter_map(|element| element.as_string_lit())
+                .filter(|string| !string.value.starts_with('$'))
+                .map(|string| TaskReference {
+                    name: string.value.to_string(),
+                    range: collapse_string_range(string.range),
+                    is_pipeline_key: false,
+                }),
+        );
+    }
+
+    references
+}
+
+/// Find the task reference under the cursor.
+fn task_reference_at(text: &str, rope: &crop::Rope, position: Position) -> Option<TaskReference> {
+    let line = position.line as usize;
+    if line >= rope.line_len() {
+        return None;
+    }
+    let offset = rope.byte_of_line(line) + position.character as usize;
+
+    task_references(text)
+        .into_iter()
+        .find(|task| task.range.start <= offset && offset <= task.range.end)
+}
+
+/// Find the ranges that need to change to rename the task `name`, covering
+/// only the task part of references such as `^name` and `package#name`.
+fn rename_ranges(text: &str, name: &str) -> Vec<ByteRange<usize>> {
+    task_references(text)
+        .into_iter()
+        .filter_map(|task| {
+            let (_, task_name) = task.package_and_task();
+            (task_name == name).then(|| {
+                let end = task.range.end;
+                end - name.len()..end
+            })
+        })
+        .collect()
+}
+
+/// Find the key of the `name` script in a package.json.
+fn script_range(text: &str, name: &str) -> Option<jsonc_parser::common::Range> {
+    let parse = jsonc_parser::parse_to_ast(text, &Default::default(), &Default::default()).ok()?;
+
+    let script = parse
+        .value
+        .as_ref()
+        .and_then(|v| v.as_object())
+        .and_then(|o| o.get_object("scripts"))?
+        .properties
+        .iter()
+        .find(|property| property.name.as_str() == name)?;
+
+    let start = script.range.start + 1; // account for quote
+    Some(jsonc_parser::common::Range {
+        start,
+        end: start + name.len(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::*;
+
+    const TURBO_JSON: &str = r#"{
+  "pipeline": {
+    "build": { "dependsOn": ["^build", "$NODE_ENV"] },
+    "web#build": { "dependsOn": ["lint"] },
+    "test": { "dependsOn": ["build", "web#lint"] }
+  }
+}"#;
+
+    #[test_case("build", &["build", "^build", "web#build", "build"] ; "task")]
+    #[test_case("lint", &["lint", "web#lint"] ; "dependency only")]
+    #[test_case("NODE_ENV", &[] ; "env var")]
+    #[test_case("web", &[] ; "package")]
+    fn test_rename_ranges(name: &str, references: &[&str]) {
+        let ranges = rename_ranges(TURBO_JSON, name);
+        assert_eq!(ranges.len(), references.len());
+        for (range, reference) in ranges.into_iter().zip(references) {
+            assert_eq!(&TURBO_JSON[range.clone()], name);
+            // the edit only covers the task part of the reference
+            assert_eq!(
+                &TURBO_JSON[range.end - reference.len()..range.end],
+                *reference
+            );
+        }
+    }
+
+    #[test_case(2, 6, Some(("build", true)) ; "pipeline key")]
+    #[test_case(3, 7, Some(("web#build", true)) ; "package task key")]
+    #[test_case(4, 30, Some(("build", false)) ; "dependency")]
+    #[test_case(4, 12, None ; "task definition")]
+    #[test_case(9, 0, None ; "past the end")]
+    fn test_task_reference_at(line: u32, character: u32, expected: Option<(&str, bool)>) {
+        let rope = crop::Rope::from(TURBO_JSON);
+        let task = task_reference_at(TURBO_JSON, &rope, Position { line, character });
+        assert_eq!(
+            task.map(|task| (task.name, task.is_pipeline_key)),
+            expected.map(|(name, is_key)| (name.to_string(), is_key))
+        );
+    }
+
+    #[test_case("build", Some("build") ; "script")]
+    #[test_case("test", None ; "missing script")]
+    #[test_case("name", None ; "not a script")]
+    fn test_script_range(name: &str, expected: Option<&str>) {
+        let package_json =
+            r#"{ "name": "web", "scripts": { "dev": "next", "build": "next build" } }"#;
+        let range = script_range(package_json, name);
+        assert_eq!(range.map(|r| &package_json[r.start..r.end]), expected);
+    }
+}
@@ -9,14 +9,25 @@
 //! these strategies will implement some sort of monad-style composition so that
 //! we can track areas of run that are performing sub-optimally.
 
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    fmt,
+    str::FromStr,
+};
+
 use tokio_stream::{iter, StreamExt};
-use turbopath::AbsoluteSystemPathBuf;
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
 
+pub use self::{cargo::CargoDiscovery, go::GoDiscovery, python::PythonDiscovery};
 use crate::{
     package_json::PackageJson,
     package_manager::{self, PackageManager},
 };
 
+mod cargo;
+mod go;
+mod python;
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct WorkspaceData {
     pub package_json: AbsoluteSystemPathBuf,
@@ -247,6 +258,227 @@ impl<P: PackageDiscovery + Send> PackageDiscovery for CachingPackageDiscovery<P>
     }
 }
 
+/// The ecosystems besides npm workspaces that can contribute packages to the
+/// package graph.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Ecosystem {
+    Cargo,
+    Go,
+    Python,
+}
+
+impl Ecosystem {
+    pub fn discovery(self) -> Box<dyn ManifestDiscovery> {
+        match self {
+            Ecosystem::Cargo => Box::new(CargoDiscovery),
+            Ecosystem::Go => Box::new(GoDiscovery),
+            Ecosystem::Python => Box::new(PythonDiscovery),
+        }
+    }
+}
+
+impl fmt::Display for Ecosystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Ecosystem::Cargo => "cargo",
+            Ecosystem::Go => "go",
+            Ecosystem::Python => "python",
+        })
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("unknown package discovery '{0}', expected one of cargo, go or python")]
+pub struct UnknownEcosystem(String);
+
+impl FromStr for Ecosystem {
+    type Err = UnknownEcosystem;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cargo" => Ok(Ecosystem::Cargo),
+            "go" => Ok(Ecosystem::Go),
+            "python" => Ok(Ecosystem::Python),
+            _ => Err(UnknownEcosystem(s.to_string())),
+        }
+    }
+}
+
+/// A package described by its ecosystem's own manifest, e.g. a `Cargo.toml`,
+/// rather than a `package.json`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ManifestPackage {
+    pub ecosystem: Ecosystem,
+    pub manifest: AbsoluteSystemPathBuf,
+    pub name: String,
+    pub version: Option<String>,
+    /// Names of the packages in the same workspace that this package
+    /// depends on
+    pub internal_dependencies: BTreeSet<String>,
+    /// Every other dependency, mapped to its version requirement
+    pub external_dependencies: BTreeMap<String, String>,
+    /// The external packages this package resolves to, if the workspace has a
+    /// lockfile
+    pub locked_dependencies: Option<HashSet<turborepo_lockfiles::Package>>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ManifestError {
+    #[error("unable to read {path}: {source}")]
+    Io {
+        path: AbsoluteSystemPathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("unable to parse {path}: {source}")]
+    Toml {
+        path: AbsoluteSystemPathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+    #[error("unable to parse {path} on line {line}: {message}")]
+    Syntax {
+        path: AbsoluteSystemPathBuf,
+        line: usize,
+        message: String,
+    },
+    #[error("{path}: missing {field}")]
+    MissingField {
+        path: AbsoluteSystemPathBuf,
+        field: &'static str,
+    },
+    #[error(transparent)]
+    Path(#[from] turbopath::PathError),
+    #[error(transparent)]
+    Glob(#[from] globwalk::GlobError),
+    #[error(transparent)]
+    Walk(#[from] globwalk::WalkError),
+}
+
+/// Discovers the packages of one ecosystem from its manifests. These run
+/// alongside the `PackageDiscovery` strategy, which only finds `package.json`
+/// workspaces, and an ecosystem without a workspace at the repo root has no
+/// packages.
+pub trait ManifestDiscovery: Send + Sync {
+    fn discover_manifests(
+        &self,
+        repo_root: &AbsoluteSystemPath,
+    ) -> Result<Vec<ManifestPackage>, ManifestError>;
+}
+
+fn read_manifest(path: &AbsoluteSystemPath) -> Result<Option<String>, ManifestError> {
+    match path.read_to_string() {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(source) => Err(ManifestError::Io {
+            path: path.to_owned(),
+            source,
+        }),
+    }
+}
+
+fn parse_toml<T: serde::de::DeserializeOwned>(
+    path: &AbsoluteSystemPath,
+    contents: &str,
+) -> Result<T, ManifestError> {
+    toml::from_str(contents).map_err(|source| ManifestError::Toml {
+        path: path.to_owned(),
+        source,
+    })
+}
+
+/// Finds the `manifest` of every workspace member matched by `members` and
+/// none of `exclude`.
+fn find_members(
+    repo_root: &AbsoluteSystemPath,
+    members: &[String],
+    exclude: &[String],
+    manifest: &str,
+) -> Result<Vec<AbsoluteSystemPathBuf>, ManifestError> {
+    let inclusions = members
+        .iter()
+        .map(|member| format!("{}/{manifest}", member.trim_end_matches('/')).parse())
+        .collect::<Result<Vec<globwalk::ValidatedGlob>, _>>()?;
+    let exclusions = exclude
+        .iter()
+        .map(|excluded| format!("{}/**", excluded.trim_end_matches('/')).parse())
+        .collect::<Result<Vec<globwalk::ValidatedGlob>, _>>()?;
+    let mut manifests: Vec<_> = globwalk::globwalk(
+        repo_root,
+        &inclusions,
+        &exclusions,
+        globwalk::WalkType::Files,
+    )?
+    .into_iter()
+    // The root manifest describes the workspace, its directory is the root
+    // package
+    .filter(|path| path.parent() != Some(repo_root))
+    .collect();
+    manifests.sort();
+    Ok(manifests)
+}
+
+/// A package in a lockfile that records the dependency graph, like
+/// `Cargo.lock` or `uv.lock`.
+struct LockedPackage {
+    name: String,
+    version: String,
+    /// Whether this is a workspace member rather than an external package
+    local: bool,
+    /// Dependencies by name, with a version when the name is ambiguous
+    dependencies: Vec<(String, Option<String>)>,
+}
+
+/// Collects the external packages that `name` transitively depends on. Other
+/// workspace members aren't followed, their dependencies belong to them.
+fn locked_closure(
+    packages: &[LockedPackage],
+    name: &str,
+    version: Option<&str>,
+) -> HashSet<turborepo_lockfiles::Package> {
+    let mut by_name: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (index, package) in packages.iter().enumerate() {
+        by_name.entry(&package.name).or_default().push(index);
+    }
+    let find = |name: &str, version: Option<&str>| {
+        by_name.get(name).and_then(|candidates| {
+            candidates
+                .iter()
+                .copied()
+                .find(|index| version.map_or(true, |version| packages[*index].version == version))
+        })
+    };
+
+    let mut closure = HashSet::new();
+    let Some(start) = find(name, version) else {
+        return closure;
+    };
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(index) = queue.pop_front() {
+        if !seen.insert(index) {
+            continue;
+        }
+        let package = &packages[index];
+        if package.local && index != start {
+            continue;
+        }
+        if !package.local {
+            closure.insert(turborepo_lockfiles::Package {
+                key: package.name.clone(),
+                version: package.version.clone(),
+            });
+        }
+        queue.extend(
+            package
+                .dependencies
+                .iter()
+                .filter_map(|(name, version)| find(name, version.as_deref())),
+        );
+    }
+    closure
+}
+
 #[cfg(test)]
 mod fallback_tests {
     use std::time::Duration;
@@ -0,0 +1,381 @@
+//! Discovers the crates of a Cargo workspace declared in the root
+//! `Cargo.toml`.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use serde::Deserialize;
+use turbopath::AbsoluteSystemPath;
+
+use super::{
+    find_members, locked_closure, parse_toml, read_manifest, Ecosystem, LockedPackage,
+    ManifestDiscovery, ManifestError, ManifestPackage,
+};
+
+const MANIFEST: &str = "Cargo.toml";
+const LOCKFILE: &str = "Cargo.lock";
+
+pub struct CargoDiscovery;
+
+#[derive(Deserialize, Default)]
+struct Manifest {
+    package: Option<Package>,
+    workspace: Option<Workspace>,
+    #[serde(flatten)]
+    dependencies: DependencyTables,
+    #[serde(default)]
+    target: BTreeMap<String, DependencyTables>,
+}
+
+#[derive(Deserialize, Default)]
+struct Workspace {
+    #[serde(default)]
+    members: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+    package: Option<WorkspacePackage>,
+    #[serde(default)]
+    dependencies: BTreeMap<String, Dependency>,
+}
+
+#[derive(Deserialize)]
+struct WorkspacePackage {
+    version: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    name: String,
+    version: Option<Inheritable>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Inheritable {
+    Value(String),
+    Workspace { workspace: bool },
+}
+
+#[derive(Deserialize, Default)]
+struct DependencyTables {
+    #[serde(default)]
+    dependencies: BTreeMap<String, Dependency>,
+    #[serde(default, rename = "dev-dependencies")]
+    dev_dependencies: BTreeMap<String, Dependency>,
+    #[serde(default, rename = "build-dependencies")]
+    build_dependencies: BTreeMap<String, Dependency>,
+}
+
+impl DependencyTables {
+    fn iter(&self) -> impl Iterator<Item = (&String, &Dependency)> {
+        self.dependencies
+            .iter()
+            .chain(&self.dev_dependencies)
+            .chain(&self.build_dependencies)
+    }
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+enum Dependency {
+    Version(String),
+    Detailed(DetailedDependency),
+}
+
+#[derive(Deserialize, Clone, Default)]
+struct DetailedDependency {
+    version: Option<String>,
+    path: Option<String>,
+    git: Option<String>,
+    package: Option<String>,
+    #[serde(default)]
+    workspace: bool,
+}
+
+#[derive(Deserialize)]
+struct Lockfile {
+    #[serde(default)]
+    package: Vec<LockfilePackage>,
+}
+
+#[derive(Deserialize)]
+struct LockfilePackage {
+    name: String,
+    version: String,
+    source: Option<String>,
+    #[serde(default)]
+    dependencies: Vec<String>,
+}
+
+impl From<LockfilePackage> for LockedPackage {
+    fn from(package: LockfilePackage) -> Self {
+        LockedPackage {
+            name: package.name,
+            version: package.version,
+            // Only workspace members and other path dependencies lack a source
+            local: package.source.is_none(),
+            // Entries are "name", "name version" or "name version (source)"
+            dependencies: package
+                .dependencies
+                .iter()
+                .map(|dependency| {
+                    let mut parts = dependency.split_whitespace();
+                    let name = parts.next().unwrap_or_default().to_string();
+                    (name, parts.next().map(|version| version.to_string()))
+                })
+                .collect(),
+        }
+    }
+}
+
+impl ManifestDiscovery for CargoDiscovery {
+    fn discover_manifests(
+        &self,
+        repo_root: &AbsoluteSystemPath,
+    ) -> Result<Vec<ManifestPackage>, ManifestError> {
+        let root_path = repo_root.join_component(MANIFEST);
+        let Some(contents) = read_manifest(&root_path)? else {
+            return Ok(Vec::new());
+        };
+        let root: Manifest = parse_toml(&root_path, &contents)?;
+        let Some(workspace) = root.workspace else {
+            return Ok(Vec::new());
+        };
+
+        let mut members = Vec::new();
+        for path in find_members(repo_root, &workspace.members, &workspace.exclude, MANIFEST)? {
+            let contents = read_manifest(&path)?.unwrap_or_default();
+            let manifest: Manifest = parse_toml(&path, &contents)?;
+            // Nested virtual manifests aren't crates
+            if manifest.package.is_some() {
+                members.push((path, manifest));
+            }
+        }
+        let names: HashSet<String> = members
+            .iter()
+            .filter_map(|(_, manifest)| manifest.package.as_ref())
+            .map(|package| package.name.clone())
+            .collect();
+
+        let lockfile_path = repo_root.join_component(LOCKFILE);
+        let locked: Option<Vec<LockedPackage>> = read_manifest(&lockfile_path)?
+            .map(|contents| parse_toml::<Lockfile>(&lockfile_path, &contents))
+            .transpose()?
+            .map(|lockfile| lockfile.package.into_iter().map(Into::into).collect());
+
+        members
+            .into_iter()
+            .map(|(path, manifest)| {
+                let package = manifest.package.expect("members have a package");
+                let version = match package.version {
+                    Some(Inheritable::Value(version)) => Some(version),
+                    Some(Inheritable::Workspace { workspace: true }) => workspace
+                        .package
+                        .as_ref()
+                        .and_then(|package| package.version.clone()),
+                    Some(Inheritable::Workspace { workspace: false }) | None => None,
+                };
+
+                let mut internal_dependencies = BTreeSet::new();
+                let mut external_dependencies = BTreeMap::new();
+                let tables =
+                    std::iter::once(&manifest.dependencies).chain(manifest.target.values());
+                for (key, dependency) in tables.flat_map(DependencyTables::iter) {
+                    let dependency = match dependency {
+                        Dependency::Detailed(DetailedDependency {
+                            workspace: true, ..
+                        }) => workspace
+                            .dependencies
+                            .get(key)
+                            .cloned()
+                            .unwrap_or_else(|| Dependency::Version("*".to_string())),
+                        dependency => dependency.clone(),
+                    };
+                    let (name, requirement, is_path) = match dependency {
+                        Dependency::Version(version) => (key.clone(), version, false),
+                        Dependency::Detailed(detailed) => (
+                            detailed.package.unwrap_or_else(|| key.clone()),
+                            detailed
+                                .version
+                                .or(detailed.git)
+                                .unwrap_or_else(|| "*".to_string()),
+                            detailed.path.is_some(),
+                        ),
+                    };
+                    // Crates can only come from the workspace through a path
+                    if is_path && names.contains(&name) {
+                        internal_dependencies.insert(name);
+                    } else {
+                        external_dependencies.insert(name, requirement);
+                    }
+                }
+
+                let locked_dependencies = locked
+                    .as_ref()
+                    .map(|locked| locked_closure(locked, &package.name, version.as_deref()));
+
+                Ok(ManifestPackage {
+                    ecosystem: Ecosystem::Cargo,
+                    manifest: path,
+                    name: package.name,
+                    version,
+                    internal_dependencies,
+                    external_dependencies,
+                    locked_dependencies,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::*;
+
+    fn package(key: &str, version: &str) -> turborepo_lockfiles::Package {
+        turborepo_lockfiles::Package {
+            key: key.to_string(),
+            version: version.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_cargo_workspace() -> Result<(), ManifestError> {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        let write = |path: &[&str], contents: &str| {
+            let file = root.join_components(path);
+            file.ensure_dir().unwrap();
+            file.create_with_contents(contents).unwrap();
+        };
+        write(
+            &["Cargo.toml"],
+            r#"
+            [workspace]
+            members = ["crates/*"]
+            exclude = ["crates/ignored"]
+
+            [workspace.package]
+            version = "0.2.0"
+
+            [workspace.dependencies]
+            serde = "1.0"
+            core = { path = "crates/core" }
+            "#,
+        );
+        write(
+            &["crates", "core", "Cargo.toml"],
+            r#"
+            [package]
+            name = "core"
+            version.workspace = true
+
+            [dependencies]
+            serde = { workspace = true }
+            "#,
+        );
+        write(
+            &["crates", "cli", "Cargo.toml"],
+            r#"
+            [package]
+            name = "cli"
+            version = "1.0.0"
+
+            [dependencies]
+            core.workspace = true
+            clap = { version = "4", features = ["derive"] }
+
+            [target.'cfg(unix)'.dev-dependencies]
+            libc = "0.2"
+            "#,
+        );
+        write(
+            &["crates", "ignored", "Cargo.toml"],
+            "[package]\nname = \"ignored\"\n",
+        );
+        write(
+            &["Cargo.lock"],
+            r#"
+            version = 3
+
+            [[package]]
+            name = "cli"
+            version = "1.0.0"
+            dependencies = ["clap", "core", "libc"]
+
+            [[package]]
+            name = "clap"
+            version = "4.4.0"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+
+            [[package]]
+            name = "core"
+            version = "0.2.0"
+            dependencies = ["serde 1.0.190"]
+
+            [[package]]
+            name = "libc"
+            version = "0.2.150"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+
+            [[package]]
+            name = "serde"
+            version = "1.0.190"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+            "#,
+        );
+
+        let packages = CargoDiscovery.discover_manifests(&root)?;
+        assert_eq!(
+            packages,
+            vec![
+                ManifestPackage {
+                    ecosystem: Ecosystem::Cargo,
+                    manifest: root.join_components(&["crates", "cli", "Cargo.toml"]),
+                    name: "cli".to_string(),
+                    version: Some("1.0.0".to_string()),
+                    internal_dependencies: BTreeSet::from(["core".to_string()]),
+                    external_dependencies: BTreeMap::from([
+                        ("clap".to_string(), "4".to_string()),
+                        ("libc".to_string(), "0.2".to_string()),
+                    ]),
+                    locked_dependencies: Some(HashSet::from([
+                        package("clap", "4.4.0"),
+                        package("libc", "0.2.150"),
+                    ])),
+                },
+                ManifestPackage {
+                    ecosystem: Ecosystem::Cargo,
+                    manifest: root.join_components(&["crates", "core", "Cargo.toml"]),
+                    name: "core".to_string(),
+                    version: Some("0.2.0".to_string()),
+                    internal_dependencies: BTreeSet::new(),
+                    external_dependencies: BTreeMap::from([(
+                        "serde".to_string(),
+                        "1.0".to_string()
+                    )]),
+                    locked_dependencies: Some(HashSet::from([package("serde", "1.0.190")])),
+                },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_workspace() -> Result<(), ManifestError> {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        assert!(CargoDiscovery.discover_manifests(&root)?.is_empty());
+
+        root.join_component("Cargo.toml")
+            .create_with_contents("[package]\nname = \"single\"\n")
+            .unwrap();
+        assert!(CargoDiscovery.discover_manifests(&root)?.is_empty());
+
+        Ok(())
+    }
+}
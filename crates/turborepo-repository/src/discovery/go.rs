@@ -0,0 +1,261 @@
+//! Discovers the modules of a Go workspace declared in the root `go.work`.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
+
+use super::{read_manifest, Ecosystem, ManifestDiscovery, ManifestError, ManifestPackage};
+
+const WORKSPACE: &str = "go.work";
+const MANIFEST: &str = "go.mod";
+const CHECKSUMS: &str = "go.sum";
+
+pub struct GoDiscovery;
+
+/// The directives of a `go.work` or `go.mod` file, each with its arguments.
+/// Blocks like `require ( ... )` are flattened into a directive per line.
+fn directives(
+    path: &AbsoluteSystemPath,
+    contents: &str,
+) -> Result<Vec<(String, Vec<String>)>, ManifestError> {
+    let mut directives = Vec::new();
+    // The directive of the open block and the line it was opened on
+    let mut block: Option<(String, usize)> = None;
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.split("//").next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut words = line
+            .split_whitespace()
+            .map(|word| word.trim_matches('"').to_string());
+        match &block {
+            Some(_) if line == ")" => block = None,
+            Some((verb, _)) => directives.push((verb.clone(), words.collect())),
+            None => {
+                let verb = words.next().expect("line is not empty");
+                let args: Vec<_> = words.collect();
+                match args.as_slice() {
+                    [open] if open == "(" => block = Some((verb, index + 1)),
+                    _ => directives.push((verb, args)),
+                }
+            }
+        }
+    }
+    match block {
+        Some((verb, line)) => Err(ManifestError::Syntax {
+            path: path.to_owned(),
+            line,
+            message: format!("unterminated {verb} block"),
+        }),
+        None => Ok(directives),
+    }
+}
+
+struct Module {
+    manifest: AbsoluteSystemPathBuf,
+    path: String,
+    requirements: Vec<(String, String)>,
+}
+
+impl Module {
+    fn load(dir: &AbsoluteSystemPath) -> Result<Option<Self>, ManifestError> {
+        let manifest = dir.join_component(MANIFEST);
+        let Some(contents) = read_manifest(&manifest)? else {
+            return Ok(None);
+        };
+        let mut path = None;
+        let mut requirements = Vec::new();
+        for (verb, mut args) in directives(&manifest, &contents)? {
+            match verb.as_str() {
+                "module" => path = args.pop(),
+                "require" if args.len() >= 2 => {
+                    requirements.push((args[0].clone(), args[1].clone()));
+                }
+                _ => (),
+            }
+        }
+        let path = path.ok_or_else(|| ManifestError::MissingField {
+            path: manifest.clone(),
+            field: "module",
+        })?;
+        Ok(Some(Module {
+            manifest,
+            path,
+            requirements,
+        }))
+    }
+
+    /// The module versions listed in the module's `go.sum`, if it has one.
+    fn checksums(&self) -> Result<Option<HashSet<turborepo_lockfiles::Package>>, ManifestError> {
+        let path = self
+            .manifest
+            .parent()
+            .expect("go.mod has a parent")
+            .join_component(CHECKSUMS);
+        let Some(contents) = read_manifest(&path)? else {
+            return Ok(None);
+        };
+        Ok(Some(
+            contents
+                .lines()
+                .filter_map(|line| {
+                    let mut words = line.split_whitespace();
+                    let key = words.next()?;
+                    // Both the module and its go.mod are summed
+                    let version = words.next()?.trim_end_matches("/go.mod");
+                    Some(turborepo_lockfiles::Package {
+                        key: key.to_string(),
+                        version: version.to_string(),
+                    })
+                })
+                .collect(),
+        ))
+    }
+}
+
+impl ManifestDiscovery for GoDiscovery {
+    fn discover_manifests(
+        &self,
+        repo_root: &AbsoluteSystemPath,
+    ) -> Result<Vec<ManifestPackage>, ManifestError> {
+        let workspace_path = repo_root.join_component(WORKSPACE);
+        let Some(contents) = read_manifest(&workspace_path)? else {
+            return Ok(Vec::new());
+        };
+
+        let mut modules = Vec::new();
+        for (verb, args) in directives(&workspace_path, &contents)? {
+            if verb != "use" {
+                continue;
+            }
+            for dir in args {
+                let dir = dir.trim_start_matches("./").trim_end_matches('/');
+                // A module at the root is the root package
+                if dir.is_empty() || dir == "." {
+                    continue;
+                }
+                let dir = repo_root.join_unix_path(turbopath::RelativeUnixPathBuf::new(dir)?)?;
+                if let Some(module) = Module::load(&dir)? {
+                    modules.push(module);
+                }
+            }
+        }
+        let paths: HashSet<String> = modules.iter().map(|module| module.path.clone()).collect();
+
+        modules
+            .into_iter()
+            .map(|module| {
+                let (internal, external): (Vec<_>, Vec<_>) = module
+                    .requirements
+                    .iter()
+                    .cloned()
+                    .partition(|(path, _)| paths.contains(path));
+                let locked_dependencies = module.checksums()?.map(|checksums| {
+                    checksums
+                        .into_iter()
+                        .filter(|package| !paths.contains(&package.key))
+                        .collect()
+                });
+                Ok(ManifestPackage {
+                    ecosystem: Ecosystem::Go,
+                    manifest: module.manifest,
+                    name: module.path,
+                    version: None,
+                    internal_dependencies: internal
+                        .into_iter()
+                        .map(|(path, _)| path)
+                        .collect::<BTreeSet<_>>(),
+                    external_dependencies: external.into_iter().collect::<BTreeMap<_, _>>(),
+                    locked_dependencies,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::*;
+
+    #[test]
+    fn test_go_workspace() -> Result<(), ManifestError> {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        let write = |path: &[&str], contents: &str| {
+            let file = root.join_components(path);
+            file.ensure_dir().unwrap();
+            file.create_with_contents(contents).unwrap();
+        };
+        write(
+            &["go.work"],
+            "go 1.21\n\nuse (\n\t./services/api\n\t./libs/log // shared logging\n)\n",
+        );
+        write(
+            &["services", "api", "go.mod"],
+            "module example.com/api\n\ngo 1.21\n\nrequire (\n\texample.com/log \
+             v0.0.0\n\tgithub.com/go-chi/chi/v5 v5.0.10\n\tgolang.org/x/sys v0.13.0 // \
+             indirect\n)\n",
+        );
+        write(
+            &["services", "api", "go.sum"],
+            "github.com/go-chi/chi/v5 v5.0.10 h1:abc=\ngithub.com/go-chi/chi/v5 v5.0.10/go.mod \
+             h1:def=\ngolang.org/x/sys v0.13.0 h1:ghi=\n",
+        );
+        write(&["libs", "log", "go.mod"], "module \"example.com/log\"\n");
+
+        let packages = GoDiscovery.discover_manifests(&root)?;
+        assert_eq!(
+            packages,
+            vec![
+                ManifestPackage {
+                    ecosystem: Ecosystem::Go,
+                    manifest: root.join_components(&["services", "api", "go.mod"]),
+                    name: "example.com/api".to_string(),
+                    version: None,
+                    internal_dependencies: BTreeSet::from(["example.com/log".to_string()]),
+                    external_dependencies: BTreeMap::from([
+                        (
+                            "github.com/go-chi/chi/v5".to_string(),
+                            "v5.0.10".to_string()
+                        ),
+                        ("golang.org/x/sys".to_string(), "v0.13.0".to_string()),
+                    ]),
+                    locked_dependencies: Some(HashSet::from([
+                        turborepo_lockfiles::Package {
+                            key: "github.com/go-chi/chi/v5".to_string(),
+                            version: "v5.0.10".to_string(),
+                        },
+                        turborepo_lockfiles::Package {
+                            key: "golang.org/x/sys".to_string(),
+                            version: "v0.13.0".to_string(),
+                        },
+                    ])),
+                },
+                ManifestPackage {
+                    ecosystem: Ecosystem::Go,
+                    manifest: root.join_components(&["libs", "log", "go.mod"]),
+                    name: "example.com/log".to_string(),
+                    version: None,
+                    internal_dependencies: BTreeSet::new(),
+                    external_dependencies: BTreeMap::new(),
+                    locked_dependencies: None,
+                },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unterminated_block() {
+        let root =
+            AbsoluteSystemPathBuf::new(if cfg!(windows) { r"C:\repo" } else { "/repo" }).unwrap();
+        let result = directives(&root.join_component("go.work"), "go 1.21\nuse (\n./a\n");
+        assert!(matches!(result, Err(ManifestError::Syntax { line: 2, .. })));
+    }
+}
@@ -0,0 +1,343 @@
+//! Discovers the projects of a uv workspace declared in the root
+//! `pyproject.toml`.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::Deserialize;
+use turbopath::AbsoluteSystemPath;
+
+use super::{
+    find_members, locked_closure, parse_toml, read_manifest, Ecosystem, LockedPackage,
+    ManifestDiscovery, ManifestError, ManifestPackage,
+};
+
+const MANIFEST: &str = "pyproject.toml";
+const LOCKFILE: &str = "uv.lock";
+
+pub struct PythonDiscovery;
+
+#[derive(Deserialize, Default)]
+struct PyProject {
+    project: Option<Project>,
+    #[serde(default)]
+    tool: Tool,
+}
+
+#[derive(Deserialize, Default)]
+struct Tool {
+    #[serde(default)]
+    uv: Uv,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+struct Uv {
+    workspace: Option<Workspace>,
+    #[serde(default)]
+    dev_dependencies: Vec<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct Workspace {
+    #[serde(default)]
+    members: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Project {
+    name: String,
+    version: Option<String>,
+    #[serde(default)]
+    dependencies: Vec<String>,
+    #[serde(default)]
+    optional_dependencies: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct Lockfile {
+    #[serde(default)]
+    package: Vec<LockfilePackage>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct LockfilePackage {
+    name: String,
+    version: String,
+    #[serde(default)]
+    source: HashMap<String, toml::Value>,
+    #[serde(default)]
+    dependencies: Vec<LockfileDependency>,
+    #[serde(default)]
+    optional_dependencies: BTreeMap<String, Vec<LockfileDependency>>,
+    #[serde(default)]
+    dev_dependencies: BTreeMap<String, Vec<LockfileDependency>>,
+}
+
+#[derive(Deserialize)]
+struct LockfileDependency {
+    name: String,
+    version: Option<String>,
+}
+
+impl From<LockfilePackage> for LockedPackage {
+    fn from(package: LockfilePackage) -> Self {
+        let local = ["editable", "virtual", "directory"]
+            .iter()
+            .any(|kind| package.source.contains_key(*kind));
+        LockedPackage {
+            name: package.name,
+            version: package.version,
+            local,
+            dependencies: package
+                .dependencies
+                .into_iter()
+                .chain(package.optional_dependencies.into_values().flatten())
+                .chain(package.dev_dependencies.into_values().flatten())
+                .map(|dependency| (dependency.name, dependency.version))
+                .collect(),
+        }
+    }
+}
+
+/// Normalizes a distribution name as described in PEP 503, which is how
+/// requirements and lockfiles refer to it.
+fn normalize(name: &str) -> String {
+    let mut normalized = String::with_capacity(name.len());
+    for c in name.chars() {
+        if matches!(c, '-' | '_' | '.') {
+            if !normalized.ends_with('-') {
+                normalized.push('-');
+            }
+        } else {
+            normalized.push(c.to_ascii_lowercase());
+        }
+    }
+    normalized
+}
+
+/// Splits a PEP 508 requirement like `requests[socks]>=2.0; python_version >
+/// "3.8"` into its name and version specifier.
+fn parse_requirement(requirement: &str) -> (String, String) {
+    let requirement = requirement.split(';').next().unwrap_or_default().trim();
+    let name_end = requirement
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+        .unwrap_or(requirement.len());
+    let (name, rest) = requirement.split_at(name_end);
+    let specifier = match rest.trim_start().strip_prefix('[') {
+        Some(extras) => extras.split_once(']').map_or("", |(_, rest)| rest),
+        None => rest,
+    }
+    .trim()
+    .trim_start_matches('(')
+    .trim_end_matches(')')
+    .trim();
+    let specifier = if specifier.is_empty() { "*" } else { specifier };
+    (normalize(name), specifier.to_string())
+}
+
+impl ManifestDiscovery for PythonDiscovery {
+    fn discover_manifests(
+        &self,
+        repo_root: &AbsoluteSystemPath,
+    ) -> Result<Vec<ManifestPackage>, ManifestError> {
+        let root_path = repo_root.join_component(MANIFEST);
+        let Some(contents) = read_manifest(&root_path)? else {
+            return Ok(Vec::new());
+        };
+        let root: PyProject = parse_toml(&root_path, &contents)?;
+        let Some(workspace) = root.tool.uv.workspace else {
+            return Ok(Vec::new());
+        };
+
+        let mut members = Vec::new();
+        for path in find_members(repo_root, &workspace.members, &workspace.exclude, MANIFEST)? {
+            let contents = read_manifest(&path)?.unwrap_or_default();
+            let pyproject: PyProject = parse_toml(&path, &contents)?;
+            let project = pyproject
+                .project
+                .ok_or_else(|| ManifestError::MissingField {
+                    path: path.clone(),
+                    field: "project",
+                })?;
+            members.push((path, project, pyproject.tool.uv.dev_dependencies));
+        }
+        // Requirements refer to projects by their normalized name
+        let names: HashMap<String, String> = members
+            .iter()
+            .map(|(_, project, _)| (normalize(&project.name), project.name.clone()))
+            .collect();
+
+        let lockfile_path = repo_root.join_component(LOCKFILE);
+        let locked: Option<Vec<LockedPackage>> = read_manifest(&lockfile_path)?
+            .map(|contents| parse_toml::<Lockfile>(&lockfile_path, &contents))
+            .transpose()?
+            .map(|lockfile| lockfile.package.into_iter().map(Into::into).collect());
+
+        Ok(members
+            .into_iter()
+            .map(|(path, project, dev_dependencies)| {
+                let mut internal_dependencies = BTreeSet::new();
+                let mut external_dependencies = BTreeMap::new();
+                let requirements = project
+                    .dependencies
+                    .iter()
+                    .chain(project.optional_dependencies.values().flatten())
+                    .chain(&dev_dependencies);
+                for requirement in requirements {
+                    let (name, specifier) = parse_requirement(requirement);
+                    match names.get(&name) {
+                        Some(member) => {
+                            internal_dependencies.insert(member.clone());
+                        }
+                        None => {
+                            external_dependencies.insert(name, specifier);
+                        }
+                    }
+                }
+
+                let locked_dependencies = locked
+                    .as_ref()
+                    .map(|locked| locked_closure(locked, &normalize(&project.name), None));
+
+                ManifestPackage {
+                    ecosystem: Ecosystem::Python,
+                    manifest: path,
+                    name: project.name,
+                    version: project.version,
+                    internal_dependencies,
+                    external_dependencies,
+                    locked_dependencies,
+                }
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+    use test_case::test_case;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::*;
+
+    #[test_case("requests", ("requests", "*") ; "bare name")]
+    #[test_case("Flask_SQLAlchemy>=3.0", ("flask-sqlalchemy", ">=3.0") ; "normalized name")]
+    #[test_case("requests[socks] >=2.0,<3", ("requests", ">=2.0,<3") ; "extras")]
+    #[test_case("pywin32 (>=306); sys_platform == 'win32'", ("pywin32", ">=306") ; "marker")]
+    fn test_parse_requirement(requirement: &str, expected: (&str, &str)) {
+        let (name, specifier) = parse_requirement(requirement);
+        assert_eq!((name.as_str(), specifier.as_str()), expected);
+    }
+
+    #[test]
+    fn test_uv_workspace() -> Result<(), ManifestError> {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = AbsoluteSystemPathBuf::try_from(tmp.path()).unwrap();
+        let write = |path: &[&str], contents: &str| {
+            let file = root.join_components(path);
+            file.ensure_dir().unwrap();
+            file.create_with_contents(contents).unwrap();
+        };
+        write(
+            &["pyproject.toml"],
+            "[tool.uv.workspace]\nmembers = [\"packages/*\"]\n",
+        );
+        write(
+            &["packages", "api", "pyproject.toml"],
+            r#"
+            [project]
+            name = "api"
+            version = "0.1.0"
+            dependencies = ["My_Utils", "fastapi>=0.100"]
+
+            [tool.uv]
+            dev-dependencies = ["pytest"]
+            "#,
+        );
+        write(
+            &["packages", "utils", "pyproject.toml"],
+            "[project]\nname = \"my-utils\"\n",
+        );
+        write(
+            &["uv.lock"],
+            r#"
+            version = 1
+
+            [[package]]
+            name = "api"
+            version = "0.1.0"
+            source = { editable = "packages/api" }
+            dependencies = [{ name = "fastapi" }, { name = "my-utils" }]
+
+            [package.dev-dependencies]
+            dev = [{ name = "pytest" }]
+
+            [[package]]
+            name = "fastapi"
+            version = "0.104.1"
+            source = { registry = "https://pypi.org/simple" }
+            dependencies = [{ name = "pydantic" }]
+
+            [[package]]
+            name = "my-utils"
+            version = "0.0.0"
+            source = { editable = "packages/utils" }
+            dependencies = [{ name = "pydantic" }]
+
+            [[package]]
+            name = "pydantic"
+            version = "2.5.0"
+            source = { registry = "https://pypi.org/simple" }
+
+            [[package]]
+            name = "pytest"
+            version = "7.4.3"
+            source = { registry = "https://pypi.org/simple" }
+            "#,
+        );
+
+        let package = |key: &str, version: &str| turborepo_lockfiles::Package {
+            key: key.to_string(),
+            version: version.to_string(),
+        };
+        let packages = PythonDiscovery.discover_manifests(&root)?;
+        assert_eq!(
+            packages,
+            vec![
+                ManifestPackage {
+                    ecosystem: Ecosystem::Python,
+                    manifest: root.join_components(&["packages", "api", "pyproject.toml"]),
+                    name: "api".to_string(),
+                    version: Some("0.1.0".to_string()),
+                    internal_dependencies: BTreeSet::from(["my-utils".to_string()]),
+                    external_dependencies: BTreeMap::from([
+                        ("fastapi".to_string(), ">=0.100".to_string()),
+                        ("pytest".to_string(), "*".to_string()),
+                    ]),
+                    locked_dependencies: Some(HashSet::from([
+                        package("fastapi", "0.104.1"),
+                        package("pydantic", "2.5.0"),
+                        package("pytest", "7.4.3"),
+                    ])),
+                },
+                ManifestPackage {
+                    ecosystem: Ecosystem::Python,
+                    manifest: root.join_components(&["packages", "utils", "pyproject.toml"]),
+                    name: "my-utils".to_string(),
+                    version: None,
+                    internal_dependencies: BTreeSet::new(),
+                    external_dependencies: BTreeMap::new(),
+                    locked_dependencies: Some(HashSet::from([package("pydantic", "2.5.0")])),
+                },
+            ]
+        );
+
+        Ok(())
+    }
+}
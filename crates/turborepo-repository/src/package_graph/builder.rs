@@ -16,8 +16,8 @@ use turborepo_lockfiles::Lockfile;
 use super::{PackageGraph, PackageInfo, PackageName, PackageNode};
 use crate::{
     discovery::{
-        self, CachingPackageDiscovery, LocalPackageDiscoveryBuilder, PackageDiscovery,
-        PackageDiscoveryBuilder,
+        self, CachingPackageDiscovery, LocalPackageDiscoveryBuilder, ManifestDiscovery,
+        ManifestPackage, PackageDiscovery, PackageDiscoveryBuilder,
     },
     package_json::PackageJson,
 };
@@ -29,6 +29,7 @@ pub struct PackageGraphBuilder<'a, T> {
     package_jsons: Option<HashMap<AbsoluteSystemPathBuf, PackageJson>>,
     lockfile: Option<Box<dyn Lockfile>>,
     package_discovery: T,
+    manifest_discovery: Vec<Box<dyn ManifestDiscovery>>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -59,6 +60,8 @@ pub enum Error {
     Lockfile(#[from] turborepo_lockfiles::Error),
     #[error(transparent)]
     Discovery(#[from] crate::discovery::Error),
+    #[error(transparent)]
+    Manifest(#[from] crate::discovery::ManifestError),
 }
 
 impl<'a> PackageGraphBuilder<'a, LocalPackageDiscoveryBuilder> {
@@ -74,6 +77,7 @@ impl<'a> PackageGraphBuilder<'a, LocalPackageDiscoveryBuilder> {
             is_single_package: false,
             package_jsons: None,
             lockfile: None,
+            manifest_discovery: Vec::new(),
         }
     }
 }
@@ -99,6 +103,16 @@ impl<'a, P> PackageGraphBuilder<'a, P> {
         self
     }
 
+    /// Add packages from other ecosystems' manifests, e.g. the crates of a
+    /// Cargo workspace, alongside the `package.json` workspaces.
+    pub fn with_manifest_discovery(
+        mut self,
+        discovery: impl IntoIterator<Item = Box<dyn ManifestDiscovery>>,
+    ) -> Self {
+        self.manifest_discovery.extend(discovery);
+        self
+    }
+
     /// Set the package discovery strategy to use. Note that whatever strategy
     /// selected here will be wrapped in a `CachingPackageDiscovery` to
     /// prevent unnecessary work during building.
@@ -113,6 +127,7 @@ impl<'a, P> PackageGraphBuilder<'a, P> {
             package_jsons: self.package_jsons,
             lockfile: self.lockfile,
             package_discovery: discovery,
+            manifest_discovery: self.manifest_discovery,
         }
     }
 }
@@ -150,6 +165,10 @@ struct BuildState<'a, S, T> {
     package_jsons: Option<HashMap<AbsoluteSystemPathBuf, PackageJson>>,
     state: std::marker::PhantomData<S>,
     package_discovery: T,
+    manifest_discovery: Vec<Box<dyn ManifestDiscovery>>,
+    // Internal dependencies of the packages from `manifest_discovery`, which
+    // unlike npm workspaces can't be derived from their package.json
+    manifest_dependencies: HashMap<PackageName, HashSet<PackageName>>,
 }
 
 // Allows us to perform workspace discovery and parse package jsons
@@ -196,6 +215,7 @@ where
             package_jsons,
             lockfile,
             package_discovery,
+            manifest_discovery,
         } = builder;
         let mut workspaces = HashMap::new();
         workspaces.insert(
@@ -220,6 +240,8 @@ where
             package_discovery: CachingPackageDiscovery::new(
                 package_discovery.build().map_err(Into::into)?,
             ),
+            manifest_discovery,
+            manifest_dependencies: HashMap::new(),
         })
     }
 }
@@ -259,6 +281,50 @@ impl<'a, T: PackageDiscovery> BuildState<'a, ResolvedPackageManager, T> {
         Ok(())
     }
 
+    fn add_manifest_package(&mut self, package: ManifestPackage) -> Result<(), Error> {
+        let ManifestPackage {
+            ecosystem,
+            manifest,
+            name,
+            version,
+            internal_dependencies,
+            external_dependencies,
+            locked_dependencies,
+        } = package;
+        let entry = PackageInfo {
+            package_json: PackageJson {
+                name: Some(name.clone()),
+                version,
+                ..Default::default()
+            },
+            package_json_path: AnchoredSystemPathBuf::relative_path_between(
+                self.repo_root,
+                &manifest,
+            ),
+            unresolved_external_dependencies: Some(external_dependencies),
+            transitive_dependencies: locked_dependencies,
+            ecosystem: Some(ecosystem),
+        };
+        let name = PackageName::Other(name);
+        if let Some(existing) = self.workspaces.get(&name) {
+            return Err(Error::DuplicateWorkspace {
+                name: name.to_string(),
+                path: entry.package_json_path.to_string(),
+                existing_path: existing.package_json_path.to_string(),
+            });
+        }
+        self.workspaces.insert(name.clone(), entry);
+        self.manifest_dependencies.insert(
+            name.clone(),
+            internal_dependencies
+                .into_iter()
+                .map(PackageName::Other)
+                .collect(),
+        );
+        self.add_node(PackageNode::Workspace(name));
+        Ok(())
+    }
+
     // need our own type
     #[tracing::instrument(skip(self))]
     async fn parse_package_jsons(mut self) -> Result<BuildState<'a, ResolvedWorkspaces, T>, Error> {
@@ -293,6 +359,12 @@ impl<'a, T: PackageDiscovery> BuildState<'a, ResolvedPackageManager, T> {
             }
         }
 
+        for discovery in std::mem::take(&mut self.manifest_discovery) {
+            for package in discovery.discover_manifests(self.repo_root)? {
+                self.add_manifest_package(package)?;
+            }
+        }
+
         let Self {
             repo_root,
             single,
@@ -301,6 +373,7 @@ impl<'a, T: PackageDiscovery> BuildState<'a, ResolvedPackageManager, T> {
             node_lookup,
             lockfile,
             package_discovery,
+            manifest_dependencies,
             ..
         } = self;
         Ok(BuildState {
@@ -313,6 +386,8 @@ impl<'a, T: PackageDiscovery> BuildState<'a, ResolvedPackageManager, T> {
             package_discovery,
             package_jsons: None,
             state: std::marker::PhantomData,
+            manifest_discovery: Vec::new(),
+            manifest_dependencies,
         })
     }
 
@@ -349,15 +424,24 @@ impl<'a, T: PackageDiscovery> BuildState<'a, ResolvedWorkspaces, T> {
             .iter()
             .map(|(name, entry)| {
                 // TODO avoid clone
-                (
-                    name.clone(),
-                    Dependencies::new(
+                let deps = match self.manifest_dependencies.remove(name) {
+                    // External dependencies were already recorded when the
+                    // package was added
+                    Some(internal) => Dependencies {
+                        internal,
+                        external: entry
+                            .unresolved_external_dependencies
+                            .clone()
+                            .unwrap_or_default(),
+                    },
+                    None => Dependencies::new(
                         self.repo_root,
                         &entry.package_json_path,
                         &self.workspaces,
                         entry.package_json.all_dependencies(),
                     ),
-                )
+                };
+                (name.clone(), deps)
             })
             .collect::<Vec<_>>();
         for (name, deps) in split_deps {
@@ -450,6 +534,8 @@ impl<'a, T: PackageDiscovery> BuildState<'a, ResolvedWorkspaces, T> {
             package_jsons: None,
             state: std::marker::PhantomData,
             package_discovery,
+            manifest_discovery: Vec::new(),
+            manifest_dependencies: HashMap::new(),
         })
     }
 }
@@ -458,6 +544,8 @@ impl<'a, T: PackageDiscovery> BuildState<'a, ResolvedLockfile, T> {
     fn all_external_dependencies(&self) -> Result<HashMap<String, HashMap<String, String>>, Error> {
         self.workspaces
             .values()
+            // Other ecosystems resolve their dependencies with their own lockfiles
+            .filter(|entry| entry.ecosystem.is_none())
             .map(|entry| {
                 let workspace_path = entry
                     .package_json_path
@@ -490,7 +578,9 @@ impl<'a, T: PackageDiscovery> BuildState<'a, ResolvedLockfile, T> {
             self.all_external_dependencies()?,
         )?;
         for (_, entry) in self.workspaces.iter_mut() {
-            entry.transitive_dependencies = closures.remove(&entry.unix_dir_str()?);
+            if entry.ecosystem.is_none() {
+                entry.transitive_dependencies = closures.remove(&entry.unix_dir_str()?);
+            }
         }
         Ok(())
     }
@@ -523,9 +613,9 @@ impl<'a, T: PackageDiscovery> BuildState<'a, ResolvedLockfile, T> {
     }
 }
 
-struct Dependencies {
-    internal: HashSet<PackageName>,
-    external: BTreeMap<String, String>, // Package name and version
+pub(super) struct Dependencies {
+    pub(super) internal: HashSet<PackageName>,
+    pub(super) external: BTreeMap<String, String>, // Package name and version
 }
 
 impl Dependencies {
@@ -746,6 +836,7 @@ mod test {
                     package_json_path: AnchoredSystemPathBuf::from_raw("unused").unwrap(),
                     unresolved_external_dependencies: None,
                     transitive_dependencies: None,
+                    ecosystem: None,
                 },
             );
             map
@@ -10,7 +10,8 @@ use turborepo_graph_utils as graph;
 use turborepo_lockfiles::Lockfile;
 
 use crate::{
-    discovery::LocalPackageDiscoveryBuilder, package_json::PackageJson,
+    discovery::{Ecosystem, LocalPackageDiscoveryBuilder},
+    package_json::PackageJson,
     package_manager::PackageManager,
 };
 
@@ -58,6 +59,10 @@ pub struct PackageInfo {
     pub package_json_path: AnchoredSystemPathBuf,
     pub unresolved_external_dependencies: Option<BTreeMap<PackageKey, PackageVersion>>, /* name -> version */
     pub transitive_dependencies: Option<HashSet<turborepo_lockfiles::Package>>,
+    /// The ecosystem of a package discovered from its own manifest, in which
+    /// case `package_json` is synthesized from that manifest and
+    /// `package_json_path` points to it. `None` for npm workspaces.
+    pub ecosystem: Option<Ecosystem>,
 }
 
 impl PackageInfo {
@@ -320,6 +325,72 @@ impl PackageGraph {
             .collect()
     }
 
+    /// Returns the external dependencies `package_json` declares when read as
+    /// the manifest of `package`, e.g. the package's manifest at an earlier
+    /// commit. Returns `None` if `package` isn't in the graph.
+    pub fn external_dependencies_from(
+        &self,
+        repo_root: &AbsoluteSystemPath,
+        package: &PackageName,
+        package_json: &PackageJson,
+    ) -> Option<BTreeMap<String, String>> {
+        let info = self.packages.get(package)?;
+        let dependencies = builder::Dependencies::new(
+            repo_root,
+            &info.package_json_path,
+            &self.packages,
+            package_json.all_dependencies(),
+        );
+        Some(dependencies.external)
+    }
+
+    /// Returns the packages that `packages` need in production, following
+    /// only `dependencies` and `optionalDependencies`: the workspaces they
+    /// depend on transitively, including themselves, and the lockfile
+    /// packages those workspaces resolve to.
+    pub fn production_closure<'a, I: IntoIterator<Item = &'a PackageName>>(
+        &self,
+        repo_root: &AbsoluteSystemPath,
+        packages: I,
+    ) -> Result<(HashSet<PackageName>, HashSet<turborepo_lockfiles::Package>), Error> {
+        let mut workspaces = HashSet::new();
+        let mut external_deps = HashMap::new();
+        let mut stack = packages.into_iter().cloned().collect::<Vec<_>>();
+        while let Some(package) = stack.pop() {
+            let Some(info) = self.packages.get(&package) else {
+                continue;
+            };
+            if !workspaces.insert(package) {
+                continue;
+            }
+            let dependencies = builder::Dependencies::new(
+                repo_root,
+                &info.package_json_path,
+                &self.packages,
+                info.package_json.production_dependencies(),
+            );
+            stack.extend(dependencies.internal);
+            // Other ecosystems resolve their dependencies with their own lockfiles
+            if info.ecosystem.is_none() {
+                external_deps.insert(
+                    info.package_path().to_unix().to_string(),
+                    dependencies.external.into_iter().collect(),
+                );
+            }
+        }
+
+        let external = match self.lockfile() {
+            Some(lockfile) => {
+                turborepo_lockfiles::all_transitive_closures(lockfile, external_deps)?
+                    .into_values()
+                    .flatten()
+                    .collect()
+            }
+            None => HashSet::new(),
+        };
+        Ok((workspaces, external))
+    }
+
     /// Returns a list of changed packages based on the contents of a previous
     /// `Lockfile`. This assumes that none of the package.json in the package
     /// change, it is the responsibility of the caller to verify this.
@@ -331,6 +402,7 @@ impl PackageGraph {
 
         let external_deps = self
             .packages()
+            .filter(|(_name, info)| info.ecosystem.is_none())
             .filter_map(|(_name, info)| {
                 info.unresolved_external_dependencies.as_ref().map(|dep| {
                     (
@@ -353,8 +425,9 @@ impl PackageGraph {
             self.packages
                 .iter()
                 .filter(|(_name, info)| {
-                    closures.get(info.package_path().to_unix().as_str())
-                        != info.transitive_dependencies.as_ref()
+                    info.ecosystem.is_none()
+                        && closures.get(info.package_path().to_unix().as_str())
+                            != info.transitive_dependencies.as_ref()
                 })
                 .map(|(name, info)| match name {
                     PackageName::Other(n) => {
@@ -667,6 +740,75 @@ mod test {
         );
     }
 
+    #[tokio::test]
+    async fn test_production_closure() {
+        let root =
+            AbsoluteSystemPathBuf::new(if cfg!(windows) { r"C:\repo" } else { "/repo" }).unwrap();
+        let pkg_graph = PackageGraph::builder(
+            &root,
+            PackageJson::from_value(json!({ "name": "root" })).unwrap(),
+        )
+        .with_package_discovery(MockDiscovery)
+        .with_package_jsons(Some({
+            let mut map = HashMap::new();
+            map.insert(
+                root.join_components(&["package_a", "package.json"]),
+                PackageJson::from_value(json!({
+                    "name": "foo",
+                    "dependencies": {
+                        "a": "1",
+                        "bar": "*"
+                    },
+                    "devDependencies": {
+                        "b": "1",
+                        "baz": "*"
+                    }
+                }))
+                .unwrap(),
+            );
+            map.insert(
+                root.join_components(&["package_b", "package.json"]),
+                PackageJson::from_value(json!({
+                    "name": "bar",
+                    "optionalDependencies": {
+                        "c": "1",
+                    }
+                }))
+                .unwrap(),
+            );
+            map.insert(
+                root.join_components(&["package_c", "package.json"]),
+                PackageJson::from_value(json!({
+                    "name": "baz",
+                    "dependencies": {
+                        "b": "1",
+                    }
+                }))
+                .unwrap(),
+            );
+            map
+        }))
+        .with_lockfile(Some(Box::new(MockLockfile {})))
+        .build()
+        .await
+        .unwrap();
+
+        let (workspaces, external) = pkg_graph
+            .production_closure(&root, [&PackageName::from("foo")])
+            .unwrap();
+        assert_eq!(
+            workspaces,
+            HashSet::from([PackageName::from("foo"), PackageName::from("bar")])
+        );
+        assert_eq!(
+            external,
+            HashSet::from([
+                turborepo_lockfiles::Package::new("key:a", "1"),
+                turborepo_lockfiles::Package::new("key:c", "1"),
+            ])
+        );
+    }
+
     #[tokio::test]
     async fn test_circular_dependency() {
         let root =
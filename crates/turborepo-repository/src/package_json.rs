@@ -72,6 +72,15 @@ impl PackageJson {
             .chain(self.optional_dependencies.iter().flatten())
             .chain(self.dependencies.iter().flatten())
     }
+
+    /// The dependencies that get installed in production, i.e. everything
+    /// but devDependencies
+    pub fn production_dependencies(&self) -> impl Iterator<Item = (&String, &String)> + '_ {
+        self.optional_dependencies
+            .iter()
+            .flatten()
+            .chain(self.dependencies.iter().flatten())
+    }
 }
 
 impl FromStr for PackageJson {
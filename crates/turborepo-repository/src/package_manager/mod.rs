@@ -346,7 +346,10 @@ impl PackageManager {
         ignores.iter().map(|s| s.to_string())
     }
 
-    fn get_configured_workspace_globs(
+    /// Returns the inclusion and exclusion globs as they're written in the
+    /// workspace configuration, without the exclusions the package manager
+    /// adds on its own
+    pub fn get_configured_workspace_globs(
         &self,
         root_path: &AbsoluteSystemPath,
     ) -> Result<(Vec<String>, Vec<String>), Error> {
@@ -419,8 +422,8 @@ impl PackageManager {
         Self::detect_package_manager(repo_root)
     }
 
-    // Attempts to read the package manager from the package.json
-    fn read_package_manager(pkg: &PackageJson) -> Result<Option<Self>, Error> {
+    /// Attempts to read the package manager from the package.json
+    pub fn read_package_manager(pkg: &PackageJson) -> Result<Option<Self>, Error> {
         let Some(package_manager) = &pkg.package_manager else {
             return Ok(None);
         };
@@ -512,15 +515,31 @@ impl PackageManager {
         root_package_json: &PackageJson,
     ) -> Result<Box<dyn Lockfile>, Error> {
         let lockfile_path = self.lockfile_path(root_path);
+        let contents = match self {
+            PackageManager::Bun => print_bun_lockfile(root_path, lockfile_path.as_std_path())?,
+            _ => lockfile_path.read()?,
+        };
+        self.parse_lockfile(root_package_json, &contents)
+    }
+
+    /// Parses the contents of a lockfile that isn't the one on disk, e.g. one
+    /// read from an earlier commit. Bun's lockfile is binary and can only be
+    /// printed by bun itself, so it gets written to a temporary file first.
+    #[tracing::instrument(skip(self, root_package_json, contents))]
+    pub fn parse_lockfile_contents(
+        &self,
+        root_path: &AbsoluteSystemPath,
+        root_package_json: &PackageJson,
+        contents: Vec<u8>,
+    ) -> Result<Box<dyn Lockfile>, Error> {
         let contents = match self {
             PackageManager::Bun => {
-                Command::new(which("bun")?)
-                    .arg(lockfile_path.to_string())
-                    .current_dir(root_path.to_string())
-                    .output()?
-                    .stdout
+                let tmp_dir = tempfile::tempdir()?;
+                let lockfile_path = tmp_dir.path().join(bun::LOCKFILE);
+                fs::write(&lockfile_path, contents)?;
+                print_bun_lockfile(root_path, &lockfile_path)?
             }
-            _ => lockfile_path.read()?,
+            _ => contents,
         };
         self.parse_lockfile(root_package_json, &contents)
     }
@@ -594,6 +613,19 @@ impl PackageManager {
     }
 }
 
+// Running bun with a binary lockfile as its only argument prints it in the
+// yarn v1 format
+fn print_bun_lockfile(
+    root_path: &AbsoluteSystemPath,
+    lockfile_path: &std::path::Path,
+) -> Result<Vec<u8>, Error> {
+    Ok(Command::new(which("bun")?)
+        .arg(lockfile_path)
+        .current_dir(root_path.to_string())
+        .output()?
+        .stdout)
+}
+
 #[cfg(test)]
 mod tests {
     use std::{collections::HashSet, fs::File};
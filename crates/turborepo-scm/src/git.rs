@@ -10,6 +10,8 @@ impl SCM {
     pub fn get_current_branch(&self, path: &AbsoluteSystemPath) -> Result<String, Error> {
         match self {
             Self::Git(git) => git.get_current_branch(),
+            Self::Hg(hg) => hg.get_current_branch(),
+            Self::Jj(jj) => jj.get_current_branch(),
             Self::Manual => Err(Error::GitRequired(path.to_owned())),
         }
     }
@@ -17,6 +19,8 @@ impl SCM {
     pub fn get_current_sha(&self, path: &AbsoluteSystemPath) -> Result<String, Error> {
         match self {
             Self::Git(git) => git.get_current_sha(),
+            Self::Hg(hg) => hg.get_current_sha(),
+            Self::Jj(jj) => jj.get_current_sha(),
             Self::Manual => Err(Error::GitRequired(path.to_owned())),
         }
     }
@@ -29,10 +33,28 @@ impl SCM {
     ) -> Result<HashSet<AnchoredSystemPathBuf>, Error> {
         match self {
             Self::Git(git) => git.changed_files(turbo_root, from_commit, to_commit),
+            Self::Hg(hg) => hg.changed_files(turbo_root, from_commit, to_commit),
+            Self::Jj(jj) => jj.changed_files(turbo_root, from_commit, to_commit),
             Self::Manual => Err(Error::GitRequired(turbo_root.to_owned())),
         }
     }
 
+    /// Finds the best common ancestor of `base` and `head`, i.e. the commit a
+    /// branch forked from.
+    pub fn merge_base(
+        &self,
+        path: &AbsoluteSystemPath,
+        base: &str,
+        head: &str,
+    ) -> Result<String, Error> {
+        match self {
+            Self::Git(git) => git.merge_base(base, head),
+            Self::Hg(hg) => hg.merge_base(base, head),
+            Self::Jj(jj) => jj.merge_base(base, head),
+            Self::Manual => Err(Error::GitRequired(path.to_owned())),
+        }
+    }
+
     pub fn previous_content(
         &self,
         from_commit: &str,
@@ -40,6 +62,8 @@ impl SCM {
     ) -> Result<Vec<u8>, Error> {
         match self {
             Self::Git(git) => git.previous_content(from_commit, file_path),
+            Self::Hg(hg) => hg.previous_content(from_commit, file_path),
+            Self::Jj(jj) => jj.previous_content(from_commit, file_path),
             Self::Manual => Err(Error::GitRequired(file_path.to_owned())),
         }
     }
@@ -92,6 +116,12 @@ impl Git {
         Ok(output.trim().to_owned())
     }
 
+    fn merge_base(&self, base: &str, head: &str) -> Result<String, Error> {
+        let output = self.execute_git_command(&["merge-base", base, head], "")?;
+        let output = String::from_utf8(output)?;
+        Ok(output.trim().to_owned())
+    }
+
     fn changed_files(
         &self,
         turbo_root: &AbsoluteSystemPath,
@@ -236,7 +266,7 @@ mod tests {
     use which::which;
 
     use super::previous_content;
-    use crate::{git::changed_files, Error};
+    use crate::{git::changed_files, Error, SCM};
 
     fn setup_repository() -> Result<(TempDir, Repository), Error> {
         let repo_root = tempfile::tempdir()?;
@@ -382,6 +412,17 @@ mod tests {
 
         assert_eq!(merge_base, second_commit_oid);
 
+        let scm = SCM::new(&AbsoluteSystemPathBuf::try_from(repo_root.path()).unwrap());
+        let root = AbsoluteSystemPathBuf::try_from(repo_root.path()).unwrap();
+        assert_eq!(
+            scm.merge_base(
+                &root,
+                &third_commit_oid.to_string(),
+                &fourth_commit_oid.to_string()
+            )?,
+            second_commit_oid.to_string()
+        );
+
         let files = changed_files(
             repo_root.path().to_path_buf(),
             repo_root.path().to_path_buf(),
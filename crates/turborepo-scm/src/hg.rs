@@ -0,0 +1,319 @@
+//! Support for Mercurial and Sapling repositories. Sapling started as a fork
+//! of Mercurial and keeps its command line, so both are driven the same way
+//! through their own binary.
+
+use std::{backtrace::Backtrace, borrow::Cow, collections::HashSet, process::Command};
+
+use turbopath::{
+    AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPath, AnchoredSystemPathBuf,
+    RelativeUnixPathBuf,
+};
+
+use crate::{
+    package_deps::{hash_listed_files, GitHashes},
+    Error,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HgFlavor {
+    Mercurial,
+    Sapling,
+}
+
+impl HgFlavor {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HgFlavor::Mercurial => "mercurial",
+            HgFlavor::Sapling => "sapling",
+        }
+    }
+
+    fn binary(&self) -> &'static str {
+        match self {
+            HgFlavor::Mercurial => "hg",
+            HgFlavor::Sapling => "sl",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Hg {
+    root: AbsoluteSystemPathBuf,
+    bin: AbsoluteSystemPathBuf,
+    flavor: HgFlavor,
+}
+
+impl Hg {
+    pub(crate) fn find(path_in_repo: &AbsoluteSystemPath) -> Option<Self> {
+        // Look for the repository's metadata directory instead of asking the
+        // binary, since `sl` is also the name of an unrelated, popular program
+        let (root, flavors): (_, &[HgFlavor]) = path_in_repo.ancestors().find_map(|dir| {
+            if dir.join_component(".sl").exists() {
+                Some((dir, &[HgFlavor::Sapling][..]))
+            } else if dir.join_component(".hg").exists() {
+                // Older Sapling versions also use .hg
+                Some((dir, &[HgFlavor::Mercurial, HgFlavor::Sapling][..]))
+            } else {
+                None
+            }
+        })?;
+
+        flavors.iter().find_map(|flavor| {
+            let bin = which::which(flavor.binary()).ok()?;
+            let bin = AbsoluteSystemPathBuf::try_from(bin.as_path()).ok()?;
+            Some(Self {
+                root: root.to_owned(),
+                bin,
+                flavor: *flavor,
+            })
+        })
+    }
+
+    pub fn flavor(&self) -> HgFlavor {
+        self.flavor
+    }
+
+    pub(crate) fn get_current_branch(&self) -> Result<String, Error> {
+        // Sapling doesn't have named branches, only bookmarks
+        let template = match self.flavor {
+            HgFlavor::Mercurial => "{if(activebookmark, activebookmark, branch)}",
+            HgFlavor::Sapling => "{activebookmark}",
+        };
+        let output = self.execute(&["log", "-r", ".", "-T", template])?;
+        Ok(String::from_utf8(output)?.trim().to_owned())
+    }
+
+    pub(crate) fn get_current_sha(&self) -> Result<String, Error> {
+        let output = self.execute(&["log", "-r", ".", "-T", "{node}"])?;
+        Ok(String::from_utf8(output)?.trim().to_owned())
+    }
+
+    pub(crate) fn merge_base(&self, base: &str, head: &str) -> Result<String, Error> {
+        let revset = format!("ancestor({}, {})", revision(base), revision(head));
+        let output = self.execute(&["log", "-r", &revset, "-T", "{node}"])?;
+        Ok(String::from_utf8(output)?.trim().to_owned())
+    }
+
+    pub(crate) fn changed_files(
+        &self,
+        turbo_root: &AbsoluteSystemPath,
+        from_commit: Option<&str>,
+        to_commit: &str,
+    ) -> Result<HashSet<AnchoredSystemPathBuf>, Error> {
+        let pattern = self.pattern(turbo_root)?;
+        let to_commit = revision(to_commit);
+        let mut files = HashSet::new();
+
+        // Changes in the working directory, including files that aren't tracked
+        // yet but aren't ignored either
+        let mut args = vec![
+            "status",
+            "-mardu",
+            "--no-status",
+            "-0",
+            "--rev",
+            &*to_commit,
+        ];
+        args.extend(pattern.as_deref());
+        self.add_files(&mut files, turbo_root, &self.execute(&args)?)?;
+
+        if let Some(from_commit) = from_commit {
+            let merge_base = format!("ancestor({}, {})", revision(from_commit), to_commit);
+            let mut args = vec![
+                "status",
+                "-mard",
+                "--no-status",
+                "-0",
+                "--rev",
+                merge_base.as_str(),
+                "--rev",
+                &*to_commit,
+            ];
+            args.extend(pattern.as_deref());
+            self.add_files(&mut files, turbo_root, &self.execute(&args)?)?;
+        }
+
+        Ok(files)
+    }
+
+    pub(crate) fn previous_content(
+        &self,
+        from_commit: &str,
+        file_path: &AbsoluteSystemPath,
+    ) -> Result<Vec<u8>, Error> {
+        let path = format!("path:{}", self.root.anchor(file_path)?.to_unix());
+        self.execute(&["cat", "-r", &revision(from_commit), &path])
+    }
+
+    pub(crate) fn get_package_file_hashes<S: AsRef<str>>(
+        &self,
+        turbo_root: &AbsoluteSystemPath,
+        package_path: &AnchoredSystemPath,
+        inputs: &[S],
+        include_default_files: bool,
+    ) -> Result<GitHashes, Error> {
+        hash_listed_files(
+            &self.root,
+            turbo_root,
+            package_path,
+            inputs,
+            include_default_files,
+            |package_dir| self.list_files(package_dir),
+        )
+    }
+
+    /// Lists the files in `dir` that are tracked or would be if they were
+    /// added, i.e. that aren't ignored. Paths are relative to the repository
+    /// root.
+    fn list_files(&self, dir: &AbsoluteSystemPath) -> Result<Vec<RelativeUnixPathBuf>, Error> {
+        let pattern = self.pattern(dir)?;
+        let mut args = vec!["status", "-macu", "--no-status", "-0"];
+        args.extend(pattern.as_deref());
+        parse_paths(&self.execute(&args)?)
+    }
+
+    // Matches everything under `dir`, or `None` for the whole repository
+    fn pattern(&self, dir: &AbsoluteSystemPath) -> Result<Option<String>, Error> {
+        let dir = self.root.anchor(dir)?.to_unix();
+        Ok((!dir.as_str().is_empty()).then(|| format!("path:{}", dir)))
+    }
+
+    fn add_files(
+        &self,
+        files: &mut HashSet<AnchoredSystemPathBuf>,
+        turbo_root: &AbsoluteSystemPath,
+        stdout: &[u8],
+    ) -> Result<(), Error> {
+        for path in parse_paths(stdout)? {
+            let path = self.root.join_unix_path(path)?;
+            files.insert(turbo_root.anchor(&path)?);
+        }
+        Ok(())
+    }
+
+    fn execute(&self, args: &[&str]) -> Result<Vec<u8>, Error> {
+        let output = Command::new(self.bin.as_std_path())
+            .args(args)
+            // Paths are always printed relative to the repository root
+            .args(["--config", "ui.relative-paths=false"])
+            .current_dir(&self.root)
+            // Ignore user configuration that changes the output format
+            .env("HGPLAIN", "1")
+            .output()?;
+
+        if output.status.success() {
+            Ok(output.stdout)
+        } else {
+            Err(Error::Vcs(
+                self.flavor.name(),
+                String::from_utf8_lossy(&output.stderr).to_string(),
+                Backtrace::capture(),
+            ))
+        }
+    }
+}
+
+// Parses the NUL-separated, repository relative paths printed with `-0`
+fn parse_paths(stdout: &[u8]) -> Result<Vec<RelativeUnixPathBuf>, Error> {
+    stdout
+        .split(|byte| *byte == b'\0')
+        .filter(|path| !path.is_empty())
+        .map(|path| {
+            let path = String::from_utf8(path.to_vec())?;
+            Ok(AnchoredSystemPathBuf::from_raw(path)?.to_unix())
+        })
+        .collect()
+}
+
+/// Translates git's name for the checked out commit, which callers use as a
+/// default, into the revset Mercurial and Sapling use for it. `HEAD~1` and
+/// `HEAD^` work unchanged on `.`.
+fn revision(rev: &str) -> Cow<'_, str> {
+    match rev.strip_prefix("HEAD") {
+        Some(suffix) if suffix.is_empty() || suffix.starts_with(['~', '^']) => {
+            Cow::Owned(format!(".{suffix}"))
+        }
+        _ => Cow::Borrowed(rev),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, process::Command};
+
+    use test_case::test_case;
+    use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPathBuf, RelativeUnixPathBuf};
+
+    use super::{parse_paths, revision};
+    use crate::SCM;
+
+    #[test_case("HEAD", "." ; "head")]
+    #[test_case("HEAD~2", ".~2" ; "ancestor")]
+    #[test_case("HEAD^", ".^" ; "parent")]
+    #[test_case("main", "main" ; "bookmark")]
+    #[test_case("HEADLINE", "HEADLINE" ; "bookmark starting with head")]
+    fn test_revision(rev: &str, expected: &str) {
+        assert_eq!(revision(rev), expected);
+    }
+
+    #[test]
+    fn test_parse_paths() {
+        let paths = parse_paths(b"package.json\0packages/ui/index.ts\0").unwrap();
+        assert_eq!(
+            paths,
+            [
+                RelativeUnixPathBuf::new("package.json").unwrap(),
+                RelativeUnixPathBuf::new("packages/ui/index.ts").unwrap(),
+            ]
+        );
+        assert!(parse_paths(b"").unwrap().is_empty());
+    }
+
+    fn require_hg_cmd(repo_root: &AbsoluteSystemPathBuf, args: &[&str]) {
+        let output = Command::new("hg")
+            .args(args)
+            .current_dir(repo_root)
+            .env("HGPLAIN", "1")
+            .env("HGUSER", "test <test@example.com>")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "hg {:?} failed", args);
+    }
+
+    #[test]
+    fn test_mercurial_repository() {
+        // Mercurial isn't available everywhere the tests run
+        if which::which("hg").is_err() {
+            return;
+        }
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp_dir.path())
+            .unwrap()
+            .to_realpath()
+            .unwrap();
+        require_hg_cmd(&repo_root, &["init"]);
+        let lockfile = repo_root.join_component("package-lock.json");
+        lockfile.create_with_contents("{}").unwrap();
+        require_hg_cmd(&repo_root, &["commit", "-A", "-m", "first"]);
+        lockfile.create_with_contents("{\"version\": 2}").unwrap();
+        repo_root
+            .join_component("new.js")
+            .create_with_contents("let x = 1;")
+            .unwrap();
+
+        let scm = SCM::new(&repo_root);
+        assert_eq!(scm.mode(), "mercurial");
+        assert_eq!(scm.previous_content("HEAD", &lockfile).unwrap(), b"{}");
+        assert_eq!(scm.get_current_branch(&repo_root).unwrap(), "default");
+        assert_eq!(scm.get_current_sha(&repo_root).unwrap().len(), 40);
+
+        let changed = scm.changed_files(&repo_root, None, "HEAD").unwrap();
+        assert_eq!(
+            changed,
+            HashSet::from([
+                AnchoredSystemPathBuf::from_raw("package-lock.json").unwrap(),
+                AnchoredSystemPathBuf::from_raw("new.js").unwrap(),
+            ])
+        );
+    }
+}
@@ -0,0 +1,301 @@
+//! Support for Jujutsu repositories. Repositories colocated with git are
+//! handled by the git backend, since git sees the same commits.
+
+use std::{backtrace::Backtrace, borrow::Cow, collections::HashSet, process::Command};
+
+use turbopath::{
+    AbsoluteSystemPath, AbsoluteSystemPathBuf, AnchoredSystemPath, AnchoredSystemPathBuf,
+    RelativeUnixPathBuf,
+};
+
+use crate::{
+    package_deps::{hash_listed_files, GitHashes},
+    Error,
+};
+
+#[derive(Debug)]
+pub struct Jj {
+    root: AbsoluteSystemPathBuf,
+    bin: AbsoluteSystemPathBuf,
+}
+
+impl Jj {
+    pub(crate) fn find(path_in_repo: &AbsoluteSystemPath) -> Option<Self> {
+        let root = path_in_repo
+            .ancestors()
+            .find(|dir| dir.join_component(".jj").exists())?;
+        let bin = which::which("jj").ok()?;
+        let bin = AbsoluteSystemPathBuf::try_from(bin.as_path()).ok()?;
+        Some(Self {
+            root: root.to_owned(),
+            bin,
+        })
+    }
+
+    /// Jujutsu has no checked out branch, so this is the closest bookmark on
+    /// an ancestor of the working copy
+    pub(crate) fn get_current_branch(&self) -> Result<String, Error> {
+        let output = self.execute(&[
+            "log",
+            "-r",
+            "heads(::@ & bookmarks())",
+            "--no-graph",
+            "-T",
+            "bookmarks ++ \"\\n\"",
+        ])?;
+        let output = String::from_utf8(output)?;
+        // Conflicted or unsynced bookmarks are marked with a trailing ? or *
+        let branch = output
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .trim_end_matches(['?', '*']);
+        Ok(branch.to_owned())
+    }
+
+    pub(crate) fn get_current_sha(&self) -> Result<String, Error> {
+        self.commit_id(&revision("HEAD"))
+    }
+
+    pub(crate) fn merge_base(&self, base: &str, head: &str) -> Result<String, Error> {
+        self.commit_id(&merge_base(base, head))
+    }
+
+    pub(crate) fn changed_files(
+        &self,
+        turbo_root: &AbsoluteSystemPath,
+        from_commit: Option<&str>,
+        to_commit: &str,
+    ) -> Result<HashSet<AnchoredSystemPathBuf>, Error> {
+        let fileset = self.fileset(turbo_root)?;
+        let to_commit = revision(to_commit);
+        let mut files = HashSet::new();
+
+        // Jujutsu snapshots the working copy into the @ commit, including new
+        // files that aren't ignored
+        let mut args = vec!["diff", "--name-only", "--from", &*to_commit, "--to", "@"];
+        args.extend(fileset.as_deref());
+        self.add_files(&mut files, turbo_root, &self.execute(&args)?)?;
+
+        if let Some(from_commit) = from_commit {
+            let merge_base = merge_base(from_commit, &to_commit);
+            let mut args = vec![
+                "diff",
+                "--name-only",
+                "--from",
+                merge_base.as_str(),
+                "--to",
+                &*to_commit,
+            ];
+            args.extend(fileset.as_deref());
+            self.add_files(&mut files, turbo_root, &self.execute(&args)?)?;
+        }
+
+        Ok(files)
+    }
+
+    pub(crate) fn previous_content(
+        &self,
+        from_commit: &str,
+        file_path: &AbsoluteSystemPath,
+    ) -> Result<Vec<u8>, Error> {
+        let path = fileset(self.root.anchor(file_path)?.to_unix().as_str());
+        self.execute(&["file", "show", "-r", &revision(from_commit), &path])
+    }
+
+    pub(crate) fn get_package_file_hashes<S: AsRef<str>>(
+        &self,
+        turbo_root: &AbsoluteSystemPath,
+        package_path: &AnchoredSystemPath,
+        inputs: &[S],
+        include_default_files: bool,
+    ) -> Result<GitHashes, Error> {
+        hash_listed_files(
+            &self.root,
+            turbo_root,
+            package_path,
+            inputs,
+            include_default_files,
+            |package_dir| self.list_files(package_dir),
+        )
+    }
+
+    /// Lists the files in `dir` in the working copy commit, which are all the
+    /// files that aren't ignored. Paths are relative to the repository root.
+    fn list_files(&self, dir: &AbsoluteSystemPath) -> Result<Vec<RelativeUnixPathBuf>, Error> {
+        let fileset = self.fileset(dir)?;
+        let mut args = vec!["file", "list", "-r", "@"];
+        args.extend(fileset.as_deref());
+        parse_paths(&self.execute(&args)?)
+    }
+
+    fn commit_id(&self, revset: &str) -> Result<String, Error> {
+        let output = self.execute(&[
+            "log",
+            "-r",
+            revset,
+            "--no-graph",
+            "-T",
+            "commit_id ++ \"\\n\"",
+        ])?;
+        let output = String::from_utf8(output)?;
+        Ok(output.lines().next().unwrap_or_default().to_owned())
+    }
+
+    // Matches everything under `dir`, or `None` for the whole repository
+    fn fileset(&self, dir: &AbsoluteSystemPath) -> Result<Option<String>, Error> {
+        let dir = self.root.anchor(dir)?.to_unix();
+        Ok((!dir.as_str().is_empty()).then(|| fileset(dir.as_str())))
+    }
+
+    fn add_files(
+        &self,
+        files: &mut HashSet<AnchoredSystemPathBuf>,
+        turbo_root: &AbsoluteSystemPath,
+        stdout: &[u8],
+    ) -> Result<(), Error> {
+        for path in parse_paths(stdout)? {
+            let path = self.root.join_unix_path(path)?;
+            files.insert(turbo_root.anchor(&path)?);
+        }
+        Ok(())
+    }
+
+    fn execute(&self, args: &[&str]) -> Result<Vec<u8>, Error> {
+        let output = Command::new(self.bin.as_std_path())
+            .args(args)
+            .args(["--color", "never", "--no-pager"])
+            // Paths are printed relative to the working directory
+            .current_dir(&self.root)
+            .output()?;
+
+        if output.status.success() {
+            Ok(output.stdout)
+        } else {
+            Err(Error::Vcs(
+                "jujutsu",
+                String::from_utf8_lossy(&output.stderr).to_string(),
+                Backtrace::capture(),
+            ))
+        }
+    }
+}
+
+// Parses the newline-separated, repository relative paths jj prints
+fn parse_paths(stdout: &[u8]) -> Result<Vec<RelativeUnixPathBuf>, Error> {
+    String::from_utf8(stdout.to_vec())?
+        .lines()
+        .filter(|path| !path.is_empty())
+        .map(|path| Ok(AnchoredSystemPathBuf::from_raw(path)?.to_unix()))
+        .collect()
+}
+
+// A fileset matching `path` and everything under it, relative to the
+// repository root. Debug formatting quotes and escapes it like a jj string.
+fn fileset(path: &str) -> String {
+    format!("root:{path:?}")
+}
+
+fn merge_base(base: &str, head: &str) -> String {
+    format!("heads(::({}) & ::({}))", revision(base), revision(head))
+}
+
+/// Translates git's names for the checked out commit and its ancestors into
+/// revsets. Jujutsu's working copy commit `@` holds uncommitted changes, so
+/// git's `HEAD` is its parent `@-`.
+fn revision(rev: &str) -> Cow<'_, str> {
+    let Some(suffix) = rev.strip_prefix("HEAD") else {
+        return Cow::Borrowed(rev);
+    };
+    let ancestors = if suffix.is_empty() {
+        Some(0)
+    } else if let Some(count) = suffix.strip_prefix('~') {
+        match count {
+            "" => Some(1),
+            count => count.parse::<usize>().ok(),
+        }
+    } else if suffix.chars().all(|c| c == '^') {
+        Some(suffix.len())
+    } else {
+        None
+    };
+
+    match ancestors {
+        Some(ancestors) => Cow::Owned(format!("@-{}", "-".repeat(ancestors))),
+        None => Cow::Borrowed(rev),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, process::Command};
+
+    use test_case::test_case;
+    use turbopath::{AbsoluteSystemPathBuf, AnchoredSystemPathBuf};
+
+    use super::{fileset, revision};
+    use crate::SCM;
+
+    #[test_case("HEAD", "@-" ; "head")]
+    #[test_case("HEAD~", "@--" ; "tilde")]
+    #[test_case("HEAD~3", "@----" ; "ancestor")]
+    #[test_case("HEAD^^", "@---" ; "grandparent")]
+    #[test_case("main", "main" ; "bookmark")]
+    #[test_case("HEADLINE", "HEADLINE" ; "bookmark starting with head")]
+    fn test_revision(rev: &str, expected: &str) {
+        assert_eq!(revision(rev), expected);
+    }
+
+    #[test]
+    fn test_fileset() {
+        assert_eq!(fileset("packages/ui"), "root:\"packages/ui\"");
+        assert_eq!(fileset("odd \"name\""), "root:\"odd \\\"name\\\"\"");
+    }
+
+    fn require_jj_cmd(repo_root: &AbsoluteSystemPathBuf, args: &[&str]) {
+        let output = Command::new("jj")
+            .args(args)
+            .current_dir(repo_root)
+            .env("JJ_USER", "test")
+            .env("JJ_EMAIL", "test@example.com")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "jj {:?} failed", args);
+    }
+
+    #[test]
+    fn test_jujutsu_repository() {
+        // Jujutsu isn't available everywhere the tests run
+        if which::which("jj").is_err() {
+            return;
+        }
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let repo_root = AbsoluteSystemPathBuf::try_from(tmp_dir.path())
+            .unwrap()
+            .to_realpath()
+            .unwrap();
+        require_jj_cmd(&repo_root, &["git", "init"]);
+        let lockfile = repo_root.join_component("package-lock.json");
+        lockfile.create_with_contents("{}").unwrap();
+        require_jj_cmd(&repo_root, &["commit", "-m", "first"]);
+        lockfile.create_with_contents("{\"version\": 2}").unwrap();
+        repo_root
+            .join_component("new.js")
+            .create_with_contents("let x = 1;")
+            .unwrap();
+
+        let scm = SCM::new(&repo_root);
+        assert_eq!(scm.mode(), "jujutsu");
+        assert_eq!(scm.previous_content("HEAD", &lockfile).unwrap(), b"{}");
+        assert_eq!(scm.get_current_sha(&repo_root).unwrap().len(), 40);
+
+        let changed = scm.changed_files(&repo_root, None, "HEAD").unwrap();
+        assert_eq!(
+            changed,
+            HashSet::from([
+                AnchoredSystemPathBuf::from_raw("package-lock.json").unwrap(),
+                AnchoredSystemPathBuf::from_raw("new.js").unwrap(),
+            ])
+        );
+    }
+}
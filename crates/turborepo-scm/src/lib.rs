@@ -4,8 +4,9 @@
 #![deny(clippy::all)]
 
 //! Turborepo's library for interacting with source control management (SCM).
-//! Currently we only support git. We use SCM for finding changed files,
-//! for getting the previous version of a lockfile, and for hashing files.
+//! We support git, Mercurial, Sapling and Jujutsu. We use SCM for finding
+//! changed files, for getting the previous version of a lockfile, and for
+//! hashing files.
 
 use std::{
     backtrace::{self, Backtrace},
@@ -18,8 +19,12 @@ use thiserror::Error;
 use tracing::debug;
 use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf, PathError, RelativeUnixPathBuf};
 
+use crate::{hg::Hg, jj::Jj};
+
 pub mod git;
 mod hash_object;
+pub mod hg;
+pub mod jj;
 mod ls_tree;
 pub mod manual;
 pub mod package_deps;
@@ -35,9 +40,11 @@ pub enum Error {
     ),
     #[error("git error: {0}")]
     Git(String, #[backtrace] backtrace::Backtrace),
+    #[error("{0} error: {1}")]
+    Vcs(&'static str, String, #[backtrace] backtrace::Backtrace),
     #[error(
-        "{0} is not part of a git repository. git is required for operations based on source \
-         control"
+        "{0} is not part of a git, Mercurial, Sapling or Jujutsu repository. One of them is \
+         required for operations based on source control"
     )]
     GitRequired(AbsoluteSystemPathBuf),
     #[error(
@@ -205,21 +212,43 @@ fn find_git_root(turbo_root: &AbsoluteSystemPath) -> Result<AbsoluteSystemPathBu
 #[derive(Debug)]
 pub enum SCM {
     Git(Git),
+    Hg(Hg),
+    Jj(Jj),
     Manual,
 }
 
 impl SCM {
     #[tracing::instrument]
     pub fn new(path_in_repo: &AbsoluteSystemPath) -> SCM {
-        Git::find(path_in_repo).map(SCM::Git).unwrap_or_else(|e| {
-            debug!("{}, continuing with manual hashing", e);
-            SCM::Manual
-        })
+        // Git goes first so that Jujutsu repositories colocated with git keep
+        // using it
+        let err = match Git::find(path_in_repo) {
+            Ok(git) => return SCM::Git(git),
+            Err(err) => err,
+        };
+        if let Some(jj) = Jj::find(path_in_repo) {
+            return SCM::Jj(jj);
+        }
+        if let Some(hg) = Hg::find(path_in_repo) {
+            return SCM::Hg(hg);
+        }
+        debug!("{}, continuing with manual hashing", err);
+        SCM::Manual
     }
 
     pub fn is_manual(&self) -> bool {
         matches!(self, SCM::Manual)
     }
+
+    /// The name of the SCM, for logging and telemetry
+    pub fn mode(&self) -> &'static str {
+        match self {
+            SCM::Git(_) => "git",
+            SCM::Hg(hg) => hg.flavor().name(),
+            SCM::Jj(_) => "jujutsu",
+            SCM::Manual => "manual",
+        }
+    }
 }
 
 #[cfg(test)]
@@ -5,7 +5,7 @@ use tracing::debug;
 use turbopath::{AbsoluteSystemPath, AnchoredSystemPath, PathError, RelativeUnixPathBuf};
 use turborepo_telemetry::events::task::{FileHashMethod, PackageTaskEventBuilder};
 
-use crate::{hash_object::hash_objects, Error, Git, SCM};
+use crate::{hash_object::hash_objects, hg::HgFlavor, Error, Git, SCM};
 
 pub type GitHashes = HashMap<RelativeUnixPathBuf, String>;
 
@@ -42,48 +42,61 @@ impl SCM {
             .iter()
             .any(|input| input.as_ref() == INPUT_INCLUDE_DEFAULT_FILES);
 
-        match self {
+        let (result, method) = match self {
             SCM::Manual => {
                 if let Some(telemetry) = telemetry {
                     telemetry.track_file_hash_method(FileHashMethod::Manual);
                 }
-                crate::manual::get_package_file_hashes_without_git(
+                return crate::manual::get_package_file_hashes_without_git(
                     turbo_root,
                     package_path,
                     inputs,
                     include_default_files,
-                )
+                );
             }
-            SCM::Git(git) => {
-                let result = git.get_package_file_hashes(
+            SCM::Git(git) => (
+                git.get_package_file_hashes(
                     turbo_root,
                     package_path,
                     inputs,
                     include_default_files,
+                ),
+                FileHashMethod::Git,
+            ),
+            SCM::Hg(hg) => (
+                hg.get_package_file_hashes(turbo_root, package_path, inputs, include_default_files),
+                match hg.flavor() {
+                    HgFlavor::Mercurial => FileHashMethod::Mercurial,
+                    HgFlavor::Sapling => FileHashMethod::Sapling,
+                },
+            ),
+            SCM::Jj(jj) => (
+                jj.get_package_file_hashes(turbo_root, package_path, inputs, include_default_files),
+                FileHashMethod::Jujutsu,
+            ),
+        };
+        match result {
+            Ok(hashes) => {
+                if let Some(telemetry) = telemetry {
+                    telemetry.track_file_hash_method(method);
+                }
+                Ok(hashes)
+            }
+            Err(err) => {
+                debug!(
+                    "failed to use {} to hash files: {}. Falling back to manual",
+                    self.mode(),
+                    err
                 );
-                match result {
-                    Ok(hashes) => {
-                        if let Some(telemetry) = telemetry {
-                            telemetry.track_file_hash_method(FileHashMethod::Git);
-                        }
-                        Ok(hashes)
-                    }
-                    Err(err) => {
-                        debug!(
-                            "failed to use git to hash files: {}. Falling back to manual",
-                            err
-                        );
-                        if let Some(telemetry) = telemetry {
-                            telemetry.track_file_hash_method(FileHashMethod::Manual);
-                        }
-                        crate::manual::get_package_file_hashes_without_git(
-                            turbo_root,
-                            package_path,
-                            inputs,
-                            include_default_files,
-                        )
-                    }
+                if let Some(telemetry) = telemetry {
+                    telemetry.track_file_hash_method(FileHashMethod::Manual);
                 }
+                crate::manual::get_package_file_hashes_without_git(
+                    turbo_root,
+                    package_path,
+                    inputs,
+                    include_default_files,
+                )
             }
         }
     }
@@ -94,7 +107,9 @@ impl SCM {
         files: impl Iterator<Item = impl AsRef<AnchoredSystemPath>>,
     ) -> Result<GitHashes, Error> {
         match self {
-            SCM::Manual => crate::manual::hash_files(turbo_root, files, false),
+            SCM::Manual | SCM::Hg(_) | SCM::Jj(_) => {
+                crate::manual::hash_files(turbo_root, files, false)
+            }
             SCM::Git(git) => git.hash_files(turbo_root, files),
         }
     }
@@ -119,23 +134,14 @@ impl Git {
         inputs: &[S],
         include_default_files: bool,
     ) -> Result<GitHashes, Error> {
-        // no inputs, and no $TURBO_DEFAULT$
-        if inputs.is_empty() {
-            return self.get_package_file_hashes_from_index(turbo_root, package_path);
-        }
-
-        // we have inputs, but no $TURBO_DEFAULT$
-        if !include_default_files {
-            return self.get_package_file_hashes_from_inputs(
-                turbo_root,
-                package_path,
-                inputs,
-                true,
-            );
-        }
-
-        // we have inputs, and $TURBO_DEFAULT$
-        self.get_package_file_hashes_from_inputs_and_index(turbo_root, package_path, inputs)
+        hash_package_files(
+            &self.root,
+            turbo_root,
+            package_path,
+            inputs,
+            include_default_files,
+            || self.get_package_file_hashes_from_index(turbo_root, package_path),
+        )
     }
 
     #[tracing::instrument(skip(self, turbo_root))]
@@ -172,121 +178,181 @@ impl Git {
         hash_objects(&self.root, process_relative_to, to_hash, &mut hashes)?;
         Ok(hashes)
     }
+}
 
-    #[tracing::instrument(skip(self, turbo_root, inputs))]
-    fn get_package_file_hashes_from_inputs<S: AsRef<str>>(
-        &self,
-        turbo_root: &AbsoluteSystemPath,
-        package_path: &AnchoredSystemPath,
-        inputs: &[S],
-        include_configs: bool,
-    ) -> Result<GitHashes, Error> {
-        let full_pkg_path = turbo_root.resolve(package_path);
-        let package_unix_path_buf = package_path.to_unix();
-        let package_unix_path = package_unix_path_buf.as_str();
+/// Hashes the files of a package matched by `inputs`. The files an SCM doesn't
+/// ignore are the default, hashed by `default_file_hashes` when there are no
+/// inputs or they include $TURBO_DEFAULT$.
+fn hash_package_files<S: AsRef<str>>(
+    scm_root: &AbsoluteSystemPath,
+    turbo_root: &AbsoluteSystemPath,
+    package_path: &AnchoredSystemPath,
+    inputs: &[S],
+    include_default_files: bool,
+    default_file_hashes: impl FnOnce() -> Result<GitHashes, Error>,
+) -> Result<GitHashes, Error> {
+    // no inputs, and no $TURBO_DEFAULT$
+    if inputs.is_empty() {
+        return default_file_hashes();
+    }
 
-        let mut inputs = inputs
-            .iter()
-            .map(|s| s.as_ref().to_string())
-            .collect::<Vec<String>>();
-
-        if include_configs {
-            // Add in package.json and turbo.json to input patterns. Both file paths are
-            // relative to pkgPath
-            //
-            // - package.json is an input because if the `scripts` in the package.json
-            //   change (i.e. the tasks that turbo executes), we want a cache miss, since
-            //   any existing cache could be invalid.
-            // - turbo.json because it's the definition of the tasks themselves. The root
-            //   turbo.json is similarly included in the global hash. This file may not
-            //   exist in the workspace, but that is ok, because it will get ignored
-            //   downstream.
-            inputs.push("package.json".to_string());
-            inputs.push("turbo.json".to_string());
-        }
+    // we have inputs, but no $TURBO_DEFAULT$
+    if !include_default_files {
+        return hash_package_inputs(scm_root, turbo_root, package_path, inputs, true);
+    }
 
-        // The input patterns are relative to the package.
-        // However, we need to change the globbing to be relative to the repo root.
-        // Prepend the package path to each of the input patterns.
+    // we have inputs, and $TURBO_DEFAULT$
+    hash_package_inputs_and_defaults(
+        scm_root,
+        turbo_root,
+        package_path,
+        inputs,
+        default_file_hashes,
+    )
+}
+
+/// Hashes a package's files for SCMs that can list the files they track, but
+/// don't store git's blob hashes for them. Files are hashed the way git hashes
+/// blobs, so the hashes match those computed in a git checkout of the same
+/// content. `list_files` returns the files under a directory that aren't
+/// ignored, relative to `scm_root`.
+pub(crate) fn hash_listed_files<S: AsRef<str>>(
+    scm_root: &AbsoluteSystemPath,
+    turbo_root: &AbsoluteSystemPath,
+    package_path: &AnchoredSystemPath,
+    inputs: &[S],
+    include_default_files: bool,
+    list_files: impl FnOnce(&AbsoluteSystemPath) -> Result<Vec<RelativeUnixPathBuf>, Error>,
+) -> Result<GitHashes, Error> {
+    hash_package_files(
+        scm_root,
+        turbo_root,
+        package_path,
+        inputs,
+        include_default_files,
+        || {
+            let full_pkg_path = turbo_root.resolve(package_path);
+            let to_hash = list_files(&full_pkg_path)?;
+            let mut hashes = GitHashes::new();
+            hash_objects(scm_root, &full_pkg_path, to_hash, &mut hashes)?;
+            Ok(hashes)
+        },
+    )
+}
+
+#[tracing::instrument(skip(scm_root, turbo_root, inputs))]
+fn hash_package_inputs<S: AsRef<str>>(
+    scm_root: &AbsoluteSystemPath,
+    turbo_root: &AbsoluteSystemPath,
+    package_path: &AnchoredSystemPath,
+    inputs: &[S],
+    include_configs: bool,
+) -> Result<GitHashes, Error> {
+    let full_pkg_path = turbo_root.resolve(package_path);
+    let package_unix_path_buf = package_path.to_unix();
+    let package_unix_path = package_unix_path_buf.as_str();
+
+    let mut inputs = inputs
+        .iter()
+        .map(|s| s.as_ref().to_string())
+        .collect::<Vec<String>>();
+
+    if include_configs {
+        // Add in package.json and turbo.json to input patterns. Both file paths are
+        // relative to pkgPath
         //
-        // FIXME: we don't yet error on absolute unix paths being passed in as inputs,
-        // and instead tack them on as if they were relative paths. This should be an
-        // error further upstream, but since we haven't pulled the switch yet,
-        // we need to mimic the Go behavior here and trim leading `/`
-        // characters.
-        let mut inclusions = vec![];
-        let mut exclusions = vec![];
-        for raw_glob in inputs {
-            if let Some(exclusion) = raw_glob.strip_prefix('!') {
-                let glob_str = [package_unix_path, exclusion.trim_start_matches('/')].join("/");
-                exclusions.push(ValidatedGlob::from_str(&glob_str)?);
-            } else {
-                let glob_str = [package_unix_path, raw_glob.trim_start_matches('/')].join("/");
-                inclusions.push(ValidatedGlob::from_str(&glob_str)?);
-            }
-        }
-        let files = globwalk::globwalk(
-            turbo_root,
-            &inclusions,
-            &exclusions,
-            globwalk::WalkType::Files,
-        )?;
-        let to_hash = files
-            .iter()
-            .map(|entry| {
-                let path = self.root.anchor(entry)?.to_unix();
-                Ok(path)
-            })
-            .collect::<Result<Vec<_>, Error>>()?;
-        let mut hashes = GitHashes::new();
-        hash_objects(&self.root, &full_pkg_path, to_hash, &mut hashes)?;
-        Ok(hashes)
+        // - package.json is an input because if the `scripts` in the package.json
+        //   change (i.e. the tasks that turbo executes), we want a cache miss, since
+        //   any existing cache could be invalid.
+        // - turbo.json because it's the definition of the tasks themselves. The root
+        //   turbo.json is similarly included in the global hash. This file may not
+        //   exist in the workspace, but that is ok, because it will get ignored
+        //   downstream.
+        inputs.push("package.json".to_string());
+        inputs.push("turbo.json".to_string());
     }
 
-    #[tracing::instrument(skip(self, turbo_root, inputs))]
-    fn get_package_file_hashes_from_inputs_and_index<S: AsRef<str>>(
-        &self,
-        turbo_root: &AbsoluteSystemPath,
-        package_path: &AnchoredSystemPath,
-        inputs: &[S],
-    ) -> Result<GitHashes, Error> {
-        // collect the default files and the inputs
-        let default_file_hashes =
-            self.get_package_file_hashes_from_index(turbo_root, package_path)?;
-
-        // we need to get hashes for excludes separately so we can remove them from the
-        // defaults later on
-        let mut includes = Vec::new();
-        let mut excludes = Vec::new();
-        for input in inputs {
-            let input_str = input.as_ref();
-            if let Some(exclude) = input_str.strip_prefix('!') {
-                excludes.push(exclude);
-            } else {
-                includes.push(input_str);
-            }
-        }
-        // we have to always run the includes search because we add default files to the
-        // includes
-        let manual_includes_hashes =
-            self.get_package_file_hashes_from_inputs(turbo_root, package_path, &includes, true)?;
-
-        // only run the excludes search if there are excludes
-        let manual_excludes_hashes = if !excludes.is_empty() {
-            self.get_package_file_hashes_from_inputs(turbo_root, package_path, &excludes, false)?
+    // The input patterns are relative to the package.
+    // However, we need to change the globbing to be relative to the repo root.
+    // Prepend the package path to each of the input patterns.
+    //
+    // FIXME: we don't yet error on absolute unix paths being passed in as inputs,
+    // and instead tack them on as if they were relative paths. This should be an
+    // error further upstream, but since we haven't pulled the switch yet,
+    // we need to mimic the Go behavior here and trim leading `/`
+    // characters.
+    let mut inclusions = vec![];
+    let mut exclusions = vec![];
+    for raw_glob in inputs {
+        if let Some(exclusion) = raw_glob.strip_prefix('!') {
+            let glob_str = [package_unix_path, exclusion.trim_start_matches('/')].join("/");
+            exclusions.push(ValidatedGlob::from_str(&glob_str)?);
         } else {
-            GitHashes::new()
-        };
-
-        // merge the two includes
-        let mut hashes = default_file_hashes;
-        hashes.extend(manual_includes_hashes);
-
-        // remove the excludes
-        hashes.retain(|key, _| !manual_excludes_hashes.contains_key(key));
+            let glob_str = [package_unix_path, raw_glob.trim_start_matches('/')].join("/");
+            inclusions.push(ValidatedGlob::from_str(&glob_str)?);
+        }
+    }
+    let files = globwalk::globwalk(
+        turbo_root,
+        &inclusions,
+        &exclusions,
+        globwalk::WalkType::Files,
+    )?;
+    let to_hash = files
+        .iter()
+        .map(|entry| {
+            let path = scm_root.anchor(entry)?.to_unix();
+            Ok(path)
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    let mut hashes = GitHashes::new();
+    hash_objects(scm_root, &full_pkg_path, to_hash, &mut hashes)?;
+    Ok(hashes)
+}
 
-        return Ok(hashes);
+#[tracing::instrument(skip(scm_root, turbo_root, inputs, default_file_hashes))]
+fn hash_package_inputs_and_defaults<S: AsRef<str>>(
+    scm_root: &AbsoluteSystemPath,
+    turbo_root: &AbsoluteSystemPath,
+    package_path: &AnchoredSystemPath,
+    inputs: &[S],
+    default_file_hashes: impl FnOnce() -> Result<GitHashes, Error>,
+) -> Result<GitHashes, Error> {
+    // collect the default files and the inputs
+    let default_file_hashes = default_file_hashes()?;
+
+    // we need to get hashes for excludes separately so we can remove them from the
+    // defaults later on
+    let mut includes = Vec::new();
+    let mut excludes = Vec::new();
+    for input in inputs {
+        let input_str = input.as_ref();
+        if let Some(exclude) = input_str.strip_prefix('!') {
+            excludes.push(exclude);
+        } else {
+            includes.push(input_str);
+        }
     }
+    // we have to always run the includes search because we add default files to the
+    // includes
+    let manual_includes_hashes =
+        hash_package_inputs(scm_root, turbo_root, package_path, &includes, true)?;
+
+    // only run the excludes search if there are excludes
+    let manual_excludes_hashes = if !excludes.is_empty() {
+        hash_package_inputs(scm_root, turbo_root, package_path, &excludes, false)?
+    } else {
+        GitHashes::new()
+    };
+
+    // merge the two includes
+    let mut hashes = default_file_hashes;
+    hashes.extend(manual_includes_hashes);
+
+    // remove the excludes
+    hashes.retain(|key, _| !manual_excludes_hashes.contains_key(key));
+
+    return Ok(hashes);
 }
 
 #[cfg(test)]
@@ -491,6 +557,30 @@ mod tests {
         let hashes = git.get_package_file_hashes::<&str>(&repo_root, &package_path, &[], false)?;
         assert_eq!(hashes, all_expected);
 
+        // Other SCMs list the files they track instead, and must hash them the same
+        // way so hashes don't depend on the SCM
+        let list_files = |_: &AbsoluteSystemPath| {
+            Ok([
+                "my-pkg/committed-file",
+                "my-pkg/uncommitted-file",
+                "my-pkg/package.json",
+                "my-pkg/turbo.json",
+                "my-pkg/dir/nested-file",
+            ]
+            .iter()
+            .map(|path| RelativeUnixPathBuf::new(*path).unwrap())
+            .collect())
+        };
+        let listed_hashes = hash_listed_files::<&str>(
+            &repo_root,
+            &repo_root,
+            &package_path,
+            &[],
+            false,
+            list_files,
+        )?;
+        assert_eq!(listed_hashes, all_expected);
+
         // add the new root file as an option
         let mut all_expected = all_expected.clone();
         all_expected.insert(
@@ -611,6 +701,17 @@ mod tests {
                 .get_package_file_hashes(&repo_root, &package_path, inputs, include_default_files)
                 .unwrap();
             assert_eq!(hashes, expected);
+
+            let listed_hashes = hash_listed_files(
+                &repo_root,
+                &repo_root,
+                &package_path,
+                inputs,
+                include_default_files,
+                list_files,
+            )
+            .unwrap();
+            assert_eq!(listed_hashes, expected);
         }
         Ok(())
     }
@@ -19,6 +19,9 @@ const ALLOWLIST: [&str; 8] = [
 
 pub enum FileHashMethod {
     Git,
+    Mercurial,
+    Sapling,
+    Jujutsu,
     Manual,
 }
 
@@ -111,6 +114,9 @@ impl PackageTaskEventBuilder {
             key: "file_hash_method".to_string(),
             value: match method {
                 FileHashMethod::Git => "git".to_string(),
+                FileHashMethod::Mercurial => "mercurial".to_string(),
+                FileHashMethod::Sapling => "sapling".to_string(),
+                FileHashMethod::Jujutsu => "jujutsu".to_string(),
                 FileHashMethod::Manual => "manual".to_string(),
             },
             is_sensitive: EventType::NonSensitive,
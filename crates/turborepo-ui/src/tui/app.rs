@@ -0,0 +1,284 @@
+use std::{
+    io::{self, Stdout},
+    time::{Duration, Instant},
+};
+
+use crossterm::{
+    event::{self as term_event, Event as TermEvent},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::Span,
+    Frame, Terminal,
+};
+
+use super::{
+    event::Event,
+    handle::{AppReceiver, FRAMERATE},
+    input::{input, Action},
+    pane::TerminalPane,
+    table::TaskTable,
+    Error,
+};
+
+/// Lines scrolled by a single page up or page down
+const SCROLL_LINES: isize = 10;
+
+struct App {
+    table: TaskTable,
+    pane: TerminalPane,
+    focused: bool,
+}
+
+/// Render the TUI for the given tasks until the sender stops it or is
+/// dropped. `on_interrupt` is called when the user presses ctrl-c as raw mode
+/// keeps the terminal from sending a SIGINT.
+pub fn run_app(
+    tasks: Vec<String>,
+    receiver: AppReceiver,
+    mut on_interrupt: impl FnMut(),
+) -> Result<(), Error> {
+    let mut terminal = startup()?;
+    let size = terminal.size()?;
+
+    // The pane is resized to fit once the layout is known on the first draw
+    let mut app = App::new(size.height, size.width, tasks);
+    let result = run_app_inner(&mut terminal, &mut app, &receiver, &mut on_interrupt);
+
+    cleanup(terminal)?;
+    result
+}
+
+fn run_app_inner<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    receiver: &AppReceiver,
+    on_interrupt: &mut impl FnMut(),
+) -> Result<(), Error> {
+    loop {
+        terminal.draw(|frame| app.draw(frame))?;
+
+        while term_event::poll(Duration::ZERO)? {
+            if let TermEvent::Key(key) = term_event::read()? {
+                if let Some(action) = input(key, app.focused) {
+                    app.handle_action(action, on_interrupt);
+                }
+            }
+        }
+
+        if !app.process_events(receiver, Instant::now() + FRAMERATE) {
+            return Ok(());
+        }
+    }
+}
+
+impl App {
+    fn new(rows: u16, cols: u16, tasks: Vec<String>) -> Self {
+        Self {
+            pane: TerminalPane::new(rows, cols, tasks.iter().cloned()),
+            table: TaskTable::new(tasks),
+            focused: false,
+        }
+    }
+
+    /// Apply events until the deadline passes, returning `false` once the UI
+    /// should stop.
+    fn process_events(&mut self, receiver: &AppReceiver, deadline: Instant) -> bool {
+        loop {
+            match receiver.recv(deadline) {
+                Ok(Some(Event::Stop)) | Err(_) => return false,
+                Ok(Some(event)) => self.handle_event(event),
+                Ok(None) => return true,
+            }
+        }
+    }
+
+    fn handle_event(&mut self, event: Event) {
+        match event {
+            Event::StartTask { task } => self.table.start(&task),
+            Event::TaskOutput { task, output } => self.pane.process_output(&task, &output),
+            Event::CacheStatus { task, status } => self.table.cache(&task, status),
+            Event::EndTask { task, result } => {
+                self.table.finish(&task, result);
+                self.pane.close_stdin(&task);
+                if self.table.selected() == Some(task.as_str()) {
+                    self.focused = false;
+                }
+            }
+            Event::SetStdin { task, stdin } => self.pane.set_stdin(&task, stdin),
+            Event::Stop => (),
+        }
+    }
+
+    fn handle_action(&mut self, action: Action, on_interrupt: &mut impl FnMut()) {
+        let Some(task) = self.table.selected().map(|task| task.to_string()) else {
+            if action == Action::Interrupt {
+                on_interrupt();
+            }
+            return;
+        };
+        match action {
+            Action::Interrupt => on_interrupt(),
+            Action::Next => self.table.next(),
+            Action::Previous => self.table.previous(),
+            Action::ScrollUp => self.pane.scroll(&task, SCROLL_LINES),
+            Action::ScrollDown => self.pane.scroll(&task, -SCROLL_LINES),
+            Action::Focus => self.focused = self.pane.has_stdin(&task),
+            Action::Unfocus => self.focused = false,
+            Action::Input(bytes) => {
+                if !self.pane.write_input(&task, &bytes) {
+                    self.focused = false;
+                }
+            }
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let (table_area, pane_area, help_area) = layout(
+            frame.size(),
+            self.table.name_width().saturating_add(TABLE_PADDING),
+        );
+        self.table.render(frame, table_area);
+        self.pane
+            .render(frame, pane_area, self.table.selected(), self.focused);
+
+        let help = if self.focused {
+            "ctrl-z: stop interacting"
+        } else {
+            "↑/↓: select task  pgup/pgdn: scroll  enter: interact  ctrl-c: quit"
+        };
+        frame.render_widget(
+            Span::styled(help, Style::default().fg(Color::DarkGray)),
+            help_area,
+        );
+    }
+}
+
+/// Space the table needs beyond the task names for borders, status, cache
+/// and duration columns
+const TABLE_PADDING: u16 = 22;
+
+/// Split the screen into the task table, the output pane and a help line
+fn layout(area: Rect, table_width: u16) -> (Rect, Rect, Rect) {
+    let [main, help] = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(1)])
+        .areas(area);
+    let [table, pane] = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Max(table_width.min(area.width / 2)),
+            Constraint::Min(0),
+        ])
+        .areas(main);
+    (table, pane, help)
+}
+
+fn startup() -> io::Result<Terminal<CrosstermBackend<Stdout>>> {
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+    terminal.hide_cursor()?;
+    Ok(terminal)
+}
+
+fn cleanup(mut terminal: Terminal<CrosstermBackend<Stdout>>) -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+
+    use ratatui::backend::TestBackend;
+
+    use super::*;
+    use crate::tui::{
+        event::{CacheResult, TaskResult},
+        AppSender,
+    };
+
+    fn app() -> App {
+        App::new(10, 40, vec!["a#dev".into(), "b#build".into()])
+    }
+
+    #[test]
+    fn test_focus_requires_stdin() {
+        let mut app = app();
+        let mut interrupted = false;
+        app.handle_action(Action::Focus, &mut || interrupted = true);
+        assert!(!app.focused);
+
+        app.handle_event(Event::SetStdin {
+            task: "a#dev".into(),
+            stdin: Box::new(io::sink()),
+        });
+        app.handle_action(Action::Focus, &mut || interrupted = true);
+        assert!(app.focused);
+        app.handle_action(Action::Unfocus, &mut || interrupted = true);
+        assert!(!app.focused);
+        assert!(!interrupted);
+    }
+
+    #[test]
+    fn test_task_exit_unfocuses() {
+        let mut app = app();
+        app.handle_event(Event::SetStdin {
+            task: "a#dev".into(),
+            stdin: Box::new(io::sink()),
+        });
+        app.handle_action(Action::Focus, &mut || ());
+        app.handle_event(Event::EndTask {
+            task: "a#dev".into(),
+            result: TaskResult::Success,
+        });
+        assert!(!app.focused);
+        assert!(!app.pane.has_stdin("a#dev"));
+    }
+
+    #[test]
+    fn test_interrupt() {
+        let mut app = app();
+        let mut interrupted = false;
+        app.handle_action(Action::Interrupt, &mut || interrupted = true);
+        assert!(interrupted);
+    }
+
+    #[test]
+    fn test_renders_task_output() {
+        let (sender, receiver) = AppSender::new();
+        let mut task = sender.task("b#build".into());
+        task.start();
+        task.cache(CacheResult::Miss);
+        writeln!(task, "building b").unwrap();
+        task.failed();
+        sender.stop();
+
+        let mut app = app();
+        assert!(!app.process_events(&receiver, Instant::now()));
+
+        // Select b#build and render its output
+        app.handle_action(Action::Next, &mut || ());
+        let mut terminal = Terminal::new(TestBackend::new(80, 12)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let screen: String = (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer.get(x, y).symbol())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert!(screen.contains("building b"), "{screen}");
+        assert!(screen.contains("miss"), "{screen}");
+        assert!(screen.contains('✘'), "{screen}");
+    }
+}
@@ -0,0 +1,61 @@
+use std::io::Write;
+
+pub enum Event {
+    StartTask {
+        task: String,
+    },
+    TaskOutput {
+        task: String,
+        output: Vec<u8>,
+    },
+    CacheStatus {
+        task: String,
+        status: CacheResult,
+    },
+    EndTask {
+        task: String,
+        result: TaskResult,
+    },
+    SetStdin {
+        task: String,
+        stdin: Box<dyn Write + Send>,
+    },
+    Stop,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskResult {
+    Success,
+    Failure,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheResult {
+    Hit,
+    Miss,
+}
+
+impl std::fmt::Debug for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::StartTask { task } => f.debug_struct("StartTask").field("task", task).finish(),
+            Self::TaskOutput { task, output } => f
+                .debug_struct("TaskOutput")
+                .field("task", task)
+                .field("output", output)
+                .finish(),
+            Self::CacheStatus { task, status } => f
+                .debug_struct("CacheStatus")
+                .field("task", task)
+                .field("status", status)
+                .finish(),
+            Self::EndTask { task, result } => f
+                .debug_struct("EndTask")
+                .field("task", task)
+                .field("result", result)
+                .finish(),
+            Self::SetStdin { task, .. } => f.debug_struct("SetStdin").field("task", task).finish(),
+            Self::Stop => write!(f, "Stop"),
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use std::{
+    io::Write,
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+use super::event::{CacheResult, Event, TaskResult};
+
+/// How often the UI redraws when no events arrive
+pub(super) const FRAMERATE: Duration = Duration::from_millis(16);
+
+/// Struct for sending app events to the TUI rendering thread
+#[derive(Debug, Clone)]
+pub struct AppSender {
+    primary: mpsc::Sender<Event>,
+}
+
+/// Struct for receiving app events
+pub struct AppReceiver {
+    primary: mpsc::Receiver<Event>,
+}
+
+/// Handle for a single task to report its status and output to the TUI
+#[derive(Debug, Clone)]
+pub struct TuiTask {
+    name: String,
+    handle: AppSender,
+}
+
+impl AppSender {
+    /// Create a new channel for sending app events.
+    ///
+    /// AppSender is meant to be held by the actual task runner
+    /// AppReceiver should be passed to `run_app`
+    pub fn new() -> (Self, AppReceiver) {
+        let (primary_tx, primary_rx) = mpsc::channel();
+        (
+            Self {
+                primary: primary_tx,
+            },
+            AppReceiver {
+                primary: primary_rx,
+            },
+        )
+    }
+
+    /// Construct a handle for the given task
+    pub fn task(&self, task: String) -> TuiTask {
+        TuiTask {
+            name: task,
+            handle: self.clone(),
+        }
+    }
+
+    /// Stop rendering the TUI and restore the terminal
+    pub fn stop(&self) {
+        // Send will only fail if the receiver is dropped, in which case the UI is
+        // already stopped
+        self.primary.send(Event::Stop).ok();
+    }
+
+    fn send(&self, event: Event) {
+        self.primary.send(event).ok();
+    }
+}
+
+impl AppReceiver {
+    /// Receive an event, returning `Ok(None)` if none arrive before it is time
+    /// to draw the next frame
+    pub(super) fn recv(&self, deadline: Instant) -> Result<Option<Event>, mpsc::RecvError> {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        match self.primary.recv_timeout(timeout) {
+            Ok(event) => Ok(Some(event)),
+            Err(mpsc::RecvTimeoutError::Timeout) => Ok(None),
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err(mpsc::RecvError),
+        }
+    }
+}
+
+impl TuiTask {
+    /// Mark the task as started
+    pub fn start(&self) {
+        self.handle.send(Event::StartTask {
+            task: self.name.clone(),
+        });
+    }
+
+    /// Report whether the task's outputs were restored from cache
+    pub fn cache(&self, status: CacheResult) {
+        self.handle.send(Event::CacheStatus {
+            task: self.name.clone(),
+            status,
+        });
+    }
+
+    /// Mark the task as finished successfully
+    pub fn succeeded(&self) {
+        self.finish(TaskResult::Success)
+    }
+
+    /// Mark the task as finished with a failure
+    pub fn failed(&self) {
+        self.finish(TaskResult::Failure)
+    }
+
+    /// Provide a writer that is connected to the task's stdin. Once set the
+    /// task can be focused in the UI and keystrokes will be forwarded to it.
+    pub fn set_stdin(&self, stdin: Box<dyn Write + Send>) {
+        self.handle.send(Event::SetStdin {
+            task: self.name.clone(),
+            stdin,
+        });
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn finish(&self, result: TaskResult) {
+        self.handle.send(Event::EndTask {
+            task: self.name.clone(),
+            result,
+        });
+    }
+}
+
+impl Write for TuiTask {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let task = self.name.clone();
+        self.handle
+            .primary
+            .send(Event::TaskOutput {
+                task,
+                output: buf.to_vec(),
+            })
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "receiver dropped"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn drain(receiver: &AppReceiver) -> Vec<String> {
+        let mut events = Vec::new();
+        while let Ok(Some(event)) = receiver.recv(Instant::now()) {
+            events.push(format!("{event:?}"));
+        }
+        events
+    }
+
+    #[test]
+    fn test_task_events() {
+        let (sender, receiver) = AppSender::new();
+        let mut task = sender.task("web#build".into());
+        task.start();
+        task.cache(CacheResult::Miss);
+        task.write_all(b"hello\n").unwrap();
+        task.succeeded();
+        sender.stop();
+
+        assert_eq!(
+            drain(&receiver),
+            vec![
+                r#"StartTask { task: "web#build" }"#,
+                r#"CacheStatus { task: "web#build", status: Miss }"#,
+                r#"TaskOutput { task: "web#build", output: [104, 101, 108, 108, 111, 10] }"#,
+                r#"EndTask { task: "web#build", result: Success }"#,
+                "Stop",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_after_receiver_dropped() {
+        let (sender, receiver) = AppSender::new();
+        drop(receiver);
+        let mut task = sender.task("web#build".into());
+        assert_eq!(
+            task.write(b"hello").unwrap_err().kind(),
+            std::io::ErrorKind::BrokenPipe
+        );
+    }
+}
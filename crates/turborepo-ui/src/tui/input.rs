@@ -0,0 +1,94 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+/// What a key press asks the app to do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Action {
+    Interrupt,
+    Next,
+    Previous,
+    ScrollUp,
+    ScrollDown,
+    Focus,
+    Unfocus,
+    Input(Vec<u8>),
+}
+
+/// Translate a key press into an action. While a task is focused every key
+/// except the unfocus binding is forwarded to the task.
+pub(super) fn input(key: KeyEvent, focused: bool) -> Option<Action> {
+    if key.kind == KeyEventKind::Release {
+        return None;
+    }
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    match key.code {
+        KeyCode::Char('z') if ctrl && focused => Some(Action::Unfocus),
+        KeyCode::Char('c') if ctrl && !focused => Some(Action::Interrupt),
+        _ if focused => encode_key(key).map(Action::Input),
+        KeyCode::Up | KeyCode::Char('k') => Some(Action::Previous),
+        KeyCode::Down | KeyCode::Char('j') => Some(Action::Next),
+        KeyCode::PageUp | KeyCode::Char('u') => Some(Action::ScrollUp),
+        KeyCode::PageDown | KeyCode::Char('d') => Some(Action::ScrollDown),
+        KeyCode::Enter => Some(Action::Focus),
+        _ => None,
+    }
+}
+
+/// The bytes a terminal would send to a program for the given key
+fn encode_key(key: KeyEvent) -> Option<Vec<u8>> {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let bytes = match key.code {
+        // Control characters are the letter's position in the alphabet
+        KeyCode::Char(c) if ctrl && c.is_ascii_alphabetic() => {
+            vec![c.to_ascii_lowercase() as u8 - b'a' + 1]
+        }
+        KeyCode::Char(c) => c.to_string().into_bytes(),
+        KeyCode::Enter => b"\n".to_vec(),
+        KeyCode::Backspace => vec![0x7f],
+        KeyCode::Tab => b"\t".to_vec(),
+        KeyCode::Esc => vec![0x1b],
+        KeyCode::Up => b"\x1b[A".to_vec(),
+        KeyCode::Down => b"\x1b[B".to_vec(),
+        KeyCode::Right => b"\x1b[C".to_vec(),
+        KeyCode::Left => b"\x1b[D".to_vec(),
+        _ => return None,
+    };
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn ctrl(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+
+    #[test_case(key(KeyCode::Down), Some(Action::Next) ; "down")]
+    #[test_case(key(KeyCode::Char('k')), Some(Action::Previous) ; "k")]
+    #[test_case(key(KeyCode::PageUp), Some(Action::ScrollUp) ; "page up")]
+    #[test_case(key(KeyCode::Enter), Some(Action::Focus) ; "enter")]
+    #[test_case(ctrl('c'), Some(Action::Interrupt) ; "ctrl c")]
+    #[test_case(ctrl('z'), None ; "ctrl z")]
+    #[test_case(key(KeyCode::Char('x')), None ; "unbound")]
+    fn test_unfocused(key: KeyEvent, expected: Option<Action>) {
+        assert_eq!(input(key, false), expected);
+    }
+
+    #[test_case(key(KeyCode::Char('r')), Some(Action::Input(b"r".to_vec())) ; "char")]
+    #[test_case(key(KeyCode::Char('k')), Some(Action::Input(b"k".to_vec())) ; "navigation char")]
+    #[test_case(key(KeyCode::Enter), Some(Action::Input(b"\n".to_vec())) ; "enter")]
+    #[test_case(key(KeyCode::Backspace), Some(Action::Input(vec![0x7f])) ; "backspace")]
+    #[test_case(key(KeyCode::Up), Some(Action::Input(b"\x1b[A".to_vec())) ; "up")]
+    #[test_case(ctrl('c'), Some(Action::Input(vec![0x03])) ; "ctrl c")]
+    #[test_case(ctrl('z'), Some(Action::Unfocus) ; "ctrl z")]
+    #[test_case(key(KeyCode::F(1)), None ; "unsupported")]
+    fn test_focused(key: KeyEvent, expected: Option<Action>) {
+        assert_eq!(input(key, true), expected);
+    }
+}
@@ -0,0 +1,21 @@
+//! Full screen terminal UI for `turbo run`.
+//!
+//! The UI runs on its own thread and is driven by [`Event`]s sent through an
+//! [`AppSender`]. Each task gets a [`TuiTask`] handle that reports its status
+//! and acts as the writer for its output.
+mod app;
+mod event;
+mod handle;
+mod input;
+mod pane;
+mod table;
+
+pub use app::run_app;
+pub use event::{CacheResult, TaskResult};
+pub use handle::{AppReceiver, AppSender, TuiTask};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("failed to set up terminal: {0}")]
+    Io(#[from] std::io::Error),
+}
@@ -0,0 +1,222 @@
+use std::{collections::BTreeMap, io::Write};
+
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    widgets::{Block, Borders},
+    Frame,
+};
+use tui_term::widget::{Cursor, PseudoTerminal};
+
+/// Number of lines of output to keep for each task beyond what fits on screen
+const SCROLLBACK_LEN: usize = 1024;
+
+/// The virtual terminals that hold the output of each task
+pub(super) struct TerminalPane {
+    rows: u16,
+    cols: u16,
+    tasks: BTreeMap<String, TerminalOutput>,
+}
+
+struct TerminalOutput {
+    parser: vt100::Parser,
+    stdin: Option<Box<dyn Write + Send>>,
+}
+
+impl TerminalPane {
+    pub fn new(rows: u16, cols: u16, tasks: impl IntoIterator<Item = String>) -> Self {
+        let tasks = tasks
+            .into_iter()
+            .map(|name| (name, TerminalOutput::new(rows, cols)))
+            .collect();
+        Self { rows, cols, tasks }
+    }
+
+    pub fn process_output(&mut self, task: &str, output: &[u8]) {
+        if let Some(terminal) = self.tasks.get_mut(task) {
+            terminal.parser.process(&normalize_newlines(output));
+        }
+    }
+
+    pub fn set_stdin(&mut self, task: &str, stdin: Box<dyn Write + Send>) {
+        if let Some(terminal) = self.tasks.get_mut(task) {
+            terminal.stdin = Some(stdin);
+        }
+    }
+
+    pub fn has_stdin(&self, task: &str) -> bool {
+        self.tasks
+            .get(task)
+            .map_or(false, |terminal| terminal.stdin.is_some())
+    }
+
+    /// Forward bytes to the task's stdin. If the task is no longer accepting
+    /// input the stdin is dropped and `false` is returned.
+    pub fn write_input(&mut self, task: &str, input: &[u8]) -> bool {
+        let Some(terminal) = self.tasks.get_mut(task) else {
+            return false;
+        };
+        let Some(stdin) = &mut terminal.stdin else {
+            return false;
+        };
+        if stdin.write_all(input).and_then(|_| stdin.flush()).is_err() {
+            terminal.stdin = None;
+            return false;
+        }
+        true
+    }
+
+    /// Drop the task's stdin, used once the task has exited
+    pub fn close_stdin(&mut self, task: &str) {
+        if let Some(terminal) = self.tasks.get_mut(task) {
+            terminal.stdin = None;
+        }
+    }
+
+    pub fn resize(&mut self, rows: u16, cols: u16) {
+        if (rows, cols) == (self.rows, self.cols) {
+            return;
+        }
+        self.rows = rows;
+        self.cols = cols;
+        for terminal in self.tasks.values_mut() {
+            terminal.parser.set_size(rows, cols);
+        }
+    }
+
+    /// Scroll the task's output up (into the scrollback) by a positive amount
+    /// or back down towards the latest output by a negative amount.
+    pub fn scroll(&mut self, task: &str, lines: isize) {
+        if let Some(terminal) = self.tasks.get_mut(task) {
+            let current = terminal.parser.screen().scrollback();
+            terminal
+                .parser
+                .set_scrollback(current.saturating_add_signed(lines));
+        }
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect, task: Option<&str>, focused: bool) {
+        let inner = Block::default().borders(Borders::ALL).inner(area);
+        self.resize(inner.height, inner.width);
+
+        let Some((task, terminal)) = task.and_then(|task| self.tasks.get_key_value(task)) else {
+            frame.render_widget(Block::default().borders(Borders::ALL), area);
+            return;
+        };
+        let (title, style) = if focused {
+            (
+                format!("{task} (ctrl-z to stop interacting)"),
+                Style::default().fg(Color::Yellow),
+            )
+        } else if terminal.stdin.is_some() {
+            (format!("{task} (enter to interact)"), Style::default())
+        } else {
+            (task.to_string(), Style::default())
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(style)
+            .title(title);
+        // Only show the task's cursor when keystrokes are going to it
+        let mut cursor = Cursor::default();
+        if !focused {
+            cursor.hide();
+        }
+        frame.render_widget(
+            PseudoTerminal::new(terminal.parser.screen())
+                .block(block)
+                .cursor(cursor),
+            area,
+        );
+    }
+}
+
+impl TerminalOutput {
+    fn new(rows: u16, cols: u16) -> Self {
+        Self {
+            parser: vt100::Parser::new(rows, cols, SCROLLBACK_LEN),
+            stdin: None,
+        }
+    }
+}
+
+/// Output that isn't coming from a pty uses bare `\n` line endings, which a
+/// terminal treats as only moving down a line. Convert those to `\r\n` so
+/// each line starts back at the first column.
+fn normalize_newlines(output: &[u8]) -> Vec<u8> {
+    let mut normalized = Vec::with_capacity(output.len());
+    let mut previous = None;
+    for &byte in output {
+        if byte == b'\n' && previous != Some(b'\r') {
+            normalized.push(b'\r');
+        }
+        normalized.push(byte);
+        previous = Some(byte);
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case(b"hello\n", b"hello\r\n" ; "bare newline")]
+    #[test_case(b"hello\r\n", b"hello\r\n" ; "crlf")]
+    #[test_case(b"a\nb\r\nc", b"a\r\nb\r\nc" ; "mixed")]
+    fn test_normalize_newlines(input: &[u8], expected: &[u8]) {
+        assert_eq!(normalize_newlines(input), expected);
+    }
+
+    #[test]
+    fn test_output_starts_at_first_column() {
+        let mut pane = TerminalPane::new(4, 20, ["a#build".to_string()]);
+        pane.process_output("a#build", b"first\nsecond\n");
+        let contents = pane.tasks["a#build"].parser.screen().contents();
+        assert_eq!(contents, "first\nsecond");
+    }
+
+    #[test]
+    fn test_scroll() {
+        let mut pane = TerminalPane::new(2, 20, ["a#build".to_string()]);
+        for i in 0..10 {
+            pane.process_output("a#build", format!("line {i}\n").as_bytes());
+        }
+        pane.scroll("a#build", 3);
+        assert_eq!(pane.tasks["a#build"].parser.screen().scrollback(), 3);
+        pane.scroll("a#build", -5);
+        assert_eq!(pane.tasks["a#build"].parser.screen().scrollback(), 0);
+    }
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_stdin() {
+        let mut pane = TerminalPane::new(2, 20, ["a#dev".to_string(), "b#dev".to_string()]);
+        let buffer = SharedBuffer::default();
+        pane.set_stdin("a#dev", Box::new(buffer.clone()));
+
+        assert!(pane.has_stdin("a#dev"));
+        assert!(!pane.has_stdin("b#dev"));
+        assert!(pane.write_input("a#dev", b"r"));
+        assert!(!pane.write_input("b#dev", b"r"));
+        assert_eq!(buffer.0.lock().unwrap().as_slice(), b"r");
+
+        pane.close_stdin("a#dev");
+        assert!(!pane.has_stdin("a#dev"));
+    }
+}
@@ -0,0 +1,229 @@
+use std::time::{Duration, Instant};
+
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    text::Span,
+    widgets::{Block, Borders, Cell, Row, Table, TableState},
+    Frame,
+};
+
+use super::event::{CacheResult, TaskResult};
+
+/// The list of tasks in the run along with their current status
+pub(super) struct TaskTable {
+    tasks: Vec<TaskRow>,
+    state: TableState,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TaskRow {
+    name: String,
+    status: TaskStatus,
+    cache: Option<CacheResult>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TaskStatus {
+    Pending,
+    Running { started: Instant },
+    Finished { result: TaskResult, took: Duration },
+}
+
+impl TaskTable {
+    pub fn new(tasks: impl IntoIterator<Item = String>) -> Self {
+        let tasks: Vec<_> = tasks
+            .into_iter()
+            .map(|name| TaskRow {
+                name,
+                status: TaskStatus::Pending,
+                cache: None,
+            })
+            .collect();
+        let state = TableState::default().with_selected((!tasks.is_empty()).then_some(0));
+        Self { tasks, state }
+    }
+
+    /// Width needed to display every task name without truncation
+    pub fn name_width(&self) -> u16 {
+        self.tasks
+            .iter()
+            .map(|task| task.name.len())
+            .max()
+            .unwrap_or_default()
+            .try_into()
+            .unwrap_or(u16::MAX)
+    }
+
+    pub fn start(&mut self, task: &str) {
+        if let Some(row) = self.row_mut(task) {
+            row.status = TaskStatus::Running {
+                started: Instant::now(),
+            };
+        }
+    }
+
+    pub fn cache(&mut self, task: &str, status: CacheResult) {
+        if let Some(row) = self.row_mut(task) {
+            row.cache = Some(status);
+        }
+    }
+
+    pub fn finish(&mut self, task: &str, result: TaskResult) {
+        if let Some(row) = self.row_mut(task) {
+            let took = match row.status {
+                TaskStatus::Running { started } => started.elapsed(),
+                _ => Duration::ZERO,
+            };
+            row.status = TaskStatus::Finished { result, took };
+        }
+    }
+
+    /// The name of the currently selected task
+    pub fn selected(&self) -> Option<&str> {
+        let index = self.state.selected()?;
+        self.tasks.get(index).map(|task| task.name.as_str())
+    }
+
+    pub fn next(&mut self) {
+        if self.tasks.is_empty() {
+            return;
+        }
+        let next = self
+            .state
+            .selected()
+            .map_or(0, |i| (i + 1) % self.tasks.len());
+        self.state.select(Some(next));
+    }
+
+    pub fn previous(&mut self) {
+        if self.tasks.is_empty() {
+            return;
+        }
+        let previous = self
+            .state
+            .selected()
+            .map_or(0, |i| i.checked_sub(1).unwrap_or(self.tasks.len() - 1));
+        self.state.select(Some(previous));
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let rows = self.tasks.iter().map(|task| {
+            let (icon, style, duration) = match task.status {
+                TaskStatus::Pending => ("·", Style::default().fg(Color::DarkGray), None),
+                TaskStatus::Running { started } => (
+                    "»",
+                    Style::default().fg(Color::Yellow),
+                    Some(started.elapsed()),
+                ),
+                TaskStatus::Finished {
+                    result: TaskResult::Success,
+                    took,
+                } => ("✔", Style::default().fg(Color::Green), Some(took)),
+                TaskStatus::Finished {
+                    result: TaskResult::Failure,
+                    took,
+                } => ("✘", Style::default().fg(Color::Red), Some(took)),
+            };
+            let cache = match task.cache {
+                Some(CacheResult::Hit) => Span::styled("hit", Style::default().fg(Color::Cyan)),
+                Some(CacheResult::Miss) => Span::raw("miss"),
+                None => Span::raw(""),
+            };
+            Row::new(vec![
+                Cell::from(Span::styled(icon, style)),
+                Cell::from(task.name.as_str()),
+                Cell::from(cache),
+                Cell::from(duration.map(format_duration).unwrap_or_default()),
+            ])
+        });
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(1),
+                Constraint::Min(4),
+                Constraint::Length(5),
+                Constraint::Length(7),
+            ],
+        )
+        .header(
+            Row::new(vec!["", "Task", "Cache", "Time"])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(Block::default().borders(Borders::ALL).title("Tasks"))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        frame.render_stateful_widget(table, area, &mut self.state);
+    }
+
+    fn row_mut(&mut self, task: &str) -> Option<&mut TaskRow> {
+        self.tasks.iter_mut().find(|row| row.name == task)
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs_f64();
+    if secs < 60.0 {
+        format!("{secs:.1}s")
+    } else {
+        format!("{}m{}s", duration.as_secs() / 60, duration.as_secs() % 60)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::*;
+
+    fn table() -> TaskTable {
+        TaskTable::new(["a#build", "b#build", "c#build"].map(String::from))
+    }
+
+    #[test]
+    fn test_selection_wraps() {
+        let mut table = table();
+        assert_eq!(table.selected(), Some("a#build"));
+        table.previous();
+        assert_eq!(table.selected(), Some("c#build"));
+        table.next();
+        assert_eq!(table.selected(), Some("a#build"));
+        table.next();
+        assert_eq!(table.selected(), Some("b#build"));
+    }
+
+    #[test]
+    fn test_status_updates() {
+        let mut table = table();
+        table.start("b#build");
+        table.cache("b#build", CacheResult::Miss);
+        assert!(matches!(table.tasks[1].status, TaskStatus::Running { .. }));
+        table.finish("b#build", TaskResult::Failure);
+        assert!(matches!(
+            table.tasks[1].status,
+            TaskStatus::Finished {
+                result: TaskResult::Failure,
+                ..
+            }
+        ));
+        assert_eq!(table.tasks[1].cache, Some(CacheResult::Miss));
+        // Unknown tasks are ignored
+        table.start("d#build");
+        assert_eq!(table.tasks[0].status, TaskStatus::Pending);
+    }
+
+    #[test]
+    fn test_empty_table() {
+        let mut table = TaskTable::new(Vec::new());
+        table.next();
+        table.previous();
+        assert_eq!(table.selected(), None);
+    }
+
+    #[test_case(Duration::from_millis(1200), "1.2s" ; "seconds")]
+    #[test_case(Duration::from_secs(135), "2m15s" ; "minutes")]
+    fn test_format_duration(duration: Duration, expected: &str) {
+        assert_eq!(format_duration(duration), expected);
+    }
+}
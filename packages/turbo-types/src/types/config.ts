@@ -22,6 +22,14 @@ export interface BaseSchema {
      */
     [script: string]: Pipeline;
   };
+
+  /**
+   * Tags of this package, used by the root `boundaries` rules to restrict
+   * which packages may depend on each other.
+   *
+   * @defaultValue `[]`
+   */
+  tags?: Array<string>;
 }
 
 export interface WorkspaceSchema extends BaseSchema {
@@ -111,6 +119,53 @@ export interface RootSchema extends BaseSchema {
    * @defaultValue `{}`
    */
   remoteCache?: RemoteCache;
+
+  /**
+   * Limits for the local filesystem cache. Once a limit is exceeded, the least
+   * recently used entries are evicted. The `--cache-max-size` and
+   * `--cache-max-age` flags and the `TURBO_CACHE_MAX_SIZE` and
+   * `TURBO_CACHE_MAX_AGE` environment variables take precedence over these
+   * values.
+   *
+   * @defaultValue `{}`
+   */
+  localCache?: LocalCache;
+
+  /**
+   * Named resource pools and their capacities. Tasks declare how much of each
+   * pool they occupy with `resources`, and turbo only starts a task once that
+   * much of every pool it uses is free.
+   *
+   * @defaultValue `{}`
+   */
+  resources?: Record<string, number>;
+
+  /**
+   * Export a trace and cache metrics for every run to an OpenTelemetry
+   * collector over OTLP. The `OTEL_EXPORTER_OTLP_*` environment variables
+   * take precedence over these values.
+   *
+   * @defaultValue `{}`
+   */
+  otel?: Otel;
+
+  /**
+   * Other ecosystems whose workspaces are added to the package graph
+   * alongside the `package.json` workspaces: the root `Cargo.toml` workspace,
+   * the modules in the root `go.work` or the root `pyproject.toml` uv
+   * workspace.
+   *
+   * @defaultValue `[]`
+   */
+  packageDiscovery?: Array<"cargo" | "go" | "python">;
+
+  /**
+   * Rules about which tagged packages may depend on each other, checked by
+   * `turbo boundaries`.
+   *
+   * @defaultValue `{}`
+   */
+  boundaries?: Boundaries;
 }
 
 export interface Pipeline {
@@ -179,6 +234,16 @@ export interface Pipeline {
    */
   dotEnv?: null | Array<AnchoredUnixPath>;
 
+  /**
+   * Whether to load the task's `dotEnv` files into its environment. Variables
+   * already set in the environment are never overridden.
+   *
+   * Documentation: https://turbo.build/repo/docs/reference/configuration#injectDotEnv
+   *
+   * @defaultValue false
+   */
+  injectDotEnv?: boolean;
+
   /**
    * The set of glob patterns indicating a task's cacheable filesystem outputs.
    *
@@ -249,6 +314,54 @@ export interface Pipeline {
    * @defaultValue false
    */
   persistent?: boolean;
+
+  /**
+   * The number of times to re-run this task if it fails or times out before
+   * reporting it as failed.
+   *
+   * @defaultValue 0
+   */
+  retries?: number;
+
+  /**
+   * How long to wait before retrying a failed attempt, as a duration such as
+   * `"500ms"` or `"5s"`.
+   */
+  retryDelay?: string;
+
+  /**
+   * How long a single attempt of this task may run before it is killed, as a
+   * duration such as `"30s"` or `"10m"`.
+   */
+  timeout?: string;
+
+  /**
+   * How much of each resource pool declared in the root `resources` this task
+   * occupies while it runs, e.g. `{ "memory": 4, "gpu-lock": 1 }`.
+   *
+   * @defaultValue `{}`
+   */
+  resources?: Record<string, number>;
+
+  /**
+   * The command to run for this task instead of the package's package.json
+   * script of the same name, e.g. `cargo test`. Arguments passed after `--`
+   * are appended to it.
+   */
+  command?: string;
+
+  /**
+   * The directory, relative to the package, that `command` runs in.
+   */
+  cwd?: string;
+
+  /**
+   * Whether `command` runs through the platform's shell. When false, it is
+   * split into a program and its arguments and run directly.
+   *
+   * @defaultValue true
+   */
+  shell?: boolean;
 }
 
 export interface RemoteCache {
@@ -273,6 +386,82 @@ export interface RemoteCache {
   enabled?: boolean;
 }
 
+export interface LocalCache {
+  /**
+   * The maximum total size of the cache, e.g. `"500MB"` or `"10GB"`. Units
+   * are binary and a bare number is in bytes.
+   *
+   * @defaultValue unbounded
+   */
+  maxSize?: string;
+
+  /**
+   * How long an entry is kept after it was last used, e.g. `"12h"` or `"7d"`.
+   *
+   * @defaultValue unbounded
+   */
+  maxAge?: string;
+}
+
+export interface Otel {
+  /**
+   * Base URL of the OTLP receiver, e.g. `"http://localhost:4318"`.
+   * Nothing is exported unless this is set.
+   */
+  endpoint?: string;
+
+  /**
+   * The OTLP transport to use.
+   *
+   * @defaultValue `"http/protobuf"`
+   */
+  protocol?: "http/protobuf" | "grpc";
+
+  /**
+   * Headers sent with every export request, e.g. for authentication.
+   *
+   * @defaultValue `{}`
+   */
+  headers?: Record<string, string>;
+}
+
+export interface Boundaries {
+  /**
+   * The rules for the packages with each tag, keyed by tag.
+   *
+   * @defaultValue `{}`
+   */
+  tags?: Record<string, TagRules>;
+}
+
+export interface TagRules {
+  /**
+   * Restricts the packages that a package with this tag may depend on.
+   */
+  dependencies?: Permissions;
+
+  /**
+   * Restricts the packages that may depend on a package with this tag.
+   */
+  dependents?: Permissions;
+}
+
+export interface Permissions {
+  /**
+   * When set, the package on the other side of the dependency must have one
+   * of these tags. An empty list allows no packages.
+   */
+  allow?: Array<string>;
+
+  /**
+   * The package on the other side of the dependency must not have any of
+   * these tags.
+   *
+   * @defaultValue `[]`
+   */
+  deny?: Array<string>;
+}
+
 export type OutputMode =
   | "full"
   | "hash-only"